@@ -48,11 +48,11 @@ async fn main() -> Result<()> {
         .execute(&prepared, (44_i32, "I'm prepared 3!"))
         .await?;
 
-    // Rows can be parsed as tuples
+    // Rows can be deserialized as tuples, borrowing strings from the response
     let result = session
         .query("SELECT a, b, c FROM examples_ks.basic", &[])
         .await?;
-    let mut iter = result.rows_typed::<(i32, i32, String)>()?;
+    let mut iter = result.rows::<(i32, i32, &str)>()?;
     while let Some((a, b, c)) = iter.next().transpose()? {
         println!("a, b, c: {}, {}, {}", a, b, c);
     }
@@ -68,7 +68,7 @@ async fn main() -> Result<()> {
     let result = session
         .query("SELECT a, b, c FROM examples_ks.basic", &[])
         .await?;
-    let mut iter = result.rows_typed::<RowData>()?;
+    let mut iter = result.into_legacy_result()?.rows_typed::<RowData>()?;
     while let Some(row_data) = iter.next().transpose()? {
         println!("row_data: {:?}", row_data);
     }
@@ -77,7 +77,7 @@ async fn main() -> Result<()> {
     let result = session
         .query("SELECT a, b, c FROM examples_ks.basic", &[])
         .await?;
-    let rows = result.rows.unwrap();
+    let rows = result.into_legacy_result()?.rows.unwrap();
     for row in rows {
         let a = row.columns[0].as_ref().unwrap().as_int().unwrap();
         let b = row.columns[1].as_ref().unwrap().as_int().unwrap();
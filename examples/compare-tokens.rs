@@ -51,6 +51,7 @@ async fn main() -> Result<()> {
                 (pk,),
             )
             .await?
+            .into_legacy_result()?
             .single_row_typed::<(i64,)>()?;
         assert_eq!(t, qt);
         println!("token for {}: {}", pk, t);
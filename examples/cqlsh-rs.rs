@@ -3,6 +3,8 @@ use rustyline::completion::{Completer, Pair};
 use rustyline::error::ReadlineError;
 use rustyline::{CompletionType, Config, Context, Editor};
 use rustyline_derive::{Helper, Highlighter, Hinter, Validator};
+use scylla::frame::response::result::Row;
+use scylla::transport::query_result::RowsError;
 use scylla::transport::Compression;
 use scylla::{QueryResult, Session, SessionBuilder};
 use std::env;
@@ -173,12 +175,17 @@ impl Completer for CqlHelper {
     }
 }
 
-fn print_result(result: &QueryResult) {
-    if result.rows.is_none() {
-        println!("OK");
-        return;
-    }
-    for row in result.rows.as_ref().unwrap() {
+fn print_result(result: &QueryResult) -> Result<()> {
+    let rows = match result.rows::<Row>() {
+        Ok(rows) => rows,
+        Err(RowsError::RowsExpected(_)) => {
+            println!("OK");
+            return Ok(());
+        }
+        Err(err) => return Err(err.into()),
+    };
+    for row in rows {
+        let row = row?;
         for column in &row.columns {
             print!("|");
             print!(
@@ -191,6 +198,7 @@ fn print_result(result: &QueryResult) {
         }
         println!("|")
     }
+    Ok(())
 }
 
 #[tokio::main]
@@ -222,7 +230,11 @@ async fn main() -> Result<()> {
                 let maybe_res = session.query(line, &[]).await;
                 match maybe_res {
                     Err(err) => println!("Error: {}", err),
-                    Ok(res) => print_result(&res),
+                    Ok(res) => {
+                        if let Err(err) = print_result(&res) {
+                            println!("Error: {}", err)
+                        }
+                    }
                 }
             }
             Err(ReadlineError::Interrupted) => continue,
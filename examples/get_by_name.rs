@@ -46,7 +46,11 @@ async fn main() -> Result<()> {
         .ok_or_else(|| anyhow!("No value column found"))?;
     println!("ck           |  value");
     println!("---------------------");
-    for row in query_result.rows.ok_or_else(|| anyhow!("no rows found"))? {
+    for row in query_result
+        .into_legacy_result()?
+        .rows
+        .ok_or_else(|| anyhow!("no rows found"))?
+    {
         println!("{:?} | {:?}", row.columns[ck_idx], row.columns[value_idx]);
     }
 
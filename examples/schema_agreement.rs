@@ -69,7 +69,9 @@ async fn main() -> Result<()> {
     let result = session
         .query("SELECT a, b, c FROM examples_ks.schema_agreement", &[])
         .await?;
-    let mut iter = result.rows_typed::<(i32, i32, String)>()?;
+    let mut iter = result
+        .into_legacy_result()?
+        .rows_typed::<(i32, i32, String)>()?;
     while let Some((a, b, c)) = iter.next().transpose()? {
         println!("a, b, c: {}, {}, {}", a, b, c);
     }
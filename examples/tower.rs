@@ -44,27 +44,11 @@ async fn main() -> anyhow::Result<()> {
         .call("SELECT keyspace_name, table_name FROM system_schema.tables;".into())
         .await?;
 
-    let print_text = |t: &Option<scylla::frame::response::result::CqlValue>| {
-        t.as_ref()
-            .unwrap_or(&scylla::frame::response::result::CqlValue::Text(
-                "<null>".to_string(),
-            ))
-            .as_text()
-            .unwrap_or(&"<null>".to_string())
-            .clone()
-    };
+    let tables = resp
+        .rows::<(&str, &str)>()?
+        .map(|row| row.map(|(ks, table)| format!("\t{}.{}", ks, table)))
+        .collect::<Result<Vec<String>, _>>()?;
 
-    println!(
-        "Tables:\n{}",
-        resp.rows()?
-            .into_iter()
-            .map(|r| format!(
-                "\t{}.{}",
-                print_text(&r.columns[0]),
-                print_text(&r.columns[1])
-            ))
-            .collect::<Vec<String>>()
-            .join("\n")
-    );
+    println!("Tables:\n{}", tables.join("\n"));
     Ok(())
 }
@@ -52,7 +52,7 @@ async fn main() -> Result<()> {
     let result = session
         .query("SELECT my FROM examples_ks.user_defined_type_table", &[])
         .await?;
-    let mut iter = result.rows_typed::<(MyType,)>()?;
+    let mut iter = result.into_legacy_result()?.rows_typed::<(MyType,)>()?;
     while let Some((my_val,)) = iter.next().transpose()? {
         println!("{:?}", my_val);
     }
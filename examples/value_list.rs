@@ -63,5 +63,5 @@ async fn main() {
         .await
         .unwrap();
 
-    println!("Q: {:?}", q.rows);
+    println!("Q: {:?}", q.into_legacy_result().unwrap().rows);
 }
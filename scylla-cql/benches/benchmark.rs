@@ -4,7 +4,7 @@ use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
 
 use scylla_cql::frame::request::SerializableRequest;
 use scylla_cql::frame::response::result::ColumnType;
-use scylla_cql::frame::{request::query, Compression, SerializedRequest};
+use scylla_cql::frame::{request::query, Compression, ProtocolVersion, SerializedRequest};
 use scylla_cql::types::serialize::row::SerializedValues;
 
 fn make_query(contents: &str, values: SerializedValues) -> query::Query<'_> {
@@ -54,8 +54,10 @@ fn serialized_request_make_bench(c: &mut Criterion) {
                 b.iter(|| {
                     let _ = criterion::black_box(SerializedRequest::make(
                         query,
+                        ProtocolVersion::V4,
                         Some(Compression::Lz4),
                         false,
+                        None,
                     ));
                 })
             },
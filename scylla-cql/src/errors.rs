@@ -8,6 +8,7 @@ use crate::Consistency;
 use bytes::Bytes;
 use std::io::ErrorKind;
 use std::sync::Arc;
+use std::time::Duration;
 use thiserror::Error;
 
 /// Error that occurred during query execution
@@ -47,9 +48,28 @@ pub enum QueryError {
     #[error("Request timeout: {0}")]
     RequestTimeout(String),
 
+    /// The request deadline, shared by all the attempts of a request, passed
+    /// before the request succeeded
+    #[error("Request deadline of {}ms exceeded after {attempts} attempts", .deadline.as_millis())]
+    RequestDeadlineExceeded {
+        /// The deadline of the request.
+        deadline: Duration,
+        /// The number of attempts made before the deadline passed.
+        attempts: usize,
+    },
+
     /// Address translation failed
     #[error("Address translation failed: {0}")]
     TranslationError(#[from] TranslationError),
+
+    /// The request was not sent, because a client-side limit
+    /// of requests in flight was reached
+    #[error("Too many requests in flight: {1}")]
+    TooManyInFlightRequests(InFlightLimitScope, String),
+
+    /// The request was not sent, because the `Session` is shutting down or was shut down
+    #[error("Session is closed")]
+    SessionClosed,
 }
 
 /// An error sent from the database in response to a query
@@ -194,6 +214,25 @@ pub enum DbError {
         write_type: WriteType,
     },
 
+    /// An error during a write to a table with CDC enabled (protocol v5)
+    #[error("An error occurred during a write to a table with CDC enabled")]
+    CdcWriteFailure,
+
+    /// The outcome of a lightweight transaction is unknown, because of
+    /// a timeout or a contention with another transaction (protocol v5)
+    #[error(
+        "The outcome of a lightweight transaction is unknown \
+        (consistency: {consistency}, received: {received}, required: {required})"
+    )]
+    CasWriteUnknown {
+        /// Consistency level of the query
+        consistency: Consistency,
+        /// Number of nodes that acknowledged the request
+        received: i32,
+        /// Number of nodes required to acknowledge the request to satisfy required consistency level
+        required: i32,
+    },
+
     /// Tried to execute a prepared statement that is not prepared. Driver should prepare it again
     #[error(
         "Tried to execute a prepared statement that is not prepared. Driver should prepare it again"
@@ -274,6 +313,12 @@ impl DbError {
                 numfailures: _,
                 write_type: _,
             } => 0x1500,
+            DbError::CdcWriteFailure => 0x1600,
+            DbError::CasWriteUnknown {
+                consistency: _,
+                received: _,
+                required: _,
+            } => 0x1700,
             DbError::SyntaxError => 0x2000,
             DbError::Unauthorized => 0x2100,
             DbError::Invalid => 0x2200,
@@ -301,6 +346,29 @@ pub enum TranslationError {
     InvalidAddressInRule,
 }
 
+/// Scope of the client-side limit of requests in flight which was reached
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum InFlightLimitScope {
+    /// The limit of requests in flight in the whole session
+    Session,
+    /// The limit of requests in flight to a single node
+    Node,
+    /// The limit of requests in flight to a single shard of a node
+    Shard,
+}
+
+impl std::fmt::Display for InFlightLimitScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let scope = match self {
+            InFlightLimitScope::Session => "session",
+            InFlightLimitScope::Node => "node",
+            InFlightLimitScope::Shard => "shard",
+        };
+        write!(f, "{}", scope)
+    }
+}
+
 /// Type of the operation rejected by rate limiting
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum OperationType {
@@ -408,9 +476,29 @@ pub enum NewSessionError {
     #[error("Client timeout: {0}")]
     RequestTimeout(String),
 
+    /// The deadline of a request made during `Session` creation passed
+    /// before the request succeeded
+    #[error("Request deadline of {}ms exceeded after {attempts} attempts", .deadline.as_millis())]
+    RequestDeadlineExceeded {
+        /// The deadline of the request.
+        deadline: Duration,
+        /// The number of attempts made before the deadline passed.
+        attempts: usize,
+    },
+
     /// Address translation failed
     #[error("Address translation failed: {0}")]
     TranslationError(#[from] TranslationError),
+
+    /// A request made during `Session` creation was not sent,
+    /// because a client-side limit of requests in flight was reached
+    #[error("Too many requests in flight: {1}")]
+    TooManyInFlightRequests(InFlightLimitScope, String),
+
+    /// A request made during `Session` creation was not sent,
+    /// because the `Session` was shut down
+    #[error("Session is closed")]
+    SessionClosed,
 }
 
 /// Invalid keyspace name given to `Session::use_keyspace()`
@@ -491,7 +579,14 @@ impl From<QueryError> for NewSessionError {
             }
             QueryError::UnableToAllocStreamId => NewSessionError::UnableToAllocStreamId,
             QueryError::RequestTimeout(msg) => NewSessionError::RequestTimeout(msg),
+            QueryError::RequestDeadlineExceeded { deadline, attempts } => {
+                NewSessionError::RequestDeadlineExceeded { deadline, attempts }
+            }
             QueryError::TranslationError(e) => NewSessionError::TranslationError(e),
+            QueryError::TooManyInFlightRequests(scope, msg) => {
+                NewSessionError::TooManyInFlightRequests(scope, msg)
+            }
+            QueryError::SessionClosed => NewSessionError::SessionClosed,
         }
     }
 }
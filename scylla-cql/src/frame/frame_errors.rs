@@ -15,8 +15,14 @@ pub enum FrameError {
     FrameFromClient,
     #[error("Received frame marked as coming from the server")]
     FrameFromServer,
-    #[error("Received a frame from version {0}, but only 4 is supported")]
+    #[error("Received a frame from unsupported protocol version {0}")]
     VersionNotSupported(u8),
+    #[error("Segment header checksum mismatch: computed {computed:06x}, received {received:06x}")]
+    SegmentHeaderChecksumMismatch { computed: u32, received: u32 },
+    #[error("Segment payload checksum mismatch: computed {computed:08x}, received {received:08x}")]
+    SegmentPayloadChecksumMismatch { computed: u32, received: u32 },
+    #[error("Compression {0} is not supported with segment framing, only lz4 is")]
+    SegmentCompressionNotSupported(super::Compression),
     #[error("Connection was closed before body was read: missing {0} out of {1}")]
     ConnectionClosed(usize, usize),
     #[error("Frame decompression failed.")]
@@ -2,6 +2,7 @@ pub mod frame_errors;
 pub mod protocol_features;
 pub mod request;
 pub mod response;
+pub mod segment;
 pub mod server_event_type;
 pub mod types;
 pub mod value;
@@ -21,7 +22,7 @@ use std::{collections::HashMap, convert::TryFrom};
 use request::SerializableRequest;
 use response::ResponseOpcode;
 
-const HEADER_SIZE: usize = 9;
+pub(crate) const HEADER_SIZE: usize = 9;
 
 // Frame flags
 const FLAG_COMPRESSION: u8 = 0x01;
@@ -63,6 +64,69 @@ impl Display for Compression {
     }
 }
 
+/// The version of the CQL native protocol used on a connection.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+#[non_exhaustive]
+pub enum ProtocolVersion {
+    /// Version 3 of the protocol, supported by Cassandra since 2.1.
+    /// Used only with legacy clusters which do not support newer versions.
+    V3,
+    /// Version 4 of the protocol, supported by all ScyllaDB versions
+    /// and by Cassandra since 2.2.
+    #[default]
+    V4,
+    /// Version 5 of the protocol, supported by Cassandra since 4.0.
+    ///
+    /// Once the connection is established, frames are wrapped in segments
+    /// protected by CRC24/CRC32 checksums (see [`segment`]).
+    V5,
+}
+
+impl ProtocolVersion {
+    /// The highest protocol version supported by the driver.
+    pub const LATEST: ProtocolVersion = ProtocolVersion::V5;
+
+    /// The lowest protocol version supported by the driver.
+    pub const OLDEST: ProtocolVersion = ProtocolVersion::V3;
+
+    /// Returns the version number, as put in the frame header.
+    pub fn as_u8(self) -> u8 {
+        match self {
+            ProtocolVersion::V3 => 0x03,
+            ProtocolVersion::V4 => 0x04,
+            ProtocolVersion::V5 => 0x05,
+        }
+    }
+
+    /// Returns true if frames of this version are wrapped in checksummed
+    /// segments after the STARTUP handshake completes.
+    pub fn uses_segments(self) -> bool {
+        self >= ProtocolVersion::V5
+    }
+}
+
+impl TryFrom<u8> for ProtocolVersion {
+    type Error = TryFromPrimitiveError<u8>;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x03 => Ok(Self::V3),
+            0x04 => Ok(Self::V4),
+            0x05 => Ok(Self::V5),
+            _ => Err(TryFromPrimitiveError {
+                enum_name: "ProtocolVersion",
+                primitive: value,
+            }),
+        }
+    }
+}
+
+impl Display for ProtocolVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "v{}", self.as_u8())
+    }
+}
+
 pub struct SerializedRequest {
     data: Vec<u8>,
 }
@@ -70,25 +134,49 @@ pub struct SerializedRequest {
 impl SerializedRequest {
     pub fn make<R: SerializableRequest>(
         req: &R,
+        version: ProtocolVersion,
         compression: Option<Compression>,
         tracing: bool,
+        custom_payload: Option<&HashMap<String, Vec<u8>>>,
     ) -> Result<SerializedRequest, FrameError> {
         let mut flags = 0;
         let mut data = vec![0; HEADER_SIZE];
 
-        if let Some(compression) = compression {
-            flags |= FLAG_COMPRESSION;
-            let body = req.to_bytes()?;
-            compress_append(&body, compression, &mut data)?;
-        } else {
-            req.serialize(&mut data)?;
+        let write_body = |buf: &mut Vec<u8>| -> Result<(), FrameError> {
+            if let Some(custom_payload) = custom_payload {
+                if version < ProtocolVersion::V4 {
+                    return Err(frame_errors::ParseError::BadDataToSerialize(format!(
+                        "Custom payloads are not supported in protocol {version}"
+                    ))
+                    .into());
+                }
+                types::write_bytes_map(custom_payload, buf)
+                    .map_err(frame_errors::ParseError::from)?;
+            }
+            req.serialize_for_version(version, buf)?;
+            Ok(())
+        };
+
+        match compression {
+            // Starting with v5, compression is applied to whole segments
+            // instead of individual frames.
+            Some(compression) if !version.uses_segments() => {
+                flags |= FLAG_COMPRESSION;
+                let mut body = Vec::new();
+                write_body(&mut body)?;
+                compress_append(&body, compression, &mut data)?;
+            }
+            _ => write_body(&mut data)?,
         }
 
         if tracing {
             flags |= FLAG_TRACING;
         }
+        if custom_payload.is_some() {
+            flags |= FLAG_CUSTOM_PAYLOAD;
+        }
 
-        data[0] = 4; // We only support version 4 for now
+        data[0] = version.as_u8();
         data[1] = flags;
         // Leave space for the stream number
         data[4] = R::OPCODE as u8;
@@ -126,22 +214,29 @@ impl Default for FrameParams {
     }
 }
 
-pub async fn read_response_frame(
-    reader: &mut (impl AsyncRead + Unpin),
-) -> Result<(FrameParams, ResponseOpcode, Bytes), FrameError> {
-    let mut raw_header = [0u8; HEADER_SIZE];
-    reader.read_exact(&mut raw_header[..]).await?;
+impl FrameParams {
+    /// Returns the protocol version the frame was encoded with.
+    pub fn protocol_version(&self) -> Result<ProtocolVersion, FrameError> {
+        ProtocolVersion::try_from(self.version & 0x7F)
+            .map_err(|_| FrameError::VersionNotSupported(self.version & 0x7F))
+    }
+}
 
+/// Parses the header of a response frame, returning the frame parameters,
+/// the opcode and the length of the body which follows the header.
+///
+/// Frames of any protocol version supported by the driver are accepted,
+/// because during protocol negotiation the server may respond with
+/// a version different from the one the request was sent with.
+pub fn parse_response_header(
+    raw_header: &[u8; HEADER_SIZE],
+) -> Result<(FrameParams, ResponseOpcode, usize), FrameError> {
     let mut buf = &raw_header[..];
 
-    // TODO: Validate version
     let version = buf.get_u8();
     if version & 0x80 != 0x80 {
         return Err(FrameError::FrameFromClient);
     }
-    if version & 0x7F != 0x04 {
-        return Err(FrameError::VersionNotSupported(version & 0x7f));
-    }
 
     let flags = buf.get_u8();
     let stream = buf.get_i16();
@@ -151,12 +246,24 @@ pub async fn read_response_frame(
         flags,
         stream,
     };
+    frame_params.protocol_version()?;
 
     let opcode = ResponseOpcode::try_from(buf.get_u8())?;
 
     // TODO: Guard from frames that are too large
     let length = buf.get_u32() as usize;
 
+    Ok((frame_params, opcode, length))
+}
+
+pub async fn read_response_frame(
+    reader: &mut (impl AsyncRead + Unpin),
+) -> Result<(FrameParams, ResponseOpcode, Bytes), FrameError> {
+    let mut raw_header = [0u8; HEADER_SIZE];
+    reader.read_exact(&mut raw_header[..]).await?;
+
+    let (frame_params, opcode, length) = parse_response_header(&raw_header)?;
+
     let mut raw_body = Vec::with_capacity(length).limit(length);
     while raw_body.has_remaining_mut() {
         let n = reader.read_buf(&mut raw_body).await?;
@@ -305,4 +412,45 @@ mod test {
         assert_eq!(32, comp_body.len());
         assert_eq!(uncomp_body.as_bytes(), result);
     }
+
+    #[test]
+    fn test_custom_payload_roundtrip() {
+        let payload: HashMap<String, Vec<u8>> = [("tenant".to_string(), b"acme".to_vec())]
+            .into_iter()
+            .collect();
+        let body = request::Options;
+
+        for compression in [None, Some(Compression::Lz4), Some(Compression::Snappy)] {
+            let request = SerializedRequest::make(
+                &body,
+                ProtocolVersion::V4,
+                compression,
+                false,
+                Some(&payload),
+            )
+            .unwrap();
+            let data = request.get_data();
+            let flags = data[1];
+            assert_ne!(flags & FLAG_CUSTOM_PAYLOAD, 0);
+
+            // Requests and responses share the layout of the frame extensions.
+            let parsed = parse_response_body_extensions(
+                flags,
+                compression,
+                Bytes::copy_from_slice(&data[HEADER_SIZE..]),
+            )
+            .unwrap();
+            assert_eq!(parsed.custom_payload.as_ref(), Some(&payload));
+            assert!(parsed.body.is_empty());
+        }
+
+        let request =
+            SerializedRequest::make(&body, ProtocolVersion::V4, None, false, None).unwrap();
+        assert_eq!(request.get_data()[1] & FLAG_CUSTOM_PAYLOAD, 0);
+
+        assert!(
+            SerializedRequest::make(&body, ProtocolVersion::V3, None, false, Some(&payload))
+                .is_err()
+        );
+    }
 }
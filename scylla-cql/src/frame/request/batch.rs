@@ -7,6 +7,7 @@ use crate::{
         request::{RequestOpcode, SerializableRequest},
         types::{self, SerialConsistency},
         value::SerializeValuesError,
+        ProtocolVersion,
     },
     types::serialize::{
         raw_batch::{RawBatchValues, RawBatchValuesIterator},
@@ -84,6 +85,14 @@ where
     const OPCODE: RequestOpcode = RequestOpcode::Batch;
 
     fn serialize(&self, buf: &mut Vec<u8>) -> Result<(), ParseError> {
+        self.serialize_for_version(ProtocolVersion::V4, buf)
+    }
+
+    fn serialize_for_version(
+        &self,
+        version: ProtocolVersion,
+        buf: &mut Vec<u8>,
+    ) -> Result<(), ParseError> {
         // Serializing type of batch
         buf.put_u8(self.batch_type as u8);
 
@@ -148,7 +157,12 @@ where
             flags |= FLAG_WITH_DEFAULT_TIMESTAMP;
         }
 
-        buf.put_u8(flags);
+        // Protocol v5 widened the flags to an [int].
+        if version >= ProtocolVersion::V5 {
+            buf.put_u32(flags as u32);
+        } else {
+            buf.put_u8(flags);
+        }
 
         if let Some(serial_consistency) = self.serial_consistency {
             types::write_serial_consistency(serial_consistency, buf);
@@ -4,6 +4,7 @@ use bytes::Bytes;
 use crate::{
     frame::request::{query, RequestOpcode, SerializableRequest},
     frame::types,
+    frame::ProtocolVersion,
 };
 
 use super::{query::QueryParameters, DeserializableRequest};
@@ -11,6 +12,9 @@ use super::{query::QueryParameters, DeserializableRequest};
 #[cfg_attr(test, derive(Debug, PartialEq, Eq))]
 pub struct Execute<'a> {
     pub id: Bytes,
+    /// Id of the result metadata the client has cached. Sent only in protocol v5,
+    /// where the server uses it to detect that the metadata has changed.
+    pub result_metadata_id: Option<Bytes>,
     pub parameters: query::QueryParameters<'a>,
 }
 
@@ -18,11 +22,25 @@ impl SerializableRequest for Execute<'_> {
     const OPCODE: RequestOpcode = RequestOpcode::Execute;
 
     fn serialize(&self, buf: &mut Vec<u8>) -> Result<(), ParseError> {
+        self.serialize_for_version(ProtocolVersion::V4, buf)
+    }
+
+    fn serialize_for_version(
+        &self,
+        version: ProtocolVersion,
+        buf: &mut Vec<u8>,
+    ) -> Result<(), ParseError> {
         // Serializing statement id
         types::write_short_bytes(&self.id[..], buf)?;
 
+        // Serializing result metadata id
+        if version >= ProtocolVersion::V5 {
+            let result_metadata_id = self.result_metadata_id.as_deref().unwrap_or_default();
+            types::write_short_bytes(result_metadata_id, buf)?;
+        }
+
         // Serializing params
-        self.parameters.serialize(buf)?;
+        self.parameters.serialize_for_version(version, buf)?;
         Ok(())
     }
 }
@@ -32,6 +50,10 @@ impl<'e> DeserializableRequest for Execute<'e> {
         let id = types::read_short_bytes(buf)?.to_vec().into();
         let parameters = QueryParameters::deserialize(buf)?;
 
-        Ok(Self { id, parameters })
+        Ok(Self {
+            id,
+            result_metadata_id: None,
+            parameters,
+        })
     }
 }
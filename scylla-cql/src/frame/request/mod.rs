@@ -22,7 +22,7 @@ pub use startup::Startup;
 use self::batch::BatchStatement;
 
 use super::types::SerialConsistency;
-use super::TryFromPrimitiveError;
+use super::{ProtocolVersion, TryFromPrimitiveError};
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 #[repr(u8)]
@@ -63,6 +63,18 @@ pub trait SerializableRequest {
 
     fn serialize(&self, buf: &mut Vec<u8>) -> Result<(), ParseError>;
 
+    /// Serializes the request body in the format of the given protocol version.
+    ///
+    /// The default implementation is suitable for requests whose body
+    /// does not depend on the protocol version.
+    fn serialize_for_version(
+        &self,
+        _version: ProtocolVersion,
+        buf: &mut Vec<u8>,
+    ) -> Result<(), ParseError> {
+        self.serialize(buf)
+    }
+
     fn to_bytes(&self) -> Result<Bytes, ParseError> {
         let mut v = Vec::new();
         self.serialize(&mut v)?;
@@ -135,6 +147,7 @@ mod tests {
             },
             response::result::ColumnType,
             types::{self, SerialConsistency},
+            ProtocolVersion,
         },
         types::serialize::row::SerializedValues,
         Consistency,
@@ -186,7 +199,11 @@ mod tests {
                 Cow::Owned(vals)
             },
         };
-        let execute = Execute { id, parameters };
+        let execute = Execute {
+            id,
+            result_metadata_id: None,
+            parameters,
+        };
         {
             let mut buf = Vec::new();
             execute.serialize(&mut buf).unwrap();
@@ -308,4 +325,48 @@ mod tests {
             let _parse_error = Batch::deserialize(&mut &buf[..]).unwrap_err();
         }
     }
+
+    #[test]
+    fn v5_serialization() {
+        let id: Bytes = vec![2, 4, 5, 2, 6, 7, 3, 1].into();
+        let result_metadata_id: Bytes = vec![9, 8, 7].into();
+        let parameters = QueryParameters {
+            consistency: Consistency::One,
+            serial_consistency: None,
+            timestamp: None,
+            page_size: None,
+            paging_state: None,
+            skip_metadata: true,
+            values: Cow::Borrowed(SerializedValues::EMPTY),
+        };
+        let execute = Execute {
+            id: id.clone(),
+            result_metadata_id: Some(result_metadata_id.clone()),
+            parameters,
+        };
+
+        let mut v4 = Vec::new();
+        execute
+            .serialize_for_version(ProtocolVersion::V4, &mut v4)
+            .unwrap();
+        let mut v5 = Vec::new();
+        execute
+            .serialize_for_version(ProtocolVersion::V5, &mut v5)
+            .unwrap();
+
+        // v4: [short bytes] id, [consistency], [byte] flags
+        let mut expected_v4 = Vec::new();
+        types::write_short_bytes(&id, &mut expected_v4).unwrap();
+        types::write_consistency(Consistency::One, &mut expected_v4);
+        expected_v4.push(0x02);
+        assert_eq!(v4, expected_v4);
+
+        // v5: [short bytes] id, [short bytes] result_metadata_id, [consistency], [int] flags
+        let mut expected_v5 = Vec::new();
+        types::write_short_bytes(&id, &mut expected_v5).unwrap();
+        types::write_short_bytes(&result_metadata_id, &mut expected_v5).unwrap();
+        types::write_consistency(Consistency::One, &mut expected_v5);
+        expected_v5.extend_from_slice(&[0x00, 0x00, 0x00, 0x02]);
+        assert_eq!(v5, expected_v5);
+    }
 }
@@ -1,8 +1,10 @@
 use crate::frame::frame_errors::ParseError;
+use bytes::BufMut;
 
 use crate::{
     frame::request::{RequestOpcode, SerializableRequest},
     frame::types,
+    frame::ProtocolVersion,
 };
 
 pub struct Prepare<'a> {
@@ -13,7 +15,19 @@ impl<'a> SerializableRequest for Prepare<'a> {
     const OPCODE: RequestOpcode = RequestOpcode::Prepare;
 
     fn serialize(&self, buf: &mut Vec<u8>) -> Result<(), ParseError> {
+        self.serialize_for_version(ProtocolVersion::V4, buf)
+    }
+
+    fn serialize_for_version(
+        &self,
+        version: ProtocolVersion,
+        buf: &mut Vec<u8>,
+    ) -> Result<(), ParseError> {
         types::write_long_string(self.query, buf)?;
+        if version >= ProtocolVersion::V5 {
+            // Flags; the driver never sets the keyspace of a PREPARE request.
+            buf.put_u32(0);
+        }
         Ok(())
     }
 }
@@ -1,7 +1,7 @@
 use std::borrow::Cow;
 
 use crate::{
-    frame::{frame_errors::ParseError, types::SerialConsistency},
+    frame::{frame_errors::ParseError, types::SerialConsistency, ProtocolVersion},
     types::serialize::row::SerializedValues,
 };
 use bytes::{Buf, BufMut, Bytes};
@@ -39,8 +39,16 @@ impl SerializableRequest for Query<'_> {
     const OPCODE: RequestOpcode = RequestOpcode::Query;
 
     fn serialize(&self, buf: &mut Vec<u8>) -> Result<(), ParseError> {
+        self.serialize_for_version(ProtocolVersion::V4, buf)
+    }
+
+    fn serialize_for_version(
+        &self,
+        version: ProtocolVersion,
+        buf: &mut Vec<u8>,
+    ) -> Result<(), ParseError> {
         types::write_long_string(&self.contents, buf)?;
-        self.parameters.serialize(buf)?;
+        self.parameters.serialize_for_version(version, buf)?;
         Ok(())
     }
 }
@@ -83,6 +91,14 @@ impl Default for QueryParameters<'_> {
 
 impl QueryParameters<'_> {
     pub fn serialize(&self, buf: &mut impl BufMut) -> Result<(), ParseError> {
+        self.serialize_for_version(ProtocolVersion::V4, buf)
+    }
+
+    pub fn serialize_for_version(
+        &self,
+        version: ProtocolVersion,
+        buf: &mut impl BufMut,
+    ) -> Result<(), ParseError> {
         types::write_consistency(self.consistency, buf);
 
         let mut flags = 0;
@@ -110,7 +126,12 @@ impl QueryParameters<'_> {
             flags |= FLAG_WITH_DEFAULT_TIMESTAMP;
         }
 
-        buf.put_u8(flags);
+        // Protocol v5 widened the flags to an [int].
+        if version >= ProtocolVersion::V5 {
+            buf.put_u32(flags as u32);
+        } else {
+            buf.put_u8(flags);
+        }
 
         if !self.values.is_empty() {
             self.values.write_to_request(buf);
@@ -1,9 +1,10 @@
 use crate::errors::{DbError, OperationType, QueryError, WriteType};
+use crate::frame::frame_errors::LowLevelDeserializationError;
 use crate::frame::frame_errors::ParseError;
 use crate::frame::protocol_features::ProtocolFeatures;
-use crate::frame::types;
+use crate::frame::{types, ProtocolVersion};
 use byteorder::ReadBytesExt;
-use bytes::Bytes;
+use bytes::{Buf, Bytes};
 
 #[derive(Debug, Clone)]
 pub struct Error {
@@ -12,7 +13,11 @@ pub struct Error {
 }
 
 impl Error {
-    pub fn deserialize(features: &ProtocolFeatures, buf: &mut &[u8]) -> Result<Self, ParseError> {
+    pub fn deserialize(
+        features: &ProtocolFeatures,
+        version: ProtocolVersion,
+        buf: &mut &[u8],
+    ) -> Result<Self, ParseError> {
         let code = types::read_int(buf)?;
         let reason = types::read_string(buf)?.to_owned();
 
@@ -44,7 +49,7 @@ impl Error {
                 consistency: types::read_consistency(buf)?,
                 received: types::read_int(buf)?,
                 required: types::read_int(buf)?,
-                numfailures: types::read_int(buf)?,
+                numfailures: read_failure_count(version, buf)?,
                 data_present: buf.read_u8()? != 0,
             },
             0x1400 => DbError::FunctionFailure {
@@ -56,9 +61,15 @@ impl Error {
                 consistency: types::read_consistency(buf)?,
                 received: types::read_int(buf)?,
                 required: types::read_int(buf)?,
-                numfailures: types::read_int(buf)?,
+                numfailures: read_failure_count(version, buf)?,
                 write_type: WriteType::from(types::read_string(buf)?),
             },
+            0x1600 => DbError::CdcWriteFailure,
+            0x1700 => DbError::CasWriteUnknown {
+                consistency: types::read_consistency(buf)?,
+                received: types::read_int(buf)?,
+                required: types::read_int(buf)?,
+            },
             0x2000 => DbError::SyntaxError,
             0x2100 => DbError::Unauthorized,
             0x2200 => DbError::Invalid,
@@ -81,6 +92,31 @@ impl Error {
     }
 }
 
+// Protocol v5 replaced the number of failures with a map from the failed
+// replicas' addresses to failure codes. Only the number of failures is reported.
+fn read_failure_count(version: ProtocolVersion, buf: &mut &[u8]) -> Result<i32, ParseError> {
+    let count = types::read_int(buf)?;
+    if version >= ProtocolVersion::V5 {
+        for _ in 0..count {
+            // [inetaddr]: a single byte length followed by the address bytes.
+            let addr_len = buf.read_u8()?;
+            if addr_len != 4 && addr_len != 16 {
+                return Err(LowLevelDeserializationError::InvalidInetLength(addr_len).into());
+            }
+            if buf.len() < addr_len as usize {
+                return Err(LowLevelDeserializationError::TooFewBytesReceived {
+                    expected: addr_len as usize,
+                    received: buf.len(),
+                }
+                .into());
+            }
+            buf.advance(addr_len as usize);
+            let _failure_code = types::read_short(buf)?;
+        }
+    }
+    Ok(count)
+}
+
 impl From<Error> for QueryError {
     fn from(error: Error) -> QueryError {
         QueryError::DbError(error.error, error.reason)
@@ -92,6 +128,7 @@ mod tests {
     use super::Error;
     use crate::errors::{DbError, OperationType, WriteType};
     use crate::frame::protocol_features::ProtocolFeatures;
+    use crate::frame::ProtocolVersion;
     use crate::Consistency;
     use bytes::Bytes;
     use std::convert::TryInto;
@@ -112,13 +149,14 @@ mod tests {
     // Tests deserialization of all errors without and additional data
     #[test]
     fn deserialize_simple_errors() {
-        let simple_error_mappings: [(i32, DbError); 11] = [
+        let simple_error_mappings: [(i32, DbError); 12] = [
             (0x0000, DbError::ServerError),
             (0x000A, DbError::ProtocolError),
             (0x0100, DbError::AuthenticationError),
             (0x1001, DbError::Overloaded),
             (0x1002, DbError::IsBootstrapping),
             (0x1003, DbError::TruncateError),
+            (0x1600, DbError::CdcWriteFailure),
             (0x2000, DbError::SyntaxError),
             (0x2100, DbError::Unauthorized),
             (0x2200, DbError::Invalid),
@@ -130,7 +168,8 @@ mod tests {
 
         for (error_code, expected_error) in &simple_error_mappings {
             let bytes: Vec<u8> = make_error_request_bytes(*error_code, "simple message");
-            let error: Error = Error::deserialize(&features, &mut bytes.as_slice()).unwrap();
+            let error: Error =
+                Error::deserialize(&features, ProtocolVersion::V4, &mut bytes.as_slice()).unwrap();
             assert_eq!(error.error, *expected_error);
             assert_eq!(error.reason, "simple message");
         }
@@ -145,7 +184,8 @@ mod tests {
         bytes.extend(2_i32.to_be_bytes());
         bytes.extend(3_i32.to_be_bytes());
 
-        let error: Error = Error::deserialize(&features, &mut bytes.as_slice()).unwrap();
+        let error: Error =
+            Error::deserialize(&features, ProtocolVersion::V4, &mut bytes.as_slice()).unwrap();
 
         assert_eq!(
             error.error,
@@ -172,7 +212,8 @@ mod tests {
         bytes.extend(write_type_str_len.to_be_bytes());
         bytes.extend(write_type_str.as_bytes());
 
-        let error: Error = Error::deserialize(&features, &mut bytes.as_slice()).unwrap();
+        let error: Error =
+            Error::deserialize(&features, ProtocolVersion::V4, &mut bytes.as_slice()).unwrap();
 
         assert_eq!(
             error.error,
@@ -196,7 +237,8 @@ mod tests {
         bytes.extend(32_i32.to_be_bytes());
         bytes.push(0_u8);
 
-        let error: Error = Error::deserialize(&features, &mut bytes.as_slice()).unwrap();
+        let error: Error =
+            Error::deserialize(&features, ProtocolVersion::V4, &mut bytes.as_slice()).unwrap();
 
         assert_eq!(
             error.error,
@@ -221,7 +263,8 @@ mod tests {
         bytes.extend(6_i32.to_be_bytes());
         bytes.push(123_u8); // Any non-zero value means data_present is true
 
-        let error: Error = Error::deserialize(&features, &mut bytes.as_slice()).unwrap();
+        let error: Error =
+            Error::deserialize(&features, ProtocolVersion::V4, &mut bytes.as_slice()).unwrap();
 
         assert_eq!(
             error.error,
@@ -236,6 +279,40 @@ mod tests {
         assert_eq!(error.reason, "message 2");
     }
 
+    #[test]
+    fn deserialize_read_failure_v5() {
+        let features = ProtocolFeatures::default();
+
+        let mut bytes = make_error_request_bytes(0x1300, "message 2");
+        bytes.extend(0x0003_i16.to_be_bytes());
+        bytes.extend(4_i32.to_be_bytes());
+        bytes.extend(5_i32.to_be_bytes());
+        // Reason map with an IPv4 and an IPv6 endpoint
+        bytes.extend(2_i32.to_be_bytes());
+        bytes.push(4);
+        bytes.extend([127, 0, 0, 1]);
+        bytes.extend(0x0001_i16.to_be_bytes());
+        bytes.push(16);
+        bytes.extend([0; 16]);
+        bytes.extend(0x0002_i16.to_be_bytes());
+        bytes.push(0_u8);
+
+        let error: Error =
+            Error::deserialize(&features, ProtocolVersion::V5, &mut bytes.as_slice()).unwrap();
+
+        assert_eq!(
+            error.error,
+            DbError::ReadFailure {
+                consistency: Consistency::Three,
+                received: 4,
+                required: 5,
+                numfailures: 2,
+                data_present: false,
+            }
+        );
+        assert_eq!(error.reason, "message 2");
+    }
+
     #[test]
     fn deserialize_function_failure() {
         let features = ProtocolFeatures::default();
@@ -264,7 +341,8 @@ mod tests {
         bytes.extend(type2_len.to_be_bytes());
         bytes.extend(type2.as_bytes());
 
-        let error: Error = Error::deserialize(&features, &mut bytes.as_slice()).unwrap();
+        let error: Error =
+            Error::deserialize(&features, ProtocolVersion::V4, &mut bytes.as_slice()).unwrap();
 
         assert_eq!(
             error.error,
@@ -293,7 +371,8 @@ mod tests {
         bytes.extend(write_type_str_len.to_be_bytes());
         bytes.extend(write_type_str.as_bytes());
 
-        let error: Error = Error::deserialize(&features, &mut bytes.as_slice()).unwrap();
+        let error: Error =
+            Error::deserialize(&features, ProtocolVersion::V4, &mut bytes.as_slice()).unwrap();
 
         assert_eq!(
             error.error,
@@ -308,6 +387,29 @@ mod tests {
         assert_eq!(error.reason, "message 2");
     }
 
+    #[test]
+    fn deserialize_cas_write_unknown() {
+        let features = ProtocolFeatures::default();
+
+        let mut bytes = make_error_request_bytes(0x1700, "message 2");
+        bytes.extend(0x0008_i16.to_be_bytes());
+        bytes.extend(1_i32.to_be_bytes());
+        bytes.extend(2_i32.to_be_bytes());
+
+        let error: Error =
+            Error::deserialize(&features, ProtocolVersion::V5, &mut bytes.as_slice()).unwrap();
+
+        assert_eq!(
+            error.error,
+            DbError::CasWriteUnknown {
+                consistency: Consistency::Serial,
+                received: 1,
+                required: 2,
+            }
+        );
+        assert_eq!(error.reason, "message 2");
+    }
+
     #[test]
     fn deserialize_already_exists() {
         let features = ProtocolFeatures::default();
@@ -325,7 +427,8 @@ mod tests {
         bytes.extend(table_name_len.to_be_bytes());
         bytes.extend(table_name.as_bytes());
 
-        let error: Error = Error::deserialize(&features, &mut bytes.as_slice()).unwrap();
+        let error: Error =
+            Error::deserialize(&features, ProtocolVersion::V4, &mut bytes.as_slice()).unwrap();
 
         assert_eq!(
             error.error,
@@ -346,7 +449,8 @@ mod tests {
         bytes.extend((statement_id.len() as i16).to_be_bytes());
         bytes.extend(statement_id);
 
-        let error: Error = Error::deserialize(&features, &mut bytes.as_slice()).unwrap();
+        let error: Error =
+            Error::deserialize(&features, ProtocolVersion::V4, &mut bytes.as_slice()).unwrap();
 
         assert_eq!(
             error.error,
@@ -366,7 +470,8 @@ mod tests {
         let mut bytes = make_error_request_bytes(0x4321, "message 1");
         bytes.extend([0u8]); // Read type
         bytes.extend([1u8]); // Rejected by coordinator
-        let error = Error::deserialize(&features, &mut bytes.as_slice()).unwrap();
+        let error =
+            Error::deserialize(&features, ProtocolVersion::V4, &mut bytes.as_slice()).unwrap();
 
         assert_eq!(
             error.error,
@@ -384,7 +489,8 @@ mod tests {
         let mut bytes = make_error_request_bytes(0x8765, "message 2");
         bytes.extend([1u8]); // Write type
         bytes.extend([0u8]); // Not rejected by coordinator
-        let error = Error::deserialize(&features, &mut bytes.as_slice()).unwrap();
+        let error =
+            Error::deserialize(&features, ProtocolVersion::V4, &mut bytes.as_slice()).unwrap();
 
         assert_eq!(
             error.error,
@@ -22,7 +22,7 @@ pub enum StatusChangeEvent {
     Down(SocketAddr),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum SchemaChangeEvent {
     KeyspaceChange {
         change_type: SchemaChangeType,
@@ -52,7 +52,7 @@ pub enum SchemaChangeEvent {
     },
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum SchemaChangeType {
     Created,
     Updated,
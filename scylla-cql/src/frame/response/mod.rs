@@ -10,8 +10,9 @@ pub use supported::Supported;
 
 use crate::frame::protocol_features::ProtocolFeatures;
 use crate::frame::response::result::ResultMetadata;
-use crate::frame::TryFromPrimitiveError;
+use crate::frame::{ProtocolVersion, TryFromPrimitiveError};
 use crate::{errors::QueryError, frame::frame_errors::ParseError};
+use bytes::Bytes;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 #[repr(u8)]
@@ -62,18 +63,22 @@ pub enum Response {
 impl Response {
     pub fn deserialize(
         features: &ProtocolFeatures,
+        version: ProtocolVersion,
         opcode: ResponseOpcode,
-        buf: &mut &[u8],
+        buf_bytes: &Bytes,
         cached_metadata: Option<&ResultMetadata>,
     ) -> Result<Response, ParseError> {
+        let buf = &mut &**buf_bytes;
         let response = match opcode {
-            ResponseOpcode::Error => Response::Error(Error::deserialize(features, buf)?),
+            ResponseOpcode::Error => Response::Error(Error::deserialize(features, version, buf)?),
             ResponseOpcode::Ready => Response::Ready,
             ResponseOpcode::Authenticate => {
                 Response::Authenticate(authenticate::Authenticate::deserialize(buf)?)
             }
             ResponseOpcode::Supported => Response::Supported(Supported::deserialize(buf)?),
-            ResponseOpcode::Result => Response::Result(result::deserialize(buf, cached_metadata)?),
+            ResponseOpcode::Result => {
+                Response::Result(result::deserialize(buf_bytes, version, cached_metadata)?)
+            }
             ResponseOpcode::Event => Response::Event(event::Event::deserialize(buf)?),
             ResponseOpcode::AuthChallenge => {
                 Response::AuthChallenge(authenticate::AuthChallenge::deserialize(buf)?)
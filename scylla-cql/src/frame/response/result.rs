@@ -3,12 +3,13 @@ use crate::frame::response::event::SchemaChangeEvent;
 use crate::frame::value::{
     Counter, CqlDate, CqlDecimal, CqlDuration, CqlTime, CqlTimestamp, CqlTimeuuid, CqlVarint,
 };
-use crate::frame::{frame_errors::ParseError, types};
+use crate::frame::{frame_errors::ParseError, types, ProtocolVersion};
 use crate::types::deserialize::result::{RowIterator, TypedRowIterator};
+use crate::types::deserialize::row::DeserializeRow;
 use crate::types::deserialize::value::{
     mk_deser_err, BuiltinDeserializationErrorKind, DeserializeValue, MapIterator, UdtIterator,
 };
-use crate::types::deserialize::{DeserializationError, FrameSlice};
+use crate::types::deserialize::{DeserializationError, FrameSlice, TypeCheckError};
 use bytes::{Buf, Bytes};
 use std::borrow::Cow;
 use std::{convert::TryInto, net::IpAddr, result::Result as StdResult, str};
@@ -22,6 +23,8 @@ pub struct SetKeyspace {
 #[derive(Debug)]
 pub struct Prepared {
     pub id: Bytes,
+    /// Id of the result metadata, present only in protocol v5.
+    pub result_metadata_id: Option<Bytes>,
     pub prepared_metadata: PreparedMetadata,
     pub result_metadata: ResultMetadata,
 }
@@ -69,6 +72,12 @@ pub enum ColumnType {
     Tuple(Vec<ColumnType>),
     Uuid,
     Varint,
+    /// Fixed-length vector of non-null elements, introduced in Cassandra 5.0.
+    /// On the wire, it is sent as a custom type.
+    Vector {
+        typ: Box<ColumnType>,
+        dimensions: u16,
+    },
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -110,6 +119,7 @@ pub enum CqlValue {
     Tuple(Vec<Option<CqlValue>>),
     Uuid(Uuid),
     Varint(CqlVarint),
+    Vector(Vec<CqlValue>),
 }
 
 impl<'a> TableSpec<'a> {
@@ -164,11 +174,30 @@ impl ColumnType {
             | ColumnType::Map(_, _)
             | ColumnType::Set(_)
             | ColumnType::UserDefinedType { .. }
-            | ColumnType::Custom(_) => false,
+            | ColumnType::Custom(_)
+            | ColumnType::Vector { .. } => false,
 
             _ => true,
         }
     }
+
+    // Returns the length of serialized values of this type, if all of them
+    // have the same length. Elements of such types are serialized in vectors
+    // without a length prefix.
+    pub(crate) fn fixed_serialized_size(&self) -> Option<usize> {
+        match self {
+            ColumnType::Boolean => Some(1),
+            ColumnType::Date | ColumnType::Float | ColumnType::Int => Some(4),
+            ColumnType::BigInt | ColumnType::Double | ColumnType::Time | ColumnType::Timestamp => {
+                Some(8)
+            }
+            ColumnType::Timeuuid | ColumnType::Uuid => Some(16),
+            ColumnType::Vector { typ, dimensions } => typ
+                .fixed_serialized_size()
+                .map(|size| size * *dimensions as usize),
+            _ => None,
+        }
+    }
 }
 
 impl CqlValue {
@@ -377,10 +406,18 @@ impl CqlValue {
         }
     }
 
+    pub fn as_vector(&self) -> Option<&Vec<CqlValue>> {
+        match self {
+            Self::Vector(v) => Some(v),
+            _ => None,
+        }
+    }
+
     pub fn into_vec(self) -> Option<Vec<CqlValue>> {
         match self {
             Self::List(s) => Some(s),
             Self::Set(s) => Some(s),
+            Self::Vector(v) => Some(v),
             _ => None,
         }
     }
@@ -426,6 +463,9 @@ pub struct ColumnSpec {
 pub struct ResultMetadata {
     col_count: usize,
     pub paging_state: Option<Bytes>,
+    /// Set if the server noticed that the result metadata cached by the client
+    /// is outdated and sent the new metadata along with its id (protocol v5 only).
+    pub new_metadata_id: Option<Bytes>,
     pub col_specs: Vec<ColumnSpec>,
 }
 
@@ -463,11 +503,37 @@ impl Row {
 pub struct Rows {
     pub metadata: ResultMetadata,
     pub rows_count: usize,
-    pub rows: Vec<Row>,
+    /// Serialized rows, as received from the server.
+    /// They are deserialized lazily, see [Rows::rows_iter].
+    pub raw_rows: Bytes,
     /// Original size of the serialized rows.
     pub serialized_size: usize,
 }
 
+impl Rows {
+    /// Returns an iterator which deserializes the rows as `R`.
+    ///
+    /// The rows can borrow from `self`, so types like `&str` and `&[u8]`
+    /// can be deserialized without copying the data.
+    pub fn rows_iter<'frame, R: DeserializeRow<'frame>>(
+        &'frame self,
+    ) -> StdResult<TypedRowIterator<'frame, R>, TypeCheckError> {
+        let raw_rows_iter = RowIterator::new(
+            self.rows_count,
+            &self.metadata.col_specs,
+            FrameSlice::new(&self.raw_rows),
+        );
+        TypedRowIterator::new(raw_rows_iter)
+    }
+
+    /// Deserializes all rows into [Row]s holding [CqlValue]s.
+    pub fn legacy_rows(&self) -> StdResult<Vec<Row>, DeserializationError> {
+        self.rows_iter::<Row>()
+            .map_err(|err| DeserializationError::new(err.0))?
+            .collect()
+    }
+}
+
 #[derive(Debug)]
 pub enum Result {
     Void,
@@ -491,7 +557,10 @@ fn deser_type(buf: &mut &[u8]) -> StdResult<ColumnType, ParseError> {
             let type_str: String = types::read_string(buf)?.to_string();
             match type_str.as_str() {
                 "org.apache.cassandra.db.marshal.DurationType" => Duration,
-                _ => Custom(type_str),
+                _ => match parse_marshal_type(&type_str) {
+                    Some(typ @ Vector { .. }) => typ,
+                    _ => Custom(type_str),
+                },
             }
         }
         0x0001 => Ascii,
@@ -552,6 +621,121 @@ fn deser_type(buf: &mut &[u8]) -> StdResult<ColumnType, ParseError> {
     })
 }
 
+const MARSHAL_PACKAGE: &str = "org.apache.cassandra.db.marshal.";
+
+/// Parses a type given by the name of its Cassandra marshal class,
+/// e.g. `org.apache.cassandra.db.marshal.VectorType(org.apache.cassandra.db.marshal.FloatType, 3)`.
+/// Such names are used to describe custom types, which don't have their own type id.
+fn parse_marshal_type(name: &str) -> Option<ColumnType> {
+    match parse_marshal_type_prefix(name)? {
+        (typ, "") => Some(typ),
+        _ => None,
+    }
+}
+
+enum MarshalTypeParam {
+    Type(ColumnType),
+    Number(u16),
+}
+
+fn parse_marshal_type_prefix(name: &str) -> Option<(ColumnType, &str)> {
+    use ColumnType::*;
+
+    let name = name.trim_start();
+    let class_end = name.find(['(', ',', ')']).unwrap_or(name.len());
+    let (class, rest) = name.split_at(class_end);
+    let class = class.trim_end();
+    let class = class.strip_prefix(MARSHAL_PACKAGE).unwrap_or(class);
+
+    let typ = match class {
+        "AsciiType" => Ascii,
+        "BooleanType" => Boolean,
+        "BytesType" => Blob,
+        "CounterColumnType" => Counter,
+        "DecimalType" => Decimal,
+        "DoubleType" => Double,
+        "DurationType" => Duration,
+        "FloatType" => Float,
+        "InetAddressType" => Inet,
+        "Int32Type" => Int,
+        "IntegerType" => Varint,
+        "LongType" => BigInt,
+        "ShortType" => SmallInt,
+        "ByteType" => TinyInt,
+        "SimpleDateType" => Date,
+        "TimeType" => Time,
+        "TimeUUIDType" => Timeuuid,
+        "TimestampType" => Timestamp,
+        "UTF8Type" => Text,
+        "UUIDType" => Uuid,
+        _ => return parse_parameterized_marshal_type(class, rest),
+    };
+    Some((typ, rest))
+}
+
+fn parse_parameterized_marshal_type<'a>(
+    class: &str,
+    rest: &'a str,
+) -> Option<(ColumnType, &'a str)> {
+    use ColumnType::*;
+    use MarshalTypeParam::*;
+
+    let mut rest = rest.strip_prefix('(')?;
+    let mut params = Vec::new();
+    loop {
+        if let Some((typ, params_rest)) = parse_marshal_type_prefix(rest) {
+            params.push(Type(typ));
+            rest = params_rest;
+        } else {
+            let param_end = rest.find([',', ')'])?;
+            params.push(Number(rest[..param_end].trim().parse().ok()?));
+            rest = &rest[param_end..];
+        }
+
+        let params_rest = rest.trim_start();
+        if let Some(params_rest) = params_rest.strip_prefix(',') {
+            rest = params_rest;
+        } else {
+            rest = params_rest.strip_prefix(')')?;
+            break;
+        }
+    }
+
+    let mut params = params.into_iter();
+    let mut next_type = || match params.next()? {
+        Type(typ) => Some(typ),
+        Number(_) => None,
+    };
+    let typ = match class {
+        "FrozenType" | "ReversedType" => next_type()?,
+        "ListType" => List(Box::new(next_type()?)),
+        "SetType" => Set(Box::new(next_type()?)),
+        "MapType" => Map(Box::new(next_type()?), Box::new(next_type()?)),
+        "TupleType" => Tuple(
+            params
+                .by_ref()
+                .map(|param| match param {
+                    Type(typ) => Some(typ),
+                    Number(_) => None,
+                })
+                .collect::<Option<_>>()?,
+        ),
+        "VectorType" => {
+            let typ = Box::new(next_type()?);
+            match params.next()? {
+                Number(dimensions) => Vector { typ, dimensions },
+                Type(_) => return None,
+            }
+        }
+        _ => return None,
+    };
+    // All parameters must have been consumed.
+    match params.next() {
+        None => Some((typ, rest)),
+        Some(_) => None,
+    }
+}
+
 fn deser_col_specs(
     buf: &mut &[u8],
     global_table_spec: &Option<TableSpec<'static>>,
@@ -580,6 +764,7 @@ fn deser_result_metadata(buf: &mut &[u8]) -> StdResult<ResultMetadata, ParseErro
     let global_tables_spec = flags & 0x0001 != 0;
     let has_more_pages = flags & 0x0002 != 0;
     let no_metadata = flags & 0x0004 != 0;
+    let metadata_changed = flags & 0x0008 != 0;
 
     let col_count: usize = types::read_int(buf)?.try_into()?;
 
@@ -589,10 +774,17 @@ fn deser_result_metadata(buf: &mut &[u8]) -> StdResult<ResultMetadata, ParseErro
         None
     };
 
+    let new_metadata_id = if metadata_changed {
+        Some(types::read_short_bytes(buf)?.to_owned().into())
+    } else {
+        None
+    };
+
     if no_metadata {
         return Ok(ResultMetadata {
             col_count,
             paging_state,
+            new_metadata_id,
             col_specs: vec![],
         });
     }
@@ -608,17 +800,26 @@ fn deser_result_metadata(buf: &mut &[u8]) -> StdResult<ResultMetadata, ParseErro
     Ok(ResultMetadata {
         col_count,
         paging_state,
+        new_metadata_id,
         col_specs,
     })
 }
 
-fn deser_prepared_metadata(buf: &mut &[u8]) -> StdResult<PreparedMetadata, ParseError> {
+fn deser_prepared_metadata(
+    buf: &mut &[u8],
+    version: ProtocolVersion,
+) -> StdResult<PreparedMetadata, ParseError> {
     let flags = types::read_int(buf)?;
     let global_tables_spec = flags & 0x0001 != 0;
 
     let col_count = types::read_int_length(buf)?;
 
-    let pk_count: usize = types::read_int(buf)?.try_into()?;
+    // Partition key indexes were added in protocol v4.
+    let pk_count: usize = if version >= ProtocolVersion::V4 {
+        types::read_int(buf)?.try_into()?
+    } else {
+        0
+    };
 
     let mut pk_indexes = Vec::with_capacity(pk_count);
     for i in 0..pk_count {
@@ -802,19 +1003,28 @@ pub fn deser_cql_value(
                 .collect::<StdResult<_, _>>()?;
             CqlValue::Tuple(t)
         }
+        Vector { .. } => {
+            let v = Vec::<CqlValue>::deserialize(typ, v)?;
+            CqlValue::Vector(v)
+        }
     })
 }
 
 fn deser_rows(
+    buf_bytes: &Bytes,
     buf: &mut &[u8],
     cached_metadata: Option<&ResultMetadata>,
 ) -> StdResult<Rows, ParseError> {
     let server_metadata = deser_result_metadata(buf)?;
 
+    // If the server reports that the cached metadata is outdated,
+    // it provides the new metadata, which must be used instead.
+    let cached_metadata = cached_metadata.filter(|_| server_metadata.new_metadata_id.is_none());
+
     let metadata = match cached_metadata {
         Some(metadata) => metadata.clone(),
         None => {
-            // No cached_metadata provided. Server is supposed to provide the result metadata.
+            // No up-to-date cached_metadata provided. Server is supposed to provide the result metadata.
             if server_metadata.col_count != server_metadata.col_specs.len() {
                 return Err(ParseError::BadIncomingData(format!(
                     "Bad result metadata provided in the response. Expected {} column specifications, received: {}",
@@ -830,21 +1040,16 @@ fn deser_rows(
 
     let rows_count: usize = types::read_int(buf)?.try_into()?;
 
-    let raw_rows_iter = RowIterator::new(
-        rows_count,
-        &metadata.col_specs,
-        FrameSlice::new_borrowed(buf),
-    );
-    let rows_iter = TypedRowIterator::<Row>::new(raw_rows_iter)
-        .map_err(|err| DeserializationError::new(err.0))?;
-
-    let rows = rows_iter.collect::<StdResult<_, _>>()?;
+    // Rows are the last part of the response, so all the remaining bytes
+    // belong to them.
+    let raw_rows = buf_bytes.slice_ref(buf);
+    buf.advance(buf.len());
 
     Ok(Rows {
         metadata,
         rows_count,
-        rows,
-        serialized_size: original_size - buf.len(),
+        raw_rows,
+        serialized_size: original_size,
     })
 }
 
@@ -854,14 +1059,20 @@ fn deser_set_keyspace(buf: &mut &[u8]) -> StdResult<SetKeyspace, ParseError> {
     Ok(SetKeyspace { keyspace_name })
 }
 
-fn deser_prepared(buf: &mut &[u8]) -> StdResult<Prepared, ParseError> {
+fn deser_prepared(buf: &mut &[u8], version: ProtocolVersion) -> StdResult<Prepared, ParseError> {
     let id_len = types::read_short(buf)? as usize;
     let id: Bytes = buf[0..id_len].to_owned().into();
     buf.advance(id_len);
-    let prepared_metadata = deser_prepared_metadata(buf)?;
+    let result_metadata_id = if version >= ProtocolVersion::V5 {
+        Some(types::read_short_bytes(buf)?.to_owned().into())
+    } else {
+        None
+    };
+    let prepared_metadata = deser_prepared_metadata(buf, version)?;
     let result_metadata = deser_result_metadata(buf)?;
     Ok(Prepared {
         id,
+        result_metadata_id,
         prepared_metadata,
         result_metadata,
     })
@@ -875,15 +1086,17 @@ fn deser_schema_change(buf: &mut &[u8]) -> StdResult<SchemaChange, ParseError> {
 }
 
 pub fn deserialize(
-    buf: &mut &[u8],
+    buf_bytes: &Bytes,
+    version: ProtocolVersion,
     cached_metadata: Option<&ResultMetadata>,
 ) -> StdResult<Result, ParseError> {
     use self::Result::*;
+    let buf = &mut &**buf_bytes;
     Ok(match types::read_int(buf)? {
         0x0001 => Void,
-        0x0002 => Rows(deser_rows(buf, cached_metadata)?),
+        0x0002 => Rows(deser_rows(buf_bytes, buf, cached_metadata)?),
         0x0003 => SetKeyspace(deser_set_keyspace(buf)?),
-        0x0004 => Prepared(deser_prepared(buf)?),
+        0x0004 => Prepared(deser_prepared(buf, version)?),
         0x0005 => SchemaChange(deser_schema_change(buf)?),
         k => {
             return Err(ParseError::BadIncomingData(format!(
@@ -897,8 +1110,10 @@ pub fn deserialize(
 #[cfg(test)]
 mod tests {
     use crate as scylla;
+    use crate::frame::types;
     use crate::frame::value::{Counter, CqlDate, CqlDuration, CqlTime, CqlTimestamp, CqlTimeuuid};
-    use scylla::frame::response::result::{ColumnType, CqlValue};
+    use crate::frame::ProtocolVersion;
+    use scylla::frame::response::result::{ColumnType, CqlValue, ResultMetadata};
     use std::str::FromStr;
     use uuid::Uuid;
 
@@ -1715,4 +1930,155 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_deserialize_prepared_v5() {
+        let mut buf = Vec::new();
+        types::write_int(0x0004, &mut buf);
+        types::write_short_bytes(&[1, 2, 3], &mut buf).unwrap();
+        types::write_short_bytes(&[4, 5], &mut buf).unwrap();
+        // Prepared metadata: no flags, no bind markers, no partition key indexes
+        types::write_int(0, &mut buf);
+        types::write_int(0, &mut buf);
+        types::write_int(0, &mut buf);
+        // Result metadata: no metadata, no columns
+        types::write_int(0x0004, &mut buf);
+        types::write_int(0, &mut buf);
+
+        let result = super::deserialize(&buf.into(), ProtocolVersion::V5, None).unwrap();
+        match result {
+            super::Result::Prepared(prepared) => {
+                assert_eq!(&prepared.id[..], &[1, 2, 3]);
+                assert_eq!(prepared.result_metadata_id.as_deref(), Some(&[4, 5][..]));
+            }
+            _ => panic!("Expected a Prepared result"),
+        }
+    }
+
+    #[test]
+    fn test_deserialize_vector_type() {
+        let cases = [
+            (
+                "org.apache.cassandra.db.marshal.VectorType(org.apache.cassandra.db.marshal.FloatType, 3)",
+                ColumnType::Vector {
+                    typ: Box::new(ColumnType::Float),
+                    dimensions: 3,
+                },
+            ),
+            (
+                "org.apache.cassandra.db.marshal.VectorType(\
+                    org.apache.cassandra.db.marshal.MapType(\
+                        org.apache.cassandra.db.marshal.UTF8Type,\
+                        org.apache.cassandra.db.marshal.VectorType(org.apache.cassandra.db.marshal.Int32Type, 2)\
+                    ), 1024)",
+                ColumnType::Vector {
+                    typ: Box::new(ColumnType::Map(
+                        Box::new(ColumnType::Text),
+                        Box::new(ColumnType::Vector {
+                            typ: Box::new(ColumnType::Int),
+                            dimensions: 2,
+                        }),
+                    )),
+                    dimensions: 1024,
+                },
+            ),
+            // Other custom types are left as they are.
+            (
+                "org.apache.cassandra.db.marshal.VectorType(org.apache.cassandra.db.marshal.FloatType)",
+                ColumnType::Custom(
+                    "org.apache.cassandra.db.marshal.VectorType(org.apache.cassandra.db.marshal.FloatType)"
+                        .to_string(),
+                ),
+            ),
+            (
+                "org.apache.cassandra.db.marshal.ListType(org.apache.cassandra.db.marshal.FloatType)",
+                ColumnType::Custom(
+                    "org.apache.cassandra.db.marshal.ListType(org.apache.cassandra.db.marshal.FloatType)"
+                        .to_string(),
+                ),
+            ),
+            (
+                "com.example.MyType",
+                ColumnType::Custom("com.example.MyType".to_string()),
+            ),
+        ];
+
+        for (class_name, expected) in cases {
+            let mut buf = Vec::new();
+            types::write_short(0x0000, &mut buf);
+            types::write_string(class_name, &mut buf).unwrap();
+            assert_eq!(super::deser_type(&mut &buf[..]).unwrap(), expected);
+        }
+
+        let vector = CqlValue::Vector(vec![CqlValue::Float(1.0), CqlValue::Float(2.0)]);
+        assert_eq!(vector.as_vector().unwrap().len(), 2);
+        assert_eq!(vector.into_vec().unwrap()[1], CqlValue::Float(2.0));
+    }
+
+    #[test]
+    fn test_deserialize_prepared_v3() {
+        let mut buf = Vec::new();
+        types::write_int(0x0004, &mut buf);
+        types::write_short_bytes(&[1, 2, 3], &mut buf).unwrap();
+        // Prepared metadata: global table spec, one bind marker and no partition key indexes
+        types::write_int(0x0001, &mut buf);
+        types::write_int(1, &mut buf);
+        types::write_string("ks", &mut buf).unwrap();
+        types::write_string("t", &mut buf).unwrap();
+        types::write_string("a", &mut buf).unwrap();
+        types::write_short(0x0009, &mut buf);
+        // Result metadata: no metadata, no columns
+        types::write_int(0x0004, &mut buf);
+        types::write_int(0, &mut buf);
+
+        let result = super::deserialize(&buf.into(), ProtocolVersion::V3, None).unwrap();
+        match result {
+            super::Result::Prepared(prepared) => {
+                assert_eq!(&prepared.id[..], &[1, 2, 3]);
+                assert_eq!(prepared.result_metadata_id, None);
+                assert_eq!(prepared.prepared_metadata.col_count, 1);
+                assert!(prepared.prepared_metadata.pk_indexes.is_empty());
+                assert_eq!(prepared.prepared_metadata.col_specs[0].name, "a");
+            }
+            _ => panic!("Expected a Prepared result"),
+        }
+    }
+
+    #[test]
+    fn test_deserialize_rows_with_changed_metadata() {
+        let mut buf = Vec::new();
+        types::write_int(0x0002, &mut buf);
+        // Result metadata: global table spec and metadata changed flags, one column
+        types::write_int(0x0001 | 0x0008, &mut buf);
+        types::write_int(1, &mut buf);
+        types::write_short_bytes(&[7, 7], &mut buf).unwrap();
+        types::write_string("ks", &mut buf).unwrap();
+        types::write_string("t", &mut buf).unwrap();
+        types::write_string("a", &mut buf).unwrap();
+        types::write_short(0x0009, &mut buf);
+        // A single row
+        types::write_int(1, &mut buf);
+        types::write_bytes(&42_i32.to_be_bytes(), &mut buf).unwrap();
+
+        // The cached metadata is outdated, so it must be ignored.
+        let cached_metadata = ResultMetadata {
+            col_count: 0,
+            paging_state: None,
+            new_metadata_id: None,
+            col_specs: vec![],
+        };
+
+        let result =
+            super::deserialize(&buf.into(), ProtocolVersion::V5, Some(&cached_metadata)).unwrap();
+        match result {
+            super::Result::Rows(rows) => {
+                assert_eq!(rows.metadata.new_metadata_id.as_deref(), Some(&[7, 7][..]));
+                assert_eq!(rows.metadata.col_specs.len(), 1);
+                assert_eq!(rows.rows_count, 1);
+                let rows = rows.legacy_rows().unwrap();
+                assert_eq!(rows[0].columns[0], Some(CqlValue::Int(42)));
+            }
+            _ => panic!("Expected a Rows result"),
+        }
+    }
 }
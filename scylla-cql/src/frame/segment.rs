@@ -0,0 +1,462 @@
+//! Segment framing, used by protocol v5 and above.
+//!
+//! Starting with protocol v5, once the STARTUP handshake completes, frames
+//! are no longer sent over the connection on their own. Instead, they are
+//! wrapped in segments, each of which carries a CRC24 checksum of its header
+//! and a CRC32 checksum of its payload. A segment is either self-contained,
+//! in which case it holds one or more complete frames, or it holds a part
+//! of a single frame that is too large to fit into one segment.
+//!
+//! If compression is negotiated, it is applied to segment payloads instead
+//! of individual frames. Only LZ4 compression is available in this mode.
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use super::frame_errors::{FrameError, ParseError};
+use super::response::ResponseOpcode;
+use super::{parse_response_header, Compression, FrameParams, HEADER_SIZE};
+
+/// The maximum length of a segment payload, both before and after compression.
+pub const MAX_PAYLOAD_LENGTH: usize = (1 << 17) - 1;
+
+const UNCOMPRESSED_HEADER_LENGTH: usize = 3;
+const COMPRESSED_HEADER_LENGTH: usize = 5;
+const HEADER_CRC_LENGTH: usize = 3;
+const PAYLOAD_CRC_LENGTH: usize = 4;
+
+const CRC24_INIT: u32 = 0x875060;
+const CRC24_POLY: u32 = 0x1974F0B;
+
+// The payload checksum is computed as if these bytes preceded the payload.
+const CRC32_INITIAL_BYTES: [u8; 4] = [0xFA, 0x2D, 0x55, 0xCA];
+const CRC32_TABLE: [u32; 256] = make_crc32_table();
+
+const fn make_crc32_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB88320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+fn crc24(bytes: &[u8]) -> u32 {
+    let mut crc = CRC24_INIT;
+    for &byte in bytes {
+        crc ^= (byte as u32) << 16;
+        for _ in 0..8 {
+            crc <<= 1;
+            if crc & 0x1000000 != 0 {
+                crc ^= CRC24_POLY;
+            }
+        }
+    }
+    crc
+}
+
+fn crc32_update(mut crc: u32, bytes: &[u8]) -> u32 {
+    for &byte in bytes {
+        crc = CRC32_TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    crc
+}
+
+fn crc32(payload: &[u8]) -> u32 {
+    !crc32_update(crc32_update(!0, &CRC32_INITIAL_BYTES), payload)
+}
+
+/// A segment read from the wire, with its checksums verified
+/// and its payload decompressed.
+#[derive(Debug, Clone)]
+pub struct Segment {
+    pub payload: Bytes,
+    pub self_contained: bool,
+}
+
+/// Wraps serialized frames in segments and appends them to `out`.
+///
+/// `frames` must consist of complete frames, laid out one after another.
+/// The frames are packed into self-contained segments, except for frames
+/// too large to fit into one segment, which are split over multiple segments.
+pub fn encode_segments(
+    frames: &[u8],
+    compression: Option<Compression>,
+    out: &mut Vec<u8>,
+) -> Result<(), FrameError> {
+    let mut segment_start = 0;
+    let mut pos = 0;
+    while pos < frames.len() {
+        let frame_len = frame_length(&frames[pos..])?;
+        if frame_len > MAX_PAYLOAD_LENGTH {
+            if segment_start < pos {
+                write_segment(&frames[segment_start..pos], true, compression, out)?;
+            }
+            for part in frames[pos..pos + frame_len].chunks(MAX_PAYLOAD_LENGTH) {
+                write_segment(part, false, compression, out)?;
+            }
+            pos += frame_len;
+            segment_start = pos;
+            continue;
+        }
+        if pos + frame_len - segment_start > MAX_PAYLOAD_LENGTH {
+            write_segment(&frames[segment_start..pos], true, compression, out)?;
+            segment_start = pos;
+        }
+        pos += frame_len;
+    }
+    if segment_start < pos {
+        write_segment(&frames[segment_start..pos], true, compression, out)?;
+    }
+    Ok(())
+}
+
+// Returns the total length (header included) of the frame at the beginning of `buf`.
+fn frame_length(buf: &[u8]) -> Result<usize, FrameError> {
+    if buf.len() < HEADER_SIZE {
+        return Err(ParseError::BadDataToSerialize(format!(
+            "Incomplete frame header: {} bytes",
+            buf.len()
+        ))
+        .into());
+    }
+    let body_len = u32::from_be_bytes(buf[5..HEADER_SIZE].try_into().unwrap()) as usize;
+    let frame_len = HEADER_SIZE + body_len;
+    if buf.len() < frame_len {
+        return Err(ParseError::BadDataToSerialize(format!(
+            "Incomplete frame: expected {} bytes, got {}",
+            frame_len,
+            buf.len()
+        ))
+        .into());
+    }
+    Ok(frame_len)
+}
+
+fn write_segment(
+    payload: &[u8],
+    self_contained: bool,
+    compression: Option<Compression>,
+    out: &mut Vec<u8>,
+) -> Result<(), FrameError> {
+    debug_assert!(payload.len() <= MAX_PAYLOAD_LENGTH);
+    match compression {
+        None => {
+            let header = payload.len() as u64 | (self_contained as u64) << 17;
+            write_header(header, UNCOMPRESSED_HEADER_LENGTH, out);
+            write_payload(payload, out);
+        }
+        Some(Compression::Lz4) => {
+            let compressed = lz4_flex::block::compress(payload);
+            // If compression does not pay off, the payload is sent as is,
+            // which is marked by an uncompressed length of zero.
+            let (payload, uncompressed_len) = if compressed.len() < payload.len() {
+                (&compressed[..], payload.len())
+            } else {
+                (payload, 0)
+            };
+            let header = payload.len() as u64
+                | (uncompressed_len as u64) << 17
+                | (self_contained as u64) << 34;
+            write_header(header, COMPRESSED_HEADER_LENGTH, out);
+            write_payload(payload, out);
+        }
+        Some(compression @ Compression::Snappy) => {
+            return Err(FrameError::SegmentCompressionNotSupported(compression));
+        }
+    }
+    Ok(())
+}
+
+fn write_header(header: u64, header_len: usize, out: &mut Vec<u8>) {
+    let header_bytes = &header.to_le_bytes()[..header_len];
+    out.extend_from_slice(header_bytes);
+    out.extend_from_slice(&crc24(header_bytes).to_le_bytes()[..HEADER_CRC_LENGTH]);
+}
+
+fn write_payload(payload: &[u8], out: &mut Vec<u8>) {
+    out.extend_from_slice(payload);
+    out.put_u32_le(crc32(payload));
+}
+
+/// Reads a single segment, verifying its checksums and decompressing its payload.
+pub async fn read_segment(
+    reader: &mut (impl AsyncRead + Unpin),
+    compression: Option<Compression>,
+) -> Result<Segment, FrameError> {
+    let header_len = match compression {
+        None => UNCOMPRESSED_HEADER_LENGTH,
+        Some(Compression::Lz4) => COMPRESSED_HEADER_LENGTH,
+        Some(compression @ Compression::Snappy) => {
+            return Err(FrameError::SegmentCompressionNotSupported(compression))
+        }
+    };
+
+    let mut raw_header = [0u8; COMPRESSED_HEADER_LENGTH + HEADER_CRC_LENGTH];
+    let raw_header = &mut raw_header[..header_len + HEADER_CRC_LENGTH];
+    reader.read_exact(raw_header).await?;
+
+    let (header_bytes, crc_bytes) = raw_header.split_at(header_len);
+    let computed = crc24(header_bytes);
+    let received = read_uint_le(crc_bytes) as u32;
+    if computed != received {
+        return Err(FrameError::SegmentHeaderChecksumMismatch { computed, received });
+    }
+
+    let header = read_uint_le(header_bytes);
+    let payload_len = (header & MAX_PAYLOAD_LENGTH as u64) as usize;
+    let (uncompressed_len, self_contained) = match compression {
+        None => (0, header & (1 << 17) != 0),
+        Some(_) => (
+            ((header >> 17) & MAX_PAYLOAD_LENGTH as u64) as usize,
+            header & (1 << 34) != 0,
+        ),
+    };
+
+    let mut payload = vec![0u8; payload_len + PAYLOAD_CRC_LENGTH];
+    reader.read_exact(&mut payload).await?;
+    let received = (&payload[payload_len..]).get_u32_le();
+    payload.truncate(payload_len);
+    let computed = crc32(&payload);
+    if computed != received {
+        return Err(FrameError::SegmentPayloadChecksumMismatch { computed, received });
+    }
+
+    if uncompressed_len != 0 {
+        payload = lz4_flex::block::decompress(&payload, uncompressed_len)?;
+    }
+
+    Ok(Segment {
+        payload: payload.into(),
+        self_contained,
+    })
+}
+
+fn read_uint_le(bytes: &[u8]) -> u64 {
+    let mut buf = [0u8; 8];
+    buf[..bytes.len()].copy_from_slice(bytes);
+    u64::from_le_bytes(buf)
+}
+
+/// Reads response frames from a connection which uses segment framing.
+///
+/// Frames are reassembled from segment payloads, so a frame split over
+/// multiple segments is returned only after all of its parts arrive.
+pub struct SegmentedFrameReader {
+    compression: Option<Compression>,
+    buffer: BytesMut,
+}
+
+impl SegmentedFrameReader {
+    pub fn new(compression: Option<Compression>) -> Self {
+        Self {
+            compression,
+            buffer: BytesMut::new(),
+        }
+    }
+
+    /// Reads the next response frame, reading as many segments as necessary.
+    pub async fn read_response_frame(
+        &mut self,
+        reader: &mut (impl AsyncRead + Unpin),
+    ) -> Result<(FrameParams, ResponseOpcode, Bytes), FrameError> {
+        loop {
+            if let Some(raw_header) = self.buffer.get(..HEADER_SIZE) {
+                let (params, opcode, length) =
+                    parse_response_header(raw_header.try_into().unwrap())?;
+                if self.buffer.len() >= HEADER_SIZE + length {
+                    self.buffer.advance(HEADER_SIZE);
+                    let body = self.buffer.split_to(length).freeze();
+                    return Ok((params, opcode, body));
+                }
+            }
+
+            let segment = read_segment(reader, self.compression).await?;
+            self.buffer.extend_from_slice(&segment.payload);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+
+    fn make_response_frame(stream: i16, body: &[u8]) -> Vec<u8> {
+        let mut frame = vec![0x85, 0x00];
+        frame.extend(stream.to_be_bytes());
+        frame.push(ResponseOpcode::Result as u8);
+        frame.extend((body.len() as u32).to_be_bytes());
+        frame.extend(body);
+        frame
+    }
+
+    async fn read_frames(
+        mut segments: &[u8],
+        compression: Option<Compression>,
+        count: usize,
+    ) -> Vec<(i16, Bytes)> {
+        let mut reader = SegmentedFrameReader::new(compression);
+        let mut frames = Vec::new();
+        for _ in 0..count {
+            let (params, opcode, body) = reader.read_response_frame(&mut segments).await.unwrap();
+            assert_eq!(opcode, ResponseOpcode::Result);
+            frames.push((params.stream, body));
+        }
+        assert!(segments.is_empty());
+        frames
+    }
+
+    #[test]
+    fn test_crc32() {
+        // Standard check value of CRC-32/ISO-HDLC.
+        assert_eq!(!crc32_update(!0, b"123456789"), 0xCBF43926);
+    }
+
+    #[tokio::test]
+    async fn test_self_contained_segments() {
+        for compression in [None, Some(Compression::Lz4)] {
+            let bodies: Vec<Vec<u8>> = vec![vec![], b"hello".repeat(10), vec![42; 1000]];
+            let mut frames = Vec::new();
+            for (stream, body) in bodies.iter().enumerate() {
+                frames.extend(make_response_frame(stream as i16, body));
+            }
+
+            let mut segments = Vec::new();
+            encode_segments(&frames, compression, &mut segments).unwrap();
+
+            let segment = read_segment(&mut &segments[..], compression).await.unwrap();
+            assert!(segment.self_contained);
+            assert_eq!(&segment.payload[..], &frames[..]);
+
+            let read = read_frames(&segments, compression, bodies.len()).await;
+            for (stream, body) in bodies.iter().enumerate() {
+                assert_eq!(read[stream].0, stream as i16);
+                assert_eq!(&read[stream].1[..], &body[..]);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_large_frame_is_split() {
+        for compression in [None, Some(Compression::Lz4)] {
+            let small_body = b"small".to_vec();
+            let large_body: Vec<u8> = (0..3 * MAX_PAYLOAD_LENGTH).map(|i| i as u8).collect();
+            let mut frames = make_response_frame(1, &small_body);
+            frames.extend(make_response_frame(2, &large_body));
+            frames.extend(make_response_frame(3, &small_body));
+
+            let mut segments = Vec::new();
+            encode_segments(&frames, compression, &mut segments).unwrap();
+
+            // The first frame forms a self-contained segment,
+            // the large one is split into 4 parts.
+            let mut buf = &segments[..];
+            let first = read_segment(&mut buf, compression).await.unwrap();
+            assert!(first.self_contained);
+            for _ in 0..4 {
+                let part = read_segment(&mut buf, compression).await.unwrap();
+                assert!(!part.self_contained);
+            }
+            let last = read_segment(&mut buf, compression).await.unwrap();
+            assert!(last.self_contained);
+            assert!(buf.is_empty());
+
+            let read = read_frames(&segments, compression, 3).await;
+            assert_eq!(read[0], (1, Bytes::from(small_body.clone())));
+            assert_eq!(read[1], (2, Bytes::from(large_body)));
+            assert_eq!(read[2], (3, Bytes::from(small_body)));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_many_frames_fill_multiple_segments() {
+        let body = vec![7u8; 1000];
+        let frame = make_response_frame(0, &body);
+        let frames = frame.repeat(300);
+
+        let mut segments = Vec::new();
+        encode_segments(&frames, None, &mut segments).unwrap();
+
+        // Frames must not be split when they fit into a segment.
+        let mut buf = &segments[..];
+        let mut total = 0;
+        while !buf.is_empty() {
+            let segment = read_segment(&mut buf, None).await.unwrap();
+            assert!(segment.self_contained);
+            assert!(segment.payload.len() <= MAX_PAYLOAD_LENGTH);
+            assert_eq!(segment.payload.len() % frame.len(), 0);
+            total += segment.payload.len();
+        }
+        assert_eq!(total, frames.len());
+    }
+
+    #[tokio::test]
+    async fn test_incompressible_payload() {
+        // Pseudo-random bytes which LZ4 cannot compress.
+        let mut state = 0x2545F491u32;
+        let body: Vec<u8> = (0..4096)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect();
+        let frames = make_response_frame(5, &body);
+
+        let mut segments = Vec::new();
+        encode_segments(&frames, Some(Compression::Lz4), &mut segments).unwrap();
+
+        // Uncompressed length is zero, so the payload is stored as is.
+        let header = read_uint_le(&segments[..COMPRESSED_HEADER_LENGTH]);
+        assert_eq!((header >> 17) & MAX_PAYLOAD_LENGTH as u64, 0);
+
+        let read = read_frames(&segments, Some(Compression::Lz4), 1).await;
+        assert_eq!(&read[0].1[..], &body[..]);
+    }
+
+    #[tokio::test]
+    async fn test_corruption_is_detected() {
+        let frames = make_response_frame(0, b"some body");
+        let mut segments = Vec::new();
+        encode_segments(&frames, None, &mut segments).unwrap();
+
+        let mut corrupted_header = segments.clone();
+        corrupted_header[0] ^= 0x01;
+        assert_matches!(
+            read_segment(&mut &corrupted_header[..], None).await,
+            Err(FrameError::SegmentHeaderChecksumMismatch { .. })
+        );
+
+        let mut corrupted_payload = segments.clone();
+        corrupted_payload[UNCOMPRESSED_HEADER_LENGTH + HEADER_CRC_LENGTH + 3] ^= 0x01;
+        assert_matches!(
+            read_segment(&mut &corrupted_payload[..], None).await,
+            Err(FrameError::SegmentPayloadChecksumMismatch { .. })
+        );
+    }
+
+    #[test]
+    fn test_snappy_is_rejected() {
+        let frames = make_response_frame(0, b"some body");
+        let mut segments = Vec::new();
+        assert_matches!(
+            encode_segments(&frames, Some(Compression::Snappy), &mut segments),
+            Err(FrameError::SegmentCompressionNotSupported(
+                Compression::Snappy
+            ))
+        );
+    }
+}
@@ -1390,6 +1390,47 @@ fn serialize_tuple<V: Value>(
     Ok(())
 }
 
+// Vector elements are not prefixed with their [int] length. Elements of
+// variable-size types are prefixed with their length encoded as unsigned vint.
+fn serialize_vector(elements: &[CqlValue], buf: &mut Vec<u8>) -> Result<(), ValueTooBig> {
+    fn has_fixed_size(value: &CqlValue) -> bool {
+        match value {
+            CqlValue::Boolean(_)
+            | CqlValue::Date(_)
+            | CqlValue::Double(_)
+            | CqlValue::Float(_)
+            | CqlValue::Int(_)
+            | CqlValue::BigInt(_)
+            | CqlValue::Time(_)
+            | CqlValue::Timestamp(_)
+            | CqlValue::Timeuuid(_)
+            | CqlValue::Uuid(_) => true,
+            CqlValue::Vector(v) => v.iter().all(has_fixed_size),
+            _ => false,
+        }
+    }
+
+    let bytes_num_pos: usize = buf.len();
+    buf.put_i32(0);
+
+    let mut element_buf = Vec::new();
+    for element in elements {
+        element_buf.clear();
+        element.serialize(&mut element_buf)?;
+        let element_bytes = &element_buf[4..];
+        if !has_fixed_size(element) {
+            types::unsigned_vint_encode(element_bytes.len() as u64, buf);
+        }
+        buf.extend_from_slice(element_bytes);
+    }
+
+    let written_bytes: usize = buf.len() - bytes_num_pos - 4;
+    let written_bytes_i32: i32 = written_bytes.try_into().map_err(|_| ValueTooBig)?;
+    buf[bytes_num_pos..(bytes_num_pos + 4)].copy_from_slice(&written_bytes_i32.to_be_bytes());
+
+    Ok(())
+}
+
 fn serialize_empty(buf: &mut Vec<u8>) -> Result<(), ValueTooBig> {
     buf.put_i32(0);
     Ok(())
@@ -1414,6 +1455,7 @@ impl Value for CqlValue {
 
             CqlValue::Ascii(s) | CqlValue::Text(s) => s.serialize(buf),
             CqlValue::List(v) | CqlValue::Set(v) => v.serialize(buf),
+            CqlValue::Vector(v) => serialize_vector(v, buf),
 
             CqlValue::Blob(b) => b.serialize(buf),
             CqlValue::Boolean(b) => b.serialize(buf),
@@ -1683,7 +1725,10 @@ where
     IT: Iterator<Item = &'a VL> + Clone,
     VL: ValueList + 'a,
 {
-    type LegacyBatchValuesIter<'r> = LegacyBatchValuesIteratorFromIterator<IT> where Self: 'r;
+    type LegacyBatchValuesIter<'r>
+        = LegacyBatchValuesIteratorFromIterator<IT>
+    where
+        Self: 'r;
     fn batch_values_iter(&self) -> Self::LegacyBatchValuesIter<'_> {
         self.it.clone().into()
     }
@@ -1691,7 +1736,10 @@ where
 
 // Implement BatchValues for slices of ValueList types
 impl<T: ValueList> LegacyBatchValues for [T] {
-    type LegacyBatchValuesIter<'r> = LegacyBatchValuesIteratorFromIterator<std::slice::Iter<'r, T>> where Self: 'r;
+    type LegacyBatchValuesIter<'r>
+        = LegacyBatchValuesIteratorFromIterator<std::slice::Iter<'r, T>>
+    where
+        Self: 'r;
     fn batch_values_iter(&self) -> Self::LegacyBatchValuesIter<'_> {
         self.iter().into()
     }
@@ -1699,7 +1747,10 @@ impl<T: ValueList> LegacyBatchValues for [T] {
 
 // Implement BatchValues for Vec<ValueList>
 impl<T: ValueList> LegacyBatchValues for Vec<T> {
-    type LegacyBatchValuesIter<'r> = LegacyBatchValuesIteratorFromIterator<std::slice::Iter<'r, T>> where Self: 'r;
+    type LegacyBatchValuesIter<'r>
+        = LegacyBatchValuesIteratorFromIterator<std::slice::Iter<'r, T>>
+    where
+        Self: 'r;
     fn batch_values_iter(&self) -> Self::LegacyBatchValuesIter<'_> {
         LegacyBatchValues::batch_values_iter(self.as_slice())
     }
@@ -1708,7 +1759,10 @@ impl<T: ValueList> LegacyBatchValues for Vec<T> {
 // Here is an example implementation for (T0, )
 // Further variants are done using a macro
 impl<T0: ValueList> LegacyBatchValues for (T0,) {
-    type LegacyBatchValuesIter<'r> = LegacyBatchValuesIteratorFromIterator<std::iter::Once<&'r T0>> where Self: 'r;
+    type LegacyBatchValuesIter<'r>
+        = LegacyBatchValuesIteratorFromIterator<std::iter::Once<&'r T0>>
+    where
+        Self: 'r;
     fn batch_values_iter(&self) -> Self::LegacyBatchValuesIter<'_> {
         std::iter::once(&self.0).into()
     }
@@ -1797,7 +1851,10 @@ impl_batch_values_for_tuple!(T0, T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T
 
 // Every &impl BatchValues should also implement BatchValues
 impl<'a, T: LegacyBatchValues + ?Sized> LegacyBatchValues for &'a T {
-    type LegacyBatchValuesIter<'r> = <T as LegacyBatchValues>::LegacyBatchValuesIter<'r> where Self: 'r;
+    type LegacyBatchValuesIter<'r>
+        = <T as LegacyBatchValues>::LegacyBatchValuesIter<'r>
+    where
+        Self: 'r;
     fn batch_values_iter(&self) -> Self::LegacyBatchValuesIter<'_> {
         <T as LegacyBatchValues>::batch_values_iter(*self)
     }
@@ -1827,8 +1884,10 @@ impl<'f, T: LegacyBatchValues> LegacyBatchValuesFirstSerialized<'f, T> {
 }
 
 impl<'f, BV: LegacyBatchValues> LegacyBatchValues for LegacyBatchValuesFirstSerialized<'f, BV> {
-    type LegacyBatchValuesIter<'r> =
-        LegacyBatchValuesFirstSerialized<'f, <BV as LegacyBatchValues>::LegacyBatchValuesIter<'r>> where Self: 'r;
+    type LegacyBatchValuesIter<'r>
+        = LegacyBatchValuesFirstSerialized<'f, <BV as LegacyBatchValues>::LegacyBatchValuesIter<'r>>
+    where
+        Self: 'r;
     fn batch_values_iter(&self) -> Self::LegacyBatchValuesIter<'_> {
         LegacyBatchValuesFirstSerialized {
             first: self.first,
@@ -35,6 +35,10 @@ pub mod _macro_internal {
     };
     pub use crate::macros::*;
 
+    pub use crate::types::compat::{
+        assert_deserialize_compatible, assert_serialize_compatible, cql, DeserializeCompatible,
+        SerializeCompatible,
+    };
     pub use crate::types::deserialize::row::{
         deser_error_replace_rust_name as row_deser_error_replace_rust_name,
         mk_deser_err as mk_row_deser_err, mk_typck_err as mk_row_typck_err,
@@ -57,8 +61,8 @@ pub mod _macro_internal {
         BuiltinSerializationError as BuiltinRowSerializationError,
         BuiltinSerializationErrorKind as BuiltinRowSerializationErrorKind,
         BuiltinTypeCheckError as BuiltinRowTypeCheckError,
-        BuiltinTypeCheckErrorKind as BuiltinRowTypeCheckErrorKind, RowSerializationContext,
-        SerializeRow,
+        BuiltinTypeCheckErrorKind as BuiltinRowTypeCheckErrorKind, FlattenedRow,
+        RowSerializationContext, SerializeRow,
     };
     pub use crate::types::serialize::value::{
         BuiltinSerializationError as BuiltinTypeSerializationError,
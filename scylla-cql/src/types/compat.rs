@@ -0,0 +1,428 @@
+//! Compile-time compatibility of Rust types with CQL types.
+//!
+//! The derive macros use it to check structs against the tables and UDTs
+//! described by a CQL schema file. The CQL types are represented by
+//! the marker types from the [`cql`] module. A Rust type implements
+//! [`SerializeCompatible<C>`] if its [`SerializeValue`](super::serialize::value::SerializeValue)
+//! implementation passes the type check for the CQL type represented by `C`,
+//! and likewise for [`DeserializeCompatible<C>`].
+//!
+//! The impls for the native CQL types live next to the respective
+//! (de)serialization impls. The impls for the types which are generic
+//! over their contents (options, collections, tuples, pointers)
+//! are defined here.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use crate::frame::response::result::CqlValue;
+use crate::frame::value::{MaybeUnset, Unset};
+use crate::types::deserialize::value::{
+    Emptiable, ListlikeIterator, MapIterator, MaybeEmpty, UdtIterator, VectorIterator,
+};
+
+/// Marker types which represent CQL types at the type level.
+///
+/// Their names are the same as the ones of the
+/// [`ColumnType`](crate::frame::response::result::ColumnType) variants.
+pub mod cql {
+    use std::marker::PhantomData;
+
+    macro_rules! native_types {
+        ($($name:ident),* $(,)?) => {
+            $(
+                #[doc = concat!("The `", stringify!($name), "` CQL type.")]
+                pub struct $name;
+            )*
+        };
+    }
+
+    native_types!(
+        Ascii,
+        Boolean,
+        Blob,
+        Counter,
+        Date,
+        Decimal,
+        Double,
+        Duration,
+        Float,
+        Int,
+        BigInt,
+        Text,
+        Timestamp,
+        Inet,
+        SmallInt,
+        TinyInt,
+        Time,
+        Timeuuid,
+        Uuid,
+        Varint,
+        UserDefinedType,
+    );
+
+    /// The `list<T>` CQL type.
+    pub struct List<T>(PhantomData<T>);
+
+    /// The `set<T>` CQL type.
+    pub struct Set<T>(PhantomData<T>);
+
+    /// The `map<K, V>` CQL type.
+    pub struct Map<K, V>(PhantomData<(K, V)>);
+
+    /// The `vector<T, N>` CQL type. The dimensions are not checked.
+    pub struct Vector<T>(PhantomData<T>);
+
+    /// The `tuple<...>` CQL type, with a Rust tuple of the element types.
+    pub struct Tuple<T>(PhantomData<T>);
+}
+
+/// Implemented by types whose values can be serialized
+/// as values of the CQL type represented by `C`.
+///
+/// If a field fails the check while it should pass, the `skip_schema_check`
+/// attribute of the derive macros opts the field out of it.
+pub trait SerializeCompatible<C> {}
+
+/// Implemented by types whose values can be deserialized
+/// from values of the CQL type represented by `C`.
+///
+/// If a field fails the check while it should pass, the `skip_schema_check`
+/// attribute of the derive macros opts the field out of it.
+pub trait DeserializeCompatible<C> {}
+
+/// Used by the generated code to check a field type against a column type.
+pub fn assert_serialize_compatible<T: SerializeCompatible<C> + ?Sized, C>() {}
+
+/// Used by the generated code to check a field type against a column type.
+pub fn assert_deserialize_compatible<T: DeserializeCompatible<C> + ?Sized, C>() {}
+
+// Implements both traits for types which are (de)serialized like their contents.
+macro_rules! impl_compatible_via {
+    ($([$($generics:tt)*] $t:ty => $inner:ty;)*) => {
+        $(
+            impl<$($generics)*, C> SerializeCompatible<C> for $t
+            where
+                $inner: SerializeCompatible<C>,
+            {
+            }
+
+            impl<$($generics)*, C> DeserializeCompatible<C> for $t
+            where
+                $inner: DeserializeCompatible<C>,
+            {
+            }
+        )*
+    };
+}
+
+impl_compatible_via!(
+    [T] Option<T> => T;
+);
+
+impl<T: SerializeCompatible<C> + ?Sized, C> SerializeCompatible<C> for &T {}
+impl<T: SerializeCompatible<C> + ?Sized, C> SerializeCompatible<C> for Box<T> {}
+impl<T: SerializeCompatible<C>, C> SerializeCompatible<C> for MaybeUnset<T> {}
+impl<C> SerializeCompatible<C> for Unset {}
+
+impl<T: DeserializeCompatible<C> + Emptiable, C> DeserializeCompatible<C> for MaybeEmpty<T> {}
+
+#[cfg(feature = "secrecy-08")]
+impl_compatible_via!(
+    [T: secrecy_08::Zeroize] secrecy_08::Secret<T> => T;
+);
+
+// `CqlValue` is checked against the type at runtime, so it is compatible with all of them.
+impl<C> SerializeCompatible<C> for CqlValue {}
+impl<C> DeserializeCompatible<C> for CqlValue {}
+
+impl DeserializeCompatible<cql::UserDefinedType> for UdtIterator<'_> {}
+
+// collections
+
+// Implements both traits for the given collection types.
+macro_rules! impl_compatible_collection {
+    ($([$($generics:tt)*] $t:ty => $cql:ty;)*) => {
+        $(
+            impl<$($generics)*> SerializeCompatible<$cql> for $t
+            where
+                T: SerializeCompatible<C>,
+            {
+            }
+
+            impl<$($generics)*> DeserializeCompatible<$cql> for $t
+            where
+                T: DeserializeCompatible<C>,
+            {
+            }
+        )*
+    };
+}
+
+impl_compatible_collection!(
+    [T, C] Vec<T> => cql::List<C>;
+    [T, C] Vec<T> => cql::Set<C>;
+    [T, C] Vec<T> => cql::Vector<C>;
+    [T, C] BTreeSet<T> => cql::List<C>;
+    [T, C] BTreeSet<T> => cql::Set<C>;
+    [T, C, S] HashSet<T, S> => cql::List<C>;
+    [T, C, S] HashSet<T, S> => cql::Set<C>;
+);
+
+impl<T: SerializeCompatible<C>, C> SerializeCompatible<cql::List<C>> for [T] {}
+impl<T: SerializeCompatible<C>, C> SerializeCompatible<cql::Set<C>> for [T] {}
+impl<T: SerializeCompatible<C>, C> SerializeCompatible<cql::Vector<C>> for [T] {}
+
+impl<T: DeserializeCompatible<C>, C> DeserializeCompatible<cql::List<C>>
+    for ListlikeIterator<'_, T>
+{
+}
+impl<T: DeserializeCompatible<C>, C> DeserializeCompatible<cql::Set<C>>
+    for ListlikeIterator<'_, T>
+{
+}
+impl<T: DeserializeCompatible<C>, C> DeserializeCompatible<cql::Vector<C>>
+    for VectorIterator<'_, T>
+{
+}
+
+impl<const N: usize> SerializeCompatible<cql::Vector<cql::Float>> for [f32; N] {}
+impl<const N: usize> DeserializeCompatible<cql::Vector<cql::Float>> for [f32; N] {}
+
+// Implements both traits for the given map types.
+macro_rules! impl_compatible_map {
+    ($([$($generics:tt)*] $t:ty;)*) => {
+        $(
+            impl<$($generics)*, CK, CV> SerializeCompatible<cql::Map<CK, CV>> for $t
+            where
+                K: SerializeCompatible<CK>,
+                V: SerializeCompatible<CV>,
+            {
+            }
+
+            impl<$($generics)*, CK, CV> DeserializeCompatible<cql::Map<CK, CV>> for $t
+            where
+                K: DeserializeCompatible<CK>,
+                V: DeserializeCompatible<CV>,
+            {
+            }
+        )*
+    };
+}
+
+impl_compatible_map!(
+    [K, V] BTreeMap<K, V>;
+    [K, V, S] HashMap<K, V, S>;
+);
+
+impl<K, V, CK, CV> DeserializeCompatible<cql::Map<CK, CV>> for MapIterator<'_, K, V>
+where
+    K: DeserializeCompatible<CK>,
+    V: DeserializeCompatible<CV>,
+{
+}
+
+// tuples
+
+macro_rules! impl_compatible_tuple {
+    ($($T:ident $C:ident),*) => {
+        impl<$($T, $C),*> SerializeCompatible<cql::Tuple<($($C,)*)>> for ($($T,)*)
+        where
+            $($T: SerializeCompatible<$C>),*
+        {
+        }
+
+        impl<$($T, $C),*> DeserializeCompatible<cql::Tuple<($($C,)*)>> for ($($T,)*)
+        where
+            $($T: DeserializeCompatible<$C>),*
+        {
+        }
+    };
+}
+
+// Implements the traits for all tuple sizes up to 16, accumulating
+// the already handled elements in the brackets.
+macro_rules! impl_compatible_tuples {
+    ([$($T:ident $C:ident),*]) => {
+        impl_compatible_tuple!($($T $C),*);
+    };
+    ([$($T:ident $C:ident),*] $T_next:ident $C_next:ident $(, $T_rest:ident $C_rest:ident)*) => {
+        impl_compatible_tuple!($($T $C),*);
+        impl_compatible_tuples!([$($T $C,)* $T_next $C_next] $($T_rest $C_rest),*);
+    };
+}
+
+impl_compatible_tuples!(
+    [] T0 C0, T1 C1, T2 C2, T3 C3, T4 C4, T5 C5, T6 C6, T7 C7, T8 C8, T9 C9, T10 C10, T11 C11,
+    T12 C12, T13 C13, T14 C14, T15 C15
+);
+
+/// ```compile_fail
+///
+/// #[derive(scylla_macros::SerializeRow)]
+/// #[scylla(crate = scylla_cql, schema_file = "src/types/test_schema.cql", table = "ks.users")]
+/// struct TestRow {
+///     id: uuid::Uuid,
+///     age: i32,
+/// }
+/// ```
+fn _test_schema_check_missing_column() {}
+
+/// ```compile_fail
+///
+/// #[derive(scylla_macros::SerializeRow)]
+/// #[scylla(crate = scylla_cql, schema_file = "src/types/test_schema.cql", table = "ks.users")]
+/// struct TestRow {
+///     id: i64,
+/// }
+/// ```
+fn _test_schema_check_incompatible_serialized_type() {}
+
+/// ```compile_fail
+///
+/// #[derive(scylla_macros::DeserializeRow)]
+/// #[scylla(crate = scylla_cql, schema_file = "src/types/test_schema.cql", table = "ks.users")]
+/// struct TestRow {
+///     tags: Vec<i32>,
+/// }
+/// ```
+fn _test_schema_check_incompatible_deserialized_type() {}
+
+/// ```compile_fail
+///
+/// #[derive(scylla_macros::DeserializeValue)]
+/// #[scylla(crate = scylla_cql, schema_file = "src/types/test_schema.cql", udt = "address")]
+/// struct TestUdt {
+///     street: String,
+///     city: String,
+/// }
+/// ```
+fn _test_schema_check_missing_udt_field() {}
+
+/// ```compile_fail
+///
+/// #[derive(scylla_macros::SerializeRow)]
+/// #[scylla(crate = scylla_cql, schema_file = "src/types/test_schema.cql", table = "dropped")]
+/// struct TestRow {
+///     id: i32,
+/// }
+/// ```
+fn _test_schema_check_unknown_table() {}
+
+/// ```compile_fail
+///
+/// #[derive(scylla_macros::SerializeRow)]
+/// #[scylla(crate = scylla_cql, schema_file = "src/types/test_schema.cql", table = "users")]
+/// struct TestRow {
+///     id: uuid::Uuid,
+/// }
+/// ```
+fn _test_schema_check_ambiguous_table() {}
+
+/// ```compile_fail
+///
+/// #[derive(scylla_macros::DeserializeRow)]
+/// #[scylla(crate = scylla_cql, schema_file = "src/types/test_schema.cql")]
+/// struct TestRow {
+///     id: uuid::Uuid,
+/// }
+/// ```
+fn _test_schema_check_requires_table() {}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashMap, HashSet};
+
+    use uuid::Uuid;
+
+    use super::{assert_deserialize_compatible, assert_serialize_compatible, cql};
+    use crate::frame::response::result::CqlValue;
+    use crate::frame::value::{Counter, CqlTimestamp, MaybeUnset};
+    use crate::macros::{DeserializeRow, DeserializeValue, SerializeRow, SerializeValue};
+
+    #[derive(SerializeValue, DeserializeValue)]
+    #[scylla(crate = crate, schema_file = "src/types/test_schema.cql", udt = "ks.address")]
+    struct Address {
+        street: String,
+        #[scylla(rename = "Number")]
+        number: Option<i32>,
+        zip_code: String,
+    }
+
+    #[derive(SerializeValue, DeserializeValue)]
+    #[scylla(crate = crate)]
+    enum Bucket {
+        First = 1,
+        Second = 2,
+    }
+
+    #[derive(SerializeValue, DeserializeValue)]
+    #[scylla(crate = crate, transparent)]
+    struct Nickname(String);
+
+    #[derive(SerializeRow, DeserializeRow)]
+    #[scylla(
+        crate = crate,
+        schema_file = "src/types/test_schema.cql",
+        keyspace = "ks",
+        table = "users"
+    )]
+    #[allow(dead_code)]
+    struct User<'a> {
+        id: Uuid,
+        bucket: Bucket,
+        login: &'a str,
+        #[scylla(rename = "Email")]
+        email: String,
+        tags: HashSet<String>,
+        scores: BTreeMap<String, Vec<i32>>,
+        home: Address,
+        location: (f64, f64),
+        embedding: Vec<f32>,
+        payload: CqlValue,
+        nickname: Option<Nickname>,
+        last_seen: CqlTimestamp,
+        #[scylla(skip_schema_check)]
+        unchecked: Option<i32>,
+    }
+
+    #[derive(DeserializeRow)]
+    #[scylla(crate = crate)]
+    #[allow(dead_code)]
+    struct UserActivity {
+        last_seen: Option<CqlTimestamp>,
+    }
+
+    #[derive(DeserializeRow)]
+    #[scylla(crate = crate, schema_file = "src/types/test_schema.cql", table = "ks.users")]
+    #[allow(dead_code)]
+    struct UserSummary {
+        id: Uuid,
+        #[scylla(default_when_missing)]
+        age: Option<i32>,
+        #[scylla(flatten)]
+        activity: UserActivity,
+    }
+
+    #[test]
+    fn test_compatible_types() {
+        assert_serialize_compatible::<&str, cql::Ascii>();
+        assert_serialize_compatible::<&[u8], cql::Blob>();
+        assert_serialize_compatible::<[u8; 4], cql::Blob>();
+        assert_serialize_compatible::<&[i8], cql::List<cql::TinyInt>>();
+        assert_serialize_compatible::<MaybeUnset<Box<i64>>, cql::BigInt>();
+        assert_serialize_compatible::<[f32; 3], cql::Vector<cql::Float>>();
+        assert_serialize_compatible::<Counter, cql::Counter>();
+        assert_serialize_compatible::<Bucket, cql::SmallInt>();
+        assert_serialize_compatible::<Nickname, cql::Ascii>();
+
+        assert_deserialize_compatible::<&[u8], cql::Blob>();
+        assert_deserialize_compatible::<i64, cql::Counter>();
+        assert_deserialize_compatible::<Vec<Vec<u8>>, cql::Set<cql::Blob>>();
+        assert_deserialize_compatible::<
+            HashMap<String, (i32, Option<bool>)>,
+            cql::Map<cql::Text, cql::Tuple<(cql::Int, cql::Boolean)>>,
+        >();
+        assert_deserialize_compatible::<CqlValue, cql::UserDefinedType>();
+        assert_deserialize_compatible::<Address, cql::UserDefinedType>();
+    }
+}
@@ -155,6 +155,31 @@ impl<'frame> FrameSlice<'frame> {
             original_frame: self.original_frame,
         }))
     }
+
+    /// Reads an element of a CQL vector. Elements are prefixed with
+    /// their length (as an unsigned vint) only if their type doesn't
+    /// have a fixed size.
+    #[inline]
+    pub(super) fn read_vector_element(
+        &mut self,
+        fixed_size: Option<usize>,
+    ) -> Result<FrameSlice<'frame>, LowLevelDeserializationError> {
+        // We copy the slice reference, not to mutate the FrameSlice in case of an error.
+        let mut slice = self.frame_subslice;
+
+        let len = match fixed_size {
+            Some(size) => size,
+            None => types::unsigned_vint_decode(&mut slice)?.try_into()?,
+        };
+        let element = types::read_raw_bytes(len, &mut slice)?;
+
+        self.frame_subslice = slice;
+
+        Ok(Self {
+            frame_subslice: element,
+            original_frame: self.original_frame,
+        })
+    }
 }
 
 #[cfg(test)]
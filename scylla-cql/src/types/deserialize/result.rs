@@ -38,6 +38,12 @@ impl<'frame> RowIterator<'frame> {
     pub fn rows_remaining(&self) -> usize {
         self.remaining
     }
+
+    /// Returns the part of the serialized response which holds the remaining rows.
+    #[inline]
+    pub fn remaining_slice(&self) -> FrameSlice<'frame> {
+        self.slice
+    }
 }
 
 impl<'frame> Iterator for RowIterator<'frame> {
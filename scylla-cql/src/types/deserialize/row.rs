@@ -9,6 +9,7 @@ use super::{make_error_replace_rust_name, DeserializationError, FrameSlice, Type
 use crate::frame::response::result::{ColumnSpec, ColumnType, CqlValue, Row};
 
 /// Represents a raw, unparsed column value.
+#[derive(Clone, Debug)]
 #[non_exhaustive]
 pub struct RawColumn<'frame> {
     pub index: usize,
@@ -19,8 +20,19 @@ pub struct RawColumn<'frame> {
 /// Iterates over columns of a single row.
 #[derive(Clone, Debug)]
 pub struct ColumnIterator<'frame> {
-    specs: std::iter::Enumerate<std::slice::Iter<'frame, ColumnSpec>>,
-    slice: FrameSlice<'frame>,
+    inner: ColumnIteratorInner<'frame>,
+}
+
+#[derive(Clone, Debug)]
+enum ColumnIteratorInner<'frame> {
+    /// Columns read one by one from the serialized row.
+    Serialized {
+        specs: std::iter::Enumerate<std::slice::Iter<'frame, ColumnSpec>>,
+        slice: FrameSlice<'frame>,
+    },
+
+    /// Columns which were already read from the serialized row.
+    Raw(std::vec::IntoIter<RawColumn<'frame>>),
 }
 
 impl<'frame> ColumnIterator<'frame> {
@@ -31,8 +43,22 @@ impl<'frame> ColumnIterator<'frame> {
     #[inline]
     pub(crate) fn new(specs: &'frame [ColumnSpec], slice: FrameSlice<'frame>) -> Self {
         Self {
-            specs: specs.iter().enumerate(),
-            slice,
+            inner: ColumnIteratorInner::Serialized {
+                specs: specs.iter().enumerate(),
+                slice,
+            },
+        }
+    }
+
+    /// Creates an iterator over columns which were already read from a row.
+    ///
+    /// This is useful for passing a subset of the row's columns to another
+    /// [DeserializeRow] implementation, e.g. for a struct flattened into
+    /// another one.
+    #[inline]
+    pub fn from_raw_columns(columns: Vec<RawColumn<'frame>>) -> Self {
+        Self {
+            inner: ColumnIteratorInner::Raw(columns.into_iter()),
         }
     }
 
@@ -40,7 +66,10 @@ impl<'frame> ColumnIterator<'frame> {
     /// to return.
     #[inline]
     pub fn columns_remaining(&self) -> usize {
-        self.specs.len()
+        match &self.inner {
+            ColumnIteratorInner::Serialized { specs, .. } => specs.len(),
+            ColumnIteratorInner::Raw(columns) => columns.len(),
+        }
     }
 }
 
@@ -49,9 +78,13 @@ impl<'frame> Iterator for ColumnIterator<'frame> {
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        let (column_index, spec) = self.specs.next()?;
+        let (specs, slice) = match &mut self.inner {
+            ColumnIteratorInner::Serialized { specs, slice } => (specs, slice),
+            ColumnIteratorInner::Raw(columns) => return columns.next().map(Ok),
+        };
+        let (column_index, spec) = specs.next()?;
         Some(
-            self.slice
+            slice
                 .read_cql_bytes()
                 .map(|slice| RawColumn {
                     index: column_index,
@@ -72,7 +105,8 @@ impl<'frame> Iterator for ColumnIterator<'frame> {
 
     #[inline]
     fn size_hint(&self) -> (usize, Option<usize>) {
-        self.specs.size_hint()
+        let remaining = self.columns_remaining();
+        (remaining, Some(remaining))
     }
 }
 
@@ -534,3 +568,40 @@ fn _test_struct_deserialization_skip_rename_collision_with_field() {}
 /// }
 /// ```
 fn _test_struct_deserialization_rename_collision_with_another_rename() {}
+
+/// ```compile_fail
+///
+/// #[derive(scylla_macros::DeserializeRow)]
+/// #[scylla(crate = scylla_cql, enforce_order)]
+/// struct TestRow {
+///     #[scylla(default_when_missing)]
+///     a: i32,
+///     b: String,
+/// }
+/// ```
+fn _test_struct_deserialization_enforce_order_requires_optional_suffix() {}
+
+/// ```compile_fail
+///
+/// #[derive(scylla_macros::DeserializeRow)]
+/// #[scylla(crate = scylla_cql, enforce_order)]
+/// struct TestRow {
+///     a: i32,
+///     #[scylla(flatten)]
+///     b: (String,),
+/// }
+/// ```
+fn _test_struct_deserialization_flatten_conflicts_with_enforce_order() {}
+
+/// ```compile_fail
+///
+/// #[derive(scylla_macros::DeserializeRow)]
+/// #[scylla(crate = scylla_cql)]
+/// struct TestRow {
+///     #[scylla(flatten)]
+///     a: (i32,),
+///     #[scylla(flatten)]
+///     b: (String,),
+/// }
+/// ```
+fn _test_struct_deserialization_multiple_flattened_fields() {}
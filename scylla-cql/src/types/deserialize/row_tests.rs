@@ -243,6 +243,201 @@ fn test_struct_deserialization_cross_rename_fields() {
     }
 }
 
+#[test]
+fn test_struct_deserialization_default_when_missing() {
+    fn default_b() -> i32 {
+        42
+    }
+
+    #[derive(DeserializeRow, PartialEq, Eq, Debug)]
+    #[scylla(crate = "crate")]
+    struct MyRow<'a> {
+        a: &'a str,
+        #[scylla(default = "default_b")]
+        b: i32,
+        #[scylla(default_when_missing)]
+        c: Option<String>,
+    }
+
+    // All columns present
+    let specs = &[
+        spec("c", ColumnType::Text),
+        spec("a", ColumnType::Text),
+        spec("b", ColumnType::Int),
+    ];
+    let byts = serialize_cells([val_str("def"), val_str("abc"), val_int(123)]);
+    let row = deserialize::<MyRow<'_>>(specs, &byts).unwrap();
+    assert_eq!(
+        row,
+        MyRow {
+            a: "abc",
+            b: 123,
+            c: Some("def".to_owned()),
+        }
+    );
+
+    // Optional columns missing
+    let specs = &[spec("a", ColumnType::Text)];
+    let byts = serialize_cells([val_str("abc")]);
+    let row = deserialize::<MyRow<'_>>(specs, &byts).unwrap();
+    assert_eq!(
+        row,
+        MyRow {
+            a: "abc",
+            b: 42,
+            c: None,
+        }
+    );
+
+    // Required column missing
+    let specs = &[spec("b", ColumnType::Int)];
+    MyRow::type_check(specs).unwrap_err();
+}
+
+#[test]
+fn test_struct_deserialization_allow_missing() {
+    #[derive(DeserializeRow, PartialEq, Eq, Debug)]
+    #[scylla(crate = "crate", allow_missing)]
+    struct MyRow {
+        a: i32,
+        b: Option<i32>,
+    }
+
+    let specs = &[spec("b", ColumnType::Int)];
+    let byts = serialize_cells([val_int(123)]);
+    let row = deserialize::<MyRow>(specs, &byts).unwrap();
+    assert_eq!(row, MyRow { a: 0, b: Some(123) });
+
+    let specs = &[];
+    let byts = Bytes::new();
+    let row = deserialize::<MyRow>(specs, &byts).unwrap();
+    assert_eq!(row, MyRow { a: 0, b: None });
+
+    // Unknown columns are still rejected
+    let specs = &[spec("c", ColumnType::Int)];
+    MyRow::type_check(specs).unwrap_err();
+}
+
+#[test]
+fn test_struct_deserialization_strict_ordering_default_when_missing() {
+    #[derive(DeserializeRow, PartialEq, Eq, Debug)]
+    #[scylla(crate = "crate", enforce_order)]
+    struct MyRow<'a> {
+        a: &'a str,
+        #[scylla(skip)]
+        b: String,
+        #[scylla(default_when_missing)]
+        c: i32,
+    }
+
+    // All columns present
+    let specs = &[spec("a", ColumnType::Text), spec("c", ColumnType::Int)];
+    let byts = serialize_cells([val_str("abc"), val_int(123)]);
+    let row = deserialize::<MyRow<'_>>(specs, &byts).unwrap();
+    assert_eq!(
+        row,
+        MyRow {
+            a: "abc",
+            b: String::new(),
+            c: 123,
+        }
+    );
+
+    // Trailing optional column missing
+    let specs = &[spec("a", ColumnType::Text)];
+    let byts = serialize_cells([val_str("abc")]);
+    let row = deserialize::<MyRow<'_>>(specs, &byts).unwrap();
+    assert_eq!(
+        row,
+        MyRow {
+            a: "abc",
+            b: String::new(),
+            c: 0,
+        }
+    );
+
+    // Required column missing
+    let specs = &[];
+    MyRow::type_check(specs).unwrap_err();
+
+    // Too many columns
+    let specs = &[
+        spec("a", ColumnType::Text),
+        spec("c", ColumnType::Int),
+        spec("d", ColumnType::Int),
+    ];
+    MyRow::type_check(specs).unwrap_err();
+}
+
+#[test]
+fn test_struct_deserialization_flatten() {
+    #[derive(DeserializeRow, PartialEq, Eq, Debug)]
+    #[scylla(crate = "crate")]
+    struct Inner<'a> {
+        b: &'a str,
+        c: Option<i32>,
+    }
+
+    #[derive(DeserializeRow, PartialEq, Eq, Debug)]
+    #[scylla(crate = "crate")]
+    struct Outer<'a> {
+        a: i32,
+        #[scylla(flatten)]
+        inner: Inner<'a>,
+    }
+
+    // Columns of the outer and inner struct interleaved
+    let specs = &[
+        spec("c", ColumnType::Int),
+        spec("a", ColumnType::Int),
+        spec("b", ColumnType::Text),
+    ];
+    let byts = serialize_cells([val_int(1), val_int(2), val_str("abc")]);
+    let row = deserialize::<Outer<'_>>(specs, &byts).unwrap();
+    assert_eq!(
+        row,
+        Outer {
+            a: 2,
+            inner: Inner {
+                b: "abc",
+                c: Some(1),
+            },
+        }
+    );
+
+    // Column of the inner struct missing
+    let specs = &[spec("a", ColumnType::Int), spec("c", ColumnType::Int)];
+    let err = Outer::type_check(specs).unwrap_err();
+    let err = get_typck_err_inner(err.0.as_ref());
+    assert_eq!(err.rust_name, std::any::type_name::<Inner<'_>>());
+    assert_matches!(
+        &err.kind,
+        BuiltinTypeCheckErrorKind::ValuesMissingForColumns { column_names }
+            if column_names == &["b"]
+    );
+
+    // Unknown column is reported by the inner struct
+    let specs = &[
+        spec("a", ColumnType::Int),
+        spec("b", ColumnType::Text),
+        spec("c", ColumnType::Int),
+        spec("d", ColumnType::Int),
+    ];
+    let err = Outer::type_check(specs).unwrap_err();
+    let err = get_typck_err_inner(err.0.as_ref());
+    assert_matches!(
+        &err.kind,
+        BuiltinTypeCheckErrorKind::ColumnWithUnknownName { column_name, .. }
+            if column_name == "d"
+    );
+
+    // Column of the outer struct missing
+    let specs = &[spec("b", ColumnType::Text), spec("c", ColumnType::Int)];
+    let err = Outer::type_check(specs).unwrap_err();
+    let err = get_typck_err_inner(err.0.as_ref());
+    assert_eq!(err.rust_name, std::any::type_name::<Outer<'_>>());
+}
+
 fn val_int(i: i32) -> Option<Vec<u8>> {
     Some(i.to_be_bytes().to_vec())
 }
@@ -20,6 +20,7 @@ use crate::frame::types;
 use crate::frame::value::{
     Counter, CqlDate, CqlDecimal, CqlDuration, CqlTime, CqlTimestamp, CqlTimeuuid, CqlVarint,
 };
+use crate::types::compat::{cql, DeserializeCompatible};
 
 /// A type that can be deserialized from a column value inside a row that was
 /// returned from a query.
@@ -149,6 +150,8 @@ macro_rules! impl_strict_type {
                 $conv(typ, v)
             }
         }
+
+        impl_deserialize_compatible!($t, [$($cql)|+] $(, $l)?);
     };
 
     // Convenience pattern for omitting brackets if type-checking as single types.
@@ -157,6 +160,17 @@ macro_rules! impl_strict_type {
     };
 }
 
+// Makes the CQL types accepted by the type check of a strict type
+// known at compile time, for the schema checks of the derive macros.
+macro_rules! impl_deserialize_compatible {
+    ($t:ty, [$($cql:ident)|+]) => {
+        $(impl DeserializeCompatible<cql::$cql> for $t {})+
+    };
+    ($t:ty, [$($cql:ident)|+], $l:lifetime) => {
+        $(impl<$l> DeserializeCompatible<cql::$cql> for $t {})+
+    };
+}
+
 macro_rules! impl_emptiable_strict_type {
     ($t:ty, [$($cql:ident)|+], $conv:expr $(, $l:lifetime)?) => {
         impl<$($l,)?> Emptiable for $t {}
@@ -752,18 +766,25 @@ where
     T: DeserializeValue<'frame>,
 {
     fn type_check(typ: &ColumnType) -> Result<(), TypeCheckError> {
-        // It makes sense for both Set and List to deserialize to Vec.
-        ListlikeIterator::<'frame, T>::type_check(typ)
-            .map_err(typck_error_replace_rust_name::<Self>)
+        match typ {
+            ColumnType::Vector { .. } => VectorIterator::<'frame, T>::type_check(typ),
+            // It makes sense for both Set and List to deserialize to Vec.
+            _ => ListlikeIterator::<'frame, T>::type_check(typ),
+        }
+        .map_err(typck_error_replace_rust_name::<Self>)
     }
 
     fn deserialize(
         typ: &'frame ColumnType,
         v: Option<FrameSlice<'frame>>,
     ) -> Result<Self, DeserializationError> {
-        ListlikeIterator::<'frame, T>::deserialize(typ, v)
-            .and_then(|it| it.collect::<Result<_, DeserializationError>>())
-            .map_err(deser_error_replace_rust_name::<Self>)
+        match typ {
+            ColumnType::Vector { .. } => VectorIterator::<'frame, T>::deserialize(typ, v)
+                .and_then(|it| it.collect::<Result<_, DeserializationError>>()),
+            _ => ListlikeIterator::<'frame, T>::deserialize(typ, v)
+                .and_then(|it| it.collect::<Result<_, DeserializationError>>()),
+        }
+        .map_err(deser_error_replace_rust_name::<Self>)
     }
 }
 
@@ -822,6 +843,126 @@ where
     }
 }
 
+// vectors
+
+/// An iterator over a CQL vector.
+pub struct VectorIterator<'frame, T> {
+    vector_typ: &'frame ColumnType,
+    elem_typ: &'frame ColumnType,
+    elem_size: Option<usize>,
+    remaining: usize,
+    slice: FrameSlice<'frame>,
+    phantom_data: std::marker::PhantomData<T>,
+}
+
+impl<'frame, T> DeserializeValue<'frame> for VectorIterator<'frame, T>
+where
+    T: DeserializeValue<'frame>,
+{
+    fn type_check(typ: &ColumnType) -> Result<(), TypeCheckError> {
+        match typ {
+            ColumnType::Vector { typ: el_t, .. } => {
+                <T as DeserializeValue<'frame>>::type_check(el_t).map_err(|err| {
+                    mk_typck_err::<Self>(typ, VectorTypeCheckErrorKind::ElementTypeCheckFailed(err))
+                })
+            }
+            _ => Err(mk_typck_err::<Self>(
+                typ,
+                VectorTypeCheckErrorKind::NotVector,
+            )),
+        }
+    }
+
+    fn deserialize(
+        typ: &'frame ColumnType,
+        v: Option<FrameSlice<'frame>>,
+    ) -> Result<Self, DeserializationError> {
+        let slice = ensure_not_null_frame_slice::<Self>(typ, v)?;
+        let (elem_typ, dimensions) = match typ {
+            ColumnType::Vector {
+                typ: elem_typ,
+                dimensions,
+            } => (elem_typ, *dimensions),
+            _ => {
+                unreachable!("Typecheck should have prevented this scenario!")
+            }
+        };
+        Ok(Self {
+            vector_typ: typ,
+            elem_typ,
+            elem_size: elem_typ.fixed_serialized_size(),
+            remaining: dimensions.into(),
+            slice,
+            phantom_data: std::marker::PhantomData,
+        })
+    }
+}
+
+impl<'frame, T> Iterator for VectorIterator<'frame, T>
+where
+    T: DeserializeValue<'frame>,
+{
+    type Item = Result<T, DeserializationError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.remaining = self.remaining.checked_sub(1)?;
+        let raw = self
+            .slice
+            .read_vector_element(self.elem_size)
+            .map_err(|err| {
+                mk_deser_err::<Self>(
+                    self.vector_typ,
+                    BuiltinDeserializationErrorKind::RawCqlBytesReadError(err),
+                )
+            });
+        Some(raw.and_then(|raw| {
+            T::deserialize(self.elem_typ, Some(raw)).map_err(|err| {
+                mk_deser_err::<Self>(
+                    self.vector_typ,
+                    VectorDeserializationErrorKind::ElementDeserializationFailed(err),
+                )
+            })
+        }))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'frame, const N: usize> DeserializeValue<'frame> for [f32; N] {
+    fn type_check(typ: &ColumnType) -> Result<(), TypeCheckError> {
+        match typ {
+            ColumnType::Vector { dimensions, .. } if usize::from(*dimensions) != N => {
+                Err(mk_typck_err::<Self>(
+                    typ,
+                    VectorTypeCheckErrorKind::WrongDimensions {
+                        rust_type_dimensions: N,
+                        cql_type_dimensions: *dimensions,
+                    },
+                ))
+            }
+            _ => VectorIterator::<'frame, f32>::type_check(typ)
+                .map_err(typck_error_replace_rust_name::<Self>),
+        }
+    }
+
+    fn deserialize(
+        typ: &'frame ColumnType,
+        v: Option<FrameSlice<'frame>>,
+    ) -> Result<Self, DeserializationError> {
+        let iter = VectorIterator::<'frame, f32>::deserialize(typ, v)
+            .map_err(deser_error_replace_rust_name::<Self>)?;
+        // Type check guarantees that the vector has exactly N elements.
+        let mut array = [0.0; N];
+        for (slot, elem) in array.iter_mut().zip(iter) {
+            *slot = elem.map_err(deser_error_replace_rust_name::<Self>)?;
+        }
+        Ok(array)
+    }
+}
+
 /// An iterator over a CQL map.
 pub struct MapIterator<'frame, K, V> {
     coll_typ: &'frame ColumnType,
@@ -1392,6 +1533,9 @@ pub enum BuiltinTypeCheckErrorKind {
 
     /// A type check failure specific to a CQL UDT.
     UdtError(UdtTypeCheckErrorKind),
+
+    /// A type check failure specific to a CQL vector.
+    VectorError(VectorTypeCheckErrorKind),
 }
 
 impl From<SetOrListTypeCheckErrorKind> for BuiltinTypeCheckErrorKind {
@@ -1422,6 +1566,13 @@ impl From<UdtTypeCheckErrorKind> for BuiltinTypeCheckErrorKind {
     }
 }
 
+impl From<VectorTypeCheckErrorKind> for BuiltinTypeCheckErrorKind {
+    #[inline]
+    fn from(value: VectorTypeCheckErrorKind) -> Self {
+        BuiltinTypeCheckErrorKind::VectorError(value)
+    }
+}
+
 impl Display for BuiltinTypeCheckErrorKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -1432,6 +1583,7 @@ impl Display for BuiltinTypeCheckErrorKind {
             BuiltinTypeCheckErrorKind::MapError(err) => err.fmt(f),
             BuiltinTypeCheckErrorKind::TupleError(err) => err.fmt(f),
             BuiltinTypeCheckErrorKind::UdtError(err) => err.fmt(f),
+            BuiltinTypeCheckErrorKind::VectorError(err) => err.fmt(f),
         }
     }
 }
@@ -1464,6 +1616,44 @@ impl Display for SetOrListTypeCheckErrorKind {
     }
 }
 
+/// Describes why type checking of a vector type failed.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum VectorTypeCheckErrorKind {
+    /// The CQL type is not a vector.
+    NotVector,
+    /// The Rust type has a fixed number of elements, different than the vector's dimensions.
+    WrongDimensions {
+        /// The number of elements of the Rust type.
+        rust_type_dimensions: usize,
+        /// The dimensions of the CQL vector.
+        cql_type_dimensions: u16,
+    },
+    /// Incompatible element types.
+    ElementTypeCheckFailed(TypeCheckError),
+}
+
+impl Display for VectorTypeCheckErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VectorTypeCheckErrorKind::NotVector => {
+                f.write_str("the CQL type the Rust type was attempted to be type checked against was not a vector")
+            }
+            VectorTypeCheckErrorKind::WrongDimensions {
+                rust_type_dimensions,
+                cql_type_dimensions,
+            } => write!(
+                f,
+                "the Rust type has {} elements, but the CQL vector has {} dimensions",
+                rust_type_dimensions, cql_type_dimensions
+            ),
+            VectorTypeCheckErrorKind::ElementTypeCheckFailed(err) => {
+                write!(f, "the vector element types between the CQL type and the Rust type failed to type check against each other: {}", err)
+            }
+        }
+    }
+}
+
 /// Describes why type checking of a map type failed.
 #[derive(Debug, Clone)]
 #[non_exhaustive]
@@ -1710,6 +1900,15 @@ pub enum BuiltinDeserializationErrorKind {
     /// The length of read value in bytes is not suitable for IP address.
     BadInetLength { got: usize },
 
+    /// The read text does not name any variant of the Rust enum.
+    UnknownEnumVariantName(String),
+
+    /// The read integer is not a discriminant of any variant of the Rust enum.
+    UnknownEnumDiscriminant(i64),
+
+    /// The CQL type is neither a text nor an integer type, so it can't hold a Rust enum.
+    MismatchedEnumType,
+
     /// A deserialization failure specific to a CQL set or list.
     SetOrListError(SetOrListDeserializationErrorKind),
 
@@ -1721,6 +1920,9 @@ pub enum BuiltinDeserializationErrorKind {
 
     /// A deserialization failure specific to a CQL UDT.
     UdtError(UdtDeserializationErrorKind),
+
+    /// A deserialization failure specific to a CQL vector.
+    VectorError(VectorDeserializationErrorKind),
 }
 
 impl Display for BuiltinDeserializationErrorKind {
@@ -1750,10 +1952,20 @@ impl Display for BuiltinDeserializationErrorKind {
                 f,
                 "the length of read value in bytes ({got}) is not suitable for IP address; expected 4 or 16"
             ),
+            BuiltinDeserializationErrorKind::UnknownEnumVariantName(name) => {
+                write!(f, "no enum variant corresponds to the name '{}'", name)
+            }
+            BuiltinDeserializationErrorKind::UnknownEnumDiscriminant(discriminant) => {
+                write!(f, "no enum variant corresponds to the discriminant {}", discriminant)
+            }
+            BuiltinDeserializationErrorKind::MismatchedEnumType => {
+                f.write_str("the CQL type can't hold an enum variant")
+            }
             BuiltinDeserializationErrorKind::SetOrListError(err) => err.fmt(f),
             BuiltinDeserializationErrorKind::MapError(err) => err.fmt(f),
             BuiltinDeserializationErrorKind::TupleError(err) => err.fmt(f),
             BuiltinDeserializationErrorKind::UdtError(err) => err.fmt(f),
+            BuiltinDeserializationErrorKind::VectorError(err) => err.fmt(f),
             BuiltinDeserializationErrorKind::CustomTypeNotSupported(typ) => write!(f, "Support for custom types is not yet implemented: {}", typ),
         }
     }
@@ -1790,6 +2002,31 @@ impl From<SetOrListDeserializationErrorKind> for BuiltinDeserializationErrorKind
     }
 }
 
+/// Describes why deserialization of a vector type failed.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum VectorDeserializationErrorKind {
+    /// One of the elements of the vector failed to deserialize.
+    ElementDeserializationFailed(DeserializationError),
+}
+
+impl Display for VectorDeserializationErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VectorDeserializationErrorKind::ElementDeserializationFailed(err) => {
+                write!(f, "failed to deserialize one of the elements: {}", err)
+            }
+        }
+    }
+}
+
+impl From<VectorDeserializationErrorKind> for BuiltinDeserializationErrorKind {
+    #[inline]
+    fn from(err: VectorDeserializationErrorKind) -> Self {
+        Self::VectorError(err)
+    }
+}
+
 /// Describes why deserialization of a map type failed.
 #[derive(Debug)]
 #[non_exhaustive]
@@ -1950,6 +2187,36 @@ fn _test_udt_bad_attributes_rename_collision_with_another_rename() {}
 /// ```
 fn _test_udt_bad_attributes_name_skip_name_checks_limitations_on_allow_missing() {}
 
+/// ```compile_fail
+///
+/// #[derive(scylla_macros::DeserializeValue)]
+/// #[scylla(crate = scylla_cql)]
+/// enum TestEnum {
+///     A = 1,
+///     B,
+/// }
+/// ```
+fn _test_enum_bad_attributes_partial_discriminants() {}
+
+/// ```compile_fail
+///
+/// #[derive(scylla_macros::DeserializeValue)]
+/// #[scylla(crate = scylla_cql)]
+/// enum TestEnum {
+///     A,
+///     B(i32),
+/// }
+/// ```
+fn _test_enum_variant_with_fields() {}
+
+/// ```compile_fail
+///
+/// #[derive(scylla_macros::DeserializeValue)]
+/// #[scylla(crate = scylla_cql, transparent)]
+/// struct TestNewtype(i32, i32);
+/// ```
+fn _test_transparent_requires_single_field() {}
+
 /// ```
 /// #[derive(scylla_macros::DeserializeValue)]
 /// #[scylla(crate = scylla_cql)]
@@ -20,7 +20,8 @@ use super::{
     BuiltinTypeCheckError, BuiltinTypeCheckErrorKind, DeserializeValue, ListlikeIterator,
     MapDeserializationErrorKind, MapIterator, MapTypeCheckErrorKind, MaybeEmpty,
     SetOrListDeserializationErrorKind, SetOrListTypeCheckErrorKind, UdtDeserializationErrorKind,
-    UdtTypeCheckErrorKind,
+    UdtTypeCheckErrorKind, VectorDeserializationErrorKind, VectorIterator,
+    VectorTypeCheckErrorKind,
 };
 
 #[test]
@@ -499,6 +500,92 @@ fn test_map() {
     );
 }
 
+#[test]
+fn test_vector() {
+    // Elements of fixed size are not prefixed with their length.
+    let mut vector_contents = BytesMut::new();
+    for f in [1.0f32, -2.5, 3.25] {
+        vector_contents.put_f32(f);
+    }
+    let vector = make_bytes(&vector_contents);
+    let typ = ColumnType::Vector {
+        typ: Box::new(ColumnType::Float),
+        dimensions: 3,
+    };
+
+    let mut iter = deserialize::<VectorIterator<f32>>(&typ, &vector).unwrap();
+    assert_eq!(iter.size_hint(), (3, Some(3)));
+    assert_eq!(iter.next().transpose().unwrap(), Some(1.0));
+    assert_eq!(iter.next().transpose().unwrap(), Some(-2.5));
+    assert_eq!(iter.next().transpose().unwrap(), Some(3.25));
+    assert_eq!(iter.next().transpose().unwrap(), None);
+
+    let decoded_vec = deserialize::<Vec<f32>>(&typ, &vector).unwrap();
+    assert_eq!(decoded_vec, vec![1.0, -2.5, 3.25]);
+    let decoded_array = deserialize::<[f32; 3]>(&typ, &vector).unwrap();
+    assert_eq!(decoded_array, [1.0, -2.5, 3.25]);
+    let decoded_cql_value = deserialize::<CqlValue>(&typ, &vector).unwrap();
+    assert_eq!(
+        decoded_cql_value,
+        CqlValue::Vector(vec![
+            CqlValue::Float(1.0),
+            CqlValue::Float(-2.5),
+            CqlValue::Float(3.25)
+        ])
+    );
+
+    // Elements of variable size are prefixed with their length as unsigned vint.
+    let mut vector_contents = BytesMut::new();
+    for s in ["quick", "brown", "fox"] {
+        vector_contents.put_u8(s.len() as u8);
+        vector_contents.put_slice(s.as_bytes());
+    }
+    let vector = make_bytes(&vector_contents);
+    let typ = ColumnType::Vector {
+        typ: Box::new(ColumnType::Text),
+        dimensions: 3,
+    };
+    let decoded_vec = deserialize::<Vec<&str>>(&typ, &vector).unwrap();
+    assert_eq!(decoded_vec, vec!["quick", "brown", "fox"]);
+
+    // ser/de identity
+    assert_ser_de_identity(
+        &ColumnType::Vector {
+            typ: Box::new(ColumnType::Float),
+            dimensions: 2,
+        },
+        &[0.5f32, 1.5],
+        &mut Bytes::new(),
+    );
+    assert_ser_de_identity(
+        &typ,
+        &vec!["a".repeat(200), String::new(), "b".to_string()],
+        &mut Bytes::new(),
+    );
+    assert_ser_de_identity(
+        &ColumnType::Vector {
+            typ: Box::new(ColumnType::Vector {
+                typ: Box::new(ColumnType::BigInt),
+                dimensions: 2,
+            }),
+            dimensions: 2,
+        },
+        &vec![vec![1i64, 2], vec![3, 4]],
+        &mut Bytes::new(),
+    );
+    assert_ser_de_identity(
+        &ColumnType::Vector {
+            typ: Box::new(ColumnType::List(Box::new(ColumnType::Int))),
+            dimensions: 2,
+        },
+        &CqlValue::Vector(vec![
+            CqlValue::List(vec![CqlValue::Int(1)]),
+            CqlValue::List(vec![]),
+        ]),
+        &mut Bytes::new(),
+    );
+}
+
 #[test]
 fn test_tuples() {
     let mut tuple_contents = BytesMut::new();
@@ -971,6 +1058,155 @@ fn test_udt_cross_rename_fields() {
     }
 }
 
+#[test]
+fn test_udt_default_when_missing() {
+    fn default_b() -> i32 {
+        42
+    }
+
+    #[derive(scylla_macros::DeserializeValue, PartialEq, Eq, Debug)]
+    #[scylla(crate = crate)]
+    struct TestUdt {
+        a: String,
+        #[scylla(default = "default_b")]
+        b: i32,
+        #[scylla(default_when_missing)]
+        c: Option<i64>,
+    }
+
+    #[derive(scylla_macros::DeserializeValue, PartialEq, Eq, Debug)]
+    #[scylla(crate = crate, allow_missing)]
+    struct TestUdtAllowMissing {
+        a: String,
+        b: i32,
+    }
+
+    let udt_bytes = UdtSerializer::new()
+        .field("The quick brown fox".as_bytes())
+        .finalize();
+    let typ = udt_def_with_fields([("a", ColumnType::Text)]);
+
+    // Fields missing from the UDT definition get their default values.
+    let udt = deserialize::<TestUdt>(&typ, &udt_bytes).unwrap();
+    assert_eq!(
+        udt,
+        TestUdt {
+            a: "The quick brown fox".to_owned(),
+            b: 42,
+            c: None,
+        }
+    );
+
+    let udt = deserialize::<TestUdtAllowMissing>(&typ, &udt_bytes).unwrap();
+    assert_eq!(
+        udt,
+        TestUdtAllowMissing {
+            a: "The quick brown fox".to_owned(),
+            b: 0,
+        }
+    );
+
+    let typ = udt_def_with_fields([("b", ColumnType::Int)]);
+    TestUdt::type_check(&typ).unwrap_err();
+    TestUdtAllowMissing::type_check(&typ).unwrap();
+}
+
+#[test]
+fn test_enum() {
+    #[derive(
+        scylla_macros::DeserializeValue, scylla_macros::SerializeValue, PartialEq, Eq, Debug,
+    )]
+    #[scylla(crate = crate)]
+    enum Color {
+        Red,
+        #[scylla(rename = "verde")]
+        Green,
+    }
+
+    #[derive(
+        scylla_macros::DeserializeValue, scylla_macros::SerializeValue, PartialEq, Eq, Debug,
+    )]
+    #[scylla(crate = crate)]
+    enum Size {
+        Small = 1,
+        Large = 1000,
+    }
+
+    // By name
+    let bytes = make_bytes(b"Red");
+    assert_eq!(
+        deserialize::<Color>(&ColumnType::Text, &bytes).unwrap(),
+        Color::Red
+    );
+    let bytes = make_bytes(b"verde");
+    assert_eq!(
+        deserialize::<Color>(&ColumnType::Ascii, &bytes).unwrap(),
+        Color::Green
+    );
+    let bytes = make_bytes(b"Large");
+    assert_eq!(
+        deserialize::<Size>(&ColumnType::Text, &bytes).unwrap(),
+        Size::Large
+    );
+
+    // By discriminant
+    let bytes = make_bytes(&1_i8.to_be_bytes());
+    assert_eq!(
+        deserialize::<Size>(&ColumnType::TinyInt, &bytes).unwrap(),
+        Size::Small
+    );
+    let bytes = make_bytes(&1000_i32.to_be_bytes());
+    assert_eq!(
+        deserialize::<Size>(&ColumnType::Int, &bytes).unwrap(),
+        Size::Large
+    );
+    let bytes = make_bytes(&1000_i64.to_be_bytes());
+    assert_eq!(
+        deserialize::<Size>(&ColumnType::BigInt, &bytes).unwrap(),
+        Size::Large
+    );
+
+    // Serialization roundtrip
+    let mut buf = Bytes::new();
+    assert_ser_de_identity(&ColumnType::Text, &Color::Green, &mut buf);
+    assert_ser_de_identity(&ColumnType::SmallInt, &Size::Large, &mut buf);
+}
+
+#[test]
+fn test_transparent() {
+    #[derive(scylla_macros::DeserializeValue, PartialEq, Eq, Debug)]
+    #[scylla(crate = crate, transparent)]
+    struct UserId(i64);
+
+    #[derive(scylla_macros::DeserializeValue, PartialEq, Eq, Debug)]
+    #[scylla(crate = crate, transparent)]
+    struct Name<'a> {
+        name: &'a str,
+    }
+
+    let bytes = make_bytes(&42_i64.to_be_bytes());
+    assert_eq!(
+        deserialize::<UserId>(&ColumnType::BigInt, &bytes).unwrap(),
+        UserId(42)
+    );
+    let bytes = make_bytes(b"foo");
+    assert_eq!(
+        deserialize::<Name>(&ColumnType::Text, &bytes).unwrap(),
+        Name { name: "foo" }
+    );
+
+    // Errors come from the inner type
+    let err = UserId::type_check(&ColumnType::Text).unwrap_err();
+    let err = get_typeck_err_inner(err.0.as_ref());
+    assert_eq!(err.rust_name, std::any::type_name::<i64>());
+    assert_matches!(
+        err.kind,
+        BuiltinTypeCheckErrorKind::MismatchedType {
+            expected: &[ColumnType::BigInt, ColumnType::Counter]
+        }
+    );
+}
+
 #[test]
 fn test_custom_type_parser() {
     #[derive(Default, Debug, PartialEq, Eq)]
@@ -1198,6 +1434,70 @@ fn test_native_errors() {
     }
 }
 
+#[test]
+fn test_vector_errors() {
+    let typ = ColumnType::Vector {
+        typ: Box::new(ColumnType::Float),
+        dimensions: 3,
+    };
+
+    // Not a vector
+    assert_type_check_error!(
+        &Bytes::new(),
+        [f32; 3],
+        ColumnType::List(Box::new(ColumnType::Float)),
+        BuiltinTypeCheckErrorKind::VectorError(VectorTypeCheckErrorKind::NotVector)
+    );
+
+    // Wrong dimensions
+    assert_type_check_error!(
+        &Bytes::new(),
+        [f32; 4],
+        typ.clone(),
+        BuiltinTypeCheckErrorKind::VectorError(VectorTypeCheckErrorKind::WrongDimensions {
+            rust_type_dimensions: 4,
+            cql_type_dimensions: 3,
+        })
+    );
+
+    // Bad element type
+    assert_type_check_error!(
+        &Bytes::new(),
+        Vec<f64>,
+        typ.clone(),
+        BuiltinTypeCheckErrorKind::VectorError(VectorTypeCheckErrorKind::ElementTypeCheckFailed(_))
+    );
+
+    // Too few bytes
+    {
+        let bytes = make_bytes(&[0; 8]);
+        let err = deserialize::<Vec<f32>>(&typ, &bytes).unwrap_err();
+        let err = get_deser_err(&err);
+        assert_eq!(err.rust_name, std::any::type_name::<Vec<f32>>());
+        assert_matches!(
+            err.kind,
+            BuiltinDeserializationErrorKind::RawCqlBytesReadError(_)
+        );
+    }
+
+    // Bad element
+    {
+        let typ = ColumnType::Vector {
+            typ: Box::new(ColumnType::Ascii),
+            dimensions: 1,
+        };
+        let bytes = make_bytes(&[1, 0xFF]);
+        let err = deserialize::<Vec<&str>>(&typ, &bytes).unwrap_err();
+        let err = get_deser_err(&err);
+        assert_matches!(
+            err.kind,
+            BuiltinDeserializationErrorKind::VectorError(
+                VectorDeserializationErrorKind::ElementDeserializationFailed(_)
+            )
+        );
+    }
+}
+
 #[test]
 fn test_set_or_list_errors() {
     // Not a set or list
@@ -1572,6 +1872,91 @@ fn test_null_errors() {
     deserialize::<MaybeEmpty<i32>>(&ser_typ, &bytes).unwrap_err();
 }
 
+#[test]
+fn test_enum_errors() {
+    #[derive(scylla_macros::DeserializeValue, Debug)]
+    #[scylla(crate = crate)]
+    enum Color {
+        Red,
+        #[scylla(rename = "verde")]
+        Green,
+    }
+
+    #[derive(scylla_macros::DeserializeValue, Debug)]
+    #[scylla(crate = crate)]
+    enum Size {
+        Small = 1,
+        Large = 1000,
+    }
+
+    // Type check errors
+    assert_type_check_error!(
+        &make_bytes(&1_i32.to_be_bytes()),
+        Color,
+        ColumnType::Int,
+        BuiltinTypeCheckErrorKind::MismatchedType {
+            expected: &[ColumnType::Ascii, ColumnType::Text]
+        }
+    );
+    assert_type_check_error!(
+        &make_bytes(&[]),
+        Size,
+        ColumnType::Blob,
+        BuiltinTypeCheckErrorKind::MismatchedType {
+            expected: &[
+                ColumnType::Ascii,
+                ColumnType::Text,
+                ColumnType::TinyInt,
+                ColumnType::SmallInt,
+                ColumnType::Int,
+                ColumnType::BigInt,
+            ]
+        }
+    );
+
+    // Unknown variants
+    {
+        let bytes = make_bytes(b"Green");
+        let err = deserialize::<Color>(&ColumnType::Text, &bytes).unwrap_err();
+        let err = get_deser_err(&err);
+        assert_eq!(err.rust_name, std::any::type_name::<Color>());
+        let BuiltinDeserializationErrorKind::UnknownEnumVariantName(ref name) = err.kind else {
+            panic!("unexpected error kind: {:?}", err.kind)
+        };
+        assert_eq!(name, "Green");
+    }
+    assert_deser_error!(
+        &make_bytes(&2_i16.to_be_bytes()),
+        Size,
+        ColumnType::SmallInt,
+        BuiltinDeserializationErrorKind::UnknownEnumDiscriminant(2)
+    );
+
+    // Deserializing without a type check doesn't panic
+    {
+        let bytes = Bytes::new();
+        let err = <Size as DeserializeValue>::deserialize(
+            &ColumnType::Blob,
+            Some(FrameSlice::new(&bytes)),
+        )
+        .unwrap_err();
+        let err = get_deser_err(&err);
+        assert_eq!(err.rust_name, std::any::type_name::<Size>());
+        assert_matches!(
+            err.kind,
+            BuiltinDeserializationErrorKind::MismatchedEnumType
+        );
+    }
+
+    // Errors from the underlying type are reported for the enum
+    assert_deser_error!(
+        &make_null(),
+        Color,
+        ColumnType::Text,
+        BuiltinDeserializationErrorKind::ExpectedNonNull
+    );
+}
+
 #[test]
 fn test_udt_errors() {
     // Loose ordering
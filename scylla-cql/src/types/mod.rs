@@ -1,2 +1,4 @@
+#[doc(hidden)]
+pub mod compat;
 pub mod deserialize;
 pub mod serialize;
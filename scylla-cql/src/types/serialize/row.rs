@@ -612,6 +612,13 @@ pub enum BuiltinTypeCheckErrorKind {
         /// Name of the column for which the DB requested a value.
         db_column_name: String,
     },
+
+    /// The columns of a flattened row are not placed next to each other
+    /// in the statement.
+    FlattenedColumnsNotContiguous {
+        /// Name of the first column which is separated from the others.
+        name: String,
+    },
 }
 
 impl Display for BuiltinTypeCheckErrorKind {
@@ -636,6 +643,10 @@ impl Display for BuiltinTypeCheckErrorKind {
                 f,
                 "expected column with name {db_column_name} at given position, but the Rust field name is {rust_column_name}"
             ),
+            BuiltinTypeCheckErrorKind::FlattenedColumnsNotContiguous { name } => write!(
+                f,
+                "column {name} is separated from the other columns of the flattened row; they must be placed next to each other in the statement"
+            ),
         }
     }
 }
@@ -829,6 +840,90 @@ impl Default for SerializedValues {
     }
 }
 
+/// A row which is flattened into another row.
+///
+/// The flattened row is serialized directly into the outer row's writer,
+/// with a context shifted to the run of consecutive columns which are not
+/// claimed by the outer row.
+// Not part of the public API; used in derive macros.
+#[doc(hidden)]
+pub struct FlattenedRow<'r, T> {
+    row: &'r T,
+    written: bool,
+}
+
+impl<'r, T: SerializeRow> FlattenedRow<'r, T> {
+    pub fn new(row: &'r T) -> Self {
+        Self {
+            row,
+            written: false,
+        }
+    }
+
+    /// Handles the column at `index`, which is not claimed by the outer row.
+    ///
+    /// The first such column makes the flattened row write its values
+    /// for it and all the following columns up to the next outer one.
+    pub fn serialize_column(
+        &mut self,
+        index: usize,
+        ctx: &RowSerializationContext<'_>,
+        is_outer_column: impl Fn(&str) -> bool,
+        writer: &mut RowWriter,
+    ) -> Result<(), SerializationError> {
+        if self.written {
+            // The whole run was written when its first column was handled.
+            return Ok(());
+        }
+
+        let end = ctx.columns()[index..]
+            .iter()
+            .position(|spec| is_outer_column(&spec.name))
+            .map_or(ctx.columns().len(), |len| index + len);
+        if let Some(separated) = ctx.columns()[end..]
+            .iter()
+            .find(|spec| !is_outer_column(&spec.name))
+        {
+            return Err(mk_typck_err::<T>(
+                BuiltinTypeCheckErrorKind::FlattenedColumnsNotContiguous {
+                    name: separated.name.clone(),
+                },
+            ));
+        }
+        self.write(&ctx.columns()[index..end], writer)
+    }
+
+    /// Makes sure that the flattened row was serialized, even if all
+    /// the columns were claimed by the outer row.
+    pub fn finish(mut self, writer: &mut RowWriter) -> Result<(), SerializationError> {
+        if !self.written {
+            self.write(&[], writer)?;
+        }
+        Ok(())
+    }
+
+    fn write(
+        &mut self,
+        columns: &[ColumnSpec],
+        writer: &mut RowWriter,
+    ) -> Result<(), SerializationError> {
+        let ctx = RowSerializationContext { columns };
+        let value_count_before = writer.value_count();
+        self.row.serialize(&ctx, writer)?;
+        let value_count = writer.value_count() - value_count_before;
+        if value_count != ctx.columns().len() {
+            return Err(mk_typck_err::<T>(
+                BuiltinTypeCheckErrorKind::WrongColumnCount {
+                    rust_cols: value_count,
+                    cql_cols: ctx.columns().len(),
+                },
+            ));
+        }
+        self.written = true;
+        Ok(())
+    }
+}
+
 /// An iterator over raw values in some [`SerializedValues`].
 #[derive(Clone, Copy)]
 pub struct SerializedValuesIterator<'a> {
@@ -1548,4 +1643,74 @@ mod tests {
 
         assert_eq!(reference, row);
     }
+    #[derive(SerializeRow)]
+    #[scylla(crate = crate)]
+    struct TestRowInner {
+        b: i32,
+        c: Vec<i64>,
+    }
+
+    #[derive(SerializeRow)]
+    #[scylla(crate = crate)]
+    struct TestRowWithFlattenedField {
+        a: String,
+        #[scylla(flatten)]
+        inner: TestRowInner,
+    }
+
+    #[test]
+    fn test_row_serialization_with_flattened_field() {
+        let row = TestRowWithFlattenedField {
+            a: "Ala ma kota".to_owned(),
+            inner: TestRowInner {
+                b: 42,
+                c: vec![1, 2, 3],
+            },
+        };
+
+        // Columns of the flattened struct before the outer ones
+        let spec = [
+            col("c", ColumnType::List(Box::new(ColumnType::BigInt))),
+            col("b", ColumnType::Int),
+            col("a", ColumnType::Text),
+        ];
+        let reference = do_serialize((vec![1i64, 2i64, 3i64], 42i32, "Ala ma kota"), &spec);
+        let serialized = do_serialize(&row, &spec);
+        assert_eq!(reference, serialized);
+
+        // Columns of the flattened struct interleaved with the outer ones
+        let spec = [
+            col("c", ColumnType::List(Box::new(ColumnType::BigInt))),
+            col("a", ColumnType::Text),
+            col("b", ColumnType::Int),
+        ];
+        let err = do_serialize_err(&row, &spec);
+        let err = get_typeck_err(&err);
+        assert_eq!(err.rust_name, std::any::type_name::<TestRowInner>());
+        assert_matches!(
+            err.kind,
+            BuiltinTypeCheckErrorKind::FlattenedColumnsNotContiguous { ref name } if name == "b"
+        );
+
+        // Column of the flattened struct missing
+        let spec = [col("a", ColumnType::Text), col("b", ColumnType::Int)];
+        let err = do_serialize_err(&row, &spec);
+        let err = get_typeck_err(&err);
+        assert_eq!(err.rust_name, std::any::type_name::<TestRowInner>());
+        assert_matches!(
+            err.kind,
+            BuiltinTypeCheckErrorKind::ValueMissingForColumn { .. }
+        );
+
+        // Unknown column is reported by the flattened struct
+        let spec = [
+            col("a", ColumnType::Text),
+            col("b", ColumnType::Int),
+            col("c", ColumnType::List(Box::new(ColumnType::BigInt))),
+            col("d", ColumnType::Int),
+        ];
+        let err = do_serialize_err(&row, &spec);
+        let err = get_typeck_err(&err);
+        assert_matches!(err.kind, BuiltinTypeCheckErrorKind::NoColumnWithName { .. });
+    }
 }
@@ -10,11 +10,12 @@ use thiserror::Error;
 use uuid::Uuid;
 
 use crate::frame::response::result::{ColumnType, CqlValue};
-use crate::frame::types::vint_encode;
+use crate::frame::types::{self, vint_encode};
 use crate::frame::value::{
     Counter, CqlDate, CqlDecimal, CqlDuration, CqlTime, CqlTimestamp, CqlTimeuuid, CqlVarint,
     MaybeUnset, Unset, Value,
 };
+use crate::types::compat::{cql, SerializeCompatible};
 
 #[cfg(feature = "chrono-04")]
 use crate::frame::value::ValueOverflow;
@@ -65,6 +66,35 @@ macro_rules! exact_type_check {
     };
 }
 
+// Implements SerializeValue for a type which accepts only the given CQL types.
+// The accepted types are also made known at compile time,
+// for the schema checks of the derive macros.
+macro_rules! impl_serialize_exact {
+    ([$($generics:tt)*] $t:ty, [$($cql:ident)|+], |$me:ident, $typ:ident, $writer:ident| $e:expr) => {
+        impl<$($generics)*> SerializeValue for $t {
+            impl_serialize_via_writer!(|$me, $typ, $writer| {
+                exact_type_check!($typ, $($cql),+);
+                $e
+            });
+        }
+
+        impl_serialize_compatible!([$($generics)*] $t, [$($cql)|+]);
+    };
+
+    // Convenience pattern for omitting the brackets if the type has no generics.
+    ($t:ty, [$($cql:ident)|+], |$me:ident, $typ:ident, $writer:ident| $e:expr) => {
+        impl_serialize_exact!([] $t, [$($cql)|+], |$me, $typ, $writer| $e);
+    };
+}
+
+macro_rules! impl_serialize_compatible {
+    ([$($generics:tt)*] $t:ty, [$cql:ident $(| $rest:ident)*]) => {
+        impl<$($generics)*> SerializeCompatible<cql::$cql> for $t {}
+        impl_serialize_compatible!([$($generics)*] $t, [$($rest)|*]);
+    };
+    ([$($generics:tt)*] $t:ty, []) => {};
+}
+
 macro_rules! impl_serialize_via_writer {
     (|$me:ident, $writer:ident| $e:expr) => {
         impl_serialize_via_writer!(|$me, _typ, $writer| $e);
@@ -84,121 +114,78 @@ macro_rules! impl_serialize_via_writer {
     };
 }
 
-impl SerializeValue for i8 {
-    impl_serialize_via_writer!(|me, typ, writer| {
-        exact_type_check!(typ, TinyInt);
-        writer.set_value(me.to_be_bytes().as_slice()).unwrap()
-    });
-}
-impl SerializeValue for i16 {
-    impl_serialize_via_writer!(|me, typ, writer| {
-        exact_type_check!(typ, SmallInt);
-        writer.set_value(me.to_be_bytes().as_slice()).unwrap()
-    });
-}
-impl SerializeValue for i32 {
-    impl_serialize_via_writer!(|me, typ, writer| {
-        exact_type_check!(typ, Int);
-        writer.set_value(me.to_be_bytes().as_slice()).unwrap()
-    });
-}
-impl SerializeValue for i64 {
-    impl_serialize_via_writer!(|me, typ, writer| {
-        exact_type_check!(typ, BigInt);
-        writer.set_value(me.to_be_bytes().as_slice()).unwrap()
-    });
-}
-impl SerializeValue for CqlDecimal {
-    impl_serialize_via_writer!(|me, typ, writer| {
-        exact_type_check!(typ, Decimal);
-        let mut builder = writer.into_value_builder();
-        let (bytes, scale) = me.as_signed_be_bytes_slice_and_exponent();
-        builder.append_bytes(&scale.to_be_bytes());
-        builder.append_bytes(bytes);
-        builder
-            .finish()
-            .map_err(|_| mk_ser_err::<Self>(typ, BuiltinSerializationErrorKind::SizeOverflow))?
-    });
-}
+impl_serialize_exact!(i8, [TinyInt], |me, typ, writer| {
+    writer.set_value(me.to_be_bytes().as_slice()).unwrap()
+});
+impl_serialize_exact!(i16, [SmallInt], |me, typ, writer| {
+    writer.set_value(me.to_be_bytes().as_slice()).unwrap()
+});
+impl_serialize_exact!(i32, [Int], |me, typ, writer| {
+    writer.set_value(me.to_be_bytes().as_slice()).unwrap()
+});
+impl_serialize_exact!(i64, [BigInt], |me, typ, writer| {
+    writer.set_value(me.to_be_bytes().as_slice()).unwrap()
+});
+impl_serialize_exact!(CqlDecimal, [Decimal], |me, typ, writer| {
+    let mut builder = writer.into_value_builder();
+    let (bytes, scale) = me.as_signed_be_bytes_slice_and_exponent();
+    builder.append_bytes(&scale.to_be_bytes());
+    builder.append_bytes(bytes);
+    builder
+        .finish()
+        .map_err(|_| mk_ser_err::<Self>(typ, BuiltinSerializationErrorKind::SizeOverflow))?
+});
 #[cfg(feature = "bigdecimal-04")]
-impl SerializeValue for bigdecimal_04::BigDecimal {
-    impl_serialize_via_writer!(|me, typ, writer| {
-        exact_type_check!(typ, Decimal);
-        let mut builder = writer.into_value_builder();
-        let (value, scale) = me.as_bigint_and_exponent();
-        let scale: i32 = scale
-            .try_into()
-            .map_err(|_| mk_ser_err::<Self>(typ, BuiltinSerializationErrorKind::ValueOverflow))?;
-        builder.append_bytes(&scale.to_be_bytes());
-        builder.append_bytes(&value.to_signed_bytes_be());
-        builder
-            .finish()
-            .map_err(|_| mk_ser_err::<Self>(typ, BuiltinSerializationErrorKind::SizeOverflow))?
-    });
-}
-impl SerializeValue for CqlDate {
-    impl_serialize_via_writer!(|me, typ, writer| {
-        exact_type_check!(typ, Date);
-        writer.set_value(me.0.to_be_bytes().as_slice()).unwrap()
-    });
-}
-impl SerializeValue for CqlTimestamp {
-    impl_serialize_via_writer!(|me, typ, writer| {
-        exact_type_check!(typ, Timestamp);
-        writer.set_value(me.0.to_be_bytes().as_slice()).unwrap()
-    });
-}
-impl SerializeValue for CqlTime {
-    impl_serialize_via_writer!(|me, typ, writer| {
-        exact_type_check!(typ, Time);
-        writer.set_value(me.0.to_be_bytes().as_slice()).unwrap()
-    });
-}
+impl_serialize_exact!(bigdecimal_04::BigDecimal, [Decimal], |me, typ, writer| {
+    let mut builder = writer.into_value_builder();
+    let (value, scale) = me.as_bigint_and_exponent();
+    let scale: i32 = scale
+        .try_into()
+        .map_err(|_| mk_ser_err::<Self>(typ, BuiltinSerializationErrorKind::ValueOverflow))?;
+    builder.append_bytes(&scale.to_be_bytes());
+    builder.append_bytes(&value.to_signed_bytes_be());
+    builder
+        .finish()
+        .map_err(|_| mk_ser_err::<Self>(typ, BuiltinSerializationErrorKind::SizeOverflow))?
+});
+impl_serialize_exact!(CqlDate, [Date], |me, typ, writer| {
+    writer.set_value(me.0.to_be_bytes().as_slice()).unwrap()
+});
+impl_serialize_exact!(CqlTimestamp, [Timestamp], |me, typ, writer| {
+    writer.set_value(me.0.to_be_bytes().as_slice()).unwrap()
+});
+impl_serialize_exact!(CqlTime, [Time], |me, typ, writer| {
+    writer.set_value(me.0.to_be_bytes().as_slice()).unwrap()
+});
 #[cfg(feature = "chrono-04")]
-impl SerializeValue for chrono_04::NaiveDate {
-    impl_serialize_via_writer!(|me, typ, writer| {
-        exact_type_check!(typ, Date);
-        <CqlDate as SerializeValue>::serialize(&(*me).into(), typ, writer)?
-    });
-}
+impl_serialize_exact!(chrono_04::NaiveDate, [Date], |me, typ, writer| {
+    <CqlDate as SerializeValue>::serialize(&(*me).into(), typ, writer)?
+});
 #[cfg(feature = "chrono-04")]
-impl SerializeValue for chrono_04::DateTime<chrono_04::Utc> {
-    impl_serialize_via_writer!(|me, typ, writer| {
-        exact_type_check!(typ, Timestamp);
-        <CqlTimestamp as SerializeValue>::serialize(&(*me).into(), typ, writer)?
-    });
-}
+impl_serialize_exact!(
+    chrono_04::DateTime<chrono_04::Utc>,
+    [Timestamp],
+    |me, typ, writer| <CqlTimestamp as SerializeValue>::serialize(&(*me).into(), typ, writer)?
+);
 #[cfg(feature = "chrono-04")]
-impl SerializeValue for chrono_04::NaiveTime {
-    impl_serialize_via_writer!(|me, typ, writer| {
-        exact_type_check!(typ, Time);
-        let cql_time = CqlTime::try_from(*me).map_err(|_: ValueOverflow| {
-            mk_ser_err::<Self>(typ, BuiltinSerializationErrorKind::ValueOverflow)
-        })?;
-        <CqlTime as SerializeValue>::serialize(&cql_time, typ, writer)?
-    });
-}
+impl_serialize_exact!(chrono_04::NaiveTime, [Time], |me, typ, writer| {
+    let cql_time = CqlTime::try_from(*me).map_err(|_: ValueOverflow| {
+        mk_ser_err::<Self>(typ, BuiltinSerializationErrorKind::ValueOverflow)
+    })?;
+    <CqlTime as SerializeValue>::serialize(&cql_time, typ, writer)?
+});
 #[cfg(feature = "time-03")]
-impl SerializeValue for time_03::Date {
-    impl_serialize_via_writer!(|me, typ, writer| {
-        exact_type_check!(typ, Date);
-        <CqlDate as SerializeValue>::serialize(&(*me).into(), typ, writer)?
-    });
-}
+impl_serialize_exact!(time_03::Date, [Date], |me, typ, writer| {
+    <CqlDate as SerializeValue>::serialize(&(*me).into(), typ, writer)?
+});
 #[cfg(feature = "time-03")]
-impl SerializeValue for time_03::OffsetDateTime {
-    impl_serialize_via_writer!(|me, typ, writer| {
-        exact_type_check!(typ, Timestamp);
-        <CqlTimestamp as SerializeValue>::serialize(&(*me).into(), typ, writer)?
-    });
-}
+impl_serialize_exact!(time_03::OffsetDateTime, [Timestamp], |me, typ, writer| {
+    <CqlTimestamp as SerializeValue>::serialize(&(*me).into(), typ, writer)?
+});
 #[cfg(feature = "time-03")]
-impl SerializeValue for time_03::Time {
-    impl_serialize_via_writer!(|me, typ, writer| {
-        exact_type_check!(typ, Time);
-        <CqlTime as SerializeValue>::serialize(&(*me).into(), typ, writer)?
-    });
-}
+impl_serialize_exact!(time_03::Time, [Time], |me, typ, writer| {
+    <CqlTime as SerializeValue>::serialize(&(*me).into(), typ, writer)?
+});
 #[cfg(feature = "secrecy-08")]
 impl<V: SerializeValue + secrecy_08::Zeroize> SerializeValue for secrecy_08::Secret<V> {
     fn serialize<'b>(
@@ -210,115 +197,73 @@ impl<V: SerializeValue + secrecy_08::Zeroize> SerializeValue for secrecy_08::Sec
         V::serialize(self.expose_secret(), typ, writer)
     }
 }
-impl SerializeValue for bool {
-    impl_serialize_via_writer!(|me, typ, writer| {
-        exact_type_check!(typ, Boolean);
-        writer.set_value(&[*me as u8]).unwrap()
-    });
-}
-impl SerializeValue for f32 {
-    impl_serialize_via_writer!(|me, typ, writer| {
-        exact_type_check!(typ, Float);
-        writer.set_value(me.to_be_bytes().as_slice()).unwrap()
-    });
-}
-impl SerializeValue for f64 {
-    impl_serialize_via_writer!(|me, typ, writer| {
-        exact_type_check!(typ, Double);
-        writer.set_value(me.to_be_bytes().as_slice()).unwrap()
-    });
-}
-impl SerializeValue for Uuid {
-    impl_serialize_via_writer!(|me, typ, writer| {
-        exact_type_check!(typ, Uuid);
-        writer.set_value(me.as_bytes().as_ref()).unwrap()
-    });
-}
-impl SerializeValue for CqlTimeuuid {
-    impl_serialize_via_writer!(|me, typ, writer| {
-        exact_type_check!(typ, Timeuuid);
-        writer.set_value(me.as_bytes().as_ref()).unwrap()
-    });
-}
-impl SerializeValue for CqlVarint {
-    impl_serialize_via_writer!(|me, typ, writer| {
-        exact_type_check!(typ, Varint);
-        writer
-            .set_value(me.as_signed_bytes_be_slice())
-            .map_err(|_| mk_ser_err::<Self>(typ, BuiltinSerializationErrorKind::SizeOverflow))?
-    });
-}
+impl_serialize_exact!(bool, [Boolean], |me, typ, writer| {
+    writer.set_value(&[*me as u8]).unwrap()
+});
+impl_serialize_exact!(f32, [Float], |me, typ, writer| {
+    writer.set_value(me.to_be_bytes().as_slice()).unwrap()
+});
+impl_serialize_exact!(f64, [Double], |me, typ, writer| {
+    writer.set_value(me.to_be_bytes().as_slice()).unwrap()
+});
+impl_serialize_exact!(Uuid, [Uuid], |me, typ, writer| {
+    writer.set_value(me.as_bytes().as_ref()).unwrap()
+});
+impl_serialize_exact!(CqlTimeuuid, [Timeuuid], |me, typ, writer| {
+    writer.set_value(me.as_bytes().as_ref()).unwrap()
+});
+impl_serialize_exact!(CqlVarint, [Varint], |me, typ, writer| {
+    writer
+        .set_value(me.as_signed_bytes_be_slice())
+        .map_err(|_| mk_ser_err::<Self>(typ, BuiltinSerializationErrorKind::SizeOverflow))?
+});
 #[cfg(feature = "num-bigint-03")]
-impl SerializeValue for num_bigint_03::BigInt {
-    impl_serialize_via_writer!(|me, typ, writer| {
-        exact_type_check!(typ, Varint);
-        // TODO: The allocation here can be avoided and we can reimplement
-        // `to_signed_bytes_be` by using `to_u64_digits` and a bit of custom
-        // logic. Need better tests in order to do this.
-        writer
-            .set_value(me.to_signed_bytes_be().as_slice())
-            .map_err(|_| mk_ser_err::<Self>(typ, BuiltinSerializationErrorKind::SizeOverflow))?
-    });
-}
+impl_serialize_exact!(num_bigint_03::BigInt, [Varint], |me, typ, writer| {
+    // TODO: The allocation here can be avoided and we can reimplement
+    // `to_signed_bytes_be` by using `to_u64_digits` and a bit of custom
+    // logic. Need better tests in order to do this.
+    writer
+        .set_value(me.to_signed_bytes_be().as_slice())
+        .map_err(|_| mk_ser_err::<Self>(typ, BuiltinSerializationErrorKind::SizeOverflow))?
+});
 #[cfg(feature = "num-bigint-04")]
-impl SerializeValue for num_bigint_04::BigInt {
-    impl_serialize_via_writer!(|me, typ, writer| {
-        exact_type_check!(typ, Varint);
-        // TODO: See above comment for num-bigint-03.
-        writer
-            .set_value(me.to_signed_bytes_be().as_slice())
-            .map_err(|_| mk_ser_err::<Self>(typ, BuiltinSerializationErrorKind::SizeOverflow))?
-    });
-}
-impl SerializeValue for &str {
-    impl_serialize_via_writer!(|me, typ, writer| {
-        exact_type_check!(typ, Ascii, Text);
-        writer
-            .set_value(me.as_bytes())
-            .map_err(|_| mk_ser_err::<Self>(typ, BuiltinSerializationErrorKind::SizeOverflow))?
-    });
-}
-impl SerializeValue for Vec<u8> {
-    impl_serialize_via_writer!(|me, typ, writer| {
-        exact_type_check!(typ, Blob);
-        writer
-            .set_value(me.as_ref())
-            .map_err(|_| mk_ser_err::<Self>(typ, BuiltinSerializationErrorKind::SizeOverflow))?
-    });
-}
-impl SerializeValue for &[u8] {
-    impl_serialize_via_writer!(|me, typ, writer| {
-        exact_type_check!(typ, Blob);
-        writer
-            .set_value(me)
-            .map_err(|_| mk_ser_err::<Self>(typ, BuiltinSerializationErrorKind::SizeOverflow))?
-    });
-}
-impl<const N: usize> SerializeValue for [u8; N] {
-    impl_serialize_via_writer!(|me, typ, writer| {
-        exact_type_check!(typ, Blob);
-        writer
-            .set_value(me.as_ref())
-            .map_err(|_| mk_ser_err::<Self>(typ, BuiltinSerializationErrorKind::SizeOverflow))?
-    });
-}
-impl SerializeValue for IpAddr {
-    impl_serialize_via_writer!(|me, typ, writer| {
-        exact_type_check!(typ, Inet);
-        match me {
-            IpAddr::V4(ip) => writer.set_value(&ip.octets()).unwrap(),
-            IpAddr::V6(ip) => writer.set_value(&ip.octets()).unwrap(),
-        }
-    });
-}
-impl SerializeValue for String {
-    impl_serialize_via_writer!(|me, typ, writer| {
-        exact_type_check!(typ, Ascii, Text);
-        writer
-            .set_value(me.as_bytes())
-            .map_err(|_| mk_ser_err::<Self>(typ, BuiltinSerializationErrorKind::SizeOverflow))?
-    });
-}
+impl_serialize_exact!(num_bigint_04::BigInt, [Varint], |me, typ, writer| {
+    // TODO: See above comment for num-bigint-03.
+    writer
+        .set_value(me.to_signed_bytes_be().as_slice())
+        .map_err(|_| mk_ser_err::<Self>(typ, BuiltinSerializationErrorKind::SizeOverflow))?
+});
+impl_serialize_exact!(&str, [Ascii | Text], |me, typ, writer| {
+    writer
+        .set_value(me.as_bytes())
+        .map_err(|_| mk_ser_err::<Self>(typ, BuiltinSerializationErrorKind::SizeOverflow))?
+});
+impl_serialize_exact!(Vec<u8>, [Blob], |me, typ, writer| {
+    writer
+        .set_value(me.as_ref())
+        .map_err(|_| mk_ser_err::<Self>(typ, BuiltinSerializationErrorKind::SizeOverflow))?
+});
+impl_serialize_exact!(&[u8], [Blob], |me, typ, writer| {
+    writer
+        .set_value(me)
+        .map_err(|_| mk_ser_err::<Self>(typ, BuiltinSerializationErrorKind::SizeOverflow))?
+});
+impl_serialize_exact!([const N: usize] [u8; N], [Blob], |me, typ, writer| {
+    writer
+        .set_value(me.as_ref())
+        .map_err(|_| mk_ser_err::<Self>(typ, BuiltinSerializationErrorKind::SizeOverflow))?
+});
+impl_serialize_exact!(IpAddr, [Inet], |me, typ, writer| {
+    match me {
+        IpAddr::V4(ip) => writer.set_value(&ip.octets()).unwrap(),
+        IpAddr::V6(ip) => writer.set_value(&ip.octets()).unwrap(),
+    }
+});
+impl_serialize_exact!(String, [Ascii | Text], |me, typ, writer| {
+    writer
+        .set_value(me.as_bytes())
+        .map_err(|_| mk_ser_err::<Self>(typ, BuiltinSerializationErrorKind::SizeOverflow))?
+});
 impl<T: SerializeValue> SerializeValue for Option<T> {
     fn serialize<'b>(
         &self,
@@ -334,23 +279,17 @@ impl<T: SerializeValue> SerializeValue for Option<T> {
 impl SerializeValue for Unset {
     impl_serialize_via_writer!(|_me, writer| writer.set_unset());
 }
-impl SerializeValue for Counter {
-    impl_serialize_via_writer!(|me, typ, writer| {
-        exact_type_check!(typ, Counter);
-        writer.set_value(me.0.to_be_bytes().as_slice()).unwrap()
-    });
-}
-impl SerializeValue for CqlDuration {
-    impl_serialize_via_writer!(|me, typ, writer| {
-        exact_type_check!(typ, Duration);
-        // TODO: adjust vint_encode to use CellValueBuilder or something like that
-        let mut buf = Vec::with_capacity(27); // worst case size is 27
-        vint_encode(me.months as i64, &mut buf);
-        vint_encode(me.days as i64, &mut buf);
-        vint_encode(me.nanoseconds, &mut buf);
-        writer.set_value(buf.as_slice()).unwrap()
-    });
-}
+impl_serialize_exact!(Counter, [Counter], |me, typ, writer| {
+    writer.set_value(me.0.to_be_bytes().as_slice()).unwrap()
+});
+impl_serialize_exact!(CqlDuration, [Duration], |me, typ, writer| {
+    // TODO: adjust vint_encode to use CellValueBuilder or something like that
+    let mut buf = Vec::with_capacity(27); // worst case size is 27
+    vint_encode(me.months as i64, &mut buf);
+    vint_encode(me.days as i64, &mut buf);
+    vint_encode(me.nanoseconds, &mut buf);
+    writer.set_value(buf.as_slice()).unwrap()
+});
 impl<V: SerializeValue> SerializeValue for MaybeUnset<V> {
     fn serialize<'b>(
         &self,
@@ -447,13 +386,13 @@ impl<T: SerializeValue> SerializeValue for Vec<T> {
         typ: &ColumnType,
         writer: CellWriter<'b>,
     ) -> Result<WrittenCellProof<'b>, SerializationError> {
-        serialize_sequence(
-            std::any::type_name::<Self>(),
-            self.len(),
-            self.iter(),
-            typ,
-            writer,
-        )
+        let rust_name = std::any::type_name::<Self>();
+        match typ {
+            ColumnType::Vector { .. } => {
+                serialize_vector(rust_name, self.len(), self.iter(), typ, writer)
+            }
+            _ => serialize_sequence(rust_name, self.len(), self.iter(), typ, writer),
+        }
     }
 }
 impl<'a, T: SerializeValue + 'a> SerializeValue for &'a [T] {
@@ -462,13 +401,22 @@ impl<'a, T: SerializeValue + 'a> SerializeValue for &'a [T] {
         typ: &ColumnType,
         writer: CellWriter<'b>,
     ) -> Result<WrittenCellProof<'b>, SerializationError> {
-        serialize_sequence(
-            std::any::type_name::<Self>(),
-            self.len(),
-            self.iter(),
-            typ,
-            writer,
-        )
+        let rust_name = std::any::type_name::<Self>();
+        match typ {
+            ColumnType::Vector { .. } => {
+                serialize_vector(rust_name, self.len(), self.iter(), typ, writer)
+            }
+            _ => serialize_sequence(rust_name, self.len(), self.iter(), typ, writer),
+        }
+    }
+}
+impl<const N: usize> SerializeValue for [f32; N] {
+    fn serialize<'b>(
+        &self,
+        typ: &ColumnType,
+        writer: CellWriter<'b>,
+    ) -> Result<WrittenCellProof<'b>, SerializationError> {
+        serialize_vector(std::any::type_name::<Self>(), N, self.iter(), typ, writer)
     }
 }
 impl SerializeValue for CqlValue {
@@ -561,6 +509,13 @@ fn serialize_cql_value<'b>(
         }
         CqlValue::Uuid(u) => <_ as SerializeValue>::serialize(&u, typ, writer),
         CqlValue::Varint(v) => <_ as SerializeValue>::serialize(&v, typ, writer),
+        CqlValue::Vector(v) => serialize_vector(
+            std::any::type_name::<CqlValue>(),
+            v.len(),
+            v.iter(),
+            typ,
+            writer,
+        ),
     }
 }
 
@@ -831,6 +786,85 @@ fn serialize_sequence<'t, 'b, T: SerializeValue + 't>(
         .map_err(|_| mk_ser_err_named(rust_name, typ, BuiltinSerializationErrorKind::SizeOverflow))
 }
 
+fn serialize_vector<'t, 'b, T: SerializeValue + 't>(
+    rust_name: &'static str,
+    len: usize,
+    iter: impl Iterator<Item = &'t T>,
+    typ: &ColumnType,
+    writer: CellWriter<'b>,
+) -> Result<WrittenCellProof<'b>, SerializationError> {
+    let (elt, dimensions) = match typ {
+        ColumnType::Vector { typ, dimensions } => (typ, *dimensions),
+        _ => {
+            return Err(mk_typck_err_named(
+                rust_name,
+                typ,
+                VectorTypeCheckErrorKind::NotVector,
+            ));
+        }
+    };
+
+    if len != usize::from(dimensions) {
+        return Err(mk_typck_err_named(
+            rust_name,
+            typ,
+            VectorTypeCheckErrorKind::WrongDimensions {
+                rust_type_dimensions: len,
+                cql_type_dimensions: dimensions,
+            },
+        ));
+    }
+
+    let elt_size = elt.fixed_serialized_size();
+    let mut builder = writer.into_value_builder();
+    let mut element = Vec::new();
+
+    for el in iter {
+        // Vector elements are not prefixed with their [int] length,
+        // so each of them is serialized to a temporary buffer first.
+        element.clear();
+        T::serialize(el, elt, CellWriter::new(&mut element)).map_err(|err| {
+            mk_ser_err_named(
+                rust_name,
+                typ,
+                VectorSerializationErrorKind::ElementSerializationFailed(err),
+            )
+        })?;
+        let (len, contents) = element.split_at(4);
+        if i32::from_be_bytes(len.try_into().unwrap()) < 0 {
+            return Err(mk_ser_err_named(
+                rust_name,
+                typ,
+                VectorSerializationErrorKind::NullElement,
+            ));
+        }
+
+        match elt_size {
+            Some(size) if contents.len() != size => {
+                return Err(mk_ser_err_named(
+                    rust_name,
+                    typ,
+                    VectorSerializationErrorKind::ElementSizeMismatch {
+                        expected: size,
+                        got: contents.len(),
+                    },
+                ));
+            }
+            Some(_) => {}
+            None => {
+                let mut len = Vec::new();
+                types::unsigned_vint_encode(contents.len() as u64, &mut len);
+                builder.append_bytes(&len);
+            }
+        }
+        builder.append_bytes(contents);
+    }
+
+    builder
+        .finish()
+        .map_err(|_| mk_ser_err_named(rust_name, typ, BuiltinSerializationErrorKind::SizeOverflow))
+}
+
 fn serialize_mapping<'t, 'b, K: SerializeValue + 't, V: SerializeValue + 't>(
     rust_name: &'static str,
     len: usize,
@@ -1108,6 +1142,9 @@ pub enum BuiltinTypeCheckErrorKind {
     /// A type check failure specific to a CQL UDT.
     UdtError(UdtTypeCheckErrorKind),
 
+    /// A type check failure specific to a CQL vector.
+    VectorError(VectorTypeCheckErrorKind),
+
     /// Custom CQL type - unsupported
     // TODO: Should we actually support it? Counters used to be implemented like that.
     CustomTypeUnsupported,
@@ -1137,6 +1174,12 @@ impl From<UdtTypeCheckErrorKind> for BuiltinTypeCheckErrorKind {
     }
 }
 
+impl From<VectorTypeCheckErrorKind> for BuiltinTypeCheckErrorKind {
+    fn from(value: VectorTypeCheckErrorKind) -> Self {
+        BuiltinTypeCheckErrorKind::VectorError(value)
+    }
+}
+
 impl Display for BuiltinTypeCheckErrorKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -1150,6 +1193,7 @@ impl Display for BuiltinTypeCheckErrorKind {
             BuiltinTypeCheckErrorKind::MapError(err) => err.fmt(f),
             BuiltinTypeCheckErrorKind::TupleError(err) => err.fmt(f),
             BuiltinTypeCheckErrorKind::UdtError(err) => err.fmt(f),
+            BuiltinTypeCheckErrorKind::VectorError(err) => err.fmt(f),
             BuiltinTypeCheckErrorKind::CustomTypeUnsupported => {
                 f.write_str("custom CQL types are unsupported")
             }
@@ -1179,6 +1223,9 @@ pub enum BuiltinSerializationErrorKind {
 
     /// A serialization failure specific to a CQL UDT.
     UdtError(UdtSerializationErrorKind),
+
+    /// A serialization failure specific to a CQL vector.
+    VectorError(VectorSerializationErrorKind),
 }
 
 impl From<SetOrListSerializationErrorKind> for BuiltinSerializationErrorKind {
@@ -1205,6 +1252,12 @@ impl From<UdtSerializationErrorKind> for BuiltinSerializationErrorKind {
     }
 }
 
+impl From<VectorSerializationErrorKind> for BuiltinSerializationErrorKind {
+    fn from(value: VectorSerializationErrorKind) -> Self {
+        BuiltinSerializationErrorKind::VectorError(value)
+    }
+}
+
 impl Display for BuiltinSerializationErrorKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -1218,6 +1271,7 @@ impl Display for BuiltinSerializationErrorKind {
             BuiltinSerializationErrorKind::MapError(err) => err.fmt(f),
             BuiltinSerializationErrorKind::TupleError(err) => err.fmt(f),
             BuiltinSerializationErrorKind::UdtError(err) => err.fmt(f),
+            BuiltinSerializationErrorKind::VectorError(err) => err.fmt(f),
         }
     }
 }
@@ -1312,6 +1366,78 @@ impl Display for SetOrListSerializationErrorKind {
     }
 }
 
+/// Describes why type checking of a vector type failed.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum VectorTypeCheckErrorKind {
+    /// The CQL type is not a vector.
+    NotVector,
+
+    /// The number of elements is different than the vector's dimensions.
+    WrongDimensions {
+        /// The number of elements that the Rust value has.
+        rust_type_dimensions: usize,
+
+        /// The dimensions of the CQL vector.
+        cql_type_dimensions: u16,
+    },
+}
+
+impl Display for VectorTypeCheckErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VectorTypeCheckErrorKind::NotVector => f.write_str(
+                "the CQL type the Rust type was attempted to be type checked against was not a vector",
+            ),
+            VectorTypeCheckErrorKind::WrongDimensions {
+                rust_type_dimensions,
+                cql_type_dimensions,
+            } => write!(
+                f,
+                "the Rust value has {rust_type_dimensions} elements, but the CQL vector has {cql_type_dimensions} dimensions"
+            ),
+        }
+    }
+}
+
+/// Describes why serialization of a vector type failed.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum VectorSerializationErrorKind {
+    /// One of the elements of the vector failed to serialize.
+    ElementSerializationFailed(SerializationError),
+
+    /// One of the elements was serialized as null or unset, which vectors don't support.
+    NullElement,
+
+    /// One of the elements was serialized with a size different than
+    /// the fixed size of the element type.
+    ElementSizeMismatch {
+        /// The size of the element type.
+        expected: usize,
+
+        /// The size of the serialized element.
+        got: usize,
+    },
+}
+
+impl Display for VectorSerializationErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VectorSerializationErrorKind::ElementSerializationFailed(err) => {
+                write!(f, "failed to serialize one of the elements: {err}")
+            }
+            VectorSerializationErrorKind::NullElement => {
+                f.write_str("vector elements cannot be null or unset")
+            }
+            VectorSerializationErrorKind::ElementSizeMismatch { expected, got } => write!(
+                f,
+                "the vector element type requires {expected} bytes, but the element was serialized to {got} bytes"
+            ),
+        }
+    }
+}
+
 /// Describes why type checking of a tuple failed.
 #[derive(Debug, Clone)]
 #[non_exhaustive]
@@ -1502,7 +1628,8 @@ mod tests {
         BuiltinSerializationError, BuiltinSerializationErrorKind, BuiltinTypeCheckError,
         BuiltinTypeCheckErrorKind, MapSerializationErrorKind, MapTypeCheckErrorKind,
         SetOrListSerializationErrorKind, SetOrListTypeCheckErrorKind, TupleSerializationErrorKind,
-        TupleTypeCheckErrorKind, ValueAdapter,
+        TupleTypeCheckErrorKind, ValueAdapter, VectorSerializationErrorKind,
+        VectorTypeCheckErrorKind,
     };
     use crate::types::serialize::{CellWriter, SerializationError};
 
@@ -1695,6 +1822,108 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_vector_serialization() {
+        let typ = ColumnType::Vector {
+            typ: Box::new(ColumnType::Float),
+            dimensions: 2,
+        };
+        // Elements of fixed size are not prefixed with their length.
+        let expected = [
+            &8i32.to_be_bytes()[..],
+            &1.5f32.to_be_bytes(),
+            &(-2f32).to_be_bytes(),
+        ]
+        .concat();
+        assert_eq!(do_serialize([1.5f32, -2.0], &typ), expected);
+        assert_eq!(do_serialize(vec![1.5f32, -2.0], &typ), expected);
+        assert_eq!(do_serialize(&[1.5f32, -2.0][..], &typ), expected);
+        let cql_value = CqlValue::Vector(vec![CqlValue::Float(1.5), CqlValue::Float(-2.0)]);
+        assert_eq!(do_serialize(&cql_value, &typ), expected);
+
+        let mut legacy = Vec::new();
+        Value::serialize(&cql_value, &mut legacy).unwrap();
+        assert_eq!(legacy, expected);
+
+        // Elements of variable size are prefixed with their length as unsigned vint.
+        let typ = ColumnType::Vector {
+            typ: Box::new(ColumnType::Text),
+            dimensions: 2,
+        };
+        let long = "a".repeat(200);
+        let expected = [
+            &206i32.to_be_bytes()[..],
+            &[0x80, 200],
+            long.as_bytes(),
+            &[3],
+            b"foo",
+        ]
+        .concat();
+        assert_eq!(do_serialize(vec![long.as_str(), "foo"], &typ), expected);
+        let cql_value = CqlValue::Vector(vec![CqlValue::Text(long), CqlValue::Text("foo".into())]);
+        assert_eq!(do_serialize(&cql_value, &typ), expected);
+
+        let mut legacy = Vec::new();
+        Value::serialize(&cql_value, &mut legacy).unwrap();
+        assert_eq!(legacy, expected);
+    }
+
+    #[test]
+    fn test_vector_errors() {
+        let typ = ColumnType::Vector {
+            typ: Box::new(ColumnType::Float),
+            dimensions: 3,
+        };
+
+        // Not a vector
+        let err = do_serialize_err([1.0f32, 2.0, 3.0], &ColumnType::Double);
+        let err = get_typeck_err(&err);
+        assert_eq!(err.rust_name, std::any::type_name::<[f32; 3]>());
+        assert_matches!(
+            err.kind,
+            BuiltinTypeCheckErrorKind::VectorError(VectorTypeCheckErrorKind::NotVector)
+        );
+
+        // Wrong dimensions
+        let err = do_serialize_err(vec![1.0f32, 2.0], &typ);
+        let err = get_typeck_err(&err);
+        assert_eq!(err.got, typ);
+        assert_matches!(
+            err.kind,
+            BuiltinTypeCheckErrorKind::VectorError(VectorTypeCheckErrorKind::WrongDimensions {
+                rust_type_dimensions: 2,
+                cql_type_dimensions: 3,
+            })
+        );
+
+        // Error during serialization of an element
+        let err = do_serialize_err(vec![1.0f64, 2.0, 3.0], &typ);
+        let err = get_ser_err(&err);
+        assert_matches!(
+            err.kind,
+            BuiltinSerializationErrorKind::VectorError(
+                VectorSerializationErrorKind::ElementSerializationFailed(_)
+            )
+        );
+
+        // Null element
+        let err = do_serialize_err(vec![Some(1.0f32), None, Some(3.0)], &typ);
+        let err = get_ser_err(&err);
+        assert_matches!(
+            err.kind,
+            BuiltinSerializationErrorKind::VectorError(VectorSerializationErrorKind::NullElement)
+        );
+
+        // Element of a wrong size
+        let cql_value = CqlValue::Vector(vec![CqlValue::Int(1), CqlValue::Empty, CqlValue::Int(3)]);
+        let typ = ColumnType::Vector {
+            typ: Box::new(ColumnType::Int),
+            dimensions: 3,
+        };
+        let err = do_serialize_err(cql_value, &typ);
+        assert!(err.to_string().contains("requires 4 bytes"), "{}", err);
+    }
+
     #[test]
     fn test_map_errors() {
         // Not a map
@@ -2762,4 +2991,121 @@ mod tests {
 
         assert_eq!(reference, row);
     }
+
+    #[derive(SerializeValue, Debug, Clone, Copy)]
+    #[scylla(crate = crate)]
+    enum TestEnumByName {
+        Red,
+        #[scylla(rename = "verde")]
+        Green,
+    }
+
+    #[derive(SerializeValue, Debug, Clone, Copy)]
+    #[scylla(crate = crate)]
+    enum TestEnumWithDiscriminants {
+        Small = 1,
+        Large = 1000,
+    }
+
+    #[test]
+    fn test_enum_serialization() {
+        assert_eq!(
+            do_serialize(TestEnumByName::Red, &ColumnType::Text),
+            do_serialize("Red", &ColumnType::Text)
+        );
+        assert_eq!(
+            do_serialize(TestEnumByName::Green, &ColumnType::Ascii),
+            do_serialize("verde", &ColumnType::Ascii)
+        );
+        assert_eq!(
+            do_serialize(TestEnumWithDiscriminants::Large, &ColumnType::Text),
+            do_serialize("Large", &ColumnType::Text)
+        );
+        assert_eq!(
+            do_serialize(TestEnumWithDiscriminants::Small, &ColumnType::TinyInt),
+            do_serialize(1_i8, &ColumnType::TinyInt)
+        );
+        assert_eq!(
+            do_serialize(TestEnumWithDiscriminants::Large, &ColumnType::Int),
+            do_serialize(1000_i32, &ColumnType::Int)
+        );
+        assert_eq!(
+            do_serialize(TestEnumWithDiscriminants::Large, &ColumnType::BigInt),
+            do_serialize(1000_i64, &ColumnType::BigInt)
+        );
+    }
+
+    #[test]
+    fn test_enum_serialization_errors() {
+        // Enums without discriminants can't be stored in integer columns
+        let err = do_serialize_err(TestEnumByName::Red, &ColumnType::Int);
+        let err = get_typeck_err(&err);
+        assert_eq!(err.rust_name, std::any::type_name::<TestEnumByName>());
+        assert_eq!(err.got, ColumnType::Int);
+        assert_matches!(
+            err.kind,
+            BuiltinTypeCheckErrorKind::MismatchedType {
+                expected: &[ColumnType::Ascii, ColumnType::Text],
+            }
+        );
+
+        let err = do_serialize_err(TestEnumWithDiscriminants::Small, &ColumnType::Blob);
+        let err = get_typeck_err(&err);
+        assert_matches!(
+            err.kind,
+            BuiltinTypeCheckErrorKind::MismatchedType {
+                expected: &[
+                    ColumnType::Ascii,
+                    ColumnType::Text,
+                    ColumnType::TinyInt,
+                    ColumnType::SmallInt,
+                    ColumnType::Int,
+                    ColumnType::BigInt,
+                ],
+            }
+        );
+
+        // The discriminant does not fit in the column type
+        let err = do_serialize_err(TestEnumWithDiscriminants::Large, &ColumnType::TinyInt);
+        let err = get_ser_err(&err);
+        assert_eq!(
+            err.rust_name,
+            std::any::type_name::<TestEnumWithDiscriminants>()
+        );
+        assert_eq!(err.got, ColumnType::TinyInt);
+        assert_matches!(err.kind, BuiltinSerializationErrorKind::ValueOverflow);
+    }
+
+    #[derive(SerializeValue)]
+    #[scylla(crate = crate, transparent)]
+    struct TestNewtype(i32);
+
+    #[derive(SerializeValue)]
+    #[scylla(crate = crate, transparent)]
+    struct TestNamedNewtype<'a> {
+        name: &'a str,
+    }
+
+    #[test]
+    fn test_transparent_serialization() {
+        assert_eq!(
+            do_serialize(TestNewtype(42), &ColumnType::Int),
+            do_serialize(42_i32, &ColumnType::Int)
+        );
+        assert_eq!(
+            do_serialize(TestNamedNewtype { name: "foo" }, &ColumnType::Text),
+            do_serialize("foo", &ColumnType::Text)
+        );
+
+        // Errors come from the inner type
+        let err = do_serialize_err(TestNewtype(42), &ColumnType::Text);
+        let err = get_typeck_err(&err);
+        assert_eq!(err.rust_name, std::any::type_name::<i32>());
+        assert_matches!(
+            err.kind,
+            BuiltinTypeCheckErrorKind::MismatchedType {
+                expected: &[ColumnType::Int],
+            }
+        );
+    }
 }
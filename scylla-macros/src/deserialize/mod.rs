@@ -124,6 +124,15 @@ where
     }
 }
 
+/// Generates an expression which creates the default value of a field,
+/// using either the function given in the `default` attribute or `Default::default`.
+fn default_value_expr(default_fn: Option<&syn::Path>) -> syn::Expr {
+    match default_fn {
+        Some(path) => parse_quote!(#path()),
+        None => parse_quote!(::std::default::Default::default()),
+    }
+}
+
 /// Generates T: Default constraints for those fields that need it.
 fn generate_default_constraints<Field: DeserializeCommonFieldAttrs>(
     fields: &[Field],
@@ -6,6 +6,7 @@ use syn::ext::IdentExt;
 use syn::parse_quote;
 
 use super::{DeserializeCommonFieldAttrs, DeserializeCommonStructAttrs};
+use crate::schema::{CheckedField, Target};
 
 #[derive(FromAttributes)]
 #[darling(attributes(scylla))]
@@ -26,6 +27,21 @@ struct StructAttrs {
     // This annotation only works if `enforce_order` is specified.
     #[darling(default)]
     skip_name_checks: bool,
+
+    // If true, then all fields are initialized with their default value
+    // when the corresponding column is missing, as if they were annotated
+    // with `default_when_missing`.
+    #[darling(default)]
+    allow_missing: bool,
+
+    // If set, the fields are checked at compile time against the columns
+    // of the table given with the `table` attribute, as defined in this file.
+    #[darling(default)]
+    schema_file: Option<syn::LitStr>,
+
+    // Attributes of derive(Table), which may be used on the same struct.
+    #[darling(flatten)]
+    table_attrs: crate::table::TableStructAttrs,
 }
 
 impl DeserializeCommonStructAttrs for StructAttrs {
@@ -42,19 +58,46 @@ struct Field {
     #[darling(default)]
     skip: bool,
 
+    // If true, then - if the column corresponding to this field is missing
+    // from the result - the field will be initialized to its default value.
+    #[darling(default)]
+    default_when_missing: bool,
+
+    // An alias of `default_when_missing`, for consistency with DeserializeValue.
+    #[darling(default)]
+    allow_missing: bool,
+
+    // If set, then the field is initialized by calling this function instead
+    // of Default::default(). Implies `default_when_missing`.
+    #[darling(default)]
+    default: Option<syn::Path>,
+
     // If set, then deserialization will look for the column with given name
     // and deserialize it to this Rust field, instead of just using the Rust
     // field name.
     #[darling(default)]
     rename: Option<String>,
 
+    // If true, then the field is deserialized with its DeserializeRow
+    // implementation from all the columns not claimed by other fields.
+    #[darling(default)]
+    flatten: bool,
+
+    // If true, the field is not checked against the schema file.
+    #[darling(default)]
+    skip_schema_check: bool,
+
+    // Attributes of derive(Table), which may be used on the same struct.
+    #[darling(flatten)]
+    _table_attrs: crate::table::TableFieldAttrs,
+
     ident: Option<syn::Ident>,
     ty: syn::Type,
 }
 
 impl DeserializeCommonFieldAttrs for Field {
     fn needs_default(&self) -> bool {
-        self.skip
+        (self.skip || self.default_when_missing) && self.default.is_none()
     }
 
     fn deserialize_target(&self) -> &syn::Type {
@@ -65,7 +108,7 @@ impl DeserializeCommonFieldAttrs for Field {
 // derive(DeserializeRow) for the new DeserializeRow trait
 pub(crate) fn deserialize_row_derive(
     tokens_input: proc_macro::TokenStream,
-) -> Result<syn::ItemImpl, syn::Error> {
+) -> Result<proc_macro2::TokenStream, syn::Error> {
     let input = syn::parse(tokens_input)?;
 
     let implemented_trait: syn::Path = parse_quote! { DeserializeRow };
@@ -77,16 +120,28 @@ pub(crate) fn deserialize_row_derive(
         .unraw()
         .to_string();
     let constraining_trait = parse_quote! { DeserializeValue };
-    let s = StructDesc::new(&input, &implemented_trait_name, constraining_trait)?;
+    let mut s = StructDesc::new(&input, &implemented_trait_name, constraining_trait)?;
 
     validate_attrs(&s.attrs, &s.fields)?;
 
+    let allow_missing = s.attrs.allow_missing;
+    for field in s.fields.iter_mut() {
+        field.default_when_missing |=
+            (allow_missing && !field.flatten) || field.allow_missing || field.default.is_some();
+    }
+
     let items = [
         s.generate_type_check_method().into(),
         s.generate_deserialize_method().into(),
     ];
 
-    Ok(s.generate_impl(implemented_trait, items))
+    let deserialize_impl = s.generate_impl(implemented_trait, items);
+    let schema_check = s.generate_schema_check()?;
+
+    Ok(quote::quote! {
+        #deserialize_impl
+        #schema_check
+    })
 }
 
 fn validate_attrs(attrs: &StructAttrs, fields: &[Field]) -> Result<(), darling::Error> {
@@ -110,10 +165,77 @@ fn validate_attrs(attrs: &StructAttrs, fields: &[Field]) -> Result<(), darling::
                 errors.push(err);
             }
         }
-    } else {
+    }
+
+    let mut flattened_fields = fields.iter().filter(|f| f.flatten);
+    if let Some(flattened_field) = flattened_fields.next() {
+        // The flattened field receives the columns which are not claimed
+        // by other fields, so there can be only one such field.
+        for field in flattened_fields {
+            let err = darling::Error::custom("only one field per struct can have <flatten>")
+                .with_span(&field.ident);
+            errors.push(err);
+        }
+
+        // The number of columns of the flattened struct is not known
+        // when generating code for <enforce_order>.
+        if attrs.enforce_order {
+            let err = darling::Error::custom("<flatten> can't be used with <enforce_order>")
+                .with_span(&flattened_field.ident);
+            errors.push(err);
+        }
+
+        if flattened_field.rename.is_some()
+            || flattened_field.skip
+            || flattened_field.default_when_missing
+            || flattened_field.allow_missing
+            || flattened_field.default.is_some()
+        {
+            let err =
+                darling::Error::custom("<flatten> can't be combined with other field attributes")
+                    .with_span(&flattened_field.ident);
+            errors.push(err);
+        }
+    }
+
+    if attrs.enforce_order && !attrs.allow_missing {
+        // With <enforce_order>, the fields which may be missing must be
+        // at the end of the struct, so that the columns of the required
+        // fields are always at the same positions.
+        let invalid_required_field = fields
+            .iter()
+            .filter(|field| !field.skip)
+            // Skip the prefix of required fields.
+            .skip_while(|field| field.is_required())
+            // No required field is allowed after a field which may be missing.
+            .find(|field| field.is_required());
+        if let Some(invalid) = invalid_required_field {
+            let err = darling::Error::custom(
+                "when <enforce_order> is on, fields with <default_when_missing> are only permitted at the end of the struct, \
+                      i.e. no field without <default_when_missing> and <skip> is allowed to be after any field with <default_when_missing>.",
+            )
+            .with_span(&invalid.ident);
+            errors.push(err);
+        }
+    }
+
+    if attrs.schema_file.is_some() {
+        if attrs.table_attrs.table.is_none() {
+            errors.push(darling::Error::custom(
+                "attribute <schema_file> requires <table>.",
+            ));
+        }
+        if attrs.skip_name_checks {
+            errors.push(darling::Error::custom(
+                "attribute <schema_file> can't be used with <skip_name_checks>, as the columns are found by name.",
+            ));
+        }
+    }
+
+    if !attrs.skip_name_checks {
         // Detect name collisions caused by `rename`.
         let mut used_names = HashMap::<String, &Field>::new();
-        for field in fields {
+        for field in fields.iter().filter(|f| !f.flatten) {
             let column_name = field.column_name();
             if let Some(other_field) = used_names.get(&column_name) {
                 let other_field_ident = other_field.ident.as_ref().unwrap();
@@ -132,7 +254,20 @@ fn validate_attrs(attrs: &StructAttrs, fields: &[Field]) -> Result<(), darling::
 impl Field {
     // Returns whether this field is mandatory for deserialization.
     fn is_required(&self) -> bool {
-        !self.skip
+        self.is_own_column()
+            && !self.default_when_missing
+            && !self.allow_missing
+            && self.default.is_none()
+    }
+
+    // Returns whether this field is deserialized from a column of its own.
+    fn is_own_column(&self) -> bool {
+        !self.skip && !self.flatten
+    }
+
+    // An expression which creates the default value of this field
+    fn default_value(&self) -> syn::Expr {
+        super::default_value_expr(self.default.as_ref())
     }
 
     // The name of the column corresponding to this Rust struct field
@@ -152,6 +287,37 @@ impl Field {
 type StructDesc = super::StructDescForDeserialize<StructAttrs, Field>;
 
 impl StructDesc {
+    fn generate_schema_check(&self) -> Result<Option<proc_macro2::TokenStream>, syn::Error> {
+        let (Some(schema_file), Some((keyspace, name))) = (
+            &self.attrs.schema_file,
+            self.attrs.table_attrs.qualified_table(),
+        ) else {
+            return Ok(None);
+        };
+        // The columns of the flattened struct are checked by its own derive.
+        let fields = self
+            .fields
+            .iter()
+            .filter(|f| !f.flatten)
+            .map(|f| CheckedField {
+                ident: f.ident.as_ref().unwrap(),
+                ty: &f.ty,
+                name: f.column_name(),
+                may_be_missing: f.default_when_missing,
+                skip_check: f.skip || f.skip_schema_check,
+            })
+            .collect::<Vec<_>>();
+        crate::schema::generate_schema_check(
+            schema_file,
+            Target::Table { keyspace, name },
+            &fields,
+            &self.generics,
+            &self.attrs.macro_internal_path(),
+            &parse_quote!(assert_deserialize_compatible),
+        )
+        .map(Some)
+    }
+
     fn generate_type_check_method(&self) -> syn::ImplItemFn {
         if self.attrs.enforce_order {
             TypeCheckAssumeOrderGenerator(self).generate()
@@ -208,26 +374,85 @@ impl<'sd> TypeCheckAssumeOrderGenerator<'sd> {
         let macro_internal = self.0.struct_attrs().macro_internal_path();
         let constraint_lifetime = self.0.constraint_lifetime();
 
-        let required_fields_iter = || {
+        let column_fields_iter = || {
             self.0
                 .fields()
                 .iter()
                 .enumerate()
-                .filter(|(_, f)| f.is_required())
+                .filter(|(_, f)| f.is_own_column())
         };
-        let required_fields_count = required_fields_iter().count();
-        let required_fields_idents: Vec<_> = (0..required_fields_count)
-            .map(|i| quote::format_ident!("f_{}", i))
-            .collect();
-        let name_verifications = required_fields_iter()
-            .zip(required_fields_idents.iter().enumerate())
-            .map(|((field_idx, field), (col_idx, fidents))| {
-                self.generate_name_verification(field_idx, col_idx, field, fidents)
-            });
+        // Fields which may be missing are placed at the end of the struct,
+        // so the first `required_fields_count` columns are always present.
+        let required_fields_count = column_fields_iter()
+            .filter(|(_, f)| f.is_required())
+            .count();
+        let column_fields_count = column_fields_iter().count();
+        let column_spec: syn::Ident = parse_quote!(spec);
+
+        let field_verifications = column_fields_iter().enumerate().map(
+            |(column_index, (field_index, field))| -> syn::Stmt {
+                let name_verification = self.generate_name_verification(
+                    field_index,
+                    column_index,
+                    field,
+                    &column_spec,
+                );
+                let deserializer = field.deserialize_target();
+                let verification: syn::Block = parse_quote! {
+                    {
+                        // Verify the name (unless `skip_name_checks' is specified)
+                        #name_verification
+
+                        // Verify the type
+                        <#deserializer as #macro_internal::DeserializeValue<#constraint_lifetime>>::type_check(&#column_spec.typ)
+                            .map_err(|err| #macro_internal::mk_row_typck_err::<Self>(
+                                column_types_iter(),
+                                #macro_internal::DeserBuiltinRowTypeCheckErrorKind::ColumnTypeCheckFailed {
+                                    column_index: #column_index,
+                                    column_name: ::std::clone::Clone::clone(&#column_spec.name),
+                                    err,
+                                }
+                            ))?;
+                    }
+                };
+                if field.is_required() {
+                    parse_quote! {
+                        {
+                            let #column_spec = &specs[#column_index];
+                            #verification
+                        }
+                    }
+                } else {
+                    parse_quote! {
+                        if let ::std::option::Option::Some(#column_spec) = specs.get(#column_index) #verification
+                    }
+                }
+            },
+        );
 
-        let required_fields_deserializers =
-            required_fields_iter().map(|(_, f)| f.deserialize_target());
-        let numbers = 0usize..;
+        let mk_wrong_column_count_err = |rust_cols: usize| -> syn::Expr {
+            parse_quote! {
+                ::std::result::Result::Err(
+                    #macro_internal::mk_row_typck_err::<Self>(
+                        column_types_iter(),
+                        #macro_internal::DeserBuiltinRowTypeCheckErrorKind::WrongColumnCount {
+                            rust_cols: #rust_cols,
+                            cql_cols: specs.len(),
+                        }
+                    ),
+                )
+            }
+        };
+        // Comparing `specs.len() < 0` would trigger a lint in user code
+        let too_few_columns_check: Option<syn::Stmt> = (required_fields_count > 0).then(|| {
+            let err = mk_wrong_column_count_err(required_fields_count);
+            parse_quote! {
+                if specs.len() < #required_fields_count {
+                    return #err;
+                }
+            }
+        });
+        let too_many_columns_err = mk_wrong_column_count_err(column_fields_count);
 
         parse_quote! {
             fn type_check(
@@ -235,35 +460,14 @@ impl<'sd> TypeCheckAssumeOrderGenerator<'sd> {
             ) -> ::std::result::Result<(), #macro_internal::TypeCheckError> {
                 let column_types_iter = || specs.iter().map(|spec| ::std::clone::Clone::clone(&spec.typ));
 
-                match specs {
-                    [#(#required_fields_idents),*] => {
-                        #(
-                            // Verify the name (unless `skip_name_checks' is specified)
-                            #name_verifications
-
-                            // Verify the type
-                            <#required_fields_deserializers as #macro_internal::DeserializeValue<#constraint_lifetime>>::type_check(&#required_fields_idents.typ)
-                                .map_err(|err| #macro_internal::mk_row_typck_err::<Self>(
-                                    column_types_iter(),
-                                    #macro_internal::DeserBuiltinRowTypeCheckErrorKind::ColumnTypeCheckFailed {
-                                        column_index: #numbers,
-                                        column_name: ::std::clone::Clone::clone(&#required_fields_idents.name),
-                                        err,
-                                    }
-                                ))?;
-                        )*
-                        ::std::result::Result::Ok(())
-                    },
-                    _ => ::std::result::Result::Err(
-                        #macro_internal::mk_row_typck_err::<Self>(
-                            column_types_iter(),
-                            #macro_internal::DeserBuiltinRowTypeCheckErrorKind::WrongColumnCount {
-                                rust_cols: #required_fields_count,
-                                cql_cols: specs.len(),
-                            }
-                        ),
-                    ),
+                #too_few_columns_check
+                if specs.len() > #column_fields_count {
+                    return #too_many_columns_err;
                 }
+
+                #(#field_verifications)*
+
+                ::std::result::Result::Ok(())
             }
         }
     }
@@ -274,8 +478,8 @@ struct DeserializeAssumeOrderGenerator<'sd>(&'sd StructDesc);
 impl<'sd> DeserializeAssumeOrderGenerator<'sd> {
     fn generate_finalize_field(&self, field_index: usize, field: &Field) -> syn::Expr {
         if field.skip {
-            // Skipped fields are initialized with Default::default()
-            return parse_quote!(::std::default::Default::default());
+            // Skipped fields are initialized with their default value
+            return field.default_value();
         }
 
         let macro_internal = self.0.struct_attrs().macro_internal_path();
@@ -293,11 +497,9 @@ impl<'sd> DeserializeAssumeOrderGenerator<'sd> {
             }
         });
 
-        parse_quote!(
+        let deserialize_col: syn::Expr = parse_quote!(
             {
-                let col = row.next()
-                    .expect("Typecheck should have prevented this scenario! Too few columns in the serialized data.")
-                    .map_err(#macro_internal::row_deser_error_replace_rust_name::<Self>)?;
+                let col = col.map_err(#macro_internal::row_deser_error_replace_rust_name::<Self>)?;
 
                 #name_check
 
@@ -305,12 +507,31 @@ impl<'sd> DeserializeAssumeOrderGenerator<'sd> {
                     .map_err(|err| #macro_internal::mk_row_deser_err::<Self>(
                         #macro_internal::BuiltinRowDeserializationErrorKind::ColumnDeserializationFailed {
                             column_index: #field_index,
-                            column_name: <_ as std::clone::Clone>::clone(&col.spec.name),
+                            column_name: <_ as ::std::clone::Clone>::clone(&col.spec.name),
                             err,
                         }
                     ))?
             }
-        )
+        );
+
+        if field.is_required() {
+            parse_quote!(
+                {
+                    let col = row.next()
+                        .expect("Typecheck should have prevented this scenario! Too few columns in the serialized data.");
+                    #deserialize_col
+                }
+            )
+        } else {
+            // The column may be missing at the end of the row
+            let default_value = field.default_value();
+            parse_quote!(
+                match row.next() {
+                    ::std::option::Option::Some(col) => #deserialize_col,
+                    ::std::option::Option::None => #default_value,
+                }
+            )
+        }
     }
 
     fn generate(&self) -> syn::ImplItemFn {
@@ -350,7 +571,7 @@ impl<'sd> TypeCheckUnorderedGenerator<'sd> {
     // We generate it even if the flag is not required in order to protect
     // from fields appearing more than once
     fn generate_visited_flag_decl(field: &Field) -> Option<syn::Stmt> {
-        (!field.skip).then(|| {
+        field.is_own_column().then(|| {
             let visited_flag = Self::visited_flag_variable(field);
             parse_quote! {
                 let mut #visited_flag = false;
@@ -360,7 +581,7 @@ impl<'sd> TypeCheckUnorderedGenerator<'sd> {
 
     // Generates code that, given variable `typ`, type-checks given field
     fn generate_type_check(&self, field: &Field) -> Option<syn::Block> {
-        (!field.skip).then(|| {
+        field.is_own_column().then(|| {
             let macro_internal = self.0.struct_attrs().macro_internal_path();
             let constraint_lifetime = self.0.constraint_lifetime();
             let visited_flag = Self::visited_flag_variable(field);
@@ -427,43 +648,71 @@ impl<'sd> TypeCheckUnorderedGenerator<'sd> {
         let append_name_blocks = fields.iter().flat_map(Self::generate_append_name);
         let nonskipped_field_names = fields
             .iter()
-            .filter(|f| !f.skip)
+            .filter(|f| f.is_own_column())
             .map(|f| f.cql_name_literal());
         let field_count_lit = fields.iter().filter(|f| f.is_required()).count();
 
+        // Columns not claimed by other fields are passed to the flattened
+        // field, which is type-checked against them at the end.
+        let flattened_field = fields.iter().find(|f| f.flatten);
+        let flattened_specs_decl: Option<syn::Stmt> = flattened_field.map(|_| {
+            parse_quote! {
+                let mut flattened_specs = ::std::vec::Vec::<#macro_internal::ColumnSpec>::new();
+            }
+        });
+        let unknown_column_action: syn::Expr = match flattened_field {
+            Some(_) => parse_quote! {
+                flattened_specs.push(<_ as ::std::clone::Clone>::clone(spec))
+            },
+            None => parse_quote! {
+                return ::std::result::Result::Err(
+                    #macro_internal::mk_row_typck_err::<Self>(
+                        column_types_iter(),
+                        #macro_internal::DeserBuiltinRowTypeCheckErrorKind::ColumnWithUnknownName {
+                            column_index,
+                            column_name: <_ as ::std::clone::Clone>::clone(&spec.name)
+                        }
+                    )
+                )
+            },
+        };
+        let flattened_type_check: Option<syn::Stmt> = flattened_field.map(|field| {
+            let constraint_lifetime = self.0.constraint_lifetime();
+            let typ = field.deserialize_target();
+            parse_quote! {
+                <#typ as #macro_internal::DeserializeRow<#constraint_lifetime>>::type_check(&flattened_specs)?;
+            }
+        });
+
         parse_quote! {
             fn type_check(
                 specs: &[#macro_internal::ColumnSpec],
             ) -> ::std::result::Result<(), #macro_internal::TypeCheckError> {
                 // Counts down how many required fields are remaining
+                #[allow(unused_mut)]
                 let mut remaining_required_fields: ::std::primitive::usize = #field_count_lit;
 
+                #flattened_specs_decl
+
                 // For each required field, generate a "visited" boolean flag
                 #(#visited_field_declarations)*
 
                 let column_types_iter = || specs.iter().map(|spec| ::std::clone::Clone::clone(&spec.typ));
 
+                // `column_index` is unused if all columns go to the flattened field
+                #[allow(unused_variables)]
                 for (column_index, spec) in specs.iter().enumerate() {
                     // Pattern match on the name and verify that the type is correct.
                     match spec.name.as_str() {
                         #(#nonskipped_field_names => #type_check_blocks,)*
-                        _unknown => {
-                            return ::std::result::Result::Err(
-                                #macro_internal::mk_row_typck_err::<Self>(
-                                    column_types_iter(),
-                                    #macro_internal::DeserBuiltinRowTypeCheckErrorKind::ColumnWithUnknownName {
-                                        column_index,
-                                        column_name: <_ as ::std::clone::Clone>::clone(&spec.name)
-                                    }
-                                )
-                            )
-                        }
+                        _unknown => #unknown_column_action,
                     }
                 }
 
                 if remaining_required_fields > 0 {
                     // If there are some missing required fields, generate an error
                     // which contains missing field names
+                    #[allow(unused_mut)]
                     let mut missing_fields = ::std::vec::Vec::<&'static str>::with_capacity(remaining_required_fields);
                     #(#append_name_blocks)*
                     return ::std::result::Result::Err(
@@ -476,6 +725,8 @@ impl<'sd> TypeCheckUnorderedGenerator<'sd> {
                     )
                 }
 
+                #flattened_type_check
+
                 ::std::result::Result::Ok(())
             }
         }
@@ -495,13 +746,28 @@ impl<'sd> DeserializeUnorderedGenerator<'sd> {
     // of the target structure
     fn generate_finalize_field(&self, field: &Field) -> syn::Expr {
         if field.skip {
-            // Skipped fields are initialized with Default::default()
+            return field.default_value();
+        }
+
+        if field.flatten {
+            let macro_internal = self.0.struct_attrs().macro_internal_path();
+            let constraint_lifetime = self.0.constraint_lifetime();
+            let deserializer = field.deserialize_target();
             return parse_quote! {
-                ::std::default::Default::default()
+                <#deserializer as #macro_internal::DeserializeRow<#constraint_lifetime>>::deserialize(
+                    #macro_internal::ColumnIterator::from_raw_columns(flattened_columns)
+                )?
             };
         }
 
         let deserialize_field = Self::deserialize_field_variable(field);
+        if !field.is_required() {
+            let default_value = field.default_value();
+            return parse_quote! {
+                #deserialize_field.unwrap_or_else(|| #default_value)
+            };
+        }
+
         let cql_name_literal = field.cql_name_literal();
         parse_quote! {
             #deserialize_field.unwrap_or_else(|| panic!(
@@ -514,7 +780,7 @@ impl<'sd> DeserializeUnorderedGenerator<'sd> {
     // Generated code that performs deserialization when the raw field
     // is being processed
     fn generate_deserialization(&self, column_index: usize, field: &Field) -> syn::Expr {
-        assert!(!field.skip);
+        assert!(field.is_own_column());
         let macro_internal = self.0.struct_attrs().macro_internal_path();
         let constraint_lifetime = self.0.constraint_lifetime();
         let deserialize_field = Self::deserialize_field_variable(field);
@@ -534,7 +800,7 @@ impl<'sd> DeserializeUnorderedGenerator<'sd> {
                             #macro_internal::mk_row_deser_err::<Self>(
                                 #macro_internal::BuiltinRowDeserializationErrorKind::ColumnDeserializationFailed {
                                     column_index: #column_index,
-                                    column_name: <_ as std::clone::Clone>::clone(&col.spec.name),
+                                    column_name: <_ as ::std::clone::Clone>::clone(&col.spec.name),
                                     err,
                                 }
                             )
@@ -547,7 +813,7 @@ impl<'sd> DeserializeUnorderedGenerator<'sd> {
     // Generate a declaration of a variable that temporarily keeps
     // the deserialized value
     fn generate_deserialize_field_decl(field: &Field) -> Option<syn::Stmt> {
-        (!field.skip).then(|| {
+        field.is_own_column().then(|| {
             let deserialize_field = Self::deserialize_field_variable(field);
             parse_quote! {
                 let mut #deserialize_field = ::std::option::Option::None;
@@ -565,18 +831,34 @@ impl<'sd> DeserializeUnorderedGenerator<'sd> {
             .flat_map(Self::generate_deserialize_field_decl);
         let deserialize_blocks = fields
             .iter()
-            .filter(|f| !f.skip)
+            .filter(|f| f.is_own_column())
             .enumerate()
             .map(|(col_idx, f)| self.generate_deserialization(col_idx, f));
         let field_idents = fields.iter().map(|f| f.ident.as_ref().unwrap());
         let nonskipped_field_names = fields
             .iter()
-            .filter(|&f| (!f.skip))
+            .filter(|f| f.is_own_column())
             .map(|f| f.cql_name_literal());
 
         let field_finalizers = fields.iter().map(|f| self.generate_finalize_field(f));
 
-        // TODO: Allow collecting unrecognized fields into some special field
+        // Columns not claimed by other fields are collected and passed
+        // to the flattened field, if there is one.
+        let has_flattened_field = fields.iter().any(|f| f.flatten);
+        let flattened_columns_decl: Option<syn::Stmt> = has_flattened_field.then(|| {
+            parse_quote! {
+                let mut flattened_columns = ::std::vec::Vec::new();
+            }
+        });
+        let unknown_column_arm: syn::Arm = if has_flattened_field {
+            parse_quote! {
+                _ => flattened_columns.push(col),
+            }
+        } else {
+            parse_quote! {
+                unknown => unreachable!("Typecheck should have prevented this scenario! Unknown column name: {}", unknown),
+            }
+        };
 
         parse_quote! {
             fn deserialize(
@@ -587,13 +869,14 @@ impl<'sd> DeserializeUnorderedGenerator<'sd> {
                 // Generate fields that will serve as temporary storage
                 // for the fields' values. Those are of type Option<FieldType>.
                 #(#deserialize_field_decls)*
+                #flattened_columns_decl
 
                 for col in row {
                     let col = col.map_err(#macro_internal::row_deser_error_replace_rust_name::<Self>)?;
                     // Pattern match on the field name and deserialize.
                     match col.spec.name.as_str() {
                         #(#nonskipped_field_names => #deserialize_blocks,)*
-                        unknown => unreachable!("Typecheck should have prevented this scenario! Unknown column name: {}", unknown),
+                        #unknown_column_arm
                     }
                 }
 
@@ -602,7 +885,7 @@ impl<'sd> DeserializeUnorderedGenerator<'sd> {
                 // For example, if a field is missing but marked as
                 // `default_when_null` it will create a default value, otherwise
                 // it will report an error.
-                Ok(Self {
+                ::std::result::Result::Ok(Self {
                     #(#field_idents: #field_finalizers,)*
                 })
             }
@@ -6,6 +6,7 @@ use proc_macro2::Span;
 use syn::{ext::IdentExt, parse_quote};
 
 use super::{DeserializeCommonFieldAttrs, DeserializeCommonStructAttrs};
+use crate::schema::{generate_compatible_impls, CheckedField, Compatibility, Target};
 
 #[derive(FromAttributes)]
 #[darling(attributes(scylla))]
@@ -33,6 +34,25 @@ struct StructAttrs {
     // they will be ignored. With true, an error will be raised.
     #[darling(default)]
     forbid_excess_udt_fields: bool,
+
+    // If true, then the struct must have exactly one field and it is
+    // deserialized exactly like that field.
+    #[darling(default)]
+    transparent: bool,
+
+    // If true, then all fields are initialized with their default value
+    // when missing from the UDT fields metadata, as if they were annotated
+    // with `allow_missing`.
+    #[darling(default)]
+    allow_missing: bool,
+
+    // If set, the fields are checked at compile time against the fields
+    // of the UDT given with the `udt` attribute, as defined in this file.
+    #[darling(default)]
+    schema_file: Option<syn::LitStr>,
+
+    #[darling(default)]
+    udt: Option<String>,
 }
 
 impl DeserializeCommonStructAttrs for StructAttrs {
@@ -52,9 +72,17 @@ struct Field {
     // If true, then - if this field is missing from the UDT fields metadata
     // - it will be initialized to Default::default().
     #[darling(default)]
-    #[darling(rename = "allow_missing")]
+    allow_missing: bool,
+
+    // An alias of `allow_missing`, for consistency with DeserializeRow.
+    #[darling(default)]
     default_when_missing: bool,
 
+    // If set, then the field is initialized by calling this function instead
+    // of Default::default(). Implies `allow_missing`.
+    #[darling(default)]
+    default: Option<syn::Path>,
+
     // If true, then - if this field is present among UDT fields metadata
     // but at the same time missing from serialized data or set to null
     // - it will be initialized to Default::default().
@@ -66,13 +94,17 @@ struct Field {
     #[darling(default)]
     rename: Option<String>,
 
+    // If true, the field is not checked against the schema file.
+    #[darling(default)]
+    skip_schema_check: bool,
+
     ident: Option<syn::Ident>,
     ty: syn::Type,
 }
 
 impl DeserializeCommonFieldAttrs for Field {
     fn needs_default(&self) -> bool {
-        self.skip || self.default_when_missing
+        (self.skip || self.default_when_missing) && self.default.is_none()
     }
 
     fn deserialize_target(&self) -> &syn::Type {
@@ -83,8 +115,46 @@ impl DeserializeCommonFieldAttrs for Field {
 // derive(DeserializeValue) for the DeserializeValue trait
 pub(crate) fn deserialize_value_derive(
     tokens_input: TokenStream,
-) -> Result<syn::ItemImpl, syn::Error> {
-    let input = syn::parse(tokens_input)?;
+) -> Result<proc_macro2::TokenStream, syn::Error> {
+    let input: syn::DeriveInput = syn::parse(tokens_input)?;
+
+    let attrs = StructAttrs::from_attributes(&input.attrs)?;
+    let macro_internal = attrs.macro_internal_path();
+    let compatible_trait: syn::Ident = parse_quote!(DeserializeCompatible);
+    let non_udt_impls = match &input.data {
+        syn::Data::Enum(data) if !attrs.transparent => {
+            let fieldless_enum =
+                crate::parser::parse_fieldless_enum(&input, data, "DeserializeValue")?;
+            let (int_types, _) = fieldless_enum.int_types();
+            let cql_types = fieldless_enum.text_types().into_iter().chain(int_types);
+            Some((
+                generate_enum_impl(&input, &attrs, &fieldless_enum),
+                Compatibility::Types(cql_types.collect()),
+            ))
+        }
+        _ if attrs.transparent => {
+            let (member, ty) = crate::parser::parse_transparent_field(&input, "DeserializeValue")?;
+            Some((
+                generate_transparent_impl(&input, &attrs, member, ty),
+                Compatibility::Transparent(ty),
+            ))
+        }
+        _ => None,
+    };
+    if let Some((deserialize_impl, compatibility)) = non_udt_impls {
+        if let Some(schema_file) = &attrs.schema_file {
+            return Err(syn::Error::new(
+                schema_file.span(),
+                "the `schema_file` attribute is only allowed on structs mapped to UDTs",
+            ));
+        }
+        let compatible_impls =
+            generate_compatible_impls(&input, &macro_internal, &compatible_trait, compatibility);
+        return Ok(quote::quote! {
+            #deserialize_impl
+            #compatible_impls
+        });
+    }
 
     let implemented_trait: syn::Path = parse_quote!(DeserializeValue);
     let implemented_trait_name = implemented_trait
@@ -95,7 +165,13 @@ pub(crate) fn deserialize_value_derive(
         .unraw()
         .to_string();
     let constraining_trait = implemented_trait.clone();
-    let s = StructDesc::new(&input, &implemented_trait_name, constraining_trait)?;
+    let mut s = StructDesc::new(&input, &implemented_trait_name, constraining_trait)?;
+
+    let allow_missing = s.attrs.allow_missing;
+    for field in s.fields.iter_mut() {
+        field.default_when_missing |=
+            allow_missing || field.allow_missing || field.default.is_some();
+    }
 
     validate_attrs(&s.attrs, s.fields())?;
 
@@ -104,7 +180,168 @@ pub(crate) fn deserialize_value_derive(
         s.generate_deserialize_method().into(),
     ];
 
-    Ok(s.generate_impl(implemented_trait, items))
+    let deserialize_impl = s.generate_impl(implemented_trait, items);
+    let compatible_impls = generate_compatible_impls(
+        &input,
+        &macro_internal,
+        &compatible_trait,
+        Compatibility::Types(vec![parse_quote!(UserDefinedType)]),
+    );
+    let schema_check = s.generate_schema_check()?;
+
+    Ok(quote::quote! {
+        #deserialize_impl
+        #compatible_impls
+        #schema_check
+    })
+}
+
+// Generates an impl of DeserializeValue for types which are not structs
+// mapped to UDTs, i.e. fieldless enums and transparent newtypes.
+fn generate_non_udt_impl(
+    input: &syn::DeriveInput,
+    attrs: &StructAttrs,
+    type_check: syn::ImplItemFn,
+    deserialize: syn::ImplItemFn,
+) -> syn::ItemImpl {
+    let macro_internal = attrs.macro_internal_path();
+    let constraint_lifetime = super::generate_unique_lifetime_for_impl(&input.generics);
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+    let impl_generics = &input.generics.params;
+    let name = &input.ident;
+    let predicates = super::generate_lifetime_constraints_for_impl(
+        &input.generics,
+        parse_quote!(#macro_internal::DeserializeValue),
+        &constraint_lifetime,
+    );
+
+    parse_quote! {
+        impl<#constraint_lifetime, #impl_generics> #macro_internal::DeserializeValue<#constraint_lifetime> for #name #ty_generics
+        where #(#predicates),*
+        {
+            #type_check
+            #deserialize
+        }
+    }
+}
+
+fn generate_transparent_impl(
+    input: &syn::DeriveInput,
+    attrs: &StructAttrs,
+    member: syn::Member,
+    ty: &syn::Type,
+) -> syn::ItemImpl {
+    let macro_internal = attrs.macro_internal_path();
+    let constraint_lifetime = super::generate_unique_lifetime_for_impl(&input.generics);
+
+    let type_check = parse_quote! {
+        fn type_check(
+            typ: &#macro_internal::ColumnType,
+        ) -> ::std::result::Result<(), #macro_internal::TypeCheckError> {
+            <#ty as #macro_internal::DeserializeValue<#constraint_lifetime>>::type_check(typ)
+        }
+    };
+    let deserialize = parse_quote! {
+        fn deserialize(
+            typ: &#constraint_lifetime #macro_internal::ColumnType,
+            v: ::std::option::Option<#macro_internal::FrameSlice<#constraint_lifetime>>,
+        ) -> ::std::result::Result<Self, #macro_internal::DeserializationError> {
+            <#ty as #macro_internal::DeserializeValue<#constraint_lifetime>>::deserialize(typ, v)
+                .map(|inner| Self { #member: inner })
+        }
+    };
+
+    generate_non_udt_impl(input, attrs, type_check, deserialize)
+}
+
+// Fieldless enums are read from text columns by variant name and,
+// if all variants have explicit discriminants, from integer columns
+// by discriminant.
+fn generate_enum_impl(
+    input: &syn::DeriveInput,
+    attrs: &StructAttrs,
+    fieldless_enum: &crate::parser::FieldlessEnum,
+) -> syn::ItemImpl {
+    let macro_internal = attrs.macro_internal_path();
+    let constraint_lifetime = super::generate_unique_lifetime_for_impl(&input.generics);
+
+    let variant_idents = fieldless_enum
+        .variants
+        .iter()
+        .map(|v| v.ident)
+        .collect::<Vec<_>>();
+    let variant_names = fieldless_enum.variants.iter().map(|v| &v.cql_name);
+
+    let text_types = fieldless_enum.text_types();
+    let (int_types, int_rust_types) = fieldless_enum.int_types();
+    let supported_types = text_types
+        .iter()
+        .chain(int_types.iter())
+        .collect::<Vec<_>>();
+
+    let discriminant_match: syn::Expr = parse_quote! {
+        match discriminant {
+            #(discriminant if discriminant == Self::#variant_idents as i64 => {
+                ::std::result::Result::Ok(Self::#variant_idents)
+            })*
+            _ => ::std::result::Result::Err(#macro_internal::mk_value_deser_err::<Self>(
+                typ,
+                #macro_internal::BuiltinTypeDeserializationErrorKind::UnknownEnumDiscriminant(discriminant),
+            )),
+        }
+    };
+
+    let type_check = parse_quote! {
+        fn type_check(
+            typ: &#macro_internal::ColumnType,
+        ) -> ::std::result::Result<(), #macro_internal::TypeCheckError> {
+            match typ {
+                #(#macro_internal::ColumnType::#supported_types)|* => ::std::result::Result::Ok(()),
+                _ => ::std::result::Result::Err(#macro_internal::mk_value_typck_err::<Self>(
+                    typ,
+                    #macro_internal::DeserBuiltinTypeTypeCheckErrorKind::MismatchedType {
+                        expected: &[#(#macro_internal::ColumnType::#supported_types),*],
+                    },
+                )),
+            }
+        }
+    };
+    let deserialize = parse_quote! {
+        fn deserialize(
+            typ: &#constraint_lifetime #macro_internal::ColumnType,
+            v: ::std::option::Option<#macro_internal::FrameSlice<#constraint_lifetime>>,
+        ) -> ::std::result::Result<Self, #macro_internal::DeserializationError> {
+            match typ {
+                #(#macro_internal::ColumnType::#text_types)|* => {
+                    let name = <&str as #macro_internal::DeserializeValue<#constraint_lifetime>>::deserialize(typ, v)
+                        .map_err(#macro_internal::value_deser_error_replace_rust_name::<Self>)?;
+                    match name {
+                        #(#variant_names => ::std::result::Result::Ok(Self::#variant_idents),)*
+                        _ => ::std::result::Result::Err(#macro_internal::mk_value_deser_err::<Self>(
+                            typ,
+                            #macro_internal::BuiltinTypeDeserializationErrorKind::UnknownEnumVariantName(
+                                <_ as ::std::borrow::ToOwned>::to_owned(name),
+                            ),
+                        )),
+                    }
+                }
+                #(
+                    #macro_internal::ColumnType::#int_types => {
+                        let discriminant = <#int_rust_types as #macro_internal::DeserializeValue<#constraint_lifetime>>::deserialize(typ, v)
+                            .map_err(#macro_internal::value_deser_error_replace_rust_name::<Self>)?;
+                        let discriminant = <i64 as ::std::convert::From<#int_rust_types>>::from(discriminant);
+                        #discriminant_match
+                    }
+                )*
+                _ => ::std::result::Result::Err(#macro_internal::mk_value_deser_err::<Self>(
+                    typ,
+                    #macro_internal::BuiltinTypeDeserializationErrorKind::MismatchedEnumType,
+                )),
+            }
+        }
+    };
+
+    generate_non_udt_impl(input, attrs, type_check, deserialize)
 }
 
 fn validate_attrs(attrs: &StructAttrs, fields: &[Field]) -> Result<(), darling::Error> {
@@ -148,7 +385,24 @@ fn validate_attrs(attrs: &StructAttrs, fields: &[Field]) -> Result<(), darling::
                 errors.push(err);
             }
         }
-    } else {
+    }
+
+    match (&attrs.schema_file, &attrs.udt) {
+        (Some(_), None) => errors.push(darling::Error::custom(
+            "attribute <schema_file> requires <udt>.",
+        )),
+        (None, Some(_)) => errors.push(darling::Error::custom(
+            "attribute <udt> requires <schema_file>.",
+        )),
+        _ => {}
+    }
+    if attrs.schema_file.is_some() && attrs.skip_name_checks {
+        errors.push(darling::Error::custom(
+            "attribute <schema_file> can't be used with <skip_name_checks>, as the UDT fields are found by name.",
+        ));
+    }
+
+    if !attrs.skip_name_checks {
         // Detect name collisions caused by <rename>.
         let mut used_names = HashMap::<String, &Field>::new();
         for field in fields {
@@ -173,6 +427,11 @@ impl Field {
         !self.skip && !self.default_when_missing
     }
 
+    // An expression which creates the default value of this field
+    fn default_value(&self) -> syn::Expr {
+        super::default_value_expr(self.default.as_ref())
+    }
+
     // The name of UDT field corresponding to this Rust struct field
     fn udt_field_name(&self) -> String {
         match self.rename.as_ref() {
@@ -190,6 +449,32 @@ impl Field {
 type StructDesc = super::StructDescForDeserialize<StructAttrs, Field>;
 
 impl StructDesc {
+    fn generate_schema_check(&self) -> Result<Option<proc_macro2::TokenStream>, syn::Error> {
+        let (Some(schema_file), Some(udt)) = (&self.attrs.schema_file, &self.attrs.udt) else {
+            return Ok(None);
+        };
+        let fields = self
+            .fields
+            .iter()
+            .map(|f| CheckedField {
+                ident: f.ident.as_ref().unwrap(),
+                ty: &f.ty,
+                name: f.udt_field_name(),
+                may_be_missing: f.default_when_missing,
+                skip_check: f.skip || f.skip_schema_check,
+            })
+            .collect::<Vec<_>>();
+        crate::schema::generate_schema_check(
+            schema_file,
+            Target::Udt { name: udt },
+            &fields,
+            &self.generics,
+            &self.attrs.macro_internal_path(),
+            &parse_quote!(assert_deserialize_compatible),
+        )
+        .map(Some)
+    }
+
     /// Generates an expression which extracts the UDT fields or returns an error.
     fn generate_extract_fields_from_type(&self, typ_expr: syn::Expr) -> syn::Expr {
         let macro_internal = &self.struct_attrs().macro_internal_path();
@@ -404,10 +689,8 @@ struct DeserializeAssumeOrderGenerator<'sd>(&'sd StructDesc);
 impl<'sd> DeserializeAssumeOrderGenerator<'sd> {
     fn generate_finalize_field(&self, field: &Field) -> syn::Expr {
         if field.skip {
-            // Skipped fields are initialized with Default::default()
-            return parse_quote! {
-                ::std::default::Default::default()
-            };
+            // Skipped fields are initialized with their default value
+            return field.default_value();
         }
 
         let macro_internal = self.0.struct_attrs().macro_internal_path();
@@ -417,6 +700,7 @@ impl<'sd> DeserializeAssumeOrderGenerator<'sd> {
         let default_when_missing = field.default_when_missing;
         let default_when_null = field.default_when_null;
         let skip_name_checks = self.0.attrs.skip_name_checks;
+        let default_value = field.default_value();
 
         let deserialize: syn::Expr = parse_quote! {
             <#deserializer as #macro_internal::DeserializeValue<#constraint_lifetime>>::deserialize(cql_field_typ, value)
@@ -432,7 +716,7 @@ impl<'sd> DeserializeAssumeOrderGenerator<'sd> {
         let maybe_default_deserialize: syn::Expr = if default_when_null {
             parse_quote! {
                 if value.is_none() {
-                    ::std::default::Default::default()
+                    #default_value
                 } else {
                     #deserialize
                 }
@@ -452,7 +736,7 @@ impl<'sd> DeserializeAssumeOrderGenerator<'sd> {
                     // For that, store the read UDT field to be fit against the next Rust struct field.
                     saved_cql_field = ::std::option::Option::Some(next_cql_field);
 
-                    ::std::default::Default::default()
+                    #default_value
                 }
             }
         } else {
@@ -483,7 +767,7 @@ impl<'sd> DeserializeAssumeOrderGenerator<'sd> {
 
         let no_more_fields: syn::Expr = if default_when_missing {
             parse_quote! {
-                ::std::default::Default::default()
+                #default_value
             }
         } else {
             parse_quote! {
@@ -744,15 +1028,16 @@ impl<'sd> DeserializeUnorderedGenerator<'sd> {
     /// of the target structure.
     fn generate_finalize_field(&self, field: &Field) -> syn::Expr {
         if field.skip {
-            // Skipped fields are initialized with Default::default()
-            return parse_quote!(::std::default::Default::default());
+            // Skipped fields are initialized with their default value
+            return field.default_value();
         }
 
         let deserialize_field = Self::deserialize_field_variable(field);
         if field.default_when_missing {
-            // Generate Default::default if the field was missing
+            // Generate the default value if the field was missing
+            let default_value = field.default_value();
             parse_quote! {
-                #deserialize_field.unwrap_or_default()
+                #deserialize_field.unwrap_or_else(|| #default_value)
             }
         } else {
             let cql_name_literal = field.cql_name_literal();
@@ -774,6 +1059,7 @@ impl<'sd> DeserializeUnorderedGenerator<'sd> {
             let deserialize_field = Self::deserialize_field_variable(field);
             let cql_name_literal = field.cql_name_literal();
             let deserializer = field.deserialize_target();
+            let default_value = field.default_value();
 
             let do_deserialize: syn::Expr = parse_quote! {
                 <#deserializer as #macro_internal::DeserializeValue<#constraint_lifetime>>::deserialize(cql_field_typ, value)
@@ -791,7 +1077,7 @@ impl<'sd> DeserializeUnorderedGenerator<'sd> {
                     if value.is_some() {
                         #do_deserialize
                     } else {
-                        ::std::default::Default::default()
+                        #default_value
                     }
                 }
             } else {
@@ -5,6 +5,8 @@ mod from_row;
 mod from_user_type;
 mod into_user_type;
 mod parser;
+mod schema;
+mod table;
 mod value_list;
 
 mod serialize;
@@ -84,3 +86,14 @@ pub fn deserialize_value_derive(tokens_input: TokenStream) -> TokenStream {
         Err(err) => err.into_compile_error().into(),
     }
 }
+
+/// Documentation for this macro can only be found
+/// in `scylla` crate - not in scylla-macros nor in scylla-cql.
+/// This is because of rustdocs limitations that are hard to explain here.
+#[proc_macro_derive(Table, attributes(scylla))]
+pub fn table_derive(tokens_input: TokenStream) -> TokenStream {
+    match table::derive_table(tokens_input) {
+        Ok(t) => t.into_token_stream().into(),
+        Err(e) => e.into_compile_error().into(),
+    }
+}
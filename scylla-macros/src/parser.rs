@@ -1,4 +1,10 @@
-use syn::{Data, DeriveInput, Expr, ExprLit, Fields, FieldsNamed, FieldsUnnamed, Lit, Meta};
+use std::collections::HashMap;
+
+use darling::FromAttributes;
+use syn::ext::IdentExt;
+use syn::{
+    Data, DataEnum, DeriveInput, Expr, ExprLit, Fields, FieldsNamed, FieldsUnnamed, Lit, Meta,
+};
 
 /// Parses a struct DeriveInput and returns named fields of this struct.
 pub(crate) fn parse_named_fields<'a>(
@@ -50,6 +56,161 @@ pub(crate) fn parse_struct_fields<'a>(
     }
 }
 
+/// A variant of a fieldless enum handled by the value derive macros.
+pub(crate) struct UnitVariant<'a> {
+    pub(crate) ident: &'a syn::Ident,
+
+    /// The name under which the variant is stored in text columns.
+    pub(crate) cql_name: String,
+}
+
+pub(crate) struct FieldlessEnum<'a> {
+    pub(crate) variants: Vec<UnitVariant<'a>>,
+
+    /// True if all variants have explicit discriminants,
+    /// which allows storing the enum in integer columns.
+    pub(crate) has_discriminants: bool,
+}
+
+impl FieldlessEnum<'_> {
+    /// True if all variant names can be stored in an `ascii` column.
+    pub(crate) fn is_ascii(&self) -> bool {
+        self.variants.iter().all(|v| v.cql_name.is_ascii())
+    }
+
+    /// The text column types which the enum can be stored in, by variant name.
+    pub(crate) fn text_types(&self) -> Vec<syn::Ident> {
+        if self.is_ascii() {
+            vec![syn::parse_quote!(Ascii), syn::parse_quote!(Text)]
+        } else {
+            vec![syn::parse_quote!(Text)]
+        }
+    }
+
+    /// The integer column types which the enum can be stored in, by discriminant,
+    /// together with the corresponding Rust types.
+    pub(crate) fn int_types(&self) -> (Vec<syn::Ident>, Vec<syn::Type>) {
+        if self.has_discriminants {
+            vec![
+                (syn::parse_quote!(TinyInt), syn::parse_quote!(i8)),
+                (syn::parse_quote!(SmallInt), syn::parse_quote!(i16)),
+                (syn::parse_quote!(Int), syn::parse_quote!(i32)),
+                (syn::parse_quote!(BigInt), syn::parse_quote!(i64)),
+            ]
+            .into_iter()
+            .unzip()
+        } else {
+            (Vec::new(), Vec::new())
+        }
+    }
+}
+
+#[derive(FromAttributes)]
+#[darling(attributes(scylla))]
+struct VariantAttributes {
+    rename: Option<String>,
+}
+
+/// Parses an enum DeriveInput, checking that none of its variants have fields.
+pub(crate) fn parse_fieldless_enum<'a>(
+    input: &'a DeriveInput,
+    data: &'a DataEnum,
+    current_derive: &str,
+) -> Result<FieldlessEnum<'a>, syn::Error> {
+    if data.variants.is_empty() {
+        return Err(syn::Error::new_spanned(
+            data.enum_token,
+            format!(
+                "derive({}) does not support enums without variants",
+                current_derive
+            ),
+        ));
+    }
+
+    let mut errors = darling::Error::accumulator();
+    let mut variants = Vec::with_capacity(data.variants.len());
+    let mut used_names = HashMap::<String, &syn::Ident>::new();
+    for variant in data.variants.iter() {
+        if !matches!(variant.fields, Fields::Unit) {
+            let msg = format!(
+                "derive({}) works only for enums whose variants have no fields",
+                current_derive
+            );
+            errors.push(darling::Error::custom(msg).with_span(&variant.ident));
+            continue;
+        }
+        let Some(attrs) = errors.handle(VariantAttributes::from_attributes(&variant.attrs)) else {
+            continue;
+        };
+        let cql_name = attrs
+            .rename
+            .unwrap_or_else(|| variant.ident.unraw().to_string());
+        if let Some(other_variant) = used_names.get(&cql_name) {
+            let msg = format!(
+                "the name `{cql_name}` used by this variant is already used by variant `{other_variant}`"
+            );
+            errors.push(darling::Error::custom(msg).with_span(&variant.ident));
+        } else {
+            used_names.insert(cql_name.clone(), &variant.ident);
+        }
+        variants.push(UnitVariant {
+            ident: &variant.ident,
+            cql_name,
+        });
+    }
+
+    let discriminant_count = data
+        .variants
+        .iter()
+        .filter(|v| v.discriminant.is_some())
+        .count();
+    if discriminant_count != 0 && discriminant_count != data.variants.len() {
+        errors.push(
+            darling::Error::custom(
+                "either all variants or none of them must have explicit discriminants",
+            )
+            .with_span(&input.ident),
+        );
+    }
+
+    errors.finish()?;
+    Ok(FieldlessEnum {
+        variants,
+        has_discriminants: discriminant_count != 0,
+    })
+}
+
+/// Parses a struct DeriveInput marked as `transparent` and returns its only field,
+/// together with the expression member used to access it.
+pub(crate) fn parse_transparent_field<'a>(
+    input: &'a DeriveInput,
+    current_derive: &str,
+) -> Result<(syn::Member, &'a syn::Type), syn::Error> {
+    let create_err_msg = || {
+        format!(
+            "derive({}) with the `transparent` attribute works only for structs with exactly one field",
+            current_derive
+        )
+    };
+
+    let data = match &input.data {
+        Data::Struct(data) => data,
+        Data::Enum(e) => return Err(syn::Error::new_spanned(e.enum_token, create_err_msg())),
+        Data::Union(u) => return Err(syn::Error::new_spanned(u.union_token, create_err_msg())),
+    };
+    let mut fields = data.fields.iter();
+    match (fields.next(), fields.next()) {
+        (Some(field), None) => {
+            let member = match &field.ident {
+                Some(ident) => syn::Member::Named(ident.clone()),
+                None => syn::Member::Unnamed(0.into()),
+            };
+            Ok((member, &field.ty))
+        }
+        _ => Err(syn::Error::new_spanned(data.struct_token, create_err_msg())),
+    }
+}
+
 pub(crate) fn get_path(input: &DeriveInput) -> Result<syn::Path, syn::Error> {
     let mut this_path: Option<syn::Path> = None;
     for attr in input.attrs.iter() {
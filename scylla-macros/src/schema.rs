@@ -0,0 +1,734 @@
+//! Checks of derived structs against a CQL schema file.
+//!
+//! The schema file is a list of CQL statements, like the ones used to create
+//! the schema in the database. Only the statements which define tables and
+//! user defined types are interpreted, others are ignored.
+
+use std::path::PathBuf;
+
+use proc_macro2::TokenStream;
+use quote::quote_spanned;
+use syn::parse_quote;
+use syn::spanned::Spanned;
+
+/// A column of a table or a field of a user defined type.
+struct Column {
+    name: String,
+    typ: CqlType,
+}
+
+/// A table or a user defined type.
+struct Relation {
+    keyspace: Option<String>,
+    name: String,
+    columns: Vec<Column>,
+}
+
+enum CqlType {
+    /// A native type, with the name of its marker type.
+    Native(&'static str),
+    List(Box<CqlType>),
+    Set(Box<CqlType>),
+    Map(Box<CqlType>, Box<CqlType>),
+    Tuple(Vec<CqlType>),
+    Vector(Box<CqlType>),
+    UserDefined,
+    /// A custom type, given by its class name. It is not checked.
+    Custom,
+}
+
+impl CqlType {
+    /// Returns the marker type which represents the CQL type,
+    /// or None if the type can't be checked at compile time.
+    fn marker(&self, macro_internal: &syn::Path) -> Option<syn::Type> {
+        let cql: syn::Path = parse_quote!(#macro_internal::cql);
+        Some(match self {
+            CqlType::Native(name) => {
+                let name = syn::Ident::new(name, proc_macro2::Span::call_site());
+                parse_quote!(#cql::#name)
+            }
+            CqlType::List(elem) => {
+                let elem = elem.marker(macro_internal)?;
+                parse_quote!(#cql::List<#elem>)
+            }
+            CqlType::Set(elem) => {
+                let elem = elem.marker(macro_internal)?;
+                parse_quote!(#cql::Set<#elem>)
+            }
+            CqlType::Map(key, value) => {
+                let key = key.marker(macro_internal)?;
+                let value = value.marker(macro_internal)?;
+                parse_quote!(#cql::Map<#key, #value>)
+            }
+            CqlType::Tuple(elems) => {
+                let elems = elems
+                    .iter()
+                    .map(|e| e.marker(macro_internal))
+                    .collect::<Option<Vec<_>>>()?;
+                parse_quote!(#cql::Tuple<(#(#elems,)*)>)
+            }
+            CqlType::Vector(elem) => {
+                let elem = elem.marker(macro_internal)?;
+                parse_quote!(#cql::Vector<#elem>)
+            }
+            CqlType::UserDefined => parse_quote!(#cql::UserDefinedType),
+            CqlType::Custom => return None,
+        })
+    }
+}
+
+fn native_type(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "ascii" => "Ascii",
+        "bigint" => "BigInt",
+        "blob" => "Blob",
+        "boolean" => "Boolean",
+        "counter" => "Counter",
+        "date" => "Date",
+        "decimal" => "Decimal",
+        "double" => "Double",
+        "duration" => "Duration",
+        "float" => "Float",
+        "inet" => "Inet",
+        "int" => "Int",
+        "smallint" => "SmallInt",
+        "text" | "varchar" => "Text",
+        "time" => "Time",
+        "timestamp" => "Timestamp",
+        "timeuuid" => "Timeuuid",
+        "tinyint" => "TinyInt",
+        "uuid" => "Uuid",
+        "varint" => "Varint",
+        _ => return None,
+    })
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum Token {
+    /// An unquoted identifier or keyword, lowercased as CQL is case-insensitive.
+    Ident(String),
+    /// A double-quoted identifier, case-sensitive.
+    QuotedIdent(String),
+    /// A single-quoted string literal.
+    Str(String),
+    Number(String),
+    Symbol(char),
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '-' if chars.peek() == Some(&'-') => {
+                chars.by_ref().take_while(|c| *c != '\n').for_each(drop);
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                chars.by_ref().take_while(|c| *c != '\n').for_each(drop);
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = ' ';
+                loop {
+                    match chars.next() {
+                        Some('/') if prev == '*' => break,
+                        Some(c) => prev = c,
+                        None => return Err("unterminated comment".to_owned()),
+                    }
+                }
+            }
+            '"' | '\'' => {
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        // A doubled quote stands for the quote character itself.
+                        Some(q) if q == c && chars.peek() == Some(&c) => {
+                            chars.next();
+                            value.push(c);
+                        }
+                        Some(q) if q == c => break,
+                        Some(other) => value.push(other),
+                        None => return Err(format!("unterminated {c}-quoted string")),
+                    }
+                }
+                tokens.push(if c == '"' {
+                    Token::QuotedIdent(value)
+                } else {
+                    Token::Str(value)
+                });
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut ident = c.to_lowercase().collect::<String>();
+                while let Some(&c) = chars.peek() {
+                    if !(c.is_alphanumeric() || c == '_') {
+                        break;
+                    }
+                    ident.extend(c.to_lowercase());
+                    chars.next();
+                }
+                tokens.push(Token::Ident(ident));
+            }
+            c if c.is_ascii_digit() => {
+                let mut number = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if !(c.is_ascii_alphanumeric() || c == '.') {
+                        break;
+                    }
+                    number.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Number(number));
+            }
+            c => tokens.push(Token::Symbol(c)),
+        }
+    }
+    Ok(tokens)
+}
+
+/// Parses a single statement, without the terminating semicolon.
+struct StatementParser<'t> {
+    tokens: &'t [Token],
+    pos: usize,
+}
+
+impl StatementParser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Result<&Token, String> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .ok_or_else(|| "unexpected end of statement".to_owned())?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = matches!(self.peek(), Some(Token::Ident(ident)) if ident == keyword);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), String> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            Err(format!("expected `{}`", keyword.to_uppercase()))
+        }
+    }
+
+    fn eat_symbol(&mut self, symbol: char) -> bool {
+        let found = self.peek() == Some(&Token::Symbol(symbol));
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect_symbol(&mut self, symbol: char) -> Result<(), String> {
+        if self.eat_symbol(symbol) {
+            Ok(())
+        } else {
+            Err(format!("expected `{symbol}`"))
+        }
+    }
+
+    fn eat_if_exists(&mut self, not: bool) -> Result<(), String> {
+        if self.eat_keyword("if") {
+            if not {
+                self.expect_keyword("not")?;
+            }
+            self.expect_keyword("exists")?;
+        }
+        Ok(())
+    }
+
+    fn name(&mut self) -> Result<String, String> {
+        match self.next()? {
+            Token::Ident(name) | Token::QuotedIdent(name) => Ok(name.clone()),
+            other => Err(format!("expected a name, found {other:?}")),
+        }
+    }
+
+    fn qualified_name(&mut self) -> Result<(Option<String>, String), String> {
+        let name = self.name()?;
+        if self.eat_symbol('.') {
+            Ok((Some(name), self.name()?))
+        } else {
+            Ok((None, name))
+        }
+    }
+
+    fn type_args(&mut self, count: usize) -> Result<Vec<CqlType>, String> {
+        self.expect_symbol('<')?;
+        let mut args = vec![self.cql_type()?];
+        while args.len() < count {
+            self.expect_symbol(',')?;
+            args.push(self.cql_type()?);
+        }
+        self.expect_symbol('>')?;
+        Ok(args)
+    }
+
+    fn cql_type(&mut self) -> Result<CqlType, String> {
+        let name = match self.next()? {
+            Token::Str(_) => return Ok(CqlType::Custom),
+            Token::QuotedIdent(_) => {
+                self.qualified_name_suffix()?;
+                return Ok(CqlType::UserDefined);
+            }
+            Token::Ident(name) => name.clone(),
+            other => return Err(format!("expected a type, found {other:?}")),
+        };
+        let single = |args: Vec<CqlType>| Box::new(args.into_iter().next().unwrap());
+        Ok(match name.as_str() {
+            "frozen" => *single(self.type_args(1)?),
+            "list" => CqlType::List(single(self.type_args(1)?)),
+            "set" => CqlType::Set(single(self.type_args(1)?)),
+            "map" => {
+                let mut args = self.type_args(2)?.into_iter();
+                CqlType::Map(
+                    Box::new(args.next().unwrap()),
+                    Box::new(args.next().unwrap()),
+                )
+            }
+            "tuple" => {
+                self.expect_symbol('<')?;
+                let mut elems = vec![self.cql_type()?];
+                while self.eat_symbol(',') {
+                    elems.push(self.cql_type()?);
+                }
+                self.expect_symbol('>')?;
+                CqlType::Tuple(elems)
+            }
+            "vector" => {
+                self.expect_symbol('<')?;
+                let elem = self.cql_type()?;
+                self.expect_symbol(',')?;
+                if !matches!(self.next()?, Token::Number(_)) {
+                    return Err("expected the dimensions of the vector".to_owned());
+                }
+                self.expect_symbol('>')?;
+                CqlType::Vector(Box::new(elem))
+            }
+            name => match native_type(name) {
+                Some(native) => CqlType::Native(native),
+                None => {
+                    self.qualified_name_suffix()?;
+                    CqlType::UserDefined
+                }
+            },
+        })
+    }
+
+    // Skips the name of a type following the keyspace name, if present.
+    fn qualified_name_suffix(&mut self) -> Result<(), String> {
+        if self.eat_symbol('.') {
+            self.name()?;
+        }
+        Ok(())
+    }
+
+    // Skips tokens up to and including the closing parenthesis
+    // matching an already consumed opening one.
+    fn skip_parenthesized(&mut self) -> Result<(), String> {
+        let mut depth = 1;
+        while depth > 0 {
+            match self.next()? {
+                Token::Symbol('(') => depth += 1,
+                Token::Symbol(')') => depth -= 1,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn column(&mut self) -> Result<Column, String> {
+        Ok(Column {
+            name: self.name()?,
+            typ: self.cql_type()?,
+        })
+    }
+
+    /// Parses the column definitions of CREATE TABLE, after the opening parenthesis.
+    fn table_columns(&mut self) -> Result<Vec<Column>, String> {
+        let mut columns = Vec::new();
+        loop {
+            if self.eat_keyword("primary") {
+                self.expect_keyword("key")?;
+                self.expect_symbol('(')?;
+                self.skip_parenthesized()?;
+            } else {
+                columns.push(self.column()?);
+                self.eat_keyword("static");
+                if self.eat_keyword("primary") {
+                    self.expect_keyword("key")?;
+                }
+            }
+            if !self.eat_symbol(',') {
+                break;
+            }
+            // A trailing comma is allowed before the closing parenthesis.
+            if self.peek() == Some(&Token::Symbol(')')) {
+                break;
+            }
+        }
+        self.expect_symbol(')')?;
+        Ok(columns)
+    }
+
+    /// Parses the field definitions of CREATE TYPE, after the opening parenthesis.
+    fn udt_fields(&mut self) -> Result<Vec<Column>, String> {
+        let mut fields = vec![self.column()?];
+        while self.eat_symbol(',') {
+            fields.push(self.column()?);
+        }
+        self.expect_symbol(')')?;
+        Ok(fields)
+    }
+
+    /// Parses one or more comma-separated items, optionally in parentheses.
+    fn list_of<T>(
+        &mut self,
+        mut item: impl FnMut(&mut Self) -> Result<T, String>,
+    ) -> Result<Vec<T>, String> {
+        let parenthesized = self.eat_symbol('(');
+        let mut items = vec![item(self)?];
+        while self.eat_symbol(',') {
+            items.push(item(self)?);
+        }
+        if parenthesized {
+            self.expect_symbol(')')?;
+        }
+        Ok(items)
+    }
+
+    fn renames(&mut self) -> Result<Vec<(String, String)>, String> {
+        let mut renames = Vec::new();
+        loop {
+            let from = self.name()?;
+            self.expect_keyword("to")?;
+            renames.push((from, self.name()?));
+            if !self.eat_keyword("and") {
+                break;
+            }
+        }
+        Ok(renames)
+    }
+}
+
+/// Tables and user defined types defined by a schema file.
+pub(crate) struct Schema {
+    tables: Vec<Relation>,
+    types: Vec<Relation>,
+}
+
+impl Schema {
+    fn parse(input: &str) -> Result<Self, String> {
+        let mut schema = Schema {
+            tables: Vec::new(),
+            types: Vec::new(),
+        };
+        let mut current_keyspace = None;
+        let tokens = tokenize(input)?;
+        for statement in tokens.split(|t| *t == Token::Symbol(';')) {
+            let mut parser = StatementParser {
+                tokens: statement,
+                pos: 0,
+            };
+            schema
+                .apply(&mut parser, &mut current_keyspace)
+                .map_err(|err| format!("{err} in statement `{}`", describe(statement)))?;
+        }
+        Ok(schema)
+    }
+
+    fn apply(
+        &mut self,
+        p: &mut StatementParser,
+        current_keyspace: &mut Option<String>,
+    ) -> Result<(), String> {
+        if p.eat_keyword("use") {
+            *current_keyspace = Some(p.name()?);
+            return Ok(());
+        }
+
+        let qualified_name = |p: &mut StatementParser| -> Result<_, String> {
+            let (keyspace, name) = p.qualified_name()?;
+            Ok((keyspace.or_else(|| current_keyspace.clone()), name))
+        };
+
+        if p.eat_keyword("create") {
+            if p.eat_keyword("table") || p.eat_keyword("columnfamily") {
+                p.eat_if_exists(true)?;
+                let (keyspace, name) = qualified_name(p)?;
+                p.expect_symbol('(')?;
+                let columns = p.table_columns()?;
+                self.tables.push(Relation {
+                    keyspace,
+                    name,
+                    columns,
+                });
+            } else if p.eat_keyword("type") {
+                p.eat_if_exists(true)?;
+                let (keyspace, name) = qualified_name(p)?;
+                p.expect_symbol('(')?;
+                let columns = p.udt_fields()?;
+                self.types.push(Relation {
+                    keyspace,
+                    name,
+                    columns,
+                });
+            }
+        } else if p.eat_keyword("alter") {
+            let (relations, is_table) = if p.eat_keyword("table") {
+                (&mut self.tables, true)
+            } else if p.eat_keyword("type") {
+                (&mut self.types, false)
+            } else {
+                return Ok(());
+            };
+            p.eat_if_exists(false)?;
+            let (keyspace, name) = qualified_name(p)?;
+            let Some(relation) = relations
+                .iter_mut()
+                .rev()
+                .find(|r| r.name == name && r.keyspace == keyspace)
+            else {
+                return Err(format!("`{name}` is not defined before"));
+            };
+            if p.eat_keyword("add") {
+                p.eat_if_exists(true)?;
+                let columns = if is_table {
+                    p.list_of(StatementParser::column)?
+                } else {
+                    vec![p.column()?]
+                };
+                relation.columns.extend(columns);
+            } else if is_table && p.eat_keyword("drop") {
+                p.eat_if_exists(false)?;
+                let dropped = p.list_of(StatementParser::name)?;
+                relation.columns.retain(|c| !dropped.contains(&c.name));
+            } else if p.eat_keyword("rename") {
+                p.eat_if_exists(false)?;
+                for (from, to) in p.renames()? {
+                    for column in relation.columns.iter_mut().filter(|c| c.name == from) {
+                        column.name = to.clone();
+                    }
+                }
+            }
+        } else if p.eat_keyword("drop") {
+            let relations = if p.eat_keyword("table") || p.eat_keyword("columnfamily") {
+                &mut self.tables
+            } else if p.eat_keyword("type") {
+                &mut self.types
+            } else {
+                return Ok(());
+            };
+            p.eat_if_exists(false)?;
+            let (keyspace, name) = qualified_name(p)?;
+            relations.retain(|r| !(r.name == name && r.keyspace == keyspace));
+        }
+        Ok(())
+    }
+
+    fn find<'s>(
+        relations: &'s [Relation],
+        kind: &str,
+        keyspace: Option<&str>,
+        name: &str,
+    ) -> Result<&'s Relation, String> {
+        let mut found = relations.iter().filter(|r| {
+            r.name == name && (keyspace.is_none() || r.keyspace.as_deref() == keyspace)
+        });
+        match (found.next(), found.next()) {
+            (Some(relation), None) => Ok(relation),
+            (None, _) => Err(format!("{kind} `{name}` is not defined in the schema file")),
+            (Some(_), Some(_)) => Err(format!(
+                "{kind} `{name}` is defined in multiple keyspaces in the schema file, the keyspace must be given"
+            )),
+        }
+    }
+}
+
+// Formats the beginning of a statement, for error messages.
+fn describe(statement: &[Token]) -> String {
+    let words = statement
+        .iter()
+        .take(6)
+        .map(|t| match t {
+            Token::Ident(s) | Token::Number(s) => s.clone(),
+            Token::QuotedIdent(s) => format!("\"{s}\""),
+            Token::Str(s) => format!("'{s}'"),
+            Token::Symbol(c) => c.to_string(),
+        })
+        .collect::<Vec<_>>();
+    let ellipsis = if statement.len() > words.len() {
+        " ..."
+    } else {
+        ""
+    };
+    format!("{}{ellipsis}", words.join(" "))
+}
+
+/// Splits a possibly qualified name given in an attribute into the keyspace and the name.
+pub(crate) fn split_qualified_name(name: &str) -> (Option<&str>, &str) {
+    match name.split_once('.') {
+        Some((keyspace, name)) => (Some(keyspace), name),
+        None => (None, name),
+    }
+}
+
+/// What a struct is checked against.
+pub(crate) enum Target<'a> {
+    Table {
+        keyspace: Option<&'a str>,
+        name: &'a str,
+    },
+    Udt {
+        name: &'a str,
+    },
+}
+
+/// A struct field checked against a column of a table or a field of a UDT.
+pub(crate) struct CheckedField<'a> {
+    pub(crate) ident: &'a syn::Ident,
+    pub(crate) ty: &'a syn::Type,
+    pub(crate) name: String,
+    /// Whether the derived code handles the column being absent,
+    /// in which case the field may have no counterpart in the schema.
+    pub(crate) may_be_missing: bool,
+    pub(crate) skip_check: bool,
+}
+
+/// Reads the schema file and generates the compile-time checks of the fields.
+///
+/// The fields are checked with the function at `assert_fn`, which requires
+/// the field type to be compatible with the marker type of the CQL type.
+/// The field names which are missing from the schema are reported as errors.
+pub(crate) fn generate_schema_check(
+    schema_file: &syn::LitStr,
+    target: Target,
+    fields: &[CheckedField],
+    generics: &syn::Generics,
+    macro_internal: &syn::Path,
+    assert_fn: &syn::Ident,
+) -> Result<TokenStream, syn::Error> {
+    let manifest_dir = std::env::var_os("CARGO_MANIFEST_DIR").unwrap_or_default();
+    let path = PathBuf::from(manifest_dir).join(schema_file.value());
+    let contents = std::fs::read_to_string(&path).map_err(|err| {
+        syn::Error::new(
+            schema_file.span(),
+            format!("failed to read the schema file {}: {err}", path.display()),
+        )
+    })?;
+    let schema = Schema::parse(&contents).map_err(|err| {
+        syn::Error::new(
+            schema_file.span(),
+            format!("failed to parse the schema file: {err}"),
+        )
+    })?;
+    let (relation, kind) = match target {
+        Target::Table { keyspace, name } => (
+            Schema::find(&schema.tables, "table", keyspace, name),
+            "column",
+        ),
+        Target::Udt { name } => {
+            let (keyspace, name) = split_qualified_name(name);
+            (
+                Schema::find(&schema.types, "user defined type", keyspace, name),
+                "field",
+            )
+        }
+    };
+    let relation = relation.map_err(|err| syn::Error::new(schema_file.span(), err))?;
+
+    let mut errors = darling::Error::accumulator();
+    let mut assertions = Vec::new();
+    for field in fields.iter().filter(|f| !f.skip_check) {
+        let Some(column) = relation.columns.iter().find(|c| c.name == field.name) else {
+            if !field.may_be_missing {
+                errors.push(
+                    darling::Error::custom(format!(
+                        "there is no {kind} `{}` in `{}` in the schema file",
+                        field.name, relation.name
+                    ))
+                    .with_span(field.ident),
+                );
+            }
+            continue;
+        };
+        if let Some(marker) = column.typ.marker(macro_internal) {
+            let ty = field.ty;
+            assertions.push(quote_spanned! {ty.span()=>
+                #macro_internal::#assert_fn::<#ty, #marker>();
+            });
+        }
+    }
+    errors.finish()?;
+
+    let path = path.to_string_lossy();
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    Ok(quote::quote! {
+        const _: () = {
+            // Makes the crate be rebuilt when the schema file changes.
+            const _: &str = ::std::include_str!(#path);
+
+            #[allow(dead_code, clippy::extra_unused_type_parameters)]
+            fn check_schema #impl_generics () #where_clause {
+                #(#assertions)*
+            }
+        };
+    })
+}
+
+/// The CQL types which values of a derived type are compatible with.
+pub(crate) enum Compatibility<'a> {
+    /// The CQL types with the given names of their marker types.
+    Types(Vec<syn::Ident>),
+    /// The same CQL types as the given type of the only field.
+    Transparent(&'a syn::Type),
+}
+
+/// Generates the impls of SerializeCompatible or DeserializeCompatible,
+/// given by `trait_name`, for a type with a derived (de)serialization impl.
+/// They allow to check the fields of this type against a schema file.
+pub(crate) fn generate_compatible_impls(
+    input: &syn::DeriveInput,
+    macro_internal: &syn::Path,
+    trait_name: &syn::Ident,
+    compatibility: Compatibility,
+) -> TokenStream {
+    let name = &input.ident;
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+    match compatibility {
+        Compatibility::Types(types) => {
+            let (impl_generics, _, where_clause) = input.generics.split_for_impl();
+            quote::quote! {
+                #(
+                    impl #impl_generics #macro_internal::#trait_name<#macro_internal::cql::#types>
+                        for #name #ty_generics #where_clause {}
+                )*
+            }
+        }
+        Compatibility::Transparent(inner) => {
+            let mut generics = input.generics.clone();
+            let cql_type: syn::Ident = parse_quote!(__CqlType);
+            generics.params.push(parse_quote!(#cql_type));
+            generics
+                .make_where_clause()
+                .predicates
+                .push(parse_quote!(#inner: #macro_internal::#trait_name<#cql_type>));
+            let (impl_generics, _, where_clause) = generics.split_for_impl();
+            quote::quote! {
+                impl #impl_generics #macro_internal::#trait_name<#cql_type>
+                    for #name #ty_generics #where_clause {}
+            }
+        }
+    }
+}
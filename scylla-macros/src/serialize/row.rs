@@ -6,6 +6,7 @@ use proc_macro2::Span;
 use syn::parse_quote;
 
 use super::Flavor;
+use crate::schema::{CheckedField, Target};
 
 #[derive(FromAttributes)]
 #[darling(attributes(scylla))]
@@ -18,6 +19,15 @@ struct Attributes {
 
     #[darling(default)]
     skip_name_checks: bool,
+
+    // If set, the fields are checked at compile time against the columns
+    // of the table given with the `table` attribute, as defined in this file.
+    #[darling(default)]
+    schema_file: Option<syn::LitStr>,
+
+    // Attributes of derive(Table), which may be used on the same struct.
+    #[darling(flatten)]
+    table_attrs: crate::table::TableStructAttrs,
 }
 
 impl Attributes {
@@ -51,6 +61,17 @@ struct FieldAttributes {
 
     #[darling(default)]
     skip: bool,
+
+    #[darling(default)]
+    flatten: bool,
+
+    // If true, the field is not checked against the schema file.
+    #[darling(default)]
+    skip_schema_check: bool,
+
+    // Attributes of derive(Table), which may be used on the same struct.
+    #[darling(flatten)]
+    _table_attrs: crate::table::TableFieldAttrs,
 }
 
 struct Context {
@@ -58,7 +79,21 @@ struct Context {
     fields: Vec<Field>,
 }
 
-pub(crate) fn derive_serialize_row(tokens_input: TokenStream) -> Result<syn::ItemImpl, syn::Error> {
+impl Context {
+    // Fields which are serialized to the columns with their names,
+    // i.e. all fields except the flattened one.
+    fn own_fields(&self) -> impl Iterator<Item = &Field> {
+        self.fields.iter().filter(|f| !f.attrs.flatten)
+    }
+
+    fn flattened_field(&self) -> Option<&Field> {
+        self.fields.iter().find(|f| f.attrs.flatten)
+    }
+}
+
+pub(crate) fn derive_serialize_row(
+    tokens_input: TokenStream,
+) -> Result<proc_macro2::TokenStream, syn::Error> {
     let input: syn::DeriveInput = syn::parse(tokens_input)?;
     let struct_name = input.ident.clone();
     let named_fields = crate::parser::parse_named_fields(&input, "SerializeRow")?;
@@ -93,11 +128,14 @@ pub(crate) fn derive_serialize_row(tokens_input: TokenStream) -> Result<syn::Ite
     let serialize_item = gen.generate_serialize();
     let is_empty_item = gen.generate_is_empty();
 
-    let res = parse_quote! {
+    let schema_check = ctx.generate_schema_check(&input.generics)?;
+
+    let res = quote::quote! {
         impl #impl_generics #implemented_trait for #struct_name #ty_generics #where_clause {
             #serialize_item
             #is_empty_item
         }
+        #schema_check
     };
     Ok(res)
 }
@@ -128,9 +166,57 @@ impl Context {
             }
         }
 
+        let mut flattened_fields = self.fields.iter().filter(|f| f.attrs.flatten);
+        if let Some(flattened_field) = flattened_fields.next() {
+            // The flattened field receives the columns which are not claimed
+            // by other fields, so there can be only one such field.
+            for field in flattened_fields {
+                let err = darling::Error::custom(
+                    "only one field per struct can have the `flatten` attribute",
+                )
+                .with_span(&field.ident);
+                errors.push(err);
+            }
+
+            // The number of columns of the flattened struct is not known
+            // when generating code for the `enforce_order` flavor
+            if self.attributes.flavor != Flavor::MatchByName {
+                let err = darling::Error::custom(
+                    "the `flatten` attribute is only allowed with the `match_by_name` flavor",
+                )
+                .with_span(&flattened_field.ident);
+                errors.push(err);
+            }
+
+            if flattened_field.attrs.rename.is_some() {
+                let err = darling::Error::custom(
+                    "the `rename` annotation doesn't make sense with the `flatten` attribute",
+                )
+                .with_span(&flattened_field.ident);
+                errors.push(err);
+            }
+        }
+
+        if self.attributes.schema_file.is_some() {
+            if self.attributes.table_attrs.table.is_none() {
+                let err = darling::Error::custom(
+                    "the `schema_file` attribute requires the `table` attribute",
+                )
+                .with_span(struct_ident);
+                errors.push(err);
+            }
+            if self.attributes.skip_name_checks {
+                let err = darling::Error::custom(
+                    "the `schema_file` attribute can't be used with `skip_name_checks`, as the columns are found by name",
+                )
+                .with_span(struct_ident);
+                errors.push(err);
+            }
+        }
+
         // Check for name collisions
         let mut used_names = HashMap::<String, &Field>::new();
-        for field in self.fields.iter() {
+        for field in self.own_fields() {
             let column_name = field.column_name();
             if let Some(other_field) = used_names.get(&column_name) {
                 let other_field_ident = &other_field.ident;
@@ -146,6 +232,38 @@ impl Context {
         Ok(())
     }
 
+    fn generate_schema_check(
+        &self,
+        generics: &syn::Generics,
+    ) -> Result<Option<proc_macro2::TokenStream>, syn::Error> {
+        let (Some(schema_file), Some((keyspace, name))) = (
+            &self.attributes.schema_file,
+            self.attributes.table_attrs.qualified_table(),
+        ) else {
+            return Ok(None);
+        };
+        // The columns of the flattened struct are checked by its own derive.
+        let fields = self
+            .own_fields()
+            .map(|f| CheckedField {
+                ident: &f.ident,
+                ty: &f.ty,
+                name: f.column_name(),
+                may_be_missing: false,
+                skip_check: f.attrs.skip_schema_check,
+            })
+            .collect::<Vec<_>>();
+        crate::schema::generate_schema_check(
+            schema_file,
+            Target::Table { keyspace, name },
+            &fields,
+            generics,
+            &self.attributes.crate_path(),
+            &parse_quote!(assert_serialize_compatible),
+        )
+        .map(Some)
+    }
+
     fn generate_mk_typck_err(&self) -> syn::Stmt {
         let crate_path = self.attributes.crate_path();
         parse_quote! {
@@ -197,23 +315,59 @@ impl<'a> Generator for ColumnSortingGenerator<'a> {
 
         let rust_field_idents = self
             .ctx
-            .fields
-            .iter()
+            .own_fields()
             .map(|f| f.ident.clone())
             .collect::<Vec<_>>();
         let rust_field_names = self
             .ctx
-            .fields
-            .iter()
+            .own_fields()
             .map(|f| f.column_name())
             .collect::<Vec<_>>();
         let udt_field_names = rust_field_names.clone(); // For now, it's the same
-        let field_types = self.ctx.fields.iter().map(|f| &f.ty).collect::<Vec<_>>();
+        let field_types = self.ctx.own_fields().map(|f| &f.ty).collect::<Vec<_>>();
 
         // Declare a helper lambda for creating errors
         statements.push(self.ctx.generate_mk_typck_err());
         statements.push(self.ctx.generate_mk_ser_err());
 
+        // The flattened struct is serialized directly into the writer,
+        // to the columns which don't belong to other fields.
+        let mut columns_iter: syn::Expr = parse_quote!(ctx.columns());
+        let mut finish_flattened: Option<syn::Stmt> = None;
+        let unknown_column_action: syn::Expr = match self.ctx.flattened_field() {
+            Some(flattened_field) => {
+                let flattened_ident = &flattened_field.ident;
+                statements.push(parse_quote! {
+                    let mut flattened_row = #crate_path::FlattenedRow::new(&self.#flattened_ident);
+                });
+                columns_iter = parse_quote!(::std::iter::Iterator::enumerate(ctx.columns().iter()));
+                finish_flattened = Some(parse_quote! {
+                    #crate_path::FlattenedRow::finish(flattened_row, writer)?;
+                });
+                parse_quote! {
+                    #crate_path::FlattenedRow::serialize_column(
+                        &mut flattened_row,
+                        column_index,
+                        ctx,
+                        |name| false #(|| name == #rust_field_names)*,
+                        writer,
+                    )?
+                }
+            }
+            None => parse_quote! {
+                return ::std::result::Result::Err(mk_typck_err(
+                    #crate_path::BuiltinRowTypeCheckErrorKind::NoColumnWithName {
+                        name: <_ as ::std::clone::Clone>::clone(&&spec.name),
+                    }
+                ))
+            },
+        };
+        let column_pattern: syn::Pat = if finish_flattened.is_some() {
+            parse_quote!((column_index, spec))
+        } else {
+            parse_quote!(spec)
+        };
+
         // Generate a "visited" flag for each field
         let visited_flag_names = rust_field_names
             .iter()
@@ -224,7 +378,7 @@ impl<'a> Generator for ColumnSortingGenerator<'a> {
         });
 
         // Generate a variable that counts down visited fields.
-        let field_count = self.ctx.fields.len();
+        let field_count = rust_field_idents.len();
         statements.push(parse_quote! {
             let mut remaining_count = #field_count;
         });
@@ -232,7 +386,7 @@ impl<'a> Generator for ColumnSortingGenerator<'a> {
         // Generate a loop over the fields and a `match` block to match on
         // the field name.
         statements.push(parse_quote! {
-            for spec in ctx.columns() {
+            for #column_pattern in #columns_iter {
                 match ::std::string::String::as_str(&spec.name) {
                     #(
                         #udt_field_names => {
@@ -254,14 +408,11 @@ impl<'a> Generator for ColumnSortingGenerator<'a> {
                             }
                         }
                     )*
-                    _ => return ::std::result::Result::Err(mk_typck_err(
-                        #crate_path::BuiltinRowTypeCheckErrorKind::NoColumnWithName {
-                            name: <_ as ::std::clone::Clone>::clone(&&spec.name),
-                        }
-                    )),
+                    _ => #unknown_column_action,
                 }
             }
         });
+        statements.extend(finish_flattened);
 
         // Finally, check that all fields were consumed.
         // If there are some missing fields, return an error
@@ -293,11 +444,18 @@ impl<'a> Generator for ColumnSortingGenerator<'a> {
     }
 
     fn generate_is_empty(&self) -> syn::TraitItemFn {
-        let is_empty = self.ctx.fields.is_empty();
+        let crate_path = self.ctx.attributes.crate_path();
+        let is_empty = self.ctx.own_fields().next().is_none();
+        let flattened_is_empty = self.ctx.flattened_field().map(|f| -> syn::Expr {
+            let ident = &f.ident;
+            let ty = &f.ty;
+            parse_quote!(<#ty as #crate_path::SerializeRow>::is_empty(&self.#ident))
+        });
+        let flattened_is_empty = flattened_is_empty.iter();
         parse_quote! {
             #[inline]
             fn is_empty(&self) -> bool {
-                #is_empty
+                #is_empty #(&& #flattened_is_empty)*
             }
         }
     }
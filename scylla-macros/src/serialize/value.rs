@@ -6,6 +6,7 @@ use proc_macro2::Span;
 use syn::parse_quote;
 
 use super::Flavor;
+use crate::schema::{generate_compatible_impls, CheckedField, Compatibility, Target};
 
 #[derive(FromAttributes)]
 #[darling(attributes(scylla))]
@@ -21,6 +22,17 @@ struct Attributes {
 
     #[darling(default)]
     force_exact_match: bool,
+
+    #[darling(default)]
+    transparent: bool,
+
+    // If set, the fields are checked at compile time against the fields
+    // of the UDT given with the `udt` attribute, as defined in this file.
+    #[darling(default)]
+    schema_file: Option<syn::LitStr>,
+
+    #[darling(default)]
+    udt: Option<String>,
 }
 
 impl Attributes {
@@ -54,6 +66,10 @@ struct FieldAttributes {
 
     #[darling(default)]
     skip: bool,
+
+    // If true, the field is not checked against the schema file.
+    #[darling(default)]
+    skip_schema_check: bool,
 }
 
 struct Context {
@@ -63,15 +79,62 @@ struct Context {
 
 pub(crate) fn derive_serialize_value(
     tokens_input: TokenStream,
-) -> Result<syn::ItemImpl, syn::Error> {
+) -> Result<proc_macro2::TokenStream, syn::Error> {
     let input: syn::DeriveInput = syn::parse(tokens_input)?;
     let struct_name = input.ident.clone();
-    let named_fields = crate::parser::parse_named_fields(&input, "SerializeValue")?;
     let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
     let attributes = Attributes::from_attributes(&input.attrs)?;
 
     let crate_path = attributes.crate_path();
     let implemented_trait: syn::Path = parse_quote!(#crate_path::SerializeValue);
+    let compatible_trait: syn::Ident = parse_quote!(SerializeCompatible);
+
+    let non_udt_items = match &input.data {
+        syn::Data::Enum(data) if !attributes.transparent => {
+            let fieldless_enum =
+                crate::parser::parse_fieldless_enum(&input, data, "SerializeValue")?;
+            let (int_types, _) = fieldless_enum.int_types();
+            let cql_types = fieldless_enum.text_types().into_iter().chain(int_types);
+            Some((
+                generate_enum_serialize(&crate_path, &fieldless_enum),
+                Compatibility::Types(cql_types.collect()),
+            ))
+        }
+        _ if attributes.transparent => {
+            let (member, ty) = crate::parser::parse_transparent_field(&input, "SerializeValue")?;
+            Some((
+                parse_quote! {
+                    fn serialize<'b>(
+                        &self,
+                        typ: &#crate_path::ColumnType,
+                        writer: #crate_path::CellWriter<'b>,
+                    ) -> ::std::result::Result<#crate_path::WrittenCellProof<'b>, #crate_path::SerializationError> {
+                        <#ty as #crate_path::SerializeValue>::serialize(&self.#member, typ, writer)
+                    }
+                },
+                Compatibility::Transparent(ty),
+            ))
+        }
+        _ => None,
+    };
+    if let Some((serialize_item, compatibility)) = non_udt_items {
+        if let Some(schema_file) = &attributes.schema_file {
+            return Err(syn::Error::new(
+                schema_file.span(),
+                "the `schema_file` attribute is only allowed on structs mapped to UDTs",
+            ));
+        }
+        let compatible_impls =
+            generate_compatible_impls(&input, &crate_path, &compatible_trait, compatibility);
+        return Ok(quote::quote! {
+            impl #impl_generics #implemented_trait for #struct_name #ty_generics #where_clause {
+                #serialize_item
+            }
+            #compatible_impls
+        });
+    }
+
+    let named_fields = crate::parser::parse_named_fields(&input, "SerializeValue")?;
 
     let fields = named_fields
         .named
@@ -96,11 +159,20 @@ pub(crate) fn derive_serialize_value(
     };
 
     let serialize_item = gen.generate_serialize();
-
-    let res = parse_quote! {
+    let compatible_impls = generate_compatible_impls(
+        &input,
+        &crate_path,
+        &compatible_trait,
+        Compatibility::Types(vec![parse_quote!(UserDefinedType)]),
+    );
+    let schema_check = ctx.generate_schema_check(&input.generics)?;
+
+    let res = quote::quote! {
         impl #impl_generics #implemented_trait for #struct_name #ty_generics #where_clause {
             #serialize_item
         }
+        #compatible_impls
+        #schema_check
     };
     Ok(res)
 }
@@ -131,6 +203,25 @@ impl Context {
             }
         }
 
+        match (&self.attributes.schema_file, &self.attributes.udt) {
+            (Some(_), None) => errors.push(
+                darling::Error::custom("the `schema_file` attribute requires the `udt` attribute")
+                    .with_span(struct_ident),
+            ),
+            (None, Some(_)) => errors.push(
+                darling::Error::custom("the `udt` attribute requires the `schema_file` attribute")
+                    .with_span(struct_ident),
+            ),
+            _ => {}
+        }
+        if self.attributes.schema_file.is_some() && self.attributes.skip_name_checks {
+            let err = darling::Error::custom(
+                "the `schema_file` attribute can't be used with `skip_name_checks`, as the UDT fields are found by name",
+            )
+            .with_span(struct_ident);
+            errors.push(err);
+        }
+
         // Check for name collisions
         let mut used_names = HashMap::<String, &Field>::new();
         for field in self.fields.iter() {
@@ -149,6 +240,36 @@ impl Context {
         Ok(())
     }
 
+    fn generate_schema_check(
+        &self,
+        generics: &syn::Generics,
+    ) -> Result<Option<proc_macro2::TokenStream>, syn::Error> {
+        let (Some(schema_file), Some(udt)) = (&self.attributes.schema_file, &self.attributes.udt)
+        else {
+            return Ok(None);
+        };
+        let fields = self
+            .fields
+            .iter()
+            .map(|f| CheckedField {
+                ident: &f.ident,
+                ty: &f.ty,
+                name: f.field_name(),
+                may_be_missing: false,
+                skip_check: f.attrs.skip_schema_check,
+            })
+            .collect::<Vec<_>>();
+        crate::schema::generate_schema_check(
+            schema_file,
+            Target::Udt { name: udt },
+            &fields,
+            generics,
+            &self.attributes.crate_path(),
+            &parse_quote!(assert_serialize_compatible),
+        )
+        .map(Some)
+    }
+
     fn generate_udt_type_match(&self, err: syn::Expr) -> syn::Stmt {
         let crate_path = self.attributes.crate_path();
 
@@ -193,6 +314,75 @@ impl Context {
     }
 }
 
+// Generates the `serialize` method for a fieldless enum. The enum is stored
+// as the variant name in text columns and, if all variants have explicit
+// discriminants, as the discriminant in integer columns.
+fn generate_enum_serialize(
+    crate_path: &syn::Path,
+    fieldless_enum: &crate::parser::FieldlessEnum,
+) -> syn::TraitItemFn {
+    let variant_idents = fieldless_enum
+        .variants
+        .iter()
+        .map(|v| v.ident)
+        .collect::<Vec<_>>();
+    let variant_names = fieldless_enum.variants.iter().map(|v| &v.cql_name);
+
+    let text_types = fieldless_enum.text_types();
+    let (int_types, int_rust_types) = fieldless_enum.int_types();
+    let expected_types = text_types.iter().chain(int_types.iter());
+    let discriminant_match: syn::Expr = parse_quote! {
+        match self {
+            #(Self::#variant_idents => Self::#variant_idents as i64,)*
+        }
+    };
+
+    parse_quote! {
+        fn serialize<'b>(
+            &self,
+            typ: &#crate_path::ColumnType,
+            writer: #crate_path::CellWriter<'b>,
+        ) -> ::std::result::Result<#crate_path::WrittenCellProof<'b>, #crate_path::SerializationError> {
+            let mk_ser_err = |kind: #crate_path::BuiltinTypeSerializationErrorKind| -> #crate_path::SerializationError {
+                #crate_path::SerializationError::new(
+                    #crate_path::BuiltinTypeSerializationError {
+                        rust_name: ::std::any::type_name::<Self>(),
+                        got: <_ as ::std::clone::Clone>::clone(typ),
+                        kind,
+                    }
+                )
+            };
+            match typ {
+                #(#crate_path::ColumnType::#text_types)|* => {
+                    let name: &'static str = match self {
+                        #(Self::#variant_idents => #variant_names,)*
+                    };
+                    #crate_path::CellWriter::set_value(writer, name.as_bytes())
+                        .map_err(|_| mk_ser_err(#crate_path::BuiltinTypeSerializationErrorKind::SizeOverflow))
+                }
+                #(
+                    #crate_path::ColumnType::#int_types => {
+                        let discriminant: i64 = #discriminant_match;
+                        let value = <#int_rust_types as ::std::convert::TryFrom<i64>>::try_from(discriminant)
+                            .map_err(|_| mk_ser_err(#crate_path::BuiltinTypeSerializationErrorKind::ValueOverflow))?;
+                        #crate_path::CellWriter::set_value(writer, &value.to_be_bytes())
+                            .map_err(|_| mk_ser_err(#crate_path::BuiltinTypeSerializationErrorKind::SizeOverflow))
+                    }
+                )*
+                _ => ::std::result::Result::Err(#crate_path::SerializationError::new(
+                    #crate_path::BuiltinTypeTypeCheckError {
+                        rust_name: ::std::any::type_name::<Self>(),
+                        got: <_ as ::std::clone::Clone>::clone(typ),
+                        kind: #crate_path::BuiltinTypeTypeCheckErrorKind::MismatchedType {
+                            expected: &[#(#crate_path::ColumnType::#expected_types),*],
+                        },
+                    }
+                )),
+            }
+        }
+    }
+}
+
 trait Generator {
     fn generate_serialize(&self) -> syn::TraitItemFn;
 }
@@ -0,0 +1,318 @@
+use darling::{FromDeriveInput, FromField, FromMeta};
+use proc_macro::TokenStream;
+use syn::ext::IdentExt;
+use syn::parse_quote;
+
+/// Struct attributes of derive(Table). The row derives accept them as well,
+/// so that all of those derives can be used on the same struct, and use them
+/// to find the table in the schema file.
+#[derive(FromMeta, Default)]
+pub(crate) struct TableStructAttrs {
+    #[darling(default)]
+    pub(crate) keyspace: Option<String>,
+
+    #[darling(default)]
+    pub(crate) table: Option<String>,
+}
+
+impl TableStructAttrs {
+    /// Returns the keyspace and the name of the table. The keyspace
+    /// may also be given as a part of the table name, as in `ks.table`.
+    pub(crate) fn qualified_table(&self) -> Option<(Option<&str>, &str)> {
+        let table = self.table.as_deref()?;
+        Some(match &self.keyspace {
+            Some(keyspace) => (Some(keyspace.as_str()), table),
+            None => crate::schema::split_qualified_name(table),
+        })
+    }
+}
+
+/// Field attributes of derive(Table), accepted and ignored by the row derives.
+#[derive(FromMeta, Default)]
+pub(crate) struct TableFieldAttrs {
+    #[darling(default, rename = "partition_key")]
+    _partition_key: bool,
+
+    #[darling(default, rename = "clustering_key")]
+    _clustering_key: bool,
+}
+
+// The `scylla` attribute is shared with the serialization and deserialization
+// derives, so the attributes which are meaningless for derive(Table)
+// are skipped instead of being rejected.
+#[derive(FromDeriveInput)]
+#[darling(attributes(scylla), allow_unknown_fields)]
+struct StructAttrs {
+    #[darling(rename = "crate")]
+    crate_path: Option<syn::Path>,
+
+    #[darling(default)]
+    keyspace: Option<String>,
+
+    #[darling(default)]
+    table: Option<String>,
+}
+
+impl StructAttrs {
+    fn macro_internal_path(&self) -> syn::Path {
+        match &self.crate_path {
+            Some(path) => parse_quote!(#path::_macro_internal),
+            None => parse_quote!(::scylla::_macro_internal),
+        }
+    }
+
+    fn table_attrs(&self) -> TableStructAttrs {
+        TableStructAttrs {
+            keyspace: self.keyspace.clone(),
+            table: self.table.clone(),
+        }
+    }
+}
+
+#[derive(FromField)]
+#[darling(attributes(scylla), allow_unknown_fields)]
+struct Field {
+    #[darling(default)]
+    rename: Option<String>,
+
+    #[darling(default)]
+    skip: bool,
+
+    #[darling(default)]
+    flatten: bool,
+
+    #[darling(default)]
+    partition_key: bool,
+
+    #[darling(default)]
+    clustering_key: bool,
+
+    ident: Option<syn::Ident>,
+    ty: syn::Type,
+}
+
+impl Field {
+    fn ident(&self) -> &syn::Ident {
+        self.ident.as_ref().unwrap()
+    }
+
+    fn column_name(&self) -> String {
+        match &self.rename {
+            Some(name) => name.clone(),
+            None => self.ident().unraw().to_string(),
+        }
+    }
+}
+
+/// Reserved CQL keywords, which can't be used as unquoted identifiers.
+const RESERVED_KEYWORDS: &[&str] = &[
+    "add",
+    "allow",
+    "alter",
+    "and",
+    "apply",
+    "asc",
+    "authorize",
+    "batch",
+    "begin",
+    "by",
+    "columnfamily",
+    "create",
+    "default",
+    "delete",
+    "desc",
+    "describe",
+    "drop",
+    "entries",
+    "execute",
+    "from",
+    "full",
+    "grant",
+    "if",
+    "in",
+    "index",
+    "infinity",
+    "insert",
+    "into",
+    "is",
+    "keyspace",
+    "limit",
+    "materialized",
+    "mbean",
+    "mbeans",
+    "modify",
+    "nan",
+    "norecursive",
+    "not",
+    "null",
+    "of",
+    "on",
+    "or",
+    "order",
+    "primary",
+    "rename",
+    "replace",
+    "revoke",
+    "schema",
+    "select",
+    "set",
+    "table",
+    "to",
+    "token",
+    "truncate",
+    "unlogged",
+    "unset",
+    "update",
+    "use",
+    "using",
+    "view",
+    "where",
+    "with",
+];
+
+/// Returns the name as a CQL identifier, quoting it if it would otherwise
+/// be case-insensitive, a reserved keyword or not a valid identifier.
+fn cql_identifier(name: &str) -> String {
+    let is_plain = name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        && !RESERVED_KEYWORDS.contains(&name);
+    if is_plain {
+        name.to_owned()
+    } else {
+        format!("\"{}\"", name.replace('"', "\"\""))
+    }
+}
+
+/// derive(Table) for the Table trait
+pub(crate) fn derive_table(tokens_input: TokenStream) -> Result<syn::ItemImpl, syn::Error> {
+    let input: syn::DeriveInput = syn::parse(tokens_input)?;
+    let struct_name = &input.ident;
+    let named_fields = crate::parser::parse_named_fields(&input, "Table")?;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let attrs = StructAttrs::from_derive_input(&input)?;
+    let table_attrs = attrs.table_attrs();
+
+    let fields = named_fields
+        .named
+        .iter()
+        .map(Field::from_field)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut errors = darling::Error::accumulator();
+    let (keyspace, table) = table_attrs.qualified_table().unwrap_or_else(|| {
+        errors.push(
+            darling::Error::custom("derive(Table) requires the `table` attribute")
+                .with_span(struct_name),
+        );
+        (None, "")
+    });
+    for field in &fields {
+        let is_key = field.partition_key || field.clustering_key;
+        if field.partition_key && field.clustering_key {
+            errors.push(
+                darling::Error::custom(
+                    "a field can't be both a partition key and a clustering key column",
+                )
+                .with_span(field.ident()),
+            );
+        }
+        if is_key && field.skip {
+            errors.push(
+                darling::Error::custom("primary key columns can't be skipped")
+                    .with_span(field.ident()),
+            );
+        }
+        if field.flatten {
+            // The columns of the flattened struct are not known to this derive.
+            errors.push(
+                darling::Error::custom("derive(Table) doesn't support the `flatten` attribute")
+                    .with_span(field.ident()),
+            );
+        }
+    }
+    if !fields.iter().any(|f| f.partition_key) {
+        errors.push(
+            darling::Error::custom("at least one field must have the `partition_key` attribute")
+                .with_span(struct_name),
+        );
+    }
+    errors.finish()?;
+
+    let columns = fields.iter().filter(|f| !f.skip).collect::<Vec<_>>();
+    let partition_key = columns.iter().filter(|f| f.partition_key);
+    let clustering_key = columns.iter().filter(|f| f.clustering_key);
+    let primary_key = partition_key.clone().chain(clustering_key.clone());
+    let regular_columns = columns
+        .iter()
+        .filter(|f| !f.partition_key && !f.clustering_key);
+
+    let names = |fields: &mut dyn Iterator<Item = &&Field>| -> Vec<String> {
+        fields.map(|f| f.column_name()).collect()
+    };
+    let column_names = names(&mut columns.iter());
+    let partition_key_names = names(&mut partition_key.clone());
+    let clustering_key_names = names(&mut clustering_key.clone());
+
+    let cql_identifiers = |fields: &mut dyn Iterator<Item = &&Field>| -> Vec<String> {
+        fields.map(|f| cql_identifier(&f.column_name())).collect()
+    };
+    let table_name = match keyspace {
+        Some(keyspace) => format!("{}.{}", cql_identifier(keyspace), cql_identifier(table)),
+        None => cql_identifier(table),
+    };
+    let where_primary_key = cql_identifiers(&mut primary_key.clone())
+        .into_iter()
+        .map(|c| format!("{c} = ?"))
+        .collect::<Vec<_>>()
+        .join(" AND ");
+    let selected_columns = cql_identifiers(&mut columns.iter()).join(", ");
+    let set_regular_columns = cql_identifiers(&mut regular_columns.clone())
+        .into_iter()
+        .map(|c| format!("{c} = ?"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let bind_markers = vec!["?"; columns.len()].join(", ");
+
+    let insert = format!("INSERT INTO {table_name} ({selected_columns}) VALUES ({bind_markers})");
+    let select = format!("SELECT {selected_columns} FROM {table_name} WHERE {where_primary_key}");
+    // CQL doesn't allow an UPDATE with nothing to set.
+    let update: syn::Expr = if set_regular_columns.is_empty() {
+        parse_quote!(::std::option::Option::None)
+    } else {
+        let update =
+            format!("UPDATE {table_name} SET {set_regular_columns} WHERE {where_primary_key}");
+        parse_quote!(::std::option::Option::Some(#update))
+    };
+    let delete = format!("DELETE FROM {table_name} WHERE {where_primary_key}");
+
+    let keyspace: syn::Expr = match keyspace {
+        Some(keyspace) => parse_quote!(::std::option::Option::Some(#keyspace)),
+        None => parse_quote!(::std::option::Option::None),
+    };
+    let key_idents = primary_key.clone().map(|f| f.ident());
+    let key_types = primary_key.map(|f| &f.ty);
+    let macro_internal = attrs.macro_internal_path();
+
+    Ok(parse_quote! {
+        impl #impl_generics #macro_internal::Table for #struct_name #ty_generics #where_clause {
+            const KEYSPACE: ::std::option::Option<&'static str> = #keyspace;
+            const TABLE: &'static str = #table;
+            const COLUMNS: &'static [&'static str] = &[#(#column_names),*];
+            const PARTITION_KEY: &'static [&'static str] = &[#(#partition_key_names),*];
+            const CLUSTERING_KEY: &'static [&'static str] = &[#(#clustering_key_names),*];
+
+            const INSERT: &'static str = #insert;
+            const SELECT: &'static str = #select;
+            const UPDATE: ::std::option::Option<&'static str> = #update;
+            const DELETE: &'static str = #delete;
+
+            type PrimaryKey<'r> = (#(&'r #key_types,)*) where Self: 'r;
+
+            fn primary_key(&self) -> Self::PrimaryKey<'_> {
+                (#(&self.#key_idents,)*)
+            }
+        }
+    })
+}
@@ -60,6 +60,7 @@ pub trait HistoryListener: Debug + Send + Sync {
     fn log_attempt_success(&self, attempt_id: AttemptId);
 
     /// Log that an attempt ended with an error. The error and decision whether to retry the attempt are also included in the log.
+    /// If the retry is delayed, the decision carries the delay (see [RetryDecision::delay]).
     fn log_attempt_error(
         &self,
         attempt_id: AttemptId,
@@ -453,6 +454,7 @@ mod tests {
     use std::{
         net::{IpAddr, Ipv4Addr, SocketAddr},
         sync::Arc,
+        time::Duration,
     };
 
     use crate::{
@@ -713,6 +715,44 @@ mod tests {
         assert_eq!(displayed, format!("{}", set_one_time(history)));
     }
 
+    #[test]
+    fn delayed_retry() {
+        setup_tracing();
+        let history_collector = HistoryCollector::new();
+
+        let query_id: QueryId = history_collector.log_query_start();
+
+        let attempt_id: AttemptId =
+            history_collector.log_attempt_start(query_id, None, node1_addr());
+        history_collector.log_attempt_error(
+            attempt_id,
+            &QueryError::TimeoutError,
+            &RetryDecision::RetryNextNodeAfter(None, Duration::from_millis(150)),
+        );
+
+        let history: StructuredHistory = history_collector.clone_structured_history();
+        assert_matches!(
+            &history.queries[0].non_speculative_fiber.attempts[0].result,
+            Some(AttemptResult::Error(_, _, decision))
+                if decision.delay() == Some(Duration::from_millis(150))
+        );
+
+        let displayed = "Queries History:
+=== Query #0 ===
+| start_time: 2022-02-22 20:22:22 UTC
+| Non-speculative attempts:
+| - Attempt #0 sent to 127.0.0.1:19042
+|   request send time: 2022-02-22 20:22:22 UTC
+|   Error at 2022-02-22 20:22:22 UTC
+|   Error: Timeout Error
+|   Retry decision: RetryNextNodeAfter(None, 150ms)
+|
+| Query still running - no final result yet
+=================
+";
+        assert_eq!(displayed, format!("{}", set_one_time(history)));
+    }
+
     #[test]
     fn empty_fibers() {
         setup_tracing();
@@ -72,19 +72,14 @@
 //! # use scylla::Session;
 //! # use std::error::Error;
 //! # async fn check_only_compiles(session: &Session) -> Result<(), Box<dyn Error>> {
-//! use scylla::IntoTypedRows;
-//!
 //! // Read rows containing an int and text
-//! let rows_opt = session
-//! .query("SELECT a, b FROM ks.tab", &[])
-//!     .await?
-//!     .rows;
+//! let result = session
+//!     .query("SELECT a, b FROM ks.tab", &[])
+//!     .await?;
 //!
-//! if let Some(rows) = rows_opt {
-//!     for row in rows.into_typed::<(i32, String)>() {
-//!         // Parse row as int and text \
-//!         let (int_val, text_val): (i32, String) = row?;
-//!     }
+//! // Text values borrow from the result, so they are not copied
+//! for row in result.rows::<(i32, &str)>()? {
+//!     let (int_val, text_val): (i32, &str) = row?;
 //! }
 //! # Ok(())
 //! # }
@@ -95,6 +90,7 @@
 
 #[doc(hidden)]
 pub mod _macro_internal {
+    pub use crate::transport::mapper::Table;
     pub use scylla_cql::_macro_internal::*;
 }
 
@@ -103,9 +99,9 @@ pub mod macros;
 pub use macros::*;
 
 pub mod frame {
-    pub use scylla_cql::frame::{frame_errors, value, Authenticator, Compression};
+    pub use scylla_cql::frame::{frame_errors, value, Authenticator, Compression, ProtocolVersion};
     pub(crate) use scylla_cql::frame::{
-        parse_response_body_extensions, protocol_features, read_response_frame, request,
+        parse_response_body_extensions, protocol_features, read_response_frame, request, segment,
         server_event_type, FrameParams, SerializedRequest,
     };
 
@@ -162,6 +158,11 @@ pub mod deserialize {
     }
 }
 
+/// Parsing CQL literals into [`CqlValue`](frame::response::result::CqlValue)s.
+pub mod literal {
+    pub use crate::utils::literal::{parse_cql_literal, CqlLiteralParseError};
+}
+
 pub mod authentication;
 #[cfg(feature = "cloud")]
 pub mod cloud;
@@ -180,17 +181,24 @@ pub(crate) mod utils;
 pub use utils::test_utils;
 
 pub use statement::batch;
+pub use statement::paging_state;
 pub use statement::prepared_statement;
 pub use statement::query;
+pub use statement::query_builder;
 
 pub use frame::response::cql_to_rust;
 pub use frame::response::cql_to_rust::FromRow;
 
+pub use transport::bulk_writer::BulkWriter;
 pub use transport::caching_session::CachingSession;
 pub use transport::execution_profile::ExecutionProfile;
-pub use transport::query_result::QueryResult;
+pub use transport::mapper::{Mapper, Table};
+pub use transport::migrations::Migrator;
+pub use transport::mirroring_session::MirroringSession;
+pub use transport::query_result::{LegacyQueryResult, QueryResult};
 pub use transport::session::{IntoTypedRows, Session, SessionConfig};
 pub use transport::session_builder::SessionBuilder;
+pub use transport::token_range_scan::TokenRangeScan;
 
 #[cfg(feature = "cloud")]
 pub use transport::session_builder::CloudSessionBuilder;
@@ -200,5 +208,6 @@ pub use transport::host_filter;
 pub use transport::load_balancing;
 pub use transport::retry_policy;
 pub use transport::speculative_execution;
+pub use transport::timestamp_generator;
 
 pub use transport::metrics::Metrics;
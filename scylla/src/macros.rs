@@ -27,7 +27,9 @@ pub use scylla_cql::macros::IntoUserType;
 /// Derive macro for the [`SerializeValue`](crate::serialize::value::SerializeValue) trait
 /// which serializes given Rust structure as a User Defined Type (UDT).
 ///
-/// At the moment, only structs with named fields are supported.
+/// At the moment, only structs with named fields are supported as UDTs.
+/// The macro can also be used on fieldless enums and on single-field
+/// newtypes, see [Enums](#enums) and [Newtypes](#newtypes) below.
 ///
 /// Serialization will fail if there are some fields in the Rust struct that don't match
 /// to any of the UDT fields.
@@ -122,6 +124,13 @@ pub use scylla_cql::macros::IntoUserType;
 /// Forces Rust struct to have all the fields present in UDT, otherwise
 /// serialization fails.
 ///
+/// `#[scylla(schema_file = "path/to/schema.cql", udt = "keyspace.type_name")]`
+///
+/// Checks the struct against the definition of the UDT in the schema file
+/// at compile time. Compilation fails if a field has no counterpart in the UDT
+/// or if its type can't be serialized to the type of the UDT field.
+/// See the [`SerializeRow`](macro@crate::SerializeRow) derive for details.
+///
 /// # Field attributes
 ///
 /// `#[scylla(rename = "name_in_the_udt")]`
@@ -133,6 +142,47 @@ pub use scylla_cql::macros::IntoUserType;
 ///
 /// Don't use the field during serialization.
 ///
+/// `#[scylla(skip_schema_check)]`
+///
+/// Excludes the field from the check against the schema file.
+///
+/// # Enums
+///
+/// Enums whose variants have no fields are serialized to `text` and `ascii`
+/// columns as the name of the variant. If all variants have explicit
+/// discriminants, the enum can also be serialized to `tinyint`, `smallint`,
+/// `int` and `bigint` columns as the discriminant. Serialization fails with
+/// [`ValueOverflow`](crate::serialize::value::BuiltinSerializationErrorKind::ValueOverflow)
+/// if the discriminant does not fit in the column type.
+///
+/// ```rust
+/// # use scylla::SerializeValue;
+/// #[derive(SerializeValue)]
+/// enum Status {
+///     Active = 1,
+///     #[scylla(rename = "disabled")]
+///     Inactive = 2,
+/// }
+/// ```
+///
+/// `#[scylla(rename = "name_in_the_db")]`
+///
+/// Stores the variant under the given name instead of its Rust name.
+///
+/// # Newtypes
+///
+/// `#[scylla(transparent)]`
+///
+/// Serializes a struct with exactly one field (named or unnamed) exactly
+/// as that field would be serialized, including its type checks.
+///
+/// ```rust
+/// # use scylla::SerializeValue;
+/// #[derive(SerializeValue)]
+/// #[scylla(transparent)]
+/// struct UserId(i64);
+/// ```
+///
 /// ---
 ///
 pub use scylla_cql::macros::SerializeValue;
@@ -222,6 +272,37 @@ pub use scylla_cql::macros::SerializeValue;
 /// OK if i-th Rust struct field has a different name than the column / bind
 /// marker. The values are still being type-checked.
 ///
+/// `#[scylla(schema_file = "path/to/schema.cql", table = "keyspace.table")]`
+///
+/// Checks the struct against the definition of the table in the schema file
+/// at compile time, instead of waiting for the type check when the statement
+/// is executed. The schema file is a list of CQL statements, as used to create
+/// the schema: `CREATE TABLE`, `CREATE TYPE`, `ALTER TABLE`, `ALTER TYPE`,
+/// `DROP TABLE`, `DROP TYPE` and `USE` are interpreted, other statements are
+/// ignored. The path is relative to the directory containing the crate's
+/// `Cargo.toml`. The keyspace can also be given with the `keyspace` attribute,
+/// or omitted if no other keyspace in the file has a table with the same name.
+///
+/// Compilation fails if a field has no corresponding column in the table,
+/// or if the type of the field can't be serialized to the type of the column.
+/// The types are checked with the same rules as at runtime. The check knows
+/// the types supported by the driver and the types which derive `SerializeValue`;
+/// fields of types with manual `SerializeValue` implementations need the
+/// `skip_schema_check` attribute. Columns of custom types are not checked.
+///
+/// ```rust,ignore
+/// // schema.cql:
+/// // CREATE TABLE ks.my_t (a int PRIMARY KEY, b text, c blob);
+///
+/// #[derive(SerializeRow)]
+/// #[scylla(schema_file = "schema.cql", table = "ks.my_t")]
+/// struct MyValues {
+///     a: i32,
+///     b: Option<String>,
+///     c: Vec<u8>,
+/// }
+/// ```
+///
 /// # Field attributes
 ///
 /// `#[scylla(rename = "column_or_bind_marker_name")]`
@@ -233,6 +314,20 @@ pub use scylla_cql::macros::SerializeValue;
 ///
 /// Don't use the field during serialization.
 ///
+/// `#[scylla(flatten)]`
+///
+/// _Specific only to the `match_by_name` flavor._
+///
+/// The field must implement `SerializeRow` itself. It is serialized into all
+/// the columns / bind markers which don't correspond to any other field,
+/// which allows a struct to embed the columns of another struct.
+/// These columns must be placed next to each other in the statement.
+/// Only one field per struct can have this attribute.
+///
+/// `#[scylla(skip_schema_check)]`
+///
+/// Excludes the field from the check against the schema file.
+///
 /// ---
 ///
 pub use scylla_cql::macros::SerializeRow;
@@ -241,7 +336,9 @@ pub use scylla_cql::macros::SerializeRow;
 /// which deserializes a User Defined Type with the same layout as the Rust
 /// struct.
 ///
-/// At the moment, only structs with named fields are supported.
+/// At the moment, only structs with named fields are supported as UDTs.
+/// The macro can also be used on fieldless enums and on single-field
+/// newtypes, see [Enums](#enums) and [Newtypes](#newtypes) below.
 ///
 /// This macro properly supports structs with lifetimes, meaning that you can
 /// deserialize UDTs with fields that borrow memory from the serialized response.
@@ -329,6 +426,19 @@ pub use scylla_cql::macros::SerializeRow;
 /// If more strictness is desired, this flag makes sure that no excess fields
 /// are present and forces error in case there are some.
 ///
+/// `#[scylla(allow_missing)]`
+///
+/// Treats all fields as if they were annotated with `allow_missing`,
+/// which is handy when the UDT definition may lag behind the Rust struct.
+///
+/// `#[scylla(schema_file = "path/to/schema.cql", udt = "keyspace.type_name")]`
+///
+/// Checks the struct against the definition of the UDT in the schema file
+/// at compile time. Compilation fails if a field which isn't allowed to be
+/// missing has no counterpart in the UDT, or if the type of the UDT field
+/// can't be deserialized to the type of the field.
+/// See the [`SerializeRow`](macro@crate::SerializeRow) derive for details.
+///
 /// ## Field attributes
 ///
 /// `#[scylla(skip)]`
@@ -339,7 +449,14 @@ pub use scylla_cql::macros::SerializeRow;
 /// `#[scylla(allow_missing)]`
 ///
 /// If the UDT definition does not contain this field, it will be initialized
-/// with `Default::default()`.
+/// with `Default::default()`. `#[scylla(default_when_missing)]` is accepted
+/// as an alias.
+///
+/// `#[scylla(default = "path")]`
+///
+/// Initializes the field by calling the given function instead of
+/// `Default::default()`, whenever a default value is used for it.
+/// Implies `allow_missing`.
 ///
 /// `#[scylla(default_when_null)]`
 ///
@@ -351,6 +468,48 @@ pub use scylla_cql::macros::SerializeRow;
 /// By default, the generated implementation will try to match the Rust field
 /// to a UDT field with the same name. This attribute instead allows to match
 /// to a UDT field with provided name.
+///
+/// `#[scylla(skip_schema_check)]`
+///
+/// Excludes the field from the check against the schema file.
+///
+/// # Enums
+///
+/// Enums whose variants have no fields are deserialized from `text` and `ascii`
+/// columns by the name of the variant. If all variants have explicit
+/// discriminants, the enum can also be deserialized from `tinyint`, `smallint`,
+/// `int` and `bigint` columns by the discriminant. Values which do not
+/// correspond to any variant result in a deserialization error.
+///
+/// ```rust
+/// # use scylla_cql::macros::DeserializeValue;
+/// #[derive(DeserializeValue)]
+/// # #[scylla(crate = "scylla_cql")]
+/// enum Status {
+///     Active = 1,
+///     #[scylla(rename = "disabled")]
+///     Inactive = 2,
+/// }
+/// ```
+///
+/// `#[scylla(rename = "name_in_the_db")]`
+///
+/// Matches the variant by the given name instead of its Rust name.
+///
+/// # Newtypes
+///
+/// `#[scylla(transparent)]`
+///
+/// Deserializes a struct with exactly one field (named or unnamed) exactly
+/// as that field would be deserialized, including its type checks.
+///
+/// ```rust
+/// # use scylla_cql::macros::DeserializeValue;
+/// #[derive(DeserializeValue)]
+/// # #[scylla(crate = "scylla_cql")]
+/// #[scylla(transparent)]
+/// struct UserId(i64);
+/// ```
 pub use scylla_macros::DeserializeValue;
 
 /// Derive macro for the `DeserializeRow` trait that generates an implementation
@@ -448,6 +607,21 @@ pub use scylla_macros::DeserializeValue;
 /// column into the first field, second column into the second field and so on.
 /// It will still still verify that the column types and field types match.
 ///
+/// `#[scylla(allow_missing)]`
+///
+/// Treats all fields as if they were annotated with `default_when_missing`,
+/// so that the struct can be used with queries which select only some of
+/// its columns.
+///
+/// `#[scylla(schema_file = "path/to/schema.cql", table = "keyspace.table")]`
+///
+/// Checks the struct against the definition of the table in the schema file
+/// at compile time. Compilation fails if a field which isn't allowed to be
+/// missing has no corresponding column in the table, or if the type of
+/// the column can't be deserialized to the type of the field. Flattened fields
+/// are not checked, but their own structs may have this attribute.
+/// See the [`SerializeRow`](macro@crate::SerializeRow) derive for details.
+///
 /// ## Field attributes
 ///
 /// `#[scylla(skip)]`
@@ -455,13 +629,134 @@ pub use scylla_macros::DeserializeValue;
 /// The field will be completely ignored during deserialization and will
 /// be initialized with `Default::default()`.
 ///
+/// `#[scylla(default_when_missing)]`
+///
+/// If there is no column corresponding to this field, it will be initialized
+/// with `Default::default()`. `#[scylla(allow_missing)]` is accepted as an alias.
+/// With `enforce_order`, such fields must be placed at the end of the struct.
+///
+/// `#[scylla(default = "path")]`
+///
+/// Initializes the field by calling the given function instead of
+/// `Default::default()`, whenever a default value is used for it.
+/// Implies `default_when_missing`.
+///
 /// `#[scylla(rename = "field_name")`
 ///
 /// By default, the generated implementation will try to match the Rust field
 /// to a column with the same name. This attribute allows to match to a column
 /// with provided name.
+///
+/// `#[scylla(flatten)]`
+///
+/// The field must implement `DeserializeRow` itself. It is deserialized from
+/// all the columns which don't correspond to any other field, which allows
+/// a struct to embed the columns of another struct. Only one field per struct
+/// can have this attribute, and it can't be used together with `enforce_order`.
+///
+/// ```rust
+/// # use scylla_cql::macros::DeserializeRow;
+/// #[derive(DeserializeRow)]
+/// # #[scylla(crate = "scylla_cql")]
+/// struct Audit {
+///     created_by: String,
+///     #[scylla(default_when_missing)]
+///     updated_by: Option<String>,
+/// }
+///
+/// #[derive(DeserializeRow)]
+/// # #[scylla(crate = "scylla_cql")]
+/// struct Document {
+///     id: i32,
+///     #[scylla(flatten)]
+///     audit: Audit,
+/// }
+/// ```
+///
+/// `#[scylla(skip_schema_check)]`
+///
+/// Excludes the field from the check against the schema file.
 pub use scylla_macros::DeserializeRow;
 
+/// Derive macro for the [`Table`](crate::Table) trait, which describes the table
+/// that a Rust struct is stored in and generates the CQL statements
+/// for inserting, selecting, updating and deleting its rows.
+///
+/// At the moment, only structs with named fields are supported.
+/// The struct usually derives `SerializeRow` and `DeserializeRow` as well,
+/// so that it can be used with a [`Mapper`](crate::Mapper). The column names
+/// are taken from the fields in the same way as by those derives.
+///
+/// # Example
+///
+/// ```rust
+/// # use scylla::{DeserializeRow, SerializeRow, Table};
+/// #[derive(Table, SerializeRow, DeserializeRow)]
+/// #[scylla(keyspace = "ks", table = "events")]
+/// struct Event {
+///     #[scylla(partition_key)]
+///     device: i32,
+///     #[scylla(clustering_key)]
+///     time: i64,
+///     payload: Vec<u8>,
+/// }
+///
+/// assert_eq!(
+///     Event::INSERT,
+///     "INSERT INTO ks.events (device, time, payload) VALUES (?, ?, ?)"
+/// );
+/// assert_eq!(
+///     Event::SELECT,
+///     "SELECT device, time, payload FROM ks.events WHERE device = ? AND time = ?"
+/// );
+/// assert_eq!(
+///     Event::UPDATE,
+///     Some("UPDATE ks.events SET payload = ? WHERE device = ? AND time = ?")
+/// );
+/// assert_eq!(Event::DELETE, "DELETE FROM ks.events WHERE device = ? AND time = ?");
+/// ```
+///
+/// Names which are not lowercase identifiers are quoted in the generated statements.
+///
+/// # Struct attributes
+///
+/// `#[scylla(table = "name")]`
+///
+/// The name of the table. Required. It may be qualified with the keyspace,
+/// as in `"keyspace.name"`.
+///
+/// `#[scylla(keyspace = "name")]`
+///
+/// The keyspace of the table. If omitted, the statements refer to the table
+/// in the session's current keyspace.
+///
+/// `#[scylla(crate = crate_name)]`
+///
+/// By default, the code generated by the derive macro will refer to the items
+/// defined by the driver via the `::scylla` path. This attribute allows
+/// to provide an alternative path to the `scylla` crate.
+///
+/// # Field attributes
+///
+/// `#[scylla(partition_key)]`
+///
+/// The column is a part of the partition key. At least one field must have
+/// this attribute. Composite partition keys follow the order of the fields.
+///
+/// `#[scylla(clustering_key)]`
+///
+/// The column is a clustering column. Clustering columns follow the order
+/// of the fields.
+///
+/// `#[scylla(rename = "column_name")]`
+///
+/// Maps the field to the column with given name instead of its Rust name.
+///
+/// `#[scylla(skip)]`
+///
+/// The field is not mapped to any column.
+pub use scylla_macros::Table;
+
 /// #[derive(ValueList)] allows to pass struct as a list of values for a query
 ///
 /// ---
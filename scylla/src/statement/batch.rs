@@ -1,4 +1,5 @@
 use std::borrow::Cow;
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use crate::history::HistoryListener;
@@ -109,6 +110,18 @@ impl Batch {
         self.config.tracing
     }
 
+    /// Sets the custom payload sent along with this batch.
+    /// The payload is not interpreted by the driver; it is meant for server-side
+    /// extensions, such as a custom query handler. Requires protocol v4 or newer.
+    pub fn set_custom_payload(&mut self, custom_payload: Option<HashMap<String, Vec<u8>>>) {
+        self.config.custom_payload = custom_payload;
+    }
+
+    /// Gets the custom payload sent along with this batch
+    pub fn get_custom_payload(&self) -> Option<&HashMap<String, Vec<u8>>> {
+        self.config.custom_payload.as_ref()
+    }
+
     /// Sets the default timestamp for this batch in microseconds.
     /// If not None, it will replace the server side assigned timestamp as default timestamp for
     /// all the statements contained in the batch.
@@ -1,11 +1,13 @@
-use std::{sync::Arc, time::Duration};
+use std::{collections::HashMap, sync::Arc, time::Duration};
 
 use crate::transport::execution_profile::ExecutionProfileHandle;
 use crate::{history::HistoryListener, retry_policy::RetryPolicy};
 
 pub mod batch;
+pub mod paging_state;
 pub mod prepared_statement;
 pub mod query;
+pub mod query_builder;
 
 pub use crate::frame::types::{Consistency, SerialConsistency};
 
@@ -20,6 +22,8 @@ pub(crate) struct StatementConfig {
     pub(crate) tracing: bool,
     pub(crate) timestamp: Option<i64>,
     pub(crate) request_timeout: Option<Duration>,
+    pub(crate) request_deadline: Option<Duration>,
+    pub(crate) custom_payload: Option<HashMap<String, Vec<u8>>>,
 
     pub(crate) history_listener: Option<Arc<dyn HistoryListener>>,
 
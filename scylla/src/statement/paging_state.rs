@@ -0,0 +1,128 @@
+//! Saving the position of a paged query, to resume it later.
+
+use bytes::{Buf, BufMut, Bytes};
+use thiserror::Error;
+
+use crate::transport::errors::{BadQuery, QueryError};
+use crate::utils::hash::fnv1a_64;
+
+/// Position of a paged query after one of its pages, from which the query can be resumed
+/// with [`Session::query_iter_from`](crate::Session::query_iter_from)
+/// or [`Session::execute_iter_from`](crate::Session::execute_iter_from).
+///
+/// The paging state is opaque: it's the state returned by the database, bound to the text
+/// of the statement which produced it. It can be sent to other processes (e.g. as a cursor
+/// handed to web clients) with [`to_bytes`](PagingState::to_bytes) and
+/// [`from_bytes`](PagingState::from_bytes). Resuming another statement with it fails.
+/// The database doesn't protect against a paging state which was tampered with,
+/// so the bytes shouldn't be accepted from untrusted sources without authenticating them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PagingState {
+    raw: Bytes,
+    statement_hash: u64,
+}
+
+/// Error returned when reading a malformed [`PagingState`].
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("Invalid paging state: {0}")]
+pub struct InvalidPagingState(&'static str);
+
+const FORMAT_VERSION: u8 = 1;
+
+impl PagingState {
+    pub(crate) fn new(raw: Bytes, statement: &str) -> Self {
+        Self {
+            raw,
+            statement_hash: statement_hash(statement),
+        }
+    }
+
+    /// Returns the raw paging state to be sent with the given statement,
+    /// or an error if the paging state was produced by another statement.
+    pub(crate) fn raw_for(&self, statement: &str) -> Result<Bytes, QueryError> {
+        if self.statement_hash != statement_hash(statement) {
+            return Err(QueryError::BadQuery(BadQuery::Other(
+                "Paging state was produced by a different statement".to_owned(),
+            )));
+        }
+        Ok(self.raw.clone())
+    }
+
+    /// Serializes the paging state.
+    pub fn to_bytes(&self) -> Bytes {
+        let mut buf = Vec::with_capacity(9 + self.raw.len());
+        buf.put_u8(FORMAT_VERSION);
+        buf.put_u64(self.statement_hash);
+        buf.put_slice(&self.raw);
+        buf.into()
+    }
+
+    /// Deserializes a paging state serialized with [`to_bytes`](PagingState::to_bytes).
+    pub fn from_bytes(mut buf: &[u8]) -> Result<Self, InvalidPagingState> {
+        if buf.is_empty() || buf.get_u8() != FORMAT_VERSION {
+            return Err(InvalidPagingState("unsupported format version"));
+        }
+        if buf.remaining() < 8 {
+            return Err(InvalidPagingState("unexpected end of data"));
+        }
+        let statement_hash = buf.get_u64();
+        if buf.is_empty() {
+            return Err(InvalidPagingState("empty paging state"));
+        }
+        Ok(Self {
+            raw: Bytes::copy_from_slice(buf),
+            statement_hash,
+        })
+    }
+}
+
+fn statement_hash(statement: &str) -> u64 {
+    fnv1a_64(statement.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use bytes::Bytes;
+
+    use super::{InvalidPagingState, PagingState};
+    use crate::test_utils::setup_tracing;
+    use crate::transport::errors::{BadQuery, QueryError};
+
+    const STATEMENT: &str = "SELECT a FROM ks.t";
+
+    #[test]
+    fn paging_state_serialization() {
+        setup_tracing();
+        let paging_state = PagingState::new(Bytes::from_static(b"state"), STATEMENT);
+        let bytes = paging_state.to_bytes();
+        assert_eq!(PagingState::from_bytes(&bytes), Ok(paging_state));
+
+        assert_eq!(
+            PagingState::from_bytes(&bytes[..9]),
+            Err(InvalidPagingState("empty paging state"))
+        );
+        assert_eq!(
+            PagingState::from_bytes(&bytes[..5]),
+            Err(InvalidPagingState("unexpected end of data"))
+        );
+        assert_eq!(
+            PagingState::from_bytes(&[2, 0, 0, 0, 0, 0, 0, 0, 0, 1]),
+            Err(InvalidPagingState("unsupported format version"))
+        );
+    }
+
+    #[test]
+    fn paging_state_is_bound_to_statement() {
+        setup_tracing();
+        let paging_state = PagingState::new(Bytes::from_static(b"state"), STATEMENT);
+        assert_eq!(
+            paging_state.raw_for(STATEMENT).unwrap(),
+            Bytes::from_static(b"state")
+        );
+        assert_matches!(
+            paging_state.raw_for("SELECT b FROM ks.t"),
+            Err(QueryError::BadQuery(BadQuery::Other(_)))
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use arc_swap::ArcSwap;
 use bytes::{Bytes, BytesMut};
 use scylla_cql::errors::{BadQuery, QueryError};
 use scylla_cql::frame::response::result::{
@@ -7,8 +8,10 @@ use scylla_cql::frame::types::RawValue;
 use scylla_cql::types::serialize::row::{RowSerializationContext, SerializeRow, SerializedValues};
 use scylla_cql::types::serialize::SerializationError;
 use smallvec::{smallvec, SmallVec};
+use std::collections::HashMap;
 use std::convert::TryInto;
-use std::sync::Arc;
+use std::ops::Deref;
+use std::sync::{Arc, Weak};
 use std::time::Duration;
 use thiserror::Error;
 use uuid::Uuid;
@@ -66,22 +69,24 @@ use crate::transport::partitioner::{Partitioner, PartitionerHasher, PartitionerN
 /// There are two reasons for this:
 ///
 /// ### CQL v4 protocol limitations
-/// The driver only supports CQL version 4.
-///
 /// In multi-client scenario, only the first client which reprepares the statement
 /// will receive the updated metadata from the server.
 /// The rest of the clients will still hold on the outdated metadata.
 /// In version 4 of CQL protocol there is currently no way for the server to notify other
 /// clients about prepared statement's metadata update.
+/// Version 5 of the protocol lets the server detect outdated result metadata,
+/// in which case it sends the new metadata along with the result. The statement
+/// (and all its copies) then adopts the new result metadata and its id.
 ///
 /// ### Client-side metadata immutability
-/// The decision was made to keep client-side metadata immutable.
+/// The decision was made to keep client-side metadata immutable, apart from
+/// the result metadata updated by the server in protocol v5.
 /// Mainly because of the CQLv4 limitations mentioned above. This means
 /// that metadata is not updated during statement repreparation.
 /// This raises two issues:
 /// * bound values serialization errors - since [`PreparedMetadata`] is not updated
-/// * result deserialization errors - when [`PreparedStatement::set_use_cached_result_metadata`] is enabled,
-///   since [`ResultMetadata`] is not updated
+/// * result deserialization errors - when [`PreparedStatement::set_use_cached_result_metadata`] is enabled
+///   in protocol v4, since [`ResultMetadata`] is not updated
 ///
 /// So, to mitigate those issues, drop the outdated [`PreparedStatement`] manually
 /// and prepare it again against the new schema.
@@ -100,10 +105,52 @@ pub struct PreparedStatement {
 #[derive(Debug)]
 struct PreparedStatementSharedData {
     metadata: PreparedMetadata,
-    result_metadata: ResultMetadata,
+    result_metadata: ArcSwap<VersionedResultMetadata>,
     statement: String,
 }
 
+/// Result metadata of a statement along with its id, returned by the database
+/// in protocol v5 and above.
+#[derive(Debug)]
+pub(crate) struct VersionedResultMetadata {
+    pub(crate) metadata: ResultMetadata,
+    pub(crate) id: Option<Bytes>,
+}
+
+/// Column specifications of the result set of a [`PreparedStatement`].
+///
+/// They stay unchanged even if the statement adopts new result metadata in the meantime.
+#[derive(Debug, Clone)]
+pub struct ResultSetColSpecs {
+    result_metadata: Arc<VersionedResultMetadata>,
+}
+
+impl Deref for ResultSetColSpecs {
+    type Target = [ColumnSpec];
+
+    fn deref(&self) -> &[ColumnSpec] {
+        &self.result_metadata.metadata.col_specs
+    }
+}
+
+/// A reference to a [`PreparedStatement`] which doesn't keep it alive.
+/// It stays valid as long as the statement or any of its clones exists.
+#[derive(Debug, Clone)]
+pub(crate) struct WeakPreparedStatement {
+    shared: Weak<PreparedStatementSharedData>,
+}
+
+impl WeakPreparedStatement {
+    /// Returns the contents of the statement, unless it and all its clones were dropped.
+    pub(crate) fn get_statement(&self) -> Option<String> {
+        self.shared.upgrade().map(|shared| shared.statement.clone())
+    }
+
+    pub(crate) fn is_alive(&self) -> bool {
+        self.shared.strong_count() > 0
+    }
+}
+
 impl Clone for PreparedStatement {
     fn clone(&self) -> Self {
         Self {
@@ -119,11 +166,13 @@ impl Clone for PreparedStatement {
 }
 
 impl PreparedStatement {
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         id: Bytes,
         is_lwt: bool,
         metadata: PreparedMetadata,
         result_metadata: ResultMetadata,
+        result_metadata_id: Option<Bytes>,
         statement: String,
         page_size: Option<i32>,
         config: StatementConfig,
@@ -132,7 +181,10 @@ impl PreparedStatement {
             id,
             shared: Arc::new(PreparedStatementSharedData {
                 metadata,
-                result_metadata,
+                result_metadata: ArcSwap::from_pointee(VersionedResultMetadata {
+                    metadata: result_metadata,
+                    id: result_metadata_id,
+                }),
                 statement,
             }),
             prepare_tracing_ids: Vec::new(),
@@ -151,6 +203,13 @@ impl PreparedStatement {
         &self.shared.statement
     }
 
+    /// Creates a reference to this statement which doesn't keep it alive.
+    pub(crate) fn downgrade(&self) -> WeakPreparedStatement {
+        WeakPreparedStatement {
+            shared: Arc::downgrade(&self.shared),
+        }
+    }
+
     /// Sets the page size for this CQL query.
     pub fn set_page_size(&mut self, page_size: i32) {
         assert!(page_size > 0, "page size must be larger than 0");
@@ -344,6 +403,18 @@ impl PreparedStatement {
         self.config.tracing
     }
 
+    /// Sets the custom payload sent along with this statement.
+    /// The payload is not interpreted by the driver; it is meant for server-side
+    /// extensions, such as a custom query handler. Requires protocol v4 or newer.
+    pub fn set_custom_payload(&mut self, custom_payload: Option<HashMap<String, Vec<u8>>>) {
+        self.config.custom_payload = custom_payload;
+    }
+
+    /// Gets the custom payload sent along with this statement
+    pub fn get_custom_payload(&self) -> Option<&HashMap<String, Vec<u8>>> {
+        self.config.custom_payload.as_ref()
+    }
+
     /// Make use of cached metadata to decode results
     /// of the statement's execution.
     ///
@@ -391,6 +462,21 @@ impl PreparedStatement {
         self.config.request_timeout
     }
 
+    /// Sets the overall deadline for this statement.
+    /// Unlike the request timeout, the deadline is shared by all the attempts
+    /// made to execute the statement, i.e. retries and speculative executions,
+    /// and in case of iterators it covers fetching all the pages.
+    /// The remaining time is passed to the retry policy.
+    /// If None, the deadline from the execution profile will be applied.
+    pub fn set_request_deadline(&mut self, deadline: Option<Duration>) {
+        self.config.request_deadline = deadline
+    }
+
+    /// Gets the overall deadline associated with this statement.
+    pub fn get_request_deadline(&self) -> Option<Duration> {
+        self.config.request_deadline
+    }
+
     /// Sets the name of the partitioner used for this statement.
     pub(crate) fn set_partitioner_name(&mut self, partitioner_name: PartitionerName) {
         self.partitioner_name = partitioner_name;
@@ -411,14 +497,33 @@ impl PreparedStatement {
         &self.shared.metadata.pk_indexes
     }
 
-    /// Access metadata about the result of prepared statement returned by the database
-    pub(crate) fn get_result_metadata(&self) -> &ResultMetadata {
-        &self.shared.result_metadata
+    /// Access metadata about the result of prepared statement returned by the database,
+    /// along with its id, returned by the database in protocol v5 and above
+    pub(crate) fn get_result_metadata(&self) -> Arc<VersionedResultMetadata> {
+        self.shared.result_metadata.load_full()
+    }
+
+    /// Replaces the result metadata and its id with the ones sent by the database
+    /// after it noticed that they changed, e.g. because a column was added to the table.
+    /// The change is visible in all the copies of this statement.
+    pub(crate) fn update_result_metadata(&self, metadata: &ResultMetadata, id: Bytes) {
+        let mut metadata = metadata.clone();
+        // These are specific to the response which carried the metadata.
+        metadata.paging_state = None;
+        metadata.new_metadata_id = None;
+        self.shared
+            .result_metadata
+            .store(Arc::new(VersionedResultMetadata {
+                metadata,
+                id: Some(id),
+            }));
     }
 
     /// Access column specifications of the result set returned after the execution of this statement
-    pub fn get_result_set_col_specs(&self) -> &[ColumnSpec] {
-        &self.shared.result_metadata.col_specs
+    pub fn get_result_set_col_specs(&self) -> ResultSetColSpecs {
+        ResultSetColSpecs {
+            result_metadata: self.get_result_metadata(),
+        }
     }
 
     /// Get the name of the partitioner used for this statement.
@@ -598,14 +703,18 @@ impl<'ps> PartitionKey<'ps> {
 
 #[cfg(test)]
 mod tests {
+    use bytes::Bytes;
     use scylla_cql::{
         frame::response::result::{
-            ColumnSpec, ColumnType, PartitionKeyIndex, PreparedMetadata, TableSpec,
+            ColumnSpec, ColumnType, PartitionKeyIndex, PreparedMetadata, ResultMetadata, TableSpec,
         },
         types::serialize::row::SerializedValues,
     };
 
-    use crate::{prepared_statement::PartitionKey, test_utils::setup_tracing};
+    use crate::{
+        prepared_statement::{PartitionKey, PreparedStatement},
+        test_utils::setup_tracing,
+    };
 
     fn make_meta(
         cols: impl IntoIterator<Item = ColumnType>,
@@ -671,4 +780,35 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn test_result_metadata_update_is_shared_by_copies() {
+        setup_tracing();
+        let statement = PreparedStatement::new(
+            Bytes::from_static(b"id"),
+            false,
+            make_meta([], []),
+            ResultMetadata::default(),
+            Some(Bytes::from_static(b"old")),
+            "SELECT * FROM ks.t".to_owned(),
+            None,
+            Default::default(),
+        );
+        let copy = statement.clone();
+        let old_col_specs = copy.get_result_set_col_specs();
+
+        let mut new_metadata = ResultMetadata::default();
+        new_metadata.col_specs = make_meta([ColumnType::Int], []).col_specs;
+        new_metadata.paging_state = Some(Bytes::from_static(b"page"));
+        new_metadata.new_metadata_id = Some(Bytes::from_static(b"new"));
+        statement.update_result_metadata(&new_metadata, Bytes::from_static(b"new"));
+
+        let result_metadata = copy.get_result_metadata();
+        assert_eq!(result_metadata.id.as_deref(), Some(&b"new"[..]));
+        assert_eq!(result_metadata.metadata.paging_state, None);
+        assert_eq!(result_metadata.metadata.new_metadata_id, None);
+        assert_eq!(copy.get_result_set_col_specs().len(), 1);
+        // Column specifications obtained before the update stay unchanged.
+        assert!(old_col_specs.is_empty());
+    }
 }
@@ -3,6 +3,7 @@ use crate::frame::types::{Consistency, SerialConsistency};
 use crate::history::HistoryListener;
 use crate::retry_policy::RetryPolicy;
 use crate::transport::execution_profile::ExecutionProfileHandle;
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -98,6 +99,18 @@ impl Query {
         self.config.tracing
     }
 
+    /// Sets the custom payload sent along with this statement.
+    /// The payload is not interpreted by the driver; it is meant for server-side
+    /// extensions, such as a custom query handler. Requires protocol v4 or newer.
+    pub fn set_custom_payload(&mut self, custom_payload: Option<HashMap<String, Vec<u8>>>) {
+        self.config.custom_payload = custom_payload;
+    }
+
+    /// Gets the custom payload sent along with this statement
+    pub fn get_custom_payload(&self) -> Option<&HashMap<String, Vec<u8>>> {
+        self.config.custom_payload.as_ref()
+    }
+
     /// Sets the default timestamp for this statement in microseconds.
     /// If not None, it will replace the server side assigned timestamp as default timestamp
     /// If a statement contains a `USING TIMESTAMP` clause, calling this method won't change
@@ -124,6 +137,21 @@ impl Query {
         self.config.request_timeout
     }
 
+    /// Sets the overall deadline for this statement.
+    /// Unlike the request timeout, the deadline is shared by all the attempts
+    /// made to execute the statement, i.e. retries and speculative executions,
+    /// and in case of iterators it covers fetching all the pages.
+    /// The remaining time is passed to the retry policy.
+    /// If None, the deadline from the execution profile will be applied.
+    pub fn set_request_deadline(&mut self, deadline: Option<Duration>) {
+        self.config.request_deadline = deadline
+    }
+
+    /// Gets the overall deadline associated with this statement.
+    pub fn get_request_deadline(&self) -> Option<Duration> {
+        self.config.request_deadline
+    }
+
     /// Set the retry policy for this statement, overriding the one from execution profile if not None.
     #[inline]
     pub fn set_retry_policy(&mut self, retry_policy: Option<Arc<dyn RetryPolicy>>) {
@@ -0,0 +1,1102 @@
+//! Building CQL statements without concatenating strings.
+//!
+//! The builders produce a [`Query`] together with [`BoundValues`] - the values of its
+//! bind markers, in the order of the markers. Table and column names are quoted when needed,
+//! so they are always interpreted exactly as given (i.e. case-sensitively).
+//!
+//! ```rust
+//! # use scylla::Session;
+//! # use std::error::Error;
+//! # async fn check_only_compiles(session: &Session) -> Result<(), Box<dyn Error>> {
+//! use scylla::query_builder::{col, select_from, Order};
+//!
+//! let min_age: Option<i32> = Some(18);
+//!
+//! let mut select = select_from("users")
+//!     .keyspace("ks")
+//!     .columns(["name", "age"])
+//!     .filter(col("group").eq(3))
+//!     .order_by("age", Order::Desc)
+//!     .limit(10);
+//! if let Some(min_age) = min_age {
+//!     select = select.filter(col("age").ge(min_age));
+//! }
+//!
+//! let (query, values) = select.build();
+//! session.query(query, values).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt::Write;
+
+use scylla_cql::frame::value::Counter;
+use scylla_cql::types::serialize::row::{RowSerializationContext, SerializeRow};
+use scylla_cql::types::serialize::value::SerializeValue;
+use scylla_cql::types::serialize::{RowWriter, SerializationError};
+
+use crate::batch::{Batch, BatchType};
+use crate::query::Query;
+use crate::utils::pretty::{CommaSeparatedDisplayer, CqlIdentifierDisplayer};
+
+type BoxedValue = Box<dyn SerializeValue + Send + Sync>;
+
+/// Values of the bind markers of a built statement, in the order of the markers.
+#[derive(Default)]
+pub struct BoundValues(Vec<BoxedValue>);
+
+impl BoundValues {
+    /// Returns the number of values.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if the statement has no bind markers.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn push(&mut self, value: BoxedValue) {
+        self.0.push(value);
+    }
+}
+
+impl std::fmt::Debug for BoundValues {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BoundValues")
+            .field("len", &self.0.len())
+            .finish()
+    }
+}
+
+impl SerializeRow for BoundValues {
+    fn serialize(
+        &self,
+        ctx: &RowSerializationContext<'_>,
+        writer: &mut RowWriter,
+    ) -> Result<(), SerializationError> {
+        SerializeRow::serialize(&self.0, ctx, writer)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Starts building a relation (in `WHERE`) or a condition (in `IF`) on the given column.
+pub fn col(name: &str) -> Column {
+    Column(name.to_owned())
+}
+
+/// A column on which a [`Relation`] is built.
+#[derive(Debug, Clone)]
+pub struct Column(String);
+
+macro_rules! relation_ops {
+    ($($(#[$attr:meta])* $name:ident => $op:literal;)*) => {
+        $(
+            $(#[$attr])*
+            pub fn $name(self, value: impl SerializeValue + Send + Sync + 'static) -> Relation {
+                Relation {
+                    column: self.0,
+                    op: $op,
+                    value: Box::new(value),
+                }
+            }
+        )*
+    };
+}
+
+impl Column {
+    relation_ops! {
+        /// `column = ?`
+        eq => "=";
+        /// `column != ?`, only allowed in conditions.
+        ne => "!=";
+        /// `column < ?`
+        lt => "<";
+        /// `column <= ?`
+        le => "<=";
+        /// `column > ?`
+        gt => ">";
+        /// `column >= ?`
+        ge => ">=";
+        /// `column IN ?`, with a list (e.g. a `Vec`) of the values.
+        in_list => "IN";
+        /// `column CONTAINS ?`, for collection columns.
+        contains => "CONTAINS";
+        /// `column CONTAINS KEY ?`, for map columns.
+        contains_key => "CONTAINS KEY";
+    }
+}
+
+/// A relation of a `WHERE` clause or a condition of an `IF` clause,
+/// comparing a column with a bound value. Built with [`col`].
+pub struct Relation {
+    column: String,
+    op: &'static str,
+    value: BoxedValue,
+}
+
+impl std::fmt::Debug for Relation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Relation")
+            .field("column", &self.column)
+            .field("op", &self.op)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Ordering of the rows of a [`Select`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Order {
+    /// Ascending order
+    Asc,
+    /// Descending order
+    Desc,
+}
+
+#[derive(Debug, Default)]
+struct TableName {
+    keyspace: Option<String>,
+    table: String,
+}
+
+impl TableName {
+    fn new(table: &str) -> Self {
+        Self {
+            keyspace: None,
+            table: table.to_owned(),
+        }
+    }
+
+    fn write(&self, query: &mut String) {
+        if let Some(keyspace) = &self.keyspace {
+            write!(query, "{}.", CqlIdentifierDisplayer(keyspace)).unwrap();
+        }
+        write!(query, "{}", CqlIdentifierDisplayer(&self.table)).unwrap();
+    }
+}
+
+// Writes `relations` joined with `AND` after the given keyword, binding their values.
+fn write_relations(
+    query: &mut String,
+    values: &mut BoundValues,
+    keyword: &str,
+    relations: Vec<Relation>,
+) {
+    for (i, relation) in relations.into_iter().enumerate() {
+        let separator = if i == 0 { keyword } else { " AND" };
+        write!(
+            query,
+            "{} {} {} ?",
+            separator,
+            CqlIdentifierDisplayer(&relation.column),
+            relation.op
+        )
+        .unwrap();
+        values.push(relation.value);
+    }
+}
+
+// Writes the `USING` clause of a write, binding its values.
+fn write_using(
+    query: &mut String,
+    values: &mut BoundValues,
+    ttl: Option<i32>,
+    timestamp: Option<i64>,
+) {
+    let mut keyword = " USING";
+    if let Some(ttl) = ttl {
+        write!(query, "{} TTL ?", keyword).unwrap();
+        values.push(Box::new(ttl));
+        keyword = " AND";
+    }
+    if let Some(timestamp) = timestamp {
+        write!(query, "{} TIMESTAMP ?", keyword).unwrap();
+        values.push(Box::new(timestamp));
+    }
+}
+
+// Writes the `IF` clause of an update or a delete, binding its values.
+fn write_conditions(
+    query: &mut String,
+    values: &mut BoundValues,
+    if_exists: bool,
+    conditions: Vec<Relation>,
+) {
+    if if_exists {
+        query.push_str(" IF EXISTS");
+    } else {
+        write_relations(query, values, " IF", conditions);
+    }
+}
+
+/// Starts building a `SELECT` statement reading from the given table.
+pub fn select_from(table: &str) -> Select {
+    Select {
+        table: TableName::new(table),
+        columns: Vec::new(),
+        relations: Vec::new(),
+        order_by: Vec::new(),
+        per_partition_limit: None,
+        limit: None,
+        allow_filtering: false,
+    }
+}
+
+/// Builder of a `SELECT` statement, created with [`select_from`].
+#[derive(Debug)]
+pub struct Select {
+    table: TableName,
+    columns: Vec<String>,
+    relations: Vec<Relation>,
+    order_by: Vec<(String, Order)>,
+    per_partition_limit: Option<i32>,
+    limit: Option<i32>,
+    allow_filtering: bool,
+}
+
+impl Select {
+    /// Sets the keyspace of the table. Without it, the keyspace of the session is used.
+    pub fn keyspace(mut self, keyspace: &str) -> Self {
+        self.table.keyspace = Some(keyspace.to_owned());
+        self
+    }
+
+    /// Adds a selected column. When no columns are added, all columns are selected.
+    pub fn column(mut self, name: &str) -> Self {
+        self.columns.push(name.to_owned());
+        self
+    }
+
+    /// Adds selected columns.
+    pub fn columns<'a>(mut self, names: impl IntoIterator<Item = &'a str>) -> Self {
+        self.columns.extend(names.into_iter().map(str::to_owned));
+        self
+    }
+
+    /// Adds a relation to the `WHERE` clause.
+    pub fn filter(mut self, relation: Relation) -> Self {
+        self.relations.push(relation);
+        self
+    }
+
+    /// Adds a column to the `ORDER BY` clause.
+    pub fn order_by(mut self, column: &str, order: Order) -> Self {
+        self.order_by.push((column.to_owned(), order));
+        self
+    }
+
+    /// Sets the `PER PARTITION LIMIT`.
+    pub fn per_partition_limit(mut self, limit: i32) -> Self {
+        self.per_partition_limit = Some(limit);
+        self
+    }
+
+    /// Sets the `LIMIT`.
+    pub fn limit(mut self, limit: i32) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Adds `ALLOW FILTERING`.
+    pub fn allow_filtering(mut self) -> Self {
+        self.allow_filtering = true;
+        self
+    }
+
+    /// Builds the statement and the values of its bind markers.
+    pub fn build(self) -> (Query, BoundValues) {
+        let mut query = String::from("SELECT ");
+        let mut values = BoundValues::default();
+
+        if self.columns.is_empty() {
+            query.push('*');
+        } else {
+            let columns = self.columns.iter().map(|c| CqlIdentifierDisplayer(c));
+            write!(query, "{}", CommaSeparatedDisplayer(columns)).unwrap();
+        }
+        query.push_str(" FROM ");
+        self.table.write(&mut query);
+        write_relations(&mut query, &mut values, " WHERE", self.relations);
+
+        if !self.order_by.is_empty() {
+            let order_by = self.order_by.iter().map(|(column, order)| {
+                let order = match order {
+                    Order::Asc => "ASC",
+                    Order::Desc => "DESC",
+                };
+                format!("{} {}", CqlIdentifierDisplayer(column), order)
+            });
+            write!(query, " ORDER BY {}", CommaSeparatedDisplayer(order_by)).unwrap();
+        }
+        if let Some(limit) = self.per_partition_limit {
+            query.push_str(" PER PARTITION LIMIT ?");
+            values.push(Box::new(limit));
+        }
+        if let Some(limit) = self.limit {
+            query.push_str(" LIMIT ?");
+            values.push(Box::new(limit));
+        }
+        if self.allow_filtering {
+            query.push_str(" ALLOW FILTERING");
+        }
+
+        (Query::new(query), values)
+    }
+}
+
+/// Starts building an `INSERT` statement writing to the given table.
+pub fn insert_into(table: &str) -> Insert {
+    Insert {
+        table: TableName::new(table),
+        values: Vec::new(),
+        if_not_exists: false,
+        ttl: None,
+        timestamp: None,
+    }
+}
+
+/// Builder of an `INSERT` statement, created with [`insert_into`].
+pub struct Insert {
+    table: TableName,
+    values: Vec<(String, BoxedValue)>,
+    if_not_exists: bool,
+    ttl: Option<i32>,
+    timestamp: Option<i64>,
+}
+
+impl std::fmt::Debug for Insert {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let columns: Vec<_> = self.values.iter().map(|(column, _)| column).collect();
+        f.debug_struct("Insert")
+            .field("table", &self.table)
+            .field("columns", &columns)
+            .field("if_not_exists", &self.if_not_exists)
+            .field("ttl", &self.ttl)
+            .field("timestamp", &self.timestamp)
+            .finish()
+    }
+}
+
+impl Insert {
+    /// Sets the keyspace of the table. Without it, the keyspace of the session is used.
+    pub fn keyspace(mut self, keyspace: &str) -> Self {
+        self.table.keyspace = Some(keyspace.to_owned());
+        self
+    }
+
+    /// Sets the value of a column.
+    pub fn value(
+        mut self,
+        column: &str,
+        value: impl SerializeValue + Send + Sync + 'static,
+    ) -> Self {
+        self.values.push((column.to_owned(), Box::new(value)));
+        self
+    }
+
+    /// Adds `IF NOT EXISTS`, making the statement a lightweight transaction.
+    pub fn if_not_exists(mut self) -> Self {
+        self.if_not_exists = true;
+        self
+    }
+
+    /// Sets the time to live of the written values, in seconds.
+    pub fn ttl(mut self, ttl: i32) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Sets the timestamp of the write, in microseconds.
+    pub fn timestamp(mut self, timestamp: i64) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// Builds the statement and the values of its bind markers.
+    pub fn build(self) -> (Query, BoundValues) {
+        let mut query = String::from("INSERT INTO ");
+        let mut values = BoundValues::default();
+
+        self.table.write(&mut query);
+        let columns = self.values.iter().map(|(c, _)| CqlIdentifierDisplayer(c));
+        let markers = self.values.iter().map(|_| "?");
+        write!(
+            query,
+            " ({}) VALUES ({})",
+            CommaSeparatedDisplayer(columns),
+            CommaSeparatedDisplayer(markers)
+        )
+        .unwrap();
+        for (_, value) in self.values {
+            values.push(value);
+        }
+        if self.if_not_exists {
+            query.push_str(" IF NOT EXISTS");
+        }
+        write_using(&mut query, &mut values, self.ttl, self.timestamp);
+
+        (Query::new(query), values)
+    }
+}
+
+enum Assignment {
+    // column = ?
+    Set(String, BoxedValue),
+    // column = column + ?
+    Add(String, BoxedValue),
+    // column = column - ?
+    Remove(String, BoxedValue),
+    // column = ? + column
+    Prepend(String, BoxedValue),
+    // column[?] = ?
+    SetEntry(String, BoxedValue, BoxedValue),
+}
+
+/// Starts building an `UPDATE` statement modifying the given table.
+pub fn update(table: &str) -> Update {
+    Update {
+        table: TableName::new(table),
+        assignments: Vec::new(),
+        relations: Vec::new(),
+        conditions: Vec::new(),
+        if_exists: false,
+        ttl: None,
+        timestamp: None,
+    }
+}
+
+/// Builder of an `UPDATE` statement, created with [`update`].
+pub struct Update {
+    table: TableName,
+    assignments: Vec<Assignment>,
+    relations: Vec<Relation>,
+    conditions: Vec<Relation>,
+    if_exists: bool,
+    ttl: Option<i32>,
+    timestamp: Option<i64>,
+}
+
+impl std::fmt::Debug for Update {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Update")
+            .field("table", &self.table)
+            .field("relations", &self.relations)
+            .field("conditions", &self.conditions)
+            .field("if_exists", &self.if_exists)
+            .field("ttl", &self.ttl)
+            .field("timestamp", &self.timestamp)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Update {
+    /// Sets the keyspace of the table. Without it, the keyspace of the session is used.
+    pub fn keyspace(mut self, keyspace: &str) -> Self {
+        self.table.keyspace = Some(keyspace.to_owned());
+        self
+    }
+
+    /// Sets the value of a column: `column = ?`.
+    pub fn set(mut self, column: &str, value: impl SerializeValue + Send + Sync + 'static) -> Self {
+        self.assignments
+            .push(Assignment::Set(column.to_owned(), Box::new(value)));
+        self
+    }
+
+    /// Adds elements to a set or a map, or appends them to a list: `column = column + ?`.
+    pub fn add(mut self, column: &str, value: impl SerializeValue + Send + Sync + 'static) -> Self {
+        self.assignments
+            .push(Assignment::Add(column.to_owned(), Box::new(value)));
+        self
+    }
+
+    /// Removes elements from a collection: `column = column - ?`.
+    /// For maps, the value is a set of the removed keys.
+    pub fn remove(
+        mut self,
+        column: &str,
+        value: impl SerializeValue + Send + Sync + 'static,
+    ) -> Self {
+        self.assignments
+            .push(Assignment::Remove(column.to_owned(), Box::new(value)));
+        self
+    }
+
+    /// Prepends elements to a list: `column = ? + column`.
+    pub fn prepend(
+        mut self,
+        column: &str,
+        value: impl SerializeValue + Send + Sync + 'static,
+    ) -> Self {
+        self.assignments
+            .push(Assignment::Prepend(column.to_owned(), Box::new(value)));
+        self
+    }
+
+    /// Sets an element of a map, or of a list at the given index: `column[?] = ?`.
+    pub fn set_entry(
+        mut self,
+        column: &str,
+        key: impl SerializeValue + Send + Sync + 'static,
+        value: impl SerializeValue + Send + Sync + 'static,
+    ) -> Self {
+        self.assignments.push(Assignment::SetEntry(
+            column.to_owned(),
+            Box::new(key),
+            Box::new(value),
+        ));
+        self
+    }
+
+    /// Increments a counter column: `column = column + ?`.
+    pub fn increment(self, column: &str, delta: i64) -> Self {
+        self.add(column, Counter(delta))
+    }
+
+    /// Decrements a counter column: `column = column - ?`.
+    pub fn decrement(self, column: &str, delta: i64) -> Self {
+        self.remove(column, Counter(delta))
+    }
+
+    /// Adds a relation to the `WHERE` clause.
+    pub fn filter(mut self, relation: Relation) -> Self {
+        self.relations.push(relation);
+        self
+    }
+
+    /// Adds a condition to the `IF` clause, making the statement a lightweight transaction.
+    /// Replaces `IF EXISTS`, if it was set with [`if_exists`](Update::if_exists).
+    pub fn if_condition(mut self, condition: Relation) -> Self {
+        self.if_exists = false;
+        self.conditions.push(condition);
+        self
+    }
+
+    /// Adds `IF EXISTS`, making the statement a lightweight transaction.
+    /// Replaces the conditions added with [`if_condition`](Update::if_condition), if any.
+    pub fn if_exists(mut self) -> Self {
+        self.conditions.clear();
+        self.if_exists = true;
+        self
+    }
+
+    /// Sets the time to live of the written values, in seconds.
+    pub fn ttl(mut self, ttl: i32) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Sets the timestamp of the write, in microseconds.
+    pub fn timestamp(mut self, timestamp: i64) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// Builds the statement and the values of its bind markers.
+    pub fn build(self) -> (Query, BoundValues) {
+        let mut query = String::from("UPDATE ");
+        let mut values = BoundValues::default();
+
+        self.table.write(&mut query);
+        write_using(&mut query, &mut values, self.ttl, self.timestamp);
+        for (i, assignment) in self.assignments.into_iter().enumerate() {
+            query.push_str(if i == 0 { " SET " } else { ", " });
+            match assignment {
+                Assignment::Set(column, value) => {
+                    write!(query, "{} = ?", CqlIdentifierDisplayer(&column)).unwrap();
+                    values.push(value);
+                }
+                Assignment::Add(column, value) => {
+                    let column = CqlIdentifierDisplayer(&column);
+                    write!(query, "{} = {} + ?", column, column).unwrap();
+                    values.push(value);
+                }
+                Assignment::Remove(column, value) => {
+                    let column = CqlIdentifierDisplayer(&column);
+                    write!(query, "{} = {} - ?", column, column).unwrap();
+                    values.push(value);
+                }
+                Assignment::Prepend(column, value) => {
+                    let column = CqlIdentifierDisplayer(&column);
+                    write!(query, "{} = ? + {}", column, column).unwrap();
+                    values.push(value);
+                }
+                Assignment::SetEntry(column, key, value) => {
+                    write!(query, "{}[?] = ?", CqlIdentifierDisplayer(&column)).unwrap();
+                    values.push(key);
+                    values.push(value);
+                }
+            }
+        }
+        write_relations(&mut query, &mut values, " WHERE", self.relations);
+        write_conditions(&mut query, &mut values, self.if_exists, self.conditions);
+
+        (Query::new(query), values)
+    }
+}
+
+/// Starts building a `DELETE` statement removing rows or values from the given table.
+pub fn delete_from(table: &str) -> Delete {
+    Delete {
+        table: TableName::new(table),
+        columns: Vec::new(),
+        relations: Vec::new(),
+        conditions: Vec::new(),
+        if_exists: false,
+        timestamp: None,
+    }
+}
+
+/// Builder of a `DELETE` statement, created with [`delete_from`].
+#[derive(Debug)]
+pub struct Delete {
+    table: TableName,
+    columns: Vec<String>,
+    relations: Vec<Relation>,
+    conditions: Vec<Relation>,
+    if_exists: bool,
+    timestamp: Option<i64>,
+}
+
+impl Delete {
+    /// Sets the keyspace of the table. Without it, the keyspace of the session is used.
+    pub fn keyspace(mut self, keyspace: &str) -> Self {
+        self.table.keyspace = Some(keyspace.to_owned());
+        self
+    }
+
+    /// Adds a column whose value is deleted. When no columns are added, whole rows are deleted.
+    pub fn column(mut self, name: &str) -> Self {
+        self.columns.push(name.to_owned());
+        self
+    }
+
+    /// Adds a relation to the `WHERE` clause.
+    pub fn filter(mut self, relation: Relation) -> Self {
+        self.relations.push(relation);
+        self
+    }
+
+    /// Adds a condition to the `IF` clause, making the statement a lightweight transaction.
+    /// Replaces `IF EXISTS`, if it was set with [`if_exists`](Delete::if_exists).
+    pub fn if_condition(mut self, condition: Relation) -> Self {
+        self.if_exists = false;
+        self.conditions.push(condition);
+        self
+    }
+
+    /// Adds `IF EXISTS`, making the statement a lightweight transaction.
+    /// Replaces the conditions added with [`if_condition`](Delete::if_condition), if any.
+    pub fn if_exists(mut self) -> Self {
+        self.conditions.clear();
+        self.if_exists = true;
+        self
+    }
+
+    /// Sets the timestamp of the deletion, in microseconds.
+    pub fn timestamp(mut self, timestamp: i64) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// Builds the statement and the values of its bind markers.
+    pub fn build(self) -> (Query, BoundValues) {
+        let mut query = String::from("DELETE");
+        let mut values = BoundValues::default();
+
+        if !self.columns.is_empty() {
+            let columns = self.columns.iter().map(|c| CqlIdentifierDisplayer(c));
+            write!(query, " {}", CommaSeparatedDisplayer(columns)).unwrap();
+        }
+        query.push_str(" FROM ");
+        self.table.write(&mut query);
+        write_using(&mut query, &mut values, None, self.timestamp);
+        write_relations(&mut query, &mut values, " WHERE", self.relations);
+        write_conditions(&mut query, &mut values, self.if_exists, self.conditions);
+
+        (Query::new(query), values)
+    }
+}
+
+/// Starts building a batch of the given type from built statements.
+pub fn batch(batch_type: BatchType) -> BatchBuilder {
+    BatchBuilder {
+        batch: Batch::new(batch_type),
+        values: Vec::new(),
+    }
+}
+
+/// Builder of a [`Batch`], created with [`batch`].
+pub struct BatchBuilder {
+    batch: Batch,
+    values: Vec<BoundValues>,
+}
+
+impl BatchBuilder {
+    /// Appends a built statement, e.g. the result of [`Insert::build`].
+    pub fn statement(mut self, (query, values): (Query, BoundValues)) -> Self {
+        self.batch.append_statement(query);
+        self.values.push(values);
+        self
+    }
+
+    /// Builds the batch and the values of its statements.
+    pub fn build(self) -> (Batch, Vec<BoundValues>) {
+        (self.batch, self.values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use scylla_cql::frame::response::result::{
+        ColumnSpec, ColumnType, PreparedMetadata, TableSpec,
+    };
+    use scylla_cql::frame::value::Counter;
+    use scylla_cql::types::serialize::row::{RowSerializationContext, SerializedValues};
+
+    use super::{batch, col, delete_from, insert_into, select_from, update, BoundValues, Order};
+    use crate::batch::{BatchStatement, BatchType};
+    use crate::test_utils::setup_tracing;
+    use crate::utils::test_utils::{create_new_session_builder, unique_keyspace_name};
+
+    // Serializes the values as if all bind markers were of the given types.
+    fn serialize(values: &BoundValues, types: &[ColumnType]) -> Vec<Vec<u8>> {
+        let col_specs: Vec<_> = types
+            .iter()
+            .map(|typ| ColumnSpec {
+                table_spec: TableSpec::borrowed("ks", "t"),
+                name: "v".to_owned(),
+                typ: typ.clone(),
+            })
+            .collect();
+        let metadata = PreparedMetadata {
+            flags: 0,
+            col_count: col_specs.len(),
+            pk_indexes: Vec::new(),
+            col_specs,
+        };
+        let ctx = RowSerializationContext::from_prepared(&metadata);
+        let serialized = SerializedValues::from_serializable(&ctx, values).unwrap();
+        serialized
+            .iter()
+            .map(|value| value.as_value().unwrap().to_vec())
+            .collect()
+    }
+
+    #[test]
+    fn select_is_built_with_values_in_order() {
+        setup_tracing();
+        let (query, values) = select_from("Users")
+            .keyspace("ks")
+            .columns(["name", "Age"])
+            .filter(col("group").eq(1))
+            .filter(col("id").in_list(vec![2, 3]))
+            .filter(col("age").ge(4))
+            .order_by("age", Order::Desc)
+            .per_partition_limit(5)
+            .limit(6)
+            .allow_filtering()
+            .build();
+        assert_eq!(
+            query.contents,
+            "SELECT name,\"Age\" FROM ks.\"Users\" WHERE group = ? AND id IN ? AND age >= ? \
+             ORDER BY age DESC PER PARTITION LIMIT ? LIMIT ? ALLOW FILTERING"
+        );
+        let list = ColumnType::List(Box::new(ColumnType::Int));
+        let types = [
+            ColumnType::Int,
+            list,
+            ColumnType::Int,
+            ColumnType::Int,
+            ColumnType::Int,
+        ];
+        let serialized = serialize(&values, &types);
+        assert_eq!(serialized[0], 1_i32.to_be_bytes());
+        assert_eq!(serialized[2], 4_i32.to_be_bytes());
+        assert_eq!(serialized[4], 6_i32.to_be_bytes());
+
+        let (query, values) = select_from("t").build();
+        assert_eq!(query.contents, "SELECT * FROM t");
+        assert!(values.is_empty());
+    }
+
+    #[test]
+    fn insert_is_built_with_values_in_order() {
+        setup_tracing();
+        let (query, values) = insert_into("t")
+            .value("a", 1)
+            .value("b", "x")
+            .if_not_exists()
+            .ttl(2)
+            .timestamp(3_i64)
+            .build();
+        assert_eq!(
+            query.contents,
+            "INSERT INTO t (a,b) VALUES (?,?) IF NOT EXISTS USING TTL ? AND TIMESTAMP ?"
+        );
+        let types = [
+            ColumnType::Int,
+            ColumnType::Text,
+            ColumnType::Int,
+            ColumnType::BigInt,
+        ];
+        assert_eq!(
+            serialize(&values, &types),
+            vec![
+                1_i32.to_be_bytes().to_vec(),
+                b"x".to_vec(),
+                2_i32.to_be_bytes().to_vec(),
+                3_i64.to_be_bytes().to_vec(),
+            ]
+        );
+    }
+
+    #[test]
+    fn update_is_built_with_values_in_order() {
+        setup_tracing();
+        let (query, values) = update("t")
+            .timestamp(1)
+            .set("a", 2)
+            .add("tags", vec!["x"])
+            .remove("tags2", vec!["y"])
+            .prepend("list", vec![3])
+            .set_entry("map", "k", 4)
+            .increment("c", 5)
+            .filter(col("pk").eq(6))
+            .if_condition(col("a").ne(7))
+            .build();
+        assert_eq!(
+            query.contents,
+            "UPDATE t USING TIMESTAMP ? SET a = ?, tags = tags + ?, tags2 = tags2 - ?, \
+             list = ? + list, map[?] = ?, c = c + ? WHERE pk = ? IF a != ?"
+        );
+        assert_eq!(values.len(), 10);
+        let text_list = ColumnType::List(Box::new(ColumnType::Text));
+        let int_list = ColumnType::List(Box::new(ColumnType::Int));
+        let types = [
+            ColumnType::BigInt,
+            ColumnType::Int,
+            text_list.clone(),
+            text_list,
+            int_list,
+            ColumnType::Text,
+            ColumnType::Int,
+            ColumnType::Counter,
+            ColumnType::Int,
+            ColumnType::Int,
+        ];
+        let serialized = serialize(&values, &types);
+        assert_eq!(serialized[0], 1_i64.to_be_bytes());
+        assert_eq!(serialized[5], b"k");
+        assert_eq!(serialized[7], 5_i64.to_be_bytes());
+        assert_eq!(serialized[9], 7_i32.to_be_bytes());
+
+        let (query, _) = update("t")
+            .decrement("c", 1)
+            .filter(col("pk").eq(1))
+            .if_exists()
+            .build();
+        assert_eq!(
+            query.contents,
+            "UPDATE t SET c = c - ? WHERE pk = ? IF EXISTS"
+        );
+    }
+
+    #[test]
+    fn delete_is_built_with_values_in_order() {
+        setup_tracing();
+        let (query, values) = delete_from("t")
+            .column("a")
+            .column("weird \"name\"")
+            .timestamp(1)
+            .filter(col("pk").eq(2))
+            .filter(col("ck").lt(3))
+            .if_condition(col("a").eq(4))
+            .build();
+        assert_eq!(
+            query.contents,
+            "DELETE a,\"weird \"\"name\"\"\" FROM t USING TIMESTAMP ? WHERE pk = ? AND ck < ? IF a = ?"
+        );
+        assert_eq!(values.len(), 4);
+
+        let (query, _) = delete_from("t").filter(col("pk").eq(1)).build();
+        assert_eq!(query.contents, "DELETE FROM t WHERE pk = ?");
+    }
+
+    #[test]
+    fn if_exists_and_conditions_replace_each_other() {
+        setup_tracing();
+        let (query, values) = delete_from("t")
+            .filter(col("pk").eq(1))
+            .if_condition(col("a").eq(2))
+            .if_exists()
+            .build();
+        assert_eq!(query.contents, "DELETE FROM t WHERE pk = ? IF EXISTS");
+        assert_eq!(values.len(), 1);
+
+        let (query, values) = update("t")
+            .set("b", 3)
+            .filter(col("pk").eq(1))
+            .if_exists()
+            .if_condition(col("a").eq(2))
+            .build();
+        assert_eq!(query.contents, "UPDATE t SET b = ? WHERE pk = ? IF a = ?");
+        assert_eq!(values.len(), 3);
+    }
+
+    #[test]
+    fn batch_is_built_from_statements() {
+        setup_tracing();
+        let (batch, values) = batch(BatchType::Unlogged)
+            .statement(insert_into("t").value("a", 1).build())
+            .statement(delete_from("t").filter(col("a").eq(2)).build())
+            .build();
+        assert!(matches!(batch.get_type(), BatchType::Unlogged));
+        let statements: Vec<_> = batch
+            .statements
+            .iter()
+            .map(|statement| match statement {
+                BatchStatement::Query(query) => query.contents.as_str(),
+                BatchStatement::PreparedStatement(_) => unreachable!(),
+            })
+            .collect();
+        assert_eq!(
+            statements,
+            vec!["INSERT INTO t (a) VALUES (?)", "DELETE FROM t WHERE a = ?"]
+        );
+        assert_eq!(values.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_query_builder() {
+        setup_tracing();
+        let session = create_new_session_builder().build().await.unwrap();
+        let ks = unique_keyspace_name();
+
+        session
+            .query(format!("CREATE KEYSPACE IF NOT EXISTS {ks} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}"), &[])
+            .await
+            .unwrap();
+        session
+            .query(
+                format!(
+                    r#"CREATE TABLE IF NOT EXISTS {ks}."Items" (pk int, ck int, "Name" text, tags set<text>, PRIMARY KEY (pk, ck))"#
+                ),
+                &[],
+            )
+            .await
+            .unwrap();
+        session
+            .query(
+                format!("CREATE TABLE IF NOT EXISTS {ks}.counters (pk int PRIMARY KEY, c counter)"),
+                &[],
+            )
+            .await
+            .unwrap();
+
+        let (batch, values) = batch(BatchType::Logged)
+            .statement(
+                insert_into("Items")
+                    .keyspace(&ks)
+                    .value("pk", 1)
+                    .value("ck", 1)
+                    .value("Name", "a")
+                    .build(),
+            )
+            .statement(
+                insert_into("Items")
+                    .keyspace(&ks)
+                    .value("pk", 1)
+                    .value("ck", 2)
+                    .value("Name", "b")
+                    .ttl(3600)
+                    .build(),
+            )
+            .build();
+        session.batch(&batch, values).await.unwrap();
+
+        let (query, values) = insert_into("Items")
+            .keyspace(&ks)
+            .value("pk", 1)
+            .value("ck", 1)
+            .value("Name", "c")
+            .if_not_exists()
+            .build();
+        let result = session.query(query, values).await.unwrap();
+        assert!(!result
+            .lwt_outcome::<(i32, i32, &str, Option<Vec<&str>>)>()
+            .unwrap()
+            .is_applied());
+
+        let (query, values) = update("Items")
+            .keyspace(&ks)
+            .add("tags", vec!["x", "y"])
+            .filter(col("pk").eq(1))
+            .filter(col("ck").in_list(vec![1, 2]))
+            .build();
+        session.query(query, values).await.unwrap();
+        let (query, values) = update("Items")
+            .keyspace(&ks)
+            .remove("tags", vec!["x"])
+            .filter(col("pk").eq(1))
+            .filter(col("ck").eq(2))
+            .build();
+        session.query(query, values).await.unwrap();
+
+        let (query, values) = select_from("Items")
+            .keyspace(&ks)
+            .columns(["ck", "Name", "tags"])
+            .filter(col("pk").eq(1))
+            .filter(col("ck").ge(1))
+            .order_by("ck", Order::Desc)
+            .limit(10)
+            .build();
+        let result = session.query(query, values).await.unwrap();
+        let rows: Vec<(i32, String, Vec<String>)> =
+            result.rows().unwrap().map(|row| row.unwrap()).collect();
+        assert_eq!(
+            rows,
+            vec![
+                (2, "b".to_owned(), vec!["y".to_owned()]),
+                (1, "a".to_owned(), vec!["x".to_owned(), "y".to_owned()]),
+            ]
+        );
+
+        let (query, values) = delete_from("Items")
+            .keyspace(&ks)
+            .filter(col("pk").eq(1))
+            .filter(col("ck").eq(1))
+            .build();
+        session.query(query, values).await.unwrap();
+        let (query, values) = select_from("Items")
+            .keyspace(&ks)
+            .filter(col("pk").eq(1))
+            .build();
+        let result = session.query(query, values).await.unwrap();
+        assert_eq!(result.rows_num().unwrap(), 1);
+
+        let (query, values) = update("counters")
+            .keyspace(&ks)
+            .increment("c", 5)
+            .filter(col("pk").eq(1))
+            .build();
+        session.query(query, values).await.unwrap();
+        let (query, values) = update("counters")
+            .keyspace(&ks)
+            .decrement("c", 2)
+            .filter(col("pk").eq(1))
+            .build();
+        session.query(query, values).await.unwrap();
+        let (query, values) = select_from("counters")
+            .keyspace(&ks)
+            .column("c")
+            .filter(col("pk").eq(1))
+            .build();
+        let result = session.query(query, values).await.unwrap();
+        let (c,): (Counter,) = result.single_row().unwrap();
+        assert_eq!(c, Counter(3));
+    }
+}
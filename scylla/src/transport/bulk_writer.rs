@@ -0,0 +1,399 @@
+//! Writing large numbers of rows with a single prepared statement.
+//!
+//! [`BulkWriter`] consumes a stream of rows and executes the statement for each
+//! of them concurrently, grouping the rows by the replica and shard owning them.
+//! The number of writes in flight is bounded, both per node and in total,
+//! and rows of the same partition can be packed into unlogged batches.
+//! Failed rows are reported, but don't stop the rest of the load.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+use futures::stream::FuturesUnordered;
+use futures::{pin_mut, Stream, StreamExt};
+use scylla_cql::errors::QueryError;
+use scylla_cql::types::serialize::row::SerializeRow;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use uuid::Uuid;
+
+use crate::batch::{Batch, BatchType};
+use crate::prepared_statement::PreparedStatement;
+use crate::routing::{Shard, Token};
+use crate::Session;
+
+/// Executes a prepared statement for each row of a stream, with bounded concurrency.
+///
+/// ```rust
+/// # use scylla::Session;
+/// # use std::error::Error;
+/// # async fn check_only_compiles(session: &Session) -> Result<(), Box<dyn Error>> {
+/// use futures::stream;
+/// use scylla::BulkWriter;
+///
+/// let insert = session
+///     .prepare("INSERT INTO ks.events (device, time, value) VALUES (?, ?, ?)")
+///     .await?;
+/// let rows = stream::iter((0..1_000_000).map(|i: i64| (i as i32 % 100, i, "value")));
+///
+/// let report = BulkWriter::new(session, insert)
+///     .concurrency_per_node(64)
+///     .unlogged_batches(Some(16))
+///     .write(rows)
+///     .await;
+/// for failure in &report.failures {
+///     eprintln!("Row #{} failed: {}", failure.row_index, failure.error);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct BulkWriter<'a> {
+    session: &'a Session,
+    statement: PreparedStatement,
+    concurrency_per_node: usize,
+    max_concurrency: usize,
+    max_batch_size: Option<usize>,
+}
+
+/// Summary of a [`BulkWriter::write`] call.
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct BulkWriteReport {
+    /// Number of rows written successfully.
+    pub rows_written: usize,
+
+    /// Number of unlogged batches sent, successfully or not.
+    pub batches_sent: usize,
+
+    /// Rows which couldn't be written, ordered by their index.
+    pub failures: Vec<RowWriteFailure>,
+}
+
+/// A row which couldn't be written by [`BulkWriter`].
+#[derive(Debug)]
+pub struct RowWriteFailure {
+    /// Position of the row in the input stream, starting from 0.
+    pub row_index: usize,
+
+    /// The error with which writing the row (or the batch containing it) failed.
+    pub error: QueryError,
+}
+
+struct PendingRow<V> {
+    index: usize,
+    token: Option<Token>,
+    values: V,
+}
+
+// Rows sent in a single request, i.e. a single row or an unlogged batch.
+struct Write<V> {
+    node: Option<Uuid>,
+    rows: Vec<PendingRow<V>>,
+}
+
+struct WriteResult {
+    row_indices: Vec<usize>,
+    batched: bool,
+    result: Result<(), QueryError>,
+}
+
+impl<'a> BulkWriter<'a> {
+    /// Creates a writer executing the given statement, with at most 32 writes
+    /// in flight per node and 1024 in total, and without batching.
+    pub fn new(session: &'a Session, statement: PreparedStatement) -> Self {
+        Self {
+            session,
+            statement,
+            concurrency_per_node: 32,
+            max_concurrency: 1024,
+            max_batch_size: None,
+        }
+    }
+
+    /// Sets the maximum number of writes in flight to a single node. The node
+    /// of a row is the first replica owning its token.
+    pub fn concurrency_per_node(mut self, concurrency: usize) -> Self {
+        self.concurrency_per_node = concurrency.max(1);
+        self
+    }
+
+    /// Sets the maximum number of writes in flight (or waiting for their node)
+    /// in total. It bounds the number of rows read ahead from the input stream.
+    pub fn max_concurrency(mut self, concurrency: usize) -> Self {
+        self.max_concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Enables packing rows of the same partition into unlogged batches
+    /// of at most `max_batch_size` rows. Rows are buffered per replica and shard
+    /// until `max_batch_size` of them are collected, so only rows of a partition
+    /// which arrive close to each other end up in the same batch.
+    /// `None`, the default, disables batching.
+    pub fn unlogged_batches(mut self, max_batch_size: Option<usize>) -> Self {
+        self.max_batch_size = max_batch_size.filter(|&size| size > 1);
+        self
+    }
+
+    /// Writes all the rows of the stream. Rows which fail, after the retries
+    /// decided by the statement's retry policy, are reported in the result.
+    pub async fn write<V, S>(&self, rows: S) -> BulkWriteReport
+    where
+        V: SerializeRow,
+        S: Stream<Item = V>,
+    {
+        let mut report = BulkWriteReport::default();
+        let rows = rows.enumerate();
+        pin_mut!(rows);
+        let mut rows_done = false;
+
+        let mut pending_rows: HashMap<Option<(Uuid, Shard)>, Vec<PendingRow<V>>> = HashMap::new();
+        let mut ready_writes: VecDeque<Write<V>> = VecDeque::new();
+        let mut node_limits: HashMap<Option<Uuid>, Arc<Semaphore>> = HashMap::new();
+        let mut in_flight = FuturesUnordered::new();
+
+        loop {
+            // Writes to nodes at their limit wait in the queue, without blocking
+            // the writes to other nodes queued after them.
+            let mut waiting_writes = VecDeque::new();
+            while in_flight.len() < self.max_concurrency {
+                let Some(write) = ready_writes.pop_front() else {
+                    break;
+                };
+                let node_limit = node_limits
+                    .entry(write.node)
+                    .or_insert_with(|| Arc::new(Semaphore::new(self.concurrency_per_node)));
+                match node_limit.clone().try_acquire_owned() {
+                    Ok(permit) => in_flight.push(self.send(write, permit)),
+                    Err(_) => waiting_writes.push_back(write),
+                }
+            }
+            waiting_writes.append(&mut ready_writes);
+            ready_writes = waiting_writes;
+            if rows_done && in_flight.is_empty() {
+                break;
+            }
+
+            // New rows are read only when there is room for them among the writes
+            // in flight or waiting for their node, so the rows read ahead are bounded
+            // by `max_concurrency` (and the batches being collected).
+            let read_rows =
+                !rows_done && in_flight.len() + ready_writes.len() < self.max_concurrency;
+            tokio::select! {
+                Some(write) = in_flight.next() => {
+                    report.record(write);
+                }
+                row = rows.next(), if read_rows => match row {
+                    Some((index, values)) => {
+                        let token = match self.statement.calculate_token(&values) {
+                            Ok(token) => token,
+                            Err(error) => {
+                                report.failures.push(RowWriteFailure { row_index: index, error });
+                                continue;
+                            }
+                        };
+                        let replica = token.and_then(|token| self.first_replica(token));
+                        let rows = pending_rows.entry(replica).or_default();
+                        rows.push(PendingRow { index, token, values });
+                        if rows.len() >= self.max_batch_size.unwrap_or(1) {
+                            let rows = std::mem::take(rows);
+                            ready_writes.extend(split_into_writes(replica, rows));
+                        }
+                    }
+                    None => {
+                        rows_done = true;
+                        for (replica, rows) in pending_rows.drain() {
+                            ready_writes.extend(split_into_writes(replica, rows));
+                        }
+                    }
+                },
+            }
+        }
+
+        report.failures.sort_by_key(|failure| failure.row_index);
+        report
+    }
+
+    fn first_replica(&self, token: Token) -> Option<(Uuid, Shard)> {
+        let table_spec = self.statement.get_table_spec()?;
+        self.session
+            .get_cluster_data()
+            .get_token_endpoints_iter(table_spec, token)
+            .next()
+            .map(|(node, shard)| (node.host_id, shard))
+    }
+
+    async fn send<V: SerializeRow>(
+        &self,
+        write: Write<V>,
+        _node_permit: OwnedSemaphorePermit,
+    ) -> WriteResult {
+        let row_indices = write.rows.iter().map(|row| row.index).collect();
+        let (batched, result) = match write.rows.as_slice() {
+            [row] => (
+                false,
+                self.session.execute(&self.statement, &row.values).await,
+            ),
+            rows => {
+                let mut batch = Batch::new(BatchType::Unlogged);
+                batch.config = self.statement.config.clone();
+                for _ in rows {
+                    batch.append_statement(self.statement.clone());
+                }
+                let values: Vec<&V> = rows.iter().map(|row| &row.values).collect();
+                (true, self.session.batch(&batch, values).await)
+            }
+        };
+        WriteResult {
+            row_indices,
+            batched,
+            result: result.map(|_| ()),
+        }
+    }
+}
+
+// Packs the rows of each partition into a single write.
+// Rows without a known token are never batched.
+fn split_into_writes<V>(replica: Option<(Uuid, Shard)>, rows: Vec<PendingRow<V>>) -> Vec<Write<V>> {
+    let node = replica.map(|(host_id, _)| host_id);
+    let mut writes: Vec<Write<V>> = Vec::new();
+    let mut partition_writes: HashMap<i64, usize> = HashMap::new();
+    for row in rows {
+        let partition = row.token.map(|token| token.value());
+        match partition.and_then(|partition| partition_writes.get(&partition)) {
+            Some(&write_idx) => writes[write_idx].rows.push(row),
+            None => {
+                if let Some(partition) = partition {
+                    partition_writes.insert(partition, writes.len());
+                }
+                writes.push(Write {
+                    node,
+                    rows: vec![row],
+                });
+            }
+        }
+    }
+    writes
+}
+
+impl BulkWriteReport {
+    fn record(&mut self, write: WriteResult) {
+        if write.batched {
+            self.batches_sent += 1;
+        }
+        match write.result {
+            Ok(()) => self.rows_written += write.row_indices.len(),
+            Err(error) => self
+                .failures
+                .extend(
+                    write
+                        .row_indices
+                        .into_iter()
+                        .map(|row_index| RowWriteFailure {
+                            row_index,
+                            error: error.clone(),
+                        }),
+                ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::stream;
+    use uuid::Uuid;
+
+    use super::{split_into_writes, BulkWriter, PendingRow};
+    use crate::frame::response::result::CqlValue;
+    use crate::routing::Token;
+    use crate::test_utils::{create_new_session_builder, setup_tracing};
+    use crate::utils::test_utils::unique_keyspace_name;
+
+    fn row(index: usize, token: Option<i64>) -> PendingRow<()> {
+        PendingRow {
+            index,
+            token: token.map(Token::new),
+            values: (),
+        }
+    }
+
+    #[test]
+    fn rows_are_grouped_by_partition() {
+        let node = Uuid::from_u128(1);
+        let rows = vec![
+            row(0, Some(10)),
+            row(1, Some(20)),
+            row(2, None),
+            row(3, Some(10)),
+            row(4, None),
+            row(5, Some(10)),
+        ];
+
+        let writes = split_into_writes(Some((node, 3)), rows);
+        let indices: Vec<Vec<usize>> = writes
+            .iter()
+            .map(|write| write.rows.iter().map(|row| row.index).collect())
+            .collect();
+        assert_eq!(indices, [vec![0, 3, 5], vec![1], vec![2], vec![4]]);
+        assert!(writes.iter().all(|write| write.node == Some(node)));
+    }
+
+    #[tokio::test]
+    async fn test_bulk_writer() {
+        setup_tracing();
+        let session = create_new_session_builder().build().await.unwrap();
+        let ks = unique_keyspace_name();
+
+        session
+            .query(format!("CREATE KEYSPACE IF NOT EXISTS {ks} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}"), &[])
+            .await
+            .unwrap();
+        session
+            .query(
+                format!(
+                    "CREATE TABLE IF NOT EXISTS {ks}.t (a int, b int, c text, PRIMARY KEY (a, b))"
+                ),
+                &[],
+            )
+            .await
+            .unwrap();
+        session.await_schema_agreement().await.unwrap();
+        let insert = session
+            .prepare(format!("INSERT INTO {ks}.t (a, b, c) VALUES (?, ?, ?)"))
+            .await
+            .unwrap();
+
+        let rows = (0..100).map(|i: i32| (i % 10, i, "row"));
+        let report = BulkWriter::new(&session, insert.clone())
+            .concurrency_per_node(4)
+            .max_concurrency(8)
+            .unlogged_batches(Some(5))
+            .write(stream::iter(rows))
+            .await;
+        assert_eq!(report.rows_written, 100);
+        assert!(report.batches_sent > 0);
+        assert!(report.failures.is_empty());
+
+        let (count,): (i64,) = session
+            .query(format!("SELECT COUNT(*) FROM {ks}.t"), &[])
+            .await
+            .unwrap()
+            .into_legacy_result()
+            .unwrap()
+            .single_row_typed()
+            .unwrap();
+        assert_eq!(count, 100);
+
+        // Rows which can't be serialized fail alone, the others are still written.
+        let rows = [
+            (CqlValue::Int(100), 100, "ok"),
+            (CqlValue::Text("bad".to_owned()), 101, "bad"),
+            (CqlValue::Int(102), 102, "ok"),
+        ];
+        let report = BulkWriter::new(&session, insert)
+            .write(stream::iter(rows))
+            .await;
+        assert_eq!(report.rows_written, 2);
+        assert_eq!(report.batches_sent, 0);
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(report.failures[0].row_index, 1);
+    }
+}
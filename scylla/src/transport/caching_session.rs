@@ -6,13 +6,18 @@ use crate::transport::iterator::RowIterator;
 use crate::transport::partitioner::PartitionerName;
 use crate::{QueryResult, Session};
 use bytes::Bytes;
-use dashmap::DashMap;
 use futures::future::try_join_all;
-use scylla_cql::frame::response::result::{PreparedMetadata, ResultMetadata};
+use scylla_cql::frame::response::event::SchemaChangeEvent;
+use scylla_cql::frame::response::result::{PreparedMetadata, ResultMetadata, TableSpec};
 use scylla_cql::types::serialize::batch::BatchValues;
 use scylla_cql::types::serialize::row::SerializeRow;
 use std::collections::hash_map::RandomState;
+use std::collections::{BTreeMap, HashMap};
 use std::hash::BuildHasher;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use tokio::sync::broadcast::{self, error::TryRecvError};
+use tracing::debug;
 
 /// Contains just the parts of a prepared statement that were returned
 /// from the database. All remaining parts (query string, page size,
@@ -24,10 +29,202 @@ struct RawPreparedStatementData {
     is_confirmed_lwt: bool,
     metadata: PreparedMetadata,
     result_metadata: ResultMetadata,
+    result_metadata_id: Option<Bytes>,
     partitioner_name: PartitionerName,
+    /// The table of the bound values or of the returned columns. Statements without
+    /// either don't depend on the schema of their table.
+    table: Option<TableSpec<'static>>,
+    /// Keeps the statement prepared by the session alive, so that the session prepares it
+    /// on the nodes which come up or join the cluster for as long as it's cached.
+    _prepared: PreparedStatement,
+}
+
+#[derive(Debug)]
+struct CachedStatement {
+    data: Arc<RawPreparedStatementData>,
+    last_used: u64,
+}
+
+/// The maximum number of keyspaces and tables whose last schema changes are tracked
+/// by a [`StatementCache`]. Above it, the changes are forgotten and treated as a change
+/// of all tables, so that the statements being prepared at that time aren't cached.
+const MAX_TRACKED_SCHEMA_CHANGES: usize = 1024;
+
+/// Prepared statements, evicted in the least recently used order
+/// and when the schema of their tables changes.
+#[derive(Debug)]
+struct StatementCache<S> {
+    statements: HashMap<String, CachedStatement, S>,
+    /// Keys of the statements by the time of their last use.
+    usage_order: BTreeMap<u64, String>,
+    clock: u64,
+    schema_changes: broadcast::Receiver<SchemaChangeEvent>,
+    /// Incremented with every schema change applied to the cache.
+    schema_generation: u64,
+    /// Generations of the last changes of each keyspace (affecting all its tables),
+    /// of each table and of all tables (when schema change events were missed).
+    keyspace_changes: HashMap<String, u64>,
+    table_changes: HashMap<(String, String), u64>,
+    all_tables_changed: u64,
+}
+
+impl<S: BuildHasher> StatementCache<S> {
+    fn new(hasher: S, schema_changes: broadcast::Receiver<SchemaChangeEvent>) -> Self {
+        Self {
+            statements: HashMap::with_hasher(hasher),
+            usage_order: BTreeMap::new(),
+            clock: 0,
+            schema_changes,
+            schema_generation: 0,
+            keyspace_changes: HashMap::new(),
+            table_changes: HashMap::new(),
+            all_tables_changed: 0,
+        }
+    }
+
+    fn get(&mut self, query: &str) -> Option<Arc<RawPreparedStatementData>> {
+        let statement = self.statements.get_mut(query)?;
+        let key = self.usage_order.remove(&statement.last_used)?;
+        self.clock += 1;
+        statement.last_used = self.clock;
+        self.usage_order.insert(self.clock, key);
+        Some(statement.data.clone())
+    }
+
+    /// Inserts the statement, evicting the least recently used ones to make room for it.
+    /// Returns the number of evicted statements.
+    fn insert(
+        &mut self,
+        query: String,
+        data: Arc<RawPreparedStatementData>,
+        max_capacity: usize,
+    ) -> usize {
+        if max_capacity == 0 {
+            return 0;
+        }
+        if let Some(previous) = self.statements.remove(&query) {
+            self.usage_order.remove(&previous.last_used);
+        }
+
+        let mut evicted = 0;
+        while self.statements.len() >= max_capacity {
+            let Some((_, oldest)) = self.usage_order.pop_first() else {
+                break;
+            };
+            self.statements.remove(&oldest);
+            evicted += 1;
+        }
+
+        self.clock += 1;
+        self.usage_order.insert(self.clock, query.clone());
+        self.statements.insert(
+            query,
+            CachedStatement {
+                data,
+                last_used: self.clock,
+            },
+        );
+        evicted
+    }
+
+    /// Evicts the statements whose tables were affected by the schema changes received
+    /// since the last call. Returns the number of evicted statements.
+    fn apply_schema_changes(&mut self) -> usize {
+        let mut evicted = 0;
+        loop {
+            let event = match self.schema_changes.try_recv() {
+                Ok(event) => Some(event),
+                Err(TryRecvError::Lagged(_)) => None,
+                Err(TryRecvError::Empty | TryRecvError::Closed) => return evicted,
+            };
+            self.schema_generation += 1;
+            let generation = self.schema_generation;
+            match event {
+                Some(event) => {
+                    debug!("Evicting prepared statements affected by {:?}", event);
+                    evicted +=
+                        self.evict_where(|table| is_affected_by_schema_change(table, &event));
+                    match event {
+                        SchemaChangeEvent::TableChange {
+                            keyspace_name,
+                            object_name,
+                            ..
+                        } => {
+                            self.table_changes
+                                .insert((keyspace_name, object_name), generation);
+                        }
+                        SchemaChangeEvent::KeyspaceChange { keyspace_name, .. }
+                        | SchemaChangeEvent::TypeChange { keyspace_name, .. }
+                        | SchemaChangeEvent::FunctionChange { keyspace_name, .. }
+                        | SchemaChangeEvent::AggregateChange { keyspace_name, .. } => {
+                            self.keyspace_changes.insert(keyspace_name, generation);
+                        }
+                    }
+                    if self.keyspace_changes.len() + self.table_changes.len()
+                        > MAX_TRACKED_SCHEMA_CHANGES
+                    {
+                        self.keyspace_changes.clear();
+                        self.table_changes.clear();
+                        self.all_tables_changed = generation;
+                    }
+                }
+                None => {
+                    debug!("Missed schema change events, evicting all prepared statements");
+                    evicted += self.evict_where(|_| true);
+                    self.all_tables_changed = generation;
+                }
+            }
+        }
+    }
+
+    /// Checks if the schema of the table may have changed after the given generation,
+    /// i.e. if a statement using it, prepared since then, may have outdated metadata.
+    fn is_changed_since(&self, table: &TableSpec, generation: u64) -> bool {
+        let changed_after =
+            |change: Option<&u64>| change.map_or(false, |&change| change > generation);
+        self.all_tables_changed > generation
+            || changed_after(self.keyspace_changes.get(table.ks_name()))
+            || changed_after(
+                self.table_changes
+                    .get(&(table.ks_name().to_owned(), table.table_name().to_owned())),
+            )
+    }
+
+    fn evict_where(&mut self, predicate: impl Fn(&TableSpec) -> bool) -> usize {
+        let len_before = self.statements.len();
+        let usage_order = &mut self.usage_order;
+        self.statements.retain(|_, statement| {
+            let evict = statement.data.table.as_ref().map_or(false, &predicate);
+            if evict {
+                usage_order.remove(&statement.last_used);
+            }
+            !evict
+        });
+        len_before - self.statements.len()
+    }
+}
+
+fn is_affected_by_schema_change(table: &TableSpec, event: &SchemaChangeEvent) -> bool {
+    match event {
+        SchemaChangeEvent::TableChange {
+            keyspace_name,
+            object_name,
+            ..
+        } => table.ks_name() == keyspace_name && table.table_name() == object_name,
+        // User defined types, functions and aggregates can be used by any table of the keyspace.
+        SchemaChangeEvent::KeyspaceChange { keyspace_name, .. }
+        | SchemaChangeEvent::TypeChange { keyspace_name, .. }
+        | SchemaChangeEvent::FunctionChange { keyspace_name, .. }
+        | SchemaChangeEvent::AggregateChange { keyspace_name, .. } => {
+            table.ks_name() == keyspace_name
+        }
+    }
 }
 
 /// Provides auto caching while executing queries
+///
+/// Prepared statements are evicted from the cache when the schema of their keyspace or table
+/// changes, so that they're prepared again with up to date metadata.
 #[derive(Debug)]
 pub struct CachingSession<S = RandomState>
 where
@@ -35,10 +232,13 @@ where
 {
     session: Session,
     /// The prepared statement cache size
-    /// If a prepared statement is added while the limit is reached, the oldest prepared statement
-    /// is removed from the cache
+    /// If a prepared statement is added while the limit is reached, the least recently used
+    /// prepared statement is removed from the cache
     max_capacity: usize,
-    cache: DashMap<String, RawPreparedStatementData, S>,
+    cache: Mutex<StatementCache<S>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
 }
 
 impl<S> CachingSession<S>
@@ -46,11 +246,7 @@ where
     S: Default + BuildHasher + Clone,
 {
     pub fn from(session: Session, cache_size: usize) -> Self {
-        Self {
-            session,
-            max_capacity: cache_size,
-            cache: Default::default(),
-        }
+        Self::with_hasher(session, cache_size, Default::default())
     }
 }
 
@@ -61,10 +257,14 @@ where
     /// Builds a [`CachingSession`] from a [`Session`], a cache size, and a [`BuildHasher`].,
     /// using a customer hasher.
     pub fn with_hasher(session: Session, cache_size: usize, hasher: S) -> Self {
+        let schema_changes = session.subscribe_to_schema_changes();
         Self {
             session,
             max_capacity: cache_size,
-            cache: DashMap::with_hasher(hasher),
+            cache: Mutex::new(StatementCache::new(hasher, schema_changes)),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
         }
     }
 
@@ -163,13 +363,20 @@ where
     ) -> Result<PreparedStatement, QueryError> {
         let query = query.into();
 
-        if let Some(raw) = self.cache.get(&query.contents) {
+        let (cached, schema_generation) = {
+            let mut cache = self.lock_cache();
+            (cache.get(&query.contents), cache.schema_generation)
+        };
+
+        if let Some(raw) = cached {
+            self.hits.fetch_add(1, Ordering::Relaxed);
             let page_size = query.get_page_size();
             let mut stmt = PreparedStatement::new(
                 raw.id.clone(),
                 raw.is_confirmed_lwt,
                 raw.metadata.clone(),
                 raw.result_metadata.clone(),
+                raw.result_metadata_id.clone(),
                 query.contents,
                 page_size,
                 query.config,
@@ -177,39 +384,75 @@ where
             stmt.set_partitioner_name(raw.partitioner_name.clone());
             Ok(stmt)
         } else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
             let query_contents = query.contents.clone();
             let prepared = self.session.prepare(query).await?;
 
-            if self.max_capacity == self.cache.len() {
-                // Cache is full, remove the first entry
-                // Don't hold a reference into the map (that's why the to_string() is called)
-                // This is because the documentation of the remove fn tells us that it may deadlock
-                // when holding some sort of reference into the map
-                let query = self.cache.iter().next().map(|c| c.key().to_string());
-
-                // Don't inline this: https://stackoverflow.com/questions/69873846/an-owned-value-is-still-references-somehow
-                if let Some(q) = query {
-                    self.cache.remove(&q);
-                }
-            }
-
+            let result_metadata = prepared.get_result_metadata();
+            let table = prepared
+                .get_table_spec()
+                .or_else(|| {
+                    result_metadata
+                        .metadata
+                        .col_specs
+                        .first()
+                        .map(|spec| &spec.table_spec)
+                })
+                .map(TableSpec::to_owned);
             let raw = RawPreparedStatementData {
                 id: prepared.get_id().clone(),
                 is_confirmed_lwt: prepared.is_confirmed_lwt(),
                 metadata: prepared.get_prepared_metadata().clone(),
-                result_metadata: prepared.get_result_metadata().clone(),
+                result_metadata: result_metadata.metadata.clone(),
+                result_metadata_id: result_metadata.id.clone(),
                 partitioner_name: prepared.get_partitioner_name().clone(),
+                table,
+                _prepared: prepared.clone(),
             };
-            self.cache.insert(query_contents, raw);
+
+            let mut cache = self.lock_cache();
+            // If the schema of its table changed while the statement was being prepared,
+            // its metadata may be already outdated.
+            let is_outdated = raw.table.as_ref().map_or(false, |table| {
+                cache.is_changed_since(table, schema_generation)
+            });
+            if !is_outdated {
+                let evicted = cache.insert(query_contents, Arc::new(raw), self.max_capacity);
+                self.evictions.fetch_add(evicted as u64, Ordering::Relaxed);
+            }
 
             Ok(prepared)
         }
     }
 
+    /// Locks the cache, after applying the schema changes received since it was last locked.
+    fn lock_cache(&self) -> MutexGuard<'_, StatementCache<S>> {
+        let mut cache = self.cache.lock().unwrap();
+        let evicted = cache.apply_schema_changes();
+        self.evictions.fetch_add(evicted as u64, Ordering::Relaxed);
+        cache
+    }
+
     pub fn get_max_capacity(&self) -> usize {
         self.max_capacity
     }
 
+    /// Returns the number of statements which were found in the cache.
+    pub fn get_cache_hits_num(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of statements which weren't found in the cache and were prepared.
+    pub fn get_cache_misses_num(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of statements evicted from the cache, either to make room
+    /// for other statements or because the schema of their tables changed.
+    pub fn get_cache_evictions_num(&self) -> u64 {
+        self.evictions.load(Ordering::Relaxed)
+    }
+
     pub fn get_session(&self) -> &Session {
         &self.session
     }
@@ -217,6 +460,7 @@ where
 
 #[cfg(test)]
 mod tests {
+    use super::{StatementCache, MAX_TRACKED_SCHEMA_CHANGES};
     use crate::query::Query;
     use crate::test_utils::{create_new_session_builder, scylla_supports_tablets, setup_tracing};
     use crate::transport::partitioner::PartitionerName;
@@ -224,10 +468,32 @@ mod tests {
     use crate::{
         batch::{Batch, BatchStatement},
         prepared_statement::PreparedStatement,
-        CachingSession, Session,
+        CachingSession, QueryResult, Session,
     };
     use futures::TryStreamExt;
+    use scylla_cql::frame::response::event::{SchemaChangeEvent, SchemaChangeType};
+    use scylla_cql::frame::response::result::TableSpec;
+    use std::collections::hash_map::RandomState;
     use std::collections::BTreeSet;
+    use std::hash::BuildHasher;
+    use std::time::Duration;
+    use tokio::sync::broadcast;
+
+    impl<S: BuildHasher + Clone> CachingSession<S> {
+        fn cache_len(&self) -> usize {
+            self.cache.lock().unwrap().statements.len()
+        }
+
+        fn is_cached(&self, query: &str) -> bool {
+            self.cache.lock().unwrap().statements.contains_key(query)
+        }
+
+        fn clear_cache(&self) {
+            let mut cache = self.cache.lock().unwrap();
+            cache.statements.clear();
+            cache.usage_order.clear();
+        }
+    }
 
     async fn new_for_test(with_tablet_support: bool) -> Session {
         let session = create_new_session_builder()
@@ -278,15 +544,15 @@ mod tests {
             .unwrap();
 
         // Clear the cache because it now contains an insert
-        assert_eq!(session.cache.len(), 1);
+        assert_eq!(session.cache_len(), 1);
 
-        session.cache.clear();
+        session.clear_cache();
 
         session
     }
 
     /// Test that when the cache is full and a different query comes in, that query will be added
-    /// to the cache and a random query is removed
+    /// to the cache and the least recently used query is removed
     #[tokio::test]
     async fn test_full() {
         setup_tracing();
@@ -304,21 +570,126 @@ mod tests {
             .add_prepared_statement(&middle_query.into())
             .await
             .unwrap();
+        // Using the first query makes the middle one the least recently used
+        session
+            .add_prepared_statement(&first_query.into())
+            .await
+            .unwrap();
         session
             .add_prepared_statement(&last_query.into())
             .await
             .unwrap();
 
-        assert_eq!(2, session.cache.len());
+        assert_eq!(2, session.cache_len());
+        assert!(session.is_cached(first_query));
+        assert!(session.is_cached(last_query));
+        assert!(!session.is_cached(middle_query));
+
+        // The insert made in create_caching_session also counts
+        assert_eq!(session.get_cache_hits_num(), 1);
+        assert_eq!(session.get_cache_misses_num(), 4);
+        assert_eq!(session.get_cache_evictions_num(), 1);
+    }
+
+    /// Checks that only the schema changes of a statement's keyspace or table
+    /// outdate the statements prepared concurrently with them
+    #[test]
+    fn test_schema_changes_since_generation() {
+        setup_tracing();
+        let (sender, receiver) = broadcast::channel(2);
+        let mut cache = StatementCache::new(RandomState::new(), receiver);
+        let table = TableSpec::borrowed("ks", "t");
+        let table_change =
+            |keyspace_name: &str, object_name: &str| SchemaChangeEvent::TableChange {
+                change_type: SchemaChangeType::Updated,
+                keyspace_name: keyspace_name.to_owned(),
+                object_name: object_name.to_owned(),
+            };
+
+        let generation = cache.schema_generation;
+        sender.send(table_change("ks", "other")).unwrap();
+        sender.send(table_change("other", "t")).unwrap();
+        cache.apply_schema_changes();
+        assert!(!cache.is_changed_since(&table, generation));
+
+        sender.send(table_change("ks", "t")).unwrap();
+        cache.apply_schema_changes();
+        assert!(cache.is_changed_since(&table, generation));
+        let generation = cache.schema_generation;
+        assert!(!cache.is_changed_since(&table, generation));
+
+        sender
+            .send(SchemaChangeEvent::TypeChange {
+                change_type: SchemaChangeType::Created,
+                keyspace_name: "ks".to_owned(),
+                type_name: "typ".to_owned(),
+            })
+            .unwrap();
+        cache.apply_schema_changes();
+        assert!(cache.is_changed_since(&table, generation));
+        let generation = cache.schema_generation;
+
+        // Missed events may have changed any table.
+        for _ in 0..3 {
+            sender.send(table_change("other", "t")).unwrap();
+        }
+        cache.apply_schema_changes();
+        assert!(cache.is_changed_since(&table, generation));
+
+        // Tracking too many changes is treated as a change of all tables.
+        let generation = cache.schema_generation;
+        for i in 0..=MAX_TRACKED_SCHEMA_CHANGES {
+            sender.send(table_change("other", &i.to_string())).unwrap();
+            cache.apply_schema_changes();
+        }
+        assert!(cache.is_changed_since(&table, generation));
+        assert!(
+            cache.keyspace_changes.len() + cache.table_changes.len() <= MAX_TRACKED_SCHEMA_CHANGES
+        );
+    }
+
+    /// Checks that statements are evicted when the schema of their table changes,
+    /// and prepared again with the new metadata
+    #[tokio::test]
+    async fn test_schema_change_eviction() {
+        setup_tracing();
+        let session = create_caching_session().await;
+        session
+            .execute(
+                "CREATE TABLE IF NOT EXISTS other_table (a int primary key, b int)",
+                (),
+            )
+            .await
+            .unwrap();
+        session.clear_cache();
+
+        let select = "select * from test_table";
+        let other_select = "select * from other_table";
+        let has_column_c = |result: QueryResult| result.get_column_spec("c").is_some();
 
-        // This query should be in the cache
-        assert!(session.cache.get(last_query).is_some());
+        assert!(!has_column_c(session.execute(select, ()).await.unwrap()));
+        session.execute(other_select, ()).await.unwrap();
+        assert_eq!(session.cache_len(), 2);
 
-        // Either the first or middle query should be removed
-        let first_query_removed = session.cache.get(first_query).is_none();
-        let middle_query_removed = session.cache.get(middle_query).is_none();
+        session
+            .get_session()
+            .query("alter table test_table add c int", ())
+            .await
+            .unwrap();
+
+        // Schema change events are received asynchronously
+        let mut attempts = 0;
+        while session.is_cached(select) {
+            assert!(attempts < 100, "The statement wasn't evicted");
+            attempts += 1;
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            // Applies the events received so far
+            session.execute(other_select, ()).await.unwrap();
+        }
 
-        assert!(first_query_removed || middle_query_removed);
+        assert!(session.is_cached(other_select));
+        assert!(session.get_cache_evictions_num() >= 1);
+        assert!(has_column_c(session.execute(select, ()).await.unwrap()));
     }
 
     /// Checks that the same prepared statement is reused when executing the same query twice
@@ -331,7 +702,7 @@ mod tests {
             .await
             .unwrap();
 
-        assert_eq!(1, session.cache.len());
+        assert_eq!(1, session.cache_len());
         assert_eq!(1, result.rows_num().unwrap());
 
         let result = session
@@ -339,7 +710,7 @@ mod tests {
             .await
             .unwrap();
 
-        assert_eq!(1, session.cache.len());
+        assert_eq!(1, session.cache_len());
         assert_eq!(1, result.rows_num().unwrap());
     }
 
@@ -349,7 +720,7 @@ mod tests {
         setup_tracing();
         let session = create_caching_session().await;
 
-        assert!(session.cache.is_empty());
+        assert_eq!(0, session.cache_len());
 
         let iter = session
             .execute_iter("select * from test_table", &[])
@@ -359,7 +730,7 @@ mod tests {
         let rows = iter.try_collect::<Vec<_>>().await.unwrap().len();
 
         assert_eq!(1, rows);
-        assert_eq!(1, session.cache.len());
+        assert_eq!(1, session.cache_len());
     }
 
     /// Checks that caching works with execute_paged
@@ -368,14 +739,14 @@ mod tests {
         setup_tracing();
         let session = create_caching_session().await;
 
-        assert!(session.cache.is_empty());
+        assert_eq!(0, session.cache_len());
 
         let result = session
             .execute_paged("select * from test_table", &[], None)
             .await
             .unwrap();
 
-        assert_eq!(1, session.cache.len());
+        assert_eq!(1, session.cache_len());
         assert_eq!(1, result.rows_num().unwrap());
     }
 
@@ -387,6 +758,8 @@ mod tests {
             .execute("SELECT a, b FROM test_batch_table", ())
             .await
             .unwrap()
+            .into_legacy_result()
+            .unwrap()
             .rows_typed::<(i32, i32)>()
             .unwrap()
             .map(|r| r.unwrap())
@@ -596,6 +969,8 @@ mod tests {
             .execute("SELECT b, WRITETIME(b) FROM tbl", ())
             .await
             .unwrap()
+            .into_legacy_result()
+            .unwrap()
             .rows_typed_or_empty::<(i32, i64)>()
             .collect::<Result<Vec<_>, _>>()
             .unwrap();
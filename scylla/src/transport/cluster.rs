@@ -1,16 +1,18 @@
 /// Cluster manages up to date information and connections to database nodes
-use crate::frame::response::event::{Event, StatusChangeEvent};
+use crate::frame::response::event::{Event, SchemaChangeEvent, StatusChangeEvent};
 use crate::prepared_statement::TokenCalculationError;
 use crate::routing::{Shard, Token};
 use crate::transport::host_filter::HostFilter;
 use crate::transport::session::TABLET_CHANNEL_SIZE;
 use crate::transport::{
-    connection::{Connection, VerifiedKeyspaceName},
+    connection::{Connection, NegotiatedProtocolVersion, VerifiedKeyspaceName},
     connection_pool::PoolConfig,
     errors::QueryError,
+    in_flight_limiter::InFlightLimiter,
     node::Node,
     partitioner::PartitionerName,
     topology::{Keyspace, Metadata, MetadataReader},
+    ProtocolVersion,
 };
 
 use arc_swap::ArcSwap;
@@ -44,6 +46,8 @@ pub(crate) struct Cluster {
 
     refresh_channel: tokio::sync::mpsc::Sender<RefreshRequest>,
     use_keyspace_channel: tokio::sync::mpsc::Sender<UseKeyspaceRequest>,
+    schema_change_channel: tokio::sync::broadcast::Sender<SchemaChangeEvent>,
+    nodes_up_channel: tokio::sync::broadcast::Sender<Arc<Node>>,
 
     _worker_handle: RemoteHandle<()>,
 }
@@ -71,6 +75,7 @@ pub struct ClusterData {
     pub(crate) known_peers: HashMap<Uuid, Arc<Node>>, // Invariant: nonempty after Cluster::new()
     pub(crate) keyspaces: HashMap<String, Keyspace>,
     pub(crate) locator: ReplicaLocator,
+    pub(crate) protocol_version: Arc<NegotiatedProtocolVersion>,
 }
 
 /// Enables printing [ClusterData] struct in a neat way, skipping the clutter involved by
@@ -114,6 +119,12 @@ struct ClusterWorker {
     // Channel used to receive server events
     server_events_channel: tokio::sync::mpsc::Receiver<Event>,
 
+    // Channel used to pass schema change events to their subscribers
+    schema_change_channel: tokio::sync::broadcast::Sender<SchemaChangeEvent>,
+
+    // Channel used to pass the nodes which came up or joined the cluster to their subscribers
+    nodes_up_channel: tokio::sync::broadcast::Sender<Arc<Node>>,
+
     // Channel used to receive signals that control connection is broken
     control_connection_repair_channel: tokio::sync::broadcast::Receiver<()>,
 
@@ -131,6 +142,10 @@ struct ClusterWorker {
     // This value determines how frequently the cluster
     // worker will refresh the cluster metadata
     cluster_metadata_refresh_interval: Duration,
+
+    // Limits of requests in flight, whose per-node limits
+    // are forgotten when nodes leave the cluster
+    in_flight_limiter: Arc<InFlightLimiter>,
 }
 
 #[derive(Debug)]
@@ -145,6 +160,7 @@ struct UseKeyspaceRequest {
 }
 
 impl Cluster {
+    #[allow(clippy::too_many_arguments)]
     pub(crate) async fn new(
         known_nodes: Vec<KnownNode>,
         pool_config: PoolConfig,
@@ -153,12 +169,15 @@ impl Cluster {
         host_filter: Option<Arc<dyn HostFilter>>,
         cluster_metadata_refresh_interval: Duration,
         tablet_receiver: tokio::sync::mpsc::Receiver<(TableSpec<'static>, RawTablet)>,
+        in_flight_limiter: Arc<InFlightLimiter>,
     ) -> Result<Cluster, NewSessionError> {
         let (refresh_sender, refresh_receiver) = tokio::sync::mpsc::channel(32);
         let (use_keyspace_sender, use_keyspace_receiver) = tokio::sync::mpsc::channel(32);
         let (server_events_sender, server_events_receiver) = tokio::sync::mpsc::channel(32);
         let (control_connection_repair_sender, control_connection_repair_receiver) =
             tokio::sync::broadcast::channel(32);
+        let (schema_change_sender, _) = tokio::sync::broadcast::channel(32);
+        let (nodes_up_sender, _) = tokio::sync::broadcast::channel(32);
 
         let mut metadata_reader = MetadataReader::new(
             known_nodes,
@@ -194,6 +213,8 @@ impl Cluster {
 
             refresh_channel: refresh_receiver,
             server_events_channel: server_events_receiver,
+            schema_change_channel: schema_change_sender.clone(),
+            nodes_up_channel: nodes_up_sender.clone(),
             control_connection_repair_channel: control_connection_repair_receiver,
             tablets_channel: tablet_receiver,
 
@@ -202,15 +223,19 @@ impl Cluster {
 
             host_filter,
             cluster_metadata_refresh_interval,
+            in_flight_limiter,
         };
 
+        let task_tracker = worker.pool_config.connection_config.task_tracker.clone();
         let (fut, worker_handle) = worker.work().remote_handle();
-        tokio::spawn(fut);
+        tokio::spawn(task_tracker.track(fut));
 
         let result = Cluster {
             data: cluster_data,
             refresh_channel: refresh_sender,
             use_keyspace_channel: use_keyspace_sender,
+            schema_change_channel: schema_change_sender,
+            nodes_up_channel: nodes_up_sender,
             _worker_handle: worker_handle,
         };
 
@@ -221,6 +246,19 @@ impl Cluster {
         self.data.load_full()
     }
 
+    /// Returns a receiver of the schema change events sent by the cluster from now on.
+    pub(crate) fn subscribe_to_schema_changes(
+        &self,
+    ) -> tokio::sync::broadcast::Receiver<SchemaChangeEvent> {
+        self.schema_change_channel.subscribe()
+    }
+
+    /// Returns a receiver of the nodes which come up or join the cluster from now on.
+    /// The nodes are sent once their connection pools are created, but not necessarily filled.
+    pub(crate) fn subscribe_to_nodes_up(&self) -> tokio::sync::broadcast::Receiver<Arc<Node>> {
+        self.nodes_up_channel.subscribe()
+    }
+
     pub(crate) async fn refresh_metadata(&self) -> Result<(), QueryError> {
         let (response_sender, response_receiver) = tokio::sync::oneshot::channel();
 
@@ -229,13 +267,14 @@ impl Cluster {
                 response_chan: response_sender,
             })
             .await
-            .expect("Bug in Cluster::refresh_metadata sending");
-        // Other end of this channel is in ClusterWorker, can't be dropped while we have &self to Cluster with _worker_handle
+            .map_err(|_| QueryError::SessionClosed)?;
+        // Other end of this channel is in ClusterWorker, which can be dropped while we have &self
+        // to Cluster with _worker_handle only if the session was shut down
 
         response_receiver
             .await
-            .expect("Bug in Cluster::refresh_metadata receiving")
-        // ClusterWorker always responds
+            .map_err(|_| QueryError::SessionClosed)?
+        // ClusterWorker always responds, unless the session was shut down
     }
 
     pub(crate) async fn use_keyspace(
@@ -250,10 +289,14 @@ impl Cluster {
                 response_chan: response_sender,
             })
             .await
-            .expect("Bug in Cluster::use_keyspace sending");
-        // Other end of this channel is in ClusterWorker, can't be dropped while we have &self to Cluster with _worker_handle
+            .map_err(|_| QueryError::SessionClosed)?;
+        // Other end of this channel is in ClusterWorker, which can be dropped while we have &self
+        // to Cluster with _worker_handle only if the session was shut down
 
-        response_receiver.await.unwrap() // ClusterWorker always responds
+        // ClusterWorker always responds, unless the session was shut down
+        response_receiver
+            .await
+            .map_err(|_| QueryError::SessionClosed)?
     }
 }
 
@@ -400,9 +443,19 @@ impl ClusterData {
             known_peers: new_known_peers,
             keyspaces,
             locator,
+            protocol_version: pool_config.connection_config.protocol_version.clone(),
         }
     }
 
+    /// Returns the lowest protocol version negotiated with any of the cluster's nodes.
+    ///
+    /// If no node has rejected a version, this is the highest version
+    /// allowed by the session's configuration. Use [`Node::protocol_version`]
+    /// to find out which version a particular node uses.
+    pub fn get_protocol_version(&self) -> ProtocolVersion {
+        self.protocol_version.lowest()
+    }
+
     /// Access keyspaces details collected by the driver
     /// Driver collects various schema details like tables, partitioners, columns, types.
     /// They can be read using this method
@@ -626,12 +679,23 @@ impl ClusterWorker {
                                 // later as planned.
 
                                 match status {
-                                    StatusChangeEvent::Down(addr) => self.change_node_down_marker(addr, true),
-                                    StatusChangeEvent::Up(addr) => self.change_node_down_marker(addr, false),
+                                    StatusChangeEvent::Down(addr) => {
+                                        self.change_node_down_marker(addr, true);
+                                    }
+                                    StatusChangeEvent::Up(addr) => {
+                                        if let Some(node) = self.change_node_down_marker(addr, false) {
+                                            // It's fine if nobody is subscribed.
+                                            let _ = self.nodes_up_channel.send(node);
+                                        }
+                                    }
                                 }
                                 continue;
                             },
-                            _ => continue, // Don't go to refreshing
+                            Event::SchemaChange(schema_change) => {
+                                // It's fine if nobody is subscribed.
+                                let _ = self.schema_change_channel.send(schema_change);
+                                continue; // Don't go to refreshing
+                            }
                         }
                     } else {
                         // If server_events_channel was closed, than TopologyReader was dropped,
@@ -646,7 +710,7 @@ impl ClusterWorker {
 
                             let cluster_data = self.cluster_data.load_full();
                             let use_keyspace_future = Self::handle_use_keyspace_request(cluster_data, request);
-                            tokio::spawn(use_keyspace_future);
+                            tokio::spawn(self.pool_config.connection_config.task_tracker.track(use_keyspace_future));
                         },
                         None => return, // If use_keyspace_channel was closed then cluster was dropped, we can stop working
                     }
@@ -690,7 +754,8 @@ impl ClusterWorker {
         }
     }
 
-    fn change_node_down_marker(&mut self, addr: SocketAddr, is_down: bool) {
+    // Returns the node whose marker was changed, if it's known.
+    fn change_node_down_marker(&mut self, addr: SocketAddr, is_down: bool) -> Option<Arc<Node>> {
         let cluster_data = self.cluster_data.load_full();
 
         // We need to iterate through the whole map here, but there will rarely more than ~100 nodes,
@@ -704,11 +769,12 @@ impl ClusterWorker {
             Some(node) => node,
             None => {
                 warn!("Unknown node address {}", addr);
-                return;
+                return None;
             }
         };
 
         node.change_down_marker(is_down);
+        Some(node.clone())
     }
 
     async fn handle_use_keyspace_request(
@@ -780,12 +846,29 @@ impl ClusterWorker {
             .wait_until_all_pools_are_initialized()
             .await;
 
-        self.update_cluster_data(new_cluster_data);
+        self.update_cluster_data(new_cluster_data.clone());
+
+        for (host_id, node) in new_cluster_data.known_peers.iter() {
+            if !cluster_data.known_peers.contains_key(host_id) {
+                // It's fine if nobody is subscribed.
+                let _ = self.nodes_up_channel.send(node.clone());
+            }
+        }
 
         Ok(())
     }
 
     fn update_cluster_data(&mut self, new_cluster_data: Arc<ClusterData>) {
+        self.in_flight_limiter
+            .retain_nodes(|host_id| new_cluster_data.known_peers.contains_key(host_id));
+        let known_addrs: HashSet<SocketAddr> = new_cluster_data
+            .known_peers
+            .values()
+            .map(|node| node.address.into_inner())
+            .collect();
+        new_cluster_data
+            .protocol_version
+            .retain_nodes(|addr| known_addrs.contains(addr));
         self.cluster_data.store(new_cluster_data);
     }
 }
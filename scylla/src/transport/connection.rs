@@ -11,7 +11,7 @@ use scylla_cql::types::serialize::row::{RowSerializationContext, SerializedValue
 use socket2::{SockRef, TcpKeepalive};
 use tokio::io::{split, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader, BufWriter};
 use tokio::net::{TcpSocket, TcpStream};
-use tokio::sync::{mpsc, oneshot};
+use tokio::sync::{mpsc, oneshot, watch};
 use tokio::time::Instant;
 use tracing::{debug, error, trace, warn};
 use uuid::Uuid;
@@ -43,8 +43,9 @@ use std::{
 use super::errors::{BadKeyspaceName, DbError, QueryError};
 use super::iterator::RowIterator;
 use super::locator::tablets::{RawTablet, TabletParsingError};
-use super::query_result::SingleRowTypedError;
+use super::query_result::RowError;
 use super::session::AddressTranslator;
+use super::task_tracker::TaskTracker;
 use super::topology::{PeerEndpoint, UntranslatedEndpoint, UntranslatedPeer};
 use super::NodeAddr;
 #[cfg(feature = "cloud")]
@@ -56,8 +57,9 @@ use crate::frame::{
     self,
     request::{self, batch, execute, query, register, SerializableRequest},
     response::{event::Event, result, NonErrorResponse, Response, ResponseOpcode},
+    segment::{self, SegmentedFrameReader},
     server_event_type::EventType,
-    FrameParams, SerializedRequest,
+    FrameParams, ProtocolVersion, SerializedRequest,
 };
 use crate::query::Query;
 use crate::routing::ShardInfo;
@@ -85,10 +87,23 @@ pub(crate) struct Connection {
     config: ConnectionConfig,
     features: ConnectionFeatures,
     router_handle: Arc<RouterHandle>,
+    framing_sender: watch::Sender<Framing>,
+}
+
+/// Describes how frames are laid out on the wire.
+///
+/// Every connection starts with sending frames as they are. Starting with
+/// protocol v5, both sides switch to wrapping frames in segments
+/// once the server accepts the STARTUP request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Framing {
+    Frames,
+    Segments { compression: Option<Compression> },
 }
 
 struct RouterHandle {
     submit_channel: mpsc::Sender<Task>,
+    protocol_version: ProtocolVersion,
 
     // Each request send by `Connection::send_request` needs a unique request id.
     // This field is a monotonic generator of such ids.
@@ -112,8 +127,15 @@ impl RouterHandle {
         request: &impl SerializableRequest,
         compression: Option<Compression>,
         tracing: bool,
+        custom_payload: Option<&HashMap<String, Vec<u8>>>,
     ) -> Result<TaskResponse, QueryError> {
-        let serialized_request = SerializedRequest::make(request, compression, tracing)?;
+        let serialized_request = SerializedRequest::make(
+            request,
+            self.protocol_version,
+            compression,
+            tracing,
+            custom_payload,
+        )?;
         let request_id = self.allocate_request_id();
 
         let (response_sender, receiver) = oneshot::channel();
@@ -217,7 +239,6 @@ pub(crate) struct QueryResponse {
     pub(crate) response: Response,
     pub(crate) tracing_id: Option<Uuid>,
     pub(crate) warnings: Vec<String>,
-    #[allow(dead_code)] // This is not exposed to user (yet?)
     pub(crate) custom_payload: Option<HashMap<String, Vec<u8>>>,
 }
 
@@ -226,6 +247,7 @@ pub(crate) struct NonErrorQueryResponse {
     pub(crate) response: NonErrorResponse,
     pub(crate) tracing_id: Option<Uuid>,
     pub(crate) warnings: Vec<String>,
+    pub(crate) custom_payload: Option<HashMap<String, Vec<u8>>>,
 }
 
 impl QueryResponse {
@@ -234,6 +256,7 @@ impl QueryResponse {
             response: self.response.into_non_error_response()?,
             tracing_id: self.tracing_id,
             warnings: self.warnings,
+            custom_payload: self.custom_payload,
         })
     }
 
@@ -258,14 +281,15 @@ impl NonErrorQueryResponse {
     }
 
     pub(crate) fn into_query_result(self) -> Result<QueryResult, QueryError> {
-        let (rows, paging_state, col_specs, serialized_size) = match self.response {
+        let (raw_rows, rows_count, paging_state, col_specs, serialized_size) = match self.response {
             NonErrorResponse::Result(result::Result::Rows(rs)) => (
-                Some(rs.rows),
+                Some(rs.raw_rows),
+                rs.rows_count,
                 rs.metadata.paging_state,
                 rs.metadata.col_specs,
                 rs.serialized_size,
             ),
-            NonErrorResponse::Result(_) => (None, None, vec![], 0),
+            NonErrorResponse::Result(_) => (None, 0, None, vec![], 0),
             _ => {
                 return Err(QueryError::ProtocolError(
                     "Unexpected server response, expected Result or Error",
@@ -274,9 +298,11 @@ impl NonErrorQueryResponse {
         };
 
         Ok(QueryResult {
-            rows,
+            raw_rows,
+            rows_count,
             warnings: self.warnings,
             tracing_id: self.tracing_id,
+            custom_payload: self.custom_payload,
             paging_state,
             col_specs,
             serialized_size,
@@ -532,8 +558,69 @@ impl<'id: 'map, 'map> SelfIdentity<'id> {
     }
 }
 
+/// The protocol versions that new connections of a session are opened with.
+///
+/// Every node starts at the highest version allowed by the session's
+/// configuration. When a node rejects it, the version the node accepted
+/// instead is remembered for that node only, so that only its first
+/// connection has to find out which version it supports.
+#[derive(Debug)]
+pub(crate) struct NegotiatedProtocolVersion {
+    max_version: ProtocolVersion,
+    per_node: StdMutex<HashMap<SocketAddr, ProtocolVersion>>,
+}
+
+impl NegotiatedProtocolVersion {
+    pub(crate) fn new(max_version: ProtocolVersion) -> Self {
+        Self {
+            max_version,
+            per_node: Default::default(),
+        }
+    }
+
+    /// Returns the version to open new connections to the given node with.
+    pub(crate) fn get(&self, node_addr: SocketAddr) -> ProtocolVersion {
+        self.per_node
+            .lock()
+            .unwrap()
+            .get(&node_addr)
+            .copied()
+            .unwrap_or(self.max_version)
+    }
+
+    /// Returns the lowest version negotiated with any of the nodes.
+    pub(crate) fn lowest(&self) -> ProtocolVersion {
+        self.per_node
+            .lock()
+            .unwrap()
+            .values()
+            .copied()
+            .min()
+            .unwrap_or(self.max_version)
+    }
+
+    fn set(&self, node_addr: SocketAddr, version: ProtocolVersion) {
+        self.per_node.lock().unwrap().insert(node_addr, version);
+    }
+
+    /// Forgets the versions negotiated with nodes that are no longer part of the cluster.
+    pub(crate) fn retain_nodes(&self, mut is_known: impl FnMut(&SocketAddr) -> bool) {
+        self.per_node
+            .lock()
+            .unwrap()
+            .retain(|node_addr, _| is_known(node_addr));
+    }
+}
+
+impl Default for NegotiatedProtocolVersion {
+    fn default() -> Self {
+        Self::new(ProtocolVersion::LATEST)
+    }
+}
+
 #[derive(Clone)]
 pub(crate) struct ConnectionConfig {
+    pub(crate) protocol_version: Arc<NegotiatedProtocolVersion>,
     pub(crate) compression: Option<Compression>,
     pub(crate) tcp_nodelay: bool,
     pub(crate) tcp_keepalive_interval: Option<Duration>,
@@ -554,11 +641,15 @@ pub(crate) struct ConnectionConfig {
     pub(crate) tablet_sender: Option<mpsc::Sender<(TableSpec<'static>, RawTablet)>>,
 
     pub(crate) identity: SelfIdentity<'static>,
+
+    // Tracks the background tasks of the session, which are cancelled when it's shut down.
+    pub(crate) task_tracker: TaskTracker,
 }
 
 impl Default for ConnectionConfig {
     fn default() -> Self {
         Self {
+            protocol_version: Default::default(),
             compression: None,
             tcp_nodelay: true,
             tcp_keepalive_interval: None,
@@ -580,6 +671,8 @@ impl Default for ConnectionConfig {
             tablet_sender: None,
 
             identity: SelfIdentity::default(),
+
+            task_tracker: TaskTracker::default(),
         }
     }
 }
@@ -610,6 +703,7 @@ impl Connection {
     pub(crate) async fn new(
         addr: SocketAddr,
         source_port: Option<u16>,
+        protocol_version: ProtocolVersion,
         config: ConnectionConfig,
     ) -> Result<(Self, ErrorReceiver), QueryError> {
         let stream_connector = match source_port {
@@ -637,8 +731,11 @@ impl Connection {
         // Unbounded because it allows for synchronous pushes
         let (orphan_notification_sender, orphan_notification_receiver) = mpsc::unbounded_channel();
 
+        let (framing_sender, framing_receiver) = watch::channel(Framing::Frames);
+
         let router_handle = Arc::new(RouterHandle {
             submit_channel: sender,
+            protocol_version,
             request_id_generator: AtomicU64::new(0),
             orphan_notification_sender,
         });
@@ -650,6 +747,7 @@ impl Connection {
             error_sender,
             orphan_notification_receiver,
             router_handle.clone(),
+            framing_receiver,
             addr.ip(),
         )
         .await?;
@@ -660,6 +758,7 @@ impl Connection {
             features: Default::default(),
             connect_address: addr,
             router_handle,
+            framing_sender,
         };
 
         Ok((connection, error_receiver))
@@ -722,14 +821,14 @@ impl Connection {
         options: HashMap<Cow<'_, str>, Cow<'_, str>>,
     ) -> Result<Response, QueryError> {
         Ok(self
-            .send_request(&request::Startup { options }, false, false, None)
+            .send_request(&request::Startup { options }, false, false, None, None)
             .await?
             .response)
     }
 
     pub(crate) async fn get_options(&self) -> Result<Response, QueryError> {
         Ok(self
-            .send_request(&request::Options {}, false, false, None)
+            .send_request(&request::Options {}, false, false, None, None)
             .await?
             .response)
     }
@@ -742,6 +841,7 @@ impl Connection {
                 },
                 true,
                 query.config.tracing,
+                query.config.custom_payload.as_ref(),
                 None,
             )
             .await?;
@@ -755,6 +855,7 @@ impl Connection {
                     .prepared_flags_contain_lwt_mark(p.prepared_metadata.flags as u32),
                 p.prepared_metadata,
                 p.result_metadata,
+                p.result_metadata_id,
                 query.contents.clone(),
                 query.get_page_size(),
                 query.config.clone(),
@@ -794,8 +895,14 @@ impl Connection {
         &self,
         response: Option<Vec<u8>>,
     ) -> Result<QueryResponse, QueryError> {
-        self.send_request(&request::AuthResponse { response }, false, false, None)
-            .await
+        self.send_request(
+            &request::AuthResponse { response },
+            false,
+            false,
+            None,
+            None,
+        )
+        .await
     }
 
     pub(crate) async fn query_single_page(
@@ -821,7 +928,8 @@ impl Connection {
         serial_consistency: Option<SerialConsistency>,
     ) -> Result<QueryResult, QueryError> {
         let query: Query = query.into();
-        self.query_with_consistency(&query, consistency, serial_consistency, None)
+        let timestamp = query.get_timestamp();
+        self.query_with_consistency(&query, consistency, serial_consistency, timestamp, None)
             .await?
             .into_query_result()
     }
@@ -838,6 +946,7 @@ impl Connection {
                 .config
                 .determine_consistency(self.config.default_consistency),
             query.config.serial_consistency.flatten(),
+            query.get_timestamp(),
             paging_state,
         )
         .await
@@ -848,6 +957,7 @@ impl Connection {
         query: &Query,
         consistency: Consistency,
         serial_consistency: Option<SerialConsistency>,
+        timestamp: Option<i64>,
         paging_state: Option<Bytes>,
     ) -> Result<QueryResponse, QueryError> {
         let query_frame = query::Query {
@@ -859,12 +969,18 @@ impl Connection {
                 page_size: query.get_page_size(),
                 paging_state,
                 skip_metadata: false,
-                timestamp: query.get_timestamp(),
+                timestamp,
             },
         };
 
-        self.send_request(&query_frame, true, query.config.tracing, None)
-            .await
+        self.send_request(
+            &query_frame,
+            true,
+            query.config.tracing,
+            query.config.custom_payload.as_ref(),
+            None,
+        )
+        .await
     }
 
     #[allow(dead_code)]
@@ -882,6 +998,7 @@ impl Connection {
                 .config
                 .determine_consistency(self.config.default_consistency),
             prepared.config.serial_consistency.flatten(),
+            prepared.get_timestamp(),
             paging_state,
         )
         .await
@@ -893,16 +1010,19 @@ impl Connection {
         values: &SerializedValues,
         consistency: Consistency,
         serial_consistency: Option<SerialConsistency>,
+        timestamp: Option<i64>,
         paging_state: Option<Bytes>,
     ) -> Result<QueryResponse, QueryError> {
+        let result_metadata = prepared_statement.get_result_metadata();
         let execute_frame = execute::Execute {
             id: prepared_statement.get_id().to_owned(),
+            result_metadata_id: result_metadata.id.clone(),
             parameters: query::QueryParameters {
                 consistency,
                 serial_consistency,
                 values: Cow::Borrowed(values),
                 page_size: prepared_statement.get_page_size(),
-                timestamp: prepared_statement.get_timestamp(),
+                timestamp,
                 skip_metadata: prepared_statement.get_use_cached_result_metadata(),
                 paging_state,
             },
@@ -910,13 +1030,14 @@ impl Connection {
 
         let cached_metadata = prepared_statement
             .get_use_cached_result_metadata()
-            .then(|| prepared_statement.get_result_metadata());
+            .then_some(&result_metadata.metadata);
 
         let query_response = self
             .send_request(
                 &execute_frame,
                 true,
                 prepared_statement.config.tracing,
+                prepared_statement.config.custom_payload.as_ref(),
                 cached_metadata,
             )
             .await?;
@@ -930,7 +1051,7 @@ impl Connection {
             }
         }
 
-        match &query_response.response {
+        let response = match &query_response.response {
             Response::Error(frame::response::Error {
                 error: DbError::Unprepared { statement_id },
                 ..
@@ -944,6 +1065,7 @@ impl Connection {
                         &execute_frame,
                         true,
                         prepared_statement.config.tracing,
+                        prepared_statement.config.custom_payload.as_ref(),
                         cached_metadata,
                     )
                     .await?;
@@ -957,10 +1079,20 @@ impl Connection {
                     }
                 }
 
-                Ok(new_response)
+                new_response
+            }
+            _ => query_response,
+        };
+
+        // In protocol v5 the server notices that the result metadata id sent
+        // with the request is outdated, and sends the new metadata and its id.
+        if let Response::Result(result::Result::Rows(rows)) = &response.response {
+            if let Some(new_metadata_id) = &rows.metadata.new_metadata_id {
+                prepared_statement.update_result_metadata(&rows.metadata, new_metadata_id.clone());
             }
-            _ => Ok(query_response),
         }
+
+        Ok(response)
     }
 
     /// Executes a query and fetches its results over multiple pages, using
@@ -1013,6 +1145,7 @@ impl Connection {
                 .config
                 .determine_consistency(self.config.default_consistency),
             batch.config.serial_consistency.flatten(),
+            batch.get_timestamp(),
         )
         .await
     }
@@ -1023,6 +1156,7 @@ impl Connection {
         values: impl BatchValues,
         consistency: Consistency,
         serial_consistency: Option<SerialConsistency>,
+        timestamp: Option<i64>,
     ) -> Result<QueryResult, QueryError> {
         let batch = self.prepare_batch(init_batch, &values).await?;
 
@@ -1041,12 +1175,18 @@ impl Connection {
             batch_type: batch.get_type(),
             consistency,
             serial_consistency,
-            timestamp: batch.get_timestamp(),
+            timestamp,
         };
 
         loop {
             let query_response = self
-                .send_request(&batch_frame, true, batch.config.tracing, None)
+                .send_request(
+                    &batch_frame,
+                    true,
+                    batch.config.tracing,
+                    batch.config.custom_payload.as_ref(),
+                    None,
+                )
                 .await?;
 
             return match query_response.response {
@@ -1167,7 +1307,7 @@ impl Connection {
         };
 
         match self
-            .send_request(&register_frame, true, false, None)
+            .send_request(&register_frame, true, false, None, None)
             .await?
             .response
         {
@@ -1183,15 +1323,15 @@ impl Connection {
         let (version_id,): (Uuid,) = self
             .query_single_page(LOCAL_VERSION)
             .await?
-            .single_row_typed()
+            .single_row()
             .map_err(|err| match err {
-                SingleRowTypedError::RowsExpected(_) => {
+                RowError::RowsExpected(_) => {
                     QueryError::ProtocolError("Version query returned not rows")
                 }
-                SingleRowTypedError::BadNumberOfRows(_) => {
+                RowError::RowsEmpty | RowError::BadNumberOfRows(_) => {
                     QueryError::ProtocolError("system.local query returned a wrong number of rows")
                 }
-                SingleRowTypedError::FromRowError(_) => {
+                RowError::TypeCheckFailed(_) | RowError::DeserializationFailed(_) => {
                     QueryError::ProtocolError("Row is not uuid type as it should be")
                 }
             })?;
@@ -1203,6 +1343,7 @@ impl Connection {
         request: &impl SerializableRequest,
         compress: bool,
         tracing: bool,
+        custom_payload: Option<&HashMap<String, Vec<u8>>>,
         cached_metadata: Option<&ResultMetadata>,
     ) -> Result<QueryResponse, QueryError> {
         let compression = if compress {
@@ -1213,7 +1354,7 @@ impl Connection {
 
         let task_response = self
             .router_handle
-            .send_request(request, compression, tracing)
+            .send_request(request, compression, tracing, custom_payload)
             .await?;
 
         Self::parse_response(
@@ -1245,8 +1386,9 @@ impl Connection {
 
         let response = Response::deserialize(
             features,
+            task_response.params.protocol_version()?,
             task_response.opcode,
-            &mut &*body_with_ext.body,
+            &body_with_ext.body,
             cached_metadata,
         )?;
 
@@ -1258,6 +1400,7 @@ impl Connection {
         })
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn run_router(
         config: ConnectionConfig,
         stream: TcpStream,
@@ -1265,8 +1408,11 @@ impl Connection {
         error_sender: tokio::sync::oneshot::Sender<QueryError>,
         orphan_notification_receiver: mpsc::UnboundedReceiver<RequestId>,
         router_handle: Arc<RouterHandle>,
+        framing_receiver: watch::Receiver<Framing>,
         node_address: IpAddr,
     ) -> Result<RemoteHandle<()>, std::io::Error> {
+        let task_tracker = config.task_tracker.clone();
+
         #[cfg(feature = "ssl")]
         if let Some(ssl_config) = &config.ssl_config {
             let ssl = ssl_config.new_ssl()?;
@@ -1280,10 +1426,11 @@ impl Connection {
                 error_sender,
                 orphan_notification_receiver,
                 router_handle,
+                framing_receiver,
                 node_address,
             )
             .remote_handle();
-            tokio::task::spawn(task);
+            tokio::task::spawn(task_tracker.track(task));
             return Ok(handle);
         }
 
@@ -1294,13 +1441,15 @@ impl Connection {
             error_sender,
             orphan_notification_receiver,
             router_handle,
+            framing_receiver,
             node_address,
         )
         .remote_handle();
-        tokio::task::spawn(task);
+        tokio::task::spawn(task_tracker.track(task));
         Ok(handle)
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn router(
         config: ConnectionConfig,
         stream: (impl AsyncRead + AsyncWrite),
@@ -1308,6 +1457,7 @@ impl Connection {
         error_sender: tokio::sync::oneshot::Sender<QueryError>,
         orphan_notification_receiver: mpsc::UnboundedReceiver<RequestId>,
         router_handle: Arc<RouterHandle>,
+        framing_receiver: watch::Receiver<Framing>,
         node_address: IpAddr,
     ) {
         let (read_half, write_half) = split(stream);
@@ -1338,12 +1488,14 @@ impl Connection {
             BufReader::with_capacity(8192, read_half),
             &handler_map,
             config,
+            framing_receiver.clone(),
         );
         let w = Self::writer(
             BufWriter::with_capacity(8192, write_half),
             &handler_map,
             receiver,
             enable_write_coalescing,
+            framing_receiver,
         );
         let o = Self::orphaner(&handler_map, orphan_notification_receiver);
 
@@ -1371,9 +1523,25 @@ impl Connection {
         mut read_half: (impl AsyncRead + Unpin),
         handler_map: &StdMutex<ResponseHandlerMap>,
         config: ConnectionConfig,
+        mut framing_receiver: watch::Receiver<Framing>,
     ) -> Result<(), QueryError> {
+        let mut segmented_reader: Option<SegmentedFrameReader> = None;
         loop {
-            let (params, opcode, body) = frame::read_response_frame(&mut read_half).await?;
+            let (params, opcode, body) = match segmented_reader.as_mut() {
+                Some(segmented_reader) => {
+                    segmented_reader.read_response_frame(&mut read_half).await?
+                }
+                None => frame::read_response_frame(&mut read_half).await?,
+            };
+
+            // In protocol v5 and above, a successful response to STARTUP
+            // is the last frame the server sends without segment framing.
+            let switches_framing = segmented_reader.is_none()
+                && matches!(opcode, ResponseOpcode::Ready | ResponseOpcode::Authenticate)
+                && params
+                    .protocol_version()
+                    .map_or(false, |version| version.uses_segments());
+
             let response = TaskResponse {
                 params,
                 opcode,
@@ -1427,6 +1595,23 @@ impl Connection {
                     // been marked as orphaned
                 }
             }
+
+            if switches_framing {
+                // The server sends nothing more until the next request, which is sent
+                // only after `Connection` switches the framing - wait for that.
+                let framing = *framing_receiver
+                    .wait_for(|framing| *framing != Framing::Frames)
+                    .await
+                    .map_err(|_| {
+                        QueryError::IoError(Arc::new(std::io::Error::new(
+                            ErrorKind::Other,
+                            "Connection broken",
+                        )))
+                    })?;
+                if let Framing::Segments { compression } = framing {
+                    segmented_reader = Some(SegmentedFrameReader::new(compression));
+                }
+            }
         }
     }
 
@@ -1454,11 +1639,18 @@ impl Connection {
         handler_map: &StdMutex<ResponseHandlerMap>,
         mut task_receiver: mpsc::Receiver<Task>,
         enable_write_coalescing: bool,
+        framing_receiver: watch::Receiver<Framing>,
     ) -> Result<(), QueryError> {
+        // With segment framing, requests are gathered here and wrapped
+        // in segments before being written.
+        let mut frames = Vec::new();
+        let mut segments = Vec::new();
+
         // When the Connection object is dropped, the sender half
         // of the channel will be dropped, this task will return an error
         // and the whole worker will be stopped
         while let Some(mut task) = task_receiver.recv().await {
+            let framing = *framing_receiver.borrow();
             let mut num_requests = 0;
             let mut total_sent = 0;
             while let Some(stream_id) = Self::alloc_stream_id(handler_map, task.response_handler) {
@@ -1467,7 +1659,10 @@ impl Connection {
                 let req_data: &[u8] = req.get_data();
                 total_sent += req_data.len();
                 num_requests += 1;
-                write_half.write_all(req_data).await?;
+                match framing {
+                    Framing::Frames => write_half.write_all(req_data).await?,
+                    Framing::Segments { .. } => frames.extend_from_slice(req_data),
+                }
                 task = match task_receiver.try_recv() {
                     Ok(t) => t,
                     Err(_) if enable_write_coalescing => {
@@ -1483,6 +1678,12 @@ impl Connection {
                     Err(_) => break,
                 }
             }
+            if let Framing::Segments { compression } = framing {
+                segment::encode_segments(&frames, compression, &mut segments)?;
+                frames.clear();
+                write_half.write_all(&segments).await?;
+                segments.clear();
+            }
             trace!("Sending {} requests; {} bytes", num_requests, total_sent);
             write_half.flush().await?;
         }
@@ -1537,7 +1738,7 @@ impl Connection {
     ) -> Result<(), QueryError> {
         async fn issue_keepalive_query(router_handle: &RouterHandle) -> Result<(), QueryError> {
             router_handle
-                .send_request(&Options, None, false)
+                .send_request(&Options, None, false, None)
                 .await
                 .map(|_| ())
         }
@@ -1632,6 +1833,17 @@ impl Connection {
         self.features = features;
     }
 
+    pub(crate) fn get_protocol_version(&self) -> ProtocolVersion {
+        self.router_handle.protocol_version
+    }
+
+    // Must be called after the server accepts STARTUP, before any further request is sent.
+    fn switch_to_segments(&self) {
+        self.framing_sender.send_replace(Framing::Segments {
+            compression: self.config.compression,
+        });
+    }
+
     pub(crate) fn get_connect_address(&self) -> SocketAddr {
         self.connect_address
     }
@@ -1707,17 +1919,99 @@ async fn maybe_translated_addr(
 /// - registers for all event types using REGISTER request (if this is control connection).
 ///
 /// At the beginning, translates node's address, if it is subject to address translation.
+///
+/// If the node rejects the protocol version, the connection is opened again
+/// using the highest older version the node claims to support. The version
+/// that succeeded is remembered for this node in the config, so that its
+/// subsequent connections do not have to repeat the negotiation.
 pub(crate) async fn open_connection(
     endpoint: UntranslatedEndpoint,
     source_port: Option<u16>,
     config: &ConnectionConfig,
 ) -> Result<(Connection, ErrorReceiver), QueryError> {
+    let node_addr = endpoint.address().into_inner();
+
     /* Translate the address, if applicable. */
     let addr = maybe_translated_addr(endpoint, config.address_translator.as_deref()).await?;
 
+    let mut protocol_version = config.protocol_version.get(node_addr);
+    loop {
+        match open_translated_connection(addr, source_port, protocol_version, config).await {
+            Err(QueryError::DbError(DbError::ProtocolError, reason)) => {
+                let Some(next_version) = next_protocol_version(protocol_version, &reason) else {
+                    return Err(QueryError::DbError(DbError::ProtocolError, reason));
+                };
+                debug!(
+                    "Node {} rejected protocol {}: {}. Retrying with protocol {}",
+                    addr, protocol_version, reason, next_version
+                );
+                config.protocol_version.set(node_addr, next_version);
+                protocol_version = next_version;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Chooses the protocol version to retry with after the server rejected
+/// `rejected` with the given error message.
+///
+/// Servers usually list the versions they support in the message, e.g.:
+/// - Cassandra 3.x: "Invalid or unsupported protocol version (5); the lowest supported version is 3 and the greatest is 4",
+/// - Cassandra 4.x: "Invalid or unsupported protocol version (6); supported versions are (3/v3, 4/v4, 5/v5, 6/v6-beta)".
+///
+/// Returns `None` if the error is not a rejection of the protocol version,
+/// or if there is no version left to try.
+fn next_protocol_version(rejected: ProtocolVersion, reason: &str) -> Option<ProtocolVersion> {
+    if !reason.contains("Invalid or unsupported protocol version") {
+        return None;
+    }
+
+    let older_versions = || {
+        [
+            ProtocolVersion::V5,
+            ProtocolVersion::V4,
+            ProtocolVersion::V3,
+        ]
+        .into_iter()
+        .filter(move |v| *v < rejected)
+    };
+
+    let advertised: Vec<u8> = if let Some((_, greatest)) = reason.split_once("the greatest is ") {
+        let digits = greatest
+            .trim_start()
+            .split(|c: char| !c.is_ascii_digit())
+            .next()
+            .unwrap_or_default();
+        digits.parse().ok().into_iter().collect()
+    } else if let Some((_, list)) = reason.split_once("supported versions are (") {
+        list.split(')')
+            .next()
+            .unwrap_or_default()
+            .split(',')
+            // Beta versions are never chosen by the server unless explicitly requested.
+            .filter(|v| !v.contains("beta"))
+            .filter_map(|v| v.trim().split('/').next()?.parse().ok())
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    match advertised.iter().max() {
+        Some(&greatest) => older_versions().find(|v| v.as_u8() <= greatest),
+        None => older_versions().next(),
+    }
+}
+
+async fn open_translated_connection(
+    addr: SocketAddr,
+    source_port: Option<u16>,
+    protocol_version: ProtocolVersion,
+    config: &ConnectionConfig,
+) -> Result<(Connection, ErrorReceiver), QueryError> {
     /* Setup connection on TCP level and prepare for sending/receiving CQL frames. */
     let (mut connection, error_receiver) =
-        Connection::new(addr, source_port, config.clone()).await?;
+        Connection::new(addr, source_port, protocol_version, config.clone()).await?;
 
     /* Perform OPTIONS/SUPPORTED/STARTUP handshake. */
 
@@ -1780,7 +2074,15 @@ pub(crate) async fn open_connection(
     // Optional compression.
     if let Some(compression) = &config.compression {
         let compression_str = compression.as_str();
-        if supported_compression.iter().any(|c| c == compression_str) {
+        if *compression == Compression::Snappy && protocol_version.uses_segments() {
+            // Segment framing supports only LZ4.
+            tracing::warn!(
+                "Requested compression <{}> is not supported by protocol {}. Falling back to no compression",
+                compression_str,
+                protocol_version
+            );
+            connection.config.compression = None;
+        } else if supported_compression.iter().any(|c| c == compression_str) {
             // Compression is reported to be supported by the server,
             // request it from the server
             options.insert(
@@ -1799,6 +2101,11 @@ pub(crate) async fn open_connection(
 
     /* Send the STARTUP frame with all the requested options. */
     let result = connection.startup(options).await?;
+    if matches!(result, Response::Ready | Response::Authenticate(_))
+        && connection.get_protocol_version().uses_segments()
+    {
+        connection.switch_to_segments();
+    }
     match result {
         Response::Ready => {}
         Response::Authenticate(authenticate) => {
@@ -2129,23 +2436,29 @@ mod tests {
     use scylla_cql::frame::types;
     use scylla_proxy::{
         Condition, Node, Proxy, Reaction, RequestFrame, RequestOpcode, RequestReaction,
-        RequestRule, ResponseFrame, ShardAwareness,
+        RequestRule, ResponseFrame, ResponseOpcode, ShardAwareness,
     };
 
+    use bytes::BytesMut;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
     use tokio::select;
     use tokio::sync::mpsc;
 
-    use super::ConnectionConfig;
+    use super::{next_protocol_version, ConnectionConfig};
+    use crate::frame::segment;
     use crate::query::Query;
     use crate::test_utils::setup_tracing;
     use crate::transport::connection::open_connection;
     use crate::transport::node::ResolvedContactPoint;
     use crate::transport::topology::UntranslatedEndpoint;
+    use crate::transport::{Compression, ProtocolVersion};
     use crate::utils::test_utils::unique_keyspace_name;
-    use crate::{IntoTypedRows, SessionBuilder};
+    use crate::SessionBuilder;
     use futures::{StreamExt, TryStreamExt};
     use std::collections::HashMap;
     use std::net::SocketAddr;
+    use std::sync::atomic::{AtomicU64, Ordering};
     use std::sync::Arc;
     use std::time::Duration;
 
@@ -2362,9 +2675,10 @@ mod tests {
                 .unwrap()
                 .into_query_result()
                 .unwrap()
-                .rows()
+                .into_legacy_result()
+                .unwrap()
+                .rows_typed::<(i32, Vec<u8>)>()
                 .unwrap()
-                .into_typed::<(i32, Vec<u8>)>()
                 .collect::<Result<Vec<_>, _>>()
                 .unwrap();
             results.sort();
@@ -2538,4 +2852,280 @@ mod tests {
 
         let _ = proxy.finish().await;
     }
+
+    // Returns the length of the first frame in `buf` if the whole frame is there.
+    fn complete_frame_length(buf: &[u8]) -> Option<usize> {
+        let body_length = u32::from_be_bytes(buf.get(5..9)?.try_into().unwrap()) as usize;
+        (buf.len() >= 9 + body_length).then_some(9 + body_length)
+    }
+
+    // A fake node, which speaks just enough of the protocol to open a connection
+    // and respond to QUERY requests with a Void result. Protocol versions above
+    // `max_version` are rejected with a protocol error, the way Cassandra does it.
+    async fn serve_fake_node(listener: TcpListener, max_version: u8, rejections: Arc<AtomicU64>) {
+        loop {
+            let (socket, _) = listener.accept().await.unwrap();
+            tokio::spawn(serve_fake_node_connection(
+                socket,
+                max_version,
+                rejections.clone(),
+            ));
+        }
+    }
+
+    async fn serve_fake_node_connection(
+        mut socket: TcpStream,
+        max_version: u8,
+        rejections: Arc<AtomicU64>,
+    ) {
+        let mut compression = None;
+        // Set after the connection switches to segment framing.
+        let mut segment_compression: Option<Option<Compression>> = None;
+        let mut segment_payloads = BytesMut::new();
+
+        loop {
+            let request = match segment_compression {
+                None => {
+                    let mut request = vec![0u8; 9];
+                    if socket.read_exact(&mut request).await.is_err() {
+                        return;
+                    }
+                    let body_length = u32::from_be_bytes(request[5..9].try_into().unwrap());
+                    request.resize(9 + body_length as usize, 0);
+                    socket.read_exact(&mut request[9..]).await.unwrap();
+                    request
+                }
+                Some(compression) => loop {
+                    if let Some(length) = complete_frame_length(&segment_payloads) {
+                        break segment_payloads.split_to(length).to_vec();
+                    }
+                    match segment::read_segment(&mut socket, compression).await {
+                        Ok(segment) => segment_payloads.extend_from_slice(&segment.payload),
+                        Err(_) => return,
+                    }
+                },
+            };
+            let version = request[0];
+            let opcode = RequestOpcode::try_from(request[4]).unwrap();
+            let mut body = &request[9..];
+            // Echo the custom payload of uncompressed requests back to the client.
+            let custom_payload = (request[1] & 0x04 != 0 && request[1] & 0x01 == 0)
+                .then(|| types::read_bytes_map(&mut body).unwrap());
+
+            let mut response_body = Vec::new();
+            let mut response_flags = 0;
+            if let Some(custom_payload) = &custom_payload {
+                response_flags |= 0x04;
+                types::write_bytes_map(custom_payload, &mut response_body).unwrap();
+            }
+            let response_opcode = if version > max_version {
+                rejections.fetch_add(1, Ordering::Relaxed);
+                types::write_int(0x000A, &mut response_body);
+                types::write_string(
+                    &format!(
+                        "Invalid or unsupported protocol version ({}); \
+                        the lowest supported version is 3 and the greatest is {}",
+                        version, max_version
+                    ),
+                    &mut response_body,
+                )
+                .unwrap();
+                ResponseOpcode::Error
+            } else {
+                match opcode {
+                    RequestOpcode::Options => {
+                        let supported =
+                            HashMap::from([("COMPRESSION".to_string(), vec!["lz4".to_string()])]);
+                        types::write_string_multimap(&supported, &mut response_body).unwrap();
+                        ResponseOpcode::Supported
+                    }
+                    RequestOpcode::Startup => {
+                        let options = types::read_string_map(&mut body).unwrap();
+                        compression = options.get("COMPRESSION").map(|_| Compression::Lz4);
+                        ResponseOpcode::Ready
+                    }
+                    RequestOpcode::Query => {
+                        types::write_int(0x0001, &mut response_body);
+                        ResponseOpcode::Result
+                    }
+                    _ => panic!("Unexpected request: {:?}", opcode),
+                }
+            };
+
+            let mut response = vec![0x80 | version.min(max_version), response_flags];
+            response.extend_from_slice(&request[2..4]);
+            response.push(response_opcode as u8);
+            response.extend_from_slice(&(response_body.len() as u32).to_be_bytes());
+            response.extend_from_slice(&response_body);
+            match segment_compression {
+                None => socket.write_all(&response).await.unwrap(),
+                Some(compression) => {
+                    let mut segments = Vec::new();
+                    segment::encode_segments(&response, compression, &mut segments).unwrap();
+                    socket.write_all(&segments).await.unwrap();
+                }
+            }
+
+            if response_opcode == ResponseOpcode::Error {
+                return;
+            }
+            if response_opcode == ResponseOpcode::Ready && version >= 5 {
+                segment_compression = Some(compression);
+            }
+        }
+    }
+
+    #[tokio::test]
+    #[ntest::timeout(20000)]
+    async fn connection_negotiates_protocol_version() {
+        setup_tracing();
+        // A statement which is too large to fit into a single segment.
+        let large_statement = format!("INSERT INTO t (a) VALUES ('{}')", "a".repeat(300_000));
+
+        for (max_version, expected_version, expected_rejections) in [
+            (5, ProtocolVersion::V5, 0),
+            (4, ProtocolVersion::V4, 1),
+            (3, ProtocolVersion::V3, 1),
+        ] {
+            for compression in [None, Some(Compression::Lz4)] {
+                let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+                let addr = listener.local_addr().unwrap();
+                let rejections = Arc::new(AtomicU64::new(0));
+                let server =
+                    tokio::spawn(serve_fake_node(listener, max_version, rejections.clone()));
+
+                let config = ConnectionConfig {
+                    compression,
+                    ..Default::default()
+                };
+                let endpoint = UntranslatedEndpoint::ContactPoint(ResolvedContactPoint {
+                    address: addr,
+                    datacenter: None,
+                });
+                let (connection, _) = open_connection(endpoint.clone(), None, &config)
+                    .await
+                    .unwrap();
+                assert_eq!(connection.get_protocol_version(), expected_version);
+                assert_eq!(config.protocol_version.get(addr), expected_version);
+                assert_eq!(config.protocol_version.lowest(), expected_version);
+                assert_eq!(rejections.load(Ordering::Relaxed), expected_rejections);
+
+                for statement in ["SELECT a FROM t", large_statement.as_str()] {
+                    let result = connection.query_single_page(statement).await.unwrap();
+                    assert!(result.into_legacy_result().unwrap().rows.is_none());
+                }
+
+                // The negotiated version is remembered, so it is used right away.
+                let (connection, _) = open_connection(endpoint, None, &config).await.unwrap();
+                assert_eq!(connection.get_protocol_version(), expected_version);
+                assert_eq!(rejections.load(Ordering::Relaxed), expected_rejections);
+
+                // It is remembered only for that node.
+                let other_addr = SocketAddr::new(addr.ip(), addr.port().wrapping_add(1));
+                assert_eq!(config.protocol_version.get(other_addr), ProtocolVersion::V5);
+
+                server.abort();
+            }
+        }
+    }
+
+    #[tokio::test]
+    #[ntest::timeout(20000)]
+    async fn connection_sends_and_receives_custom_payload() {
+        setup_tracing();
+        let payload = HashMap::from([("tenant".to_string(), b"acme".to_vec())]);
+
+        for max_version in [5, 4] {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            let server = tokio::spawn(serve_fake_node(
+                listener,
+                max_version,
+                Arc::new(AtomicU64::new(0)),
+            ));
+
+            let endpoint = UntranslatedEndpoint::ContactPoint(ResolvedContactPoint {
+                address: addr,
+                datacenter: None,
+            });
+            let (connection, _) = open_connection(endpoint, None, &ConnectionConfig::default())
+                .await
+                .unwrap();
+            let connection = Arc::new(connection);
+
+            let mut query = Query::new("INSERT INTO t (a) VALUES (1)");
+            let result = connection.query_single_page(query.clone()).await.unwrap();
+            assert_eq!(result.custom_payload, None);
+
+            query.set_custom_payload(Some(payload.clone()));
+            let result = connection.query_single_page(query.clone()).await.unwrap();
+            assert_eq!(result.custom_payload.as_ref(), Some(&payload));
+
+            let iter = connection.clone().query_iter(query).await.unwrap();
+            assert_eq!(iter.get_custom_payload(), Some(&payload));
+
+            server.abort();
+        }
+    }
+
+    #[test]
+    fn next_protocol_version_parses_server_messages() {
+        let cases = [
+            // Cassandra 3.x
+            (
+                ProtocolVersion::V5,
+                "Invalid or unsupported protocol version (5); the lowest supported version is 3 and the greatest is 4",
+                Some(ProtocolVersion::V4),
+            ),
+            // Cassandra 2.1
+            (
+                ProtocolVersion::V5,
+                "Invalid or unsupported protocol version (5); the lowest supported version is 1 and the greatest is 3",
+                Some(ProtocolVersion::V3),
+            ),
+            // Cassandra 4.x, beta versions are skipped
+            (
+                ProtocolVersion::V5,
+                "Invalid or unsupported protocol version (5); supported versions are (3/v3, 4/v4, 5/v5-beta)",
+                Some(ProtocolVersion::V4),
+            ),
+            // ScyllaDB
+            (
+                ProtocolVersion::V5,
+                "Invalid or unsupported protocol version: 5",
+                Some(ProtocolVersion::V4),
+            ),
+            // The advertised version is never higher than the rejected one.
+            (
+                ProtocolVersion::V4,
+                "Invalid or unsupported protocol version (4); supported versions are (3/v3, 4/v4, 5/v5)",
+                Some(ProtocolVersion::V3),
+            ),
+            (
+                ProtocolVersion::V3,
+                "Invalid or unsupported protocol version: 3",
+                None,
+            ),
+            (
+                ProtocolVersion::V5,
+                "Invalid or unsupported protocol version (5); the lowest supported version is 1 and the greatest is 2",
+                None,
+            ),
+            // Other protocol errors don't cause a downgrade.
+            (
+                ProtocolVersion::V5,
+                "Invalid frame flags: 128",
+                None,
+            ),
+        ];
+
+        for (rejected, reason, expected) in cases {
+            assert_eq!(
+                next_protocol_version(rejected, reason),
+                expected,
+                "{}",
+                reason
+            );
+        }
+    }
 }
@@ -200,6 +200,7 @@ impl NodeConnectionPool {
         }
 
         let arced_endpoint = Arc::new(RwLock::new(endpoint));
+        let task_tracker = pool_config.connection_config.task_tracker.clone();
 
         let refiller = PoolRefiller::new(
             arced_endpoint.clone(),
@@ -211,7 +212,7 @@ impl NodeConnectionPool {
 
         let conns = refiller.get_shared_connections();
         let (fut, refiller_handle) = refiller.run(use_keyspace_request_receiver).remote_handle();
-        tokio::spawn(fut);
+        tokio::spawn(task_tracker.track(fut));
 
         Self {
             conns,
@@ -321,10 +322,14 @@ impl NodeConnectionPool {
                 response_sender,
             })
             .await
-            .expect("Bug in ConnectionKeeper::use_keyspace sending");
-        // Other end of this channel is in the Refiller, can't be dropped while we have &self to _refiller_handle
+            .map_err(|_| QueryError::SessionClosed)?;
+        // Other end of this channel is in the Refiller, which can be dropped while we have &self
+        // to _refiller_handle only if the session was shut down
 
-        response_receiver.await.unwrap() // NodePoolRefiller always responds
+        // NodePoolRefiller always responds, unless the session was shut down
+        response_receiver
+            .await
+            .map_err(|_| QueryError::SessionClosed)?
     }
 
     // Waits until the pool becomes initialized.
@@ -341,6 +346,20 @@ impl NodeConnectionPool {
         }
     }
 
+    // Waits until the pool has at least one working connection.
+    pub(crate) async fn wait_until_working(&self) {
+        loop {
+            // First, register for the notification
+            // so that we don't miss it
+            let notified = self.pool_updated_notify.notified();
+
+            if let MaybePoolConnections::Ready(_) = **self.conns.load() {
+                return;
+            }
+            notified.await;
+        }
+    }
+
     pub(crate) fn get_working_connections(&self) -> Result<Vec<Arc<Connection>>, QueryError> {
         self.with_connections(|pool_conns| match pool_conns {
             PoolConnections::NotSharded(conns) => conns.clone(),
@@ -1137,14 +1156,15 @@ impl PoolRefiller {
             Err(QueryError::IoError(io_error.unwrap()))
         };
 
-        tokio::task::spawn(async move {
+        let task_tracker = &self.pool_config.connection_config.task_tracker;
+        tokio::task::spawn(task_tracker.track(async move {
             let res = fut.await;
             match &res {
                 Ok(()) => debug!("[{}] Successfully changed current keyspace", address),
                 Err(err) => warn!("[{}] Failed to change keyspace: {:?}", address, err),
             }
             let _ = response_sender.send(res);
-        });
+        }));
     }
 
     // Requires the keyspace to be set
@@ -48,6 +48,8 @@ async fn insert_and_select<InsertT, SelectT>(
         .query(format!("SELECT val FROM {} WHERE p = 0", table_name), ())
         .await
         .unwrap()
+        .into_legacy_result()
+        .unwrap()
         .single_row_typed::<(SelectT,)>()
         .unwrap()
         .0;
@@ -89,6 +89,8 @@ where
             .query(select_values, &[])
             .await
             .unwrap()
+            .into_legacy_result()
+            .unwrap()
             .rows_typed::<(T,)>()
             .unwrap()
             .map(Result::unwrap)
@@ -207,6 +209,8 @@ async fn test_cql_varint() {
             .execute(&prepared_select, &[])
             .await
             .unwrap()
+            .into_legacy_result()
+            .unwrap()
             .rows_typed::<(CqlVarint,)>()
             .unwrap()
             .map(Result::unwrap)
@@ -282,6 +286,8 @@ async fn test_counter() {
             .query(select_values, (i as i32,))
             .await
             .unwrap()
+            .into_legacy_result()
+            .unwrap()
             .rows_typed::<(Counter,)>()
             .unwrap()
             .map(Result::unwrap)
@@ -358,6 +364,8 @@ async fn test_naive_date_04() {
             .query("SELECT val from chrono_naive_date_tests", &[])
             .await
             .unwrap()
+            .into_legacy_result()
+            .unwrap()
             .rows_typed::<(NaiveDate,)>()
             .unwrap()
             .next()
@@ -381,6 +389,8 @@ async fn test_naive_date_04() {
                 .query("SELECT val from chrono_naive_date_tests", &[])
                 .await
                 .unwrap()
+                .into_legacy_result()
+                .unwrap()
                 .single_row_typed::<(NaiveDate,)>()
                 .unwrap();
             assert_eq!(read_date, *naive_date);
@@ -420,6 +430,8 @@ async fn test_cql_date() {
             .query("SELECT val from cql_date_tests", &[])
             .await
             .unwrap()
+            .into_legacy_result()
+            .unwrap()
             .rows
             .unwrap()[0]
             .columns[0]
@@ -507,6 +519,8 @@ async fn test_date_03() {
             .query("SELECT val from time_date_tests", &[])
             .await
             .unwrap()
+            .into_legacy_result()
+            .unwrap()
             .first_row_typed::<(Date,)>()
             .ok()
             .map(|val| val.0);
@@ -527,6 +541,8 @@ async fn test_date_03() {
                 .query("SELECT val from time_date_tests", &[])
                 .await
                 .unwrap()
+                .into_legacy_result()
+                .unwrap()
                 .first_row_typed::<(Date,)>()
                 .unwrap();
             assert_eq!(read_date, *date);
@@ -570,6 +586,8 @@ async fn test_cql_time() {
             .query("SELECT val from cql_time_tests", &[])
             .await
             .unwrap()
+            .into_legacy_result()
+            .unwrap()
             .single_row_typed::<(CqlTime,)>()
             .unwrap();
 
@@ -588,6 +606,8 @@ async fn test_cql_time() {
             .query("SELECT val from cql_time_tests", &[])
             .await
             .unwrap()
+            .into_legacy_result()
+            .unwrap()
             .single_row_typed::<(CqlTime,)>()
             .unwrap();
 
@@ -666,6 +686,8 @@ async fn test_naive_time_04() {
             .query("SELECT val from chrono_time_tests", &[])
             .await
             .unwrap()
+            .into_legacy_result()
+            .unwrap()
             .first_row_typed::<(NaiveTime,)>()
             .unwrap();
 
@@ -684,6 +706,8 @@ async fn test_naive_time_04() {
             .query("SELECT val from chrono_time_tests", &[])
             .await
             .unwrap()
+            .into_legacy_result()
+            .unwrap()
             .first_row_typed::<(NaiveTime,)>()
             .unwrap();
         assert_eq!(read_time, *time);
@@ -746,6 +770,8 @@ async fn test_time_03() {
             .query("SELECT val from time_time_tests", &[])
             .await
             .unwrap()
+            .into_legacy_result()
+            .unwrap()
             .first_row_typed::<(Time,)>()
             .unwrap();
 
@@ -764,6 +790,8 @@ async fn test_time_03() {
             .query("SELECT val from time_time_tests", &[])
             .await
             .unwrap()
+            .into_legacy_result()
+            .unwrap()
             .first_row_typed::<(Time,)>()
             .unwrap();
         assert_eq!(read_time, *time);
@@ -817,6 +845,8 @@ async fn test_cql_timestamp() {
             .query("SELECT val from cql_timestamp_tests", &[])
             .await
             .unwrap()
+            .into_legacy_result()
+            .unwrap()
             .single_row_typed::<(CqlTimestamp,)>()
             .unwrap();
 
@@ -835,6 +865,8 @@ async fn test_cql_timestamp() {
             .query("SELECT val from cql_timestamp_tests", &[])
             .await
             .unwrap()
+            .into_legacy_result()
+            .unwrap()
             .single_row_typed::<(CqlTimestamp,)>()
             .unwrap();
 
@@ -912,6 +944,8 @@ async fn test_date_time_04() {
             .query("SELECT val from chrono_datetime_tests", &[])
             .await
             .unwrap()
+            .into_legacy_result()
+            .unwrap()
             .first_row_typed::<(DateTime<Utc>,)>()
             .unwrap();
 
@@ -930,6 +964,8 @@ async fn test_date_time_04() {
             .query("SELECT val from chrono_datetime_tests", &[])
             .await
             .unwrap()
+            .into_legacy_result()
+            .unwrap()
             .first_row_typed::<(DateTime<Utc>,)>()
             .unwrap();
         assert_eq!(read_datetime, *datetime);
@@ -958,6 +994,8 @@ async fn test_date_time_04() {
         .query("SELECT val from chrono_datetime_tests", &[])
         .await
         .unwrap()
+        .into_legacy_result()
+        .unwrap()
         .first_row_typed::<(DateTime<Utc>,)>()
         .unwrap();
     assert_eq!(read_datetime, nanosecond_precision_1st_half_rounded);
@@ -984,6 +1022,8 @@ async fn test_date_time_04() {
         .query("SELECT val from chrono_datetime_tests", &[])
         .await
         .unwrap()
+        .into_legacy_result()
+        .unwrap()
         .first_row_typed::<(DateTime<Utc>,)>()
         .unwrap();
     assert_eq!(read_datetime, nanosecond_precision_2nd_half_rounded);
@@ -1073,6 +1113,8 @@ async fn test_offset_date_time_03() {
             .query("SELECT val from time_datetime_tests", &[])
             .await
             .unwrap()
+            .into_legacy_result()
+            .unwrap()
             .first_row_typed::<(OffsetDateTime,)>()
             .unwrap();
 
@@ -1091,6 +1133,8 @@ async fn test_offset_date_time_03() {
             .query("SELECT val from time_datetime_tests", &[])
             .await
             .unwrap()
+            .into_legacy_result()
+            .unwrap()
             .first_row_typed::<(OffsetDateTime,)>()
             .unwrap();
         assert_eq!(read_datetime, *datetime);
@@ -1119,6 +1163,8 @@ async fn test_offset_date_time_03() {
         .query("SELECT val from time_datetime_tests", &[])
         .await
         .unwrap()
+        .into_legacy_result()
+        .unwrap()
         .first_row_typed::<(OffsetDateTime,)>()
         .unwrap();
     assert_eq!(read_datetime, nanosecond_precision_1st_half_rounded);
@@ -1145,6 +1191,8 @@ async fn test_offset_date_time_03() {
         .query("SELECT val from time_datetime_tests", &[])
         .await
         .unwrap()
+        .into_legacy_result()
+        .unwrap()
         .first_row_typed::<(OffsetDateTime,)>()
         .unwrap();
     assert_eq!(read_datetime, nanosecond_precision_2nd_half_rounded);
@@ -1194,6 +1242,8 @@ async fn test_timeuuid() {
             .query("SELECT val from timeuuid_tests", &[])
             .await
             .unwrap()
+            .into_legacy_result()
+            .unwrap()
             .single_row_typed::<(CqlTimeuuid,)>()
             .unwrap();
 
@@ -1213,6 +1263,8 @@ async fn test_timeuuid() {
             .query("SELECT val from timeuuid_tests", &[])
             .await
             .unwrap()
+            .into_legacy_result()
+            .unwrap()
             .single_row_typed::<(CqlTimeuuid,)>()
             .unwrap();
 
@@ -1279,6 +1331,8 @@ async fn test_timeuuid_ordering() {
         .query("SELECT t FROM tab WHERE p = 0", ())
         .await
         .unwrap()
+        .into_legacy_result()
+        .unwrap()
         .rows_typed::<(CqlTimeuuid,)>()
         .unwrap()
         .map(|r| r.unwrap().0)
@@ -1358,6 +1412,8 @@ async fn test_inet() {
             .query("SELECT val from inet_tests WHERE id = 0", &[])
             .await
             .unwrap()
+            .into_legacy_result()
+            .unwrap()
             .single_row_typed::<(IpAddr,)>()
             .unwrap();
 
@@ -1373,6 +1429,8 @@ async fn test_inet() {
             .query("SELECT val from inet_tests WHERE id = 0", &[])
             .await
             .unwrap()
+            .into_legacy_result()
+            .unwrap()
             .single_row_typed::<(IpAddr,)>()
             .unwrap();
 
@@ -1424,6 +1482,8 @@ async fn test_blob() {
             .query("SELECT val from blob_tests WHERE id = 0", &[])
             .await
             .unwrap()
+            .into_legacy_result()
+            .unwrap()
             .single_row_typed::<(Vec<u8>,)>()
             .unwrap();
 
@@ -1439,6 +1499,8 @@ async fn test_blob() {
             .query("SELECT val from blob_tests WHERE id = 0", &[])
             .await
             .unwrap()
+            .into_legacy_result()
+            .unwrap()
             .single_row_typed::<(Vec<u8>,)>()
             .unwrap();
 
@@ -1527,6 +1589,8 @@ async fn test_udt_after_schema_update() {
         .query(format!("SELECT val from {} WHERE id = 0", table_name), &[])
         .await
         .unwrap()
+        .into_legacy_result()
+        .unwrap()
         .single_row_typed::<(UdtV1,)>()
         .unwrap();
 
@@ -1544,6 +1608,8 @@ async fn test_udt_after_schema_update() {
         .query(format!("SELECT val from {} WHERE id = 0", table_name), &[])
         .await
         .unwrap()
+        .into_legacy_result()
+        .unwrap()
         .single_row_typed::<(UdtV1,)>()
         .unwrap();
 
@@ -1565,6 +1631,8 @@ async fn test_udt_after_schema_update() {
         .query(format!("SELECT val from {} WHERE id = 0", table_name), &[])
         .await
         .unwrap()
+        .into_legacy_result()
+        .unwrap()
         .single_row_typed::<(UdtV2,)>()
         .unwrap();
 
@@ -1595,6 +1663,8 @@ async fn test_empty() {
         .query("SELECT val FROM empty_tests WHERE id = 0", ())
         .await
         .unwrap()
+        .into_legacy_result()
+        .unwrap()
         .first_row_typed::<(CqlValue,)>()
         .unwrap();
 
@@ -1612,6 +1682,8 @@ async fn test_empty() {
         .query("SELECT val FROM empty_tests WHERE id = 1", ())
         .await
         .unwrap()
+        .into_legacy_result()
+        .unwrap()
         .first_row_typed::<(CqlValue,)>()
         .unwrap();
 
@@ -1698,6 +1770,8 @@ async fn test_udt_with_missing_field() {
             )
             .await
             .unwrap()
+            .into_legacy_result()
+            .unwrap()
             .single_row_typed::<(TR,)>()
             .unwrap()
             .0;
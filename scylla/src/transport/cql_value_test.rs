@@ -58,6 +58,8 @@ async fn test_cqlvalue_udt() {
         .query("SELECT my FROM cqlvalue_udt_test", &[])
         .await
         .unwrap()
+        .into_legacy_result()
+        .unwrap()
         .rows
         .unwrap();
 
@@ -113,6 +115,8 @@ async fn test_cqlvalue_duration() {
         )
         .await
         .unwrap()
+        .into_legacy_result()
+        .unwrap()
         .rows
         .unwrap();
 
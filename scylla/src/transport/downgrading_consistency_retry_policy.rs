@@ -211,6 +211,7 @@ mod tests {
             error,
             is_idempotent,
             consistency: cl,
+            remaining_budget: None,
         }
     }
 
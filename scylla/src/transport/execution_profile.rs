@@ -168,6 +168,8 @@ use scylla_cql::{frame::types::SerialConsistency, Consistency};
 use crate::{
     load_balancing::LoadBalancingPolicy, retry_policy::RetryPolicy,
     speculative_execution::SpeculativeExecutionPolicy,
+    transport::in_flight_limiter::InFlightLimitBehavior,
+    transport::timestamp_generator::TimestampGenerator,
 };
 
 pub(crate) mod defaults {
@@ -175,6 +177,8 @@ pub(crate) mod defaults {
     use crate::retry_policy::{DefaultRetryPolicy, RetryPolicy};
     use crate::speculative_execution::SpeculativeExecutionPolicy;
     use crate::transport::execution_profile::ExecutionProfileInner;
+    use crate::transport::in_flight_limiter::InFlightLimitBehavior;
+    use crate::transport::timestamp_generator::TimestampGenerator;
     use scylla_cql::frame::types::SerialConsistency;
     use scylla_cql::Consistency;
     use std::sync::Arc;
@@ -188,6 +192,9 @@ pub(crate) mod defaults {
     pub(crate) fn request_timeout() -> Option<Duration> {
         Some(Duration::from_secs(30))
     }
+    pub(crate) fn request_deadline() -> Option<Duration> {
+        None
+    }
     pub(crate) fn load_balancing_policy() -> Arc<dyn LoadBalancingPolicy> {
         Arc::new(load_balancing::DefaultPolicy::default())
     }
@@ -197,16 +204,25 @@ pub(crate) mod defaults {
     pub(crate) fn speculative_execution_policy() -> Option<Arc<dyn SpeculativeExecutionPolicy>> {
         None
     }
+    pub(crate) fn in_flight_limit_behavior() -> InFlightLimitBehavior {
+        InFlightLimitBehavior::Wait
+    }
+    pub(crate) fn timestamp_generator() -> Option<Arc<dyn TimestampGenerator>> {
+        None
+    }
 
     impl Default for ExecutionProfileInner {
         fn default() -> Self {
             Self {
                 request_timeout: request_timeout(),
+                request_deadline: request_deadline(),
                 consistency: consistency(),
                 serial_consistency: serial_consistency(),
                 load_balancing_policy: load_balancing_policy(),
                 retry_policy: retry_policy(),
                 speculative_execution_policy: speculative_execution_policy(),
+                in_flight_limit_behavior: in_flight_limit_behavior(),
+                timestamp_generator: timestamp_generator(),
             }
         }
     }
@@ -229,11 +245,14 @@ pub(crate) mod defaults {
 #[derive(Clone, Debug)]
 pub struct ExecutionProfileBuilder {
     request_timeout: Option<Option<Duration>>,
+    request_deadline: Option<Option<Duration>>,
     consistency: Option<Consistency>,
     serial_consistency: Option<Option<SerialConsistency>>,
     load_balancing_policy: Option<Arc<dyn LoadBalancingPolicy>>,
     retry_policy: Option<Box<dyn RetryPolicy>>,
     speculative_execution_policy: Option<Option<Arc<dyn SpeculativeExecutionPolicy>>>,
+    in_flight_limit_behavior: Option<InFlightLimitBehavior>,
+    timestamp_generator: Option<Option<Arc<dyn TimestampGenerator>>>,
 }
 
 impl ExecutionProfileBuilder {
@@ -256,6 +275,29 @@ impl ExecutionProfileBuilder {
         self
     }
 
+    /// Changes the overall deadline of requests, which is shared by all
+    /// the attempts made to execute a statement, i.e. retries and speculative
+    /// executions, and in case of iterators covers fetching all the pages.
+    /// When it expires, the request fails with `QueryError::RequestDeadlineExceeded`
+    /// stating the number of attempts made.
+    /// The default is no deadline.
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::transport::ExecutionProfile;
+    /// # use std::time::Duration;
+    /// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let profile: ExecutionProfile = ExecutionProfile::builder()
+    ///     .request_deadline(Some(Duration::from_millis(500)))
+    ///     .build();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn request_deadline(mut self, deadline: Option<Duration>) -> Self {
+        self.request_deadline = Some(deadline);
+        self
+    }
+
     /// Specify a default consistency to be used for queries.
     /// It's possible to override it by explicitly setting a consistency on the chosen query.
     pub fn consistency(mut self, consistency: Consistency) -> Self {
@@ -346,6 +388,53 @@ impl ExecutionProfileBuilder {
         self
     }
 
+    /// Decides what happens to requests which would exceed the limits of requests
+    /// in flight, set with [SessionBuilder::in_flight_limits](crate::SessionBuilder::in_flight_limits):
+    /// whether they wait for other requests to complete, or fail immediately with
+    /// `QueryError::TooManyInFlightRequests`.
+    /// The default is [`InFlightLimitBehavior::Wait`].
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::transport::ExecutionProfile;
+    /// # use scylla::transport::in_flight_limiter::InFlightLimitBehavior;
+    /// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let profile: ExecutionProfile = ExecutionProfile::builder()
+    ///     .in_flight_limit_behavior(InFlightLimitBehavior::FailFast)
+    ///     .build();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn in_flight_limit_behavior(mut self, behavior: InFlightLimitBehavior) -> Self {
+        self.in_flight_limit_behavior = Some(behavior);
+        self
+    }
+
+    /// Sets the generator of timestamps of requests whose statements have no timestamp set.
+    /// If the profile has no generator, the one set with
+    /// [SessionBuilder::timestamp_generator](crate::SessionBuilder::timestamp_generator) is used.
+    /// The default is None.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::sync::Arc;
+    /// # use scylla::transport::ExecutionProfile;
+    /// # use scylla::transport::timestamp_generator::MonotonicTimestampGenerator;
+    /// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let profile: ExecutionProfile = ExecutionProfile::builder()
+    ///     .timestamp_generator(Some(Arc::new(MonotonicTimestampGenerator::new())))
+    ///     .build();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn timestamp_generator(
+        mut self,
+        timestamp_generator: Option<Arc<dyn TimestampGenerator>>,
+    ) -> Self {
+        self.timestamp_generator = Some(timestamp_generator);
+        self
+    }
+
     /// Builds the ExecutionProfile after setting all the options.
     ///
     /// # Example
@@ -364,6 +453,9 @@ impl ExecutionProfileBuilder {
             request_timeout: self
                 .request_timeout
                 .unwrap_or_else(defaults::request_timeout),
+            request_deadline: self
+                .request_deadline
+                .unwrap_or_else(defaults::request_deadline),
             consistency: self.consistency.unwrap_or_else(defaults::consistency),
             serial_consistency: self
                 .serial_consistency
@@ -375,6 +467,12 @@ impl ExecutionProfileBuilder {
             speculative_execution_policy: self
                 .speculative_execution_policy
                 .unwrap_or_else(defaults::speculative_execution_policy),
+            in_flight_limit_behavior: self
+                .in_flight_limit_behavior
+                .unwrap_or_else(defaults::in_flight_limit_behavior),
+            timestamp_generator: self
+                .timestamp_generator
+                .unwrap_or_else(defaults::timestamp_generator),
         }))
     }
 }
@@ -397,6 +495,7 @@ pub struct ExecutionProfile(pub(crate) Arc<ExecutionProfileInner>);
 #[derive(Debug)]
 pub(crate) struct ExecutionProfileInner {
     pub(crate) request_timeout: Option<Duration>,
+    pub(crate) request_deadline: Option<Duration>,
 
     pub(crate) consistency: Consistency,
     pub(crate) serial_consistency: Option<SerialConsistency>,
@@ -404,6 +503,10 @@ pub(crate) struct ExecutionProfileInner {
     pub(crate) load_balancing_policy: Arc<dyn LoadBalancingPolicy>,
     pub(crate) retry_policy: Box<dyn RetryPolicy>,
     pub(crate) speculative_execution_policy: Option<Arc<dyn SpeculativeExecutionPolicy>>,
+
+    pub(crate) in_flight_limit_behavior: InFlightLimitBehavior,
+
+    pub(crate) timestamp_generator: Option<Arc<dyn TimestampGenerator>>,
 }
 
 impl ExecutionProfileInner {
@@ -411,11 +514,14 @@ impl ExecutionProfileInner {
     pub(crate) fn to_builder(&self) -> ExecutionProfileBuilder {
         ExecutionProfileBuilder {
             request_timeout: Some(self.request_timeout),
+            request_deadline: Some(self.request_deadline),
             consistency: Some(self.consistency),
             serial_consistency: Some(self.serial_consistency),
             load_balancing_policy: Some(self.load_balancing_policy.clone()),
             retry_policy: Some(self.retry_policy.clone()),
             speculative_execution_policy: Some(self.speculative_execution_policy.clone()),
+            in_flight_limit_behavior: Some(self.in_flight_limit_behavior),
+            timestamp_generator: Some(self.timestamp_generator.clone()),
         }
     }
 }
@@ -429,11 +535,14 @@ impl ExecutionProfile {
     pub fn builder() -> ExecutionProfileBuilder {
         ExecutionProfileBuilder {
             request_timeout: None,
+            request_deadline: None,
             consistency: None,
             serial_consistency: None,
             load_balancing_policy: None,
             retry_policy: None,
             speculative_execution_policy: None,
+            in_flight_limit_behavior: None,
+            timestamp_generator: None,
         }
     }
 
@@ -0,0 +1,291 @@
+use std::time::Duration;
+
+use rand::{thread_rng, Rng};
+use tokio::time::Instant;
+
+use crate::retry_policy::{
+    DefaultRetryPolicy, QueryInfo, RetryDecision, RetryPolicy, RetrySession,
+};
+
+/// Exponential backoff retry policy - decides whether to retry a query the same way
+/// as the wrapped policy ([DefaultRetryPolicy](crate::retry_policy::DefaultRetryPolicy)
+/// by default), but delays each retry, so that overloaded nodes get some time to recover.\
+/// The n-th retry of a query is delayed by `base_delay * 2^(n-1)`, capped at `max_delay`.
+/// With jitter enabled, which is the default, a random delay from the upper half
+/// of that range is used instead, so that clients which failed at the same moment
+/// don't retry in lockstep.\
+/// Retries stop once `max_elapsed_time` would be exceeded by waiting for the next one,
+/// or when the delay doesn't fit in the remaining budget of the request deadline.
+#[derive(Debug, Clone)]
+pub struct ExponentialBackoffRetryPolicy {
+    inner: Box<dyn RetryPolicy>,
+    base_delay: Duration,
+    max_delay: Duration,
+    max_elapsed_time: Option<Duration>,
+    jitter: bool,
+}
+
+impl ExponentialBackoffRetryPolicy {
+    /// Creates the policy wrapping [DefaultRetryPolicy](crate::retry_policy::DefaultRetryPolicy),
+    /// with a base delay of 100ms, a maximum delay of 10s, a maximum elapsed time of 30s and jitter.
+    pub fn new() -> ExponentialBackoffRetryPolicy {
+        ExponentialBackoffRetryPolicy {
+            inner: Box::new(DefaultRetryPolicy::new()),
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(10),
+            max_elapsed_time: Some(Duration::from_secs(30)),
+            jitter: true,
+        }
+    }
+
+    /// Sets the policy which decides whether and where a query is retried.
+    pub fn inner_policy(mut self, inner: Box<dyn RetryPolicy>) -> Self {
+        self.inner = inner;
+        self
+    }
+
+    /// Sets the delay before the first retry.
+    pub fn base_delay(mut self, base_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self
+    }
+
+    /// Sets the upper bound of the delay before a single retry.
+    pub fn max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Sets the time, counted from the start of the query (or of fetching a page),
+    /// after which no more retries are performed. `None` means no limit.
+    pub fn max_elapsed_time(mut self, max_elapsed_time: Option<Duration>) -> Self {
+        self.max_elapsed_time = max_elapsed_time;
+        self
+    }
+
+    /// Enables or disables randomization of the delays.
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+}
+
+impl Default for ExponentialBackoffRetryPolicy {
+    fn default() -> ExponentialBackoffRetryPolicy {
+        ExponentialBackoffRetryPolicy::new()
+    }
+}
+
+impl RetryPolicy for ExponentialBackoffRetryPolicy {
+    fn new_session(&self) -> Box<dyn RetrySession> {
+        Box::new(ExponentialBackoffRetrySession {
+            inner: self.inner.new_session(),
+            policy: self.clone(),
+            start: Instant::now(),
+            retries: 0,
+        })
+    }
+
+    fn clone_boxed(&self) -> Box<dyn RetryPolicy> {
+        Box::new(self.clone())
+    }
+}
+
+pub struct ExponentialBackoffRetrySession {
+    inner: Box<dyn RetrySession>,
+    policy: ExponentialBackoffRetryPolicy,
+    start: Instant,
+    retries: u32,
+}
+
+impl ExponentialBackoffRetrySession {
+    fn next_delay(&self) -> Duration {
+        let delay = self
+            .policy
+            .base_delay
+            .checked_mul(2u32.saturating_pow(self.retries))
+            .map_or(self.policy.max_delay, |delay| {
+                delay.min(self.policy.max_delay)
+            });
+
+        if self.policy.jitter {
+            thread_rng().gen_range(delay / 2..=delay)
+        } else {
+            delay
+        }
+    }
+}
+
+impl RetrySession for ExponentialBackoffRetrySession {
+    fn decide_should_retry(&mut self, query_info: QueryInfo) -> RetryDecision {
+        let remaining_budget = query_info.remaining_budget;
+        let (consistency, same_node) = match self.inner.decide_should_retry(query_info) {
+            RetryDecision::RetrySameNode(cl) => (cl, true),
+            RetryDecision::RetryNextNode(cl) => (cl, false),
+            // Decisions which already carry a delay, or don't retry at all, are kept.
+            decision => return decision,
+        };
+
+        let delay = self.next_delay();
+        let exceeds_max_elapsed_time = self
+            .policy
+            .max_elapsed_time
+            .map_or(false, |max| self.start.elapsed() + delay > max);
+        let exceeds_budget = remaining_budget.map_or(false, |budget| delay >= budget);
+        if exceeds_max_elapsed_time || exceeds_budget {
+            return RetryDecision::DontRetry;
+        }
+
+        self.retries += 1;
+        if same_node {
+            RetryDecision::RetrySameNodeAfter(consistency, delay)
+        } else {
+            RetryDecision::RetryNextNodeAfter(consistency, delay)
+        }
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset();
+        self.start = Instant::now();
+        self.retries = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::ExponentialBackoffRetryPolicy;
+    use crate::retry_policy::{QueryInfo, RetryDecision, RetryPolicy};
+    use crate::statement::Consistency;
+    use crate::test_utils::setup_tracing;
+    use crate::transport::errors::{DbError, QueryError};
+
+    fn overloaded() -> QueryError {
+        QueryError::DbError(DbError::Overloaded, String::new())
+    }
+
+    fn make_query_info(error: &QueryError, remaining_budget: Option<Duration>) -> QueryInfo<'_> {
+        QueryInfo {
+            error,
+            is_idempotent: true,
+            consistency: Consistency::One,
+            remaining_budget,
+        }
+    }
+
+    fn no_jitter_policy() -> ExponentialBackoffRetryPolicy {
+        ExponentialBackoffRetryPolicy::new()
+            .base_delay(Duration::from_millis(100))
+            .max_delay(Duration::from_millis(500))
+            .max_elapsed_time(None)
+            .jitter(false)
+    }
+
+    #[tokio::test]
+    async fn delays_grow_exponentially_up_to_max_delay() {
+        setup_tracing();
+        let error = overloaded();
+        let mut session = no_jitter_policy().new_session();
+
+        for expected_ms in [100, 200, 400, 500, 500] {
+            assert_eq!(
+                session.decide_should_retry(make_query_info(&error, None)),
+                RetryDecision::RetryNextNodeAfter(None, Duration::from_millis(expected_ms))
+            );
+        }
+
+        // Resetting starts the sequence of delays anew.
+        session.reset();
+        assert_eq!(
+            session.decide_should_retry(make_query_info(&error, None)),
+            RetryDecision::RetryNextNodeAfter(None, Duration::from_millis(100))
+        );
+    }
+
+    #[tokio::test]
+    async fn jittered_delays_stay_in_upper_half() {
+        setup_tracing();
+        let error = overloaded();
+        let mut session = no_jitter_policy().jitter(true).new_session();
+
+        for max_ms in [100, 200, 400, 500, 500] {
+            let max = Duration::from_millis(max_ms);
+            let delay = session
+                .decide_should_retry(make_query_info(&error, None))
+                .delay()
+                .unwrap();
+            assert!(max / 2 <= delay && delay <= max, "{:?}", delay);
+        }
+    }
+
+    #[tokio::test]
+    async fn inner_decisions_are_respected() {
+        setup_tracing();
+        let error = overloaded();
+        let mut session = no_jitter_policy().new_session();
+
+        // The default policy doesn't retry non-idempotent queries after this error.
+        let query_info = QueryInfo {
+            is_idempotent: false,
+            ..make_query_info(&error, None)
+        };
+        assert_eq!(
+            session.decide_should_retry(query_info),
+            RetryDecision::DontRetry
+        );
+
+        let error = QueryError::DbError(
+            DbError::ReadTimeout {
+                consistency: Consistency::Two,
+                received: 2,
+                required: 2,
+                data_present: false,
+            },
+            String::new(),
+        );
+        assert_eq!(
+            session.decide_should_retry(make_query_info(&error, None)),
+            RetryDecision::RetrySameNodeAfter(None, Duration::from_millis(100))
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn stops_after_max_elapsed_time() {
+        setup_tracing();
+        let error = overloaded();
+        let mut session = no_jitter_policy()
+            .max_elapsed_time(Some(Duration::from_millis(1000)))
+            .new_session();
+
+        // Waits for 100, 200 and 400ms fit in the limit, the next 500ms don't.
+        for expected_ms in [100, 200, 400] {
+            let decision = session.decide_should_retry(make_query_info(&error, None));
+            assert_eq!(
+                decision,
+                RetryDecision::RetryNextNodeAfter(None, Duration::from_millis(expected_ms))
+            );
+            tokio::time::sleep(decision.delay().unwrap()).await;
+        }
+        assert_eq!(
+            session.decide_should_retry(make_query_info(&error, None)),
+            RetryDecision::DontRetry
+        );
+    }
+
+    #[tokio::test]
+    async fn stops_when_delay_exceeds_remaining_budget() {
+        setup_tracing();
+        let error = overloaded();
+        let mut session = no_jitter_policy().new_session();
+
+        assert_eq!(
+            session.decide_should_retry(make_query_info(&error, Some(Duration::from_secs(1)))),
+            RetryDecision::RetryNextNodeAfter(None, Duration::from_millis(100))
+        );
+        assert_eq!(
+            session.decide_should_retry(make_query_info(&error, Some(Duration::from_millis(150)))),
+            RetryDecision::DontRetry
+        );
+    }
+}
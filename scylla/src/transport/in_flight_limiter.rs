@@ -0,0 +1,389 @@
+//! Client-side limits of the number of requests in flight.
+//!
+//! By default the driver sends every request as soon as it's issued, which may overload
+//! the cluster (or exhaust the stream ids of connections) when the application
+//! issues requests faster than they can be served. [`InFlightLimits`] set on the
+//! [`SessionBuilder`](crate::SessionBuilder) bound the number of requests in flight
+//! per session, per node and per shard. What happens to requests above the limits
+//! is decided by the [`InFlightLimitBehavior`] of their execution profile.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use uuid::Uuid;
+
+use crate::routing::Shard;
+use crate::transport::errors::{InFlightLimitScope, QueryError};
+use crate::transport::metrics::Metrics;
+
+/// Limits of the number of requests which may be in flight at the same time.
+/// `None` means no limit.
+///
+/// The session limit is applied once per request (or per page of a paged query),
+/// while the node and shard limits are applied to each attempt of sending it,
+/// to the node and shard it is sent to. Shard limits apply only to nodes
+/// which report their sharding (i.e. ScyllaDB nodes).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct InFlightLimits {
+    /// Maximum number of requests in flight in the session.
+    pub per_session: Option<usize>,
+
+    /// Maximum number of requests in flight to a single node.
+    pub per_node: Option<usize>,
+
+    /// Maximum number of requests in flight to a single shard of a node.
+    pub per_shard: Option<usize>,
+
+    /// Maximum number of requests waiting for the limits above, in the whole session.
+    /// Requests above it fail as if they had [`InFlightLimitBehavior::FailFast`].
+    pub max_queued: Option<usize>,
+}
+
+impl InFlightLimits {
+    /// Creates limits which don't limit anything.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum number of requests in flight in the session.
+    pub fn per_session(mut self, limit: Option<usize>) -> Self {
+        self.per_session = limit;
+        self
+    }
+
+    /// Sets the maximum number of requests in flight to a single node.
+    pub fn per_node(mut self, limit: Option<usize>) -> Self {
+        self.per_node = limit;
+        self
+    }
+
+    /// Sets the maximum number of requests in flight to a single shard.
+    pub fn per_shard(mut self, limit: Option<usize>) -> Self {
+        self.per_shard = limit;
+        self
+    }
+
+    /// Sets the maximum number of requests waiting for a free slot.
+    pub fn max_queued(mut self, limit: Option<usize>) -> Self {
+        self.max_queued = limit;
+        self
+    }
+}
+
+/// Decides what happens to a request which would exceed one of the [`InFlightLimits`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InFlightLimitBehavior {
+    /// Wait until another request completes, unless the queue of waiting requests is full.
+    #[default]
+    Wait,
+
+    /// Fail immediately with [`QueryError::TooManyInFlightRequests`].
+    FailFast,
+}
+
+/// Permits of a request to be in flight, released when dropped.
+#[derive(Debug, Default)]
+pub(crate) struct InFlightPermit {
+    _permits: Vec<OwnedSemaphorePermit>,
+}
+
+pub(crate) struct InFlightLimiter {
+    limits: InFlightLimits,
+    session: Option<Arc<Semaphore>>,
+    nodes: Mutex<HashMap<Uuid, Arc<Semaphore>>>,
+    shards: Mutex<HashMap<(Uuid, Shard), Arc<Semaphore>>>,
+    queued: AtomicUsize,
+    metrics: Arc<Metrics>,
+}
+
+impl InFlightLimiter {
+    pub(crate) fn new(limits: InFlightLimits, metrics: Arc<Metrics>) -> Self {
+        Self {
+            session: limits
+                .per_session
+                .map(|limit| Arc::new(Semaphore::new(limit))),
+            limits,
+            nodes: Default::default(),
+            shards: Default::default(),
+            queued: AtomicUsize::new(0),
+            metrics,
+        }
+    }
+
+    /// Acquires the permit to send a request in the session.
+    pub(crate) async fn acquire_for_request(
+        &self,
+        behavior: InFlightLimitBehavior,
+    ) -> Result<InFlightPermit, QueryError> {
+        let mut permit = InFlightPermit::default();
+        if let Some(semaphore) = &self.session {
+            permit._permits.push(
+                self.acquire(semaphore, InFlightLimitScope::Session, behavior)
+                    .await?,
+            );
+        }
+        Ok(permit)
+    }
+
+    /// Acquires the permits to send an attempt of a request to the given node and shard.
+    pub(crate) async fn acquire_for_attempt(
+        &self,
+        host_id: Uuid,
+        shard: Option<Shard>,
+        behavior: InFlightLimitBehavior,
+    ) -> Result<InFlightPermit, QueryError> {
+        let mut permit = InFlightPermit::default();
+        if let Some(limit) = self.limits.per_node {
+            let semaphore = Self::semaphore(&self.nodes, host_id, limit);
+            permit._permits.push(
+                self.acquire(&semaphore, InFlightLimitScope::Node, behavior)
+                    .await?,
+            );
+        }
+        if let (Some(limit), Some(shard)) = (self.limits.per_shard, shard) {
+            let semaphore = Self::semaphore(&self.shards, (host_id, shard), limit);
+            permit._permits.push(
+                self.acquire(&semaphore, InFlightLimitScope::Shard, behavior)
+                    .await?,
+            );
+        }
+        Ok(permit)
+    }
+
+    /// Forgets the limits of nodes which are no longer in the cluster, so that the maps
+    /// of semaphores don't grow with every node which ever joined it.
+    /// Requests in flight to the removed nodes keep their permits until they complete.
+    pub(crate) fn retain_nodes(&self, mut is_known: impl FnMut(&Uuid) -> bool) {
+        self.nodes
+            .lock()
+            .unwrap()
+            .retain(|host_id, _| is_known(host_id));
+        self.shards
+            .lock()
+            .unwrap()
+            .retain(|(host_id, _), _| is_known(host_id));
+    }
+
+    fn semaphore<K: Eq + std::hash::Hash>(
+        semaphores: &Mutex<HashMap<K, Arc<Semaphore>>>,
+        key: K,
+        limit: usize,
+    ) -> Arc<Semaphore> {
+        semaphores
+            .lock()
+            .unwrap()
+            .entry(key)
+            .or_insert_with(|| Arc::new(Semaphore::new(limit)))
+            .clone()
+    }
+
+    async fn acquire(
+        &self,
+        semaphore: &Arc<Semaphore>,
+        scope: InFlightLimitScope,
+        behavior: InFlightLimitBehavior,
+    ) -> Result<OwnedSemaphorePermit, QueryError> {
+        if let Ok(permit) = semaphore.clone().try_acquire_owned() {
+            return Ok(permit);
+        }
+        self.metrics.inc_in_flight_limit_reached();
+
+        let reason = match behavior {
+            InFlightLimitBehavior::FailFast => "",
+            InFlightLimitBehavior::Wait => match self.enqueue() {
+                Some(_queued) => {
+                    // The semaphores are never closed, so this always succeeds.
+                    if let Ok(permit) = semaphore.clone().acquire_owned().await {
+                        return Ok(permit);
+                    }
+                    ""
+                }
+                None => " and the queue of waiting requests is full",
+            },
+        };
+        self.metrics.inc_in_flight_rejected();
+        Err(QueryError::TooManyInFlightRequests(
+            scope,
+            format!(
+                "Limit of in-flight requests per {} reached{}",
+                scope, reason
+            ),
+        ))
+    }
+
+    fn enqueue(&self) -> Option<QueuedGuard<'_>> {
+        let max_queued = self.limits.max_queued.unwrap_or(usize::MAX);
+        self.queued
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |queued| {
+                (queued < max_queued).then_some(queued + 1)
+            })
+            .ok()?;
+        self.metrics.inc_in_flight_queued();
+        Some(QueuedGuard(self))
+    }
+}
+
+// Decrements the number of queued requests, also when the waiting request is cancelled.
+struct QueuedGuard<'a>(&'a InFlightLimiter);
+
+impl Drop for QueuedGuard<'_> {
+    fn drop(&mut self) {
+        self.0.queued.fetch_sub(1, Ordering::Relaxed);
+        self.0.metrics.dec_in_flight_queued();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use assert_matches::assert_matches;
+    use uuid::Uuid;
+
+    use super::{InFlightLimitBehavior, InFlightLimiter, InFlightLimits};
+    use crate::test_utils::setup_tracing;
+    use crate::transport::errors::{InFlightLimitScope, QueryError};
+    use crate::transport::metrics::Metrics;
+
+    const NODE1: Uuid = Uuid::from_u128(1);
+    const NODE2: Uuid = Uuid::from_u128(2);
+
+    #[tokio::test]
+    async fn fail_fast_rejects_requests_above_limits() {
+        setup_tracing();
+        let metrics = Arc::new(Metrics::new());
+        let limiter = InFlightLimiter::new(
+            InFlightLimits::new().per_node(Some(2)).per_shard(Some(1)),
+            metrics.clone(),
+        );
+        let fail_fast = InFlightLimitBehavior::FailFast;
+
+        let _permit1 = limiter
+            .acquire_for_attempt(NODE1, Some(0), fail_fast)
+            .await
+            .unwrap();
+        assert_matches!(
+            limiter.acquire_for_attempt(NODE1, Some(0), fail_fast).await,
+            Err(QueryError::TooManyInFlightRequests(InFlightLimitScope::Shard, msg))
+                if msg == "Limit of in-flight requests per shard reached"
+        );
+        let _permit2 = limiter
+            .acquire_for_attempt(NODE1, Some(1), fail_fast)
+            .await
+            .unwrap();
+        assert_matches!(
+            limiter.acquire_for_attempt(NODE1, Some(2), fail_fast).await,
+            Err(QueryError::TooManyInFlightRequests(InFlightLimitScope::Node, msg))
+                if msg == "Limit of in-flight requests per node reached"
+        );
+
+        // Other nodes have their own limits, nodes without shards have no shard limits.
+        let _permit3 = limiter
+            .acquire_for_attempt(NODE2, None, fail_fast)
+            .await
+            .unwrap();
+        let _permit4 = limiter
+            .acquire_for_attempt(NODE2, None, fail_fast)
+            .await
+            .unwrap();
+
+        // The session isn't limited.
+        for _ in 0..10 {
+            limiter.acquire_for_request(fail_fast).await.unwrap();
+        }
+
+        assert_eq!(metrics.get_in_flight_limit_reached_num(), 2);
+        assert_eq!(metrics.get_in_flight_rejected_num(), 2);
+    }
+
+    #[tokio::test]
+    async fn removed_nodes_are_forgotten() {
+        setup_tracing();
+        let limiter = InFlightLimiter::new(
+            InFlightLimits::new().per_node(Some(1)).per_shard(Some(1)),
+            Arc::new(Metrics::new()),
+        );
+        let fail_fast = InFlightLimitBehavior::FailFast;
+
+        let _permit1 = limiter
+            .acquire_for_attempt(NODE1, Some(0), fail_fast)
+            .await
+            .unwrap();
+        let _permit2 = limiter
+            .acquire_for_attempt(NODE2, Some(0), fail_fast)
+            .await
+            .unwrap();
+
+        limiter.retain_nodes(|host_id| *host_id == NODE1);
+        assert_eq!(limiter.nodes.lock().unwrap().len(), 1);
+        assert_eq!(limiter.shards.lock().unwrap().len(), 1);
+
+        // The limits of the remaining node are kept.
+        assert!(limiter
+            .acquire_for_attempt(NODE1, Some(0), fail_fast)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn dropping_permits_frees_slots() {
+        setup_tracing();
+        let limiter = InFlightLimiter::new(
+            InFlightLimits::new().per_session(Some(1)),
+            Arc::new(Metrics::new()),
+        );
+        let fail_fast = InFlightLimitBehavior::FailFast;
+
+        let permit = limiter.acquire_for_request(fail_fast).await.unwrap();
+        assert!(limiter.acquire_for_request(fail_fast).await.is_err());
+        drop(permit);
+        limiter.acquire_for_request(fail_fast).await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn waiting_requests_are_bounded() {
+        setup_tracing();
+        let metrics = Arc::new(Metrics::new());
+        let limiter = Arc::new(InFlightLimiter::new(
+            InFlightLimits::new()
+                .per_session(Some(1))
+                .max_queued(Some(1)),
+            metrics.clone(),
+        ));
+        let wait = InFlightLimitBehavior::Wait;
+
+        let permit = limiter.acquire_for_request(wait).await.unwrap();
+
+        // The first waiting request is queued until the permit is released.
+        let waiting = tokio::spawn({
+            let limiter = limiter.clone();
+            async move { limiter.acquire_for_request(wait).await.map(|_| ()) }
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(metrics.get_in_flight_queued_num(), 1);
+
+        // There is no place in the queue for the second one.
+        assert_matches!(
+            limiter.acquire_for_request(wait).await,
+            Err(QueryError::TooManyInFlightRequests(InFlightLimitScope::Session, msg))
+                if msg == "Limit of in-flight requests per session reached and the queue of waiting requests is full"
+        );
+
+        drop(permit);
+        waiting.await.unwrap().unwrap();
+        assert_eq!(metrics.get_in_flight_queued_num(), 0);
+        assert_eq!(metrics.get_in_flight_limit_reached_num(), 2);
+        assert_eq!(metrics.get_in_flight_rejected_num(), 1);
+
+        // Cancelled waiting requests leave the queue.
+        let _permit = limiter.acquire_for_request(wait).await.unwrap();
+        let waiting =
+            tokio::time::timeout(Duration::from_millis(10), limiter.acquire_for_request(wait));
+        assert!(waiting.await.is_err());
+        assert_eq!(metrics.get_in_flight_queued_num(), 0);
+    }
+}
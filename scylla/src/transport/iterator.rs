@@ -1,5 +1,6 @@
 //! Iterators over rows returned by paged queries
 
+use std::collections::HashMap;
 use std::future::Future;
 use std::mem;
 use std::net::SocketAddr;
@@ -7,6 +8,7 @@ use std::ops::ControlFlow;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
+use std::time::Duration;
 
 use bytes::Bytes;
 use futures::Stream;
@@ -17,23 +19,33 @@ use std::result::Result;
 use thiserror::Error;
 use tokio::sync::mpsc;
 
-use super::errors::QueryError;
+use super::errors::{InFlightLimitScope, QueryError};
 use super::execution_profile::ExecutionProfileInner;
-use super::session::RequestSpan;
+use super::session::{RequestDeadline, RequestSpan};
 use crate::cql_to_rust::{FromRow, FromRowError};
+use crate::deserialize::result::RowIterator as RawRowIterator;
+#[cfg(feature = "arrow")]
+use crate::deserialize::row::ColumnIterator;
+use crate::deserialize::{DeserializationError, DeserializeRow, FrameSlice, TypeCheckError};
+use crate::frame::frame_errors::ParseError;
 
 use crate::frame::response::{
     result,
     result::{ColumnSpec, Row, Rows},
 };
 use crate::history::{self, HistoryListener};
+use crate::statement::paging_state::PagingState;
 use crate::statement::Consistency;
 use crate::statement::{prepared_statement::PreparedStatement, query::Query};
 use crate::transport::cluster::ClusterData;
 use crate::transport::connection::{Connection, NonErrorQueryResponse, QueryResponse};
+use crate::transport::in_flight_limiter::InFlightLimiter;
 use crate::transport::load_balancing::{self, RoutingInfo};
 use crate::transport::metrics::Metrics;
+#[cfg(feature = "arrow")]
+use crate::transport::record_batch::{self, ArrowConversionError, NextRecordBatchError};
 use crate::transport::retry_policy::{QueryInfo, RetryDecision, RetrySession};
+use crate::transport::task_tracker::ActivityGuard;
 use crate::transport::NodeRef;
 use tracing::{trace, trace_span, warn, Instrument};
 use uuid::Uuid;
@@ -59,21 +71,36 @@ const DEFAULT_ITER_PAGE_SIZE: i32 = 5000;
 pub struct RowIterator {
     current_row_idx: usize,
     current_page: Rows,
+    // Rows of `current_page` deserialized into `Row`s, filled on demand
+    // by the `Stream` implementation.
+    current_legacy_rows: Option<Vec<Row>>,
+    // Offset of the row at `current_row_idx` in the serialized rows of `current_page`,
+    // unless unknown because the rows were deserialized into `current_legacy_rows`.
+    current_row_offset: Option<usize>,
+    // Paging state of the query after `current_page`.
+    current_paging_state: Option<Bytes>,
     page_receiver: mpsc::Receiver<Result<ReceivedPage, QueryError>>,
     tracing_ids: Vec<Uuid>,
+    custom_payload: Option<HashMap<String, Vec<u8>>>,
+    // Text of the statement, to which paging states are bound.
+    statement: String,
 }
 
 struct ReceivedPage {
     rows: Rows,
+    paging_state: Option<Bytes>,
     tracing_id: Option<Uuid>,
+    custom_payload: Option<HashMap<String, Vec<u8>>>,
 }
 
 pub(crate) struct PreparedIteratorConfig {
     pub(crate) prepared: PreparedStatement,
     pub(crate) values: SerializedValues,
+    pub(crate) paging_state: Option<Bytes>,
     pub(crate) execution_profile: Arc<ExecutionProfileInner>,
     pub(crate) cluster_data: Arc<ClusterData>,
     pub(crate) metrics: Arc<Metrics>,
+    pub(crate) in_flight_limiter: Arc<InFlightLimiter>,
 }
 
 /// Fetching pages is asynchronous so `RowIterator` does not implement the `Iterator` trait.\
@@ -82,18 +109,11 @@ impl Stream for RowIterator {
     type Item = Result<Row, QueryError>;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        let mut s = self.as_mut();
+        let s = self.as_mut().get_mut();
 
         if s.is_current_page_exhausted() {
-            match Pin::new(&mut s.page_receiver).poll_recv(cx) {
-                Poll::Ready(Some(Ok(received_page))) => {
-                    s.current_page = received_page.rows;
-                    s.current_row_idx = 0;
-
-                    if let Some(tracing_id) = received_page.tracing_id {
-                        s.tracing_ids.push(tracing_id);
-                    }
-                }
+            match s.poll_next_page(cx) {
+                Poll::Ready(Some(Ok(()))) => (),
                 Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err))),
                 Poll::Ready(None) => return Poll::Ready(None),
                 Poll::Pending => return Poll::Pending,
@@ -101,9 +121,21 @@ impl Stream for RowIterator {
         }
 
         let idx = s.current_row_idx;
-        if idx < s.current_page.rows.len() {
-            let row = mem::take(&mut s.current_page.rows[idx]);
+        if idx < s.current_page.rows_count {
+            let rows = match &mut s.current_legacy_rows {
+                Some(rows) => rows,
+                None => match s.current_page.legacy_rows() {
+                    Ok(rows) => s.current_legacy_rows.insert(rows),
+                    Err(err) => {
+                        // Skip the rest of the page, so that the error is returned only once.
+                        s.current_row_idx = s.current_page.rows_count;
+                        return Poll::Ready(Some(Err(ParseError::from(err).into())));
+                    }
+                },
+            };
+            let row = mem::take(&mut rows[idx]);
             s.current_row_idx += 1;
+            s.current_row_offset = None;
             return Poll::Ready(Some(Ok(row)));
         }
 
@@ -123,11 +155,102 @@ impl RowIterator {
         }
     }
 
+    /// Converts this iterator into a stream over rows deserialized as the given type.\
+    /// Rows are deserialized one at a time, straight from the received pages, without building
+    /// intermediate [`Row`]s. Fails if the type doesn't match the columns of the result.
+    ///
+    /// To deserialize rows borrowing from the received pages, e.g. as `&str` or `&[u8]`,
+    /// use [`RowIterator::next_row`] instead.
+    pub fn rows_stream<RowT: for<'frame> DeserializeRow<'frame>>(
+        self,
+    ) -> Result<TypedRowStream<RowT>, TypeCheckError> {
+        RowT::type_check(self.get_column_specs())?;
+        Ok(TypedRowStream {
+            row_iterator: self,
+            current_page_type_checked: true,
+            phantom_data: Default::default(),
+        })
+    }
+
+    /// Returns the next row deserialized as the given type, fetching the next page if needed.\
+    /// The row can borrow from the page it was received in, so types like `&str` and `&[u8]`
+    /// can be deserialized without copying the data. The row has to be dropped before
+    /// the next one is fetched. The type is checked against the columns on each call.
+    pub async fn next_row<'frame, RowT: DeserializeRow<'frame>>(
+        &'frame mut self,
+    ) -> Option<Result<RowT, NextRowError>> {
+        while self.is_current_page_exhausted() {
+            match futures::future::poll_fn(|cx| self.poll_next_page(cx)).await? {
+                Ok(()) => (),
+                Err(err) => return Some(Err(err.into())),
+            }
+        }
+        if let Err(err) = RowT::type_check(self.get_column_specs()) {
+            return Some(Err(err.into()));
+        }
+        self.deserialize_next_row()
+    }
+
+    /// Converts this iterator into a stream of pages, each with its rows deserialized
+    /// as the given type and the [`PagingState`] after that page, from which the query
+    /// can be resumed (`None` after the last page).\
+    /// Rows of the current page which were already returned by this iterator are skipped.
+    /// Fails if the type doesn't match the columns of the result.
+    pub fn pages_stream<RowT: for<'frame> DeserializeRow<'frame>>(
+        self,
+    ) -> Result<TypedPageStream<RowT>, TypeCheckError> {
+        RowT::type_check(self.get_column_specs())?;
+        Ok(TypedPageStream {
+            row_iterator: self,
+            current_page_returned: false,
+            phantom_data: Default::default(),
+        })
+    }
+
+    /// Converts this iterator into a stream of Arrow [`RecordBatch`](arrow::record_batch::RecordBatch)es,
+    /// one for each received page.\
+    /// Fails if some column has a type which can't be converted to Arrow.
+    /// See [`record_batch`](crate::transport::record_batch) for how CQL types are mapped.
+    #[cfg(feature = "arrow")]
+    pub fn record_batches(self) -> Result<RecordBatchStream, ArrowConversionError> {
+        let schema = record_batch::arrow_schema(self.get_column_specs())?;
+        Ok(RecordBatchStream {
+            row_iterator: self,
+            schema: Arc::new(schema),
+        })
+    }
+
+    // Replaces the current page with the next received one.
+    fn poll_next_page(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<(), QueryError>>> {
+        let received_page = match self.page_receiver.poll_recv(cx) {
+            Poll::Ready(Some(Ok(received_page))) => received_page,
+            Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err))),
+            Poll::Ready(None) => return Poll::Ready(None),
+            Poll::Pending => return Poll::Pending,
+        };
+
+        self.current_page = received_page.rows;
+        self.current_paging_state = received_page.paging_state;
+        self.current_legacy_rows = None;
+        self.current_row_idx = 0;
+        self.current_row_offset = Some(0);
+
+        if let Some(tracing_id) = received_page.tracing_id {
+            self.tracing_ids.push(tracing_id);
+        }
+        self.custom_payload = received_page.custom_payload;
+
+        Poll::Ready(Some(Ok(())))
+    }
+
     pub(crate) async fn new_for_query(
         mut query: Query,
+        paging_state: Option<Bytes>,
         execution_profile: Arc<ExecutionProfileInner>,
         cluster_data: Arc<ClusterData>,
         metrics: Arc<Metrics>,
+        in_flight_limiter: Arc<InFlightLimiter>,
+        request_guard: ActivityGuard,
     ) -> Result<RowIterator, QueryError> {
         if query.get_page_size().is_none() {
             query.set_page_size(DEFAULT_ITER_PAGE_SIZE);
@@ -155,6 +278,13 @@ impl RowIterator {
             .unwrap_or(&*execution_profile.retry_policy)
             .new_session();
 
+        let request_deadline = query
+            .config
+            .request_deadline
+            .or(execution_profile.request_deadline)
+            .map(RequestDeadline::new);
+
+        let statement = query.contents.clone();
         let parent_span = tracing::Span::current();
         let worker_task = async move {
             let query_ref = &query;
@@ -168,6 +298,7 @@ impl RowIterator {
                             query_ref,
                             consistency,
                             serial_consistency,
+                            query_ref.get_timestamp(),
                             paging_state,
                         )
                         .await
@@ -189,9 +320,11 @@ impl RowIterator {
                 query_is_idempotent: query.config.is_idempotent,
                 query_consistency: consistency,
                 retry_session,
+                request_deadline,
                 execution_profile,
                 metrics,
-                paging_state: None,
+                in_flight_limiter,
+                paging_state,
                 history_listener: query.config.history_listener.clone(),
                 current_query_id: None,
                 current_attempt_id: None,
@@ -202,11 +335,12 @@ impl RowIterator {
             worker.work(cluster_data).await
         };
 
-        Self::new_from_worker_future(worker_task, receiver).await
+        Self::new_from_worker_future(worker_task, receiver, statement, Some(request_guard)).await
     }
 
     pub(crate) async fn new_for_prepared_statement(
         mut config: PreparedIteratorConfig,
+        request_guard: ActivityGuard,
     ) -> Result<RowIterator, QueryError> {
         if config.prepared.get_page_size().is_none() {
             config.prepared.set_page_size(DEFAULT_ITER_PAGE_SIZE);
@@ -230,6 +364,14 @@ impl RowIterator {
             .unwrap_or(&*config.execution_profile.retry_policy)
             .new_session();
 
+        let request_deadline = config
+            .prepared
+            .config
+            .request_deadline
+            .or(config.execution_profile.request_deadline)
+            .map(RequestDeadline::new);
+
+        let statement = config.prepared.get_statement().to_owned();
         let parent_span = tracing::Span::current();
         let worker_task = async move {
             let prepared_ref = &config.prepared;
@@ -265,6 +407,7 @@ impl RowIterator {
                         values_ref,
                         consistency,
                         serial_consistency,
+                        prepared_ref.get_timestamp(),
                         paging_state,
                     )
                     .await
@@ -306,9 +449,11 @@ impl RowIterator {
                 query_is_idempotent: config.prepared.config.is_idempotent,
                 query_consistency: consistency,
                 retry_session,
+                request_deadline,
                 execution_profile: config.execution_profile,
                 metrics: config.metrics,
-                paging_state: None,
+                in_flight_limiter: config.in_flight_limiter,
+                paging_state: config.paging_state,
                 history_listener: config.prepared.config.history_listener.clone(),
                 current_query_id: None,
                 current_attempt_id: None,
@@ -319,7 +464,7 @@ impl RowIterator {
             worker.work(config.cluster_data).await
         };
 
-        Self::new_from_worker_future(worker_task, receiver).await
+        Self::new_from_worker_future(worker_task, receiver, statement, Some(request_guard)).await
     }
 
     pub(crate) async fn new_for_connection_query_iter(
@@ -333,6 +478,7 @@ impl RowIterator {
         }
         let (sender, receiver) = mpsc::channel::<Result<ReceivedPage, QueryError>>(1);
 
+        let statement = query.contents.clone();
         let worker_task = async move {
             let worker = SingleConnectionRowIteratorWorker {
                 sender: sender.into(),
@@ -341,6 +487,7 @@ impl RowIterator {
                         &query,
                         consistency,
                         serial_consistency,
+                        query.get_timestamp(),
                         paging_state,
                     )
                 },
@@ -348,7 +495,7 @@ impl RowIterator {
             worker.work().await
         };
 
-        Self::new_from_worker_future(worker_task, receiver).await
+        Self::new_from_worker_future(worker_task, receiver, statement, None).await
     }
 
     pub(crate) async fn new_for_connection_execute_iter(
@@ -363,6 +510,7 @@ impl RowIterator {
         }
         let (sender, receiver) = mpsc::channel::<Result<ReceivedPage, QueryError>>(1);
 
+        let statement = prepared.get_statement().to_owned();
         let worker_task = async move {
             let worker = SingleConnectionRowIteratorWorker {
                 sender: sender.into(),
@@ -372,6 +520,7 @@ impl RowIterator {
                         &values,
                         consistency,
                         serial_consistency,
+                        prepared.get_timestamp(),
                         paging_state,
                     )
                 },
@@ -379,14 +528,20 @@ impl RowIterator {
             worker.work().await
         };
 
-        Self::new_from_worker_future(worker_task, receiver).await
+        Self::new_from_worker_future(worker_task, receiver, statement, None).await
     }
 
     async fn new_from_worker_future(
         worker_task: impl Future<Output = PageSendAttemptedProof> + Send + 'static,
         mut receiver: mpsc::Receiver<Result<ReceivedPage, QueryError>>,
+        statement: String,
+        request_guard: Option<ActivityGuard>,
     ) -> Result<RowIterator, QueryError> {
-        tokio::task::spawn(worker_task);
+        // The request lasts until the worker stops fetching pages.
+        tokio::task::spawn(async move {
+            let _request_guard = request_guard;
+            worker_task.await
+        });
 
         // This unwrap is safe because:
         // - The future returned by worker.work sends at least one item
@@ -398,12 +553,17 @@ impl RowIterator {
         Ok(RowIterator {
             current_row_idx: 0,
             current_page: pages_received.rows,
+            current_paging_state: pages_received.paging_state,
+            current_legacy_rows: None,
+            current_row_offset: Some(0),
             page_receiver: receiver,
             tracing_ids: if let Some(tracing_id) = pages_received.tracing_id {
                 vec![tracing_id]
             } else {
                 Vec::new()
             },
+            custom_payload: pages_received.custom_payload,
+            statement,
         })
     }
 
@@ -412,20 +572,67 @@ impl RowIterator {
         &self.tracing_ids
     }
 
+    /// Returns the custom payload attached by the server to the most recently received page
+    pub fn get_custom_payload(&self) -> Option<&HashMap<String, Vec<u8>>> {
+        self.custom_payload.as_ref()
+    }
+
     /// Returns specification of row columns
     pub fn get_column_specs(&self) -> &[ColumnSpec] {
         &self.current_page.metadata.col_specs
     }
 
     fn is_current_page_exhausted(&self) -> bool {
-        self.current_row_idx >= self.current_page.rows.len()
+        self.current_row_idx >= self.current_page.rows_count
+    }
+
+    // Deserializes the row at `current_row_idx` of the current page, without a type check,
+    // and moves past it. Returns None if the current page is exhausted.
+    fn deserialize_next_row<'frame, RowT: DeserializeRow<'frame>>(
+        &'frame mut self,
+    ) -> Option<Result<RowT, NextRowError>> {
+        let page = &self.current_page;
+        let remaining = page.rows_count.checked_sub(self.current_row_idx)?;
+        let mut slice = FrameSlice::new(&page.raw_rows);
+        let mut raw_rows = match self.current_row_offset {
+            Some(offset) => {
+                *slice.as_slice_mut() = &slice.as_slice()[offset..];
+                RawRowIterator::new(remaining, &page.metadata.col_specs, slice)
+            }
+            None => {
+                // Skip the rows which were already returned as `Row`s.
+                let mut raw_rows =
+                    RawRowIterator::new(page.rows_count, &page.metadata.col_specs, slice);
+                for _ in 0..self.current_row_idx {
+                    if let Some(Err(err)) = raw_rows.next() {
+                        self.current_row_idx = page.rows_count;
+                        return Some(Err(err.into()));
+                    }
+                }
+                raw_rows
+            }
+        };
+
+        let row = raw_rows.next()?.and_then(RowT::deserialize);
+        match row {
+            Ok(_) => {
+                self.current_row_idx += 1;
+                self.current_row_offset =
+                    Some(page.raw_rows.len() - raw_rows.remaining_slice().as_slice().len());
+            }
+            // Skip the rest of the page, so that the error is returned only once.
+            Err(_) => self.current_row_idx = page.rows_count,
+        }
+        Some(row.map_err(NextRowError::from))
     }
 }
 
 // A separate module is used here so that the parent module cannot construct
 // SendAttemptedProof directly.
 mod checked_channel_sender {
+    use bytes::Bytes;
     use scylla_cql::{errors::QueryError, frame::response::result::Rows};
+    use std::collections::HashMap;
     use std::marker::PhantomData;
     use tokio::sync::mpsc;
     use uuid::Uuid;
@@ -461,6 +668,7 @@ mod checked_channel_sender {
         pub(crate) async fn send_empty_page(
             &self,
             tracing_id: Option<Uuid>,
+            custom_payload: Option<HashMap<String, Vec<u8>>>,
         ) -> (
             SendAttemptedProof<ResultPage>,
             Result<(), mpsc::error::SendError<ResultPage>>,
@@ -469,10 +677,12 @@ mod checked_channel_sender {
                 rows: Rows {
                     metadata: Default::default(),
                     rows_count: 0,
-                    rows: Vec::new(),
+                    raw_rows: Bytes::new(),
                     serialized_size: 0,
                 },
+                paging_state: None,
                 tracing_id,
+                custom_payload,
             };
             self.send(Ok(empty_page)).await
         }
@@ -496,8 +706,11 @@ struct RowIteratorWorker<'a, QueryFunc, SpanCreatorFunc> {
     query_is_idempotent: bool,
     query_consistency: Consistency,
     retry_session: Box<dyn RetrySession>,
+    // Covers fetching all the pages, counting from the creation of the iterator.
+    request_deadline: Option<RequestDeadline>,
     execution_profile: Arc<ExecutionProfileInner>,
     metrics: Arc<Metrics>,
+    in_flight_limiter: Arc<InFlightLimiter>,
 
     paging_state: Option<Bytes>,
 
@@ -525,6 +738,7 @@ where
         let mut last_error: QueryError =
             QueryError::ProtocolError("Empty query plan - driver bug!");
         let mut current_consistency: Consistency = self.query_consistency;
+        let mut retry_delay: Option<Duration> = None;
 
         self.log_query_start();
 
@@ -552,6 +766,24 @@ where
             };
 
             'same_node_retries: loop {
+                // Wait before retrying, if the retry policy asked for it. This happens
+                // only once the next attempt is known to take place.
+                if let Some(delay) = retry_delay.take() {
+                    trace!(parent: &span, delay = ?delay, "Delaying retry");
+                    let sleep = async {
+                        tokio::time::sleep(delay).await;
+                        Ok(())
+                    };
+                    // The delay counts towards the deadline, too.
+                    let slept = match &self.request_deadline {
+                        Some(deadline) => deadline.run(sleep).await,
+                        None => sleep.await,
+                    };
+                    if let Err(error) = slept {
+                        last_error = error;
+                        break 'nodes_in_plan;
+                    }
+                }
                 trace!(parent: &span, "Execution started");
                 // Query pages until an error occurs
                 let queries_result: Result<PageSendAttemptedProof, QueryError> = self
@@ -567,6 +799,26 @@ where
                         // through self.sender and we can safely return now.
                         return proof;
                     }
+                    Err(
+                        error @ QueryError::TooManyInFlightRequests(
+                            InFlightLimitScope::Node | InFlightLimitScope::Shard,
+                            _,
+                        ),
+                    ) => {
+                        trace!(
+                            parent: &span,
+                            error = %error,
+                            "In-flight limit of the node reached"
+                        );
+                        last_error = error;
+                        // The page wasn't sent, so it isn't counted as failed either
+                        continue 'nodes_in_plan;
+                    }
+                    Err(error @ QueryError::TooManyInFlightRequests(..)) => {
+                        // The session limit would be reached with any other node, too.
+                        last_error = error;
+                        break 'nodes_in_plan;
+                    }
                     Err(error) => {
                         trace!(
                             parent: &span,
@@ -577,11 +829,23 @@ where
                     }
                 };
 
+                let remaining_budget = self
+                    .request_deadline
+                    .as_ref()
+                    .map(RequestDeadline::remaining);
+                if remaining_budget == Some(Duration::ZERO) {
+                    // There is no time left for retries.
+                    self.log_attempt_error(&last_error, &RetryDecision::DontRetry);
+                    last_error = self.request_deadline.as_ref().unwrap().exceeded_error();
+                    break 'nodes_in_plan;
+                }
+
                 // Use retry policy to decide what to do next
                 let query_info = QueryInfo {
                     error: &last_error,
                     is_idempotent: self.query_is_idempotent,
                     consistency: self.query_consistency,
+                    remaining_budget,
                 };
 
                 let retry_decision = self.retry_session.decide_should_retry(query_info);
@@ -601,6 +865,18 @@ where
                         current_consistency = cl.unwrap_or(current_consistency);
                         continue 'nodes_in_plan;
                     }
+                    RetryDecision::RetrySameNodeAfter(cl, delay) => {
+                        self.metrics.inc_retries_num();
+                        current_consistency = cl.unwrap_or(current_consistency);
+                        retry_delay = Some(delay);
+                        continue 'same_node_retries;
+                    }
+                    RetryDecision::RetryNextNodeAfter(cl, delay) => {
+                        self.metrics.inc_retries_num();
+                        current_consistency = cl.unwrap_or(current_consistency);
+                        retry_delay = Some(delay);
+                        continue 'nodes_in_plan;
+                    }
                     RetryDecision::DontRetry => break 'nodes_in_plan,
                     RetryDecision::IgnoreWriteError => {
                         warn!("Ignoring error during fetching pages; stopping fetching.");
@@ -610,7 +886,7 @@ where
                         // interface isn't meant for sending writes),
                         // we must attempt to send something because
                         // the iterator expects it.
-                        let (proof, _) = self.sender.send_empty_page(None).await;
+                        let (proof, _) = self.sender.send_empty_page(None, None).await;
                         return proof;
                     }
                 };
@@ -654,6 +930,31 @@ where
         node: NodeRef<'_>,
         request_span: &RequestSpan,
     ) -> Result<ControlFlow<PageSendAttemptedProof, ()>, QueryError> {
+        // Each page is limited as a separate request. A page which is rejected
+        // by the limiter isn't sent, so it doesn't count as an attempt.
+        let in_flight_limiter = &self.in_flight_limiter;
+        let in_flight_limit_behavior = self.execution_profile.in_flight_limit_behavior;
+        let acquire_permits = async {
+            let request_permit = in_flight_limiter
+                .acquire_for_request(in_flight_limit_behavior)
+                .await?;
+            let attempt_permit = in_flight_limiter
+                .acquire_for_attempt(
+                    node.host_id,
+                    connection
+                        .get_shard_info()
+                        .as_ref()
+                        .map(|info| info.shard.into()),
+                    in_flight_limit_behavior,
+                )
+                .await?;
+            Ok((request_permit, attempt_permit))
+        };
+        let _in_flight_permits = match &self.request_deadline {
+            Some(deadline) => deadline.run(acquire_permits).await?,
+            None => acquire_permits.await?,
+        };
+
         self.metrics.inc_total_paged_queries();
         let query_start = std::time::Instant::now();
 
@@ -663,10 +964,16 @@ where
         );
         self.log_attempt_start(connection.get_connect_address());
 
-        let query_response =
-            (self.page_query)(connection.clone(), consistency, self.paging_state.clone())
-                .await
-                .and_then(QueryResponse::into_non_error_query_response);
+        let page_query_fut =
+            (self.page_query)(connection.clone(), consistency, self.paging_state.clone());
+        let query_response = match &self.request_deadline {
+            Some(deadline) => {
+                deadline.inc_attempts();
+                deadline.run(page_query_fut).await
+            }
+            None => page_query_fut.await,
+        }
+        .and_then(QueryResponse::into_non_error_query_response);
 
         let elapsed = query_start.elapsed();
 
@@ -676,6 +983,7 @@ where
             Ok(NonErrorQueryResponse {
                 response: NonErrorResponse::Result(result::Result::Rows(mut rows)),
                 tracing_id,
+                custom_payload,
                 ..
             }) => {
                 let _ = self.metrics.log_query_latency(elapsed.as_millis() as u64);
@@ -689,7 +997,12 @@ where
 
                 request_span.record_rows_fields(&rows);
 
-                let received_page = ReceivedPage { rows, tracing_id };
+                let received_page = ReceivedPage {
+                    rows,
+                    paging_state: self.paging_state.clone(),
+                    tracing_id,
+                    custom_payload,
+                };
 
                 // Send next page to RowIterator
                 let (proof, res) = self.sender.send(Ok(received_page)).await;
@@ -719,13 +1032,17 @@ where
             Ok(NonErrorQueryResponse {
                 response: NonErrorResponse::Result(_),
                 tracing_id,
+                custom_payload,
                 ..
             }) => {
                 // We have most probably sent a modification statement (e.g. INSERT or UPDATE),
                 // so let's return an empty iterator as suggested in #631.
 
                 // We must attempt to send something because the iterator expects it.
-                let (proof, _) = self.sender.send_empty_page(tracing_id).await;
+                let (proof, _) = self
+                    .sender
+                    .send_empty_page(tracing_id, custom_payload)
+                    .await;
                 Ok(ControlFlow::Break(proof))
             }
             Ok(_) => {
@@ -855,7 +1172,9 @@ where
                         .sender
                         .send(Ok(ReceivedPage {
                             rows,
+                            paging_state: paging_state.clone(),
                             tracing_id: response.tracing_id,
+                            custom_payload: response.custom_payload,
                         }))
                         .await;
                     if paging_state.is_none() || send_result.is_err() {
@@ -867,7 +1186,10 @@ where
                     // so let's return an empty iterator as suggested in #631.
 
                     // We must attempt to send something because the iterator expects it.
-                    let (proof, _) = self.sender.send_empty_page(response.tracing_id).await;
+                    let (proof, _) = self
+                        .sender
+                        .send_empty_page(response.tracing_id, response.custom_payload)
+                        .await;
                     return Ok(proof);
                 }
                 _ => {
@@ -894,6 +1216,11 @@ impl<RowT> TypedRowIterator<RowT> {
         self.row_iterator.get_tracing_ids()
     }
 
+    /// Returns the custom payload attached by the server to the most recently received page
+    pub fn get_custom_payload(&self) -> Option<&HashMap<String, Vec<u8>>> {
+        self.row_iterator.get_custom_payload()
+    }
+
     /// Returns specification of row columns
     pub fn get_column_specs(&self) -> &[ColumnSpec] {
         self.row_iterator.get_column_specs()
@@ -910,6 +1237,14 @@ pub enum NextRowError {
     /// Parsing values in row as given types failed
     #[error(transparent)]
     FromRowError(#[from] FromRowError),
+
+    /// The columns of a received page don't match the requested type
+    #[error("Type check failed: {0}")]
+    TypeCheckError(#[from] TypeCheckError),
+
+    /// Deserializing a row as the requested type failed
+    #[error("Deserialization failed: {0}")]
+    DeserializationError(#[from] DeserializationError),
 }
 
 /// Fetching pages is asynchronous so `TypedRowIterator` does not implement the `Iterator` trait.\
@@ -938,3 +1273,204 @@ impl<RowT: FromRow> Stream for TypedRowIterator<RowT> {
 
 // TypedRowIterator can be moved freely for any RowT so it's Unpin
 impl<RowT> Unpin for TypedRowIterator<RowT> {}
+
+/// Stream over rows returned by paged queries
+/// where each row is deserialized as the given type\
+/// Returned by [`RowIterator::rows_stream`]
+pub struct TypedRowStream<RowT> {
+    row_iterator: RowIterator,
+    // Whether the columns of the current page were checked against `RowT`.
+    current_page_type_checked: bool,
+    phantom_data: std::marker::PhantomData<RowT>,
+}
+
+impl<RowT> TypedRowStream<RowT> {
+    /// If tracing was enabled returns tracing ids of all finished page queries
+    pub fn get_tracing_ids(&self) -> &[Uuid] {
+        self.row_iterator.get_tracing_ids()
+    }
+
+    /// Returns the custom payload attached by the server to the most recently received page
+    pub fn get_custom_payload(&self) -> Option<&HashMap<String, Vec<u8>>> {
+        self.row_iterator.get_custom_payload()
+    }
+
+    /// Returns specification of row columns
+    pub fn get_column_specs(&self) -> &[ColumnSpec] {
+        self.row_iterator.get_column_specs()
+    }
+}
+
+/// Fetching pages is asynchronous so `TypedRowStream` does not implement the `Iterator` trait.\
+/// Instead it uses the asynchronous `Stream` trait
+impl<RowT: for<'frame> DeserializeRow<'frame>> Stream for TypedRowStream<RowT> {
+    type Item = Result<RowT, NextRowError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let s = self.as_mut().get_mut();
+        let row_iterator = &mut s.row_iterator;
+
+        // Zero-sized pages are skipped.
+        while row_iterator.is_current_page_exhausted() {
+            match row_iterator.poll_next_page(cx) {
+                Poll::Ready(Some(Ok(()))) => s.current_page_type_checked = false,
+                Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err.into()))),
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+
+        if !s.current_page_type_checked {
+            if let Err(err) = RowT::type_check(row_iterator.get_column_specs()) {
+                // Skip the page, so that the error is returned only once.
+                row_iterator.current_row_idx = row_iterator.current_page.rows_count;
+                return Poll::Ready(Some(Err(err.into())));
+            }
+            s.current_page_type_checked = true;
+        }
+
+        Poll::Ready(row_iterator.deserialize_next_row())
+    }
+}
+
+// TypedRowStream can be moved freely for any RowT so it's Unpin
+impl<RowT> Unpin for TypedRowStream<RowT> {}
+
+/// Stream over pages returned by paged queries, with rows deserialized as the given type
+/// and the paging state after each page\
+/// Returned by [`RowIterator::pages_stream`]
+pub struct TypedPageStream<RowT> {
+    row_iterator: RowIterator,
+    current_page_returned: bool,
+    phantom_data: std::marker::PhantomData<RowT>,
+}
+
+impl<RowT> TypedPageStream<RowT> {
+    /// If tracing was enabled returns tracing ids of all finished page queries
+    pub fn get_tracing_ids(&self) -> &[Uuid] {
+        self.row_iterator.get_tracing_ids()
+    }
+
+    /// Returns the custom payload attached by the server to the most recently received page
+    pub fn get_custom_payload(&self) -> Option<&HashMap<String, Vec<u8>>> {
+        self.row_iterator.get_custom_payload()
+    }
+
+    /// Returns specification of row columns
+    pub fn get_column_specs(&self) -> &[ColumnSpec] {
+        self.row_iterator.get_column_specs()
+    }
+}
+
+impl<RowT: for<'frame> DeserializeRow<'frame>> Stream for TypedPageStream<RowT> {
+    type Item = Result<(Vec<RowT>, Option<PagingState>), NextRowError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let s = self.as_mut().get_mut();
+        let row_iterator = &mut s.row_iterator;
+
+        if s.current_page_returned {
+            match row_iterator.poll_next_page(cx) {
+                Poll::Ready(Some(Ok(()))) => (),
+                Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err.into()))),
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+        s.current_page_returned = true;
+
+        let page = &row_iterator.current_page;
+        let rows = page
+            .rows_iter::<RowT>()
+            .map_err(NextRowError::from)
+            .and_then(|rows| {
+                rows.skip(row_iterator.current_row_idx)
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(NextRowError::from)
+            });
+        row_iterator.current_row_idx = page.rows_count;
+
+        let paging_state = row_iterator
+            .current_paging_state
+            .clone()
+            .map(|raw| PagingState::new(raw, &row_iterator.statement));
+        Poll::Ready(Some(rows.map(|rows| (rows, paging_state))))
+    }
+}
+
+// TypedPageStream can be moved freely for any RowT so it's Unpin
+impl<RowT> Unpin for TypedPageStream<RowT> {}
+
+/// Stream over pages returned by paged queries,
+/// where each page is converted into an Arrow [`RecordBatch`](arrow::record_batch::RecordBatch)\
+/// Returned by [`RowIterator::record_batches`]
+#[cfg(feature = "arrow")]
+pub struct RecordBatchStream {
+    row_iterator: RowIterator,
+    schema: Arc<arrow::datatypes::Schema>,
+}
+
+#[cfg(feature = "arrow")]
+impl RecordBatchStream {
+    /// Returns the Arrow schema shared by all record batches of the stream
+    pub fn schema(&self) -> Arc<arrow::datatypes::Schema> {
+        self.schema.clone()
+    }
+
+    /// If tracing was enabled returns tracing ids of all finished page queries
+    pub fn get_tracing_ids(&self) -> &[Uuid] {
+        self.row_iterator.get_tracing_ids()
+    }
+
+    /// Returns the custom payload attached by the server to the most recently received page
+    pub fn get_custom_payload(&self) -> Option<&HashMap<String, Vec<u8>>> {
+        self.row_iterator.get_custom_payload()
+    }
+
+    /// Returns specification of row columns
+    pub fn get_column_specs(&self) -> &[ColumnSpec] {
+        self.row_iterator.get_column_specs()
+    }
+}
+
+/// Fetching pages is asynchronous so `RecordBatchStream` does not implement the `Iterator` trait.\
+/// Instead it uses the asynchronous `Stream` trait
+#[cfg(feature = "arrow")]
+impl Stream for RecordBatchStream {
+    type Item = Result<arrow::record_batch::RecordBatch, NextRecordBatchError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let s = self.as_mut().get_mut();
+
+        loop {
+            let row_iterator = &mut s.row_iterator;
+            // Empty pages are skipped, they would only produce empty batches.
+            if row_iterator.is_current_page_exhausted() {
+                match row_iterator.poll_next_page(cx) {
+                    Poll::Ready(Some(Ok(()))) => continue,
+                    Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err.into()))),
+                    Poll::Ready(None) => return Poll::Ready(None),
+                    Poll::Pending => return Poll::Pending,
+                }
+            }
+
+            // Convert the rest of the page, skipping rows which might
+            // have already been returned by the `RowIterator`.
+            let page = &row_iterator.current_page;
+            let skipped = row_iterator.current_row_idx;
+            row_iterator.current_row_idx = page.rows_count;
+            row_iterator.current_row_offset = None;
+            let batch = page
+                .rows_iter::<ColumnIterator>()
+                .map_err(|err| ArrowConversionError::from(DeserializationError::new(err)))
+                .and_then(|rows| {
+                    record_batch::rows_to_record_batch(
+                        s.schema.clone(),
+                        &page.metadata.col_specs,
+                        rows.skip(skipped),
+                    )
+                });
+            return Poll::Ready(Some(batch.map_err(NextRecordBatchError::from)));
+        }
+    }
+}
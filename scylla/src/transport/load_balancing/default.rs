@@ -2680,6 +2680,8 @@ mod latency_awareness {
                 QueryError::BadQuery(_)
                 | QueryError::TooManyOrphanedStreamIds(_)
                 | QueryError::UnableToAllocStreamId
+                | QueryError::TooManyInFlightRequests(..)
+                | QueryError::SessionClosed
                 | QueryError::DbError(DbError::IsBootstrapping, _)
                 | QueryError::DbError(DbError::Unavailable { .. }, _)
                 | QueryError::DbError(DbError::Unprepared { .. }, _)
@@ -2693,7 +2695,8 @@ mod latency_awareness {
                 | QueryError::IoError(_)
                 | QueryError::ProtocolError(_)
                 | QueryError::TimeoutError
-                | QueryError::RequestTimeout(_) => true,
+                | QueryError::RequestTimeout(_)
+                | QueryError::RequestDeadlineExceeded { .. } => true,
             }
         }
     }
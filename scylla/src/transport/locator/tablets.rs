@@ -335,6 +335,11 @@ impl TableTablets {
         tablet.filter(|t| t.first_token <= token)
     }
 
+    /// Ranges of the known tablets, as (first token, last token), both inclusive.
+    pub(crate) fn tablet_ranges(&self) -> impl Iterator<Item = (Token, Token)> + '_ {
+        self.tablet_list.iter().map(Tablet::range)
+    }
+
     pub(crate) fn replicas_for_token(&self, token: Token) -> Option<&[(Arc<Node>, Shard)]> {
         self.tablet_for_token(token)
             .map(|tablet| tablet.replicas.all.as_ref())
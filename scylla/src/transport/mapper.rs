@@ -0,0 +1,342 @@
+//! Mapping Rust structs to the rows of a table.
+//!
+//! [`Table`] describes a table and the CQL statements for basic operations
+//! on its rows. It is usually implemented with `#[derive(Table)]`, together
+//! with `SerializeRow` and `DeserializeRow`. [`Mapper`] prepares those
+//! statements once and executes them.
+
+use std::hash::BuildHasher;
+use std::marker::PhantomData;
+
+use scylla_cql::errors::{BadQuery, QueryError};
+use scylla_cql::types::serialize::row::SerializeRow;
+
+use crate::prepared_statement::PreparedStatement;
+use crate::query::Query;
+use crate::transport::caching_session::CachingSession;
+use crate::{QueryResult, Session};
+
+/// A Rust type whose values are stored as the rows of a table.
+///
+/// The column names are the same ones which are used by the `SerializeRow`
+/// and `DeserializeRow` implementations of the type. The bind markers
+/// of prepared statements are named after their columns, so the type's values
+/// can be passed directly to the `INSERT` and `UPDATE` statements.
+pub trait Table {
+    /// The keyspace of the table, or `None` if the statements use
+    /// the session's current keyspace.
+    const KEYSPACE: Option<&'static str>;
+
+    /// The name of the table.
+    const TABLE: &'static str;
+
+    /// Names of all the columns mapped to the type.
+    const COLUMNS: &'static [&'static str];
+
+    /// Names of the partition key columns.
+    const PARTITION_KEY: &'static [&'static str];
+
+    /// Names of the clustering key columns.
+    const CLUSTERING_KEY: &'static [&'static str];
+
+    /// Inserts all the columns.
+    const INSERT: &'static str;
+
+    /// Selects all the columns of the row with given primary key.
+    const SELECT: &'static str;
+
+    /// Updates all the regular columns of the row with given primary key.
+    /// `None` if there are no regular columns, as CQL doesn't allow such an update.
+    const UPDATE: Option<&'static str>;
+
+    /// Deletes the row with given primary key.
+    const DELETE: &'static str;
+
+    /// Values of the primary key columns, in the order of the bind markers
+    /// of [`SELECT`](Table::SELECT) and [`DELETE`](Table::DELETE).
+    type PrimaryKey<'r>: SerializeRow
+    where
+        Self: 'r;
+
+    /// Returns the primary key of this row.
+    fn primary_key(&self) -> Self::PrimaryKey<'_>;
+}
+
+/// Executes the statements of a [`Table`], prepared once when the mapper is created.
+///
+/// ```rust
+/// # use scylla::{Mapper, Session, Table, SerializeRow, DeserializeRow};
+/// # use std::error::Error;
+/// # async fn check_only_compiles(session: &Session) -> Result<(), Box<dyn Error>> {
+/// #[derive(Table, SerializeRow, DeserializeRow)]
+/// #[scylla(keyspace = "ks", table = "users")]
+/// struct User {
+///     #[scylla(partition_key)]
+///     id: i32,
+///     name: String,
+/// }
+///
+/// let users = Mapper::<User>::new(session).await?;
+/// users.insert(&User { id: 1, name: "Alice".to_owned() }).await?;
+///
+/// let result = users.select((1,)).await?;
+/// let user: Option<User> = result.maybe_first_row::<User>()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Mapper<'s, T> {
+    session: &'s Session,
+    insert: PreparedStatement,
+    select: PreparedStatement,
+    update: Option<PreparedStatement>,
+    delete: PreparedStatement,
+    _table: PhantomData<fn(&T)>,
+}
+
+impl<'s, T: Table> Mapper<'s, T> {
+    /// Prepares the statements of the table on the given session.
+    pub async fn new(session: &'s Session) -> Result<Mapper<'s, T>, QueryError> {
+        let update = match T::UPDATE {
+            Some(update) => Some(session.prepare(update).await?),
+            None => None,
+        };
+        Ok(Self {
+            session,
+            insert: session.prepare(T::INSERT).await?,
+            select: session.prepare(T::SELECT).await?,
+            update,
+            delete: session.prepare(T::DELETE).await?,
+            _table: PhantomData,
+        })
+    }
+
+    /// Prepares the statements of the table using the prepared statement
+    /// cache of the given [`CachingSession`], and executes them on its session.
+    pub async fn with_caching_session<S>(
+        session: &'s CachingSession<S>,
+    ) -> Result<Mapper<'s, T>, QueryError>
+    where
+        S: BuildHasher + Clone,
+    {
+        let prepare = |contents: &'static str| async move {
+            session.add_prepared_statement(&Query::new(contents)).await
+        };
+        let update = match T::UPDATE {
+            Some(update) => Some(prepare(update).await?),
+            None => None,
+        };
+        Ok(Self {
+            session: session.get_session(),
+            insert: prepare(T::INSERT).await?,
+            select: prepare(T::SELECT).await?,
+            update,
+            delete: prepare(T::DELETE).await?,
+            _table: PhantomData,
+        })
+    }
+
+    /// Inserts the row.
+    pub async fn insert(&self, row: &T) -> Result<QueryResult, QueryError>
+    where
+        T: SerializeRow,
+    {
+        self.session.execute(&self.insert, row).await
+    }
+
+    /// Selects the row with given primary key.
+    /// The key can be obtained from a row with [`Table::primary_key`].
+    pub async fn select(&self, key: impl SerializeRow) -> Result<QueryResult, QueryError> {
+        self.session.execute(&self.select, key).await
+    }
+
+    /// Updates the regular columns of the row.
+    /// Fails if the table has no regular columns.
+    pub async fn update(&self, row: &T) -> Result<QueryResult, QueryError>
+    where
+        T: SerializeRow,
+    {
+        let update = self.update.as_ref().ok_or_else(|| {
+            BadQuery::Other(format!(
+                "table {} has no regular columns to update",
+                T::TABLE
+            ))
+        })?;
+        self.session.execute(update, row).await
+    }
+
+    /// Deletes the row with given primary key.
+    /// The key can be obtained from a row with [`Table::primary_key`].
+    pub async fn delete(&self, key: impl SerializeRow) -> Result<QueryResult, QueryError> {
+        self.session.execute(&self.delete, key).await
+    }
+}
+
+/// ```compile_fail
+///
+/// #[derive(scylla::Table)]
+/// struct TestRow {
+///     #[scylla(partition_key)]
+///     a: i32,
+/// }
+/// ```
+fn _test_table_requires_table_name() {}
+
+/// ```compile_fail
+///
+/// #[derive(scylla::Table)]
+/// #[scylla(table = "t")]
+/// struct TestRow {
+///     #[scylla(clustering_key)]
+///     a: i32,
+/// }
+/// ```
+fn _test_table_requires_partition_key() {}
+
+/// ```compile_fail
+///
+/// #[derive(scylla::Table)]
+/// #[scylla(table = "t")]
+/// struct TestRow {
+///     #[scylla(partition_key, skip)]
+///     a: i32,
+/// }
+/// ```
+fn _test_table_key_column_cannot_be_skipped() {}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::{create_new_session_builder, setup_tracing};
+    use crate::utils::test_utils::unique_keyspace_name;
+    use crate::{CachingSession, DeserializeRow, Mapper, SerializeRow, Table};
+
+    #[derive(Table, SerializeRow, DeserializeRow, Debug, PartialEq, Eq, Clone)]
+    #[scylla(crate = crate, table = "events")]
+    struct Event {
+        #[scylla(partition_key)]
+        device: i32,
+        #[scylla(clustering_key)]
+        time: i64,
+        #[scylla(partition_key, rename = "Region")]
+        region: String,
+        payload: Option<String>,
+        #[scylla(skip)]
+        cached: bool,
+    }
+
+    #[derive(Table)]
+    #[scylla(crate = crate, keyspace = "ks", table = "KeyOnly")]
+    struct KeyOnly {
+        #[scylla(partition_key)]
+        a: i32,
+    }
+
+    // Only the statements generated for it are checked.
+    #[derive(Table)]
+    #[scylla(crate = crate, table = "order")]
+    struct Order {
+        #[scylla(partition_key)]
+        r#type: i32,
+        #[allow(dead_code)]
+        from: String,
+    }
+
+    #[test]
+    fn test_generated_statements() {
+        assert_eq!(Event::KEYSPACE, None);
+        assert_eq!(Event::TABLE, "events");
+        assert_eq!(Event::COLUMNS, ["device", "time", "Region", "payload"]);
+        assert_eq!(Event::PARTITION_KEY, ["device", "Region"]);
+        assert_eq!(Event::CLUSTERING_KEY, ["time"]);
+        assert_eq!(
+            Event::INSERT,
+            r#"INSERT INTO events (device, time, "Region", payload) VALUES (?, ?, ?, ?)"#
+        );
+        assert_eq!(
+            Event::SELECT,
+            r#"SELECT device, time, "Region", payload FROM events WHERE device = ? AND "Region" = ? AND time = ?"#
+        );
+        assert_eq!(
+            Event::UPDATE,
+            Some(r#"UPDATE events SET payload = ? WHERE device = ? AND "Region" = ? AND time = ?"#)
+        );
+        assert_eq!(
+            Event::DELETE,
+            r#"DELETE FROM events WHERE device = ? AND "Region" = ? AND time = ?"#
+        );
+
+        assert_eq!(KeyOnly::KEYSPACE, Some("ks"));
+        assert_eq!(
+            KeyOnly::INSERT,
+            r#"INSERT INTO ks."KeyOnly" (a) VALUES (?)"#
+        );
+        assert_eq!(KeyOnly::UPDATE, None);
+        assert_eq!(KeyOnly::DELETE, r#"DELETE FROM ks."KeyOnly" WHERE a = ?"#);
+
+        // Raw identifiers are unrawed, reserved keywords are quoted
+        assert_eq!(Order::COLUMNS, ["type", "from"]);
+        assert_eq!(
+            Order::INSERT,
+            r#"INSERT INTO "order" (type, "from") VALUES (?, ?)"#
+        );
+    }
+
+    #[test]
+    fn test_primary_key() {
+        let event = Event {
+            device: 1,
+            time: 2,
+            region: "eu".to_owned(),
+            payload: None,
+            cached: false,
+        };
+        assert_eq!(event.primary_key(), (&1, &"eu".to_owned(), &2));
+    }
+
+    #[tokio::test]
+    async fn test_mapper() {
+        setup_tracing();
+        let session = create_new_session_builder().build().await.unwrap();
+        let ks = unique_keyspace_name();
+
+        session
+            .query(format!("CREATE KEYSPACE IF NOT EXISTS {ks} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}"), &[])
+            .await
+            .unwrap();
+        session
+            .query(
+                format!(
+                    r#"CREATE TABLE IF NOT EXISTS {ks}.events (device int, time bigint, "Region" text, payload text, PRIMARY KEY ((device, "Region"), time))"#
+                ),
+                &[],
+            )
+            .await
+            .unwrap();
+        session.use_keyspace(ks, false).await.unwrap();
+
+        let mut event = Event {
+            device: 1,
+            time: 2,
+            region: "eu".to_owned(),
+            payload: Some("abc".to_owned()),
+            cached: false,
+        };
+
+        let mapper = Mapper::<Event>::new(&session).await.unwrap();
+        mapper.insert(&event).await.unwrap();
+        let result = mapper.select(event.primary_key()).await.unwrap();
+        assert_eq!(result.single_row::<Event>().unwrap(), event);
+
+        let session: CachingSession = CachingSession::from(session, 10);
+        let mapper = Mapper::<Event>::with_caching_session(&session)
+            .await
+            .unwrap();
+        event.payload = None;
+        mapper.update(&event).await.unwrap();
+        let result = mapper.select(event.primary_key()).await.unwrap();
+        assert_eq!(result.single_row::<Event>().unwrap(), event);
+
+        mapper.delete(event.primary_key()).await.unwrap();
+        let result = mapper.select(event.primary_key()).await.unwrap();
+        assert_eq!(result.maybe_first_row::<Event>().unwrap(), None);
+    }
+}
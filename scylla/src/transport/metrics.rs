@@ -28,6 +28,9 @@ pub struct Metrics {
     errors_iter_num: AtomicU64,
     queries_iter_num: AtomicU64,
     retries_num: AtomicU64,
+    in_flight_limit_reached_num: AtomicU64,
+    in_flight_rejected_num: AtomicU64,
+    in_flight_queued_num: AtomicU64,
     histogram: Arc<Mutex<Histogram>>,
 }
 
@@ -39,6 +42,9 @@ impl Metrics {
             errors_iter_num: AtomicU64::new(0),
             queries_iter_num: AtomicU64::new(0),
             retries_num: AtomicU64::new(0),
+            in_flight_limit_reached_num: AtomicU64::new(0),
+            in_flight_rejected_num: AtomicU64::new(0),
+            in_flight_queued_num: AtomicU64::new(0),
             histogram: Arc::new(Mutex::new(Histogram::new())),
         }
     }
@@ -69,6 +75,26 @@ impl Metrics {
         self.retries_num.fetch_add(1, ORDER_TYPE);
     }
 
+    /// Increments counter measuring how many times a request hit an in-flight limit
+    pub(crate) fn inc_in_flight_limit_reached(&self) {
+        self.in_flight_limit_reached_num.fetch_add(1, ORDER_TYPE);
+    }
+
+    /// Increments counter for requests rejected because of an in-flight limit
+    pub(crate) fn inc_in_flight_rejected(&self) {
+        self.in_flight_rejected_num.fetch_add(1, ORDER_TYPE);
+    }
+
+    /// Increments the number of requests waiting for an in-flight limit
+    pub(crate) fn inc_in_flight_queued(&self) {
+        self.in_flight_queued_num.fetch_add(1, ORDER_TYPE);
+    }
+
+    /// Decrements the number of requests waiting for an in-flight limit
+    pub(crate) fn dec_in_flight_queued(&self) {
+        self.in_flight_queued_num.fetch_sub(1, ORDER_TYPE);
+    }
+
     /// Saves to histogram latency of completing single query.
     /// For paged queries it should log latency for every page.
     ///
@@ -120,4 +146,20 @@ impl Metrics {
     pub fn get_retries_num(&self) -> u64 {
         self.retries_num.load(ORDER_TYPE)
     }
+
+    /// Returns counter measuring how many times a request hit an in-flight limit,
+    /// i.e. had to wait for other requests to complete or was rejected
+    pub fn get_in_flight_limit_reached_num(&self) -> u64 {
+        self.in_flight_limit_reached_num.load(ORDER_TYPE)
+    }
+
+    /// Returns counter for requests rejected because of an in-flight limit
+    pub fn get_in_flight_rejected_num(&self) -> u64 {
+        self.in_flight_rejected_num.load(ORDER_TYPE)
+    }
+
+    /// Returns the number of requests currently waiting for an in-flight limit
+    pub fn get_in_flight_queued_num(&self) -> u64 {
+        self.in_flight_queued_num.load(ORDER_TYPE)
+    }
 }
@@ -0,0 +1,683 @@
+//! Applying versioned schema migrations.
+//!
+//! A migration is a CQL file named `<version>_<description>.cql`, e.g. `003_add_users_email.cql`,
+//! holding statements separated by semicolons. [`Migrator`] applies the migrations which haven't
+//! been applied yet, in the order of their versions, and records each applied migration,
+//! with a checksum of its file, in a tracking table.
+//!
+//! Before reading the tracking table, the migrator takes a lock, a row inserted with a lightweight
+//! transaction, so that deployers starting at the same time don't apply the same migrations twice.
+//! The lock expires after a TTL if its holder dies, and is renewed after each statement.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use scylla_cql::frame::response::result::Row;
+use scylla_cql::frame::value::CqlTimestamp;
+use scylla_cql::types::deserialize::DeserializationError;
+use thiserror::Error;
+use tokio::time::Instant;
+use tracing::{debug, info};
+use uuid::Uuid;
+
+use crate::prepared_statement::PreparedStatement;
+use crate::transport::errors::QueryError;
+use crate::transport::query_result::{LwtError, LwtResultError, RowsError};
+use crate::utils::hash::fnv1a_64;
+use crate::utils::pretty::CqlIdentifierDisplayer;
+use crate::Session;
+
+/// A single migration: a version and the statements to be run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Migration {
+    version: i64,
+    description: String,
+    statements: Vec<String>,
+    checksum: i64,
+}
+
+impl Migration {
+    /// Creates a migration from CQL text holding statements separated by semicolons.
+    /// Comments are allowed. The checksum is computed from the whole text.
+    pub fn new(version: i64, description: impl Into<String>, cql: &str) -> Self {
+        Self {
+            version,
+            description: description.into(),
+            statements: split_statements(cql),
+            checksum: fnv1a_64(cql.as_bytes()) as i64,
+        }
+    }
+
+    /// Reads the migrations from the `.cql` files of a directory, sorted by version.
+    /// Files with other extensions are ignored.
+    pub fn from_dir(dir: impl AsRef<Path>) -> Result<Vec<Migration>, MigrationError> {
+        let dir = dir.as_ref();
+        let io_error = |path: &Path| {
+            let path = path.to_owned();
+            move |error| MigrationError::Io { path, error }
+        };
+
+        let mut migrations = Vec::new();
+        for entry in fs::read_dir(dir).map_err(io_error(dir))? {
+            let path = entry.map_err(io_error(dir))?.path();
+            if !path.is_file() || path.extension().and_then(|ext| ext.to_str()) != Some("cql") {
+                continue;
+            }
+            let (version, description) = parse_file_name(&path)
+                .ok_or_else(|| MigrationError::InvalidFileName(path.clone()))?;
+            let cql = fs::read_to_string(&path).map_err(io_error(&path))?;
+            migrations.push(Migration::new(version, description, &cql));
+        }
+        migrations.sort_by_key(|migration| migration.version);
+        Ok(migrations)
+    }
+
+    /// Returns the version of the migration.
+    pub fn version(&self) -> i64 {
+        self.version
+    }
+
+    /// Returns the description of the migration.
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// Returns the statements of the migration, in order.
+    pub fn statements(&self) -> &[String] {
+        &self.statements
+    }
+
+    /// Returns the checksum of the text of the migration.
+    pub fn checksum(&self) -> i64 {
+        self.checksum
+    }
+}
+
+/// A migration recorded in the tracking table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppliedMigration {
+    pub version: i64,
+    pub description: String,
+    pub checksum: i64,
+    pub applied_at: CqlTimestamp,
+}
+
+/// An error returned by [`Migrator`].
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum MigrationError {
+    /// A migration file or directory couldn't be read.
+    #[error("Failed to read {path}: {error}")]
+    Io {
+        path: PathBuf,
+        error: std::io::Error,
+    },
+
+    /// The name of a migration file isn't of the form `<version>_<description>.cql`.
+    #[error("Invalid name of migration file {0}, expected <version>_<description>.cql")]
+    InvalidFileName(PathBuf),
+
+    /// Two migrations have the same version.
+    #[error("Migration version {0} is defined more than once")]
+    DuplicateVersion(i64),
+
+    /// A migration was changed after it had been applied.
+    #[error("Migration {version} was changed after it had been applied (checksum {applied_checksum}, now {checksum})")]
+    ChecksumMismatch {
+        version: i64,
+        applied_checksum: i64,
+        checksum: i64,
+    },
+
+    /// A migration which hasn't been applied is older than the latest applied migration.
+    #[error(
+        "Migration {version} hasn't been applied, but the newer migration {latest_applied} has"
+    )]
+    OutOfOrder { version: i64, latest_applied: i64 },
+
+    /// The lock was held by another deployer for longer than the lock timeout.
+    #[error("Timed out waiting for the migration lock")]
+    LockTimeout,
+
+    /// The lock expired while the migrations were being applied.
+    #[error("The migration lock expired while migrations were being applied")]
+    LockLost,
+
+    /// A statement of a migration failed. The statements of the migration which preceded it
+    /// were applied, but the migration wasn't recorded as applied.
+    #[error("Statement {statement} of migration {version} failed: {error}")]
+    MigrationFailed {
+        version: i64,
+        statement: usize,
+        error: QueryError,
+    },
+
+    /// A query on the tracking table failed.
+    #[error(transparent)]
+    Query(#[from] QueryError),
+
+    /// The result of a lightweight transaction on the lock wasn't as expected.
+    #[error(transparent)]
+    Lwt(#[from] LwtResultError),
+
+    /// The tracking table doesn't have the expected columns.
+    #[error(transparent)]
+    Rows(#[from] RowsError),
+
+    /// A row of the tracking table couldn't be deserialized.
+    #[error(transparent)]
+    Deserialization(#[from] DeserializationError),
+}
+
+impl From<LwtError> for MigrationError {
+    fn from(error: LwtError) -> Self {
+        match error {
+            LwtError::Query(error) => MigrationError::Query(error),
+            LwtError::Result(error) => MigrationError::Lwt(error),
+        }
+    }
+}
+
+/// Applies migrations which haven't been applied yet.
+///
+/// The tracking table, `schema_migrations` by default, and its lock table, with the `_lock`
+/// suffix, are created in the given keyspace, which must exist. The statements of migrations
+/// aren't run in this keyspace, so they should use qualified table names.
+///
+/// Migrations are applied statement by statement, waiting for schema agreement after each one.
+/// If a statement fails, the migration isn't recorded and is run from its first statement
+/// the next time, so statements should be idempotent, e.g. `CREATE TABLE IF NOT EXISTS`.
+///
+/// ```rust
+/// # use scylla::Session;
+/// # use std::error::Error;
+/// # async fn check_only_compiles(session: &Session) -> Result<(), Box<dyn Error>> {
+/// use scylla::transport::migrations::{Migration, Migrator};
+///
+/// let applied = Migrator::new(session, "app")
+///     .migrations(Migration::from_dir("migrations")?)
+///     .run()
+///     .await?;
+/// println!("Applied migrations: {:?}", applied);
+/// # Ok(())
+/// # }
+/// ```
+pub struct Migrator<'a> {
+    session: &'a Session,
+    keyspace: String,
+    table: String,
+    migrations: Vec<Migration>,
+    lock_ttl: Duration,
+    lock_timeout: Duration,
+    lock_retry_interval: Duration,
+}
+
+const LOCK_ID: &str = "lock";
+
+impl<'a> Migrator<'a> {
+    /// Creates a migrator keeping its tracking table in the given keyspace.
+    pub fn new(session: &'a Session, keyspace: impl Into<String>) -> Self {
+        Self {
+            session,
+            keyspace: keyspace.into(),
+            table: "schema_migrations".to_owned(),
+            migrations: Vec::new(),
+            lock_ttl: Duration::from_secs(300),
+            lock_timeout: Duration::from_secs(600),
+            lock_retry_interval: Duration::from_secs(1),
+        }
+    }
+
+    /// Sets the name of the tracking table. The default is `schema_migrations`.
+    pub fn tracking_table(mut self, table: impl Into<String>) -> Self {
+        self.table = table.into();
+        self
+    }
+
+    /// Adds migrations to be applied.
+    pub fn migrations(mut self, migrations: impl IntoIterator<Item = Migration>) -> Self {
+        self.migrations.extend(migrations);
+        self
+    }
+
+    /// Sets the time after which the lock expires if it isn't renewed, which happens
+    /// after each statement. The default is 5 minutes.
+    pub fn lock_ttl(mut self, ttl: Duration) -> Self {
+        self.lock_ttl = ttl;
+        self
+    }
+
+    /// Sets how long to wait for a lock held by another deployer. The default is 10 minutes.
+    pub fn lock_timeout(mut self, timeout: Duration) -> Self {
+        self.lock_timeout = timeout;
+        self
+    }
+
+    /// Sets the interval between attempts to take a lock held by another deployer.
+    /// The default is 1 second.
+    pub fn lock_retry_interval(mut self, interval: Duration) -> Self {
+        self.lock_retry_interval = interval;
+        self
+    }
+
+    /// Applies the migrations which haven't been applied yet and returns their versions.
+    ///
+    /// Fails without applying anything if a migration which has been applied was changed,
+    /// or if a migration which hasn't been applied is older than the latest applied one.
+    /// Applied migrations which aren't known to this migrator are ignored.
+    pub async fn run(&self) -> Result<Vec<i64>, MigrationError> {
+        let mut migrations: Vec<&Migration> = self.migrations.iter().collect();
+        migrations.sort_by_key(|migration| migration.version);
+        if let Some(duplicate) = migrations
+            .windows(2)
+            .find(|pair| pair[0].version == pair[1].version)
+        {
+            return Err(MigrationError::DuplicateVersion(duplicate[0].version));
+        }
+
+        self.create_tables().await?;
+        let lock = self.lock().await?;
+        let result = self.run_locked(&lock, &migrations).await;
+        lock.release().await;
+        result
+    }
+
+    /// Returns the migrations recorded in the tracking table, sorted by version.
+    pub async fn applied(&self) -> Result<Vec<AppliedMigration>, MigrationError> {
+        self.create_tables().await?;
+        self.read_applied().await
+    }
+
+    async fn create_tables(&self) -> Result<(), MigrationError> {
+        self.session
+            .query(
+                format!(
+                    "CREATE TABLE IF NOT EXISTS {} (version bigint PRIMARY KEY, description text, checksum bigint, applied_at timestamp)",
+                    self.table_name("")
+                ),
+                (),
+            )
+            .await?;
+        self.session
+            .query(
+                format!(
+                    "CREATE TABLE IF NOT EXISTS {} (id text PRIMARY KEY, owner uuid)",
+                    self.table_name("_lock")
+                ),
+                (),
+            )
+            .await?;
+        self.session.await_schema_agreement().await?;
+        Ok(())
+    }
+
+    async fn lock(&self) -> Result<MigrationLock<'_>, MigrationError> {
+        let ttl = self.lock_ttl.as_secs().max(1) as i32;
+        let lock_table = self.table_name("_lock");
+        let acquire = self
+            .session
+            .prepare(format!(
+                "INSERT INTO {} (id, owner) VALUES (?, ?) IF NOT EXISTS USING TTL {}",
+                lock_table, ttl
+            ))
+            .await?;
+        let renew = self
+            .session
+            .prepare(format!(
+                "UPDATE {} USING TTL {} SET owner = ? WHERE id = ? IF owner = ?",
+                lock_table, ttl
+            ))
+            .await?;
+        let release = self
+            .session
+            .prepare(format!(
+                "DELETE FROM {} WHERE id = ? IF owner = ?",
+                lock_table
+            ))
+            .await?;
+
+        let owner = Uuid::new_v4();
+        let deadline = Instant::now() + self.lock_timeout;
+        loop {
+            let outcome = self
+                .session
+                .execute_lwt::<Row>(&acquire, (LOCK_ID, owner))
+                .await?;
+            if outcome.is_applied() {
+                debug!(owner = %owner, "Migration lock taken");
+                return Ok(MigrationLock {
+                    session: self.session,
+                    owner,
+                    renew,
+                    release,
+                });
+            }
+            if Instant::now() + self.lock_retry_interval > deadline {
+                return Err(MigrationError::LockTimeout);
+            }
+            debug!("Migration lock is held by another deployer, waiting");
+            tokio::time::sleep(self.lock_retry_interval).await;
+        }
+    }
+
+    async fn run_locked(
+        &self,
+        lock: &MigrationLock<'_>,
+        migrations: &[&Migration],
+    ) -> Result<Vec<i64>, MigrationError> {
+        let applied = self.read_applied().await?;
+        let latest_applied = applied.last().map(|applied| applied.version);
+
+        let mut pending = Vec::new();
+        for migration in migrations {
+            match applied
+                .binary_search_by_key(&migration.version, |applied| applied.version)
+                .map(|idx| &applied[idx])
+            {
+                Ok(applied) if applied.checksum != migration.checksum => {
+                    return Err(MigrationError::ChecksumMismatch {
+                        version: migration.version,
+                        applied_checksum: applied.checksum,
+                        checksum: migration.checksum,
+                    });
+                }
+                Ok(_) => {}
+                Err(_) => match latest_applied {
+                    Some(latest_applied) if latest_applied > migration.version => {
+                        return Err(MigrationError::OutOfOrder {
+                            version: migration.version,
+                            latest_applied,
+                        });
+                    }
+                    _ => pending.push(*migration),
+                },
+            }
+        }
+
+        let record = self
+            .session
+            .prepare(format!(
+                "INSERT INTO {} (version, description, checksum, applied_at) VALUES (?, ?, ?, toTimestamp(now()))",
+                self.table_name("")
+            ))
+            .await?;
+
+        let mut applied_now = Vec::with_capacity(pending.len());
+        for migration in pending {
+            info!(
+                version = migration.version,
+                description = %migration.description,
+                "Applying migration"
+            );
+            for (idx, statement) in migration.statements.iter().enumerate() {
+                let run_statement = async {
+                    self.session.query(statement.as_str(), ()).await?;
+                    self.session.await_schema_agreement().await
+                };
+                run_statement
+                    .await
+                    .map_err(|error| MigrationError::MigrationFailed {
+                        version: migration.version,
+                        statement: idx + 1,
+                        error,
+                    })?;
+                lock.renew().await?;
+            }
+            self.session
+                .execute(
+                    &record,
+                    (
+                        migration.version,
+                        &migration.description,
+                        migration.checksum,
+                    ),
+                )
+                .await?;
+            applied_now.push(migration.version);
+        }
+        Ok(applied_now)
+    }
+
+    async fn read_applied(&self) -> Result<Vec<AppliedMigration>, MigrationError> {
+        let result = self
+            .session
+            .query(
+                format!(
+                    "SELECT version, description, checksum, applied_at FROM {}",
+                    self.table_name("")
+                ),
+                (),
+            )
+            .await?;
+        let mut applied = result
+            .rows::<(i64, Option<String>, i64, CqlTimestamp)>()?
+            .map(|row| {
+                row.map(
+                    |(version, description, checksum, applied_at)| AppliedMigration {
+                        version,
+                        description: description.unwrap_or_default(),
+                        checksum,
+                        applied_at,
+                    },
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        applied.sort_by_key(|applied| applied.version);
+        Ok(applied)
+    }
+
+    fn table_name(&self, suffix: &str) -> String {
+        format!(
+            "{}.{}",
+            CqlIdentifierDisplayer(&self.keyspace),
+            CqlIdentifierDisplayer(&format!("{}{}", self.table, suffix))
+        )
+    }
+}
+
+struct MigrationLock<'a> {
+    session: &'a Session,
+    owner: Uuid,
+    renew: PreparedStatement,
+    release: PreparedStatement,
+}
+
+impl MigrationLock<'_> {
+    async fn renew(&self) -> Result<(), MigrationError> {
+        let outcome = self
+            .session
+            .execute_lwt::<Row>(&self.renew, (self.owner, LOCK_ID, self.owner))
+            .await?;
+        if !outcome.is_applied() {
+            return Err(MigrationError::LockLost);
+        }
+        Ok(())
+    }
+
+    async fn release(self) {
+        // If this fails, the lock expires after its TTL.
+        if let Err(error) = self
+            .session
+            .execute(&self.release, (LOCK_ID, self.owner))
+            .await
+        {
+            debug!(error = %error, "Failed to release the migration lock");
+        }
+    }
+}
+
+fn parse_file_name(path: &Path) -> Option<(i64, String)> {
+    let (version, description) = path.file_stem()?.to_str()?.split_once('_')?;
+    if version.is_empty() || !version.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    Some((version.parse().ok()?, description.to_owned()))
+}
+
+/// Splits CQL text into statements separated by semicolons, skipping comments
+/// and semicolons inside string literals and quoted identifiers.
+fn split_statements(cql: &str) -> Vec<String> {
+    let mut statements = Vec::new();
+    let mut current = String::new();
+    let mut chars = cql.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            ';' => {
+                let statement = current.trim();
+                if !statement.is_empty() {
+                    statements.push(statement.to_owned());
+                }
+                current.clear();
+            }
+            '\'' | '"' => {
+                // A doubled quote inside a literal is an escaped quote, which the loop
+                // handles as the end of one literal followed by the start of another.
+                current.push(c);
+                for next in chars.by_ref() {
+                    current.push(next);
+                    if next == c {
+                        break;
+                    }
+                }
+            }
+            '$' if chars.peek() == Some(&'$') => {
+                current.push_str("$$");
+                chars.next();
+                while let Some(next) = chars.next() {
+                    current.push(next);
+                    if next == '$' && chars.peek() == Some(&'$') {
+                        current.push('$');
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            '-' | '/' if chars.peek() == Some(&c) => {
+                for next in chars.by_ref() {
+                    if next == '\n' {
+                        current.push('\n');
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = '\0';
+                for next in chars.by_ref() {
+                    if prev == '*' && next == '/' {
+                        break;
+                    }
+                    prev = next;
+                }
+                current.push(' ');
+            }
+            _ => current.push(c),
+        }
+    }
+    let statement = current.trim();
+    if !statement.is_empty() {
+        statements.push(statement.to_owned());
+    }
+    statements
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use assert_matches::assert_matches;
+
+    use super::{parse_file_name, split_statements, Migration, MigrationError};
+    use crate::test_utils::setup_tracing;
+
+    #[test]
+    fn statements_are_split_outside_literals_and_comments() {
+        setup_tracing();
+        let cql = "
+            -- Users; and their emails.
+            CREATE TABLE ks.users (id int PRIMARY KEY, name text);
+            /* Defaults; */ INSERT INTO ks.users (id, name) VALUES (0, 'a;''b');
+            // A function.
+            CREATE FUNCTION ks.f(a int) RETURNS NULL ON NULL INPUT RETURNS int
+                LANGUAGE lua AS $$ return a; $$;
+            ALTER TABLE ks.\"Semi;colon\" ADD email text
+        ";
+        assert_eq!(
+            split_statements(cql),
+            vec![
+                "CREATE TABLE ks.users (id int PRIMARY KEY, name text)",
+                "INSERT INTO ks.users (id, name) VALUES (0, 'a;''b')",
+                "CREATE FUNCTION ks.f(a int) RETURNS NULL ON NULL INPUT RETURNS int\n                LANGUAGE lua AS $$ return a; $$",
+                "ALTER TABLE ks.\"Semi;colon\" ADD email text",
+            ]
+        );
+        assert!(split_statements(" ;\n-- Nothing here.\n").is_empty());
+    }
+
+    #[test]
+    fn migration_file_names() {
+        setup_tracing();
+        assert_eq!(
+            parse_file_name(Path::new("migrations/001_create_users.cql")),
+            Some((1, "create_users".to_owned()))
+        );
+        assert_eq!(
+            parse_file_name(Path::new("20240301_add_email.cql")),
+            Some((20240301, "add_email".to_owned()))
+        );
+        assert_eq!(parse_file_name(Path::new("create_users.cql")), None);
+        assert_eq!(parse_file_name(Path::new("v1_create_users.cql")), None);
+        assert_eq!(parse_file_name(Path::new("001.cql")), None);
+    }
+
+    #[test]
+    fn migrations_from_dir() {
+        setup_tracing();
+        let dir = std::env::temp_dir().join(format!("scylla-migrations-{}", uuid::Uuid::new_v4()));
+        fs::create_dir(&dir).unwrap();
+        fs::write(dir.join("010_second.cql"), "ALTER TABLE ks.t ADD b int;").unwrap();
+        fs::write(
+            dir.join("002_first.cql"),
+            "CREATE TABLE ks.t (a int PRIMARY KEY);",
+        )
+        .unwrap();
+        fs::write(dir.join("README.md"), "Not a migration.").unwrap();
+
+        let migrations = Migration::from_dir(&dir).unwrap();
+        assert_eq!(
+            migrations
+                .iter()
+                .map(|migration| (migration.version(), migration.description()))
+                .collect::<Vec<_>>(),
+            vec![(2, "first"), (10, "second")]
+        );
+
+        fs::write(dir.join("third.cql"), "DROP TABLE ks.t;").unwrap();
+        assert_matches!(
+            Migration::from_dir(&dir),
+            Err(MigrationError::InvalidFileName(path)) if path.ends_with("third.cql")
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn migration_checksum_depends_on_text() {
+        setup_tracing();
+        let migration = Migration::new(1, "users", "CREATE TABLE ks.t (a int PRIMARY KEY);");
+        assert_eq!(
+            migration.statements(),
+            ["CREATE TABLE ks.t (a int PRIMARY KEY)"]
+        );
+        assert_eq!(
+            migration.checksum(),
+            Migration::new(1, "users", "CREATE TABLE ks.t (a int PRIMARY KEY);").checksum()
+        );
+        assert_ne!(
+            migration.checksum(),
+            Migration::new(1, "users", "CREATE TABLE ks.t (a bigint PRIMARY KEY);").checksum()
+        );
+    }
+}
@@ -0,0 +1,618 @@
+//! Writing to two clusters at once, e.g. while migrating from one to the other.
+//!
+//! A [`MirroringSession`] wraps sessions connected to a primary and a secondary cluster.
+//! Writes are sent to both of them, while reads are served by the primary one.
+//! Some of the reads can also be sent to the secondary cluster ("shadow reads"),
+//! so that their results can be compared with the ones of the primary cluster
+//! and the differences reported to a [`MismatchReporter`].
+//!
+//! Statements are prepared on both clusters and cached, like in [`CachingSession`].
+
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use rand::Rng;
+use scylla_cql::frame::types::RawValue;
+use scylla_cql::types::serialize::batch::BatchValues;
+use scylla_cql::types::serialize::row::{
+    BuiltinTypeCheckError, BuiltinTypeCheckErrorKind, RowSerializationContext, SerializeRow,
+    SerializedValues,
+};
+use scylla_cql::types::serialize::{RowWriter, SerializationError};
+use thiserror::Error;
+use tokio::sync::oneshot;
+use tracing::warn;
+
+use crate::batch::Batch;
+use crate::frame::response::result::Row;
+use crate::query::Query;
+use crate::transport::errors::QueryError;
+use crate::transport::execution_profile::ExecutionProfileHandle;
+use crate::transport::timestamp_generator::{MonotonicTimestampGenerator, TimestampGenerator};
+use crate::{CachingSession, QueryResult, Session};
+
+/// Decides what happens when a write to the secondary cluster fails.
+/// A write to the primary cluster always has to succeed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SecondaryWritePolicy {
+    /// The failure is passed to the [`MismatchReporter`],
+    /// and the result of the primary cluster is returned.
+    #[default]
+    BestEffort,
+
+    /// The write fails with [`MirroringError::Secondary`].
+    Required,
+}
+
+/// Receives the differences between the clusters noticed by a [`MirroringSession`].
+pub trait MismatchReporter: Send + Sync + std::fmt::Debug {
+    /// Called when a shadow read returned a different result from the secondary cluster
+    /// than from the primary one.
+    fn report_mismatch(&self, statement: &str, primary: &QueryResult, secondary: &QueryResult);
+
+    /// Called when a request succeeded in the primary cluster, but failed in the secondary one,
+    /// and the failure isn't returned to the caller.
+    fn report_secondary_error(&self, statement: &str, error: &QueryError);
+}
+
+/// Logs the differences between the clusters as warnings.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LoggingMismatchReporter;
+
+impl MismatchReporter for LoggingMismatchReporter {
+    fn report_mismatch(&self, statement: &str, primary: &QueryResult, secondary: &QueryResult) {
+        warn!(
+            statement,
+            primary_rows = primary.rows_num().ok(),
+            secondary_rows = secondary.rows_num().ok(),
+            "Shadow read returned a different result from the secondary cluster"
+        );
+    }
+
+    fn report_secondary_error(&self, statement: &str, error: &QueryError) {
+        warn!(
+            statement,
+            error = %error,
+            "Request to the secondary cluster failed"
+        );
+    }
+}
+
+/// Configuration of a [`MirroringSession`].
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct MirroringConfig {
+    /// What happens when a write to the secondary cluster fails.
+    pub secondary_writes: SecondaryWritePolicy,
+
+    // Set through `shadow_read_ratio()`, which checks that it's between 0 and 1.
+    shadow_read_ratio: f64,
+
+    /// Receives the differences between the clusters.
+    pub mismatch_reporter: Arc<dyn MismatchReporter>,
+}
+
+impl Default for MirroringConfig {
+    fn default() -> Self {
+        Self {
+            secondary_writes: SecondaryWritePolicy::default(),
+            shadow_read_ratio: 0.0,
+            mismatch_reporter: Arc::new(LoggingMismatchReporter),
+        }
+    }
+}
+
+impl MirroringConfig {
+    /// Creates a configuration with best-effort writes to the secondary cluster,
+    /// no shadow reads, and the differences logged.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets what happens when a write to the secondary cluster fails.
+    pub fn secondary_writes(mut self, policy: SecondaryWritePolicy) -> Self {
+        self.secondary_writes = policy;
+        self
+    }
+
+    /// Sets the fraction of reads which are also sent to the secondary cluster,
+    /// to compare their results. Shadow reads complete in the background,
+    /// without delaying the results of the primary cluster.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `ratio` isn't between 0 and 1.
+    pub fn shadow_read_ratio(mut self, ratio: f64) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&ratio),
+            "shadow read ratio must be between 0 and 1"
+        );
+        self.shadow_read_ratio = ratio;
+        self
+    }
+
+    /// Returns the fraction of reads which are also sent to the secondary cluster.
+    pub fn get_shadow_read_ratio(&self) -> f64 {
+        self.shadow_read_ratio
+    }
+
+    /// Sets the receiver of the differences between the clusters.
+    pub fn mismatch_reporter(mut self, reporter: Arc<dyn MismatchReporter>) -> Self {
+        self.mismatch_reporter = reporter;
+        self
+    }
+}
+
+/// An error returned by [`MirroringSession`].
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum MirroringError {
+    /// The request to the primary cluster failed.
+    #[error("Request to the primary cluster failed: {0}")]
+    Primary(QueryError),
+
+    /// The write to the secondary cluster failed,
+    /// and [`SecondaryWritePolicy::Required`] is set.
+    #[error("Request to the secondary cluster failed: {0}")]
+    Secondary(QueryError),
+}
+
+/// Writes to two clusters at once, and reads from the primary one.
+///
+/// `SELECT` statements are reads, all other statements and batches are writes.
+/// A write is sent to both clusters concurrently and completes once both of them respond,
+/// so it's never faster than the slower of the clusters. When the write fails in the primary
+/// cluster, it may still have been applied in the secondary one.
+///
+/// Writes without a timestamp set get a client-side one, the same in both clusters,
+/// so that concurrent writes are resolved the same way in each of them. It comes from
+/// the [`TimestampGenerator`] configured on the primary session for the statement,
+/// or from a [`MonotonicTimestampGenerator`] if there is none.
+#[derive(Debug)]
+pub struct MirroringSession {
+    primary: CachingSession,
+    secondary: Arc<CachingSession>,
+    config: MirroringConfig,
+    // Used when the primary session has no timestamp generator configured.
+    fallback_timestamp_generator: MonotonicTimestampGenerator,
+}
+
+impl MirroringSession {
+    /// Builds a [`MirroringSession`] with the default [`MirroringConfig`].
+    /// The statements prepared in each of the clusters are cached, up to `cache_size` per cluster.
+    pub fn new(primary: Session, secondary: Session, cache_size: usize) -> Self {
+        Self::with_config(primary, secondary, cache_size, MirroringConfig::default())
+    }
+
+    /// Builds a [`MirroringSession`] with the given [`MirroringConfig`].
+    pub fn with_config(
+        primary: Session,
+        secondary: Session,
+        cache_size: usize,
+        config: MirroringConfig,
+    ) -> Self {
+        Self {
+            primary: CachingSession::from(primary, cache_size),
+            secondary: Arc::new(CachingSession::from(secondary, cache_size)),
+            config,
+            fallback_timestamp_generator: MonotonicTimestampGenerator::new(),
+        }
+    }
+
+    /// Executes a statement, prepared in each of the clusters if it wasn't before.
+    /// Writes are sent to both clusters, reads to the primary one and,
+    /// as a shadow read, possibly to the secondary one.
+    pub async fn execute(
+        &self,
+        query: impl Into<Query>,
+        values: impl SerializeRow,
+    ) -> Result<QueryResult, MirroringError> {
+        let mut query = query.into();
+
+        if !is_read(&query.contents) {
+            if query.get_timestamp().is_none() {
+                query.set_timestamp(Some(
+                    self.next_timestamp(query.get_execution_profile_handle()),
+                ));
+            }
+            let (primary_res, secondary_res) = futures::join!(
+                self.primary.execute(query.clone(), &values),
+                self.secondary.execute(query.clone(), &values),
+            );
+            return self.merge_write_results(&query.contents, primary_res, secondary_res);
+        }
+
+        let shadow_read = self.config.shadow_read_ratio > 0.0
+            && rand::thread_rng().gen_bool(self.config.shadow_read_ratio);
+        if !shadow_read {
+            return self
+                .primary
+                .execute(query, values)
+                .await
+                .map_err(MirroringError::Primary);
+        }
+
+        // The shadow read outlives the borrowed values, so they're serialized upfront
+        // for the statement prepared in the primary cluster.
+        let prepared = self
+            .primary
+            .add_prepared_statement(&query)
+            .await
+            .map_err(MirroringError::Primary)?;
+        let serialized_values = prepared
+            .serialize_values(&values)
+            .map_err(|err| MirroringError::Primary(err.into()))?;
+
+        let (primary_result_sender, primary_result_receiver) = oneshot::channel();
+        let secondary = self.secondary.clone();
+        let reporter = self.config.mismatch_reporter.clone();
+        tokio::spawn(async move {
+            let secondary_res = secondary
+                .execute(query.clone(), PreSerializedValues(serialized_values))
+                .await;
+            // Nothing to compare with if the primary read failed or was cancelled.
+            let Ok(primary_result) = primary_result_receiver.await else {
+                return;
+            };
+            match secondary_res {
+                Ok(secondary_result) => {
+                    if !results_match(&primary_result, &secondary_result) {
+                        reporter.report_mismatch(
+                            &query.contents,
+                            &primary_result,
+                            &secondary_result,
+                        );
+                    }
+                }
+                Err(err) => reporter.report_secondary_error(&query.contents, &err),
+            }
+        });
+
+        let primary_result = self
+            .primary
+            .get_session()
+            .execute(&prepared, values)
+            .await
+            .map_err(MirroringError::Primary)?;
+        let _ = primary_result_sender.send(primary_result.clone());
+        Ok(primary_result)
+    }
+
+    /// Executes a batch in both clusters, preparing its statements in each of them if needed.
+    pub async fn batch(
+        &self,
+        batch: &Batch,
+        values: impl BatchValues,
+    ) -> Result<QueryResult, MirroringError> {
+        let batch = if batch.get_timestamp().is_none() {
+            let mut batch = batch.clone();
+            batch.set_timestamp(Some(
+                self.next_timestamp(batch.get_execution_profile_handle()),
+            ));
+            Cow::Owned(batch)
+        } else {
+            Cow::Borrowed(batch)
+        };
+        let (primary_res, secondary_res) = futures::join!(
+            self.primary.batch(&batch, &values),
+            self.secondary.batch(&batch, &values),
+        );
+        self.merge_write_results("BATCH", primary_res, secondary_res)
+    }
+
+    fn next_timestamp(&self, execution_profile: Option<&ExecutionProfileHandle>) -> i64 {
+        self.primary
+            .get_session()
+            .generate_timestamp(execution_profile)
+            .unwrap_or_else(|| self.fallback_timestamp_generator.next_timestamp())
+    }
+
+    fn merge_write_results(
+        &self,
+        statement: &str,
+        primary_res: Result<QueryResult, QueryError>,
+        secondary_res: Result<QueryResult, QueryError>,
+    ) -> Result<QueryResult, MirroringError> {
+        let primary_result = primary_res.map_err(MirroringError::Primary)?;
+        match (secondary_res, self.config.secondary_writes) {
+            (Ok(_), _) => {}
+            (Err(err), SecondaryWritePolicy::Required) => {
+                return Err(MirroringError::Secondary(err));
+            }
+            (Err(err), SecondaryWritePolicy::BestEffort) => self
+                .config
+                .mismatch_reporter
+                .report_secondary_error(statement, &err),
+        }
+        Ok(primary_result)
+    }
+
+    /// Returns the session connected to the primary cluster.
+    pub fn get_primary(&self) -> &Session {
+        self.primary.get_session()
+    }
+
+    /// Returns the session connected to the secondary cluster.
+    pub fn get_secondary(&self) -> &Session {
+        self.secondary.get_session()
+    }
+
+    pub fn get_config(&self) -> &MirroringConfig {
+        &self.config
+    }
+}
+
+fn is_read(statement: &str) -> bool {
+    statement
+        .trim_start_matches(|c: char| c.is_whitespace() || c == '(')
+        .get(..6)
+        .map_or(false, |keyword| keyword.eq_ignore_ascii_case("SELECT"))
+}
+
+// Values serialized for the statement prepared in the primary cluster,
+// sent as they are to the secondary one.
+struct PreSerializedValues(SerializedValues);
+
+impl SerializeRow for PreSerializedValues {
+    fn serialize(
+        &self,
+        ctx: &RowSerializationContext<'_>,
+        writer: &mut RowWriter,
+    ) -> Result<(), SerializationError> {
+        // The statement may have been prepared differently in the secondary cluster,
+        // e.g. if the schemas of the clusters differ.
+        if ctx.columns().len() != self.0.element_count() as usize {
+            return Err(SerializationError::new(BuiltinTypeCheckError {
+                rust_name: std::any::type_name::<Self>(),
+                kind: BuiltinTypeCheckErrorKind::WrongColumnCount {
+                    rust_cols: self.0.element_count() as usize,
+                    cql_cols: ctx.columns().len(),
+                },
+            }));
+        }
+        for value in self.0.iter() {
+            let cell_writer = writer.make_cell_writer();
+            let _proof = match value {
+                RawValue::Null => cell_writer.set_null(),
+                RawValue::Unset => cell_writer.set_unset(),
+                // The value was already written as a cell, so its size is correct.
+                RawValue::Value(v) => cell_writer.set_value(v).unwrap(),
+            };
+        }
+        Ok(())
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Compares the names of the columns and the rows of the results.
+fn results_match(primary: &QueryResult, secondary: &QueryResult) -> bool {
+    fn rows(result: &QueryResult) -> Option<Vec<Row>> {
+        result.rows::<Row>().ok()?.collect::<Result<_, _>>().ok()
+    }
+
+    let column_names = |result: &QueryResult| {
+        result
+            .col_specs
+            .iter()
+            .map(|spec| spec.name.clone())
+            .collect::<Vec<_>>()
+    };
+    if column_names(primary) != column_names(secondary) {
+        return false;
+    }
+    match (rows(primary), rows(secondary)) {
+        (Some(primary_rows), Some(secondary_rows)) => primary_rows == secondary_rows,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use assert_matches::assert_matches;
+    use scylla_cql::frame::response::result::{
+        ColumnSpec, ColumnType, PreparedMetadata, TableSpec,
+    };
+    use scylla_cql::types::serialize::row::{
+        RowSerializationContext, SerializeRow, SerializedValues,
+    };
+    use scylla_cql::types::serialize::RowWriter;
+
+    use super::{
+        is_read, MirroringConfig, MirroringError, MirroringSession, MismatchReporter,
+        PreSerializedValues, SecondaryWritePolicy,
+    };
+    use crate::test_utils::setup_tracing;
+    use crate::transport::errors::QueryError;
+    use crate::utils::test_utils::{create_new_session_builder, unique_keyspace_name};
+    use crate::{QueryResult, Session};
+
+    #[test]
+    fn selects_are_reads() {
+        setup_tracing();
+        assert!(is_read("SELECT a FROM t"));
+        assert!(is_read("  select a FROM t"));
+        assert!(!is_read("INSERT INTO t (a) VALUES (?)"));
+        assert!(!is_read("UPDATE t SET a = ? WHERE b = ?"));
+        assert!(!is_read("DELETE FROM t WHERE b = ?"));
+        assert!(!is_read("SEL"));
+    }
+
+    #[test]
+    fn shadow_read_ratio_is_validated() {
+        setup_tracing();
+        assert_eq!(MirroringConfig::new().get_shadow_read_ratio(), 0.0);
+        assert_eq!(
+            MirroringConfig::new()
+                .shadow_read_ratio(0.5)
+                .get_shadow_read_ratio(),
+            0.5
+        );
+        let result = std::panic::catch_unwind(|| MirroringConfig::new().shadow_read_ratio(1.5));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn pre_serialized_values_check_column_count() {
+        setup_tracing();
+        let mut values = SerializedValues::new();
+        values.add_value(&1i32, &ColumnType::Int).unwrap();
+        values.add_value(&2i32, &ColumnType::Int).unwrap();
+        let values = PreSerializedValues(values);
+
+        let metadata = |column_count: usize| {
+            let col_specs: Vec<_> = (0..column_count)
+                .map(|i| ColumnSpec {
+                    table_spec: TableSpec::owned("ks".to_owned(), "t".to_owned()),
+                    name: format!("col_{}", i),
+                    typ: ColumnType::Int,
+                })
+                .collect();
+            PreparedMetadata {
+                flags: 0,
+                col_count: col_specs.len(),
+                pk_indexes: Vec::new(),
+                col_specs,
+            }
+        };
+
+        let mut data = Vec::new();
+        let matching = metadata(2);
+        let ctx = RowSerializationContext::from_prepared(&matching);
+        values
+            .serialize(&ctx, &mut RowWriter::new(&mut data))
+            .unwrap();
+
+        let mismatched = metadata(3);
+        let ctx = RowSerializationContext::from_prepared(&mismatched);
+        let err = values
+            .serialize(&ctx, &mut RowWriter::new(&mut data))
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("the statement operates on 3 columns, but the given rust type provides 2"));
+    }
+
+    #[derive(Debug, Default)]
+    struct RecordingReporter {
+        mismatches: Mutex<Vec<String>>,
+        errors: Mutex<Vec<String>>,
+    }
+
+    impl MismatchReporter for RecordingReporter {
+        fn report_mismatch(&self, statement: &str, _: &QueryResult, _: &QueryResult) {
+            self.mismatches.lock().unwrap().push(statement.to_owned());
+        }
+
+        fn report_secondary_error(&self, statement: &str, _: &QueryError) {
+            self.errors.lock().unwrap().push(statement.to_owned());
+        }
+    }
+
+    // Both "clusters" are keyspaces of the test cluster, used by their sessions.
+    async fn create_session(create_table: bool) -> Session {
+        let session = create_new_session_builder().build().await.unwrap();
+        let ks = unique_keyspace_name();
+        session
+            .query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[])
+            .await
+            .unwrap();
+        session.use_keyspace(ks, false).await.unwrap();
+        if create_table {
+            session
+                .query("CREATE TABLE t (a int primary key, b int)", &[])
+                .await
+                .unwrap();
+        }
+        session.await_schema_agreement().await.unwrap();
+        session
+    }
+
+    #[tokio::test]
+    async fn test_mirroring() {
+        setup_tracing();
+        let reporter = Arc::new(RecordingReporter::default());
+        let session = MirroringSession::with_config(
+            create_session(true).await,
+            create_session(true).await,
+            16,
+            MirroringConfig::new()
+                .shadow_read_ratio(1.0)
+                .mismatch_reporter(reporter.clone()),
+        );
+
+        session
+            .execute("INSERT INTO t (a, b) VALUES (?, ?)", (1, 2))
+            .await
+            .unwrap();
+        // The write has the same timestamp in both clusters
+        async fn write_time(session: &Session) -> (i64,) {
+            session
+                .query("SELECT WRITETIME(b) FROM t WHERE a = 1", &[])
+                .await
+                .unwrap()
+                .single_row::<(i64,)>()
+                .unwrap()
+        }
+        assert_eq!(
+            write_time(session.get_primary()).await,
+            write_time(session.get_secondary()).await
+        );
+
+        let select = "SELECT a, b FROM t";
+        let result = session.execute(select, ()).await.unwrap();
+        assert_eq!(result.single_row::<(i32, i32)>().unwrap(), (1, 2));
+
+        // Make the clusters differ
+        session
+            .get_secondary()
+            .query("INSERT INTO t (a, b) VALUES (3, 4)", &[])
+            .await
+            .unwrap();
+        let result = session.execute(select, ()).await.unwrap();
+        assert_eq!(result.rows_num().unwrap(), 1);
+        // Shadow reads are compared in the background
+        tokio::time::timeout(Duration::from_secs(10), async {
+            while reporter.mismatches.lock().unwrap().is_empty() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(*reporter.mismatches.lock().unwrap(), vec![select]);
+        assert!(reporter.errors.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_mirroring_secondary_write_failure() {
+        setup_tracing();
+        let insert = "INSERT INTO t (a, b) VALUES (?, ?)";
+
+        // The table is missing in the secondary cluster
+        let reporter = Arc::new(RecordingReporter::default());
+        let best_effort = MirroringSession::with_config(
+            create_session(true).await,
+            create_session(false).await,
+            16,
+            MirroringConfig::new().mismatch_reporter(reporter.clone()),
+        );
+        best_effort.execute(insert, (1, 2)).await.unwrap();
+        assert_eq!(*reporter.errors.lock().unwrap(), vec![insert]);
+
+        let required = MirroringSession::with_config(
+            create_session(true).await,
+            create_session(false).await,
+            16,
+            MirroringConfig::new().secondary_writes(SecondaryWritePolicy::Required),
+        );
+        assert_matches!(
+            required.execute(insert, (1, 2)).await,
+            Err(MirroringError::Secondary(_))
+        );
+    }
+}
@@ -1,24 +1,36 @@
+pub mod bulk_writer;
 pub(crate) mod caching_session;
 mod cluster;
 pub(crate) mod connection;
 mod connection_pool;
 pub mod downgrading_consistency_retry_policy;
 pub mod execution_profile;
+pub mod exponential_backoff_retry_policy;
 pub mod host_filter;
+pub mod in_flight_limiter;
 pub mod iterator;
 pub mod load_balancing;
 pub mod locator;
+pub mod mapper;
 pub(crate) mod metrics;
+pub mod migrations;
+pub mod mirroring_session;
 mod node;
 pub mod partitioner;
 pub mod query_result;
+#[cfg(feature = "arrow")]
+pub mod record_batch;
+mod repreparation;
 pub mod retry_policy;
 pub mod session;
 pub mod session_builder;
 pub mod speculative_execution;
+mod task_tracker;
+pub mod timestamp_generator;
+pub mod token_range_scan;
 pub mod topology;
 
-pub use crate::frame::{Authenticator, Compression};
+pub use crate::frame::{Authenticator, Compression, ProtocolVersion};
 pub use connection::SelfIdentity;
 pub use execution_profile::ExecutionProfile;
 pub use scylla_cql::errors;
@@ -8,6 +8,7 @@ use crate::transport::connection::Connection;
 use crate::transport::connection::VerifiedKeyspaceName;
 use crate::transport::connection_pool::{NodeConnectionPool, PoolConfig};
 use crate::transport::errors::QueryError;
+use crate::transport::ProtocolVersion;
 
 use std::fmt::Display;
 use std::io;
@@ -165,6 +166,15 @@ impl Node {
         self.down_marker.load(Ordering::Relaxed)
     }
 
+    /// Returns the protocol version used by connections to this node,
+    /// or `None` if there are no working connections.
+    pub fn protocol_version(&self) -> Option<ProtocolVersion> {
+        let connections = self.get_working_connections().ok()?;
+        connections
+            .first()
+            .map(|connection| connection.get_protocol_version())
+    }
+
     /// Returns a boolean which indicates whether this node was is enabled.
     /// Only enabled nodes will have connections open. For disabled nodes,
     /// no connections will be opened.
@@ -196,6 +206,12 @@ impl Node {
         }
     }
 
+    pub(crate) async fn wait_until_pool_working(&self) {
+        if let Some(pool) = &self.pool {
+            pool.wait_until_working().await;
+        }
+    }
+
     fn get_pool(&self) -> Result<&NodeConnectionPool, QueryError> {
         self.pool.as_ref().ok_or_else(|| {
             QueryError::IoError(Arc::new(std::io::Error::new(
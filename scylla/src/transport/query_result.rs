@@ -1,16 +1,272 @@
 use crate::frame::response::cql_to_rust::{FromRow, FromRowError};
 use crate::frame::response::result::ColumnSpec;
+use crate::frame::response::result::ColumnType;
 use crate::frame::response::result::Row;
+use crate::transport::errors::QueryError;
+#[cfg(feature = "arrow")]
+use crate::transport::record_batch::{self, ArrowConversionError};
 use crate::transport::session::{IntoTypedRows, TypedRowIter};
 use bytes::Bytes;
+use scylla_cql::types::deserialize::result::{RowIterator, TypedRowIterator};
+use scylla_cql::types::deserialize::row::{ColumnIterator, DeserializeRow};
+use scylla_cql::types::deserialize::value::DeserializeValue;
+use scylla_cql::types::deserialize::{DeserializationError, FrameSlice, TypeCheckError};
+use std::collections::HashMap;
 use thiserror::Error;
 use uuid::Uuid;
 
 /// Result of a single query\
-/// Contains all rows returned by the database and some more information
+/// Contains the serialized rows returned by the database and some more information.\
+/// Rows are deserialized lazily, straight from the response, with [`rows()`](QueryResult::rows)
+/// and similar methods. Deserialized rows can borrow from the `QueryResult`,
+/// so e.g. `&str` or `&[u8]` columns can be read without copying them.
 #[non_exhaustive]
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone)]
 pub struct QueryResult {
+    /// Warnings returned by the database
+    pub warnings: Vec<String>,
+    /// CQL Tracing uuid - can only be Some if tracing is enabled for this query
+    pub tracing_id: Option<Uuid>,
+    /// Custom payload returned by the server, if any
+    pub custom_payload: Option<HashMap<String, Vec<u8>>>,
+    /// Paging state returned from the server
+    pub paging_state: Option<Bytes>,
+    /// Column specification returned from the server
+    pub col_specs: Vec<ColumnSpec>,
+    /// The original size of the serialized rows in request
+    pub serialized_size: usize,
+    /// Serialized rows, `Some` for queries that can return rows (e.g `SELECT`).
+    pub(crate) raw_rows: Option<Bytes>,
+    pub(crate) rows_count: usize,
+}
+
+impl QueryResult {
+    /// Returns the number of received rows.\
+    /// Fails when the query isn't of a type that could return rows, same as [`rows()`](QueryResult::rows).
+    pub fn rows_num(&self) -> Result<usize, RowsExpectedError> {
+        match &self.raw_rows {
+            Some(_) => Ok(self.rows_count),
+            None => Err(RowsExpectedError),
+        }
+    }
+
+    /// Returns an iterator over the received rows, deserialized as the given type.\
+    /// Fails when the query isn't of a type that could return rows (e.g `INSERT`)
+    /// or when the type doesn't match the columns of the result.\
+    /// Each row is deserialized only when the iterator reaches it.
+    pub fn rows<'frame, R: DeserializeRow<'frame>>(
+        &'frame self,
+    ) -> Result<TypedRowIterator<'frame, R>, RowsError> {
+        let raw_rows = self.raw_rows.as_ref().ok_or(RowsExpectedError)?;
+        let raw_rows_iter =
+            RowIterator::new(self.rows_count, &self.col_specs, FrameSlice::new(raw_rows));
+        Ok(TypedRowIterator::new(raw_rows_iter)?)
+    }
+
+    /// Returns `Ok` for a result of a query that shouldn't contain any rows.\
+    /// Will return `Ok` for `INSERT` result, but a `SELECT` result, even an empty one, will cause an error.\
+    /// Opposite of [`rows()`](QueryResult::rows).
+    pub fn result_not_rows(&self) -> Result<(), RowsNotExpectedError> {
+        match self.raw_rows {
+            Some(_) => Err(RowsNotExpectedError),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the first received row deserialized as the given type, or `None` if there are no rows.\
+    /// Fails when the query isn't of a type that could return rows, same as [`rows()`](QueryResult::rows).
+    pub fn maybe_first_row<'frame, R: DeserializeRow<'frame>>(
+        &'frame self,
+    ) -> Result<Option<R>, RowError> {
+        Ok(self.rows::<R>()?.next().transpose()?)
+    }
+
+    /// Returns the first received row deserialized as the given type.\
+    /// When the first row is not available, returns an error.
+    pub fn first_row<'frame, R: DeserializeRow<'frame>>(&'frame self) -> Result<R, RowError> {
+        self.maybe_first_row()?.ok_or(RowError::RowsEmpty)
+    }
+
+    /// Returns the only received row deserialized as the given type.\
+    /// Fails if the result is anything else than a single row.
+    pub fn single_row<'frame, R: DeserializeRow<'frame>>(&'frame self) -> Result<R, RowError> {
+        let mut rows = self.rows::<R>()?;
+        if rows.rows_remaining() != 1 {
+            return Err(RowError::BadNumberOfRows(rows.rows_remaining()));
+        }
+        Ok(rows.next().unwrap()?)
+    }
+
+    /// Reads the outcome of a lightweight transaction (a statement with an `IF` condition).\
+    /// When the condition wasn't met, the only received row, without its `[applied]` column,
+    /// is deserialized as the given type. It holds the current values of the columns
+    /// which the database returned, e.g. `NULL`s when the row doesn't exist.\
+    /// Fails if the result isn't a result of a lightweight transaction.
+    pub fn lwt_outcome<'frame, R: DeserializeRow<'frame>>(
+        &'frame self,
+    ) -> Result<LwtOutcome<R>, LwtResultError> {
+        self.lwt_outcome_of(false)
+    }
+
+    /// Like [`lwt_outcome()`](QueryResult::lwt_outcome), but for a result of a statement
+    /// which the database confirmed to be an LWT when preparing it. Its first column
+    /// is then trusted to be the outcome, regardless of its name.
+    pub(crate) fn lwt_outcome_of<'frame, R: DeserializeRow<'frame>>(
+        &'frame self,
+        is_confirmed_lwt: bool,
+    ) -> Result<LwtOutcome<R>, LwtResultError> {
+        match self.lwt_outcome_rows_of(is_confirmed_lwt)? {
+            LwtOutcome::Applied => Ok(LwtOutcome::Applied),
+            LwtOutcome::NotApplied(rows) if rows.len() != 1 => {
+                Err(LwtResultError::BadNumberOfRows(rows.len()))
+            }
+            LwtOutcome::NotApplied(mut rows) => Ok(LwtOutcome::NotApplied(rows.pop().unwrap())),
+        }
+    }
+
+    /// Reads the outcome of a batch with conditions, which may return several rows
+    /// when it isn't applied, e.g. one for each conditional statement.\
+    /// All received rows are deserialized as the given type, without their `[applied]` column.
+    pub fn lwt_outcome_rows<'frame, R: DeserializeRow<'frame>>(
+        &'frame self,
+    ) -> Result<LwtOutcome<Vec<R>>, LwtResultError> {
+        self.lwt_outcome_rows_of(false)
+    }
+
+    /// Like [`lwt_outcome_rows()`](QueryResult::lwt_outcome_rows), see
+    /// [`lwt_outcome_of()`](QueryResult::lwt_outcome_of).
+    pub(crate) fn lwt_outcome_rows_of<'frame, R: DeserializeRow<'frame>>(
+        &'frame self,
+        is_confirmed_lwt: bool,
+    ) -> Result<LwtOutcome<Vec<R>>, LwtResultError> {
+        let rows = self
+            .rows::<ColumnIterator<'frame>>()
+            .map_err(|err| match err {
+                RowsError::RowsExpected(e) => LwtResultError::RowsExpected(e),
+                RowsError::TypeCheckFailed(e) => LwtResultError::TypeCheckFailed(e),
+            })?;
+        // Without the confirmation, e.g. for unprepared statements or when the database
+        // doesn't report LWTs, the result is recognized by the name of its first column.
+        let is_lwt_result = self.col_specs.first().map_or(false, |spec| {
+            (is_confirmed_lwt || spec.name == "[applied]") && spec.typ == ColumnType::Boolean
+        });
+        if !is_lwt_result {
+            return Err(LwtResultError::NotLwt);
+        }
+
+        let mut rows = rows.peekable();
+        let first_row = rows.peek().ok_or(LwtResultError::RowsEmpty)?.clone()?;
+        let (applied, _) = read_applied_column(first_row)?;
+        if applied {
+            return Ok(LwtOutcome::Applied);
+        }
+
+        // Results of applied transactions may lack the other columns,
+        // so they are checked only now.
+        R::type_check(&self.col_specs[1..])?;
+        let rows = rows
+            .map(|row| {
+                let (_, columns) = read_applied_column(row?)?;
+                R::deserialize(columns)
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(LwtOutcome::NotApplied(rows))
+    }
+
+    /// Returns a column specification for a column with given name, or None if not found
+    pub fn get_column_spec<'a>(&'a self, name: &str) -> Option<(usize, &'a ColumnSpec)> {
+        self.col_specs
+            .iter()
+            .enumerate()
+            .find(|(_id, spec)| spec.name == name)
+    }
+
+    /// Converts the received rows into an Arrow [`RecordBatch`](arrow::record_batch::RecordBatch),
+    /// with one Arrow column per result column.\
+    /// See [`record_batch`](crate::transport::record_batch) for how CQL types are mapped.
+    #[cfg(feature = "arrow")]
+    pub fn to_record_batch(
+        &self,
+    ) -> Result<arrow::record_batch::RecordBatch, ArrowConversionError> {
+        let rows = self.rows::<ColumnIterator>().map_err(|err| match err {
+            RowsError::RowsExpected(err) => ArrowConversionError::from(err),
+            // `ColumnIterator` accepts columns of any type
+            RowsError::TypeCheckFailed(err) => DeserializationError::new(err).into(),
+        })?;
+        let schema = record_batch::arrow_schema(&self.col_specs)?;
+        record_batch::rows_to_record_batch(schema.into(), &self.col_specs, rows)
+    }
+
+    /// Deserializes all rows into [`Row`]s of [`CqlValue`](crate::frame::response::result::CqlValue)s,
+    /// converting this result into the [`LegacyQueryResult`] which offers the old,
+    /// [`FromRow`]-based API.
+    pub fn into_legacy_result(self) -> Result<LegacyQueryResult, DeserializationError> {
+        let rows = match &self.raw_rows {
+            Some(raw_rows) => {
+                let raw_rows_iter =
+                    RowIterator::new(self.rows_count, &self.col_specs, FrameSlice::new(raw_rows));
+                let rows_iter = TypedRowIterator::<Row>::new(raw_rows_iter)
+                    .map_err(DeserializationError::new)?;
+                Some(rows_iter.collect::<Result<_, _>>()?)
+            }
+            None => None,
+        };
+
+        Ok(LegacyQueryResult {
+            rows,
+            warnings: self.warnings,
+            tracing_id: self.tracing_id,
+            custom_payload: self.custom_payload,
+            paging_state: self.paging_state,
+            col_specs: self.col_specs,
+            serialized_size: self.serialized_size,
+        })
+    }
+}
+
+// Reads the `[applied]` column of a row, returning the iterator over the rest of the columns.
+fn read_applied_column(
+    mut columns: ColumnIterator<'_>,
+) -> Result<(bool, ColumnIterator<'_>), DeserializationError> {
+    // The column is known to exist, the specs of the result were checked.
+    let column = columns.next().unwrap()?;
+    let applied = bool::deserialize(&column.spec.typ, column.slice)?;
+    Ok((applied, columns))
+}
+
+/// Outcome of a lightweight transaction, read with [`QueryResult::lwt_outcome`],
+/// [`QueryResult::lwt_outcome_rows`] or [`Session::execute_lwt`](crate::Session::execute_lwt).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LwtOutcome<T> {
+    /// The condition was met and the write was applied.
+    Applied,
+
+    /// The condition wasn't met, so nothing was written.
+    /// Holds the current values returned by the database.
+    NotApplied(T),
+}
+
+impl<T> LwtOutcome<T> {
+    /// Returns `true` if the write was applied.
+    pub fn is_applied(&self) -> bool {
+        matches!(self, LwtOutcome::Applied)
+    }
+
+    /// Returns the current values if the write wasn't applied.
+    pub fn not_applied(self) -> Option<T> {
+        match self {
+            LwtOutcome::Applied => None,
+            LwtOutcome::NotApplied(values) => Some(values),
+        }
+    }
+}
+
+/// Result of a single query, with all rows deserialized into [`Row`]s\
+/// Contains all rows returned by the database and some more information.\
+/// Obtained with [`QueryResult::into_legacy_result`].
+#[non_exhaustive]
+#[derive(Default, Debug)]
+pub struct LegacyQueryResult {
     /// Rows returned by the database.\
     /// Queries like `SELECT` will have `Some(Vec)`, while queries like `INSERT` will have `None`.\
     /// Can contain an empty Vec.
@@ -19,6 +275,8 @@ pub struct QueryResult {
     pub warnings: Vec<String>,
     /// CQL Tracing uuid - can only be Some if tracing is enabled for this query
     pub tracing_id: Option<Uuid>,
+    /// Custom payload returned by the server, if any
+    pub custom_payload: Option<HashMap<String, Vec<u8>>>,
     /// Paging state returned from the server
     pub paging_state: Option<Bytes>,
     /// Column specification returned from the server
@@ -27,9 +285,9 @@ pub struct QueryResult {
     pub serialized_size: usize,
 }
 
-impl QueryResult {
+impl LegacyQueryResult {
     /// Returns the number of received rows.\
-    /// Fails when the query isn't of a type that could return rows, same as [`rows()`](QueryResult::rows).
+    /// Fails when the query isn't of a type that could return rows, same as [`rows()`](LegacyQueryResult::rows).
     pub fn rows_num(&self) -> Result<usize, RowsExpectedError> {
         match &self.rows {
             Some(rows) => Ok(rows.len()),
@@ -38,7 +296,7 @@ impl QueryResult {
     }
 
     /// Returns the received rows when present.\
-    /// If `QueryResult.rows` is `None`, which means that this query is not supposed to return rows (e.g `INSERT`), returns an error.\
+    /// If `LegacyQueryResult.rows` is `None`, which means that this query is not supposed to return rows (e.g `INSERT`), returns an error.\
     /// Can return an empty `Vec`.
     pub fn rows(self) -> Result<Vec<Row>, RowsExpectedError> {
         match self.rows {
@@ -49,14 +307,14 @@ impl QueryResult {
 
     /// Returns the received rows parsed as the given type.\
     /// Equal to `rows()?.into_typed()`.\
-    /// Fails when the query isn't of a type that could return rows, same as [`rows()`](QueryResult::rows).
+    /// Fails when the query isn't of a type that could return rows, same as [`rows()`](LegacyQueryResult::rows).
     pub fn rows_typed<RowT: FromRow>(self) -> Result<TypedRowIter<RowT>, RowsExpectedError> {
         Ok(self.rows()?.into_typed())
     }
 
     /// Returns `Ok` for a result of a query that shouldn't contain any rows.\
     /// Will return `Ok` for `INSERT` result, but a `SELECT` result, even an empty one, will cause an error.\
-    /// Opposite of [`rows()`](QueryResult::rows).
+    /// Opposite of [`rows()`](LegacyQueryResult::rows).
     pub fn result_not_rows(&self) -> Result<(), RowsNotExpectedError> {
         match self.rows {
             Some(_) => Err(RowsNotExpectedError),
@@ -64,14 +322,14 @@ impl QueryResult {
         }
     }
 
-    /// Returns rows when `QueryResult.rows` is `Some`, otherwise an empty Vec.\
+    /// Returns rows when `LegacyQueryResult.rows` is `Some`, otherwise an empty Vec.\
     /// Equal to `rows().unwrap_or_default()`.
     pub fn rows_or_empty(self) -> Vec<Row> {
         self.rows.unwrap_or_default()
     }
 
     /// Returns rows parsed as the given type.\
-    /// When `QueryResult.rows` is `None`, returns 0 rows.\
+    /// When `LegacyQueryResult.rows` is `None`, returns 0 rows.\
     /// Equal to `rows_or_empty().into_typed::<RowT>()`.
     pub fn rows_typed_or_empty<RowT: FromRow>(self) -> TypedRowIter<RowT> {
         self.rows_or_empty().into_typed::<RowT>()
@@ -93,13 +351,13 @@ impl QueryResult {
     }
 
     /// Returns `Option<RowT>` containing the first of a result.\
-    /// Fails when the query isn't of a type that could return rows, same as [`rows()`](QueryResult::rows).
+    /// Fails when the query isn't of a type that could return rows, same as [`rows()`](LegacyQueryResult::rows).
     pub fn maybe_first_row(self) -> Result<Option<Row>, RowsExpectedError> {
         Ok(self.rows()?.into_iter().next())
     }
 
     /// Returns `Option<RowT>` containing the first of a result.\
-    /// Fails when the query isn't of a type that could return rows, same as [`rows()`](QueryResult::rows).
+    /// Fails when the query isn't of a type that could return rows, same as [`rows()`](LegacyQueryResult::rows).
     pub fn maybe_first_row_typed<RowT: FromRow>(
         self,
     ) -> Result<Option<RowT>, MaybeFirstRowTypedError> {
@@ -164,28 +422,28 @@ pub struct RowsNotExpectedError;
 
 #[derive(Debug, Clone, Error, PartialEq, Eq)]
 pub enum FirstRowError {
-    /// [`QueryResult::first_row()`](QueryResult::first_row) called on a bad QueryResult.\
-    /// Expected `QueryResult.rows` to be `Some`, but it was `None`.\
-    /// `QueryResult.rows` is `Some` for queries that can return rows (e.g `SELECT`).\
+    /// [`LegacyQueryResult::first_row()`](LegacyQueryResult::first_row) called on a bad LegacyQueryResult.\
+    /// Expected `LegacyQueryResult.rows` to be `Some`, but it was `None`.\
+    /// `LegacyQueryResult.rows` is `Some` for queries that can return rows (e.g `SELECT`).\
     /// It is `None` for queries that can't return rows (e.g `INSERT`).
     #[error(transparent)]
     RowsExpected(#[from] RowsExpectedError),
 
-    /// Rows in `QueryResult` are empty
+    /// Rows in `LegacyQueryResult` are empty
     #[error("Rows in QueryResult are empty")]
     RowsEmpty,
 }
 
 #[derive(Debug, Clone, Error, PartialEq, Eq)]
 pub enum FirstRowTypedError {
-    /// [`QueryResult::first_row_typed()`](QueryResult::first_row_typed) called on a bad QueryResult.\
-    /// Expected `QueryResult.rows` to be `Some`, but it was `None`.\
-    /// `QueryResult.rows` is `Some` for queries that can return rows (e.g `SELECT`).\
+    /// [`LegacyQueryResult::first_row_typed()`](LegacyQueryResult::first_row_typed) called on a bad LegacyQueryResult.\
+    /// Expected `LegacyQueryResult.rows` to be `Some`, but it was `None`.\
+    /// `LegacyQueryResult.rows` is `Some` for queries that can return rows (e.g `SELECT`).\
     /// It is `None` for queries that can't return rows (e.g `INSERT`).
     #[error(transparent)]
     RowsExpected(#[from] RowsExpectedError),
 
-    /// Rows in `QueryResult` are empty
+    /// Rows in `LegacyQueryResult` are empty
     #[error("Rows in QueryResult are empty")]
     RowsEmpty,
 
@@ -196,9 +454,9 @@ pub enum FirstRowTypedError {
 
 #[derive(Debug, Clone, Error, PartialEq, Eq)]
 pub enum MaybeFirstRowTypedError {
-    /// [`QueryResult::maybe_first_row_typed()`](QueryResult::maybe_first_row_typed) called on a bad QueryResult.\
-    /// Expected `QueryResult.rows` to be `Some`, but it was `None`.
-    /// `QueryResult.rows` is `Some` for queries that can return rows (e.g `SELECT`).\
+    /// [`LegacyQueryResult::maybe_first_row_typed()`](LegacyQueryResult::maybe_first_row_typed) called on a bad LegacyQueryResult.\
+    /// Expected `LegacyQueryResult.rows` to be `Some`, but it was `None`.
+    /// `LegacyQueryResult.rows` is `Some` for queries that can return rows (e.g `SELECT`).\
     /// It is `None` for queries that can't return rows (e.g `INSERT`).
     #[error(transparent)]
     RowsExpected(#[from] RowsExpectedError),
@@ -210,9 +468,9 @@ pub enum MaybeFirstRowTypedError {
 
 #[derive(Debug, Clone, Error, PartialEq, Eq)]
 pub enum SingleRowError {
-    /// [`QueryResult::single_row()`](QueryResult::single_row) called on a bad QueryResult.\
-    /// Expected `QueryResult.rows` to be `Some`, but it was `None`.\
-    /// `QueryResult.rows` is `Some` for queries that can return rows (e.g `SELECT`).\
+    /// [`LegacyQueryResult::single_row()`](LegacyQueryResult::single_row) called on a bad LegacyQueryResult.\
+    /// Expected `LegacyQueryResult.rows` to be `Some`, but it was `None`.\
+    /// `LegacyQueryResult.rows` is `Some` for queries that can return rows (e.g `SELECT`).\
     /// It is `None` for queries that can't return rows (e.g `INSERT`).
     #[error(transparent)]
     RowsExpected(#[from] RowsExpectedError),
@@ -224,9 +482,9 @@ pub enum SingleRowError {
 
 #[derive(Debug, Clone, Error, PartialEq, Eq)]
 pub enum SingleRowTypedError {
-    /// [`QueryResult::single_row_typed()`](QueryResult::single_row_typed) called on a bad QueryResult.\
-    /// Expected `QueryResult.rows` to be `Some`, but it was `None`.\
-    /// `QueryResult.rows` is `Some` for queries that can return rows (e.g `SELECT`).\
+    /// [`LegacyQueryResult::single_row_typed()`](LegacyQueryResult::single_row_typed) called on a bad LegacyQueryResult.\
+    /// Expected `LegacyQueryResult.rows` to be `Some`, but it was `None`.\
+    /// `LegacyQueryResult.rows` is `Some` for queries that can return rows (e.g `SELECT`).\
     /// It is `None` for queries that can't return rows (e.g `INSERT`).
     #[error(transparent)]
     RowsExpected(#[from] RowsExpectedError),
@@ -240,6 +498,95 @@ pub enum SingleRowTypedError {
     FromRowError(#[from] FromRowError),
 }
 
+/// An error returned by [`QueryResult::rows()`](QueryResult::rows).
+#[derive(Debug, Clone, Error)]
+pub enum RowsError {
+    /// The query isn't of a type that could return rows (e.g `INSERT`).
+    #[error(transparent)]
+    RowsExpected(#[from] RowsExpectedError),
+
+    /// The columns of the result don't match the requested type
+    #[error("Type check failed: {0}")]
+    TypeCheckFailed(#[from] TypeCheckError),
+}
+
+/// An error returned by [`QueryResult::first_row()`](QueryResult::first_row),
+/// [`QueryResult::maybe_first_row()`](QueryResult::maybe_first_row)
+/// and [`QueryResult::single_row()`](QueryResult::single_row).
+#[derive(Debug, Clone, Error)]
+pub enum RowError {
+    /// The query isn't of a type that could return rows (e.g `INSERT`).
+    #[error(transparent)]
+    RowsExpected(#[from] RowsExpectedError),
+
+    /// The columns of the result don't match the requested type
+    #[error("Type check failed: {0}")]
+    TypeCheckFailed(#[from] TypeCheckError),
+
+    /// Rows in `QueryResult` are empty
+    #[error("Rows in QueryResult are empty")]
+    RowsEmpty,
+
+    /// Expected a single row, found other number of rows
+    #[error("Expected a single row, found {0} rows")]
+    BadNumberOfRows(usize),
+
+    /// Deserializing the row failed
+    #[error("Deserialization failed: {0}")]
+    DeserializationFailed(#[from] DeserializationError),
+}
+
+/// An error returned by [`QueryResult::lwt_outcome()`](QueryResult::lwt_outcome)
+/// and [`QueryResult::lwt_outcome_rows()`](QueryResult::lwt_outcome_rows).
+#[derive(Debug, Clone, Error)]
+pub enum LwtResultError {
+    /// The query isn't of a type that could return rows (e.g `INSERT` without a condition).
+    #[error(transparent)]
+    RowsExpected(#[from] RowsExpectedError),
+
+    /// The result doesn't start with the `[applied]` column
+    #[error("The result isn't a result of a lightweight transaction")]
+    NotLwt,
+
+    /// The columns of the result don't match the requested type
+    #[error("Type check failed: {0}")]
+    TypeCheckFailed(#[from] TypeCheckError),
+
+    /// Rows in `QueryResult` are empty
+    #[error("Rows in QueryResult are empty")]
+    RowsEmpty,
+
+    /// Expected a single row, found other number of rows
+    #[error("Expected a single row, found {0} rows")]
+    BadNumberOfRows(usize),
+
+    /// Deserializing a row failed
+    #[error("Deserialization failed: {0}")]
+    DeserializationFailed(#[from] DeserializationError),
+}
+
+/// An error returned by [`Session::execute_lwt()`](crate::Session::execute_lwt)
+/// and [`Session::batch_lwt()`](crate::Session::batch_lwt).
+#[derive(Debug, Clone, Error)]
+pub enum LwtError {
+    /// Executing the statement failed
+    #[error(transparent)]
+    Query(#[from] QueryError),
+
+    /// Reading the outcome from the result failed
+    #[error(transparent)]
+    Result(#[from] LwtResultError),
+}
+
+impl From<RowsError> for RowError {
+    fn from(err: RowsError) -> RowError {
+        match err {
+            RowsError::RowsExpected(e) => RowError::RowsExpected(e),
+            RowsError::TypeCheckFailed(e) => RowError::TypeCheckFailed(e),
+        }
+    }
+}
+
 impl From<FirstRowError> for FirstRowTypedError {
     fn from(err: FirstRowError) -> FirstRowTypedError {
         match err {
@@ -294,7 +641,7 @@ mod tests {
         rows
     }
 
-    fn make_not_rows_query_result() -> QueryResult {
+    fn make_not_rows_query_result() -> LegacyQueryResult {
         let table_spec = TableSpec::owned("some_keyspace".to_string(), "some_table".to_string());
 
         let column_spec = ColumnSpec {
@@ -303,23 +650,24 @@ mod tests {
             typ: ColumnType::Int,
         };
 
-        QueryResult {
+        LegacyQueryResult {
             rows: None,
             warnings: vec![],
             tracing_id: None,
+            custom_payload: None,
             paging_state: None,
             col_specs: vec![column_spec],
             serialized_size: 0,
         }
     }
 
-    fn make_rows_query_result(rows_num: usize) -> QueryResult {
+    fn make_rows_query_result(rows_num: usize) -> LegacyQueryResult {
         let mut res = make_not_rows_query_result();
         res.rows = Some(make_rows(rows_num));
         res
     }
 
-    fn make_string_rows_query_result(rows_num: usize) -> QueryResult {
+    fn make_string_rows_query_result(rows_num: usize) -> LegacyQueryResult {
         let mut res = make_not_rows_query_result();
         res.rows = Some(make_string_rows(rows_num));
         res
@@ -601,4 +949,273 @@ mod tests {
             Err(SingleRowTypedError::FromRowError(_))
         );
     }
+
+    // Returns a `QueryResult` with the given number of serialized rows,
+    // each one containing one int32 value, like make_rows.
+    // `None` makes a result of a query which doesn't return rows.
+    fn make_raw_query_result(rows_num: Option<usize>) -> QueryResult {
+        let col_specs = make_not_rows_query_result().col_specs;
+        let raw_rows = rows_num.map(|rows_num| {
+            let mut raw_rows = Vec::new();
+            for cur_value in 0..rows_num {
+                let int_val: i32 = cur_value.try_into().unwrap();
+                raw_rows.extend_from_slice(&4_i32.to_be_bytes());
+                raw_rows.extend_from_slice(&int_val.to_be_bytes());
+            }
+            Bytes::from(raw_rows)
+        });
+
+        QueryResult {
+            col_specs,
+            raw_rows,
+            rows_count: rows_num.unwrap_or(0),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn raw_rows_num_test() {
+        setup_tracing();
+        assert_eq!(
+            make_raw_query_result(None).rows_num(),
+            Err(RowsExpectedError)
+        );
+        assert_eq!(make_raw_query_result(Some(0)).rows_num(), Ok(0));
+        assert_eq!(make_raw_query_result(Some(3)).rows_num(), Ok(3));
+    }
+
+    #[test]
+    fn raw_rows_test() {
+        setup_tracing();
+        assert_matches!(
+            make_raw_query_result(None).rows::<(i32,)>().err(),
+            Some(RowsError::RowsExpected(RowsExpectedError))
+        );
+        assert_matches!(
+            make_raw_query_result(Some(1)).rows::<(&str,)>().err(),
+            Some(RowsError::TypeCheckFailed(_))
+        );
+        assert_matches!(
+            make_raw_query_result(Some(1)).rows::<(i32, i32)>().err(),
+            Some(RowsError::TypeCheckFailed(_))
+        );
+
+        for rows_num in 0..3 {
+            let result = make_raw_query_result(Some(rows_num));
+            let rows: Vec<(i32,)> = result
+                .rows::<(i32,)>()
+                .unwrap()
+                .map(|r| r.unwrap())
+                .collect();
+            let expected: Vec<(i32,)> = (0..rows_num as i32).map(|v| (v,)).collect();
+            assert_eq!(rows, expected);
+        }
+    }
+
+    #[test]
+    fn raw_result_not_rows_test() {
+        setup_tracing();
+        assert_eq!(make_raw_query_result(None).result_not_rows(), Ok(()));
+        assert_eq!(
+            make_raw_query_result(Some(0)).result_not_rows(),
+            Err(RowsNotExpectedError)
+        );
+    }
+
+    #[test]
+    fn raw_first_row_test() {
+        setup_tracing();
+        assert_matches!(
+            make_raw_query_result(None).maybe_first_row::<(i32,)>(),
+            Err(RowError::RowsExpected(RowsExpectedError))
+        );
+        assert_matches!(
+            make_raw_query_result(Some(0)).maybe_first_row::<(i32,)>(),
+            Ok(None)
+        );
+        assert_matches!(
+            make_raw_query_result(Some(2)).maybe_first_row::<(i32,)>(),
+            Ok(Some((0,)))
+        );
+
+        assert_matches!(
+            make_raw_query_result(Some(0)).first_row::<(i32,)>(),
+            Err(RowError::RowsEmpty)
+        );
+        assert_matches!(
+            make_raw_query_result(Some(2)).first_row::<(i32,)>(),
+            Ok((0,))
+        );
+        assert_matches!(
+            make_raw_query_result(Some(2)).first_row::<(&str,)>(),
+            Err(RowError::TypeCheckFailed(_))
+        );
+    }
+
+    #[test]
+    fn raw_single_row_test() {
+        setup_tracing();
+        assert_matches!(
+            make_raw_query_result(None).single_row::<(i32,)>(),
+            Err(RowError::RowsExpected(RowsExpectedError))
+        );
+        assert_matches!(
+            make_raw_query_result(Some(0)).single_row::<(i32,)>(),
+            Err(RowError::BadNumberOfRows(0))
+        );
+        assert_matches!(
+            make_raw_query_result(Some(1)).single_row::<(i32,)>(),
+            Ok((0,))
+        );
+        assert_matches!(
+            make_raw_query_result(Some(2)).single_row::<(i32,)>(),
+            Err(RowError::BadNumberOfRows(2))
+        );
+    }
+
+    #[test]
+    fn raw_rows_deserialization_error_test() {
+        setup_tracing();
+        // A row which claims to hold 4 bytes, but holds only 2
+        let result = QueryResult {
+            col_specs: make_not_rows_query_result().col_specs,
+            raw_rows: Some(Bytes::from_static(&[0, 0, 0, 4, 0, 0])),
+            rows_count: 1,
+            ..Default::default()
+        };
+        assert_matches!(
+            result.first_row::<(i32,)>(),
+            Err(RowError::DeserializationFailed(_))
+        );
+        assert!(result.into_legacy_result().is_err());
+    }
+
+    // Returns a result of a lightweight transaction with the given rows
+    // of `[applied]` and an int32 column.
+    fn make_lwt_query_result(rows: &[(bool, Option<i32>)]) -> QueryResult {
+        let table_spec = TableSpec::owned("some_keyspace".to_string(), "some_table".to_string());
+        let col_specs = vec![
+            ColumnSpec {
+                table_spec: table_spec.clone(),
+                name: "[applied]".to_string(),
+                typ: ColumnType::Boolean,
+            },
+            ColumnSpec {
+                table_spec,
+                name: "column0".to_string(),
+                typ: ColumnType::Int,
+            },
+        ];
+        let mut raw_rows = Vec::new();
+        for (applied, value) in rows {
+            raw_rows.extend_from_slice(&1_i32.to_be_bytes());
+            raw_rows.push(*applied as u8);
+            match value {
+                Some(value) => {
+                    raw_rows.extend_from_slice(&4_i32.to_be_bytes());
+                    raw_rows.extend_from_slice(&value.to_be_bytes());
+                }
+                None => raw_rows.extend_from_slice(&(-1_i32).to_be_bytes()),
+            }
+        }
+
+        QueryResult {
+            col_specs,
+            raw_rows: Some(Bytes::from(raw_rows)),
+            rows_count: rows.len(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn lwt_outcome_test() {
+        setup_tracing();
+        assert_matches!(
+            make_lwt_query_result(&[(true, None)]).lwt_outcome::<(i32,)>(),
+            Ok(LwtOutcome::Applied)
+        );
+        assert_matches!(
+            make_lwt_query_result(&[(false, Some(5))]).lwt_outcome::<(i32,)>(),
+            Ok(LwtOutcome::NotApplied((5,)))
+        );
+        assert_matches!(
+            make_lwt_query_result(&[(false, None)]).lwt_outcome::<(Option<i32>,)>(),
+            Ok(LwtOutcome::NotApplied((None,)))
+        );
+        assert_matches!(
+            make_lwt_query_result(&[(false, Some(5))]).lwt_outcome::<(&str,)>(),
+            Err(LwtResultError::TypeCheckFailed(_))
+        );
+        assert_matches!(
+            make_lwt_query_result(&[(false, Some(5)), (false, Some(6))]).lwt_outcome::<(i32,)>(),
+            Err(LwtResultError::BadNumberOfRows(2))
+        );
+        assert_matches!(
+            make_lwt_query_result(&[]).lwt_outcome::<(i32,)>(),
+            Err(LwtResultError::RowsEmpty)
+        );
+        assert_matches!(
+            make_raw_query_result(Some(1)).lwt_outcome::<(i32,)>(),
+            Err(LwtResultError::NotLwt)
+        );
+        assert_matches!(
+            make_raw_query_result(None).lwt_outcome::<(i32,)>(),
+            Err(LwtResultError::RowsExpected(RowsExpectedError))
+        );
+    }
+
+    #[test]
+    fn lwt_outcome_rows_test() {
+        setup_tracing();
+        assert_matches!(
+            make_lwt_query_result(&[(true, Some(5)), (true, Some(6))]).lwt_outcome_rows::<(i32,)>(),
+            Ok(LwtOutcome::Applied)
+        );
+        assert_eq!(
+            make_lwt_query_result(&[(false, Some(5)), (false, None)])
+                .lwt_outcome_rows::<(Option<i32>,)>()
+                .unwrap(),
+            LwtOutcome::NotApplied(vec![(Some(5),), (None,)])
+        );
+    }
+
+    #[test]
+    fn confirmed_lwt_outcome_test() {
+        setup_tracing();
+        let mut result = make_lwt_query_result(&[(false, Some(5))]);
+        result.col_specs[0].name = "applied".to_string();
+
+        // The name of the outcome column matters only if the LWT isn't confirmed.
+        assert_matches!(result.lwt_outcome::<(i32,)>(), Err(LwtResultError::NotLwt));
+        assert_matches!(
+            result.lwt_outcome_of::<(i32,)>(true),
+            Ok(LwtOutcome::NotApplied((5,)))
+        );
+
+        // Its type is always checked.
+        result.col_specs[0].typ = ColumnType::Int;
+        assert_matches!(
+            result.lwt_outcome_of::<(i32,)>(true),
+            Err(LwtResultError::NotLwt)
+        );
+    }
+
+    #[test]
+    fn into_legacy_result_test() {
+        setup_tracing();
+        assert_eq!(
+            make_raw_query_result(None)
+                .into_legacy_result()
+                .unwrap()
+                .rows,
+            None
+        );
+        for rows_num in 0..3 {
+            let legacy = make_raw_query_result(Some(rows_num))
+                .into_legacy_result()
+                .unwrap();
+            assert_eq!(legacy.rows, Some(make_rows(rows_num)));
+            assert_eq!(legacy.col_specs, make_not_rows_query_result().col_specs);
+        }
+    }
 }
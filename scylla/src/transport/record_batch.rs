@@ -0,0 +1,774 @@
+//! Conversion of query results into Apache Arrow [`RecordBatch`]es
+//!
+//! Enabled with the `arrow` feature. Each column of the result becomes one Arrow column,
+//! with its Arrow type derived from the CQL type found in the [`ColumnSpec`]:
+//!
+//! | CQL type                      | Arrow type                                       |
+//! |-------------------------------|--------------------------------------------------|
+//! | `ascii`, `text`, `varchar`    | `Utf8`                                           |
+//! | `inet`                        | `Utf8`, the textual form of the address          |
+//! | `boolean`                     | `Boolean`                                        |
+//! | `tinyint`, `smallint`, `int`  | `Int8`, `Int16`, `Int32`                         |
+//! | `bigint`, `counter`           | `Int64`                                          |
+//! | `float`, `double`             | `Float32`, `Float64`                             |
+//! | `blob`                        | `Binary`                                         |
+//! | `varint`                      | `Binary`, big-endian two's complement            |
+//! | `decimal`                     | `Struct` of `unscaled` (as `varint`) and `scale` |
+//! | `date`                        | `Date32`                                         |
+//! | `time`                        | `Time64(Nanosecond)`                             |
+//! | `timestamp`                   | `Timestamp(Millisecond, "UTC")`                  |
+//! | `duration`                    | `Interval(MonthDayNano)`                         |
+//! | `uuid`, `timeuuid`            | `FixedSizeBinary(16)`                            |
+//! | `list<T>`, `set<T>`           | `List<T>`                                        |
+//! | `map<K, V>`                   | `Map<K, V>`                                      |
+//! | user defined type             | `Struct`, with the fields of the UDT             |
+//! | `tuple<...>`                  | `Struct`, with fields named `0`, `1`, ...        |
+//! | `vector<T, N>`                | `FixedSizeList<T, N>`                            |
+//!
+//! Custom types can't be converted.
+
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use arrow::array::{
+    ArrayRef, BinaryArray, BooleanArray, Date32Array, FixedSizeBinaryArray, FixedSizeListArray,
+    Float32Array, Float64Array, Int16Array, Int32Array, Int64Array, Int8Array,
+    IntervalMonthDayNanoArray, ListArray, MapArray, StringArray, StructArray,
+    Time64NanosecondArray, TimestampMillisecondArray,
+};
+use arrow::buffer::{NullBuffer, OffsetBuffer};
+use arrow::datatypes::{
+    DataType, Field, FieldRef, Fields, IntervalMonthDayNanoType, IntervalUnit, Schema, TimeUnit,
+};
+use arrow::error::ArrowError;
+use arrow::record_batch::{RecordBatch, RecordBatchOptions};
+use scylla_cql::frame::types;
+use scylla_cql::types::deserialize::value::{BytesSequenceIterator, VectorIterator};
+use thiserror::Error;
+use uuid::Uuid;
+
+use super::errors::QueryError;
+use super::query_result::RowsExpectedError;
+use crate::deserialize::row::ColumnIterator;
+use crate::deserialize::value::{ListlikeIterator, MapIterator, UdtIterator};
+use crate::deserialize::{DeserializationError, DeserializeValue, FrameSlice, TypeCheckError};
+use crate::frame::response::result::{ColumnSpec, ColumnType};
+use crate::frame::value::{CqlDate, CqlDuration, CqlTime, CqlTimestamp, CqlTimeuuid};
+
+/// Timezone of the Arrow timestamps, CQL timestamps are always UTC.
+const TIMESTAMP_TIMEZONE: &str = "UTC";
+
+/// Converting query results into Arrow failed
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum ArrowConversionError {
+    /// The query isn't of a type that could return rows (e.g `INSERT`)
+    #[error(transparent)]
+    RowsExpected(#[from] RowsExpectedError),
+
+    /// Deserializing the received rows failed
+    #[error("Deserialization failed: {0}")]
+    DeserializationFailed(#[from] DeserializationError),
+
+    /// A column has a type which has no Arrow counterpart
+    #[error("Column type {0} can't be converted to Arrow")]
+    UnsupportedType(String),
+
+    /// A map has an empty key, while Arrow map keys can't be null
+    #[error("Map keys can't be empty")]
+    EmptyMapKey,
+
+    /// Building the Arrow arrays failed
+    #[error("Building Arrow arrays failed: {0}")]
+    ArrowError(#[from] ArrowError),
+}
+
+/// Couldn't get the next record batch from the stream
+#[derive(Error, Debug)]
+pub enum NextRecordBatchError {
+    /// Query to fetch next page has failed
+    #[error(transparent)]
+    QueryError(#[from] QueryError),
+
+    /// Converting the received page failed
+    #[error(transparent)]
+    ConversionError(#[from] ArrowConversionError),
+}
+
+/// Returns the Arrow schema matching the given columns.\
+/// Every field is nullable, as any CQL value can be null.
+pub fn arrow_schema(col_specs: &[ColumnSpec]) -> Result<Schema, ArrowConversionError> {
+    let fields = col_specs
+        .iter()
+        .map(|spec| arrow_field(&spec.name, &spec.typ))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Schema::new(fields))
+}
+
+/// Builds a record batch with the given schema out of the serialized rows, column by column.
+pub(crate) fn rows_to_record_batch<'frame>(
+    schema: Arc<Schema>,
+    col_specs: &'frame [ColumnSpec],
+    rows: impl Iterator<Item = Result<ColumnIterator<'frame>, DeserializationError>>,
+) -> Result<RecordBatch, ArrowConversionError> {
+    let mut columns: Vec<Vec<Cell<'frame>>> = vec![Vec::new(); col_specs.len()];
+    let mut row_count = 0;
+    for row in rows {
+        for (cells, column) in columns.iter_mut().zip(row?) {
+            cells.push(column?.slice);
+        }
+        row_count += 1;
+    }
+
+    let columns = col_specs
+        .iter()
+        .zip(&columns)
+        .map(|(spec, cells)| build_array(&spec.typ, cells))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let options = RecordBatchOptions::new().with_row_count(Some(row_count));
+    Ok(RecordBatch::try_new_with_options(
+        schema, columns, &options,
+    )?)
+}
+
+fn arrow_field(name: &str, typ: &ColumnType) -> Result<Field, ArrowConversionError> {
+    Ok(Field::new(name, arrow_type(typ)?, true))
+}
+
+fn arrow_type(typ: &ColumnType) -> Result<DataType, ArrowConversionError> {
+    let data_type = match typ {
+        ColumnType::Ascii | ColumnType::Text | ColumnType::Inet => DataType::Utf8,
+        ColumnType::Boolean => DataType::Boolean,
+        ColumnType::TinyInt => DataType::Int8,
+        ColumnType::SmallInt => DataType::Int16,
+        ColumnType::Int => DataType::Int32,
+        ColumnType::BigInt | ColumnType::Counter => DataType::Int64,
+        ColumnType::Float => DataType::Float32,
+        ColumnType::Double => DataType::Float64,
+        ColumnType::Blob | ColumnType::Varint => DataType::Binary,
+        ColumnType::Decimal => DataType::Struct(decimal_fields()),
+        ColumnType::Date => DataType::Date32,
+        ColumnType::Time => DataType::Time64(TimeUnit::Nanosecond),
+        ColumnType::Timestamp => {
+            DataType::Timestamp(TimeUnit::Millisecond, Some(TIMESTAMP_TIMEZONE.into()))
+        }
+        ColumnType::Duration => DataType::Interval(IntervalUnit::MonthDayNano),
+        ColumnType::Uuid | ColumnType::Timeuuid => DataType::FixedSizeBinary(16),
+        ColumnType::List(elem) | ColumnType::Set(elem) => DataType::List(item_field(elem)?),
+        ColumnType::Map(key, value) => DataType::Map(entries_field(key, value)?, false),
+        ColumnType::UserDefinedType { field_types, .. } => {
+            let fields = field_types
+                .iter()
+                .map(|(name, typ)| arrow_field(name, typ))
+                .collect::<Result<Vec<_>, _>>()?;
+            DataType::Struct(fields.into())
+        }
+        ColumnType::Tuple(types) => DataType::Struct(tuple_fields(types)?),
+        ColumnType::Vector { typ, dimensions } => {
+            DataType::FixedSizeList(item_field(typ)?, *dimensions as i32)
+        }
+        ColumnType::Custom(_) => {
+            return Err(ArrowConversionError::UnsupportedType(format!("{:?}", typ)))
+        }
+    };
+    Ok(data_type)
+}
+
+fn item_field(elem: &ColumnType) -> Result<FieldRef, ArrowConversionError> {
+    Ok(Arc::new(arrow_field("item", elem)?))
+}
+
+fn entries_field(key: &ColumnType, value: &ColumnType) -> Result<FieldRef, ArrowConversionError> {
+    Ok(Arc::new(Field::new(
+        "entries",
+        DataType::Struct(entries_fields(key, value)?),
+        false,
+    )))
+}
+
+fn entries_fields(key: &ColumnType, value: &ColumnType) -> Result<Fields, ArrowConversionError> {
+    Ok(Fields::from(vec![
+        Field::new("key", arrow_type(key)?, false),
+        arrow_field("value", value)?,
+    ]))
+}
+
+fn tuple_fields(types: &[ColumnType]) -> Result<Fields, ArrowConversionError> {
+    let fields = types
+        .iter()
+        .enumerate()
+        .map(|(idx, typ)| arrow_field(&idx.to_string(), typ))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(fields.into())
+}
+
+fn decimal_fields() -> Fields {
+    Fields::from(vec![
+        Field::new("unscaled", DataType::Binary, false),
+        Field::new("scale", DataType::Int32, false),
+    ])
+}
+
+/// A serialized value, `None` if null.
+type Cell<'frame> = Option<FrameSlice<'frame>>;
+
+/// A serialized value whose deserialization is deferred until it's known
+/// which array it belongs to.
+struct RawCell<'frame>(Cell<'frame>);
+
+impl<'frame> DeserializeValue<'frame> for RawCell<'frame> {
+    fn type_check(_typ: &ColumnType) -> Result<(), TypeCheckError> {
+        Ok(())
+    }
+
+    fn deserialize(
+        _typ: &'frame ColumnType,
+        v: Option<FrameSlice<'frame>>,
+    ) -> Result<Self, DeserializationError> {
+        Ok(Self(v))
+    }
+}
+
+// Empty values carry no data, so they are treated as nulls.
+// Only strings and blobs can be empty without being "empty values".
+fn non_empty<'frame>(typ: &ColumnType, cell: Cell<'frame>) -> Cell<'frame> {
+    cell.filter(|slice| {
+        !slice.is_empty() || matches!(typ, ColumnType::Ascii | ColumnType::Text | ColumnType::Blob)
+    })
+}
+
+fn null_buffer(cells: &[Cell]) -> Option<NullBuffer> {
+    let nulls = NullBuffer::from(cells.iter().map(Option::is_some).collect::<Vec<_>>());
+    (nulls.null_count() > 0).then_some(nulls)
+}
+
+// Deserializes the non-null cells as `T`, and converts them with `conv`.
+fn convert_cells<'frame, T, U>(
+    typ: &'frame ColumnType,
+    cells: &[Cell<'frame>],
+    conv: impl Fn(T) -> U,
+) -> Result<Vec<Option<U>>, ArrowConversionError>
+where
+    T: DeserializeValue<'frame>,
+{
+    cells
+        .iter()
+        .map(|cell| {
+            cell.map(|slice| T::deserialize(typ, Some(slice)).map(&conv))
+                .transpose()
+                .map_err(ArrowConversionError::from)
+        })
+        .collect()
+}
+
+fn decimal_parts(slice: FrameSlice<'_>) -> Result<(&[u8], i32), DeserializationError> {
+    let mut bytes = slice.as_slice();
+    let scale = types::read_int(&mut bytes).map_err(DeserializationError::new)?;
+    Ok((bytes, scale))
+}
+
+fn build_array<'frame>(
+    typ: &'frame ColumnType,
+    cells: &[Cell<'frame>],
+) -> Result<ArrayRef, ArrowConversionError> {
+    let cells: Vec<Cell<'frame>> = cells.iter().map(|cell| non_empty(typ, *cell)).collect();
+    let array: ArrayRef = match typ {
+        ColumnType::Ascii | ColumnType::Text => {
+            Arc::new(StringArray::from(convert_cells(typ, &cells, |s: &str| s)?))
+        }
+        ColumnType::Inet => Arc::new(StringArray::from(convert_cells(
+            typ,
+            &cells,
+            |addr: IpAddr| addr.to_string(),
+        )?)),
+        ColumnType::Boolean => {
+            Arc::new(BooleanArray::from(convert_cells(typ, &cells, |b: bool| b)?))
+        }
+        ColumnType::TinyInt => Arc::new(Int8Array::from(convert_cells(typ, &cells, |v: i8| v)?)),
+        ColumnType::SmallInt => Arc::new(Int16Array::from(convert_cells(typ, &cells, |v: i16| v)?)),
+        ColumnType::Int => Arc::new(Int32Array::from(convert_cells(typ, &cells, |v: i32| v)?)),
+        ColumnType::BigInt | ColumnType::Counter => {
+            Arc::new(Int64Array::from(convert_cells(typ, &cells, |v: i64| v)?))
+        }
+        ColumnType::Float => Arc::new(Float32Array::from(convert_cells(typ, &cells, |v: f32| v)?)),
+        ColumnType::Double => Arc::new(Float64Array::from(convert_cells(typ, &cells, |v: f64| v)?)),
+        ColumnType::Blob => Arc::new(BinaryArray::from(convert_cells(typ, &cells, |b: &[u8]| b)?)),
+        ColumnType::Varint => {
+            // Serialized varints are already big-endian two's complement
+            let varints: Vec<Option<&[u8]>> = cells
+                .iter()
+                .map(|cell| cell.map(|slice| slice.as_slice()))
+                .collect();
+            Arc::new(BinaryArray::from(varints))
+        }
+        ColumnType::Decimal => {
+            let decimals = cells
+                .iter()
+                .map(|cell| cell.map(decimal_parts).transpose())
+                .collect::<Result<Vec<_>, _>>()?;
+            let unscaled: Vec<&[u8]> = decimals
+                .iter()
+                .map(|d| d.map_or(&[][..], |(bytes, _)| bytes))
+                .collect();
+            let scales: Vec<i32> = decimals
+                .iter()
+                .map(|d| d.map_or(0, |(_, scale)| scale))
+                .collect();
+            Arc::new(StructArray::try_new(
+                decimal_fields(),
+                vec![
+                    Arc::new(BinaryArray::from(unscaled)),
+                    Arc::new(Int32Array::from(scales)),
+                ],
+                null_buffer(&cells),
+            )?)
+        }
+        ColumnType::Date => {
+            // CQL dates are days since the epoch, shifted by 2^31 to be unsigned
+            Arc::new(Date32Array::from(convert_cells(
+                typ,
+                &cells,
+                |d: CqlDate| (d.0 as i64 - (1 << 31)) as i32,
+            )?))
+        }
+        ColumnType::Time => Arc::new(Time64NanosecondArray::from(convert_cells(
+            typ,
+            &cells,
+            |t: CqlTime| t.0,
+        )?)),
+        ColumnType::Timestamp => {
+            let millis = convert_cells(typ, &cells, |t: CqlTimestamp| t.0)?;
+            Arc::new(TimestampMillisecondArray::from(millis).with_timezone(TIMESTAMP_TIMEZONE))
+        }
+        ColumnType::Duration => Arc::new(IntervalMonthDayNanoArray::from(convert_cells(
+            typ,
+            &cells,
+            |d: CqlDuration| IntervalMonthDayNanoType::make_value(d.months, d.days, d.nanoseconds),
+        )?)),
+        ColumnType::Uuid | ColumnType::Timeuuid => {
+            let uuids = match typ {
+                ColumnType::Uuid => convert_cells(typ, &cells, |u: Uuid| *u.as_bytes())?,
+                _ => convert_cells(typ, &cells, |u: CqlTimeuuid| *u.as_bytes())?,
+            };
+            Arc::new(FixedSizeBinaryArray::try_from_sparse_iter_with_size(
+                uuids.into_iter(),
+                16,
+            )?)
+        }
+        ColumnType::List(elem) | ColumnType::Set(elem) => {
+            let mut lengths = Vec::with_capacity(cells.len());
+            let mut items: Vec<Cell<'frame>> = Vec::new();
+            for cell in &cells {
+                let items_before = items.len();
+                if let Some(slice) = cell {
+                    for item in ListlikeIterator::<RawCell>::deserialize(typ, Some(*slice))? {
+                        items.push(item?.0);
+                    }
+                }
+                lengths.push(items.len() - items_before);
+            }
+            Arc::new(ListArray::try_new(
+                item_field(elem)?,
+                OffsetBuffer::from_lengths(lengths),
+                build_array(elem, &items)?,
+                null_buffer(&cells),
+            )?)
+        }
+        ColumnType::Map(key_type, value_type) => {
+            let mut lengths = Vec::with_capacity(cells.len());
+            let mut keys: Vec<Cell<'frame>> = Vec::new();
+            let mut values: Vec<Cell<'frame>> = Vec::new();
+            for cell in &cells {
+                let entries_before = keys.len();
+                if let Some(slice) = cell {
+                    let entries = MapIterator::<RawCell, RawCell>::deserialize(typ, Some(*slice))?;
+                    for entry in entries {
+                        let (key, value) = entry?;
+                        // Arrow map keys can't be null
+                        let key =
+                            non_empty(key_type, key.0).ok_or(ArrowConversionError::EmptyMapKey)?;
+                        keys.push(Some(key));
+                        values.push(value.0);
+                    }
+                }
+                lengths.push(keys.len() - entries_before);
+            }
+            let entries = StructArray::try_new(
+                entries_fields(key_type, value_type)?,
+                vec![
+                    build_array(key_type, &keys)?,
+                    build_array(value_type, &values)?,
+                ],
+                None,
+            )?;
+            Arc::new(MapArray::try_new(
+                entries_field(key_type, value_type)?,
+                OffsetBuffer::from_lengths(lengths),
+                entries,
+                null_buffer(&cells),
+                false,
+            )?)
+        }
+        ColumnType::UserDefinedType { field_types, .. } => {
+            let mut field_cells: Vec<Vec<Cell<'frame>>> =
+                vec![Vec::with_capacity(cells.len()); field_types.len()];
+            for cell in &cells {
+                // Trailing fields may be missing, they are nulls then
+                let mut udt_fields = match cell {
+                    Some(slice) => Some(UdtIterator::deserialize(typ, Some(*slice))?),
+                    None => None,
+                };
+                for field in field_cells.iter_mut() {
+                    let value = match udt_fields.as_mut().and_then(Iterator::next) {
+                        Some((_, value)) => value?.flatten(),
+                        None => None,
+                    };
+                    field.push(value);
+                }
+            }
+            let types: Vec<&ColumnType> = field_types.iter().map(|(_, typ)| typ).collect();
+            let DataType::Struct(fields) = arrow_type(typ)? else {
+                unreachable!("UDTs are converted to structs")
+            };
+            build_struct_array(fields, &types, &field_cells, null_buffer(&cells))?
+        }
+        ColumnType::Tuple(elem_types) => {
+            let mut elem_cells: Vec<Vec<Cell<'frame>>> =
+                vec![Vec::with_capacity(cells.len()); elem_types.len()];
+            for cell in &cells {
+                // Trailing elements may be missing, they are nulls then
+                let mut elems = cell.map(BytesSequenceIterator::from);
+                for elem in elem_cells.iter_mut() {
+                    let value = match elems.as_mut().and_then(Iterator::next) {
+                        Some(value) => value.map_err(DeserializationError::new)?,
+                        None => None,
+                    };
+                    elem.push(value);
+                }
+            }
+            let types: Vec<&ColumnType> = elem_types.iter().collect();
+            build_struct_array(
+                tuple_fields(elem_types)?,
+                &types,
+                &elem_cells,
+                null_buffer(&cells),
+            )?
+        }
+        ColumnType::Vector {
+            typ: elem,
+            dimensions,
+        } => {
+            let dimensions = *dimensions as usize;
+            let mut items: Vec<Cell<'frame>> = Vec::with_capacity(cells.len() * dimensions);
+            for cell in &cells {
+                match cell {
+                    // Null vectors still take up `dimensions` slots in the child array
+                    None => items.resize(items.len() + dimensions, None),
+                    Some(slice) => {
+                        for item in VectorIterator::<RawCell>::deserialize(typ, Some(*slice))? {
+                            items.push(item?.0);
+                        }
+                    }
+                }
+            }
+            Arc::new(FixedSizeListArray::try_new(
+                item_field(elem)?,
+                dimensions as i32,
+                build_array(elem, &items)?,
+                null_buffer(&cells),
+            )?)
+        }
+        ColumnType::Custom(_) => {
+            return Err(ArrowConversionError::UnsupportedType(format!("{:?}", typ)))
+        }
+    };
+    Ok(array)
+}
+
+fn build_struct_array<'frame>(
+    fields: Fields,
+    types: &[&'frame ColumnType],
+    field_cells: &[Vec<Cell<'frame>>],
+    nulls: Option<NullBuffer>,
+) -> Result<ArrayRef, ArrowConversionError> {
+    let arrays = types
+        .iter()
+        .zip(field_cells)
+        .map(|(typ, cells)| build_array(typ, cells))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Arc::new(StructArray::try_new(fields, arrays, nulls)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deserialize::result::RowIterator;
+    use crate::frame::response::result::{CqlValue, TableSpec};
+    use crate::frame::value::Counter;
+    use crate::serialize::value::SerializeValue;
+    use crate::serialize::CellWriter;
+    use crate::test_utils::setup_tracing;
+    use crate::QueryResult;
+    use arrow::array::{Array, AsArray};
+    use arrow::datatypes::{Int32Type, Int64Type};
+    use assert_matches::assert_matches;
+    use bytes::Bytes;
+
+    fn col_spec(name: &str, typ: ColumnType) -> ColumnSpec {
+        ColumnSpec {
+            table_spec: TableSpec::borrowed("ks", "tab"),
+            name: name.to_string(),
+            typ,
+        }
+    }
+
+    fn try_convert(
+        col_specs: &[ColumnSpec],
+        rows: Vec<Vec<Option<CqlValue>>>,
+    ) -> Result<RecordBatch, ArrowConversionError> {
+        let schema = Arc::new(arrow_schema(col_specs).unwrap());
+        let mut raw_rows = Vec::new();
+        for row in &rows {
+            for (spec, value) in col_specs.iter().zip(row) {
+                value
+                    .serialize(&spec.typ, CellWriter::new(&mut raw_rows))
+                    .unwrap();
+            }
+        }
+        let raw_rows = Bytes::from(raw_rows);
+        let rows = RowIterator::new(rows.len(), col_specs, FrameSlice::new(&raw_rows));
+        rows_to_record_batch(schema, col_specs, rows)
+    }
+
+    fn convert(col_specs: &[ColumnSpec], rows: Vec<Vec<Option<CqlValue>>>) -> RecordBatch {
+        try_convert(col_specs, rows).unwrap()
+    }
+
+    #[test]
+    fn native_types_conversion() {
+        setup_tracing();
+        let col_specs = [
+            col_spec("a", ColumnType::Int),
+            col_spec("b", ColumnType::Text),
+            col_spec("c", ColumnType::Counter),
+            col_spec("d", ColumnType::Date),
+            col_spec("e", ColumnType::Timestamp),
+            col_spec("f", ColumnType::Duration),
+            col_spec("g", ColumnType::Uuid),
+        ];
+        let uuid = uuid::Uuid::from_u128(0x1234);
+        let batch = convert(
+            &col_specs,
+            vec![
+                vec![
+                    Some(CqlValue::Int(1)),
+                    Some(CqlValue::Text("one".to_string())),
+                    Some(CqlValue::Counter(Counter(10))),
+                    Some(CqlValue::Date(CqlDate((1 << 31) + 3))),
+                    Some(CqlValue::Timestamp(CqlTimestamp(1000))),
+                    Some(CqlValue::Duration(CqlDuration {
+                        months: 1,
+                        days: 2,
+                        nanoseconds: 3,
+                    })),
+                    Some(CqlValue::Uuid(uuid)),
+                ],
+                vec![Some(CqlValue::Empty), None, None, None, None, None, None],
+            ],
+        );
+
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(batch.schema().field(0).name(), "a");
+
+        let ints = batch.column(0).as_primitive::<Int32Type>();
+        assert_eq!(ints.value(0), 1);
+        assert!(ints.is_null(1));
+
+        let texts = batch.column(1).as_string::<i32>();
+        assert_eq!(texts.value(0), "one");
+        assert!(texts.is_null(1));
+
+        assert_eq!(batch.column(2).as_primitive::<Int64Type>().value(0), 10);
+
+        let dates = batch
+            .column(3)
+            .as_primitive::<arrow::datatypes::Date32Type>();
+        assert_eq!(dates.value(0), 3);
+
+        assert_eq!(
+            batch.column(4).data_type(),
+            &DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into()))
+        );
+        let durations = batch.column(5).as_primitive::<IntervalMonthDayNanoType>();
+        assert_eq!(
+            durations.value(0),
+            IntervalMonthDayNanoType::make_value(1, 2, 3)
+        );
+
+        let uuids = batch
+            .column(6)
+            .as_any()
+            .downcast_ref::<FixedSizeBinaryArray>()
+            .unwrap();
+        assert_eq!(uuids.value(0), uuid.as_bytes());
+        assert!(uuids.is_null(1));
+    }
+
+    #[test]
+    fn nested_types_conversion() {
+        setup_tracing();
+        let udt_type = ColumnType::UserDefinedType {
+            type_name: "udt".to_string(),
+            keyspace: "ks".to_string(),
+            field_types: vec![
+                ("x".to_string(), ColumnType::Int),
+                ("y".to_string(), ColumnType::Text),
+            ],
+        };
+        let col_specs = [
+            col_spec("list", ColumnType::List(Box::new(ColumnType::Int))),
+            col_spec(
+                "map",
+                ColumnType::Map(Box::new(ColumnType::Text), Box::new(ColumnType::BigInt)),
+            ),
+            col_spec("udt", udt_type),
+            col_spec(
+                "tuple",
+                ColumnType::Tuple(vec![ColumnType::Int, ColumnType::Boolean]),
+            ),
+            col_spec(
+                "vector",
+                ColumnType::Vector {
+                    typ: Box::new(ColumnType::Float),
+                    dimensions: 2,
+                },
+            ),
+        ];
+        let batch = convert(
+            &col_specs,
+            vec![
+                vec![
+                    Some(CqlValue::List(vec![CqlValue::Int(1), CqlValue::Int(2)])),
+                    Some(CqlValue::Map(vec![(
+                        CqlValue::Text("k".to_string()),
+                        CqlValue::BigInt(7),
+                    )])),
+                    Some(CqlValue::UserDefinedType {
+                        keyspace: "ks".to_string(),
+                        type_name: "udt".to_string(),
+                        // Trailing fields may be missing
+                        fields: vec![("x".to_string(), Some(CqlValue::Int(5)))],
+                    }),
+                    Some(CqlValue::Tuple(vec![Some(CqlValue::Int(8)), None])),
+                    Some(CqlValue::Vector(vec![
+                        CqlValue::Float(0.5),
+                        CqlValue::Float(1.5),
+                    ])),
+                ],
+                vec![None, None, None, None, None],
+            ],
+        );
+
+        let lists = batch.column(0).as_list::<i32>();
+        assert_eq!(lists.value_length(0), 2);
+        assert_eq!(lists.value(0).as_primitive::<Int32Type>().values(), &[1, 2]);
+        assert!(lists.is_null(1));
+
+        let maps = batch.column(1).as_map();
+        assert_eq!(maps.keys().as_string::<i32>().value(0), "k");
+        assert_eq!(maps.values().as_primitive::<Int64Type>().value(0), 7);
+        assert!(maps.is_null(1));
+
+        let udts = batch.column(2).as_struct();
+        assert_eq!(udts.column_names(), vec!["x", "y"]);
+        assert_eq!(udts.column(0).as_primitive::<Int32Type>().value(0), 5);
+        assert!(udts.column(1).is_null(0));
+        assert!(udts.is_null(1));
+
+        let tuples = batch.column(3).as_struct();
+        assert_eq!(tuples.column_names(), vec!["0", "1"]);
+        assert_eq!(tuples.column(0).as_primitive::<Int32Type>().value(0), 8);
+        assert!(tuples.column(1).is_null(0));
+
+        let vectors = batch.column(4).as_fixed_size_list();
+        assert_eq!(vectors.value_length(), 2);
+        assert_eq!(
+            vectors
+                .value(0)
+                .as_primitive::<arrow::datatypes::Float32Type>()
+                .values(),
+            &[0.5, 1.5]
+        );
+        assert!(vectors.is_null(1));
+    }
+
+    #[test]
+    fn conversion_errors() {
+        setup_tracing();
+        assert_matches!(
+            arrow_schema(&[col_spec("a", ColumnType::Custom("Foo".to_string()))]),
+            Err(ArrowConversionError::UnsupportedType(_))
+        );
+
+        // An int column holding a text value
+        let col_specs = [col_spec("a", ColumnType::Int)];
+        let schema = Arc::new(arrow_schema(&col_specs).unwrap());
+        let mut raw_rows = Vec::new();
+        raw_rows.extend_from_slice(&10_i32.to_be_bytes());
+        raw_rows.extend_from_slice(b"not an int");
+        let raw_rows = Bytes::from(raw_rows);
+        let rows = RowIterator::new(1, &col_specs, FrameSlice::new(&raw_rows));
+        assert_matches!(
+            rows_to_record_batch(schema, &col_specs, rows),
+            Err(ArrowConversionError::DeserializationFailed(_))
+        );
+
+        // Arrow map keys can't be null, so empty keys are rejected
+        let col_specs = [col_spec(
+            "a",
+            ColumnType::Map(Box::new(ColumnType::Int), Box::new(ColumnType::Int)),
+        )];
+        assert_matches!(
+            try_convert(
+                &col_specs,
+                vec![vec![Some(CqlValue::Map(vec![(
+                    CqlValue::Empty,
+                    CqlValue::Int(1)
+                )]))]]
+            ),
+            Err(ArrowConversionError::EmptyMapKey)
+        );
+    }
+
+    #[test]
+    fn query_result_to_record_batch() {
+        setup_tracing();
+        let col_specs = vec![col_spec("a", ColumnType::Int)];
+        let not_rows = QueryResult {
+            col_specs: col_specs.clone(),
+            ..Default::default()
+        };
+        assert_matches!(
+            not_rows.to_record_batch(),
+            Err(ArrowConversionError::RowsExpected(_))
+        );
+
+        // Two rows: 7 and null
+        let mut raw_rows = Vec::new();
+        raw_rows.extend_from_slice(&4_i32.to_be_bytes());
+        raw_rows.extend_from_slice(&7_i32.to_be_bytes());
+        raw_rows.extend_from_slice(&(-1_i32).to_be_bytes());
+        let result = QueryResult {
+            col_specs,
+            raw_rows: Some(Bytes::from(raw_rows)),
+            rows_count: 2,
+            ..Default::default()
+        };
+        let batch = result.to_record_batch().unwrap();
+        let ints = batch.column(0).as_primitive::<Int32Type>();
+        assert_eq!(ints.len(), 2);
+        assert_eq!(ints.value(0), 7);
+        assert!(ints.is_null(1));
+    }
+}
@@ -0,0 +1,212 @@
+//! Proactive re-preparation of statements on nodes which come up or join the cluster.
+//!
+//! A node which has just (re)started has an empty prepared statement cache, so
+//! without this the first execution of each statement on it would get an
+//! `UNPREPARED` error and have to re-prepare the statement before retrying.
+
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures::stream::{self, FuturesUnordered, StreamExt};
+use itertools::Itertools;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::{debug, warn};
+
+use crate::prepared_statement::{PreparedStatement, WeakPreparedStatement};
+use crate::query::Query;
+use crate::transport::node::Node;
+
+/// How long to wait for a node which came up to have any working connection.
+const POOL_WAIT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Statements prepared in a session, referenced weakly so that the registry
+/// doesn't keep alive the statements which were dropped by the user.
+#[derive(Debug, Default)]
+pub(crate) struct PreparedStatementRegistry {
+    inner: Mutex<RegistryInner>,
+}
+
+#[derive(Debug, Default)]
+struct RegistryInner {
+    /// The same statement may be prepared multiple times, each time giving
+    /// a separate [`PreparedStatement`], but they all share the id.
+    statements: HashMap<Bytes, Vec<WeakPreparedStatement>>,
+    /// Dropped statements are removed when the number of ids reaches this.
+    prune_threshold: usize,
+}
+
+impl RegistryInner {
+    fn prune(&mut self) {
+        self.statements.retain(|_, handles| {
+            handles.retain(WeakPreparedStatement::is_alive);
+            !handles.is_empty()
+        });
+        self.prune_threshold = (2 * self.statements.len()).max(64);
+    }
+}
+
+impl PreparedStatementRegistry {
+    pub(crate) fn register(&self, statement: &PreparedStatement) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.statements.len() >= inner.prune_threshold {
+            inner.prune();
+        }
+
+        let handles = inner
+            .statements
+            .entry(statement.get_id().clone())
+            .or_default();
+        handles.retain(WeakPreparedStatement::is_alive);
+        handles.push(statement.downgrade());
+    }
+
+    /// Returns the contents of the statements which are still alive, one per id.
+    fn live_statements(&self) -> Vec<String> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.prune();
+        inner
+            .statements
+            .values()
+            .filter_map(|handles| {
+                handles
+                    .iter()
+                    .find_map(WeakPreparedStatement::get_statement)
+            })
+            .collect()
+    }
+}
+
+/// Re-prepares the registered statements on the nodes received from `nodes_up`,
+/// at most `concurrency` statements at once on each node.
+/// Stops when the channel is closed.
+pub(crate) async fn reprepare_on_nodes_up(
+    registry: Arc<PreparedStatementRegistry>,
+    mut nodes_up: broadcast::Receiver<Arc<Node>>,
+    concurrency: NonZeroUsize,
+) {
+    let mut in_progress = FuturesUnordered::new();
+    loop {
+        tokio::select! {
+            recv_res = nodes_up.recv() => match recv_res {
+                Ok(node) => {
+                    let statements = registry.live_statements();
+                    if !statements.is_empty() {
+                        in_progress.push(reprepare_on_node(node, statements, concurrency));
+                    }
+                }
+                Err(RecvError::Lagged(missed)) => {
+                    // The statements will be re-prepared on demand on these nodes.
+                    warn!("Missed {} nodes to re-prepare statements on", missed);
+                }
+                Err(RecvError::Closed) => return,
+            },
+            Some(()) = in_progress.next(), if !in_progress.is_empty() => {}
+        }
+    }
+}
+
+async fn reprepare_on_node(node: Arc<Node>, statements: Vec<String>, concurrency: NonZeroUsize) {
+    if !node.is_enabled() {
+        return;
+    }
+    if tokio::time::timeout(POOL_WAIT_TIMEOUT, node.wait_until_pool_working())
+        .await
+        .is_err()
+    {
+        debug!(
+            "Node {} has no working connections, not re-preparing statements on it",
+            node.address
+        );
+        return;
+    }
+    let connections = match node.get_working_connections() {
+        Ok(connections) => connections,
+        Err(err) => {
+            debug!(
+                "Failed to get connections to node {} to re-prepare statements on: {}",
+                node.address, err
+            );
+            return;
+        }
+    };
+
+    debug!(
+        "Re-preparing {} statements on {} connections to node {}",
+        statements.len(),
+        connections.len(),
+        node.address
+    );
+    let node = &node;
+    stream::iter(connections.iter().cartesian_product(statements.iter()))
+        .for_each_concurrent(concurrency.get(), |(connection, statement)| async move {
+            if let Err(err) = connection.prepare(&Query::new(statement.clone())).await {
+                debug!(
+                    "Failed to re-prepare statement {} on node {}: {}",
+                    statement, node.address, err
+                );
+            }
+        })
+        .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use scylla_cql::frame::response::result::{PreparedMetadata, ResultMetadata};
+
+    use super::PreparedStatementRegistry;
+    use crate::prepared_statement::PreparedStatement;
+    use crate::test_utils::setup_tracing;
+
+    fn prepared(id: &'static [u8], statement: &str) -> PreparedStatement {
+        PreparedStatement::new(
+            Bytes::from_static(id),
+            false,
+            PreparedMetadata {
+                flags: 0,
+                col_count: 0,
+                pk_indexes: Vec::new(),
+                col_specs: Vec::new(),
+            },
+            ResultMetadata::default(),
+            None,
+            statement.to_owned(),
+            None,
+            Default::default(),
+        )
+    }
+
+    #[test]
+    fn registry_returns_only_live_statements() {
+        setup_tracing();
+        let registry = PreparedStatementRegistry::default();
+
+        let insert = prepared(b"1", "INSERT INTO ks.t (a) VALUES (?)");
+        let select = prepared(b"2", "SELECT a FROM ks.t");
+        registry.register(&insert);
+        registry.register(&select);
+        assert_eq!(registry.live_statements().len(), 2);
+
+        // The statement stays registered as long as any of its copies is alive.
+        let select_copy = select.clone();
+        drop(select);
+        assert_eq!(registry.live_statements().len(), 2);
+        drop(select_copy);
+        assert_eq!(
+            registry.live_statements(),
+            vec!["INSERT INTO ks.t (a) VALUES (?)".to_owned()]
+        );
+
+        // The same statement prepared twice is re-prepared once.
+        let insert_again = prepared(b"1", "INSERT INTO ks.t (a) VALUES (?)");
+        registry.register(&insert_again);
+        assert_eq!(registry.live_statements().len(), 1);
+        drop(insert);
+        assert_eq!(registry.live_statements().len(), 1);
+        drop(insert_again);
+        assert!(registry.live_statements().is_empty());
+    }
+}
@@ -2,10 +2,13 @@
 //! To decide when to retry a query the `Session` can use any object which implements
 //! the `RetryPolicy` trait
 
+use std::time::Duration;
+
 use crate::frame::types::Consistency;
 use crate::transport::errors::{DbError, QueryError, WriteType};
 
 /// Information about a failed query
+#[non_exhaustive]
 pub struct QueryInfo<'a> {
     /// The error with which the query failed
     pub error: &'a QueryError,
@@ -15,16 +18,37 @@ pub struct QueryInfo<'a> {
     pub is_idempotent: bool,
     /// Consistency with which the query failed
     pub consistency: Consistency,
+    /// Time left until the overall deadline of the request expires,
+    /// if a deadline is set on the statement or its execution profile.
+    /// Retrying makes little sense if it's shorter than the expected latency.
+    pub remaining_budget: Option<Duration>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum RetryDecision {
     RetrySameNode(Option<Consistency>), // None means that the same consistency should be used as before
     RetryNextNode(Option<Consistency>), // ditto
+    /// Retry on the same node, but only after the given delay has passed.
+    RetrySameNodeAfter(Option<Consistency>, Duration),
+    /// Retry on the next node, but only after the given delay has passed.
+    RetryNextNodeAfter(Option<Consistency>, Duration),
     DontRetry,
     IgnoreWriteError,
 }
 
+impl RetryDecision {
+    /// Returns the delay which has to pass before the query is retried,
+    /// or `None` if the decision is to retry immediately or not at all.
+    pub fn delay(&self) -> Option<Duration> {
+        match self {
+            RetryDecision::RetrySameNodeAfter(_, delay)
+            | RetryDecision::RetryNextNodeAfter(_, delay) => Some(*delay),
+            _ => None,
+        }
+    }
+}
+
 /// Specifies a policy used to decide when to retry a query
 pub trait RetryPolicy: std::fmt::Debug + Send + Sync {
     /// Called for each new query, starts a session of deciding about retries
@@ -231,6 +255,7 @@ mod tests {
             error,
             is_idempotent,
             consistency: Consistency::One,
+            remaining_budget: None,
         }
     }
 
@@ -13,10 +13,14 @@ use async_trait::async_trait;
 use bytes::Bytes;
 use futures::future::join_all;
 use futures::future::try_join_all;
+use futures::future::{FutureExt, RemoteHandle};
 use itertools::{Either, Itertools};
 pub use scylla_cql::errors::TranslationError;
+use scylla_cql::frame::frame_errors::ParseError;
+use scylla_cql::frame::response::event::SchemaChangeEvent;
 use scylla_cql::frame::response::result::{deser_cql_value, ColumnSpec, Rows};
 use scylla_cql::frame::response::NonErrorResponse;
+use scylla_cql::types::deserialize::row::DeserializeRow;
 use scylla_cql::types::serialize::batch::BatchValues;
 use scylla_cql::types::serialize::row::SerializeRow;
 use std::borrow::Borrow;
@@ -24,16 +28,17 @@ use std::collections::HashMap;
 use std::fmt::Display;
 use std::future::Future;
 use std::net::SocketAddr;
-use std::num::NonZeroU32;
+use std::num::{NonZeroU32, NonZeroUsize};
 use std::str::FromStr;
 use std::sync::atomic::AtomicUsize;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::time::timeout;
-use tracing::{debug, trace, trace_span, Instrument};
+use tracing::{debug, trace, trace_span, warn, Instrument};
 use uuid::Uuid;
 
+use super::connection::NegotiatedProtocolVersion;
 use super::connection::NonErrorQueryResponse;
 use super::connection::QueryResponse;
 #[cfg(feature = "ssl")]
@@ -44,7 +49,7 @@ use super::execution_profile::{ExecutionProfile, ExecutionProfileHandle, Executi
 use super::node::CloudEndpoint;
 use super::node::KnownNode;
 use super::partitioner::PartitionerName;
-use super::query_result::MaybeFirstRowTypedError;
+use super::query_result::{LwtError, LwtOutcome, MaybeFirstRowTypedError};
 use super::topology::UntranslatedPeer;
 use super::{NodeRef, SelfIdentity};
 use crate::cql_to_rust::FromRow;
@@ -53,20 +58,25 @@ use crate::frame::response::result;
 use crate::prepared_statement::PreparedStatement;
 use crate::query::Query;
 use crate::routing::{Shard, Token};
+use crate::statement::paging_state::PagingState;
 use crate::statement::Consistency;
 use crate::tracing::{TracingEvent, TracingInfo};
 use crate::transport::cluster::{Cluster, ClusterData, ClusterNeatDebug};
 use crate::transport::connection::{Connection, ConnectionConfig, VerifiedKeyspaceName};
 use crate::transport::connection_pool::PoolConfig;
 use crate::transport::host_filter::HostFilter;
+use crate::transport::in_flight_limiter::{InFlightLimiter, InFlightLimits};
 use crate::transport::iterator::{PreparedIteratorConfig, RowIterator};
 use crate::transport::load_balancing::{self, RoutingInfo};
 use crate::transport::metrics::Metrics;
 use crate::transport::node::Node;
 use crate::transport::query_result::QueryResult;
+use crate::transport::repreparation::{self, PreparedStatementRegistry};
 use crate::transport::retry_policy::{QueryInfo, RetryDecision, RetrySession};
 use crate::transport::speculative_execution;
-use crate::transport::Compression;
+use crate::transport::task_tracker::TaskTracker;
+use crate::transport::timestamp_generator::TimestampGenerator;
+use crate::transport::{Compression, ProtocolVersion};
 use crate::{
     batch::{Batch, BatchStatement},
     statement::StatementConfig,
@@ -144,6 +154,7 @@ pub struct Session {
     default_execution_profile_handle: ExecutionProfileHandle,
     schema_agreement_interval: Duration,
     metrics: Arc<Metrics>,
+    in_flight_limiter: Arc<InFlightLimiter>,
     schema_agreement_timeout: Duration,
     schema_agreement_automatic_waiting: bool,
     refresh_metadata_on_auto_schema_agreement: bool,
@@ -151,6 +162,15 @@ pub struct Session {
     tracing_info_fetch_attempts: NonZeroU32,
     tracing_info_fetch_interval: Duration,
     tracing_info_fetch_consistency: Consistency,
+    timestamp_generator: Option<Arc<dyn TimestampGenerator>>,
+    /// Statements to re-prepare on nodes which come up or join the cluster,
+    /// `None` if they aren't re-prepared proactively.
+    prepared_statement_registry: Option<Arc<PreparedStatementRegistry>>,
+    _repreparation_handle: Option<RemoteHandle<()>>,
+    /// Requests in progress, including the ones of `RowIterator` workers.
+    requests: TaskTracker,
+    /// Background tasks of the session, cancelled when it's shut down.
+    background_tasks: TaskTracker,
 }
 
 /// This implementation deliberately omits some details from Cluster in order
@@ -187,6 +207,12 @@ pub struct SessionConfig {
     /// Preferred compression algorithm to use on connections.
     /// If it's not supported by database server Session will fall back to no compression.
     pub compression: Option<Compression>,
+
+    /// The highest protocol version to use on connections.
+    /// If it's rejected by database server, Session will fall back to the highest
+    /// older version supported by the server.
+    pub protocol_version: ProtocolVersion,
+
     pub tcp_nodelay: bool,
     pub tcp_keepalive_interval: Option<Duration>,
 
@@ -293,12 +319,31 @@ pub struct SessionConfig {
     /// Driver and application self-identifying information,
     /// to be sent to server in STARTUP message.
     pub identity: SelfIdentity<'static>,
+
+    /// Limits of the number of requests in flight, per session, node and shard.
+    /// By default nothing is limited.
+    pub in_flight_limits: InFlightLimits,
+
+    /// Generator of timestamps of requests whose statements have no timestamp set,
+    /// used unless the execution profile of the request has its own generator.
+    /// By default no timestamps are generated, so the coordinators decide them.
+    pub timestamp_generator: Option<Arc<dyn TimestampGenerator>>,
+
+    /// If true, the statements prepared in the session are prepared in the background
+    /// on the nodes which come up or join the cluster, so that their first executions
+    /// on these nodes don't have to wait for re-preparing them.
+    pub reprepare_on_node_up: bool,
+
+    /// Maximal number of statements prepared at once on a node which came up or joined the cluster,
+    /// if [`Self::reprepare_on_node_up`] is enabled.
+    pub reprepare_on_node_up_concurrency: NonZeroUsize,
 }
 
 impl SessionConfig {
     /// Creates a [`SessionConfig`] with default configuration
     /// # Default configuration
     /// * Compression: None
+    /// * Protocol version: the latest one supported by the driver
     /// * Load balancing policy: Token-aware Round-robin
     ///
     /// # Example
@@ -310,6 +355,7 @@ impl SessionConfig {
         SessionConfig {
             known_nodes: Vec::new(),
             compression: None,
+            protocol_version: ProtocolVersion::LATEST,
             tcp_nodelay: true,
             tcp_keepalive_interval: None,
             schema_agreement_interval: Duration::from_millis(200),
@@ -340,6 +386,10 @@ impl SessionConfig {
             tracing_info_fetch_consistency: Consistency::One,
             cluster_metadata_refresh_interval: Duration::from_secs(60),
             identity: SelfIdentity::default(),
+            in_flight_limits: InFlightLimits::default(),
+            timestamp_generator: None,
+            reprepare_on_node_up: true,
+            reprepare_on_node_up_concurrency: NonZeroUsize::new(16).unwrap(),
         }
     }
 
@@ -502,8 +552,10 @@ impl Session {
         }
 
         let (tablet_sender, tablet_receiver) = tokio::sync::mpsc::channel(TABLET_CHANNEL_SIZE);
+        let background_tasks = TaskTracker::default();
 
         let connection_config = ConnectionConfig {
+            protocol_version: Arc::new(NegotiatedProtocolVersion::new(config.protocol_version)),
             compression: config.compression,
             tcp_nodelay: config.tcp_nodelay,
             tcp_keepalive_interval: config.tcp_keepalive_interval,
@@ -521,6 +573,7 @@ impl Session {
             keepalive_timeout: config.keepalive_timeout,
             tablet_sender: Some(tablet_sender),
             identity: config.identity,
+            task_tracker: background_tasks.clone(),
         };
 
         let pool_config = PoolConfig {
@@ -530,6 +583,12 @@ impl Session {
             keepalive_interval: config.keepalive_interval,
         };
 
+        let metrics = Arc::new(Metrics::new());
+        let in_flight_limiter = Arc::new(InFlightLimiter::new(
+            config.in_flight_limits,
+            metrics.clone(),
+        ));
+
         let cluster = Cluster::new(
             known_nodes,
             pool_config,
@@ -538,16 +597,32 @@ impl Session {
             config.host_filter,
             config.cluster_metadata_refresh_interval,
             tablet_receiver,
+            in_flight_limiter.clone(),
         )
         .await?;
 
         let default_execution_profile_handle = config.default_execution_profile_handle;
 
+        let (prepared_statement_registry, repreparation_handle) = if config.reprepare_on_node_up {
+            let registry = Arc::new(PreparedStatementRegistry::default());
+            let (fut, handle) = repreparation::reprepare_on_nodes_up(
+                registry.clone(),
+                cluster.subscribe_to_nodes_up(),
+                config.reprepare_on_node_up_concurrency,
+            )
+            .remote_handle();
+            tokio::spawn(background_tasks.track(fut));
+            (Some(registry), Some(handle))
+        } else {
+            (None, None)
+        };
+
         let session = Session {
             cluster,
             default_execution_profile_handle,
             schema_agreement_interval: config.schema_agreement_interval,
-            metrics: Arc::new(Metrics::new()),
+            metrics,
+            in_flight_limiter,
             schema_agreement_timeout: config.schema_agreement_timeout,
             schema_agreement_automatic_waiting: config.schema_agreement_automatic_waiting,
             refresh_metadata_on_auto_schema_agreement: config
@@ -556,6 +631,11 @@ impl Session {
             tracing_info_fetch_attempts: config.tracing_info_fetch_attempts,
             tracing_info_fetch_interval: config.tracing_info_fetch_interval,
             tracing_info_fetch_consistency: config.tracing_info_fetch_consistency,
+            timestamp_generator: config.timestamp_generator,
+            prepared_statement_registry,
+            _repreparation_handle: repreparation_handle,
+            requests: TaskTracker::default(),
+            background_tasks,
         };
 
         if let Some(keyspace_name) = config.used_keyspace {
@@ -600,19 +680,14 @@ impl Session {
     /// # use scylla::Session;
     /// # use std::error::Error;
     /// # async fn check_only_compiles(session: &Session) -> Result<(), Box<dyn Error>> {
-    /// use scylla::IntoTypedRows;
-    ///
     /// // Read rows containing an int and text
-    /// let rows_opt = session
-    /// .query("SELECT a, b FROM ks.tab", &[])
-    ///     .await?
-    ///     .rows;
-    ///
-    /// if let Some(rows) = rows_opt {
-    ///     for row in rows.into_typed::<(i32, String)>() {
-    ///         // Parse row as int and text \
-    ///         let (int_val, text_val): (i32, String) = row?;
-    ///     }
+    /// let result = session
+    ///     .query("SELECT a, b FROM ks.tab", &[])
+    ///     .await?;
+    ///
+    /// for row in result.rows::<(i32, &str)>()? {
+    ///     // Deserialize row as int and text
+    ///     let (int_val, text_val): (i32, &str) = row?;
     /// }
     /// # Ok(())
     /// # }
@@ -661,6 +736,8 @@ impl Session {
             ..Default::default()
         };
 
+        let timestamp = self.request_timestamp(query.get_timestamp(), &execution_profile);
+
         let span = RequestSpan::new_query(&query.contents);
         let span_ref = &span;
         let run_query_result = self
@@ -687,6 +764,7 @@ impl Session {
                                     query_ref,
                                     consistency,
                                     serial_consistency,
+                                    timestamp,
                                     paging_state_ref.clone(),
                                 )
                                 .await
@@ -701,6 +779,7 @@ impl Session {
                                     &serialized,
                                     consistency,
                                     serial_consistency,
+                                    timestamp,
                                     paging_state_ref.clone(),
                                 )
                                 .await
@@ -718,6 +797,7 @@ impl Session {
                 response: NonErrorResponse::Result(result::Result::Void),
                 tracing_id: None,
                 warnings: Vec::new(),
+                custom_payload: None,
             },
             RunQueryResult::Completed(response) => response,
         };
@@ -787,13 +867,12 @@ impl Session {
     /// # use scylla::Session;
     /// # use std::error::Error;
     /// # async fn check_only_compiles(session: &Session) -> Result<(), Box<dyn Error>> {
-    /// use scylla::IntoTypedRows;
     /// use futures::stream::StreamExt;
     ///
     /// let mut rows_stream = session
     ///    .query_iter("SELECT a, b FROM ks.t", &[])
     ///    .await?
-    ///    .into_typed::<(i32, i32)>();
+    ///    .rows_stream::<(i32, i32)>()?;
     ///
     /// while let Some(next_row_res) = rows_stream.next().await {
     ///     let (a, b): (i32, i32) = next_row_res?;
@@ -806,20 +885,66 @@ impl Session {
         &self,
         query: impl Into<Query>,
         values: impl SerializeRow,
+    ) -> Result<RowIterator, QueryError> {
+        self.query_iter_from_raw(query.into(), values, None).await
+    }
+
+    /// Resumes a paged query, started with [`Session::query_iter`], after the page
+    /// at which the given [`PagingState`] was saved.\
+    /// Fails if the paging state was produced by another statement.
+    ///
+    /// See [the book](https://rust-driver.docs.scylladb.com/stable/queries/paged.html) for more information
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use scylla::Session;
+    /// # use std::error::Error;
+    /// # async fn check_only_compiles(session: &Session, cursor: &[u8]) -> Result<(), Box<dyn Error>> {
+    /// use scylla::statement::paging_state::PagingState;
+    ///
+    /// let paging_state = PagingState::from_bytes(cursor)?;
+    /// let rows_stream = session
+    ///    .query_iter_from("SELECT a, b FROM ks.t", &[], &paging_state)
+    ///    .await?
+    ///    .rows_stream::<(i32, i32)>()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn query_iter_from(
+        &self,
+        query: impl Into<Query>,
+        values: impl SerializeRow,
+        paging_state: &PagingState,
     ) -> Result<RowIterator, QueryError> {
         let query: Query = query.into();
+        let paging_state = paging_state.raw_for(&query.contents)?;
+        self.query_iter_from_raw(query, values, Some(paging_state))
+            .await
+    }
 
+    async fn query_iter_from_raw(
+        &self,
+        mut query: Query,
+        values: impl SerializeRow,
+        paging_state: Option<Bytes>,
+    ) -> Result<RowIterator, QueryError> {
+        let request_guard = self.requests.enter()?;
         let execution_profile = query
             .get_execution_profile_handle()
             .unwrap_or_else(|| self.get_default_execution_profile_handle())
             .access();
+        query.set_timestamp(self.request_timestamp(query.get_timestamp(), &execution_profile));
 
         if values.is_empty() {
             RowIterator::new_for_query(
                 query,
+                paging_state,
                 execution_profile,
                 self.cluster.get_data(),
                 self.metrics.clone(),
+                self.in_flight_limiter.clone(),
+                request_guard,
             )
             .await
         } else {
@@ -828,13 +953,18 @@ impl Session {
             // we fully prepare a statement beforehand.
             let prepared = self.prepare(query).await?;
             let values = prepared.serialize_values(&values)?;
-            RowIterator::new_for_prepared_statement(PreparedIteratorConfig {
-                prepared,
-                values,
-                execution_profile,
-                cluster_data: self.cluster.get_data(),
-                metrics: self.metrics.clone(),
-            })
+            RowIterator::new_for_prepared_statement(
+                PreparedIteratorConfig {
+                    prepared,
+                    values,
+                    paging_state,
+                    execution_profile,
+                    cluster_data: self.cluster.get_data(),
+                    metrics: self.metrics.clone(),
+                    in_flight_limiter: self.in_flight_limiter.clone(),
+                },
+                request_guard,
+            )
             .await
         }
     }
@@ -851,6 +981,10 @@ impl Session {
     /// > must be sent as bound values
     /// > (see [performance section](https://rust-driver.docs.scylladb.com/stable/queries/prepared.html#performance))
     ///
+    /// As long as the returned statement or any of its clones is alive, it's also prepared
+    /// on the nodes which come up or join the cluster, unless
+    /// [`SessionConfig::reprepare_on_node_up`] is disabled.
+    ///
     /// See [the book](https://rust-driver.docs.scylladb.com/stable/queries/prepared.html) for more information.
     /// See the documentation of [`PreparedStatement`].
     ///
@@ -876,6 +1010,7 @@ impl Session {
     /// # }
     /// ```
     pub async fn prepare(&self, query: impl Into<Query>) -> Result<PreparedStatement, QueryError> {
+        let _request_guard = self.requests.enter()?;
         let query = query.into();
         let query_ref = &query;
 
@@ -915,6 +1050,10 @@ impl Session {
                 .unwrap_or_default(),
         );
 
+        if let Some(registry) = &self.prepared_statement_registry {
+            registry.register(&prepared);
+        }
+
         Ok(prepared)
     }
 
@@ -1035,6 +1174,8 @@ impl Session {
             }
         }
 
+        let timestamp = self.request_timestamp(prepared.get_timestamp(), &execution_profile);
+
         let run_query_result: RunQueryResult<NonErrorQueryResponse> = self
             .run_query(
                 statement_info,
@@ -1054,6 +1195,7 @@ impl Session {
                                 values_ref,
                                 consistency,
                                 serial_consistency,
+                                timestamp,
                                 paging_state_ref.clone(),
                             )
                             .await
@@ -1070,6 +1212,7 @@ impl Session {
                 response: NonErrorResponse::Result(result::Result::Void),
                 tracing_id: None,
                 warnings: Vec::new(),
+                custom_payload: None,
             },
             RunQueryResult::Completed(response) => response,
         };
@@ -1082,6 +1225,43 @@ impl Session {
         Ok(result)
     }
 
+    /// Executes a prepared lightweight transaction (a statement with an `IF` condition)
+    /// and reads its outcome.\
+    /// When the condition isn't met, the row returned by the database, without its `[applied]` column,
+    /// is deserialized as `R`. See [`QueryResult::lwt_outcome`] for details.
+    ///
+    /// See [the book](https://rust-driver.docs.scylladb.com/stable/queries/lwt.html) for more information
+    ///
+    /// # Example
+    /// ```rust
+    /// # use scylla::Session;
+    /// # use std::error::Error;
+    /// # async fn check_only_compiles(session: &Session) -> Result<(), Box<dyn Error>> {
+    /// use scylla::transport::query_result::LwtOutcome;
+    ///
+    /// let prepared = session
+    ///     .prepare("INSERT INTO ks.tab (a, b) VALUES(?, ?) IF NOT EXISTS")
+    ///     .await?;
+    ///
+    /// match session.execute_lwt::<(i32, i32)>(&prepared, (1, 2)).await? {
+    ///     LwtOutcome::Applied => println!("Inserted"),
+    ///     LwtOutcome::NotApplied((a, b)) => println!("Already exists: {}, {}", a, b),
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn execute_lwt<R>(
+        &self,
+        prepared: &PreparedStatement,
+        values: impl SerializeRow,
+    ) -> Result<LwtOutcome<R>, LwtError>
+    where
+        R: for<'frame> DeserializeRow<'frame>,
+    {
+        let result = self.execute(prepared, values).await?;
+        Ok(result.lwt_outcome_of(prepared.is_confirmed_lwt())?)
+    }
+
     /// Run a prepared query with paging\
     /// This method will query all pages of the result\
     ///
@@ -1102,7 +1282,6 @@ impl Session {
     /// # use std::error::Error;
     /// # async fn check_only_compiles(session: &Session) -> Result<(), Box<dyn Error>> {
     /// use scylla::prepared_statement::PreparedStatement;
-    /// use scylla::IntoTypedRows;
     /// use futures::stream::StreamExt;
     ///
     /// // Prepare the query for later execution
@@ -1114,7 +1293,7 @@ impl Session {
     /// let mut rows_stream = session
     ///    .execute_iter(prepared, &[])
     ///    .await?
-    ///    .into_typed::<(i32, i32)>();
+    ///    .rows_stream::<(i32, i32)>()?;
     ///
     /// while let Some(next_row_res) = rows_stream.next().await {
     ///     let (a, b): (i32, i32) = next_row_res?;
@@ -1127,22 +1306,77 @@ impl Session {
         &self,
         prepared: impl Into<PreparedStatement>,
         values: impl SerializeRow,
+    ) -> Result<RowIterator, QueryError> {
+        self.execute_iter_from_raw(prepared.into(), values, None)
+            .await
+    }
+
+    /// Resumes a paged execution of a prepared statement, started with [`Session::execute_iter`],
+    /// after the page at which the given [`PagingState`] was saved.\
+    /// Fails if the paging state was produced by another statement.
+    ///
+    /// See [the book](https://rust-driver.docs.scylladb.com/stable/queries/paged.html) for more information
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use scylla::Session;
+    /// # use std::error::Error;
+    /// # async fn check_only_compiles(session: &Session, cursor: &[u8]) -> Result<(), Box<dyn Error>> {
+    /// use scylla::prepared_statement::PreparedStatement;
+    /// use scylla::statement::paging_state::PagingState;
+    ///
+    /// let prepared: PreparedStatement = session
+    ///     .prepare("SELECT a, b FROM ks.t")
+    ///     .await?;
+    /// let paging_state = PagingState::from_bytes(cursor)?;
+    /// let rows_stream = session
+    ///    .execute_iter_from(prepared, &[], &paging_state)
+    ///    .await?
+    ///    .rows_stream::<(i32, i32)>()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn execute_iter_from(
+        &self,
+        prepared: impl Into<PreparedStatement>,
+        values: impl SerializeRow,
+        paging_state: &PagingState,
     ) -> Result<RowIterator, QueryError> {
         let prepared = prepared.into();
+        let paging_state = paging_state.raw_for(prepared.get_statement())?;
+        self.execute_iter_from_raw(prepared, values, Some(paging_state))
+            .await
+    }
+
+    async fn execute_iter_from_raw(
+        &self,
+        mut prepared: PreparedStatement,
+        values: impl SerializeRow,
+        paging_state: Option<Bytes>,
+    ) -> Result<RowIterator, QueryError> {
+        let request_guard = self.requests.enter()?;
         let serialized_values = prepared.serialize_values(&values)?;
 
         let execution_profile = prepared
             .get_execution_profile_handle()
             .unwrap_or_else(|| self.get_default_execution_profile_handle())
             .access();
+        prepared
+            .set_timestamp(self.request_timestamp(prepared.get_timestamp(), &execution_profile));
 
-        RowIterator::new_for_prepared_statement(PreparedIteratorConfig {
-            prepared,
-            values: serialized_values,
-            execution_profile,
-            cluster_data: self.cluster.get_data(),
-            metrics: self.metrics.clone(),
-        })
+        RowIterator::new_for_prepared_statement(
+            PreparedIteratorConfig {
+                prepared,
+                values: serialized_values,
+                paging_state,
+                execution_profile,
+                cluster_data: self.cluster.get_data(),
+                metrics: self.metrics.clone(),
+                in_flight_limiter: self.in_flight_limiter.clone(),
+            },
+            request_guard,
+        )
         .await
     }
 
@@ -1241,6 +1475,8 @@ impl Session {
             is_confirmed_lwt: false,
         };
 
+        let timestamp = self.request_timestamp(batch.get_timestamp(), &execution_profile);
+
         let span = RequestSpan::new_batch();
 
         let run_query_result = self
@@ -1262,6 +1498,7 @@ impl Session {
                                 values_ref,
                                 consistency,
                                 serial_consistency,
+                                timestamp,
                             )
                             .await
                     }
@@ -1279,6 +1516,24 @@ impl Session {
         Ok(result)
     }
 
+    /// Performs a batch with conditions and reads its outcome.\
+    /// When the conditions aren't met, all rows returned by the database are deserialized as `R`,
+    /// without their `[applied]` column. See [`QueryResult::lwt_outcome_rows`] for details.
+    pub async fn batch_lwt<R>(
+        &self,
+        batch: &Batch,
+        values: impl BatchValues,
+    ) -> Result<LwtOutcome<Vec<R>>, LwtError>
+    where
+        R: for<'frame> DeserializeRow<'frame>,
+    {
+        let result = self.batch(batch, values).await?;
+        let is_confirmed_lwt = batch.statements.iter().any(|statement| {
+            matches!(statement, BatchStatement::PreparedStatement(ps) if ps.is_confirmed_lwt())
+        });
+        Ok(result.lwt_outcome_rows_of(is_confirmed_lwt)?)
+    }
+
     /// Prepares all statements within the batch and returns a new batch where every
     /// statement is prepared.
     /// /// # Example
@@ -1367,6 +1622,7 @@ impl Session {
         keyspace_name: impl Into<String>,
         case_sensitive: bool,
     ) -> Result<(), QueryError> {
+        let _request_guard = self.requests.enter()?;
         let keyspace_name = keyspace_name.into();
         self.keyspace_name
             .store(Some(Arc::new(keyspace_name.clone())));
@@ -1387,6 +1643,7 @@ impl Session {
     /// Normally this is not needed,
     /// the driver should automatically detect all metadata changes in the cluster
     pub async fn refresh_metadata(&self) -> Result<(), QueryError> {
+        let _request_guard = self.requests.enter()?;
         self.cluster.refresh_metadata().await
     }
 
@@ -1404,6 +1661,12 @@ impl Session {
         self.cluster.get_data()
     }
 
+    pub(crate) fn subscribe_to_schema_changes(
+        &self,
+    ) -> tokio::sync::broadcast::Receiver<SchemaChangeEvent> {
+        self.cluster.subscribe_to_schema_changes()
+    }
+
     /// Get [`TracingInfo`] of a traced query performed earlier
     ///
     /// See [the book](https://rust-driver.docs.scylladb.com/stable/tracing/tracing.html)
@@ -1469,6 +1732,8 @@ impl Session {
 
         // Get tracing info
         let maybe_tracing_info: Option<TracingInfo> = traces_session_res
+            .into_legacy_result()
+            .map_err(ParseError::from)?
             .maybe_first_row_typed()
             .map_err(|err| match err {
                 MaybeFirstRowTypedError::RowsExpected(_) => QueryError::ProtocolError(
@@ -1485,9 +1750,13 @@ impl Session {
         };
 
         // Get tracing events
-        let tracing_event_rows = traces_events_res.rows_typed().map_err(|_| {
-            QueryError::ProtocolError("Response to system_traces.events query was not Rows")
-        })?;
+        let tracing_event_rows = traces_events_res
+            .into_legacy_result()
+            .map_err(ParseError::from)?
+            .rows_typed()
+            .map_err(|_| {
+                QueryError::ProtocolError("Response to system_traces.events query was not Rows")
+            })?;
 
         for event in tracing_event_rows {
             let tracing_event: TracingEvent = event.map_err(|_| {
@@ -1506,6 +1775,36 @@ impl Session {
         Ok(Some(tracing_info))
     }
 
+    /// Returns the timestamp to be sent with a request: the one set on its statement,
+    /// or else a new one from the timestamp generator of its execution profile
+    /// or of the session, if there is any.\
+    /// It's called once per request, so that its retries carry the same timestamp.
+    fn request_timestamp(
+        &self,
+        statement_timestamp: Option<i64>,
+        execution_profile: &ExecutionProfileInner,
+    ) -> Option<i64> {
+        statement_timestamp.or_else(|| {
+            execution_profile
+                .timestamp_generator
+                .as_ref()
+                .or(self.timestamp_generator.as_ref())
+                .map(|generator| generator.next_timestamp())
+        })
+    }
+
+    /// Returns a new timestamp from the timestamp generator of the given execution profile,
+    /// or of the default one if `None`, or else of the session, if there is any.
+    pub(crate) fn generate_timestamp(
+        &self,
+        execution_profile: Option<&ExecutionProfileHandle>,
+    ) -> Option<i64> {
+        let execution_profile = execution_profile
+            .unwrap_or_else(|| self.get_default_execution_profile_handle())
+            .access();
+        self.request_timestamp(None, &execution_profile)
+    }
+
     // This method allows to easily run a query using load balancing, retry policy etc.
     // Requires some information about the query and a closure.
     // The closure is used to do the query itself on a connection.
@@ -1527,6 +1826,7 @@ impl Session {
         QueryFut: Future<Output = Result<ResT, QueryError>>,
         ResT: AllowedRunQueryResTType,
     {
+        let _request_guard = self.requests.enter()?;
         let history_listener_and_id: Option<(&'a dyn HistoryListener, history::QueryId)> =
             statement_config
                 .history_listener
@@ -1535,6 +1835,11 @@ impl Session {
 
         let load_balancer = &execution_profile.load_balancing_policy;
 
+        let request_deadline = statement_config
+            .request_deadline
+            .or(execution_profile.request_deadline)
+            .map(RequestDeadline::new);
+
         let runner = async {
             let cluster_data = self.cluster.get_data();
             let query_plan =
@@ -1606,6 +1911,7 @@ impl Session {
                                 history_data,
                                 query_info: &statement_info,
                                 request_span,
+                                request_deadline: request_deadline.as_ref(),
                             },
                         )
                     };
@@ -1641,6 +1947,7 @@ impl Session {
                             history_data,
                             query_info: &statement_info,
                             request_span,
+                            request_deadline: request_deadline.as_ref(),
                         },
                     )
                     .await
@@ -1651,6 +1958,22 @@ impl Session {
             }
         };
 
+        // Waiting for the session in-flight limit counts towards the deadline and timeout.
+        let runner = async {
+            let _in_flight_permit = self
+                .in_flight_limiter
+                .acquire_for_request(execution_profile.in_flight_limit_behavior)
+                .await?;
+            runner.await
+        };
+
+        let runner = async {
+            match &request_deadline {
+                Some(deadline) => deadline.run(runner).await,
+                None => runner.await,
+            }
+        };
+
         let effective_timeout = statement_config
             .request_timeout
             .or(execution_profile.request_timeout);
@@ -1692,10 +2015,17 @@ impl Session {
         let mut current_consistency: Consistency = context
             .consistency_set_on_statement
             .unwrap_or(execution_profile.consistency);
+        let mut retry_delay: Option<Duration> = None;
 
         'nodes_in_plan: for (node, shard) in query_plan {
             let span = trace_span!("Executing query", node = %node.address);
             'same_node_retries: loop {
+                // Wait before retrying, if the retry policy asked for it. This happens
+                // only once the next attempt is known to take place.
+                if let Some(delay) = retry_delay.take() {
+                    trace!(parent: &span, delay = ?delay, "Delaying retry");
+                    tokio::time::sleep(delay).await;
+                }
                 trace!(parent: &span, "Execution started");
                 let connection = match node.connection_for_shard(shard).await {
                     Ok(connection) => connection,
@@ -1712,7 +2042,35 @@ impl Session {
                 };
                 context.request_span.record_shard_id(&connection);
 
+                let _in_flight_permit = match self
+                    .in_flight_limiter
+                    .acquire_for_attempt(
+                        node.host_id,
+                        connection
+                            .get_shard_info()
+                            .as_ref()
+                            .map(|info| info.shard.into()),
+                        execution_profile.in_flight_limit_behavior,
+                    )
+                    .await
+                {
+                    Ok(permit) => permit,
+                    Err(e) => {
+                        trace!(
+                            parent: &span,
+                            error = %e,
+                            "In-flight limit of the node reached"
+                        );
+                        last_error = Some(e);
+                        // The query wasn't sent, so it isn't counted as failed either
+                        continue 'nodes_in_plan;
+                    }
+                };
+
                 self.metrics.inc_total_nonpaged_queries();
+                if let Some(deadline) = context.request_deadline {
+                    deadline.inc_attempts();
+                }
                 let query_start = std::time::Instant::now();
 
                 trace!(
@@ -1758,6 +2116,14 @@ impl Session {
                 };
 
                 let the_error: &QueryError = last_error.as_ref().unwrap();
+                let remaining_budget = context.request_deadline.map(RequestDeadline::remaining);
+                if remaining_budget == Some(Duration::ZERO) {
+                    // There is no time left for retries.
+                    context.log_attempt_error(&attempt_id, the_error, &RetryDecision::DontRetry);
+                    let deadline = context.request_deadline.unwrap();
+                    return Some(Err(deadline.exceeded_error()));
+                }
+
                 // Use retry policy to decide what to do next
                 let query_info = QueryInfo {
                     error: the_error,
@@ -1765,6 +2131,7 @@ impl Session {
                     consistency: context
                         .consistency_set_on_statement
                         .unwrap_or(execution_profile.consistency),
+                    remaining_budget,
                 };
 
                 let retry_decision = context.retry_session.decide_should_retry(query_info);
@@ -1784,6 +2151,18 @@ impl Session {
                         current_consistency = new_cl.unwrap_or(current_consistency);
                         continue 'nodes_in_plan;
                     }
+                    RetryDecision::RetrySameNodeAfter(new_cl, delay) => {
+                        self.metrics.inc_retries_num();
+                        current_consistency = new_cl.unwrap_or(current_consistency);
+                        retry_delay = Some(delay);
+                        continue 'same_node_retries;
+                    }
+                    RetryDecision::RetryNextNodeAfter(new_cl, delay) => {
+                        self.metrics.inc_retries_num();
+                        current_consistency = new_cl.unwrap_or(current_consistency);
+                        retry_delay = Some(delay);
+                        continue 'nodes_in_plan;
+                    }
                     RetryDecision::DontRetry => break 'nodes_in_plan,
 
                     RetryDecision::IgnoreWriteError => {
@@ -1817,6 +2196,7 @@ impl Session {
     }
 
     pub async fn check_schema_agreement(&self) -> Result<Option<Uuid>, QueryError> {
+        let _request_guard = self.requests.enter()?;
         let cluster_data = self.get_cluster_data();
         let connections_iter = cluster_data.iter_working_connections()?;
 
@@ -1833,6 +2213,43 @@ impl Session {
     pub fn get_default_execution_profile_handle(&self) -> &ExecutionProfileHandle {
         &self.default_execution_profile_handle
     }
+
+    /// Shuts the session down gracefully.
+    ///
+    /// New requests are rejected with [`QueryError::SessionClosed`] from now on.
+    /// Requests in progress, including fetching pages by [`RowIterator`]s, are given
+    /// `grace_period` to finish, after which they fail. Then all connections are closed.
+    /// The returned future resolves once all background tasks of the session have stopped.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use scylla::Session;
+    /// # use std::error::Error;
+    /// # async fn check_only_compiles(session: &Session) -> Result<(), Box<dyn Error>> {
+    /// use std::time::Duration;
+    ///
+    /// session.shutdown(Duration::from_secs(10)).await;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn shutdown(&self, grace_period: Duration) {
+        self.requests.close();
+        if timeout(grace_period, self.requests.wait_until_idle())
+            .await
+            .is_err()
+        {
+            warn!("Requests still in progress after the shutdown grace period, cancelling them");
+        }
+
+        debug!("Closing connections and stopping background tasks of the session");
+        self.background_tasks.close();
+        self.background_tasks.wait_until_idle().await;
+    }
+
+    /// Returns true if [`Session::shutdown`] was called.
+    pub fn is_closed(&self) -> bool {
+        self.requests.is_closed()
+    }
 }
 
 // run_query, execute_query, etc have a template type called ResT.
@@ -1857,6 +2274,7 @@ struct ExecuteQueryContext<'a> {
     history_data: Option<HistoryData<'a>>,
     query_info: &'a load_balancing::RoutingInfo<'a>,
     request_span: &'a RequestSpan,
+    request_deadline: Option<&'a RequestDeadline>,
 }
 
 struct HistoryData<'a> {
@@ -1909,6 +2327,55 @@ impl<'a> ExecuteQueryContext<'a> {
     }
 }
 
+/// The overall deadline of a request, shared by all the attempts to execute it.
+pub(crate) struct RequestDeadline {
+    deadline: tokio::time::Instant,
+    duration: Duration,
+    attempts: AtomicUsize,
+}
+
+impl RequestDeadline {
+    pub(crate) fn new(duration: Duration) -> Self {
+        let now = tokio::time::Instant::now();
+        Self {
+            // Durations too long to be represented are treated as a deadline
+            // in the distant future, like in `tokio::time::sleep`.
+            deadline: now
+                .checked_add(duration)
+                .unwrap_or_else(|| now + Duration::from_secs(86400 * 365 * 30)),
+            duration,
+            attempts: 0.into(),
+        }
+    }
+
+    /// Returns the time left until the deadline, which is zero if it has already passed.
+    pub(crate) fn remaining(&self) -> Duration {
+        self.deadline
+            .saturating_duration_since(tokio::time::Instant::now())
+    }
+
+    pub(crate) fn inc_attempts(&self) {
+        self.attempts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn exceeded_error(&self) -> QueryError {
+        QueryError::RequestDeadlineExceeded {
+            deadline: self.duration,
+            attempts: self.attempts.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Awaits the given future, failing if it doesn't complete before the deadline.
+    pub(crate) async fn run<T>(
+        &self,
+        fut: impl Future<Output = Result<T, QueryError>>,
+    ) -> Result<T, QueryError> {
+        tokio::time::timeout_at(self.deadline, fut)
+            .await
+            .unwrap_or_else(|_| Err(self.exceeded_error()))
+    }
+}
+
 pub(crate) struct RequestSpan {
     span: tracing::Span,
     speculative_executions: AtomicUsize,
@@ -2005,14 +2472,14 @@ impl RequestSpan {
 
     pub(crate) fn record_result_fields(&self, result: &QueryResult) {
         self.span.record("result_size", result.serialized_size);
-        if let Some(rows) = result.rows.as_ref() {
-            self.span.record("result_rows", rows.len());
+        if let Ok(rows_num) = result.rows_num() {
+            self.span.record("result_rows", rows_num);
         }
     }
 
     pub(crate) fn record_rows_fields(&self, rows: &Rows) {
         self.span.record("result_size", rows.serialized_size);
-        self.span.record("result_rows", rows.rows.len());
+        self.span.record("result_rows", rows.rows_count);
     }
 
     pub(crate) fn record_replicas<'a>(&'a self, replicas: &'a [(impl Borrow<Arc<Node>>, Shard)]) {
@@ -2074,3 +2541,54 @@ fn partition_key_displayer<'ps, 'res>(
         }),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use assert_matches::assert_matches;
+
+    use super::RequestDeadline;
+    use crate::test_utils::setup_tracing;
+    use crate::transport::errors::QueryError;
+
+    #[tokio::test(start_paused = true)]
+    async fn request_deadline_is_shared_by_attempts() {
+        setup_tracing();
+        let deadline = RequestDeadline::new(Duration::from_millis(100));
+        assert_eq!(deadline.remaining(), Duration::from_millis(100));
+
+        // Attempts which complete in time are not affected.
+        deadline.inc_attempts();
+        let res = deadline
+            .run(async {
+                tokio::time::sleep(Duration::from_millis(60)).await;
+                Ok(())
+            })
+            .await;
+        assert_matches!(res, Ok(()));
+        assert_eq!(deadline.remaining(), Duration::from_millis(40));
+
+        // The next attempt only gets the remaining time.
+        deadline.inc_attempts();
+        let res = deadline
+            .run(async {
+                tokio::time::sleep(Duration::from_millis(60)).await;
+                Ok(())
+            })
+            .await;
+        let err = res.unwrap_err();
+        assert_matches!(
+            err,
+            QueryError::RequestDeadlineExceeded {
+                deadline,
+                attempts: 2
+            } if deadline == Duration::from_millis(100)
+        );
+        assert_eq!(
+            err.to_string(),
+            "Request deadline of 100ms exceeded after 2 attempts"
+        );
+        assert_eq!(deadline.remaining(), Duration::ZERO);
+    }
+}
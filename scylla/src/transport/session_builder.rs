@@ -4,7 +4,7 @@ use super::connection::SelfIdentity;
 use super::errors::NewSessionError;
 use super::execution_profile::ExecutionProfileHandle;
 use super::session::{AddressTranslator, Session, SessionConfig};
-use super::Compression;
+use super::{Compression, ProtocolVersion};
 
 #[cfg(feature = "cloud")]
 use crate::cloud::{CloudConfig, CloudConfigError};
@@ -14,10 +14,12 @@ use crate::ExecutionProfile;
 use crate::statement::Consistency;
 use crate::transport::connection_pool::PoolSize;
 use crate::transport::host_filter::HostFilter;
+use crate::transport::in_flight_limiter::InFlightLimits;
+use crate::transport::timestamp_generator::TimestampGenerator;
 use std::borrow::Borrow;
 use std::marker::PhantomData;
 use std::net::SocketAddr;
-use std::num::NonZeroU32;
+use std::num::{NonZeroU32, NonZeroUsize};
 #[cfg(feature = "cloud")]
 use std::path::Path;
 use std::sync::Arc;
@@ -389,6 +391,32 @@ impl<K: SessionBuilderKind> GenericSessionBuilder<K> {
         self
     }
 
+    /// Set the highest CQL protocol version to use on connections.
+    /// The default is the latest version supported by the driver.
+    /// If it is rejected by a node, Session will fall back to the highest
+    /// older version supported by that node, for connections to that node only.
+    ///
+    /// Since protocol v5, compression is applied to segments of frames,
+    /// which supports only LZ4. Snappy is ignored in this case.
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::{Session, SessionBuilder};
+    /// # use scylla::transport::ProtocolVersion;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("127.0.0.1:9042")
+    ///     .protocol_version(ProtocolVersion::V4)
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn protocol_version(mut self, protocol_version: ProtocolVersion) -> Self {
+        self.config.protocol_version = protocol_version;
+        self
+    }
+
     /// Set the delay for schema agreement check. How often driver should ask if schema is in agreement
     /// The default is 200 milliseconds.
     ///
@@ -975,6 +1003,109 @@ impl<K: SessionBuilderKind> GenericSessionBuilder<K> {
         self.config.identity = identity;
         self
     }
+
+    /// Set the limits of the number of requests in flight, per session, per node
+    /// and per shard, and of the number of requests waiting for them.
+    /// Whether requests above the limits wait or fail immediately is decided
+    /// by their [execution profile](crate::transport::ExecutionProfileBuilder::in_flight_limit_behavior).
+    ///
+    /// By default nothing is limited.
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::{Session, SessionBuilder};
+    /// # use scylla::transport::in_flight_limiter::InFlightLimits;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("127.0.0.1:9042")
+    ///     .in_flight_limits(
+    ///         InFlightLimits::new()
+    ///             .per_session(Some(4096))
+    ///             .per_shard(Some(256))
+    ///             .max_queued(Some(1024)),
+    ///     )
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn in_flight_limits(mut self, limits: InFlightLimits) -> Self {
+        self.config.in_flight_limits = limits;
+        self
+    }
+
+    /// Set the generator of timestamps of requests whose statements have no timestamp set.
+    /// The timestamp is generated once per request and stays the same across its retries.
+    /// An [execution profile](crate::transport::ExecutionProfileBuilder::timestamp_generator)
+    /// can have its own generator, used instead of this one.
+    ///
+    /// By default no timestamps are generated, so the coordinators decide them.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::sync::Arc;
+    /// # use scylla::{Session, SessionBuilder};
+    /// # use scylla::transport::timestamp_generator::MonotonicTimestampGenerator;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("127.0.0.1:9042")
+    ///     .timestamp_generator(Arc::new(MonotonicTimestampGenerator::new()))
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn timestamp_generator(mut self, generator: Arc<dyn TimestampGenerator>) -> Self {
+        self.config.timestamp_generator = Some(generator);
+        self
+    }
+
+    /// Set whether the statements prepared in the session should be prepared in the background
+    /// on the nodes which come up or join the cluster. A node which has just (re)started
+    /// doesn't know any prepared statements, so otherwise the first execution of each statement
+    /// on it has to wait for preparing the statement again.
+    ///
+    /// The default is true.
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::{Session, SessionBuilder};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("127.0.0.1:9042")
+    ///     .reprepare_on_node_up(false)
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn reprepare_on_node_up(mut self, enabled: bool) -> Self {
+        self.config.reprepare_on_node_up = enabled;
+        self
+    }
+
+    /// Set the maximal number of statements prepared at once on a node which came up
+    /// or joined the cluster, if [re-preparing](Self::reprepare_on_node_up) is enabled.
+    ///
+    /// The default is 16.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::num::NonZeroUsize;
+    /// # use scylla::{Session, SessionBuilder};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("127.0.0.1:9042")
+    ///     .reprepare_on_node_up_concurrency(NonZeroUsize::new(4).unwrap())
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn reprepare_on_node_up_concurrency(mut self, concurrency: NonZeroUsize) -> Self {
+        self.config.reprepare_on_node_up_concurrency = concurrency;
+        self
+    }
 }
 
 /// Creates a [`SessionBuilder`] with default configuration, same as [`SessionBuilder::new`]
@@ -992,9 +1123,13 @@ mod tests {
     use super::SessionBuilder;
     use crate::test_utils::setup_tracing;
     use crate::transport::execution_profile::{defaults, ExecutionProfile};
+    use crate::transport::in_flight_limiter::InFlightLimits;
     use crate::transport::node::KnownNode;
-    use crate::transport::Compression;
+    use crate::transport::timestamp_generator::MonotonicTimestampGenerator;
+    use crate::transport::{Compression, ProtocolVersion};
     use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+    use std::num::NonZeroUsize;
+    use std::sync::Arc;
     use std::time::Duration;
 
     #[test]
@@ -1004,6 +1139,7 @@ mod tests {
 
         assert!(builder.config.known_nodes.is_empty());
         assert_eq!(builder.config.compression, None);
+        assert_eq!(builder.config.protocol_version, ProtocolVersion::LATEST);
     }
 
     #[test]
@@ -1082,6 +1218,19 @@ mod tests {
         assert_eq!(builder.config.compression, None);
     }
 
+    #[test]
+    fn protocol_version() {
+        setup_tracing();
+        let mut builder = SessionBuilder::new();
+        assert_eq!(builder.config.protocol_version, ProtocolVersion::LATEST);
+
+        builder = builder.protocol_version(ProtocolVersion::V4);
+        assert_eq!(builder.config.protocol_version, ProtocolVersion::V4);
+
+        builder = builder.protocol_version(ProtocolVersion::V5);
+        assert_eq!(builder.config.protocol_version, ProtocolVersion::V5);
+    }
+
     #[test]
     fn tcp_nodelay() {
         setup_tracing();
@@ -1229,6 +1378,10 @@ mod tests {
         builder = builder.use_keyspace("ks_name", true);
         builder = builder.fetch_schema_metadata(false);
         builder = builder.cluster_metadata_refresh_interval(Duration::from_secs(1));
+        builder = builder.in_flight_limits(InFlightLimits::new().per_node(Some(128)));
+        builder = builder.timestamp_generator(Arc::new(MonotonicTimestampGenerator::new()));
+        builder = builder.reprepare_on_node_up(false);
+        builder = builder.reprepare_on_node_up_concurrency(NonZeroUsize::new(4).unwrap());
 
         assert_eq!(
             builder.config.known_nodes,
@@ -1253,6 +1406,13 @@ mod tests {
 
         assert!(builder.config.keyspace_case_sensitive);
         assert!(!builder.config.fetch_schema_metadata);
+        assert_eq!(
+            builder.config.in_flight_limits,
+            InFlightLimits::new().per_node(Some(128))
+        );
+        assert!(builder.config.timestamp_generator.is_some());
+        assert!(!builder.config.reprepare_on_node_up);
+        assert_eq!(builder.config.reprepare_on_node_up_concurrency.get(), 4);
     }
 
     // This is to assert that #705 does not break the API (i.e. it merely extends it).
@@ -5,14 +5,18 @@ use crate::prepared_statement::PreparedStatement;
 use crate::query::Query;
 use crate::retry_policy::{QueryInfo, RetryDecision, RetryPolicy, RetrySession};
 use crate::routing::Token;
+use crate::statement::paging_state::PagingState;
 use crate::statement::Consistency;
 use crate::test_utils::{scylla_supports_tablets, setup_tracing};
 use crate::tracing::TracingInfo;
 use crate::transport::cluster::Datacenter;
 use crate::transport::errors::{BadKeyspaceName, BadQuery, DbError, QueryError};
+use crate::transport::migrations::{Migration, MigrationError, Migrator};
 use crate::transport::partitioner::{
     calculate_token_for_partition_key, Murmur3Partitioner, Partitioner, PartitionerName,
 };
+use crate::transport::query_result::{LwtError, LwtOutcome, LwtResultError};
+use crate::transport::timestamp_generator::TimestampGenerator;
 use crate::transport::topology::Strategy::NetworkTopologyStrategy;
 use crate::transport::topology::{
     CollectionType, ColumnKind, CqlType, NativeType, UserDefinedType,
@@ -33,7 +37,7 @@ use scylla_cql::types::serialize::row::{SerializeRow, SerializedValues};
 use scylla_cql::types::serialize::value::SerializeValue;
 use std::collections::BTreeSet;
 use std::collections::{BTreeMap, HashMap};
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
 use std::sync::Arc;
 use tokio::net::TcpListener;
 use uuid::Uuid;
@@ -113,7 +117,7 @@ async fn test_unprepared_statement() {
     let (c_idx, _) = query_result.get_column_spec("c").unwrap();
     assert!(query_result.get_column_spec("d").is_none());
 
-    let rs = query_result.rows.unwrap();
+    let rs = query_result.into_legacy_result().unwrap().rows.unwrap();
 
     let mut results: Vec<(i32, i32, &String)> = rs
         .iter()
@@ -151,6 +155,8 @@ async fn test_unprepared_statement() {
         let rs_manual = session
             .query_paged(query.clone(), &[], paging_state)
             .await
+            .unwrap()
+            .into_legacy_result()
             .unwrap();
         results_from_manual_paging.append(&mut rs_manual.rows.unwrap());
         if watchdog > 30 || rs_manual.paging_state.is_none() {
@@ -229,6 +235,8 @@ async fn test_prepared_statement() {
             .query(format!("SELECT token(a) FROM {}.t2", ks), &[])
             .await
             .unwrap()
+            .into_legacy_result()
+            .unwrap()
             .single_row_typed()
             .unwrap();
         let token = Token::new(value);
@@ -248,6 +256,8 @@ async fn test_prepared_statement() {
             .query(format!("SELECT token(a,b,c) FROM {}.complex_pk", ks), &[])
             .await
             .unwrap()
+            .into_legacy_result()
+            .unwrap()
             .single_row_typed()
             .unwrap();
         let token = Token::new(value);
@@ -270,6 +280,8 @@ async fn test_prepared_statement() {
             .query(format!("SELECT a,b,c FROM {}.t2", ks), &[])
             .await
             .unwrap()
+            .into_legacy_result()
+            .unwrap()
             .rows
             .unwrap();
         let r = rs.first().unwrap();
@@ -287,6 +299,8 @@ async fn test_prepared_statement() {
             let rs_manual = session
                 .execute_paged(&prepared_paged, &[], paging_state)
                 .await
+                .unwrap()
+                .into_legacy_result()
                 .unwrap();
             results_from_manual_paging.append(&mut rs_manual.rows.unwrap());
             if watchdog > 30 || rs_manual.paging_state.is_none() {
@@ -302,6 +316,8 @@ async fn test_prepared_statement() {
             .query(format!("SELECT a,b,c,d,e FROM {}.complex_pk", ks), &[])
             .await
             .unwrap()
+            .into_legacy_result()
+            .unwrap()
             .single_row_typed()
             .unwrap();
         assert!(e.is_none());
@@ -348,12 +364,103 @@ async fn test_prepared_statement() {
             )
             .await
             .unwrap()
+            .into_legacy_result()
+            .unwrap()
             .single_row_typed()
             .unwrap();
         assert_eq!(input, output)
     }
 }
 
+#[tokio::test]
+async fn test_borrowed_rows() {
+    setup_tracing();
+    let session = create_new_session_builder().build().await.unwrap();
+    let ks = unique_keyspace_name();
+
+    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session
+        .query(
+            format!(
+                "CREATE TABLE IF NOT EXISTS {}.t (a int primary key, b text, c blob)",
+                ks
+            ),
+            &[],
+        )
+        .await
+        .unwrap();
+    for a in 0..3_i32 {
+        session
+            .query(
+                format!("INSERT INTO {}.t (a, b, c) VALUES (?, ?, ?)", ks),
+                (a, format!("text{}", a), vec![a as u8; 3]),
+            )
+            .await
+            .unwrap();
+    }
+
+    let result = session
+        .query(format!("SELECT a, b, c FROM {}.t", ks), &[])
+        .await
+        .unwrap();
+    assert_eq!(result.rows_num(), Ok(3));
+    let mut borrowed_rows = result
+        .rows::<(i32, &str, &[u8])>()
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    borrowed_rows.sort();
+    let expected_texts = ["text0", "text1", "text2"];
+    for (a, b, c) in borrowed_rows {
+        assert_eq!(b, expected_texts[a as usize]);
+        assert_eq!(c, &[a as u8; 3]);
+    }
+
+    // Mismatched types are reported before any row is deserialized
+    assert_matches!(
+        result.rows::<(i32, i32, &[u8])>().err(),
+        Some(crate::transport::query_result::RowsError::TypeCheckFailed(
+            _
+        ))
+    );
+
+    let single = session
+        .query(format!("SELECT b FROM {}.t WHERE a = 1", ks), &[])
+        .await
+        .unwrap();
+    let (b,) = single.single_row::<(&str,)>().unwrap();
+    assert_eq!(b, "text1");
+
+    let query = Query::new(format!("SELECT a, b, c FROM {}.t", ks)).with_page_size(1);
+    let mut streamed_rows = session
+        .query_iter(query, &[])
+        .await
+        .unwrap()
+        .rows_stream::<(i32, String, Vec<u8>)>()
+        .unwrap()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    streamed_rows.sort();
+    assert_eq!(
+        streamed_rows,
+        (0..3_i32)
+            .map(|a| (a, format!("text{}", a), vec![a as u8; 3]))
+            .collect::<Vec<_>>()
+    );
+
+    let query = Query::new(format!("SELECT a, b, c FROM {}.t", ks)).with_page_size(1);
+    let mut row_iterator = session.query_iter(query, &[]).await.unwrap();
+    let mut borrowed_rows_num = 0;
+    while let Some(row) = row_iterator.next_row::<(i32, &str, &[u8])>().await {
+        let (a, b, c) = row.unwrap();
+        assert_eq!(b, format!("text{}", a));
+        assert_eq!(c, &[a as u8; 3]);
+        borrowed_rows_num += 1;
+    }
+    assert_eq!(borrowed_rows_num, 3);
+}
+
 #[tokio::test]
 async fn test_counter_batch() {
     use crate::frame::value::Counter;
@@ -462,6 +569,8 @@ async fn test_batch() {
         .query(format!("SELECT a, b, c FROM {}.t_batch", ks), &[])
         .await
         .unwrap()
+        .into_legacy_result()
+        .unwrap()
         .rows_typed()
         .unwrap()
         .collect::<Result<_, _>>()
@@ -499,6 +608,8 @@ async fn test_batch() {
         )
         .await
         .unwrap()
+        .into_legacy_result()
+        .unwrap()
         .rows_typed()
         .unwrap()
         .collect::<Result<_, _>>()
@@ -549,6 +660,8 @@ async fn test_token_calculation() {
             )
             .await
             .unwrap()
+            .into_legacy_result()
+            .unwrap()
             .single_row_typed()
             .unwrap();
         let token = Token::new(value);
@@ -654,6 +767,8 @@ async fn test_use_keyspace() {
         .query("SELECT * FROM tab", &[])
         .await
         .unwrap()
+        .into_legacy_result()
+        .unwrap()
         .rows_typed::<(String,)>()
         .unwrap()
         .map(|res| res.unwrap().0)
@@ -703,6 +818,8 @@ async fn test_use_keyspace() {
         .query("SELECT * FROM tab", &[])
         .await
         .unwrap()
+        .into_legacy_result()
+        .unwrap()
         .rows_typed::<(String,)>()
         .unwrap()
         .map(|res| res.unwrap().0)
@@ -763,6 +880,8 @@ async fn test_use_keyspace_case_sensitivity() {
         .query("SELECT * from tab", &[])
         .await
         .unwrap()
+        .into_legacy_result()
+        .unwrap()
         .rows_typed::<(String,)>()
         .unwrap()
         .map(|row| row.unwrap().0)
@@ -778,6 +897,8 @@ async fn test_use_keyspace_case_sensitivity() {
         .query("SELECT * from tab", &[])
         .await
         .unwrap()
+        .into_legacy_result()
+        .unwrap()
         .rows_typed::<(String,)>()
         .unwrap()
         .map(|row| row.unwrap().0)
@@ -819,6 +940,8 @@ async fn test_raw_use_keyspace() {
         .query("SELECT * FROM tab", &[])
         .await
         .unwrap()
+        .into_legacy_result()
+        .unwrap()
         .rows_typed::<(String,)>()
         .unwrap()
         .map(|res| res.unwrap().0)
@@ -1245,6 +1368,8 @@ async fn test_timestamp() {
         )
         .await
         .unwrap()
+        .into_legacy_result()
+        .unwrap()
         .rows_typed::<(String, String, i64)>()
         .unwrap()
         .map(Result::unwrap)
@@ -1264,6 +1389,87 @@ async fn test_timestamp() {
     assert_eq!(results, expected_results);
 }
 
+#[tokio::test]
+async fn test_timestamp_generator() {
+    setup_tracing();
+
+    #[derive(Debug)]
+    struct CountingGenerator(AtomicI64);
+
+    impl TimestampGenerator for CountingGenerator {
+        fn next_timestamp(&self) -> i64 {
+            self.0.fetch_add(1, Ordering::Relaxed)
+        }
+    }
+
+    let session = create_new_session_builder()
+        .timestamp_generator(Arc::new(CountingGenerator(AtomicI64::new(1000))))
+        .build()
+        .await
+        .unwrap();
+    let ks = unique_keyspace_name();
+
+    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session
+        .query(
+            format!(
+                "CREATE TABLE IF NOT EXISTS {}.t_timestamp_generator (a text primary key, b text)",
+                ks
+            ),
+            &[],
+        )
+        .await
+        .unwrap();
+    session.await_schema_agreement().await.unwrap();
+
+    let insert_str = format!(
+        "INSERT INTO {}.t_timestamp_generator (a, b) VALUES (?, ?)",
+        ks
+    );
+    let prepared = session.prepare(insert_str.as_str()).await.unwrap();
+    let mut explicit = prepared.clone();
+    explicit.set_timestamp(Some(42));
+    let mut with_profile = prepared.clone();
+    with_profile.set_execution_profile_handle(Some(
+        ExecutionProfile::builder()
+            .timestamp_generator(Some(Arc::new(CountingGenerator(AtomicI64::new(5000)))))
+            .build()
+            .into_handle(),
+    ));
+    let mut batch = Batch::default();
+    batch.append_statement(prepared.clone());
+    batch.append_statement(prepared.clone());
+
+    session.execute(&explicit, ("explicit", "")).await.unwrap();
+    session
+        .execute(&with_profile, ("profile", ""))
+        .await
+        .unwrap();
+    session
+        .batch(&batch, (("batch 1", ""), ("batch 2", "")))
+        .await
+        .unwrap();
+
+    let result = session
+        .query(
+            format!("SELECT a, WRITETIME(b) FROM {}.t_timestamp_generator", ks),
+            &[],
+        )
+        .await
+        .unwrap();
+    let rows = result
+        .rows::<(String, i64)>()
+        .unwrap()
+        .collect::<Result<HashMap<_, _>, _>>()
+        .unwrap();
+
+    assert_eq!(rows["explicit"], 42);
+    assert_eq!(rows["profile"], 5000);
+    // Both statements of a batch are written with the timestamp of the batch.
+    assert_eq!(rows["batch 1"], rows["batch 2"]);
+    assert!(rows["batch 1"] > 1000 && rows["batch 1"] < 5000);
+}
+
 #[ignore = "works on remote Scylla instances only (local ones are too fast)"]
 #[tokio::test]
 async fn test_request_timeout() {
@@ -1877,6 +2083,8 @@ async fn test_named_bind_markers() {
         .query("SELECT pk, ck, v FROM t", &[])
         .await
         .unwrap()
+        .into_legacy_result()
+        .unwrap()
         .rows_typed::<(i32, i32, i32)>()
         .unwrap()
         .map(|res| res.unwrap())
@@ -2016,6 +2224,8 @@ async fn test_unprepared_reprepare_in_execute() {
         .query("SELECT a, b, c FROM tab", ())
         .await
         .unwrap()
+        .into_legacy_result()
+        .unwrap()
         .rows_typed::<(i32, i32, i32)>()
         .unwrap()
         .map(|r| r.unwrap())
@@ -2068,6 +2278,8 @@ async fn test_unusual_valuelists() {
         .query("SELECT a, b, c FROM tab", ())
         .await
         .unwrap()
+        .into_legacy_result()
+        .unwrap()
         .rows_typed::<(i32, i32, String)>()
         .unwrap()
         .map(|r| r.unwrap())
@@ -2139,6 +2351,8 @@ async fn test_unprepared_reprepare_in_batch() {
         .query("SELECT a, b, c FROM tab", ())
         .await
         .unwrap()
+        .into_legacy_result()
+        .unwrap()
         .rows_typed::<(i32, i32, i32)>()
         .unwrap()
         .map(|r| r.unwrap())
@@ -2206,6 +2420,8 @@ async fn test_unprepared_reprepare_in_caching_session_execute() {
         .execute("SELECT a, b, c FROM tab", &())
         .await
         .unwrap()
+        .into_legacy_result()
+        .unwrap()
         .rows_typed::<(i32, i32, i32)>()
         .unwrap()
         .map(|r| r.unwrap())
@@ -2273,6 +2489,8 @@ async fn assert_test_batch_table_rows_contain(sess: &Session, expected_rows: &[(
         .query("SELECT a, b FROM test_batch_table", ())
         .await
         .unwrap()
+        .into_legacy_result()
+        .unwrap()
         .rows_typed::<(i32, i32)>()
         .unwrap()
         .map(|r| r.unwrap())
@@ -2519,6 +2737,8 @@ async fn test_batch_lwts_for_scylla(session: &Session, batch: &Batch, batch_res:
     // Returned columns are:
     // [applied], p1, c1, r1, r2
     let batch_res_rows: Vec<(bool, IntOrNull, IntOrNull, IntOrNull, IntOrNull)> = batch_res
+        .into_legacy_result()
+        .unwrap()
         .rows_typed()
         .unwrap()
         .map(|r| r.unwrap())
@@ -2538,6 +2758,8 @@ async fn test_batch_lwts_for_scylla(session: &Session, batch: &Batch, batch_res:
 
     let prepared_batch_res_rows: Vec<(bool, IntOrNull, IntOrNull, IntOrNull, IntOrNull)> =
         prepared_batch_res
+            .into_legacy_result()
+            .unwrap()
             .rows_typed()
             .unwrap()
             .map(|r| r.unwrap())
@@ -2559,6 +2781,8 @@ async fn test_batch_lwts_for_cassandra(session: &Session, batch: &Batch, batch_r
     // Returned columns are:
     // [applied]
     let batch_res_rows: Vec<(bool,)> = batch_res
+        .into_legacy_result()
+        .unwrap()
         .rows_typed()
         .unwrap()
         .map(|r| r.unwrap())
@@ -2576,6 +2800,8 @@ async fn test_batch_lwts_for_cassandra(session: &Session, batch: &Batch, batch_r
     // [applied], p1, c1, r1, r2
     let prepared_batch_res_rows: Vec<(bool, IntOrNull, IntOrNull, IntOrNull, IntOrNull)> =
         prepared_batch_res
+            .into_legacy_result()
+            .unwrap()
             .rows_typed()
             .unwrap()
             .map(|r| r.unwrap())
@@ -2741,6 +2967,263 @@ async fn test_iter_methods_with_modification_statements() {
     row_iterator.next().await.ok_or(()).unwrap_err(); // assert empty
 }
 
+#[tokio::test]
+async fn test_iter_resumed_from_paging_state() {
+    setup_tracing();
+    let session = create_new_session_builder().build().await.unwrap();
+    let ks = unique_keyspace_name();
+
+    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session
+        .query(
+            format!(
+                "CREATE TABLE IF NOT EXISTS {}.t (a int, b int, primary key (a, b))",
+                ks
+            ),
+            &[],
+        )
+        .await
+        .unwrap();
+    for b in 0..10 {
+        session
+            .query(format!("INSERT INTO {}.t (a, b) VALUES (1, ?)", ks), (b,))
+            .await
+            .unwrap();
+    }
+
+    let mut prepared = session
+        .prepare(format!("SELECT b FROM {}.t WHERE a = ?", ks))
+        .await
+        .unwrap();
+    prepared.set_page_size(4);
+
+    // Read the first page and save its paging state, as a cursor handed to a client.
+    let mut pages = session
+        .execute_iter(prepared.clone(), (1,))
+        .await
+        .unwrap()
+        .pages_stream::<(i32,)>()
+        .unwrap();
+    let (rows, paging_state) = pages.next().await.unwrap().unwrap();
+    assert_eq!(rows, [(0,), (1,), (2,), (3,)]);
+    let cursor = paging_state.unwrap().to_bytes();
+    drop(pages);
+
+    let paging_state = PagingState::from_bytes(&cursor).unwrap();
+    let pages: Vec<_> = session
+        .execute_iter_from(prepared, (1,), &paging_state)
+        .await
+        .unwrap()
+        .pages_stream::<(i32,)>()
+        .unwrap()
+        .try_collect()
+        .await
+        .unwrap();
+    let rows: Vec<i32> = pages
+        .iter()
+        .flat_map(|(rows, _)| rows.iter().map(|(b,)| *b))
+        .collect();
+    assert_eq!(rows, (4..10).collect::<Vec<_>>());
+    assert!(pages.last().unwrap().1.is_none());
+
+    // Paging states can't be used with other statements.
+    let other_query = format!("SELECT a, b FROM {}.t WHERE a = ?", ks);
+    assert_matches!(
+        session
+            .query_iter_from(other_query, (1,), &paging_state)
+            .await
+            .map(|_| ()),
+        Err(QueryError::BadQuery(BadQuery::Other(_)))
+    );
+}
+
+#[tokio::test]
+async fn test_lwt_outcome() {
+    setup_tracing();
+    let session = create_new_session_builder().build().await.unwrap();
+    let ks = unique_keyspace_name();
+
+    // LWT is not yet compatible with Scylla's tablets.
+    let mut create_ks = format!(
+        "CREATE KEYSPACE IF NOT EXISTS {ks} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}");
+    if scylla_supports_tablets(&session).await {
+        create_ks += " AND TABLETS = {'enabled': false}"
+    }
+
+    session.query(create_ks, &[]).await.unwrap();
+    session.use_keyspace(ks, false).await.unwrap();
+    session
+        .query(
+            "CREATE TABLE IF NOT EXISTS t (a int, b int, c int, primary key (a, b))",
+            &[],
+        )
+        .await
+        .unwrap();
+
+    let insert = session
+        .prepare("INSERT INTO t (a, b, c) VALUES (?, ?, ?) IF NOT EXISTS")
+        .await
+        .unwrap();
+    let outcome = session
+        .execute_lwt::<(i32, i32, i32)>(&insert, (1, 1, 10))
+        .await
+        .unwrap();
+    assert_eq!(outcome, LwtOutcome::Applied);
+    session.execute(&insert, (1, 2, 20)).await.unwrap();
+
+    // The existing row is returned when the condition isn't met.
+    let outcome = session
+        .execute_lwt::<(i32, i32, i32)>(&insert, (1, 1, 11))
+        .await
+        .unwrap();
+    assert_eq!(outcome, LwtOutcome::NotApplied((1, 1, 10)));
+
+    let update = session
+        .prepare("UPDATE t SET c = ? WHERE a = ? AND b = ? IF c = ?")
+        .await
+        .unwrap();
+    let mut batch = Batch::default();
+    batch.append_statement(update.clone());
+    batch.append_statement(update);
+
+    let outcome = session
+        .batch_lwt::<(i32, i32, i32)>(&batch, ((11, 1, 1, 10), (21, 1, 2, 20)))
+        .await
+        .unwrap();
+    assert_eq!(outcome, LwtOutcome::Applied);
+
+    // None of the conditions is met now, the batch returns the current values of both rows.
+    let outcome = session
+        .batch_lwt::<(i32, i32, i32)>(&batch, ((12, 1, 1, 10), (22, 1, 2, 20)))
+        .await
+        .unwrap();
+    let LwtOutcome::NotApplied(mut rows) = outcome else {
+        panic!("Expected the batch not to be applied");
+    };
+    rows.sort();
+    assert_eq!(rows, vec![(1, 1, 11), (1, 2, 21)]);
+
+    // Statements without conditions don't return LWT results.
+    let plain_insert = session
+        .prepare("INSERT INTO t (a, b, c) VALUES (?, ?, ?)")
+        .await
+        .unwrap();
+    assert_matches!(
+        session
+            .execute_lwt::<(i32, i32, i32)>(&plain_insert, (2, 1, 1))
+            .await,
+        Err(LwtError::Result(LwtResultError::RowsExpected(_)))
+    );
+}
+
+#[tokio::test]
+async fn test_migrations() {
+    setup_tracing();
+    use std::time::Duration;
+
+    let session = create_new_session_builder().build().await.unwrap();
+    let ks = unique_keyspace_name();
+
+    // LWT is not yet compatible with Scylla's tablets.
+    let mut create_ks = format!(
+        "CREATE KEYSPACE IF NOT EXISTS {ks} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}");
+    if scylla_supports_tablets(&session).await {
+        create_ks += " AND TABLETS = {'enabled': false}"
+    }
+    session.query(create_ks, &[]).await.unwrap();
+
+    let first = Migration::new(
+        1,
+        "users",
+        &format!(
+            "CREATE TABLE {ks}.users (id int PRIMARY KEY, name text);
+            INSERT INTO {ks}.users (id, name) VALUES (0, 'admin;root');"
+        ),
+    );
+    let second = Migration::new(
+        2,
+        "email",
+        &format!("ALTER TABLE {ks}.users ADD email text;"),
+    );
+
+    let applied = Migrator::new(&session, &ks)
+        .migrations([first.clone()])
+        .run()
+        .await
+        .unwrap();
+    assert_eq!(applied, vec![1]);
+
+    // Already applied migrations are skipped.
+    let applied = Migrator::new(&session, &ks)
+        .migrations([first.clone(), second.clone()])
+        .run()
+        .await
+        .unwrap();
+    assert_eq!(applied, vec![2]);
+    let applied = Migrator::new(&session, &ks)
+        .migrations([first.clone(), second.clone()])
+        .run()
+        .await
+        .unwrap();
+    assert!(applied.is_empty());
+
+    let recorded = Migrator::new(&session, &ks).applied().await.unwrap();
+    assert_eq!(
+        recorded
+            .iter()
+            .map(|applied| (applied.version, applied.checksum))
+            .collect::<Vec<_>>(),
+        vec![(1, first.checksum()), (2, second.checksum())]
+    );
+    let (name, email) = session
+        .query(
+            format!("SELECT name, email FROM {ks}.users WHERE id = 0"),
+            (),
+        )
+        .await
+        .unwrap()
+        .single_row::<(String, Option<String>)>()
+        .unwrap();
+    assert_eq!((name.as_str(), email), ("admin;root", None));
+
+    // A changed migration stops the migrator before anything is applied.
+    let changed = Migration::new(
+        2,
+        "email",
+        &format!("ALTER TABLE {ks}.users ADD email varchar;"),
+    );
+    let third = Migration::new(3, "age", &format!("ALTER TABLE {ks}.users ADD age int;"));
+    assert_matches!(
+        Migrator::new(&session, &ks)
+            .migrations([first.clone(), changed, third.clone()])
+            .run()
+            .await,
+        Err(MigrationError::ChecksumMismatch { version: 2, .. })
+    );
+
+    // The lock of a running migrator keeps others waiting.
+    session
+        .query(
+            format!("INSERT INTO {ks}.schema_migrations_lock (id, owner) VALUES ('lock', uuid())"),
+            (),
+        )
+        .await
+        .unwrap();
+    assert_matches!(
+        Migrator::new(&session, &ks)
+            .migrations([first, second, third])
+            .lock_timeout(Duration::from_millis(100))
+            .lock_retry_interval(Duration::from_millis(50))
+            .run()
+            .await,
+        Err(MigrationError::LockTimeout)
+    );
+    assert_eq!(
+        Migrator::new(&session, &ks).applied().await.unwrap().len(),
+        2
+    );
+}
+
 #[tokio::test]
 async fn test_get_keyspace_name() {
     let ks = unique_keyspace_name();
@@ -2824,6 +3307,8 @@ async fn simple_strategy_test() {
         .query(format!("SELECT p, c, r FROM {}.tab", ks), ())
         .await
         .unwrap()
+        .into_legacy_result()
+        .unwrap()
         .rows_typed::<(i32, i32, i32)>()
         .unwrap()
         .map(|r| r.unwrap())
@@ -2936,3 +3421,35 @@ async fn test_manual_primary_key_computation() {
         .await;
     }
 }
+
+#[tokio::test]
+async fn test_shutdown() {
+    use std::time::Duration;
+
+    setup_tracing();
+    let session = create_new_session_builder().build().await.unwrap();
+
+    // A page iterator started before the shutdown holds it for the grace period.
+    let mut query = Query::new("SELECT keyspace_name FROM system_schema.keyspaces");
+    query.set_page_size(1);
+    let mut iter = session.query_iter(query, &[]).await.unwrap();
+    iter.next().await.unwrap().unwrap();
+
+    let shutdown = tokio::time::timeout(
+        Duration::from_secs(30),
+        session.shutdown(Duration::from_millis(200)),
+    );
+    shutdown.await.unwrap();
+    assert!(session.is_closed());
+
+    assert_matches!(
+        session.query("SELECT * FROM system.local", &[]).await,
+        Err(QueryError::SessionClosed)
+    );
+    assert_matches!(
+        session.prepare("SELECT * FROM system.local").await,
+        Err(QueryError::SessionClosed)
+    );
+    // The iterator fails to fetch the following pages, as the connections are closed.
+    assert!(iter.try_collect::<Vec<_>>().await.is_err());
+}
@@ -96,6 +96,8 @@ async fn assert_test_batch_table_rows_contain(sess: &Session, expected_rows: &[(
         .query("SELECT a, b FROM test_batch_table", ())
         .await
         .unwrap()
+        .into_legacy_result()
+        .unwrap()
         .rows_typed::<(i32, i32)>()
         .unwrap()
         .map(|r| r.unwrap())
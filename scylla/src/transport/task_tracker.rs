@@ -0,0 +1,143 @@
+//! Tracking of the activities of a session - requests and background tasks -
+//! so that shutting the session down can wait until they're done.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use tokio::sync::{watch, Notify};
+
+use crate::transport::errors::QueryError;
+
+/// Counts the activities which are in progress, and stops new ones from starting once closed.
+#[derive(Debug, Clone)]
+pub(crate) struct TaskTracker {
+    inner: Arc<TrackerInner>,
+}
+
+#[derive(Debug)]
+struct TrackerInner {
+    active: AtomicUsize,
+    idle_notify: Notify,
+    closed: watch::Sender<bool>,
+}
+
+/// Marks an activity as in progress until dropped.
+#[derive(Debug)]
+pub(crate) struct ActivityGuard {
+    inner: Arc<TrackerInner>,
+}
+
+impl Drop for ActivityGuard {
+    fn drop(&mut self) {
+        if self.inner.active.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.inner.idle_notify.notify_waiters();
+        }
+    }
+}
+
+impl Default for TaskTracker {
+    fn default() -> Self {
+        Self {
+            inner: Arc::new(TrackerInner {
+                active: AtomicUsize::new(0),
+                idle_notify: Notify::new(),
+                closed: watch::channel(false).0,
+            }),
+        }
+    }
+}
+
+impl TaskTracker {
+    fn guard(&self) -> ActivityGuard {
+        self.inner.active.fetch_add(1, Ordering::AcqRel);
+        ActivityGuard {
+            inner: self.inner.clone(),
+        }
+    }
+
+    /// Starts an activity, unless the tracker is closed.
+    pub(crate) fn enter(&self) -> Result<ActivityGuard, QueryError> {
+        // The guard is taken before checking whether the tracker is closed,
+        // so that `wait_until_idle` called after `close` can't miss it.
+        let guard = self.guard();
+        if self.is_closed() {
+            return Err(QueryError::SessionClosed);
+        }
+        Ok(guard)
+    }
+
+    /// Wraps a background task, so that it's tracked until it completes,
+    /// and is cancelled once the tracker is closed.
+    pub(crate) fn track(
+        &self,
+        task: impl Future<Output = ()> + Send + 'static,
+    ) -> impl Future<Output = ()> + Send + 'static {
+        let guard = self.guard();
+        let mut closed = self.inner.closed.subscribe();
+        async move {
+            let _guard = guard;
+            tokio::select! {
+                _ = task => {}
+                // The sender lives as long as the guard, so this can't fail.
+                _ = closed.wait_for(|closed| *closed) => {}
+            }
+        }
+    }
+
+    pub(crate) fn is_closed(&self) -> bool {
+        *self.inner.closed.borrow()
+    }
+
+    /// Stops new activities from starting and cancels the tracked background tasks.
+    pub(crate) fn close(&self) {
+        self.inner.closed.send_replace(true);
+    }
+
+    /// Waits until no activity is in progress.
+    pub(crate) async fn wait_until_idle(&self) {
+        loop {
+            // First, register for the notification
+            // so that we don't miss it
+            let notified = self.inner.idle_notify.notified();
+
+            if self.inner.active.load(Ordering::Acquire) == 0 {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use assert_matches::assert_matches;
+
+    use super::TaskTracker;
+    use crate::test_utils::setup_tracing;
+    use crate::transport::errors::QueryError;
+
+    #[tokio::test]
+    async fn closed_tracker_rejects_activities_and_cancels_tasks() {
+        setup_tracing();
+        let tracker = TaskTracker::default();
+
+        let guard = tracker.enter().unwrap();
+        tokio::spawn(tracker.track(futures::future::pending()));
+
+        tracker.close();
+        assert_matches!(tracker.enter(), Err(QueryError::SessionClosed));
+
+        // The background task is cancelled, but the activity is still in progress.
+        tokio::time::timeout(Duration::from_millis(100), tracker.wait_until_idle())
+            .await
+            .unwrap_err();
+
+        drop(guard);
+        tokio::time::timeout(Duration::from_secs(5), tracker.wait_until_idle())
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,163 @@
+//! Client-side generation of write timestamps.
+//!
+//! Unless a statement has its timestamp set explicitly, the timestamp of a write
+//! is decided by the clock of the coordinator handling it. When a request is retried
+//! on another coordinator, or when consecutive writes of one client are handled by
+//! different coordinators, their clocks decide which of the writes wins.
+//! A [`TimestampGenerator`] set on the [`SessionBuilder`](crate::SessionBuilder)
+//! or on an [`ExecutionProfile`](crate::ExecutionProfile) makes the driver attach
+//! a timestamp to each request instead. The timestamp is generated once per request,
+//! so all its retries and speculative executions carry the same one.
+
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use tracing::warn;
+
+/// Generates timestamps of requests whose statements have no timestamp set.
+pub trait TimestampGenerator: Send + Sync + std::fmt::Debug {
+    /// Returns the timestamp of the next request, in microseconds since the Unix epoch.
+    fn next_timestamp(&self) -> i64;
+}
+
+/// Generates timestamps from the system clock, in microseconds, which are strictly increasing:
+/// when the clock doesn't advance between two requests, or goes backwards, the timestamp
+/// of the previous request plus one microsecond is used instead.
+///
+/// If the generated timestamps get ahead of the clock by more than the warning threshold
+/// (one second by default), e.g. after the clock was moved back, a warning is logged,
+/// at most once per warning interval (one second by default).
+#[derive(Debug)]
+pub struct MonotonicTimestampGenerator {
+    last: AtomicI64,
+    warning_threshold: Duration,
+    warning_interval: Duration,
+    last_warning: Mutex<Option<Instant>>,
+}
+
+impl MonotonicTimestampGenerator {
+    /// Creates the generator with the default warning threshold and interval.
+    pub fn new() -> Self {
+        Self {
+            last: AtomicI64::new(i64::MIN),
+            warning_threshold: Duration::from_secs(1),
+            warning_interval: Duration::from_secs(1),
+            last_warning: Mutex::new(None),
+        }
+    }
+
+    /// Sets how far ahead of the clock the timestamps may get before a warning is logged.
+    pub fn warning_threshold(mut self, threshold: Duration) -> Self {
+        self.warning_threshold = threshold;
+        self
+    }
+
+    /// Sets the minimal time between consecutive warnings.
+    pub fn warning_interval(mut self, interval: Duration) -> Self {
+        self.warning_interval = interval;
+        self
+    }
+
+    fn next_after(&self, now: i64) -> i64 {
+        let mut last = self.last.load(Ordering::Relaxed);
+        loop {
+            let next = now.max(last.saturating_add(1));
+            match self
+                .last
+                .compare_exchange_weak(last, next, Ordering::Relaxed, Ordering::Relaxed)
+            {
+                Ok(_) => return next,
+                Err(actual) => last = actual,
+            }
+        }
+    }
+
+    fn warn_about_drift(&self, drift: Duration) {
+        let mut last_warning = self.last_warning.lock().unwrap();
+        let now = Instant::now();
+        if last_warning.map_or(false, |last| {
+            now.duration_since(last) < self.warning_interval
+        }) {
+            return;
+        }
+        *last_warning = Some(now);
+        warn!(
+            drift = ?drift,
+            "Generated timestamps are ahead of the clock, it was probably moved back"
+        );
+    }
+}
+
+impl Default for MonotonicTimestampGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TimestampGenerator for MonotonicTimestampGenerator {
+    fn next_timestamp(&self) -> i64 {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since_epoch| since_epoch.as_micros() as i64);
+        let next = self.next_after(now);
+
+        let drift = Duration::from_micros(next.saturating_sub(now) as u64);
+        if drift > self.warning_threshold {
+            self.warn_about_drift(drift);
+        }
+        next
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use super::{MonotonicTimestampGenerator, TimestampGenerator};
+    use crate::test_utils::setup_tracing;
+
+    #[test]
+    fn timestamps_are_strictly_increasing() {
+        setup_tracing();
+        let generator = MonotonicTimestampGenerator::new();
+
+        assert_eq!(generator.next_after(100), 100);
+        assert_eq!(generator.next_after(105), 105);
+        // The clock didn't advance or went backwards.
+        assert_eq!(generator.next_after(105), 106);
+        assert_eq!(generator.next_after(50), 107);
+        // The clock caught up.
+        assert_eq!(generator.next_after(200), 200);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn timestamps_are_unique_across_threads() {
+        setup_tracing();
+        let generator = Arc::new(
+            MonotonicTimestampGenerator::new().warning_threshold(Duration::from_secs(3600)),
+        );
+        let tasks: Vec<_> = (0..4)
+            .map(|_| {
+                let generator = generator.clone();
+                tokio::spawn(async move {
+                    (0..1000)
+                        .map(|_| generator.next_timestamp())
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        let mut timestamps = Vec::new();
+        for task in tasks {
+            let task_timestamps = task.await.unwrap();
+            assert!(task_timestamps.windows(2).all(|w| w[0] < w[1]));
+            timestamps.extend(task_timestamps);
+        }
+        let count = timestamps.len();
+        timestamps.sort_unstable();
+        timestamps.dedup();
+        assert_eq!(timestamps.len(), count);
+    }
+}
@@ -0,0 +1,699 @@
+//! Scanning whole tables in parallel, token range by token range.
+//!
+//! A full scan of a table with a single paged query is served by one coordinator
+//! after another, one page at a time. [`TokenRangeScan`] splits the token ring
+//! of the table (including the known tablets) into ranges and runs a query
+//! of the form `SELECT ... WHERE token(pk) > ? AND token(pk) <= ?` for each of them,
+//! sending it to a replica of the range. The pages of many ranges are fetched concurrently
+//! and yielded as a stream, together with a [`ScanCheckpoint`], from which an interrupted
+//! scan can be resumed.
+
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{ready, Context, Poll};
+use std::time::Duration;
+
+use bytes::{Buf, BufMut, Bytes};
+use futures::future::BoxFuture;
+use futures::stream::FuturesUnordered;
+use futures::{FutureExt, Stream, StreamExt};
+use scylla_cql::errors::{BadQuery, QueryError};
+use scylla_cql::frame::response::result::TableSpec;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::load_balancing::{FallbackPlan, LoadBalancingPolicy, RoutingInfo};
+use crate::prepared_statement::PreparedStatement;
+use crate::routing::{Shard, Token};
+use crate::transport::execution_profile::ExecutionProfileHandle;
+use crate::transport::{ClusterData, NodeRef};
+use crate::{QueryResult, Session};
+
+/// A range of token values, from `start` (exclusive) to `end` (inclusive).
+/// The first range of the ring starts at `i64::MIN`, which is lower than any token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TokenRange {
+    pub start: i64,
+    pub end: i64,
+}
+
+impl TokenRange {
+    fn contains(&self, other: &TokenRange) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+}
+
+/// Parallel scan of a whole table.
+///
+/// The statement must select from a single table, with exactly two bind markers:
+/// the start (exclusive) and the end (inclusive) of a token range, e.g.
+/// `SELECT a, b, c FROM ks.t WHERE token(a) > ? AND token(a) <= ?`.
+///
+/// ```rust
+/// # use scylla::Session;
+/// # use std::error::Error;
+/// # async fn check_only_compiles(session: &Session) -> Result<(), Box<dyn Error>> {
+/// use futures::StreamExt;
+/// use scylla::TokenRangeScan;
+///
+/// let select = session
+///     .prepare("SELECT a, b FROM ks.t WHERE token(a) > ? AND token(a) <= ?")
+///     .await?;
+/// let mut pages = TokenRangeScan::new(session, select)
+///     .parallelism(32)
+///     .into_stream()?;
+///
+/// while let Some(page) = pages.next().await {
+///     let page = page?;
+///     for row in page.result.rows::<(i32, i32)>()? {
+///         let (a, b) = row?;
+///         println!("a, b: {}, {}", a, b);
+///     }
+///     // Save `pages.checkpoint().to_bytes()` to resume the scan after a crash.
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct TokenRangeScan<'a> {
+    session: &'a Session,
+    statement: PreparedStatement,
+    parallelism: usize,
+    splits_per_range: usize,
+    checkpoint: ScanCheckpoint,
+}
+
+impl<'a> TokenRangeScan<'a> {
+    /// Creates a scan of the whole ring, with 16 ranges scanned concurrently.
+    pub fn new(session: &'a Session, statement: PreparedStatement) -> Self {
+        Self {
+            session,
+            statement,
+            parallelism: 16,
+            splits_per_range: 1,
+            checkpoint: ScanCheckpoint::default(),
+        }
+    }
+
+    /// Sets the number of ranges scanned concurrently.
+    pub fn parallelism(mut self, parallelism: usize) -> Self {
+        self.parallelism = parallelism.max(1);
+        self
+    }
+
+    /// Sets the number of equal parts into which each range of the ring (owned by
+    /// a single set of replicas) is split. More parts mean smaller units of work,
+    /// so less work is repeated after resuming, and better balance between replicas.
+    pub fn splits_per_range(mut self, splits: usize) -> Self {
+        self.splits_per_range = splits.max(1);
+        self
+    }
+
+    /// Resumes the scan from a checkpoint of an earlier scan with the same statement.
+    /// Completed ranges are skipped, and ranges in progress continue from their paging state.
+    pub fn resume_from(mut self, checkpoint: ScanCheckpoint) -> Self {
+        self.checkpoint = checkpoint;
+        self
+    }
+
+    /// Splits the ring, based on the current cluster metadata, and starts the scan.
+    pub fn into_stream(self) -> Result<TokenRangeScanStream<'a>, QueryError> {
+        let table_spec = match self.statement.get_table_spec() {
+            Some(table_spec) if self.statement.get_variable_col_specs().len() == 2 => {
+                table_spec.to_owned()
+            }
+            _ => {
+                return Err(QueryError::BadQuery(BadQuery::Other(
+                    "Token range scan requires a statement with exactly two bind markers: the start and the end of a token range".to_owned(),
+                )))
+            }
+        };
+
+        let cluster_data = self.session.get_cluster_data();
+        let locator = cluster_data.replica_locator();
+        let mut boundaries: Vec<i64> = locator
+            .ring()
+            .iter()
+            .map(|(token, _)| token.value())
+            .collect();
+        if let Some(tablets) = locator.tablets.tablets_for_table(&table_spec) {
+            for (first_token, last_token) in tablets.tablet_ranges() {
+                boundaries.extend(first_token.value().checked_sub(1));
+                boundaries.push(last_token.value());
+            }
+        }
+        // Splitting also at the ranges of the checkpoint makes each of the ranges
+        // either fully done (or in progress), or not started at all.
+        for range in self.checkpoint.ranges() {
+            boundaries.push(range.start);
+            boundaries.push(range.end);
+        }
+
+        let mut pending: VecDeque<PendingRange> = self
+            .checkpoint
+            .in_progress
+            .iter()
+            .map(|(range, paging_state)| (*range, Some(paging_state.clone())))
+            .collect();
+        pending.extend(
+            split_ring(boundaries, self.splits_per_range)
+                .into_iter()
+                .filter(|range| !self.checkpoint.ranges().any(|done| done.contains(range)))
+                .map(|range| (range, None)),
+        );
+
+        let profile_handle = self
+            .statement
+            .get_execution_profile_handle()
+            .unwrap_or_else(|| self.session.get_default_execution_profile_handle())
+            .clone();
+
+        Ok(TokenRangeScanStream {
+            session: self.session,
+            statement: self.statement,
+            table_spec,
+            profile_handle,
+            parallelism: self.parallelism,
+            pending,
+            started_ranges: 0,
+            in_flight: FuturesUnordered::new(),
+            checkpoint: self.checkpoint,
+        })
+    }
+}
+
+// A range which isn't scanned yet, with the paging state to resume from.
+type PendingRange = (TokenRange, Option<Bytes>);
+
+// Splits the whole ring, (i64::MIN, i64::MAX], at the given tokens,
+// and then each of the resulting ranges into `splits` equal parts.
+fn split_ring(mut boundaries: Vec<i64>, splits: usize) -> Vec<TokenRange> {
+    boundaries.extend([i64::MIN, i64::MAX]);
+    boundaries.sort_unstable();
+    boundaries.dedup();
+
+    let mut ranges = Vec::new();
+    for bounds in boundaries.windows(2) {
+        let (start, end) = (bounds[0] as i128, bounds[1] as i128);
+        let splits = (splits as i128).min(end - start);
+        ranges.extend((0..splits).map(|i| TokenRange {
+            start: (start + (end - start) * i / splits) as i64,
+            end: (start + (end - start) * (i + 1) / splits) as i64,
+        }));
+    }
+    ranges
+}
+
+/// A page of results of a [`TokenRangeScan`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct ScanPage {
+    /// The range from which the page was read.
+    pub range: TokenRange,
+
+    /// The page of rows.
+    pub result: QueryResult,
+}
+
+/// Stream of pages of a [`TokenRangeScan`], in no particular order.
+///
+/// When fetching a page fails (after the retries decided by the statement's retry policy),
+/// the error is yielded and the rest of its range is abandoned, but the other ranges
+/// are still scanned. The abandoned range is scanned again when the scan is resumed
+/// from a later checkpoint.
+pub struct TokenRangeScanStream<'a> {
+    session: &'a Session,
+    statement: PreparedStatement,
+    table_spec: TableSpec<'static>,
+    profile_handle: ExecutionProfileHandle,
+    parallelism: usize,
+    pending: VecDeque<PendingRange>,
+    started_ranges: usize,
+    in_flight: FuturesUnordered<BoxFuture<'a, (RangeScan, Result<QueryResult, QueryError>)>>,
+    checkpoint: ScanCheckpoint,
+}
+
+// A range being scanned, with a statement routed to its replicas.
+struct RangeScan {
+    range: TokenRange,
+    statement: PreparedStatement,
+    paging_state: Option<Bytes>,
+}
+
+impl<'a> TokenRangeScanStream<'a> {
+    /// Returns the progress of the scan, covering all the pages yielded so far.
+    pub fn checkpoint(&self) -> &ScanCheckpoint {
+        &self.checkpoint
+    }
+
+    fn start_range(&mut self, (range, paging_state): PendingRange) {
+        let policy = RangeReplicasPolicy {
+            table_spec: self.table_spec.clone(),
+            token: Token::new(range.end),
+            preferred_replica: self.started_ranges,
+            fallback: self.profile_handle.access().load_balancing_policy.clone(),
+        };
+        let profile = self
+            .profile_handle
+            .pointee_to_builder()
+            .load_balancing_policy(Arc::new(policy))
+            .build();
+        let mut statement = self.statement.clone();
+        statement.set_execution_profile_handle(Some(profile.into_handle()));
+        self.started_ranges += 1;
+
+        self.fetch_page(RangeScan {
+            range,
+            statement,
+            paging_state,
+        });
+    }
+
+    fn fetch_page(&mut self, scan: RangeScan) {
+        let session = self.session;
+        self.in_flight.push(
+            async move {
+                let bounds = (scan.range.start, scan.range.end);
+                let result = session
+                    .execute_paged(&scan.statement, bounds, scan.paging_state.clone())
+                    .await;
+                (scan, result)
+            }
+            .boxed(),
+        );
+    }
+}
+
+impl Stream for TokenRangeScanStream<'_> {
+    type Item = Result<ScanPage, QueryError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        while self.in_flight.len() < self.parallelism {
+            let Some(range) = self.pending.pop_front() else {
+                break;
+            };
+            self.start_range(range);
+        }
+
+        let Some((mut scan, result)) = ready!(self.in_flight.poll_next_unpin(cx)) else {
+            return Poll::Ready(None);
+        };
+        let result = match result {
+            Ok(result) => result,
+            Err(error) => return Poll::Ready(Some(Err(error))),
+        };
+
+        let range = scan.range;
+        match result.paging_state.clone() {
+            Some(paging_state) => {
+                self.checkpoint.set_in_progress(range, paging_state.clone());
+                scan.paging_state = Some(paging_state);
+                self.fetch_page(scan);
+            }
+            None => self.checkpoint.complete(range),
+        }
+        Poll::Ready(Some(Ok(ScanPage { range, result })))
+    }
+}
+
+/// Progress of a [`TokenRangeScan`]: the completed ranges, and the paging state
+/// of the ranges in progress.
+///
+/// A checkpoint can be saved with [`to_bytes`](ScanCheckpoint::to_bytes), and read back
+/// with [`from_bytes`](ScanCheckpoint::from_bytes). Paging states are only valid
+/// for the statement which produced them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScanCheckpoint {
+    // Sorted, adjacent ranges are merged.
+    completed: Vec<TokenRange>,
+    in_progress: Vec<(TokenRange, Bytes)>,
+}
+
+/// Error returned when reading a malformed [`ScanCheckpoint`].
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("Invalid scan checkpoint: {0}")]
+pub struct InvalidScanCheckpoint(&'static str);
+
+const CHECKPOINT_FORMAT_VERSION: u8 = 1;
+
+impl ScanCheckpoint {
+    /// Returns the completed ranges, sorted, with adjacent ranges merged.
+    pub fn completed_ranges(&self) -> &[TokenRange] {
+        &self.completed
+    }
+
+    /// Returns the ranges which are partially scanned.
+    pub fn ranges_in_progress(&self) -> impl Iterator<Item = TokenRange> + '_ {
+        self.in_progress.iter().map(|(range, _)| *range)
+    }
+
+    /// Returns `true` if the whole ring is scanned.
+    pub fn is_complete(&self) -> bool {
+        self.completed
+            == [TokenRange {
+                start: i64::MIN,
+                end: i64::MAX,
+            }]
+    }
+
+    fn ranges(&self) -> impl Iterator<Item = TokenRange> + '_ {
+        self.completed
+            .iter()
+            .copied()
+            .chain(self.ranges_in_progress())
+    }
+
+    fn set_in_progress(&mut self, range: TokenRange, paging_state: Bytes) {
+        match self.in_progress.iter_mut().find(|(r, _)| *r == range) {
+            Some((_, state)) => *state = paging_state,
+            None => self.in_progress.push((range, paging_state)),
+        }
+    }
+
+    fn complete(&mut self, range: TokenRange) {
+        self.in_progress.retain(|(r, _)| *r != range);
+
+        let idx = self.completed.partition_point(|r| r.start < range.start);
+        self.completed.insert(idx, range);
+        if idx + 1 < self.completed.len()
+            && self.completed[idx].end == self.completed[idx + 1].start
+        {
+            self.completed[idx].end = self.completed.remove(idx + 1).end;
+        }
+        if idx > 0 && self.completed[idx - 1].end == self.completed[idx].start {
+            self.completed[idx - 1].end = self.completed.remove(idx).end;
+        }
+    }
+
+    /// Serializes the checkpoint.
+    pub fn to_bytes(&self) -> Bytes {
+        let mut buf = Vec::new();
+        buf.put_u8(CHECKPOINT_FORMAT_VERSION);
+        buf.put_u32(self.completed.len() as u32);
+        for range in &self.completed {
+            buf.put_i64(range.start);
+            buf.put_i64(range.end);
+        }
+        buf.put_u32(self.in_progress.len() as u32);
+        for (range, paging_state) in &self.in_progress {
+            buf.put_i64(range.start);
+            buf.put_i64(range.end);
+            buf.put_u32(paging_state.len() as u32);
+            buf.put_slice(paging_state);
+        }
+        buf.into()
+    }
+
+    /// Deserializes a checkpoint serialized with [`to_bytes`](ScanCheckpoint::to_bytes).
+    pub fn from_bytes(mut buf: &[u8]) -> Result<Self, InvalidScanCheckpoint> {
+        fn read_range(buf: &mut &[u8]) -> Result<TokenRange, InvalidScanCheckpoint> {
+            if buf.remaining() < 16 {
+                return Err(InvalidScanCheckpoint("unexpected end of data"));
+            }
+            let range = TokenRange {
+                start: buf.get_i64(),
+                end: buf.get_i64(),
+            };
+            if range.start >= range.end {
+                return Err(InvalidScanCheckpoint("empty token range"));
+            }
+            Ok(range)
+        }
+        fn read_len(buf: &mut &[u8]) -> Result<usize, InvalidScanCheckpoint> {
+            if buf.remaining() < 4 {
+                return Err(InvalidScanCheckpoint("unexpected end of data"));
+            }
+            Ok(buf.get_u32() as usize)
+        }
+
+        if buf.is_empty() || buf.get_u8() != CHECKPOINT_FORMAT_VERSION {
+            return Err(InvalidScanCheckpoint("unsupported format version"));
+        }
+        let mut checkpoint = ScanCheckpoint::default();
+        for _ in 0..read_len(&mut buf)? {
+            checkpoint.complete(read_range(&mut buf)?);
+        }
+        for _ in 0..read_len(&mut buf)? {
+            let range = read_range(&mut buf)?;
+            let len = read_len(&mut buf)?;
+            if buf.remaining() < len {
+                return Err(InvalidScanCheckpoint("unexpected end of data"));
+            }
+            checkpoint.set_in_progress(range, Bytes::copy_from_slice(&buf[..len]));
+            buf.advance(len);
+        }
+        if buf.has_remaining() {
+            return Err(InvalidScanCheckpoint("trailing data"));
+        }
+        Ok(checkpoint)
+    }
+}
+
+// Sends the queries of a range to its replicas, starting from a different one
+// for consecutive ranges. Queries go to other nodes, as planned by the load balancing
+// policy of the statement, only if no replica of the range is known or all of them fail.
+#[derive(Debug)]
+struct RangeReplicasPolicy {
+    table_spec: TableSpec<'static>,
+    token: Token,
+    preferred_replica: usize,
+    fallback: Arc<dyn LoadBalancingPolicy>,
+}
+
+impl RangeReplicasPolicy {
+    fn replicas<'a>(&self, cluster: &'a ClusterData) -> Vec<(NodeRef<'a>, Shard)> {
+        let mut replicas: Vec<_> = cluster
+            .get_token_endpoints_iter(&self.table_spec, self.token)
+            .filter(|(node, _)| node.is_enabled())
+            .collect();
+        if !replicas.is_empty() {
+            let len = replicas.len();
+            replicas.rotate_left(self.preferred_replica % len);
+        }
+        replicas
+    }
+}
+
+impl LoadBalancingPolicy for RangeReplicasPolicy {
+    fn pick<'a>(
+        &'a self,
+        query: &'a RoutingInfo,
+        cluster: &'a ClusterData,
+    ) -> Option<(NodeRef<'a>, Option<Shard>)> {
+        match self.replicas(cluster).first() {
+            Some(&(node, shard)) => Some((node, Some(shard))),
+            None => self.fallback.pick(query, cluster),
+        }
+    }
+
+    fn fallback<'a>(
+        &'a self,
+        query: &'a RoutingInfo,
+        cluster: &'a ClusterData,
+    ) -> FallbackPlan<'a> {
+        let replicas = self.replicas(cluster);
+        let replica_ids: Vec<Uuid> = replicas.iter().map(|(node, _)| node.host_id).collect();
+        let others = self
+            .fallback
+            .fallback(query, cluster)
+            .filter(move |(node, _)| !replica_ids.contains(&node.host_id));
+        Box::new(
+            replicas
+                .into_iter()
+                .map(|(node, shard)| (node, Some(shard)))
+                .chain(others),
+        )
+    }
+
+    fn on_query_success(&self, query: &RoutingInfo, latency: Duration, node: NodeRef<'_>) {
+        self.fallback.on_query_success(query, latency, node)
+    }
+
+    fn on_query_failure(
+        &self,
+        query: &RoutingInfo,
+        latency: Duration,
+        node: NodeRef<'_>,
+        error: &QueryError,
+    ) {
+        self.fallback.on_query_failure(query, latency, node, error)
+    }
+
+    fn name(&self) -> String {
+        "RangeReplicasPolicy".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use bytes::Bytes;
+    use futures::StreamExt;
+
+    use super::{split_ring, InvalidScanCheckpoint, ScanCheckpoint, TokenRange, TokenRangeScan};
+    use crate::test_utils::{create_new_session_builder, setup_tracing};
+    use crate::utils::test_utils::unique_keyspace_name;
+
+    fn range(start: i64, end: i64) -> TokenRange {
+        TokenRange { start, end }
+    }
+
+    #[test]
+    fn ring_is_split_at_boundaries() {
+        setup_tracing();
+        assert_eq!(
+            split_ring(vec![10, -10, 10], 1),
+            [range(i64::MIN, -10), range(-10, 10), range(10, i64::MAX)]
+        );
+
+        let ranges = split_ring(vec![-100, 100, 103], 4);
+        assert_eq!(ranges.len(), 15);
+        assert_eq!(ranges[0].start, i64::MIN);
+        assert_eq!(ranges[14].end, i64::MAX);
+        assert!(ranges.windows(2).all(|r| r[0].end == r[1].start));
+        assert_eq!(
+            ranges[4..11],
+            [
+                range(-100, -50),
+                range(-50, 0),
+                range(0, 50),
+                range(50, 100),
+                // Ranges aren't split into empty parts.
+                range(100, 101),
+                range(101, 102),
+                range(102, 103),
+            ]
+        );
+    }
+
+    #[test]
+    fn checkpoint_merges_completed_ranges() {
+        setup_tracing();
+        let mut checkpoint = ScanCheckpoint::default();
+        checkpoint.set_in_progress(range(0, 10), Bytes::from_static(b"a"));
+        checkpoint.set_in_progress(range(0, 10), Bytes::from_static(b"b"));
+        checkpoint.complete(range(20, 30));
+        checkpoint.complete(range(i64::MIN, 0));
+        assert_eq!(
+            checkpoint.ranges_in_progress().collect::<Vec<_>>(),
+            [range(0, 10)]
+        );
+
+        checkpoint.complete(range(0, 10));
+        assert_eq!(checkpoint.ranges_in_progress().count(), 0);
+        assert_eq!(
+            checkpoint.completed_ranges(),
+            [range(i64::MIN, 10), range(20, 30)]
+        );
+
+        checkpoint.complete(range(30, i64::MAX));
+        checkpoint.complete(range(10, 20));
+        assert_eq!(checkpoint.completed_ranges(), [range(i64::MIN, i64::MAX)]);
+        assert!(checkpoint.is_complete());
+    }
+
+    #[test]
+    fn checkpoint_serialization() {
+        setup_tracing();
+        let mut checkpoint = ScanCheckpoint::default();
+        checkpoint.complete(range(-30, -20));
+        checkpoint.complete(range(20, 30));
+        checkpoint.set_in_progress(range(0, 10), Bytes::from_static(b"paging state"));
+
+        let bytes = checkpoint.to_bytes();
+        assert_eq!(ScanCheckpoint::from_bytes(&bytes), Ok(checkpoint));
+
+        assert_eq!(
+            ScanCheckpoint::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(InvalidScanCheckpoint("unexpected end of data"))
+        );
+        assert_eq!(
+            ScanCheckpoint::from_bytes(&[0]),
+            Err(InvalidScanCheckpoint("unsupported format version"))
+        );
+        assert_eq!(
+            ScanCheckpoint::from_bytes(&[bytes.as_ref(), &[0]].concat()),
+            Err(InvalidScanCheckpoint("trailing data"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_token_range_scan() {
+        setup_tracing();
+        let session = create_new_session_builder().build().await.unwrap();
+        let ks = unique_keyspace_name();
+
+        session
+            .query(format!("CREATE KEYSPACE IF NOT EXISTS {ks} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}"), &[])
+            .await
+            .unwrap();
+        session
+            .query(
+                format!("CREATE TABLE IF NOT EXISTS {ks}.t (a int, b int, PRIMARY KEY (a, b))"),
+                &[],
+            )
+            .await
+            .unwrap();
+        session.await_schema_agreement().await.unwrap();
+        let insert = session
+            .prepare(format!("INSERT INTO {ks}.t (a, b) VALUES (?, ?)"))
+            .await
+            .unwrap();
+        for a in 0..100 {
+            for b in 0..3 {
+                session.execute(&insert, (a, b)).await.unwrap();
+            }
+        }
+
+        let mut select = session
+            .prepare(format!(
+                "SELECT a, b FROM {ks}.t WHERE token(a) > ? AND token(a) <= ?"
+            ))
+            .await
+            .unwrap();
+        select.set_page_size(2);
+
+        // Interrupt the scan after a few pages, then resume it from the checkpoint.
+        let mut rows = Vec::new();
+        let mut pages = TokenRangeScan::new(&session, select.clone())
+            .parallelism(4)
+            .splits_per_range(3)
+            .into_stream()
+            .unwrap();
+        for _ in 0..20 {
+            let page = pages.next().await.unwrap().unwrap();
+            rows.extend(
+                page.result
+                    .rows::<(i32, i32)>()
+                    .unwrap()
+                    .map(Result::unwrap),
+            );
+        }
+        let checkpoint = ScanCheckpoint::from_bytes(&pages.checkpoint().to_bytes()).unwrap();
+        assert!(!checkpoint.is_complete());
+        drop(pages);
+
+        let mut pages = TokenRangeScan::new(&session, select)
+            .parallelism(8)
+            .resume_from(checkpoint)
+            .into_stream()
+            .unwrap();
+        while let Some(page) = pages.next().await {
+            let page = page.unwrap();
+            rows.extend(
+                page.result
+                    .rows::<(i32, i32)>()
+                    .unwrap()
+                    .map(Result::unwrap),
+            );
+        }
+        assert!(pages.checkpoint().is_complete());
+
+        // Each row is read exactly once.
+        assert_eq!(rows.len(), 300);
+        let expected: BTreeSet<(i32, i32)> =
+            (0..100).flat_map(|a| (0..3).map(move |b| (a, b))).collect();
+        assert_eq!(rows.into_iter().collect::<BTreeSet<_>>(), expected);
+    }
+}
@@ -188,6 +188,10 @@ enum PreCqlType {
         frozen: bool,
         name: String,
     },
+    Vector {
+        type_: Box<PreCqlType>,
+        dimensions: u16,
+    },
 }
 
 impl PreCqlType {
@@ -220,6 +224,10 @@ impl PreCqlType {
                 };
                 CqlType::UserDefinedType { frozen, definition }
             }
+            PreCqlType::Vector { type_, dimensions } => CqlType::Vector {
+                type_: Box::new(type_.into_cql_type(keyspace_name, udts)),
+                dimensions,
+            },
         }
     }
 }
@@ -237,6 +245,11 @@ pub enum CqlType {
         // Using Arc here in order not to have many copies of the same definition
         definition: Result<Arc<UserDefinedType>, MissingUserDefinedType>,
     },
+    /// Fixed-length vector, introduced in Cassandra 5.0.
+    Vector {
+        type_: Box<CqlType>,
+        dimensions: u16,
+    },
 }
 
 /// Definition of a user-defined type
@@ -1100,6 +1113,7 @@ fn topo_sort_udts(udts: &mut Vec<UdtRowWithParsedFieldTypes>) -> Result<(), Quer
                 .iter()
                 .for_each(|type_| do_with_referenced_udts(what, type_)),
             PreCqlType::UserDefinedType { name, .. } => what(name),
+            PreCqlType::Vector { type_, .. } => do_with_referenced_udts(what, type_),
         }
     }
 
@@ -1608,6 +1622,21 @@ fn parse_cql_type(p: ParserState<'_>) -> ParseResult<(PreCqlType, ParserState<'_
         })?;
 
         Ok((PreCqlType::Tuple(types), p))
+    } else if let Ok(p) = p.accept("vector<") {
+        let (inner_type, p) = parse_cql_type(p)?;
+        let p = p.accept(",")?.skip_white();
+        let (dimensions, p) = p.take_while(|c| c.is_ascii_digit());
+        let dimensions = dimensions
+            .parse()
+            .map_err(|_| p.error(ParseErrorCause::Other("invalid vector dimensions")))?;
+        let p = p.accept(">")?;
+
+        let typ = PreCqlType::Vector {
+            type_: Box::new(inner_type),
+            dimensions,
+        };
+
+        Ok((typ, p))
     } else if let Ok((typ, p)) = parse_native_type(p) {
         Ok((PreCqlType::Native(typ), p))
     } else if let Ok((name, p)) = parse_user_defined_type(p) {
@@ -1793,6 +1822,25 @@ mod tests {
                     PreCqlType::Native(NativeType::Varint),
                 ]),
             ),
+            (
+                "vector<float, 3>",
+                PreCqlType::Vector {
+                    type_: Box::new(PreCqlType::Native(NativeType::Float)),
+                    dimensions: 3,
+                },
+            ),
+            (
+                "vector<frozen<list<text>>, 1024>",
+                PreCqlType::Vector {
+                    type_: Box::new(PreCqlType::Collection {
+                        frozen: true,
+                        type_: PreCollectionType::List(Box::new(PreCqlType::Native(
+                            NativeType::Text,
+                        ))),
+                    }),
+                    dimensions: 1024,
+                },
+            ),
             (
                 "com.scylladb.types.AwesomeType",
                 PreCqlType::UserDefinedType {
@@ -0,0 +1,7 @@
+/// 64-bit FNV-1a, which, unlike the hashers of the standard library,
+/// is guaranteed to stay the same between processes and versions.
+pub(crate) fn fnv1a_64(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
@@ -0,0 +1,1259 @@
+use std::net::IpAddr;
+use std::num::{IntErrorKind, ParseIntError};
+use std::str::FromStr;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use scylla_cql::frame::response::result::{ColumnType, CqlValue};
+use scylla_cql::frame::types::vint_decode;
+use scylla_cql::frame::value::{
+    Counter, CqlDate, CqlDecimal, CqlDuration, CqlTime, CqlTimestamp, CqlTimeuuid, CqlVarint,
+};
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::utils::parse::{ParseErrorCause, ParseResult, ParserState};
+
+/// An error that occurred while parsing a CQL literal with [`parse_cql_literal`].
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+#[error("Invalid CQL literal at position {position}: {reason}")]
+pub struct CqlLiteralParseError {
+    /// The 1-based position of the error in characters.
+    pub position: usize,
+    /// Description of the problem.
+    pub reason: String,
+}
+
+/// Parses a CQL literal as a value of the given type.
+///
+/// The syntax is the one of CQL literals, e.g. `{1: 'a', 2: 'b'}` for a `map<int, text>`,
+/// `('x', 3, 0x00ff)` for a `tuple<text, int, blob>` or `{street: 'Main St', number: 7}`
+/// for a UDT. Values of time-related types and `inet` are given as quoted strings,
+/// e.g. `'2024-03-01 12:00:00+0000'`, but integer literals are accepted for `date`,
+/// `time` and `timestamp` as well. Durations use the `1h30m` syntax.
+/// Besides plain numbers, `decimal` and `varint` values can be given
+/// as `blobAsDecimal(0x...)` and `blobAsVarint(0x...)`, and durations
+/// whose components have mixed signs as `blobAsDuration(0x...)`.
+///
+/// Returns `None` if the literal is `null`. Elements of tuples and fields
+/// of UDTs can be `null` too, and omitted UDT fields are `null`.
+///
+/// ```rust
+/// # use scylla::frame::response::result::{ColumnType, CqlValue};
+/// # use scylla::literal::parse_cql_literal;
+/// let typ = ColumnType::Map(Box::new(ColumnType::Int), Box::new(ColumnType::Text));
+/// let value = parse_cql_literal("{1: 'a', 2: 'b'}", &typ).unwrap();
+/// assert_eq!(
+///     value,
+///     Some(CqlValue::Map(vec![
+///         (CqlValue::Int(1), CqlValue::Text("a".to_owned())),
+///         (CqlValue::Int(2), CqlValue::Text("b".to_owned())),
+///     ]))
+/// );
+///
+/// let err = parse_cql_literal("{1: 'a', x: 'b'}", &typ).unwrap_err();
+/// assert_eq!(err.position, 10);
+/// ```
+pub fn parse_cql_literal(
+    literal: &str,
+    typ: &ColumnType,
+) -> Result<Option<CqlValue>, CqlLiteralParseError> {
+    let (value, p) = parse_value(ParserState::new(literal).skip_white(), typ).map_err(|err| {
+        CqlLiteralParseError {
+            position: err.calculate_position(literal).unwrap_or(0),
+            reason: err.get_cause().to_string(),
+        }
+    })?;
+    let p = p.skip_white();
+    if !p.is_at_eof() {
+        return Err(CqlLiteralParseError {
+            position: p.calculate_position(literal).unwrap_or(0),
+            reason: "leftover characters".to_string(),
+        });
+    }
+    Ok(value)
+}
+
+fn parse_value<'s>(
+    p: ParserState<'s>,
+    typ: &ColumnType,
+) -> ParseResult<(Option<CqlValue>, ParserState<'s>)> {
+    if let Some(p) = accept_keyword(p, "null") {
+        return Ok((None, p));
+    }
+    let (value, p) = parse_non_null_value(p, typ)?;
+    Ok((Some(value), p))
+}
+
+fn parse_non_null_value<'s>(
+    p: ParserState<'s>,
+    typ: &ColumnType,
+) -> ParseResult<(CqlValue, ParserState<'s>)> {
+    let (value, new_p) = match typ {
+        ColumnType::Custom(_) => {
+            return Err(p.error(ParseErrorCause::Other(
+                "literals of custom types are not supported",
+            )))
+        }
+        ColumnType::Ascii => {
+            let (s, new_p) = parse_string(p)?;
+            if !s.is_ascii() {
+                return Err(p.error(ParseErrorCause::Other(
+                    "non-ASCII character in an ascii literal",
+                )));
+            }
+            (CqlValue::Ascii(s), new_p)
+        }
+        ColumnType::Text => {
+            let (s, p) = parse_string(p)?;
+            (CqlValue::Text(s), p)
+        }
+        ColumnType::Boolean => {
+            if let Some(p) = accept_keyword(p, "true") {
+                (CqlValue::Boolean(true), p)
+            } else if let Some(p) = accept_keyword(p, "false") {
+                (CqlValue::Boolean(false), p)
+            } else {
+                return Err(p.error(ParseErrorCause::Other("expected true or false")));
+            }
+        }
+        ColumnType::Blob => {
+            let (b, p) = parse_blob(p)?;
+            (CqlValue::Blob(b), p)
+        }
+        ColumnType::Counter => {
+            let (c, p) = parse_integer(p)?;
+            (CqlValue::Counter(Counter(c)), p)
+        }
+        ColumnType::TinyInt => {
+            let (i, p) = parse_integer(p)?;
+            (CqlValue::TinyInt(i), p)
+        }
+        ColumnType::SmallInt => {
+            let (i, p) = parse_integer(p)?;
+            (CqlValue::SmallInt(i), p)
+        }
+        ColumnType::Int => {
+            let (i, p) = parse_integer(p)?;
+            (CqlValue::Int(i), p)
+        }
+        ColumnType::BigInt => {
+            let (i, p) = parse_integer(p)?;
+            (CqlValue::BigInt(i), p)
+        }
+        ColumnType::Varint => {
+            let (v, p) = parse_varint(p)?;
+            (CqlValue::Varint(v), p)
+        }
+        ColumnType::Float => {
+            let (f, p) = parse_float(p)?;
+            (CqlValue::Float(f), p)
+        }
+        ColumnType::Double => {
+            let (f, p) = parse_float(p)?;
+            (CqlValue::Double(f), p)
+        }
+        ColumnType::Decimal => {
+            let (d, p) = parse_decimal(p)?;
+            (CqlValue::Decimal(d), p)
+        }
+        ColumnType::Date => {
+            let (d, p) = parse_date(p)?;
+            (CqlValue::Date(d), p)
+        }
+        ColumnType::Time => {
+            let (t, p) = parse_time(p)?;
+            (CqlValue::Time(t), p)
+        }
+        ColumnType::Timestamp => {
+            let (t, p) = parse_timestamp(p)?;
+            (CqlValue::Timestamp(t), p)
+        }
+        ColumnType::Duration => {
+            let (d, p) = parse_duration(p)?;
+            (CqlValue::Duration(d), p)
+        }
+        ColumnType::Inet => {
+            let (s, new_p) = parse_string(p)?;
+            let addr = IpAddr::from_str(&s)
+                .map_err(|_| p.error(ParseErrorCause::Other("invalid IP address")))?;
+            (CqlValue::Inet(addr), new_p)
+        }
+        ColumnType::Uuid => {
+            let (u, p) = parse_uuid(p)?;
+            (CqlValue::Uuid(u), p)
+        }
+        ColumnType::Timeuuid => {
+            let (u, p) = parse_uuid(p)?;
+            (CqlValue::Timeuuid(CqlTimeuuid::from(u)), p)
+        }
+        ColumnType::List(elem_type) => {
+            let mut elems = Vec::new();
+            let p = parse_sequence(p, "[", "]", |p| {
+                let (elem, p) = parse_collection_element(p, elem_type)?;
+                elems.push(elem);
+                Ok(p)
+            })?;
+            (CqlValue::List(elems), p)
+        }
+        ColumnType::Set(elem_type) => {
+            let mut elems = Vec::new();
+            let p = parse_sequence(p, "{", "}", |p| {
+                let (elem, p) = parse_collection_element(p, elem_type)?;
+                elems.push(elem);
+                Ok(p)
+            })?;
+            (CqlValue::Set(elems), p)
+        }
+        ColumnType::Map(key_type, value_type) => {
+            let mut entries = Vec::new();
+            let p = parse_sequence(p, "{", "}", |p| {
+                let (key, p) = parse_collection_element(p, key_type)?;
+                let p = p.skip_white().accept(":")?.skip_white();
+                let (value, p) = parse_collection_element(p, value_type)?;
+                entries.push((key, value));
+                Ok(p)
+            })?;
+            (CqlValue::Map(entries), p)
+        }
+        ColumnType::Vector { typ, dimensions } => {
+            let mut elems = Vec::new();
+            let new_p = parse_sequence(p, "[", "]", |p| {
+                let (elem, p) = parse_collection_element(p, typ)?;
+                elems.push(elem);
+                Ok(p)
+            })?;
+            if elems.len() != *dimensions as usize {
+                return Err(p.error(ParseErrorCause::Other(
+                    "wrong number of elements in a vector literal",
+                )));
+            }
+            (CqlValue::Vector(elems), new_p)
+        }
+        ColumnType::Tuple(types) => {
+            let mut types = types.iter();
+            let mut elems = Vec::new();
+            let p = parse_sequence(p, "(", ")", |p| {
+                let typ = types.next().ok_or_else(|| {
+                    p.error(ParseErrorCause::Other(
+                        "too many elements in a tuple literal",
+                    ))
+                })?;
+                let (elem, p) = parse_value(p, typ)?;
+                elems.push(elem);
+                Ok(p)
+            })?;
+            // Like in CQL, the trailing elements may be omitted.
+            elems.extend(types.map(|_| None));
+            (CqlValue::Tuple(elems), p)
+        }
+        ColumnType::UserDefinedType {
+            type_name,
+            keyspace,
+            field_types,
+        } => {
+            let mut values: Vec<Option<Option<CqlValue>>> = vec![None; field_types.len()];
+            let p = parse_sequence(p, "{", "}", |p| {
+                let (name, name_end) = parse_identifier(p)?;
+                let idx = field_types
+                    .iter()
+                    .position(|(field_name, _)| *field_name == name)
+                    .ok_or_else(|| {
+                        p.error(ParseErrorCause::Other(
+                            "unknown field of the user defined type",
+                        ))
+                    })?;
+                if values[idx].is_some() {
+                    return Err(p.error(ParseErrorCause::Other("duplicate field in a UDT literal")));
+                }
+                let p = name_end.skip_white().accept(":")?.skip_white();
+                let (value, p) = parse_value(p, &field_types[idx].1)?;
+                values[idx] = Some(value);
+                Ok(p)
+            })?;
+            let fields = field_types
+                .iter()
+                .zip(values)
+                .map(|((name, _), value)| (name.clone(), value.flatten()))
+                .collect();
+            let udt = CqlValue::UserDefinedType {
+                keyspace: keyspace.clone(),
+                type_name: type_name.clone(),
+                fields,
+            };
+            (udt, p)
+        }
+    };
+    Ok((value, new_p))
+}
+
+/// Parses a comma-separated sequence of items enclosed in the given brackets.
+fn parse_sequence<'s>(
+    p: ParserState<'s>,
+    open: &'static str,
+    close: &'static str,
+    mut parse_item: impl FnMut(ParserState<'s>) -> ParseResult<ParserState<'s>>,
+) -> ParseResult<ParserState<'s>> {
+    let p = p.accept(open)?.skip_white();
+    if let Ok(p) = p.accept(close) {
+        return Ok(p);
+    }
+    p.parse_while(|p| {
+        let p = parse_item(p.skip_white())?.skip_white();
+        if let Ok(p) = p.accept(",") {
+            Ok((true, p))
+        } else if let Ok(p) = p.accept(close) {
+            Ok((false, p))
+        } else {
+            Err(p.error(ParseErrorCause::Other(
+                "expected \",\" or the closing bracket",
+            )))
+        }
+    })
+}
+
+fn parse_collection_element<'s>(
+    p: ParserState<'s>,
+    typ: &ColumnType,
+) -> ParseResult<(CqlValue, ParserState<'s>)> {
+    match parse_value(p, typ)? {
+        (Some(value), p) => Ok((value, p)),
+        (None, _) => Err(p.error(ParseErrorCause::Other(
+            "null is not allowed inside collections",
+        ))),
+    }
+}
+
+/// Skips the given keyword, ignoring case.
+fn accept_keyword<'s>(p: ParserState<'s>, keyword: &str) -> Option<ParserState<'s>> {
+    let (word, p) = p.take_while(|c| c.is_alphanumeric() || c == '_');
+    word.eq_ignore_ascii_case(keyword).then_some(p)
+}
+
+/// Parses an identifier, which is lowercased unless it's quoted.
+fn parse_identifier(p: ParserState<'_>) -> ParseResult<(String, ParserState<'_>)> {
+    if let Ok(p) = p.accept("\"") {
+        return parse_quoted(p, "\"");
+    }
+    let (word, new_p) = p.take_while(|c| c.is_alphanumeric() || c == '_');
+    if word.is_empty() {
+        return Err(p.error(ParseErrorCause::Other("expected an identifier")));
+    }
+    Ok((word.to_lowercase(), new_p))
+}
+
+/// Parses a string literal, either quoted with `'` or with `$$`.
+fn parse_string(p: ParserState<'_>) -> ParseResult<(String, ParserState<'_>)> {
+    if let Ok(p) = p.accept("'") {
+        parse_quoted(p, "'")
+    } else if let Ok(p) = p.accept("$$") {
+        let mut s = String::new();
+        let p = p.parse_while(|p| {
+            let (part, p) = p.take_while(|c| c != '$');
+            s.push_str(part);
+            if let Ok(p) = p.accept("$$") {
+                Ok((false, p))
+            } else if let Ok(p) = p.accept("$") {
+                s.push('$');
+                Ok((true, p))
+            } else {
+                Err(p.error(ParseErrorCause::Other("unterminated string literal")))
+            }
+        })?;
+        Ok((s, p))
+    } else {
+        Err(p.error(ParseErrorCause::Other("expected a string literal")))
+    }
+}
+
+/// Parses the rest of a quoted string or identifier, in which the quote
+/// character is escaped by repeating it.
+fn parse_quoted<'s>(
+    p: ParserState<'s>,
+    quote: &'static str,
+) -> ParseResult<(String, ParserState<'s>)> {
+    let quote_char = quote.chars().next().unwrap();
+    let mut s = String::new();
+    let p = p.parse_while(|p| {
+        let (part, p) = p.take_while(|c| c != quote_char);
+        s.push_str(part);
+        let p = p
+            .accept(quote)
+            .map_err(|_| p.error(ParseErrorCause::Other("unterminated string literal")))?;
+        match p.accept(quote) {
+            Ok(p) => {
+                s.push(quote_char);
+                Ok((true, p))
+            }
+            Err(_) => Ok((false, p)),
+        }
+    })?;
+    Ok((s, p))
+}
+
+fn parse_blob(p: ParserState<'_>) -> ParseResult<(Vec<u8>, ParserState<'_>)> {
+    let after_prefix = p
+        .accept("0x")
+        .or_else(|_| p.accept("0X"))
+        .map_err(|_| p.error(ParseErrorCause::Other("expected a blob literal")))?;
+    let (digits, new_p) = after_prefix.take_while(|c| c.is_ascii_hexdigit());
+    if digits.len() % 2 != 0 {
+        return Err(p.error(ParseErrorCause::Other(
+            "odd number of hex digits in a blob literal",
+        )));
+    }
+    let bytes = digits
+        .as_bytes()
+        .chunks(2)
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).unwrap(), 16).unwrap())
+        .collect();
+    Ok((bytes, new_p))
+}
+
+/// Parses `name(<blob literal>)`, ignoring the case of the function name.
+fn parse_blob_function<'s>(
+    p: ParserState<'s>,
+    name: &str,
+) -> Option<ParseResult<(Vec<u8>, ParserState<'s>)>> {
+    let p = accept_keyword(p, name)?.skip_white();
+    let result = (|| {
+        let p = p.accept("(")?.skip_white();
+        let (blob, p) = parse_blob(p)?;
+        let p = p.skip_white().accept(")")?;
+        Ok((blob, p))
+    })();
+    Some(result)
+}
+
+/// Takes the characters which may belong to a numeric literal.
+fn number_token(p: ParserState<'_>) -> ParseResult<(&str, ParserState<'_>)> {
+    let (token, new_p) =
+        p.take_while(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+'));
+    if token.is_empty() {
+        return Err(p.error(ParseErrorCause::Other("expected a number")));
+    }
+    Ok((token, new_p))
+}
+
+fn parse_integer<T>(p: ParserState<'_>) -> ParseResult<(T, ParserState<'_>)>
+where
+    T: FromStr<Err = ParseIntError>,
+{
+    let (token, new_p) = number_token(p)?;
+    let value = token
+        .parse()
+        .map_err(|err: ParseIntError| match err.kind() {
+            IntErrorKind::PosOverflow | IntErrorKind::NegOverflow => {
+                p.error(ParseErrorCause::Other("integer literal out of range"))
+            }
+            _ => p.error(ParseErrorCause::Other("invalid integer literal")),
+        })?;
+    Ok((value, new_p))
+}
+
+fn parse_float<T: FromStr>(p: ParserState<'_>) -> ParseResult<(T, ParserState<'_>)> {
+    let (token, new_p) = number_token(p)?;
+    // Rust's float syntax is a superset of CQL's, including `NaN` and `Infinity`.
+    let value = token
+        .parse()
+        .map_err(|_| p.error(ParseErrorCause::Other("invalid floating point literal")))?;
+    Ok((value, new_p))
+}
+
+fn parse_varint(p: ParserState<'_>) -> ParseResult<(CqlVarint, ParserState<'_>)> {
+    if let Some(result) = parse_blob_function(p, "blobAsVarint") {
+        let (bytes, p) = result?;
+        return Ok((CqlVarint::from_signed_bytes_be(bytes), p));
+    }
+    let (token, new_p) = number_token(p)?;
+    let (negative, digits) = split_sign(token);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(p.error(ParseErrorCause::Other("invalid varint literal")));
+    }
+    let bytes = decimal_digits_to_signed_bytes_be(negative, digits);
+    Ok((CqlVarint::from_signed_bytes_be(bytes), new_p))
+}
+
+fn parse_decimal(p: ParserState<'_>) -> ParseResult<(CqlDecimal, ParserState<'_>)> {
+    if let Some(result) = parse_blob_function(p, "blobAsDecimal") {
+        let (bytes, new_p) = result?;
+        // The blob consists of a 4-byte scale followed by the unscaled value.
+        if bytes.len() < 4 {
+            return Err(p.error(ParseErrorCause::Other(
+                "the decimal blob must have at least 4 bytes",
+            )));
+        }
+        let (scale, int_val) = bytes.split_at(4);
+        let scale = i32::from_be_bytes(scale.try_into().unwrap());
+        return Ok((
+            CqlDecimal::from_signed_be_bytes_slice_and_exponent(int_val, scale),
+            new_p,
+        ));
+    }
+
+    let (token, new_p) = number_token(p)?;
+    let invalid = || p.error(ParseErrorCause::Other("invalid decimal literal"));
+    let (negative, unsigned) = split_sign(token);
+    let (mantissa, exponent) = match unsigned.find(['e', 'E']) {
+        Some(idx) => {
+            let exponent: i32 = unsigned[idx + 1..].parse().map_err(|_| invalid())?;
+            (&unsigned[..idx], exponent)
+        }
+        None => (unsigned, 0),
+    };
+    let (int_part, frac_part) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let digits = format!("{int_part}{frac_part}");
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    let scale = i32::try_from(frac_part.len())
+        .ok()
+        .and_then(|frac_len| frac_len.checked_sub(exponent))
+        .ok_or_else(invalid)?;
+    let bytes = decimal_digits_to_signed_bytes_be(negative, &digits);
+    Ok((
+        CqlDecimal::from_signed_be_bytes_and_exponent(bytes, scale),
+        new_p,
+    ))
+}
+
+fn split_sign(token: &str) -> (bool, &str) {
+    match token.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, token.strip_prefix('+').unwrap_or(token)),
+    }
+}
+
+/// Converts a string of decimal digits to the minimal two's complement
+/// big-endian representation of the (possibly negated) number.
+fn decimal_digits_to_signed_bytes_be(negative: bool, digits: &str) -> Vec<u8> {
+    // Little-endian bytes of the magnitude, with an extra byte for the sign.
+    let mut bytes = vec![0u8];
+    for digit in digits.bytes() {
+        let mut carry = (digit - b'0') as u32;
+        for byte in bytes.iter_mut() {
+            let v = *byte as u32 * 10 + carry;
+            *byte = v as u8;
+            carry = v >> 8;
+        }
+        if carry > 0 {
+            bytes.push(carry as u8);
+        }
+        if bytes.last() != Some(&0) {
+            bytes.push(0);
+        }
+    }
+    if negative {
+        let mut carry = 1;
+        for byte in bytes.iter_mut() {
+            let (v, overflow) = (!*byte).overflowing_add(carry);
+            *byte = v;
+            carry = overflow as u8;
+        }
+    }
+    bytes.reverse();
+
+    // Remove the redundant sign extension bytes.
+    let redundant = bytes
+        .windows(2)
+        .take_while(|w| (w[0] == 0x00 && w[1] < 0x80) || (w[0] == 0xff && w[1] >= 0x80))
+        .count();
+    bytes.drain(..redundant);
+    bytes
+}
+
+fn parse_date(p: ParserState<'_>) -> ParseResult<(CqlDate, ParserState<'_>)> {
+    let Ok((s, new_p)) = parse_string(p) else {
+        // An integer literal is the raw representation, i.e. days since the epoch + 2^31.
+        let (days, p) = parse_integer(p)?;
+        return Ok((CqlDate(days), p));
+    };
+    let invalid = || p.error(ParseErrorCause::Other("invalid date literal"));
+    let date = NaiveDate::parse_from_str(&s, "%Y-%m-%d").map_err(|_| invalid())?;
+    let days = date
+        .signed_duration_since(NaiveDate::from_ymd_opt(1970, 1, 1).unwrap())
+        .num_days()
+        + (1 << 31);
+    let days = u32::try_from(days).map_err(|_| invalid())?;
+    Ok((CqlDate(days), new_p))
+}
+
+const NANOS_PER_DAY: i64 = 24 * 3_600_000_000_000;
+
+fn parse_time(p: ParserState<'_>) -> ParseResult<(CqlTime, ParserState<'_>)> {
+    let invalid = || p.error(ParseErrorCause::Other("invalid time literal"));
+    let Ok((s, new_p)) = parse_string(p) else {
+        // An integer literal is the number of nanoseconds since midnight.
+        let (nanos, new_p) = parse_integer(p)?;
+        if !(0..NANOS_PER_DAY).contains(&nanos) {
+            return Err(invalid());
+        }
+        return Ok((CqlTime(nanos), new_p));
+    };
+
+    let (hms, fraction) = s.split_once('.').unwrap_or((&s, ""));
+    let mut parts = hms.split(':');
+    let mut next_part = |max: i64| -> Option<i64> {
+        let part = parts.next()?;
+        if part.len() != 2 || !part.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        part.parse().ok().filter(|v| *v < max)
+    };
+    let (hours, minutes, seconds) = match (next_part(24), next_part(60), next_part(60)) {
+        (Some(h), Some(m), Some(s)) => (h, m, s),
+        _ => return Err(invalid()),
+    };
+    if parts.next().is_some() || fraction.len() > 9 || !fraction.bytes().all(|b| b.is_ascii_digit())
+    {
+        return Err(invalid());
+    }
+    let fraction_nanos = format!("{fraction:0<9}").parse::<i64>().unwrap();
+    let nanos = ((hours * 60 + minutes) * 60 + seconds) * 1_000_000_000 + fraction_nanos;
+    Ok((CqlTime(nanos), new_p))
+}
+
+fn parse_timestamp(p: ParserState<'_>) -> ParseResult<(CqlTimestamp, ParserState<'_>)> {
+    let Ok((s, new_p)) = parse_string(p) else {
+        // An integer literal is the number of milliseconds since the epoch.
+        let (millis, p) = parse_integer(p)?;
+        return Ok((CqlTimestamp(millis), p));
+    };
+
+    const DATE_TIME_FORMATS: [&str; 4] = [
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%dT%H:%M",
+    ];
+    let s = s.trim();
+    let millis = if let Some(naive) = s.strip_suffix('Z') {
+        parse_naive_date_time(naive, &DATE_TIME_FORMATS)
+    } else {
+        DATE_TIME_FORMATS
+            .iter()
+            .find_map(|format| DateTime::parse_from_str(s, &format!("{format}%z")).ok())
+            .map(|date_time| date_time.timestamp_millis())
+            .or_else(|| parse_naive_date_time(s, &DATE_TIME_FORMATS))
+            .or_else(|| {
+                // A date alone means its midnight.
+                NaiveDate::parse_from_str(s, "%Y-%m-%d").ok().map(|date| {
+                    date.and_time(Default::default())
+                        .and_utc()
+                        .timestamp_millis()
+                })
+            })
+    };
+    let millis =
+        millis.ok_or_else(|| p.error(ParseErrorCause::Other("invalid timestamp literal")))?;
+    Ok((CqlTimestamp(millis), new_p))
+}
+
+/// Parses a date and time without the time zone, which is assumed to be UTC.
+fn parse_naive_date_time(s: &str, formats: &[&str]) -> Option<i64> {
+    formats
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(s, format).ok())
+        .map(|date_time| date_time.and_utc().timestamp_millis())
+}
+
+fn parse_duration(p: ParserState<'_>) -> ParseResult<(CqlDuration, ParserState<'_>)> {
+    let invalid = || p.error(ParseErrorCause::Other("invalid duration literal"));
+    let out_of_range = || p.error(ParseErrorCause::Other("duration out of range"));
+
+    if let Some(result) = parse_blob_function(p, "blobAsDuration") {
+        let (bytes, new_p) = result?;
+        // The blob consists of the vint-encoded months, days and nanoseconds.
+        let mut buf = bytes.as_slice();
+        let mut component = || vint_decode(&mut buf).map_err(|_| invalid());
+        let (months, days, nanoseconds) = (component()?, component()?, component()?);
+        if !buf.is_empty() {
+            return Err(invalid());
+        }
+        let duration = CqlDuration {
+            months: i32::try_from(months).map_err(|_| out_of_range())?,
+            days: i32::try_from(days).map_err(|_| out_of_range())?,
+            nanoseconds,
+        };
+        return Ok((duration, new_p));
+    }
+
+    let (negative, unsigned) = match p.accept("-") {
+        Ok(p) => (true, p),
+        Err(_) => (false, p),
+    };
+    let (token, new_p) = unsigned.take_while(|c| c.is_ascii_alphanumeric() || c == 'µ');
+    if token.is_empty() {
+        return Err(invalid());
+    }
+
+    // The components are accumulated with a wider type, so that the sign
+    // can be applied before checking the ranges.
+    let (mut months, mut days, mut nanos) = (0i128, 0i128, 0i128);
+    let mut rest = token;
+    while !rest.is_empty() {
+        let digits_end = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let unit_end = rest[digits_end..]
+            .find(|c: char| c.is_ascii_digit())
+            .map_or(rest.len(), |idx| digits_end + idx);
+        let quantity: u64 = rest[..digits_end].parse().map_err(|_| invalid())?;
+        let (target, multiplier) = match rest[digits_end..unit_end].to_lowercase().as_str() {
+            "y" => (&mut months, 12),
+            "mo" => (&mut months, 1),
+            "w" => (&mut days, 7),
+            "d" => (&mut days, 1),
+            "h" => (&mut nanos, 3_600_000_000_000),
+            "m" => (&mut nanos, 60_000_000_000),
+            "s" => (&mut nanos, 1_000_000_000),
+            "ms" => (&mut nanos, 1_000_000),
+            "us" | "µs" => (&mut nanos, 1_000),
+            "ns" => (&mut nanos, 1),
+            _ => return Err(invalid()),
+        };
+        *target = target
+            .checked_add(quantity as i128 * multiplier)
+            .ok_or_else(out_of_range)?;
+        rest = &rest[unit_end..];
+    }
+
+    let sign = if negative { -1 } else { 1 };
+    let duration = CqlDuration {
+        months: i32::try_from(sign * months).map_err(|_| out_of_range())?,
+        days: i32::try_from(sign * days).map_err(|_| out_of_range())?,
+        nanoseconds: i64::try_from(sign * nanos).map_err(|_| out_of_range())?,
+    };
+    Ok((duration, new_p))
+}
+
+fn parse_uuid(p: ParserState<'_>) -> ParseResult<(Uuid, ParserState<'_>)> {
+    let (token, new_p) = p.take_while(|c| c.is_ascii_hexdigit() || c == '-');
+    let uuid = Uuid::try_parse(token)
+        .map_err(|_| p.error(ParseErrorCause::Other("invalid UUID literal")))?;
+    Ok((uuid, new_p))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+
+    use rand::seq::SliceRandom;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+    use scylla_cql::frame::response::result::{ColumnType, CqlValue};
+    use scylla_cql::frame::value::{
+        Counter, CqlDate, CqlDecimal, CqlDuration, CqlTime, CqlTimestamp, CqlTimeuuid, CqlVarint,
+    };
+    use uuid::Uuid;
+
+    use super::{parse_cql_literal, CqlLiteralParseError, NANOS_PER_DAY};
+    use crate::test_utils::setup_tracing;
+    use crate::utils::pretty::CqlValueDisplayer;
+
+    fn udt_type() -> ColumnType {
+        ColumnType::UserDefinedType {
+            type_name: "address".to_owned(),
+            keyspace: "ks".to_owned(),
+            field_types: vec![
+                ("street".to_owned(), ColumnType::Text),
+                ("Number".to_owned(), ColumnType::Int),
+                ("zip".to_owned(), ColumnType::Text),
+            ],
+        }
+    }
+
+    fn parse(literal: &str, typ: &ColumnType) -> CqlValue {
+        match parse_cql_literal(literal, typ) {
+            Ok(Some(value)) => value,
+            other => panic!("unexpected result of parsing {literal}: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_parse_cql_literal() {
+        setup_tracing();
+
+        assert_eq!(parse_cql_literal("null", &ColumnType::Int), Ok(None));
+        assert_eq!(parse_cql_literal(" NULL ", &ColumnType::Text), Ok(None));
+
+        // Scalar types
+        assert_eq!(parse("TRUE", &ColumnType::Boolean), CqlValue::Boolean(true));
+        assert_eq!(parse("-128", &ColumnType::TinyInt), CqlValue::TinyInt(-128));
+        assert_eq!(parse("+17", &ColumnType::Int), CqlValue::Int(17));
+        assert_eq!(
+            parse("42", &ColumnType::Counter),
+            CqlValue::Counter(Counter(42))
+        );
+        assert_eq!(parse("1.5e3", &ColumnType::Float), CqlValue::Float(1500.0));
+        assert_eq!(
+            parse("-Infinity", &ColumnType::Double),
+            CqlValue::Double(f64::NEG_INFINITY)
+        );
+        assert!(matches!(parse("NaN", &ColumnType::Double), CqlValue::Double(d) if d.is_nan()));
+        assert_eq!(
+            parse("'Foo''s'", &ColumnType::Text),
+            CqlValue::Text("Foo's".to_owned())
+        );
+        assert_eq!(
+            parse("$$it's $5$$", &ColumnType::Text),
+            CqlValue::Text("it's $5".to_owned())
+        );
+        assert_eq!(
+            parse("0x00FF", &ColumnType::Blob),
+            CqlValue::Blob(vec![0x00, 0xff])
+        );
+        assert_eq!(parse("0x", &ColumnType::Blob), CqlValue::Blob(vec![]));
+        assert_eq!(
+            parse("'::1'", &ColumnType::Inet),
+            CqlValue::Inet(IpAddr::from([0, 0, 0, 0, 0, 0, 0, 1]))
+        );
+        let uuid = Uuid::parse_str("8e14e760-7fa8-11eb-bc66-000000000001").unwrap();
+        assert_eq!(
+            parse(
+                "8e14e760-7fa8-11eb-bc66-000000000001",
+                &ColumnType::Timeuuid
+            ),
+            CqlValue::Timeuuid(CqlTimeuuid::from(uuid))
+        );
+
+        // Arbitrary precision numbers
+        assert_eq!(
+            parse("-129", &ColumnType::Varint),
+            CqlValue::Varint(CqlVarint::from_signed_bytes_be(vec![0xff, 0x7f]))
+        );
+        assert_eq!(
+            parse("128", &ColumnType::Varint),
+            CqlValue::Varint(CqlVarint::from_signed_bytes_be(vec![0x00, 0x80]))
+        );
+        assert_eq!(
+            parse("blobAsVarint(0xff7f)", &ColumnType::Varint),
+            CqlValue::Varint(CqlVarint::from_signed_bytes_be(vec![0xff, 0x7f]))
+        );
+        assert_eq!(
+            parse("123.456", &ColumnType::Decimal),
+            CqlValue::Decimal(CqlDecimal::from_signed_be_bytes_and_exponent(
+                vec![0x01, 0xe2, 0x40],
+                3
+            ))
+        );
+        assert_eq!(
+            parse("-1.5E3", &ColumnType::Decimal),
+            CqlValue::Decimal(CqlDecimal::from_signed_be_bytes_and_exponent(
+                vec![0xf1],
+                -2
+            ))
+        );
+
+        // Time types
+        assert_eq!(
+            parse("'1970-02-10'", &ColumnType::Date),
+            CqlValue::Date(CqlDate(40 + (1 << 31)))
+        );
+        assert_eq!(
+            parse("'06:05:04.000000123'", &ColumnType::Time),
+            CqlValue::Time(CqlTime(((6 * 60 + 5) * 60 + 4) * 1_000_000_000 + 123))
+        );
+        assert_eq!(
+            parse("'06:05:04.5'", &ColumnType::Time),
+            CqlValue::Time(CqlTime(
+                ((6 * 60 + 5) * 60 + 4) * 1_000_000_000 + 500_000_000
+            ))
+        );
+        let timestamp = CqlValue::Timestamp(CqlTimestamp(1112470662000));
+        for literal in [
+            "'2005-04-02 19:37:42.000+0000'",
+            "'2005-04-02 21:37:42+02:00'",
+            "'2005-04-02T19:37:42Z'",
+            "'2005-04-02 19:37:42'",
+            "1112470662000",
+        ] {
+            assert_eq!(parse(literal, &ColumnType::Timestamp), timestamp);
+        }
+        assert_eq!(
+            parse("'2005-04-02'", &ColumnType::Timestamp),
+            CqlValue::Timestamp(CqlTimestamp(1112400000000))
+        );
+        assert_eq!(
+            parse("1y2mo3w4d5h6m7s8ms9us10ns", &ColumnType::Duration),
+            CqlValue::Duration(CqlDuration {
+                months: 14,
+                days: 25,
+                nanoseconds: (((5 * 60 + 6) * 60 + 7) * 1000 + 8) * 1_000_000 + 9010,
+            })
+        );
+        assert_eq!(
+            parse("-2d12h", &ColumnType::Duration),
+            CqlValue::Duration(CqlDuration {
+                months: 0,
+                days: -2,
+                nanoseconds: -12 * 3_600_000_000_000,
+            })
+        );
+        assert_eq!(
+            parse("blobAsDuration(0x020306)", &ColumnType::Duration),
+            CqlValue::Duration(CqlDuration {
+                months: 1,
+                days: -2,
+                nanoseconds: 3,
+            })
+        );
+
+        // Compound types
+        assert_eq!(
+            parse(
+                "{1: 'a', 2: 'b'}",
+                &ColumnType::Map(Box::new(ColumnType::Int), Box::new(ColumnType::Text))
+            ),
+            CqlValue::Map(vec![
+                (CqlValue::Int(1), CqlValue::Text("a".to_owned())),
+                (CqlValue::Int(2), CqlValue::Text("b".to_owned())),
+            ])
+        );
+        assert_eq!(
+            parse(
+                "('x', 3, 0x00ff)",
+                &ColumnType::Tuple(vec![ColumnType::Text, ColumnType::Int, ColumnType::Blob])
+            ),
+            CqlValue::Tuple(vec![
+                Some(CqlValue::Text("x".to_owned())),
+                Some(CqlValue::Int(3)),
+                Some(CqlValue::Blob(vec![0x00, 0xff])),
+            ])
+        );
+        assert_eq!(
+            parse(
+                "(null, 3)",
+                &ColumnType::Tuple(vec![ColumnType::Text, ColumnType::Int, ColumnType::Blob])
+            ),
+            CqlValue::Tuple(vec![None, Some(CqlValue::Int(3)), None])
+        );
+        assert_eq!(
+            parse(
+                "[ [1,2] , [] ]",
+                &ColumnType::List(Box::new(ColumnType::List(Box::new(ColumnType::Int))))
+            ),
+            CqlValue::List(vec![
+                CqlValue::List(vec![CqlValue::Int(1), CqlValue::Int(2)]),
+                CqlValue::List(vec![]),
+            ])
+        );
+        assert_eq!(
+            parse("{}", &ColumnType::Set(Box::new(ColumnType::Int))),
+            CqlValue::Set(vec![])
+        );
+        assert_eq!(
+            parse(
+                "[1.5, 2]",
+                &ColumnType::Vector {
+                    typ: Box::new(ColumnType::Float),
+                    dimensions: 2
+                }
+            ),
+            CqlValue::Vector(vec![CqlValue::Float(1.5), CqlValue::Float(2.0)])
+        );
+        assert_eq!(
+            parse("{STREET: 'Main St', \"Number\": 7}", &udt_type()),
+            CqlValue::UserDefinedType {
+                keyspace: "ks".to_owned(),
+                type_name: "address".to_owned(),
+                fields: vec![
+                    (
+                        "street".to_owned(),
+                        Some(CqlValue::Text("Main St".to_owned()))
+                    ),
+                    ("Number".to_owned(), Some(CqlValue::Int(7))),
+                    ("zip".to_owned(), None),
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_cql_literal_errors() {
+        setup_tracing();
+
+        let list = ColumnType::List(Box::new(ColumnType::Int));
+        let error = |position: usize, reason: &str| {
+            Err(CqlLiteralParseError {
+                position,
+                reason: reason.to_owned(),
+            })
+        };
+
+        assert_eq!(
+            parse_cql_literal("[1, 2, x]", &list),
+            error(8, "invalid integer literal")
+        );
+        assert_eq!(
+            parse_cql_literal("[1, null]", &list),
+            error(5, "null is not allowed inside collections")
+        );
+        assert_eq!(
+            parse_cql_literal("[1 2]", &list),
+            error(4, "expected \",\" or the closing bracket")
+        );
+        assert_eq!(
+            parse_cql_literal("[1, 2] 3", &list),
+            error(8, "leftover characters")
+        );
+        assert_eq!(parse_cql_literal("1", &list), error(1, "expected \"[\""));
+        assert_eq!(
+            parse_cql_literal("300", &ColumnType::TinyInt),
+            error(1, "integer literal out of range")
+        );
+        assert_eq!(
+            parse_cql_literal("'abc", &ColumnType::Text),
+            error(5, "unterminated string literal")
+        );
+        assert_eq!(
+            parse_cql_literal("'zażółć'", &ColumnType::Ascii),
+            error(1, "non-ASCII character in an ascii literal")
+        );
+        assert_eq!(
+            parse_cql_literal("0x123", &ColumnType::Blob),
+            error(1, "odd number of hex digits in a blob literal")
+        );
+        assert_eq!(
+            parse_cql_literal("'2024-02-30'", &ColumnType::Date),
+            error(1, "invalid date literal")
+        );
+        assert_eq!(
+            parse_cql_literal("1h1x", &ColumnType::Duration),
+            error(1, "invalid duration literal")
+        );
+        assert_eq!(
+            parse_cql_literal(
+                "(1, 2, 3)",
+                &ColumnType::Tuple(vec![ColumnType::Int, ColumnType::Int])
+            ),
+            error(8, "too many elements in a tuple literal")
+        );
+        assert_eq!(
+            parse_cql_literal(
+                "[1, 2]",
+                &ColumnType::Vector {
+                    typ: Box::new(ColumnType::Int),
+                    dimensions: 3
+                }
+            ),
+            error(1, "wrong number of elements in a vector literal")
+        );
+        assert_eq!(
+            parse_cql_literal("{street: 'a', number: 1}", &udt_type()),
+            error(15, "unknown field of the user defined type")
+        );
+        assert_eq!(
+            parse_cql_literal("{zip: 'a', zip: 'b'}", &udt_type()),
+            error(12, "duplicate field in a UDT literal")
+        );
+        assert_eq!(
+            parse_cql_literal("'x'", &ColumnType::Custom("Foo".to_owned())),
+            error(1, "literals of custom types are not supported")
+        );
+        // Positions are counted in characters, not bytes.
+        assert_eq!(
+            parse_cql_literal(
+                "{'ż': x}",
+                &ColumnType::Map(Box::new(ColumnType::Text), Box::new(ColumnType::Int))
+            ),
+            error(7, "invalid integer literal")
+        );
+    }
+
+    fn random_type(rng: &mut ChaCha8Rng, depth: usize) -> ColumnType {
+        const NATIVE_TYPES: &[ColumnType] = &[
+            ColumnType::Ascii,
+            ColumnType::Boolean,
+            ColumnType::Blob,
+            ColumnType::Counter,
+            ColumnType::Date,
+            ColumnType::Decimal,
+            ColumnType::Double,
+            ColumnType::Duration,
+            ColumnType::Float,
+            ColumnType::Int,
+            ColumnType::BigInt,
+            ColumnType::Text,
+            ColumnType::Timestamp,
+            ColumnType::Inet,
+            ColumnType::SmallInt,
+            ColumnType::TinyInt,
+            ColumnType::Time,
+            ColumnType::Timeuuid,
+            ColumnType::Uuid,
+            ColumnType::Varint,
+        ];
+        const FIELD_NAMES: &[&str] = &["a", "b_2", "Number", "with space", "quo\"te"];
+
+        if depth == 0 || rng.gen_bool(0.5) {
+            return NATIVE_TYPES.choose(rng).unwrap().clone();
+        }
+        let inner = |rng: &mut ChaCha8Rng| Box::new(random_type(rng, depth - 1));
+        match rng.gen_range(0..6) {
+            0 => ColumnType::List(inner(rng)),
+            1 => ColumnType::Set(inner(rng)),
+            2 => ColumnType::Map(inner(rng), inner(rng)),
+            3 => ColumnType::Vector {
+                typ: inner(rng),
+                dimensions: rng.gen_range(1..4),
+            },
+            4 => ColumnType::Tuple(
+                (0..rng.gen_range(1..4))
+                    .map(|_| random_type(rng, depth - 1))
+                    .collect(),
+            ),
+            _ => ColumnType::UserDefinedType {
+                type_name: "typ".to_owned(),
+                keyspace: "ks".to_owned(),
+                field_types: FIELD_NAMES
+                    .choose_multiple(rng, 3)
+                    .map(|name| (name.to_string(), random_type(rng, depth - 1)))
+                    .collect(),
+            },
+        }
+    }
+
+    fn random_value(rng: &mut ChaCha8Rng, typ: &ColumnType) -> CqlValue {
+        const CHARS: &[char] = &[
+            'a', 'Z', '0', ' ', '\'', '"', '$', ',', ':', '{', ']', 'ż', '😀',
+        ];
+
+        let random_bytes = |rng: &mut ChaCha8Rng, min_len| {
+            let len = rng.gen_range(min_len..10);
+            (0..len).map(|_| rng.gen()).collect::<Vec<u8>>()
+        };
+        let random_list = |rng: &mut ChaCha8Rng, typ, len| {
+            (0..len).map(|_| random_value(rng, typ)).collect::<Vec<_>>()
+        };
+        match typ {
+            ColumnType::Ascii | ColumnType::Text => {
+                let ascii = *typ == ColumnType::Ascii;
+                let s = (0..rng.gen_range(0..8))
+                    .map(|_| *CHARS.choose(rng).unwrap())
+                    .filter(|c| !ascii || c.is_ascii())
+                    .collect();
+                if ascii {
+                    CqlValue::Ascii(s)
+                } else {
+                    CqlValue::Text(s)
+                }
+            }
+            ColumnType::Boolean => CqlValue::Boolean(rng.gen()),
+            ColumnType::Blob => CqlValue::Blob(random_bytes(rng, 0)),
+            ColumnType::Counter => CqlValue::Counter(Counter(rng.gen())),
+            ColumnType::TinyInt => CqlValue::TinyInt(rng.gen()),
+            ColumnType::SmallInt => CqlValue::SmallInt(rng.gen()),
+            ColumnType::Int => CqlValue::Int(rng.gen()),
+            ColumnType::BigInt => CqlValue::BigInt(rng.gen()),
+            ColumnType::Varint => {
+                CqlValue::Varint(CqlVarint::from_signed_bytes_be(random_bytes(rng, 1)))
+            }
+            ColumnType::Decimal => CqlValue::Decimal(
+                CqlDecimal::from_signed_be_bytes_and_exponent(random_bytes(rng, 1), rng.gen()),
+            ),
+            // The bits are random, so that all kinds of values are covered.
+            ColumnType::Float => loop {
+                let f = f32::from_bits(rng.gen());
+                if !f.is_nan() {
+                    break CqlValue::Float(f);
+                }
+            },
+            ColumnType::Double => loop {
+                let f = f64::from_bits(rng.gen());
+                if !f.is_nan() {
+                    break CqlValue::Double(f);
+                }
+            },
+            // The ranges are limited to what the displayer can represent.
+            ColumnType::Date => {
+                let days = (1i64 << 31) + rng.gen_range(-90_000_000..90_000_000);
+                CqlValue::Date(CqlDate(days as u32))
+            }
+            ColumnType::Timestamp => CqlValue::Timestamp(CqlTimestamp(
+                rng.gen_range(-8_000_000_000_000_000..8_000_000_000_000_000),
+            )),
+            ColumnType::Time => CqlValue::Time(CqlTime(rng.gen_range(0..NANOS_PER_DAY))),
+            ColumnType::Duration => {
+                if rng.gen() {
+                    let sign = if rng.gen() { 1 } else { -1 };
+                    CqlValue::Duration(CqlDuration {
+                        months: sign * rng.gen_range(0..=i32::MAX),
+                        days: sign * rng.gen_range(0..=i32::MAX),
+                        nanoseconds: sign as i64 * rng.gen_range(0..=i64::MAX),
+                    })
+                } else {
+                    // Mixed signs, which have no literal form
+                    CqlValue::Duration(CqlDuration {
+                        months: rng.gen(),
+                        days: rng.gen(),
+                        nanoseconds: rng.gen(),
+                    })
+                }
+            }
+            ColumnType::Inet => CqlValue::Inet(if rng.gen() {
+                IpAddr::from(rng.gen::<[u8; 4]>())
+            } else {
+                IpAddr::from(rng.gen::<[u8; 16]>())
+            }),
+            ColumnType::Uuid => CqlValue::Uuid(Uuid::from_u128(rng.gen())),
+            ColumnType::Timeuuid => CqlValue::Timeuuid(CqlTimeuuid::from_u128(rng.gen())),
+            ColumnType::List(typ) => {
+                let len = rng.gen_range(0..4);
+                CqlValue::List(random_list(rng, typ, len))
+            }
+            ColumnType::Set(typ) => {
+                let len = rng.gen_range(0..4);
+                CqlValue::Set(random_list(rng, typ, len))
+            }
+            ColumnType::Vector { typ, dimensions } => {
+                CqlValue::Vector(random_list(rng, typ, *dimensions as usize))
+            }
+            ColumnType::Map(key_type, value_type) => CqlValue::Map(
+                (0..rng.gen_range(0..4))
+                    .map(|_| (random_value(rng, key_type), random_value(rng, value_type)))
+                    .collect(),
+            ),
+            ColumnType::Tuple(types) => CqlValue::Tuple(
+                types
+                    .iter()
+                    .map(|typ| rng.gen_bool(0.8).then(|| random_value(rng, typ)))
+                    .collect(),
+            ),
+            ColumnType::UserDefinedType {
+                type_name,
+                keyspace,
+                field_types,
+            } => CqlValue::UserDefinedType {
+                keyspace: keyspace.clone(),
+                type_name: type_name.clone(),
+                fields: field_types
+                    .iter()
+                    .map(|(name, typ)| {
+                        (
+                            name.clone(),
+                            rng.gen_bool(0.8).then(|| random_value(rng, typ)),
+                        )
+                    })
+                    .collect(),
+            },
+            ColumnType::Custom(_) => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_parse_cql_literal_round_trip() {
+        setup_tracing();
+
+        let mut rng = ChaCha8Rng::seed_from_u64(69);
+        for _ in 0..2000 {
+            let typ = random_type(&mut rng, 3);
+            let value = random_value(&mut rng, &typ);
+            let literal = CqlValueDisplayer(&value).to_string();
+            assert_eq!(
+                parse_cql_literal(&literal, &typ),
+                Ok(Some(value)),
+                "failed to parse {literal} as {typ:?}"
+            );
+        }
+    }
+}
@@ -1,3 +1,5 @@
+pub(crate) mod hash;
+pub(crate) mod literal;
 pub(crate) mod parse;
 
 pub(crate) mod pretty;
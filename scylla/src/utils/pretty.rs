@@ -1,6 +1,7 @@
 use chrono::{LocalResult, TimeZone, Utc};
 use scylla_cql::frame::{
     response::result::CqlValue,
+    types::vint_encode,
     value::{CqlDate, CqlTime, CqlTimestamp},
 };
 
@@ -83,7 +84,32 @@ where
                     None => f.write_str("<date out of representable range>")?,
                 }
             }
-            CqlValue::Duration(d) => write!(f, "{}mo{}d{}ns", d.months, d.days, d.nanoseconds)?,
+            CqlValue::Duration(d) => {
+                // In CQL duration literals, the sign applies to all the components.
+                let signs = [
+                    d.months.signum(),
+                    d.days.signum(),
+                    d.nanoseconds.signum() as i32,
+                ];
+                if signs.contains(&1) && signs.contains(&-1) {
+                    // Mixed-sign durations have no literal form, so they are written as blobs.
+                    let mut bytes = Vec::new();
+                    vint_encode(d.months as i64, &mut bytes);
+                    vint_encode(d.days as i64, &mut bytes);
+                    vint_encode(d.nanoseconds, &mut bytes);
+                    write!(f, "blobAsDuration(0x{:x})", HexBytes(&bytes))?
+                } else if signs.contains(&-1) {
+                    write!(
+                        f,
+                        "-{}mo{}d{}ns",
+                        d.months.unsigned_abs(),
+                        d.days.unsigned_abs(),
+                        d.nanoseconds.unsigned_abs()
+                    )?
+                } else {
+                    write!(f, "{}mo{}d{}ns", d.months, d.days, d.nanoseconds)?
+                }
+            }
             CqlValue::Time(CqlTime(t)) => {
                 write!(
                     f,
@@ -116,7 +142,8 @@ where
                 .fmt(f)?;
                 f.write_str(")")?;
             }
-            CqlValue::List(v) => {
+            // Vector literals use the same syntax as lists.
+            CqlValue::List(v) | CqlValue::Vector(v) => {
                 f.write_str("[")?;
                 CommaSeparatedDisplayer(v.iter().map(CqlValueDisplayer)).fmt(f)?;
                 f.write_str("]")?;
@@ -142,7 +169,10 @@ where
             } => {
                 f.write_str("{")?;
                 CommaSeparatedDisplayer(fields.iter().map(|(k, v)| {
-                    PairDisplayer(k, MaybeNullDisplayer(v.as_ref().map(CqlValueDisplayer)))
+                    PairDisplayer(
+                        CqlIdentifierDisplayer(k),
+                        MaybeNullDisplayer(v.as_ref().map(CqlValueDisplayer)),
+                    )
                 }))
                 .fmt(f)?;
                 f.write_str("}")?;
@@ -174,6 +204,90 @@ impl<'a> Display for CqlStringLiteralDisplayer<'a> {
     }
 }
 
+// Reserved CQL keywords, which can't be used as unquoted identifiers.
+const RESERVED_KEYWORDS: &[&str] = &[
+    "add",
+    "allow",
+    "alter",
+    "and",
+    "apply",
+    "asc",
+    "authorize",
+    "batch",
+    "begin",
+    "by",
+    "columnfamily",
+    "create",
+    "default",
+    "delete",
+    "desc",
+    "describe",
+    "drop",
+    "entries",
+    "execute",
+    "from",
+    "full",
+    "grant",
+    "if",
+    "in",
+    "index",
+    "infinity",
+    "insert",
+    "into",
+    "is",
+    "keyspace",
+    "limit",
+    "materialized",
+    "mbean",
+    "mbeans",
+    "modify",
+    "nan",
+    "norecursive",
+    "not",
+    "null",
+    "of",
+    "on",
+    "or",
+    "order",
+    "primary",
+    "rename",
+    "replace",
+    "revoke",
+    "schema",
+    "select",
+    "set",
+    "table",
+    "to",
+    "token",
+    "truncate",
+    "unlogged",
+    "unset",
+    "update",
+    "use",
+    "using",
+    "view",
+    "where",
+    "with",
+];
+
+// Displays an identifier, quoting it unless it would be interpreted
+// as the same identifier without the quotes.
+pub(crate) struct CqlIdentifierDisplayer<'a>(pub(crate) &'a str);
+
+impl<'a> Display for CqlIdentifierDisplayer<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut chars = self.0.chars();
+        let needs_quotes = !chars.next().map_or(false, |c| c.is_ascii_lowercase())
+            || !chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+            || RESERVED_KEYWORDS.contains(&self.0);
+        if needs_quotes {
+            write!(f, "\"{}\"", self.0.replace('"', "\"\""))
+        } else {
+            f.write_str(self.0)
+        }
+    }
+}
+
 pub(crate) struct CommaSeparatedDisplayer<I>(pub(crate) I);
 
 impl<I, T> Display for CommaSeparatedDisplayer<I>
@@ -230,7 +344,7 @@ mod tests {
     use scylla_cql::frame::value::{CqlDate, CqlDecimal, CqlDuration, CqlTime, CqlTimestamp};
 
     use crate::test_utils::setup_tracing;
-    use crate::utils::pretty::CqlValueDisplayer;
+    use crate::utils::pretty::{CqlIdentifierDisplayer, CqlValueDisplayer};
 
     #[test]
     fn test_cql_value_displayer() {
@@ -285,6 +399,28 @@ mod tests {
             ),
             "1mo2d3ns"
         );
+        assert_eq!(
+            format!(
+                "{}",
+                CqlValueDisplayer(CqlValue::Duration(CqlDuration {
+                    months: -1,
+                    days: 0,
+                    nanoseconds: -3,
+                }))
+            ),
+            "-1mo0d3ns"
+        );
+        assert_eq!(
+            format!(
+                "{}",
+                CqlValueDisplayer(CqlValue::Duration(CqlDuration {
+                    months: 1,
+                    days: -2,
+                    nanoseconds: 3,
+                }))
+            ),
+            "blobAsDuration(0x020306)"
+        );
         let t = chrono::NaiveTime::from_hms_nano_opt(6, 5, 4, 123)
             .unwrap()
             .signed_duration_since(chrono::NaiveTime::MIN);
@@ -353,5 +489,38 @@ mod tests {
             ),
             "{foo:123,bar:321}"
         );
+
+        let fields = vec![("Foo \"bar\"".to_owned(), None)];
+        assert_eq!(
+            format!(
+                "{}",
+                CqlValueDisplayer(CqlValue::UserDefinedType {
+                    keyspace: "ks".to_owned(),
+                    type_name: "typ".to_owned(),
+                    fields,
+                })
+            ),
+            r#"{"Foo ""bar""":null}"#
+        );
+    }
+
+    #[test]
+    fn test_cql_identifier_displayer() {
+        setup_tracing();
+        assert_eq!(format!("{}", CqlIdentifierDisplayer("foo_1")), "foo_1");
+        assert_eq!(format!("{}", CqlIdentifierDisplayer("Foo")), r#""Foo""#);
+        assert_eq!(format!("{}", CqlIdentifierDisplayer("1foo")), r#""1foo""#);
+        assert_eq!(format!("{}", CqlIdentifierDisplayer("order")), r#""order""#);
+        assert_eq!(format!("{}", CqlIdentifierDisplayer("token")), r#""token""#);
+        assert_eq!(
+            format!("{}", CqlIdentifierDisplayer("default")),
+            r#""default""#
+        );
+        assert_eq!(format!("{}", CqlIdentifierDisplayer("unset")), r#""unset""#);
+        assert_eq!(
+            format!("{}", CqlIdentifierDisplayer("mbeans")),
+            r#""mbeans""#
+        );
+        assert_eq!(format!("{}", CqlIdentifierDisplayer("")), r#""""#);
     }
 }
@@ -46,7 +46,7 @@ pub(crate) async fn supports_feature(session: &Session, feature: &str) -> bool {
         .query("SELECT supported_features FROM system.local", ())
         .await
         .unwrap()
-        .single_row_typed()
+        .single_row()
         .unwrap();
 
     features
@@ -103,7 +103,7 @@ pub async fn scylla_supports_tablets(session: &Session) -> bool {
         )
         .await
         .unwrap();
-    result.single_row().is_ok()
+    result.rows_num() == Ok(1)
 }
 
 #[cfg(test)]
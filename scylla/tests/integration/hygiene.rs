@@ -70,6 +70,37 @@ macro_rules! test_crate {
         struct TestStructNew {
             x: ::core::primitive::i32,
         }
+
+        #[allow(unused)]
+        #[derive(_scylla::macros::SerializeValue, _scylla::macros::DeserializeValue)]
+        #[scylla(crate = _scylla)]
+        enum TestEnumNew {
+            A = 1,
+            #[scylla(rename = "b")]
+            B = 2,
+        }
+
+        #[allow(unused)]
+        #[derive(_scylla::macros::SerializeValue, _scylla::macros::DeserializeValue)]
+        #[scylla(crate = _scylla, transparent)]
+        struct TestNewtypeNew<'a>(&'a ::core::primitive::str);
+
+        #[allow(unused)]
+        #[derive(_scylla::macros::SerializeRow)]
+        #[scylla(crate = _scylla)]
+        struct TestFlattenedRowNew {
+            y: ::core::primitive::i32,
+            #[scylla(flatten)]
+            inner: TestStructNew,
+        }
+
+        #[allow(unused)]
+        #[derive(_scylla::macros::SerializeRow)]
+        #[scylla(crate = _scylla, schema_file = "tests/integration/hygiene.cql", table = "ks.t")]
+        struct TestSchemaCheckedRowNew<'a> {
+            x: ::core::primitive::i32,
+            y: ::std::option::Option<&'a ::core::primitive::str>,
+        }
     };
 }
 
@@ -1,17 +1,22 @@
 use crate::utils::{setup_tracing, test_with_3_node_cluster};
-use scylla::retry_policy::FallthroughRetryPolicy;
+use scylla::history::{AttemptResult, HistoryCollector};
+use scylla::retry_policy::{
+    FallthroughRetryPolicy, QueryInfo, RetryDecision, RetryPolicy, RetrySession,
+};
 use scylla::speculative_execution::SimpleSpeculativeExecutionPolicy;
+use scylla::transport::errors::{DbError, QueryError};
+use scylla::transport::exponential_backoff_retry_policy::ExponentialBackoffRetryPolicy;
 use scylla::transport::session::Session;
 use scylla::ExecutionProfile;
 use scylla::SessionBuilder;
 use scylla::{query::Query, test_utils::unique_keyspace_name};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tracing::info;
 
 use scylla_proxy::{
-    Condition, ProxyError, Reaction, RequestOpcode, RequestReaction, RequestRule, ShardAwareness,
-    WorkerError,
+    example_db_errors, Condition, ProxyError, Reaction, RequestOpcode, RequestReaction,
+    RequestRule, ShardAwareness, WorkerError,
 };
 
 #[tokio::test]
@@ -164,3 +169,179 @@ async fn retries_occur() {
         Err(err) => panic!("{}", err),
     }
 }
+
+// Always retries on the next node, recording the remaining budgets it was given.
+#[derive(Debug, Clone, Default)]
+struct BudgetRecordingRetryPolicy {
+    budgets: Arc<Mutex<Vec<Option<Duration>>>>,
+}
+
+impl RetryPolicy for BudgetRecordingRetryPolicy {
+    fn new_session(&self) -> Box<dyn RetrySession> {
+        Box::new(self.clone())
+    }
+
+    fn clone_boxed(&self) -> Box<dyn RetryPolicy> {
+        Box::new(self.clone())
+    }
+}
+
+impl RetrySession for BudgetRecordingRetryPolicy {
+    fn decide_should_retry(&mut self, query_info: QueryInfo) -> RetryDecision {
+        self.budgets
+            .lock()
+            .unwrap()
+            .push(query_info.remaining_budget);
+        RetryDecision::RetryNextNode(None)
+    }
+
+    fn reset(&mut self) {}
+}
+
+#[tokio::test]
+#[ntest::timeout(30000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn request_deadline_spans_retries() {
+    setup_tracing();
+    const DEADLINE: Duration = Duration::from_millis(500);
+    const DELAY: Duration = Duration::from_millis(200);
+
+    let res = test_with_3_node_cluster(ShardAwareness::QueryNode, |proxy_uris, translation_map, mut running_proxy| async move {
+        let retry_policy = BudgetRecordingRetryPolicy::default();
+        let profile = ExecutionProfile::builder()
+            .retry_policy(Box::new(retry_policy.clone()))
+            .request_deadline(Some(DEADLINE))
+            .build();
+        let session: Session = SessionBuilder::new()
+            .known_node(proxy_uris[0].as_str())
+            .default_execution_profile_handle(profile.into_handle())
+            .address_translator(Arc::new(translation_map))
+            .build()
+            .await
+            .unwrap();
+
+        let ks = unique_keyspace_name();
+        session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 3}}", ks), &[]).await.unwrap();
+        session.use_keyspace(ks, false).await.unwrap();
+        session
+            .query("CREATE TABLE t (a int primary key)", &[])
+            .await
+            .unwrap();
+
+        let mut q = Query::from("INSERT INTO t (a) VALUES (?)");
+        q.set_is_idempotent(true);
+
+        // Each node responds with an error after a delay, so the deadline
+        // expires during the third attempt.
+        let delayed_error_rule = RequestRule(
+            Condition::RequestOpcode(RequestOpcode::Query)
+                .and(Condition::BodyContainsCaseSensitive(Box::new(*b"INTO t"))),
+            RequestReaction::forge_with_error_lazy_delay(
+                Box::new(example_db_errors::overloaded),
+                Some(DELAY),
+            ),
+        );
+        for node in running_proxy.running_nodes.iter_mut() {
+            node.change_request_rules(Some(vec![delayed_error_rule.clone()]));
+        }
+
+        let err = session.query(q, (1,)).await.unwrap_err();
+        match err {
+            QueryError::RequestDeadlineExceeded { attempts, .. } => assert_eq!(attempts, 3),
+            err => panic!("Expected a request timeout, got {:?}", err),
+        }
+
+        // The retry policy was consulted after each of the first two attempts,
+        // with a budget decreasing by the delay of an attempt.
+        let budgets = retry_policy.budgets.lock().unwrap().clone();
+        assert_eq!(budgets.len(), 2);
+        for (i, budget) in budgets.into_iter().enumerate() {
+            let budget = budget.unwrap();
+            assert!(budget <= DEADLINE - DELAY * (i as u32 + 1), "{:?}", budget);
+        }
+
+        running_proxy
+    }).await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}
+
+#[tokio::test]
+#[ntest::timeout(30000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn retries_are_delayed_by_backoff() {
+    setup_tracing();
+
+    let res = test_with_3_node_cluster(ShardAwareness::QueryNode, |proxy_uris, translation_map, mut running_proxy| async move {
+        let retry_policy = ExponentialBackoffRetryPolicy::new()
+            .base_delay(Duration::from_millis(100))
+            .max_elapsed_time(None)
+            .jitter(false);
+        let profile = ExecutionProfile::builder()
+            .retry_policy(Box::new(retry_policy))
+            .build();
+        let session: Session = SessionBuilder::new()
+            .known_node(proxy_uris[0].as_str())
+            .default_execution_profile_handle(profile.into_handle())
+            .address_translator(Arc::new(translation_map))
+            .build()
+            .await
+            .unwrap();
+
+        let ks = unique_keyspace_name();
+        session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 3}}", ks), &[]).await.unwrap();
+        session.use_keyspace(ks, false).await.unwrap();
+        session
+            .query("CREATE TABLE t (a int primary key)", &[])
+            .await
+            .unwrap();
+
+        let history_collector = Arc::new(HistoryCollector::new());
+        let mut q = Query::from("INSERT INTO t (a) VALUES (?)");
+        q.set_is_idempotent(true);
+        q.set_history_listener(history_collector.clone());
+
+        let overloaded_rule = RequestRule(
+            Condition::RequestOpcode(RequestOpcode::Query)
+                .and(Condition::BodyContainsCaseSensitive(Box::new(*b"INTO t"))),
+            RequestReaction::forge().overloaded(),
+        );
+        for node in running_proxy.running_nodes.iter_mut() {
+            node.change_request_rules(Some(vec![overloaded_rule.clone()]));
+        }
+
+        // Each node is tried once, the second and third attempt after 100ms and 200ms.
+        // The last decision carries a delay too, but there is no node left to retry on.
+        let start = std::time::Instant::now();
+        let err = session.query(q, (1,)).await.unwrap_err();
+        assert!(start.elapsed() >= Duration::from_millis(300));
+        assert!(matches!(err, QueryError::DbError(DbError::Overloaded, _)), "{:?}", err);
+
+        let history = history_collector.clone_structured_history();
+        let delays: Vec<Option<Duration>> = history.queries[0]
+            .non_speculative_fiber
+            .attempts
+            .iter()
+            .map(|attempt| match &attempt.result {
+                Some(AttemptResult::Error(_, _, decision)) => decision.delay(),
+                result => panic!("Unexpected attempt result: {:?}", result),
+            })
+            .collect();
+        assert_eq!(
+            delays,
+            [100, 200, 400].map(|ms| Some(Duration::from_millis(ms)))
+        );
+
+        running_proxy
+    }).await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}